@@ -1,59 +1,1557 @@
+use std::collections::HashMap;
+
 use dioxus::{fullstack::reqwest::Url, prelude::*};
+use jiff::Timestamp;
 use types::{
     ResetLink,
-    kanidm::{Group, Person},
+    account_request::{AccountRequest, AccountRequestApproval},
+    activity::{Activity, ActivityMatch, KanidmCall},
+    announcement::{ActiveAnnouncement, Announcement},
+    api_usage::AdminApiUsage,
+    audit::{AuditExport, AuditFormat, AuditManifest, ChainStatus},
+    background_job::BackgroundJob,
+    batch::{BatchResult, PendingChange},
+    break_glass::BreakGlassStatus,
+    capability::{Capabilities, TokenCapabilities},
+    capture::{CaptureBundle, CaptureStatus},
+    changelog::WhatsNew,
+    directory::DirectoryEntry,
+    email_queue::QueuedEmail,
+    email_verification::EmailAddressStatus,
+    export::UserExport,
+    group_deletion::GroupDeletionImpact,
+    group_import::GroupImport,
+    group_review::GroupReview,
+    group_webhook::{GroupWebhook, MembershipAction, WebhookDelivery},
+    ids::{GroupId, PersonId},
+    integrity::IntegrityReport,
+    job::{Job, JobItem},
+    kanidm::{
+        DomainInfo, EntryKind, Group, GroupClassification, GroupMembershipChanges, KanidmLoad,
+        KanidmNode, LdapStatus, NewPersonSetup, Person,
+    },
+    login_event::LoginEvent,
+    maintenance::Maintenance,
+    migration::MigrationStatus,
+    notification::NotificationSettings,
+    offboarding::{ChecklistTemplate, Offboarding},
+    onboarding::Onboarding,
+    preferences::{UserPreferences, UserShortcut, UserShortcuts},
+    profile_change::{MyProfile, ProfileChange, ProfileField},
+    provision::{
+        EmailRestriction, GrantedGroup, ProvisionApproval, ProvisionApprovalOutcome,
+        ProvisionCompletion, ProvisionFailureCount, ProvisionFields, ProvisionFollowUp,
+        ProvisionLinkSummary, VerifiedProvisionLink,
+    },
+    report::{Report, ReportEventKind, ReportSettings},
+    retention::{PurgeResult, RetentionPolicy},
+    rotation::RotationCampaign,
+    scheduled_membership::ScheduledMembershipChange,
+    server_info::ServerInfo,
+    session::SessionInfo,
+    settings_export::{SettingsExport, SettingsImport},
+    setup::SetupCheck,
+    shift_note::ShiftNote,
+    stats::DashboardStats,
+    tag::Tag,
+    ticket::TicketStatus,
+    user_deletion::UserDeletionImpact,
+    user_detail::UserDetail,
+    validation::FieldErrors,
+    visibility::FieldVisibility,
 };
 use uuid::Uuid;
 
-#[post("/api/current-user")]
-pub async fn get_current_user() -> ServerFnResult<Option<Person>> {
-    match server::get_current_user().await {
-        Ok(person) => Ok(Some(person)),
-        Err(_) => Ok(None),
+#[post("/api/current-user")]
+pub async fn get_current_user() -> ServerFnResult<Option<Person>> {
+    match server::get_current_user().await {
+        Ok(person) => Ok(Some(person)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// What the current user can do, given their role and what AuthIt!'s Kanidm
+/// token can do, so the UI can hide or disable what they can't use.
+#[post("/api/current-user/capabilities")]
+pub async fn get_capabilities() -> ServerFnResult<Capabilities> {
+    Ok(server::current_capabilities().await?)
+}
+
+/// List the people directory. Any signed-in user may do this, if it's enabled.
+#[post("/api/directory")]
+pub async fn list_directory() -> ServerFnResult<Vec<DirectoryEntry>> {
+    server::with_session(|user| async move { server::directory::list(&user).await }).await
+}
+
+/// Everyone, without their groups if those are fetched per user with
+/// [`get_user_groups`].
+#[post("/api/users")]
+pub async fn list_users() -> ServerFnResult<Vec<Person>> {
+    server::with_admin_session(|admin| async move {
+        let mut persons = server::IDENTITY.list_persons().await?;
+        if server::CONFIG.lazy_group_membership {
+            for person in &mut persons {
+                person.groups = Vec::new();
+            }
+        }
+        server::redact_legal_names(&admin, &mut persons).await?;
+        server::collation::sort_persons(&mut persons);
+        Ok(persons)
+    })
+    .await
+}
+
+/// Whether [`list_users`] leaves out everyone's groups, so the page fetches
+/// them per user.
+#[post("/api/users/lazy-groups")]
+pub async fn group_membership_is_lazy() -> ServerFnResult<bool> {
+    server::with_admin_session(|_| async { Ok(server::CONFIG.lazy_group_membership) }).await
+}
+
+/// The names of the groups a user is directly in.
+#[post("/api/users/groups/list")]
+pub async fn get_user_groups(user_id: PersonId) -> ServerFnResult<Vec<String>> {
+    server::with_admin_session(
+        |_| async move { server::IDENTITY.get_person_groups(&user_id).await },
+    )
+    .await
+}
+
+/// Everything a user's page shows about them, in one round trip.
+#[post("/api/users/detail")]
+pub async fn get_user_detail(user_id: Uuid) -> ServerFnResult<UserDetail> {
+    server::with_admin_session(
+        |admin| async move { server::user_detail::get(user_id, &admin).await },
+    )
+    .await
+}
+
+#[post("/api/groups")]
+pub async fn list_groups() -> ServerFnResult<Vec<Group>> {
+    server::with_admin_session(|_| async {
+        let mut groups = server::IDENTITY.list_groups().await?;
+        server::collation::sort_groups(&mut groups);
+        Ok(groups)
+    })
+    .await
+}
+
+#[post("/api/sessions")]
+pub async fn list_sessions() -> ServerFnResult<Vec<SessionInfo>> {
+    server::with_admin_session(|_| async { server::list_current_user_sessions().await }).await
+}
+
+/// Sign out one of the current admin's other sessions.
+#[post("/api/sessions/revoke")]
+pub async fn revoke_session(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::revoke_current_user_session(id).await })
+        .await
+}
+
+/// The admins with AuthIt! open right now.
+#[post("/api/presence")]
+pub async fn get_online_admins() -> ServerFnResult<Vec<Uuid>> {
+    server::with_admin_session(|_| async { server::presence::online_admins().await }).await
+}
+
+#[post("/api/preferences")]
+pub async fn get_preferences() -> ServerFnResult<UserPreferences> {
+    server::with_admin_session(|user| async move {
+        server::storage::Preferences::load(&user.user_id).await
+    })
+    .await
+}
+
+#[post("/api/preferences/set")]
+pub async fn set_preferences(mut preferences: UserPreferences) -> ServerFnResult<()> {
+    server::with_admin_session(|user| async move {
+        // Shortcuts change as the admin browses, so the client's copy may be
+        // stale.
+        let saved = server::storage::Preferences::load(&user.user_id).await?;
+        preferences.shortcuts = saved.shortcuts;
+        server::storage::Preferences::save(&user.user_id, &preferences).await
+    })
+    .await
+}
+
+/// The users in the current admin's sidebar.
+#[post("/api/preferences/shortcuts")]
+pub async fn get_shortcuts() -> ServerFnResult<UserShortcuts> {
+    server::with_admin_session(|admin| async move {
+        Ok(server::storage::Preferences::load(&admin.user_id)
+            .await?
+            .shortcuts)
+    })
+    .await
+}
+
+/// Note that the current admin viewed a user.
+#[post("/api/preferences/shortcuts/viewed")]
+pub async fn record_user_view(user: UserShortcut) -> ServerFnResult<UserShortcuts> {
+    server::with_admin_session(|admin| async move {
+        server::storage::Preferences::update_shortcuts(&admin.user_id, |s| s.viewed(user)).await
+    })
+    .await
+}
+
+#[post("/api/preferences/shortcuts/pin")]
+pub async fn set_user_pinned(user: UserShortcut, pinned: bool) -> ServerFnResult<UserShortcuts> {
+    server::with_admin_session(|admin| async move {
+        server::storage::Preferences::update_shortcuts(&admin.user_id, |s| {
+            s.set_pinned(user, pinned)
+        })
+        .await
+    })
+    .await
+}
+
+/// Release notes for versions since admins last dismissed them, if any.
+#[post("/api/whats-new")]
+pub async fn get_whats_new() -> ServerFnResult<Option<WhatsNew>> {
+    server::with_admin_session(|_| async { server::changelog::whats_new().await }).await
+}
+
+#[post("/api/whats-new/dismiss")]
+pub async fn dismiss_whats_new() -> ServerFnResult<()> {
+    server::with_admin_session(|_| async { server::changelog::dismiss().await }).await
+}
+
+/// The current site-wide banner. This is public, so it can be shown on the
+/// provision page.
+#[post("/api/announcement/active")]
+pub async fn get_active_announcement() -> ServerFnResult<Option<ActiveAnnouncement>> {
+    Ok(server::announcement::active_announcement().await?)
+}
+
+#[post("/api/announcement")]
+pub async fn get_announcement() -> ServerFnResult<Option<Announcement>> {
+    server::with_admin_session(|_| async { server::announcement::get_announcement().await }).await
+}
+
+#[post("/api/announcement/set")]
+pub async fn set_announcement(announcement: Option<Announcement>) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move {
+        server::announcement::set_announcement(announcement).await
+    })
+    .await
+}
+
+/// Whether AuthIt! still needs its first-run setup. This is public, so the
+/// login page can send the operator there.
+#[post("/api/setup/required")]
+pub async fn setup_required() -> ServerFnResult<bool> {
+    Ok(server::first_run::is_needed())
+}
+
+/// The setup functions are public, but need the code AuthIt! logs at startup.
+#[post("/api/setup/check")]
+pub async fn check_setup(code: String) -> ServerFnResult<Vec<SetupCheck>> {
+    Ok(server::first_run::check(&code).await?)
+}
+
+#[post("/api/setup/groups")]
+pub async fn list_setup_groups(code: String) -> ServerFnResult<Vec<String>> {
+    Ok(server::first_run::candidate_groups(&code).await?)
+}
+
+/// The admin group the setup has to fill, when it's already chosen but
+/// nobody is in it.
+#[post("/api/setup/chosen-group")]
+pub async fn chosen_setup_group(code: String) -> ServerFnResult<Option<String>> {
+    Ok(server::first_run::chosen_group(&code)?)
+}
+
+/// Finish the setup, creating the admin group if asked to and it's missing,
+/// and adding `bootstrap_user` to it.
+#[post("/api/setup/complete")]
+pub async fn complete_setup(
+    code: String,
+    admin_group: String,
+    create_missing: bool,
+    bootstrap_user: Option<String>,
+) -> ServerFnResult<()> {
+    // Nobody's signed in yet; the setup code is what authorizes this.
+    Ok(server::as_service(server::first_run::complete(
+        &code,
+        &admin_group,
+        create_missing,
+        bootstrap_user.as_deref(),
+    ))
+    .await?)
+}
+
+#[post("/api/maintenance")]
+pub async fn get_maintenance() -> ServerFnResult<Option<Maintenance>> {
+    server::with_admin_session(|_| async { server::maintenance::get().await }).await
+}
+
+/// Start maintenance mode with `message` for everyone else, or end it with
+/// `None`. Admins can keep working either way.
+#[post("/api/maintenance/set")]
+pub async fn set_maintenance(message: Option<String>) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        match message {
+            Some(message) => server::maintenance::start(&message, &admin.username).await,
+            None => server::maintenance::end(&admin.username).await,
+        }
+    })
+    .await
+}
+
+/// The ticket the current admin is working on, and whether dangerous changes
+/// need one.
+#[post("/api/ticket")]
+pub async fn get_ticket_status() -> ServerFnResult<TicketStatus> {
+    server::get_ticket_status().await
+}
+
+/// Set the ticket the current admin is working on, which is recorded with
+/// their changes, or clear it with `None`.
+#[post("/api/ticket/set")]
+pub async fn set_ticket(ticket: Option<String>) -> ServerFnResult<()> {
+    server::set_ticket(ticket).await
+}
+
+/// Whether dangerous changes, like deleting users, are refused until the
+/// admin enters a ticket.
+#[post("/api/ticket/require")]
+pub async fn set_require_ticket(required: bool) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::ticket::set_required(required).await })
+        .await
+}
+
+/// Whether every new account must have an email address.
+#[post("/api/settings/require-email")]
+pub async fn get_require_email() -> ServerFnResult<bool> {
+    server::with_admin_session(|_| async { server::required_email::is_required().await }).await
+}
+
+/// Require an email address for every new account, whether an admin creates
+/// it or a provision link does.
+#[post("/api/settings/require-email/set")]
+pub async fn set_require_email(required: bool) -> ServerFnResult<()> {
+    server::with_admin_session(
+        |_| async move { server::required_email::set_required(required).await },
+    )
+    .await
+}
+
+/// Whether visitors can request an account. This is public, so the login page
+/// can link to the request form.
+#[post("/api/account-requests/enabled")]
+pub async fn account_requests_enabled() -> ServerFnResult<bool> {
+    Ok(server::CONFIG.account_requests_enabled)
+}
+
+/// Request an account. This is public.
+#[post("/api/account-requests/submit")]
+pub async fn submit_account_request(
+    name: String,
+    email_address: String,
+    justification: String,
+    idempotency_key: Option<Uuid>,
+) -> ServerFnResult<()> {
+    Ok(server::idempotency::once(
+        idempotency_key,
+        "/api/account-requests/submit",
+        move || async move {
+            server::throttle_account_request().await?;
+            server::account_request::submit(&name, &email_address, &justification).await
+        },
+    )
+    .await?)
+}
+
+#[post("/api/account-requests")]
+pub async fn list_account_requests() -> ServerFnResult<Vec<AccountRequest>> {
+    server::with_admin_session(|_| async { server::storage::AccountRequests::list().await }).await
+}
+
+#[post("/api/account-requests/approve")]
+pub async fn approve_account_request(id: Uuid) -> ServerFnResult<AccountRequestApproval> {
+    server::with_admin_session(|user| async move {
+        server::account_request::approve(id, &user.username).await
+    })
+    .await
+}
+
+/// Deny an account request, returning whether the requester was emailed.
+#[post("/api/account-requests/deny")]
+pub async fn deny_account_request(id: Uuid, reason: String) -> ServerFnResult<bool> {
+    server::with_admin_session(|user| async move {
+        server::account_request::deny(id, &reason, &user).await
+    })
+    .await
+}
+
+/// The signed-in user's own profile, and what they may change on it.
+#[post("/api/profile")]
+pub async fn get_my_profile() -> ServerFnResult<MyProfile> {
+    server::with_session(|user| async move { server::profile_change::my_profile(&user).await })
+        .await
+}
+
+/// Change the signed-in user's display name or email, returning whether it
+/// was made straight away rather than left for an admin to approve.
+#[post("/api/profile/change")]
+pub async fn propose_profile_change(field: ProfileField, value: String) -> ServerFnResult<bool> {
+    server::with_session(|user| async move {
+        server::profile_change::propose(&user, field, &value).await
+    })
+    .await
+}
+
+/// Email a code to one of the signed-in user's addresses, to verify it.
+#[post("/api/profile/verify-email")]
+pub async fn send_email_verification(address: String) -> ServerFnResult<()> {
+    server::with_session(|user| async move {
+        server::email_verification::send_code(&user, &address).await
+    })
+    .await
+}
+
+/// Verify one of the signed-in user's addresses with the code sent to it.
+#[post("/api/profile/verify-email/confirm")]
+pub async fn confirm_email_verification(address: String, code: String) -> ServerFnResult<()> {
+    server::with_session(|user| async move {
+        server::email_verification::confirm(&user, &address, &code).await
+    })
+    .await
+}
+
+#[post("/api/profile-changes")]
+pub async fn list_profile_changes() -> ServerFnResult<Vec<ProfileChange>> {
+    server::with_admin_session(|_| async { server::storage::ProfileChanges::list().await }).await
+}
+
+#[post("/api/profile-changes/approve")]
+pub async fn approve_profile_change(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(
+        |admin| async move { server::profile_change::approve(id, &admin).await },
+    )
+    .await
+}
+
+#[post("/api/profile-changes/deny")]
+pub async fn deny_profile_change(id: Uuid, reason: String) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::profile_change::deny(id, &reason, &admin).await
+    })
+    .await
+}
+
+/// Notification settings, with secrets blanked.
+#[post("/api/notifications")]
+pub async fn get_notification_settings() -> ServerFnResult<NotificationSettings> {
+    server::with_admin_session(|_| async { Ok(server::notify::get_settings().await?.redacted()) })
+        .await
+}
+
+/// Save notification settings. Blank secrets keep their existing values.
+#[post("/api/notifications/set")]
+pub async fn set_notification_settings(settings: NotificationSettings) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::notify::set_settings(settings).await })
+        .await
+}
+
+/// Send a test message to every configured notifier.
+#[post("/api/notifications/test")]
+pub async fn send_test_notification() -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::notify::send_test(format!("{} sent a test notification.", admin.username)).await
+    })
+    .await
+}
+
+#[post("/api/offboarding")]
+pub async fn list_offboardings() -> ServerFnResult<Vec<Offboarding>> {
+    server::with_admin_session(|_| async { server::storage::Offboardings::list().await }).await
+}
+
+/// Onboardings with steps left, checking Kanidm for progress first.
+#[post("/api/onboarding")]
+pub async fn list_incomplete_onboardings() -> ServerFnResult<Vec<Onboarding>> {
+    server::with_admin_session(|_| async { server::onboarding::refresh_incomplete().await }).await
+}
+
+/// Stop tracking a user's onboarding.
+#[post("/api/onboarding/dismiss")]
+pub async fn dismiss_onboarding(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::storage::Onboardings::delete(id).await })
+        .await
+}
+
+/// Notes admins have left for each other, newest first.
+#[post("/api/shift-log")]
+pub async fn list_shift_notes() -> ServerFnResult<Vec<ShiftNote>> {
+    server::with_admin_session(|_| async { server::shift_log::list().await }).await
+}
+
+#[post("/api/shift-log/add")]
+pub async fn add_shift_note(content: String) -> ServerFnResult<ShiftNote> {
+    server::with_admin_session(
+        |admin| async move { server::shift_log::add(&content, &admin).await },
+    )
+    .await
+}
+
+#[post("/api/shift-log/delete")]
+pub async fn delete_shift_note(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::storage::ShiftNotes::delete(id).await })
+        .await
+}
+
+#[post("/api/offboarding/start")]
+pub async fn start_offboarding(user_id: Uuid) -> ServerFnResult<Offboarding> {
+    server::with_admin_session(
+        |user| async move { server::offboarding::start(user_id, &user).await },
+    )
+    .await
+}
+
+/// A user's or group's Kanidm entry exactly as Kanidm returns it, pretty
+/// printed, for debugging what AuthIt! doesn't show. Secrets are redacted.
+#[post("/api/entries/raw")]
+pub async fn get_raw_entry(kind: EntryKind, id: Uuid) -> ServerFnResult<String> {
+    server::with_admin_session(|admin| async move { server::raw_entry(&admin, kind, &id).await })
+        .await
+}
+
+/// Everything we hold about a user, for a subject-access request.
+#[post("/api/users/export")]
+pub async fn export_user(user_id: Uuid) -> ServerFnResult<UserExport> {
+    server::with_admin_session(|admin| async move {
+        let mut export = server::export::export_user(user_id, &admin.username).await?;
+        if !server::can_see_legal_names(&admin).await? {
+            export.kanidm_attributes.remove("legalname");
+        }
+        Ok(export)
+    })
+    .await
+}
+
+#[post("/api/offboarding/step")]
+pub async fn set_offboarding_step(id: Uuid, index: u32, done: bool) -> ServerFnResult<Offboarding> {
+    server::with_admin_session(|user| async move {
+        server::offboarding::set_step(id, index as usize, done, &user).await
+    })
+    .await
+}
+
+/// Schedule an offboarded user's deletion. Like deleting a user directly, this
+/// requires a recent sign-in.
+#[post("/api/offboarding/schedule-deletion")]
+pub async fn schedule_offboarding_deletion(
+    id: Uuid,
+    delete_at: Option<Timestamp>,
+) -> ServerFnResult<Offboarding> {
+    server::with_recent_admin_session(|_| async move {
+        server::offboarding::schedule_deletion(id, delete_at).await
+    })
+    .await
+}
+
+#[post("/api/offboarding/disable")]
+pub async fn disable_offboarded_account(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::offboarding::disable_account(id).await })
+        .await
+}
+
+#[post("/api/offboarding/cancel")]
+pub async fn cancel_offboarding(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::storage::Offboardings::delete(id).await })
+        .await
+}
+
+#[post("/api/offboarding/template")]
+pub async fn get_offboarding_template() -> ServerFnResult<ChecklistTemplate> {
+    server::with_admin_session(|_| async { server::offboarding::get_template().await }).await
+}
+
+#[post("/api/offboarding/template/set")]
+pub async fn set_offboarding_template(template: ChecklistTemplate) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::offboarding::set_template(template).await })
+        .await
+}
+
+#[post("/api/rotation")]
+pub async fn list_rotation_campaigns() -> ServerFnResult<Vec<RotationCampaign>> {
+    server::with_admin_session(|_| async { server::storage::RotationCampaigns::list().await }).await
+}
+
+/// Ask users to change their credentials by a deadline. As it can lock their
+/// accounts, this requires a recent sign-in.
+#[post("/api/rotation/start")]
+pub async fn start_rotation_campaign(
+    name: String,
+    user_ids: Vec<Uuid>,
+    deadline: Timestamp,
+    lock_at_deadline: bool,
+) -> ServerFnResult<RotationCampaign> {
+    server::with_recent_admin_session(|admin| async move {
+        server::rotation::start(&name, user_ids, deadline, lock_at_deadline, &admin).await
+    })
+    .await
+}
+
+/// Stop a campaign without locking anyone's account.
+#[post("/api/rotation/cancel")]
+pub async fn cancel_rotation_campaign(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(
+        |_| async move { server::storage::RotationCampaigns::delete(id).await },
+    )
+    .await
+}
+
+#[post("/api/retention")]
+pub async fn get_retention_policy() -> ServerFnResult<RetentionPolicy> {
+    server::with_admin_session(|_| async { server::retention::get_policy().await }).await
+}
+
+#[post("/api/retention/set")]
+pub async fn set_retention_policy(policy: RetentionPolicy) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::retention::set_policy(policy).await }).await
+}
+
+/// Purge old data now, rather than waiting for the background job.
+#[post("/api/retention/purge")]
+pub async fn purge_old_data() -> ServerFnResult<PurgeResult> {
+    server::with_admin_session(|_| async { server::retention::purge().await }).await
+}
+
+/// The last month's trends, for the dashboard's charts.
+#[post("/api/stats/dashboard")]
+pub async fn get_dashboard_stats() -> ServerFnResult<DashboardStats> {
+    server::with_admin_session(|_| async { server::stats::dashboard().await }).await
+}
+
+/// What happened from the start of `from` to the end of `to`, in UTC.
+#[post("/api/reports")]
+pub async fn get_report(from: jiff::civil::Date, to: jiff::civil::Date) -> ServerFnResult<Report> {
+    server::with_admin_session(|_| async move { server::report::generate(from, to).await }).await
+}
+
+#[post("/api/reports/settings")]
+pub async fn get_report_settings() -> ServerFnResult<ReportSettings> {
+    server::with_admin_session(|_| async { server::report::get_settings().await }).await
+}
+
+#[post("/api/reports/settings/set")]
+pub async fn set_report_settings(settings: ReportSettings) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::report::set_settings(settings).await })
+        .await
+}
+
+/// Who may see each field in the directory and on user pages.
+#[post("/api/visibility")]
+pub async fn get_field_visibility() -> ServerFnResult<FieldVisibility> {
+    server::with_admin_session(|_| async { server::visibility::get().await }).await
+}
+
+#[post("/api/visibility/set")]
+pub async fn set_field_visibility(visibility: FieldVisibility) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::visibility::set(visibility).await }).await
+}
+
+/// Which build of AuthIt! is running.
+#[post("/api/server-info")]
+pub async fn get_server_info() -> ServerFnResult<ServerInfo> {
+    server::with_session(|_| async { Ok(ServerInfo::current()) }).await
+}
+
+#[post("/api/domain")]
+pub async fn get_domain_info() -> ServerFnResult<DomainInfo> {
+    server::with_admin_session(|_| async { Ok(server::KANIDM_CLIENT.get_domain().await?) }).await
+}
+
+/// Check again what AuthIt!'s Kanidm token can do.
+#[post("/api/token-capabilities/check")]
+pub async fn check_token_capabilities() -> ServerFnResult<TokenCapabilities> {
+    server::with_admin_session(|_| async { Ok(server::capabilities::probe().await) }).await
+}
+
+/// The Kanidm servers we talk to, and which are serving requests.
+#[post("/api/kanidm-nodes")]
+pub async fn get_kanidm_nodes() -> ServerFnResult<Vec<KanidmNode>> {
+    server::with_admin_session(|_| async { Ok(server::KANIDM_CLIENT.nodes()) }).await
+}
+
+/// How many requests we have open to Kanidm, and how many are queued.
+#[post("/api/kanidm-load")]
+pub async fn get_kanidm_load() -> ServerFnResult<KanidmLoad> {
+    server::with_admin_session(|_| async { Ok(server::KANIDM_CLIENT.load()) }).await
+}
+
+/// The latest events, newest first.
+#[post("/api/activity")]
+pub async fn list_recent_activity(limit: u32) -> ServerFnResult<Vec<Activity>> {
+    server::with_admin_session(|_| async move {
+        server::storage::Activities::list_recent(limit.min(100)).await
+    })
+    .await
+}
+
+/// Search the audit history for activity mentioning every word of `text`,
+/// newest first.
+#[post("/api/activity/search")]
+pub async fn search_activity(text: String) -> ServerFnResult<Vec<ActivityMatch>> {
+    server::with_admin_session(|_| async move { server::audit_search::search(&text).await }).await
+}
+
+/// The Kanidm API calls an activity made, with secrets redacted.
+#[post("/api/activity/explain")]
+pub async fn explain_activity(id: Uuid) -> ServerFnResult<Vec<KanidmCall>> {
+    server::with_admin_session(|_| async move { server::explain::explain(id).await }).await
+}
+
+/// Export the activity stream from `from` up to `to`, with a signed manifest
+/// if `sign` is set.
+#[post("/api/audit/export")]
+pub async fn export_audit_log(
+    from: Timestamp,
+    to: Timestamp,
+    format: AuditFormat,
+    sign: bool,
+) -> ServerFnResult<AuditExport> {
+    server::with_admin_session(|admin| async move {
+        server::audit_export::export(from, to, format, sign, &admin).await
+    })
+    .await
+}
+
+/// AuthIt!'s settings as a JSON document. Including notifier secrets needs a
+/// recent sign-in.
+#[post("/api/settings/export")]
+pub async fn export_settings(include_secrets: bool) -> ServerFnResult<SettingsExport> {
+    if include_secrets {
+        server::with_recent_admin_session(|admin| async move {
+            server::settings_export::export(&admin, true).await
+        })
+        .await
+    } else {
+        server::with_admin_session(|admin| async move {
+            server::settings_export::export(&admin, false).await
+        })
+        .await
+    }
+}
+
+/// Show what importing a settings document would change, and with `apply`,
+/// change it.
+#[post("/api/settings/import")]
+pub async fn import_settings(
+    document: SettingsExport,
+    apply: bool,
+) -> ServerFnResult<SettingsImport> {
+    server::with_recent_admin_session(|admin| async move {
+        server::settings_export::import(document, apply, &admin).await
+    })
+    .await
+}
+
+/// Check an export against its signed manifest.
+#[post("/api/audit/verify")]
+pub async fn verify_audit_export(content: String, manifest: AuditManifest) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::audit_export::verify(&content, &manifest) })
+        .await
+}
+
+/// People who could be mistaken for each other, as of the last integrity
+/// check.
+#[post("/api/integrity")]
+pub async fn get_integrity_report() -> ServerFnResult<Option<IntegrityReport>> {
+    server::with_admin_session(|_| async { server::integrity::report().await }).await
+}
+
+/// Run the integrity check now, rather than waiting for the next one.
+#[post("/api/integrity/check")]
+pub async fn run_integrity_check() -> ServerFnResult<IntegrityReport> {
+    server::with_admin_session(|_| async { server::integrity::check().await }).await
+}
+
+/// Delete the local records about a user or group the integrity check found
+/// is gone from Kanidm.
+#[post("/api/integrity/clean-up")]
+pub async fn clean_up_orphan(kind: EntryKind, id: Uuid) -> ServerFnResult<IntegrityReport> {
+    server::with_admin_session(|_| async move { server::integrity::clean_up(kind, id).await }).await
+}
+
+/// Which database migrations are applied, and any that don't match this
+/// build.
+#[post("/api/migrations")]
+pub async fn get_migration_status() -> ServerFnResult<MigrationStatus> {
+    server::with_admin_session(|_| async { server::storage::migration_status().await }).await
+}
+
+/// The latest sign-ins to AuthIt! by anyone, newest first.
+#[post("/api/logins")]
+pub async fn list_login_events(limit: u32) -> ServerFnResult<Vec<LoginEvent>> {
+    server::with_admin_session(|_| async move {
+        server::storage::LoginEvents::list_recent(limit.min(200)).await
+    })
+    .await
+}
+
+/// A user's latest sign-ins to AuthIt!, newest first.
+#[post("/api/users/logins")]
+pub async fn list_user_login_events(user_id: Uuid) -> ServerFnResult<Vec<LoginEvent>> {
+    server::with_admin_session(|_| async move {
+        server::storage::LoginEvents::list_for_user(&user_id.to_string(), 20).await
+    })
+    .await
+}
+
+/// Check that the activity stream's hash chain is intact.
+#[post("/api/audit/chain")]
+pub async fn verify_audit_chain() -> ServerFnResult<ChainStatus> {
+    server::with_admin_session(|_| async { server::audit_chain::verify().await }).await
+}
+
+/// How often each admin has called each server function.
+#[post("/api/usage")]
+pub async fn get_api_usage() -> ServerFnResult<Vec<AdminApiUsage>> {
+    server::with_admin_session(|_| async { server::api_usage::by_admin().await }).await
+}
+
+#[post("/api/usage/clear")]
+pub async fn clear_api_usage() -> ServerFnResult<()> {
+    server::with_admin_session(|_| async { server::api_usage::clear().await }).await
+}
+
+#[post("/api/break-glass")]
+pub async fn get_break_glass_status() -> ServerFnResult<BreakGlassStatus> {
+    server::with_admin_session(|_| async { server::storage::BreakGlass::status().await }).await
+}
+
+#[post("/api/break-glass/password")]
+pub async fn set_break_glass_password(password: String) -> ServerFnResult<()> {
+    server::with_recent_admin_session(|user| async move {
+        server::storage::BreakGlass::set_password(&password.into(), &user.username).await
+    })
+    .await
+}
+
+#[post("/api/capture")]
+pub async fn get_capture_status() -> ServerFnResult<CaptureStatus> {
+    server::with_admin_session(|_| async { server::capture::status().await }).await
+}
+
+/// Start recording calls to Kanidm and failed server functions for
+/// `minutes`, to attach to a bug report.
+#[post("/api/capture/start")]
+pub async fn start_capture(minutes: u32) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::capture::start(minutes, &admin.username).await
+    })
+    .await
+}
+
+#[post("/api/capture/stop")]
+pub async fn stop_capture() -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move { server::capture::stop(&admin.username).await })
+        .await
+}
+
+#[post("/api/capture/discard")]
+pub async fn discard_capture() -> ServerFnResult<()> {
+    server::with_admin_session(|_| async { server::capture::discard().await }).await
+}
+
+#[post("/api/capture/bundle")]
+pub async fn get_capture_bundle() -> ServerFnResult<CaptureBundle> {
+    server::with_admin_session(|_| async { server::capture::bundle().await }).await
+}
+
+/// Create a group with the user in it, optionally starting with the members
+/// of a template group.
+#[post("/api/users/groups/create")]
+pub async fn create_group_for_user(
+    user_id: PersonId,
+    name: String,
+    description: Option<String>,
+    template: Option<GroupId>,
+    idempotency_key: Option<Uuid>,
+) -> ServerFnResult<Group> {
+    server::with_admin_session(|admin| {
+        server::idempotency::once(
+            idempotency_key,
+            "/api/users/groups/create",
+            move || async move {
+                let person = server::IDENTITY.get_person(&user_id.to_string()).await?;
+                let group = server::group_creation::create_with_member(
+                    &name,
+                    description.as_deref(),
+                    template,
+                    user_id,
+                )
+                .await?;
+                server::notify::send(
+                    server::notify::Notification::new(
+                        types::notification::NotificationEvent::GroupMembershipChanged,
+                        format!("{} changed {}'s groups.", admin.username, person.name),
+                    )
+                    .detail("Added to new group", group.name.clone())
+                    .involving_groups([group.name.clone()])
+                    .by(&admin)
+                    .about_user(user_id.into(), person.name),
+                );
+                Ok(group)
+            },
+        )
+    })
+    .await
+}
+
+#[post("/api/users/groups")]
+pub async fn update_user_group(
+    user_id: PersonId,
+    group_id: GroupId,
+    add: bool,
+    modified: Option<String>,
+) -> ServerFnResult<()> {
+    // Changing who is an admin requires a recent sign-in.
+    let group =
+        server::with_admin_session(
+            |_| async move { Ok(server::IDENTITY.get_group(&group_id).await?) },
+        )
+        .await?;
+    if group.name == server::CONFIG.admin_group() {
+        if !add {
+            server::forbid_self_lockout(
+                user_id.into(),
+                "You can't remove yourself from the admin group.",
+            )
+            .await?;
+        }
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| async move {
+        let person = server::IDENTITY
+            .get_unmodified_person(&user_id, modified.as_deref())
+            .await?;
+        let action = if add {
+            server::email_verification::check_groups(user_id.into(), [group.name.as_str()]).await?;
+            server::group_review::check_groups([group.name.as_str()]).await?;
+            server::IDENTITY
+                .add_user_to_group(&group_id, &user_id)
+                .await?;
+            MembershipAction::Added
+        } else {
+            server::IDENTITY
+                .remove_user_from_group(&group_id, &user_id)
+                .await?;
+            MembershipAction::Removed
+        };
+        server::membership::changed(&group, user_id, action).await;
+
+        let change = if add { "Added to" } else { "Removed from" };
+        server::notify::send(
+            server::notify::Notification::new(
+                types::notification::NotificationEvent::GroupMembershipChanged,
+                format!("{} changed {}'s groups.", admin.username, person.name),
+            )
+            .detail(change, group.name.clone())
+            .involving_groups([group.name])
+            .by(&admin)
+            .about_user(user_id.into(), person.name),
+        );
+        Ok(())
+    })
+    .await
+}
+
+/// Set a user's group memberships to exactly `group_ids`, applying only the
+/// needed changes. Individual failures are reported rather than aborting.
+///
+/// Refused if the user changed since `modified` was read from them.
+#[post("/api/users/set-groups")]
+pub async fn set_user_groups(
+    user_id: PersonId,
+    group_ids: Vec<GroupId>,
+    modified: Option<String>,
+) -> ServerFnResult<GroupMembershipChanges> {
+    // The person is read before anything changes, so that the changes are
+    // reported even if they can't be read afterwards.
+    let (person, plan) = server::with_admin_session(|_| async move {
+        let person = server::IDENTITY
+            .get_unmodified_person(&user_id, modified.as_deref())
+            .await?;
+        let plan = server::IDENTITY
+            .plan_user_groups(&user_id, &group_ids)
+            .await?;
+        Ok((person, plan))
+    })
+    .await?;
+
+    // Changing who is an admin requires a recent sign-in.
+    if plan.touches(server::CONFIG.admin_group()) {
+        if plan
+            .remove
+            .iter()
+            .any(|g| g.name == server::CONFIG.admin_group())
+        {
+            server::forbid_self_lockout(
+                user_id.into(),
+                "You can't remove yourself from the admin group.",
+            )
+            .await?;
+        }
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| async move {
+        server::email_verification::check_groups(
+            user_id.into(),
+            plan.add.iter().map(|g| g.name.as_str()),
+        )
+        .await?;
+        server::group_review::check_groups(plan.add.iter().map(|g| g.name.as_str())).await?;
+        let groups: Vec<Group> = plan.add.iter().chain(&plan.remove).cloned().collect();
+        let changes = server::IDENTITY.apply_group_plan(&user_id, plan).await;
+        for (ids, action) in [
+            (&changes.added, MembershipAction::Added),
+            (&changes.removed, MembershipAction::Removed),
+        ] {
+            for group in groups.iter().filter(|g| ids.contains(&g.uuid)) {
+                server::membership::changed(group, user_id, action).await;
+            }
+        }
+
+        if !changes.added.is_empty() || !changes.removed.is_empty() {
+            let names = |ids: &[Uuid]| {
+                groups
+                    .iter()
+                    .filter(|g| ids.contains(&g.uuid))
+                    .map(|g| g.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            server::notify::send(
+                server::notify::Notification::new(
+                    types::notification::NotificationEvent::GroupMembershipChanged,
+                    format!("{} changed {}'s groups.", admin.username, person.name),
+                )
+                .detail("Added to", names(&changes.added))
+                .detail("Removed from", names(&changes.removed))
+                .involving_groups(
+                    groups
+                        .iter()
+                        .filter(|g| {
+                            changes.added.contains(&g.uuid) || changes.removed.contains(&g.uuid)
+                        })
+                        .map(|g| g.name.clone()),
+                )
+                .by(&admin)
+                .about_user(user_id.into(), person.name),
+            );
+        }
+
+        Ok(changes)
+    })
+    .await
+}
+
+/// Add a user to or remove them from a group at `run_at`. Like changing who
+/// is an admin straight away, scheduling it requires a recent sign-in.
+#[post("/api/scheduled-membership/create")]
+pub async fn schedule_membership_change(
+    user_id: PersonId,
+    group_id: GroupId,
+    add: bool,
+    run_at: Timestamp,
+) -> ServerFnResult<ScheduledMembershipChange> {
+    let group =
+        server::with_admin_session(
+            |_| async move { Ok(server::IDENTITY.get_group(&group_id).await?) },
+        )
+        .await?;
+    if group.name == server::CONFIG.admin_group() {
+        if !add {
+            server::forbid_self_lockout(
+                user_id.into(),
+                "You can't remove yourself from the admin group.",
+            )
+            .await?;
+        }
+        server::require_recent_auth().await?;
+    }
+
+    let action = if add {
+        MembershipAction::Added
+    } else {
+        MembershipAction::Removed
+    };
+    server::with_admin_session(|admin| async move {
+        server::scheduled_membership::schedule(
+            user_id.into(),
+            group_id.into(),
+            action,
+            run_at,
+            &admin,
+        )
+        .await
+    })
+    .await
+}
+
+/// A user's membership changes still waiting to be made.
+#[post("/api/scheduled-membership/user")]
+pub async fn list_user_scheduled_changes(
+    user_id: Uuid,
+) -> ServerFnResult<Vec<ScheduledMembershipChange>> {
+    server::with_admin_session(|_| async move {
+        server::storage::ScheduledMembershipChanges::pending_for_user(user_id).await
+    })
+    .await
+}
+
+/// A group's membership changes still waiting to be made.
+#[post("/api/scheduled-membership/group")]
+pub async fn list_group_scheduled_changes(
+    group_id: Uuid,
+) -> ServerFnResult<Vec<ScheduledMembershipChange>> {
+    server::with_admin_session(|_| async move {
+        server::storage::ScheduledMembershipChanges::pending_for_group(group_id).await
+    })
+    .await
+}
+
+#[post("/api/scheduled-membership/cancel")]
+pub async fn cancel_scheduled_membership_change(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::scheduled_membership::cancel(id, &admin).await
+    })
+    .await
+}
+
+/// Apply a reviewed batch of staged changes. They're all checked before any
+/// are applied, and if one fails, the ones before it are rolled back.
+///
+/// `modified` has each user's `Person::modified` as last loaded; if any of
+/// them changed since, the batch is refused with a conflict.
+#[post("/api/changes/apply")]
+pub async fn apply_changes(
+    changes: Vec<PendingChange>,
+    modified: HashMap<Uuid, String>,
+) -> ServerFnResult<BatchResult> {
+    let changes = server::with_admin_session(|admin| async move {
+        server::batch::prepare(&admin, changes, &modified).await
+    })
+    .await?;
+
+    // Changing who is an admin requires a recent sign-in.
+    let admin_group = server::CONFIG.admin_group();
+    let mut touches_admin_group = false;
+    for change in &changes {
+        match change {
+            PendingChange::RemoveFromGroup {
+                user_id,
+                group_name,
+                ..
+            } if group_name == admin_group => {
+                server::forbid_self_lockout(
+                    *user_id,
+                    "You can't remove yourself from the admin group.",
+                )
+                .await?;
+                touches_admin_group = true;
+            }
+            PendingChange::AddToGroup { group_name, .. } if group_name == admin_group => {
+                touches_admin_group = true;
+            }
+            _ => {}
+        }
     }
+    if touches_admin_group {
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| async move {
+        let result = server::batch::apply(changes).await;
+
+        let group_changes: Vec<String> = result
+            .results
+            .iter()
+            .filter(|r| r.change.group_id().is_some())
+            .map(|r| format!("{}: {}", r.change.user_name(), r.change.describe()))
+            .collect();
+        if result.is_complete() && !group_changes.is_empty() {
+            server::notify::send(
+                server::notify::Notification::new(
+                    types::notification::NotificationEvent::GroupMembershipChanged,
+                    format!("{} applied a batch of changes.", admin.username),
+                )
+                .detail("Changes", group_changes.join("; "))
+                .involving_groups(
+                    result
+                        .results
+                        .iter()
+                        .filter_map(|r| r.change.group_name())
+                        .map(String::from),
+                )
+                .by(&admin),
+            );
+        }
+
+        Ok(result)
+    })
+    .await
 }
 
-#[post("/api/users")]
-pub async fn list_users() -> ServerFnResult<Vec<Person>> {
-    server::with_admin_session(|_| async { Ok(server::KANIDM_CLIENT.list_persons().await?) }).await
+/// Which groups count as built-in.
+#[post("/api/groups/classification")]
+pub async fn get_group_classification() -> ServerFnResult<GroupClassification> {
+    server::with_admin_session(|_| async { Ok(server::CONFIG.group_classification()) }).await
 }
 
-#[post("/api/groups")]
-pub async fn list_groups() -> ServerFnResult<Vec<Group>> {
-    server::with_admin_session(|_| async { Ok(server::KANIDM_CLIENT.list_groups().await?) }).await
+/// How many people are in each group, by group ID.
+#[post("/api/groups/member-counts")]
+pub async fn group_member_counts() -> ServerFnResult<HashMap<Uuid, u32>> {
+    server::with_admin_session(|_| async { server::IDENTITY.group_member_counts().await }).await
 }
 
-#[post("/api/users/groups")]
-pub async fn update_user_group(user_id: Uuid, group_id: Uuid, add: bool) -> ServerFnResult<()> {
+/// Preview creating groups and reconciling their members from a CSV of
+/// `group,username` rows.
+#[post("/api/groups/import/preview")]
+pub async fn preview_group_import(csv: String) -> ServerFnResult<GroupImport> {
+    server::with_admin_session(
+        |_| async move { Ok(server::group_import::plan(&csv).await?.import) },
+    )
+    .await
+}
+
+/// Start importing groups from a CSV in the background. Poll the returned job
+/// for progress.
+#[post("/api/groups/import")]
+pub async fn start_group_import(csv: String) -> ServerFnResult<Uuid> {
+    let preview = server::with_admin_session(|_| {
+        let csv = csv.clone();
+        async move { Ok(server::group_import::plan(&csv).await?.import) }
+    })
+    .await?;
+
+    // Changing who is an admin requires a recent sign-in.
+    if preview
+        .changes
+        .iter()
+        .any(|c| c.group == server::CONFIG.admin_group())
+    {
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| async move {
+        let groups = preview
+            .changes
+            .iter()
+            .map(|c| c.group.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let id = server::bulk::start_group_import(&csv, &admin.username).await?;
+        server::notify::send(
+            server::notify::Notification::new(
+                types::notification::NotificationEvent::GroupMembershipChanged,
+                format!("{} imported groups from a CSV.", admin.username),
+            )
+            .detail("Groups", groups)
+            .involving_groups(preview.changes.iter().map(|c| c.group.clone()))
+            .by(&admin),
+        );
+        Ok(id)
+    })
+    .await
+}
+
+/// Start deleting several users in the background.
+#[post("/api/users/delete-many")]
+pub async fn start_delete_users(user_ids: Vec<Uuid>) -> ServerFnResult<Uuid> {
+    for user_id in &user_ids {
+        server::forbid_self_lockout(*user_id, "You can't delete your own account.").await?;
+    }
+    server::with_recent_admin_session(|admin| async move {
+        server::bulk::start_delete_users(&user_ids, &admin.username).await
+    })
+    .await
+}
+
+/// Start making credential reset links for several users in the background,
+/// optionally emailing each one to its user. The links are in the job's
+/// report.
+#[post("/api/users/reset-links")]
+pub async fn start_reset_credentials(user_ids: Vec<Uuid>, email: bool) -> ServerFnResult<Uuid> {
+    server::with_recent_admin_session(|admin| async move {
+        server::bulk::start_reset_credentials(&user_ids, email, &admin.username).await
+    })
+    .await
+}
+
+/// What deleting a group would affect, to show before confirming.
+#[post("/api/groups/deletion-impact")]
+pub async fn get_group_deletion_impact(group_id: Uuid) -> ServerFnResult<GroupDeletionImpact> {
+    server::with_admin_session(|_| async move { server::group_deletion::impact(group_id).await })
+        .await
+}
+
+/// Delete a group. Groups AuthIt! still uses need `force`.
+#[post("/api/groups/delete")]
+pub async fn delete_group(group_id: Uuid, force: bool) -> ServerFnResult<()> {
+    server::with_recent_admin_session(|_| async move {
+        server::group_deletion::delete(group_id, force).await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Start making `user_ids` the group's only direct members, in the
+/// background.
+#[post("/api/groups/reconcile")]
+pub async fn start_group_reconcile(
+    group_id: GroupId,
+    user_ids: Vec<PersonId>,
+) -> ServerFnResult<Uuid> {
+    let plan = server::with_admin_session(|_| async move {
+        server::bulk::plan_group_reconcile(group_id, &user_ids).await
+    })
+    .await?;
+
+    // Changing who is an admin requires a recent sign-in.
+    if plan.group.name == server::CONFIG.admin_group() {
+        for person in &plan.remove {
+            server::forbid_self_lockout(
+                person.uuid,
+                "You can't remove yourself from the admin group.",
+            )
+            .await?;
+        }
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| async move {
+        let group = plan.group.name.clone();
+        let id = server::bulk::start_group_reconcile(plan, &admin.username).await?;
+        server::notify::send(
+            server::notify::Notification::new(
+                types::notification::NotificationEvent::GroupMembershipChanged,
+                format!("{} set {group}'s members.", admin.username),
+            )
+            .by(&admin)
+            .about_group(group),
+        );
+        Ok(id)
+    })
+    .await
+}
+
+/// Bulk jobs, newest first.
+#[post("/api/jobs")]
+pub async fn list_jobs() -> ServerFnResult<Vec<Job>> {
+    server::with_admin_session(|_| async { server::storage::Jobs::list().await }).await
+}
+
+#[post("/api/jobs/get")]
+pub async fn get_job(id: Uuid) -> ServerFnResult<Job> {
+    server::with_admin_session(|_| async move { server::storage::Jobs::find(id).await }).await
+}
+
+/// The result of each item a job has tried so far.
+#[post("/api/jobs/items")]
+pub async fn get_job_items(id: Uuid) -> ServerFnResult<Vec<JobItem>> {
+    server::with_admin_session(|_| async move { server::storage::Jobs::items(id).await }).await
+}
+
+/// The jobs the server runs on a schedule, with how each last went.
+#[post("/api/background-jobs")]
+pub async fn list_background_jobs() -> ServerFnResult<Vec<BackgroundJob>> {
+    server::with_admin_session(|_| async { server::jobs::list().await }).await
+}
+
+/// Start a background job now, like to retry one that failed, without
+/// waiting for it to finish.
+#[post("/api/background-jobs/run")]
+pub async fn run_background_job(name: String) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move { server::jobs::run_now(&name, &admin) }).await
+}
+
+/// The latest emails sent or still being tried, newest first, or only those
+/// given up on.
+#[post("/api/email")]
+pub async fn list_emails(failed_only: bool) -> ServerFnResult<Vec<QueuedEmail>> {
+    server::with_admin_session(|_| async move { server::email::list(failed_only).await }).await
+}
+
+/// Try an email that was given up on again.
+#[post("/api/email/retry")]
+pub async fn retry_email(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::email::retry(id).await }).await
+}
+
+/// How a user appears over LDAP, or `None` if Kanidm's LDAP interface isn't
+/// enabled.
+#[post("/api/users/ldap")]
+pub async fn get_ldap_status(user_id: Uuid) -> ServerFnResult<Option<LdapStatus>> {
     server::with_admin_session(|_| async move {
-        if add {
-            server::KANIDM_CLIENT
-                .add_user_to_group(&group_id.to_string(), &user_id)
-                .await?;
-        } else {
-            server::KANIDM_CLIENT
-                .remove_user_from_group(&group_id, &user_id)
-                .await?;
+        if !server::CONFIG.ldap_enabled {
+            return Ok(None);
         }
-        Ok(())
+        Ok(Some(server::KANIDM_CLIENT.get_ldap_status(&user_id).await?))
+    })
+    .await
+}
+
+/// Replace a user's email addresses, in order. The first is their primary
+/// address.
+///
+/// Refused if the user changed since `modified` was read from them.
+#[post("/api/users/email-addresses")]
+pub async fn set_email_addresses(
+    user_id: Uuid,
+    addresses: Vec<String>,
+    modified: Option<String>,
+) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move {
+        let mut cleaned: Vec<String> = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let address = address.trim().to_string();
+            types::validation::validate_email(&address)?;
+            if cleaned.iter().any(|a| a.eq_ignore_ascii_case(&address)) {
+                return Err(types::err!("'{address}' is listed more than once"));
+            }
+            cleaned.push(address);
+        }
+
+        let user_id = PersonId(user_id);
+        server::IDENTITY
+            .get_unmodified_person(&user_id, modified.as_deref())
+            .await?;
+        server::IDENTITY
+            .set_email_addresses(&user_id, &cleaned)
+            .await
+    })
+    .await
+}
+
+/// A user's email addresses, and which they've verified.
+#[post("/api/users/email-verifications")]
+pub async fn list_email_verifications(user_id: Uuid) -> ServerFnResult<Vec<EmailAddressStatus>> {
+    server::with_admin_session(|_| async move {
+        let person = server::IDENTITY.get_person(&user_id.to_string()).await?;
+        server::email_verification::statuses(&person).await
+    })
+    .await
+}
+
+#[post("/api/users/phone-number")]
+pub async fn get_phone_number(user_id: Uuid) -> ServerFnResult<Option<String>> {
+    server::with_admin_session(|_| async move { server::storage::PhoneNumbers::get(user_id).await })
+        .await
+}
+
+/// Set or clear a user's phone number. It's stored in E.164 form.
+#[post("/api/users/phone-number/set")]
+pub async fn set_phone_number(user_id: Uuid, phone_number: Option<String>) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move {
+        match phone_number.filter(|p| !p.trim().is_empty()) {
+            Some(p) => {
+                let normalized = types::validation::normalize_phone(&p)?;
+                server::storage::PhoneNumbers::set(user_id, &normalized).await
+            }
+            None => server::storage::PhoneNumbers::delete(user_id).await,
+        }
+    })
+    .await
+}
+
+/// Whether each user has set up credentials, for filtering the user list.
+#[post("/api/users/credential-status")]
+pub async fn get_credential_status(user_ids: Vec<Uuid>) -> ServerFnResult<HashMap<Uuid, bool>> {
+    server::with_admin_session(|_| async move {
+        Ok(server::KANIDM_CLIENT.credential_status(&user_ids).await)
     })
     .await
 }
 
+#[post("/api/tags")]
+pub async fn list_tags() -> ServerFnResult<Vec<Tag>> {
+    server::with_admin_session(|_| async { server::tags::list().await }).await
+}
+
+#[post("/api/tags/create")]
+pub async fn create_tag(name: String) -> ServerFnResult<Tag> {
+    server::with_admin_session(|_| async move { server::tags::create(&name).await }).await
+}
+
+#[post("/api/tags/rename")]
+pub async fn rename_tag(id: Uuid, name: String) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::tags::rename(id, &name).await }).await
+}
+
+/// Delete a tag, taking it off everyone who has it.
+#[post("/api/tags/delete")]
+pub async fn delete_tag(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::tags::delete(id).await }).await
+}
+
+/// Each tagged user's tag ids.
+#[post("/api/users/tags")]
+pub async fn list_user_tags() -> ServerFnResult<HashMap<Uuid, Vec<Uuid>>> {
+    server::with_admin_session(|_| async { server::tags::by_user().await }).await
+}
+
+/// Give a user exactly `tag_ids`.
+#[post("/api/users/tags/set")]
+pub async fn set_user_tags(user_id: Uuid, tag_ids: Vec<Uuid>) -> ServerFnResult<()> {
+    server::with_admin_session(
+        |_| async move { server::tags::set_for_user(user_id, &tag_ids).await },
+    )
+    .await
+}
+
+/// Set a user's avatar from an uploaded image. It's resized to a square.
+#[post("/api/users/avatar")]
+pub async fn upload_avatar(user_id: Uuid, data: Vec<u8>) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::avatar::save(user_id, data).await }).await
+}
+
+#[post("/api/users/avatar/delete")]
+pub async fn delete_avatar(user_id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::avatar::delete(user_id).await }).await
+}
+
 #[post("/api/users/reset-link")]
 pub async fn generate_reset_link(user_id: Uuid) -> ServerFnResult<ResetLink> {
-    server::with_admin_session(|_| async move {
-        Ok(server::KANIDM_CLIENT
-            .generate_credential_reset_link(&user_id)
-            .await?)
+    server::with_admin_session(|admin| async move {
+        let link = server::IDENTITY
+            .generate_credential_reset_link(&PersonId(user_id))
+            .await?;
+        server::report::record(ReportEventKind::ResetLinkIssued);
+        server::reset_watch::start(user_id, &admin.username, &link).await;
+        Ok(link)
     })
     .await
 }
 
+/// What deleting a user would lose, to show before confirming.
+#[post("/api/users/deletion-impact")]
+pub async fn get_user_deletion_impact(user_id: Uuid) -> ServerFnResult<UserDeletionImpact> {
+    server::with_admin_session(|_| async move { server::user_deletion::impact(user_id).await })
+        .await
+}
+
 #[post("/api/users/delete")]
 pub async fn delete_user(user_id: Uuid) -> ServerFnResult<()> {
-    server::with_admin_session(|_| async move {
-        server::KANIDM_CLIENT.delete_person(&user_id).await?;
+    server::forbid_self_lockout(user_id, "You can't delete your own account.").await?;
+    server::with_recent_admin_session(|admin| async move {
+        let person = server::IDENTITY.get_person(&user_id.to_string()).await?;
+        server::IDENTITY.delete_person(&person.id()).await?;
+        server::report::record(ReportEventKind::AccountDeleted);
+        server::forget::forget_user(&person).await?;
+        server::notify::send(
+            server::notify::Notification::new(
+                types::notification::NotificationEvent::UserDeleted,
+                format!("{} deleted {}.", admin.username, person.name),
+            )
+            .by(&admin),
+        );
         Ok(())
     })
     .await
@@ -64,12 +1562,70 @@ pub async fn create_user(
     name: String,
     display_name: String,
     email_address: String,
+    setup: NewPersonSetup,
+    idempotency_key: Option<Uuid>,
 ) -> ServerFnResult<()> {
-    server::with_admin_session(|_| async {
-        server::KANIDM_CLIENT
-            .create_person(&name, &display_name, &email_address)
-            .await?;
-        Ok(())
+    let group_ids = setup.group_ids.clone();
+    let groups = server::with_admin_session(|_| async move {
+        Ok(server::IDENTITY
+            .list_groups()
+            .await?
+            .into_iter()
+            .filter(|g| group_ids.contains(&g.uuid))
+            .collect::<Vec<_>>())
+    })
+    .await?;
+
+    // Making someone an admin requires a recent sign-in.
+    if groups
+        .iter()
+        .any(|g| g.name == server::CONFIG.admin_group())
+    {
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| {
+        server::idempotency::once(idempotency_key, "/api/users/create", move || async move {
+            let mut setup = setup;
+            let mut errors = FieldErrors::default();
+            types::validation::check_person(&mut errors, &name, &display_name);
+            let email_required = server::required_email::is_required().await?;
+            types::validation::check_new_email(&mut errors, &email_address, email_required);
+            setup.normalize(&mut errors);
+            errors.into_result()?;
+            if setup.legal_name.is_some() && !server::can_see_legal_names(&admin).await? {
+                return Err(types::err!("you aren't allowed to set legal names"));
+            }
+            let group_names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+            server::email_verification::check_new_person_groups(group_names.iter().copied())?;
+            server::group_review::check_groups(group_names.iter().copied()).await?;
+
+            let person = server::IDENTITY
+                .create_person_with_setup(&name, &display_name, &email_address, &setup)
+                .await?;
+            server::report::record(ReportEventKind::AccountCreated);
+            for group in &groups {
+                server::membership::changed(group, person.id(), MembershipAction::Added).await;
+            }
+
+            let mut notification = server::notify::Notification::new(
+                types::notification::NotificationEvent::UserCreated,
+                format!("{} created {name}.", admin.username),
+            )
+            .detail("Display name", display_name)
+            .by(&admin)
+            .about_user(person.uuid, person.name);
+            if !groups.is_empty() {
+                notification = notification
+                    .detail("Groups", group_names.join(", "))
+                    .involving_groups(group_names);
+            }
+            if setup.posix.is_some() {
+                notification = notification.detail("POSIX", "On");
+            }
+            server::notify::send(notification);
+            Ok(())
+        })
     })
     .await
 }
@@ -79,49 +1635,487 @@ pub async fn generate_provision_url(
     duration_hours: u32,
     max_uses: Option<u8>,
     group_names: Vec<String>,
+    mut fields: ProvisionFields,
+    email_restriction: EmailRestriction,
+    remind: bool,
+    require_approval: bool,
+    follow_up: ProvisionFollowUp,
+    idempotency_key: Option<Uuid>,
+) -> ServerFnResult<Url> {
+    // A link that makes people admins requires a recent sign-in.
+    if group_names
+        .iter()
+        .any(|g| g == server::CONFIG.admin_group())
+    {
+        server::require_recent_auth().await?;
+    }
+
+    server::with_admin_session(|admin| {
+        server::idempotency::once(
+            idempotency_key,
+            "/api/provision/generate",
+            move || async move {
+                let classification = server::CONFIG.group_classification();
+                if let Some(group) = group_names.iter().find(|g| classification.is_builtin(g)) {
+                    return Err(types::err!(
+                        "provision links can't grant built-in group '{group}'"
+                    ));
+                }
+                server::email_verification::check_link_groups(&group_names)?;
+
+                // A restricted link needs an address to check, and an
+                // approved account needs one to be sent its reset link.
+                let email_restriction = email_restriction.normalized()?;
+                let follow_up = follow_up.normalized()?;
+                if !email_restriction.is_empty() || require_approval {
+                    fields.email = types::provision::FieldRequirement::Required;
+                }
+
+                if server::provision_quota::is_reached(&admin.username).await? {
+                    return Err(types::err!(
+                        "your provision links have already created as many accounts as allowed today"
+                    ));
+                }
+
+                let duration = std::time::Duration::from_secs(duration_hours as u64 * 3600);
+                let remind_to = remind.then(|| admin.username.clone());
+                let link = server::ProvisionLink::create(
+                    duration,
+                    max_uses,
+                    group_names,
+                    fields,
+                    remind_to,
+                    email_restriction,
+                    &admin.username,
+                    require_approval,
+                    follow_up,
+                )
+                .await?;
+                let token = link.as_token()?;
+                Ok(server::CONFIG.provision_url(token)?)
+            },
+        )
+    })
+    .await
+}
+
+/// The groups a user owns.
+#[post("/api/groups/owned-by")]
+pub async fn list_groups_owned_by(user_id: Uuid) -> ServerFnResult<Vec<Uuid>> {
+    server::with_admin_session(|_| async move { server::group_owner::groups_of(user_id).await })
+        .await
+}
+
+/// Let a user invite people to a group, or stop them.
+#[post("/api/groups/owner")]
+pub async fn set_group_owner(group_id: Uuid, user_id: Uuid, owner: bool) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move {
+        server::group_owner::set_owner(group_id, user_id, owner).await
+    })
+    .await
+}
+
+/// Who Kanidm lets manage a group, by SPN.
+#[post("/api/groups/managed-by")]
+pub async fn get_group_managed_by(group_id: Uuid) -> ServerFnResult<Option<String>> {
+    server::with_admin_session(|_| async move { server::group_manager::get(group_id).await }).await
+}
+
+/// Let a group or service account manage a group in Kanidm, or with `None`,
+/// leave it to Kanidm's admins.
+#[post("/api/groups/managed-by/set")]
+pub async fn set_group_managed_by(group_id: Uuid, manager: Option<String>) -> ServerFnResult<()> {
+    server::with_recent_admin_session(|admin| async move {
+        server::group_manager::set(group_id, manager.as_deref(), &admin).await
+    })
+    .await
+}
+
+/// Make a link that adds whoever signs in and follows it to a group, if
+/// they have an email address `allowed_domains` allows.
+#[post("/api/groups/join-link")]
+pub async fn create_group_join_link(
+    group_id: Uuid,
+    duration_hours: u32,
+    allowed_domains: String,
+) -> ServerFnResult<Url> {
+    server::with_admin_session(|admin| async move {
+        let restriction = EmailRestriction::parse(&allowed_domains, "")?;
+        server::group_join::create(group_id, duration_hours, restriction, &admin).await
+    })
+    .await
+}
+
+/// When a group should be reviewed or expires, if either is set.
+#[post("/api/groups/review")]
+pub async fn get_group_review(group_id: Uuid) -> ServerFnResult<Option<GroupReview>> {
+    server::with_admin_session(|_| async move { server::group_review::get(group_id).await }).await
+}
+
+/// Set or clear when a group should be reviewed and when it expires.
+#[post("/api/groups/review/set")]
+pub async fn set_group_review(
+    group_id: Uuid,
+    review_by: Option<jiff::civil::Date>,
+    expires_on: Option<jiff::civil::Date>,
+    lock_when_overdue: bool,
+) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::group_review::set(group_id, review_by, expires_on, lock_when_overdue, &admin).await
+    })
+    .await
+}
+
+/// Record that a group was reviewed, and when it should next be.
+#[post("/api/groups/review/done")]
+pub async fn mark_group_reviewed(
+    group_id: Uuid,
+    next_review_by: Option<jiff::civil::Date>,
+) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::group_review::mark_reviewed(group_id, next_review_by, &admin).await
+    })
+    .await
+}
+
+/// Groups due for review or past their expiry.
+#[post("/api/groups/reviews/overdue")]
+pub async fn list_overdue_group_reviews() -> ServerFnResult<Vec<GroupReview>> {
+    server::with_admin_session(|_| async { server::group_review::list_overdue().await }).await
+}
+
+/// The webhook a group calls when its members change, with its secret
+/// blanked.
+#[post("/api/groups/webhook")]
+pub async fn get_group_webhook(group_id: Uuid) -> ServerFnResult<Option<GroupWebhook>> {
+    server::with_admin_session(|_| async move { server::group_webhook::get(group_id).await }).await
+}
+
+/// Call `url` when people are added to or removed from a group. A blank
+/// secret keeps the existing one.
+#[post("/api/groups/webhook/set")]
+pub async fn set_group_webhook(
+    group_id: Uuid,
+    url: String,
+    secret: String,
+    on_add: bool,
+    on_remove: bool,
+) -> ServerFnResult<()> {
+    server::with_recent_admin_session(|admin| async move {
+        server::group_webhook::set(group_id, &url, &secret, on_add, on_remove, &admin).await
+    })
+    .await
+}
+
+#[post("/api/groups/webhook/remove")]
+pub async fn remove_group_webhook(group_id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|admin| async move {
+        server::group_webhook::remove(group_id, &admin).await
+    })
+    .await
+}
+
+/// A group's latest webhook deliveries, newest first.
+#[post("/api/groups/webhook/deliveries")]
+pub async fn list_webhook_deliveries(group_id: Uuid) -> ServerFnResult<Vec<WebhookDelivery>> {
+    server::with_admin_session(|_| async move { server::group_webhook::deliveries(group_id).await })
+        .await
+}
+
+/// Try a webhook delivery that was given up on again.
+#[post("/api/groups/webhook/retry")]
+pub async fn retry_webhook_delivery(id: Uuid) -> ServerFnResult<()> {
+    server::with_admin_session(|_| async move { server::group_webhook::retry(id).await }).await
+}
+
+/// The groups the signed-in user owns.
+#[post("/api/my-groups")]
+pub async fn list_my_groups() -> ServerFnResult<Vec<Group>> {
+    server::with_session(|user| async move { server::group_owner::owned_groups(&user).await }).await
+}
+
+/// Make a provision link that adds people to groups the signed-in user owns.
+#[post("/api/my-groups/invite")]
+pub async fn create_group_invite(
+    duration_hours: u32,
+    max_uses: Option<u8>,
+    group_names: Vec<String>,
+    idempotency_key: Option<Uuid>,
 ) -> ServerFnResult<Url> {
+    server::with_session(|user| {
+        server::idempotency::once(
+            idempotency_key,
+            "/api/my-groups/invite",
+            move || async move {
+                server::group_owner::create_invite(&user, duration_hours, max_uses, group_names)
+                    .await
+            },
+        )
+    })
+    .await
+}
+
+/// The most recent provision links, to make another like one of them.
+#[post("/api/provision/links")]
+pub async fn list_provision_links() -> ServerFnResult<Vec<ProvisionLinkSummary>> {
+    server::with_admin_session(|_| async {
+        let links = server::ProvisionLink::list_recent(20).await?;
+        let mut summaries = Vec::with_capacity(links.len());
+        for link in &links {
+            summaries.push(server::invite_email::summary(link).await?);
+        }
+        Ok(summaries)
+    })
+    .await
+}
+
+/// Extend or shorten a provision link to expire `expires_in_hours` from now,
+/// and change how many times it can be used, without sending a new URL.
+#[post("/api/provision/links/update")]
+pub async fn update_provision_link(
+    id: Uuid,
+    expires_in_hours: u32,
+    max_uses: Option<u8>,
+) -> ServerFnResult<ProvisionLinkSummary> {
+    server::with_admin_session(|_| async move {
+        if max_uses == Some(0) {
+            return Err(types::err!("a provision link must allow at least one use"));
+        }
+
+        let mut link = server::ProvisionLink::find(id).await?;
+        let expires_at =
+            Timestamp::now() + std::time::Duration::from_secs(expires_in_hours as u64 * 3600);
+        link.update(expires_at, max_uses).await?;
+        server::invite_email::summary(&link).await
+    })
+    .await
+}
+
+/// Email a provision link to the address it's for again.
+#[post("/api/provision/links/resend")]
+pub async fn resend_provision_link(id: Uuid) -> ServerFnResult<ProvisionLinkSummary> {
     server::with_admin_session(|_| async move {
-        let duration = std::time::Duration::from_secs(duration_hours as u64 * 3600);
-        let link = server::ProvisionLink::create(duration, max_uses, group_names).await?;
-        let token = link.as_token()?;
-        Ok(server::CONFIG.provision_url(token)?)
+        server::invite_email::resend(id).await?;
+        let link = server::ProvisionLink::find(id).await?;
+        server::invite_email::summary(&link).await
     })
     .await
 }
 
+/// Check that a provision link is usable, returning the fields its form
+/// should ask for, how long it lasts and what it grants. The page checks
+/// again when it's come back to, which isn't counted as opening the link
+/// again.
 #[post("/api/provision/verify")]
-pub async fn verify_provision(token: String) -> ServerFnResult<()> {
-    server::ProvisionLink::find_token(token).await?.verify()?;
-    Ok(())
+pub async fn verify_provision(
+    token: String,
+    refresh: bool,
+) -> ServerFnResult<VerifiedProvisionLink> {
+    let link = server::ProvisionLink::find_token(token).await?;
+    if !refresh {
+        let event = server::storage::ProvisionLinkEvent::Opened;
+        server::provision_funnel::record(link.id(), event).await;
+    }
+    if let Err(error) = link.verify() {
+        if !refresh {
+            server::provision_funnel::failed(link.id(), &error).await;
+        }
+        return Err(error.into());
+    }
+    let email_required = server::required_email::is_required().await?;
+    let known = server::IDENTITY.list_groups().await?;
+    let groups = link
+        .groups()
+        .iter()
+        .map(|name| GrantedGroup {
+            name: name.clone(),
+            description: known
+                .iter()
+                .find(|g| g.name == *name)
+                .and_then(|g| g.description.clone()),
+        })
+        .collect();
+    Ok(VerifiedProvisionLink {
+        fields: link.fields().clone().requiring_email(email_required),
+        expires_at: link.expires_at(),
+        uses_remaining: link.uses_remaining(),
+        groups,
+    })
 }
 
+/// Why provision links have failed lately, most common first.
+#[post("/api/provision/failure-causes")]
+pub async fn get_provision_failure_causes() -> ServerFnResult<Vec<ProvisionFailureCount>> {
+    server::with_admin_session(|_| async {
+        server::provision_funnel::recent_failure_causes().await
+    })
+    .await
+}
+
+/// Create an account from a provision link. If the link needs an admin to
+/// approve its accounts, the account is created locked and queued instead.
 #[post("/api/provision/complete")]
 pub async fn complete_provision(
     token: String,
     name: String,
     display_name: String,
     email_address: String,
-) -> ServerFnResult<ResetLink> {
-    let link = server::ProvisionLink::consume(token).await?;
+    phone_number: String,
+    idempotency_key: Option<Uuid>,
+) -> ServerFnResult<ProvisionCompletion> {
+    // The link is what authorizes this, so it's done as us.
+    Ok(server::as_service(server::idempotency::once(
+        idempotency_key,
+        "/api/provision/complete",
+        move || async move {
+            let unconsumed = server::ProvisionLink::find_token(token.clone()).await?;
+            let link_id = unconsumed.id();
+            let result = async move {
+                let mut errors = FieldErrors::default();
+                types::validation::check_person(&mut errors, &name, &display_name);
+                let email_required = server::required_email::is_required().await?;
+                unconsumed
+                    .fields()
+                    .clone()
+                    .requiring_email(email_required)
+                    .check(&mut errors, &email_address, &phone_number);
+                if !unconsumed.email_restriction().allows(&email_address) {
+                    errors.add("email", "this link can't be used with that email address");
+                }
+                errors.into_result()?;
+                if server::IDENTITY.get_person(&name).await.is_ok() {
+                    return Err(types::provision::NameTaken { name: name.clone() }.into());
+                }
+                if let Some(creator) = unconsumed.created_by()
+                    && server::provision_quota::is_reached(creator).await?
+                {
+                    return Err(types::err!(
+                        "this link can't create any more accounts today; try again tomorrow"
+                    ));
+                }
+                server::group_review::check_groups(unconsumed.groups().iter().map(String::as_str))
+                    .await?;
 
-    let result = server::KANIDM_CLIENT
-        .create_person_with_link(&name, &display_name, &email_address)
-        .await;
+                let (link, claim) = server::ProvisionLink::consume(token, &name).await?;
 
-    if result.is_err() {
-        let _ = link.decrement().await;
-        return Ok(result?);
-    }
+                if link.require_approval() {
+                    let created = server::provision_approval::create_locked(
+                        &name,
+                        &display_name,
+                        &email_address,
+                    )
+                    .await;
+                    if let Err(error) = created {
+                        server::provision_claims::release(claim).await;
+                        return Err(error);
+                    }
+                    server::provision_claims::keep(claim).await;
+                    server::report::record(ReportEventKind::AccountCreated);
+                    server::report::record(ReportEventKind::ProvisionLinkUsed);
 
-    let reset_link = result?;
+                    let person = server::IDENTITY.get_person(&name).await?;
+                    if !phone_number.is_empty() {
+                        let phone_number = types::validation::normalize_phone(&phone_number)?;
+                        server::storage::PhoneNumbers::set(person.uuid, &phone_number).await?;
+                    }
+                    if let Some(creator) = link.created_by() {
+                        server::provision_quota::record(creator, &name).await;
+                    }
 
-    // Add the user to the groups specified in the provision link
-    let person = server::KANIDM_CLIENT.get_person(&name).await?;
-    for group_name in link.groups() {
-        server::KANIDM_CLIENT
-            .add_user_to_group(group_name, &person.uuid)
-            .await?;
-    }
+                    server::provision_approval::request(&link, &person, &email_address).await?;
+                    return Ok(ProvisionCompletion::AwaitingApproval);
+                }
 
-    Ok(reset_link)
+                let reset_link = match server::IDENTITY
+                    .create_person_with_link(&name, &display_name, &email_address)
+                    .await
+                {
+                    Ok(reset_link) => reset_link,
+                    Err(error) => {
+                        server::provision_claims::release(claim).await;
+                        return Err(error);
+                    }
+                };
+                server::provision_claims::keep(claim).await;
+                server::report::record(ReportEventKind::AccountCreated);
+                server::report::record(ReportEventKind::ProvisionLinkUsed);
+
+                // Add the user to the groups specified in the provision link
+                let person = server::IDENTITY.get_person(&name).await?;
+                for group_name in link.groups() {
+                    let group = server::IDENTITY
+                        .find_group(&group_name.as_str().into())
+                        .await?;
+                    server::IDENTITY
+                        .add_user_to_group(&group.id(), &person.id())
+                        .await?;
+                    server::membership::changed(&group, person.id(), MembershipAction::Added).await;
+                }
+
+                if !phone_number.is_empty() {
+                    let phone_number = types::validation::normalize_phone(&phone_number)?;
+                    server::storage::PhoneNumbers::set(person.uuid, &phone_number).await?;
+                }
+
+                server::onboarding::start(person.uuid, &name, link.groups()).await;
+                if let Some(creator) = link.created_by() {
+                    server::provision_quota::record(creator, &name).await;
+                }
+
+                server::notify::send(
+                    server::notify::Notification::new(
+                        types::notification::NotificationEvent::UserProvisioned,
+                        format!("{name} created their account from a provision link."),
+                    )
+                    .detail("Display name", display_name)
+                    .detail("Groups", link.groups().join(", "))
+                    .about_user(person.uuid, name.clone()),
+                );
+                server::provision_follow_up::account_ready(&link, &person);
+
+                let follow_up = link.follow_up();
+                Ok(ProvisionCompletion::ResetLink {
+                    link: reset_link,
+                    next_steps: follow_up.next_steps.clone(),
+                    redirect_url: follow_up.redirect_url.clone(),
+                })
+            }
+            .await;
+
+            match &result {
+                Ok(_) => {
+                    let event = server::storage::ProvisionLinkEvent::Completed;
+                    server::provision_funnel::record(link_id, event).await;
+                }
+                Err(error) => server::provision_funnel::failed(link_id, error).await,
+            }
+            result
+        },
+    ))
+    .await?)
+}
+
+/// Accounts made with provision links that need approval, newest first.
+#[post("/api/provision/approvals")]
+pub async fn list_provision_approvals() -> ServerFnResult<Vec<ProvisionApproval>> {
+    server::with_admin_session(|_| async { server::storage::ProvisionApprovals::list().await })
+        .await
+}
+
+/// Unlock an account waiting for approval and send it its reset link.
+#[post("/api/provision/approvals/approve")]
+pub async fn approve_provision_approval(id: Uuid) -> ServerFnResult<ProvisionApprovalOutcome> {
+    server::with_admin_session(|admin| async move {
+        server::provision_approval::approve(id, &admin).await
+    })
+    .await
+}
+
+/// Delete an account waiting for approval.
+#[post("/api/provision/approvals/reject")]
+pub async fn reject_provision_approval(id: Uuid) -> ServerFnResult<()> {
+    server::with_recent_admin_session(|admin| async move {
+        server::provision_approval::reject(id, &admin).await
+    })
+    .await
 }
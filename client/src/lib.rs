@@ -0,0 +1,172 @@
+//! A typed client for AuthIt!'s API, for other Rust tools that manage people
+//! and groups through it.
+//!
+//! AuthIt!'s API is its server functions: each takes a JSON object of its
+//! arguments, by name, and answers with JSON. They're authorized by an admin's
+//! session, so a [`Client`] carries the `authit_session` cookie of one.
+
+use reqwest::{StatusCode, header};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use types::{
+    Result, err,
+    ids::{GroupId, PersonId},
+    kanidm::{Group, GroupMembershipChanges, NewPersonSetup, Person},
+    user_detail::UserDetail,
+};
+use url::Url;
+use uuid::Uuid;
+
+pub use types;
+
+/// The cookie AuthIt! keeps its session token in.
+const SESSION_COOKIE_NAME: &str = "authit_session";
+
+/// Calls AuthIt!'s API as the admin whose session it has.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    session: SecretString,
+}
+
+impl Client {
+    /// A client for the AuthIt! at `base_url`, using the session token from
+    /// an admin's `authit_session` cookie.
+    pub fn new(base_url: Url, session: SecretString) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url,
+            session,
+        }
+    }
+
+    /// Use `http` to make requests, to share its connections or configure
+    /// it.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// The person whose session this is, if it's still valid.
+    pub async fn current_user(&self) -> Result<Option<Person>> {
+        self.call("/api/current-user", json!({})).await
+    }
+
+    pub async fn list_users(&self) -> Result<Vec<Person>> {
+        self.call("/api/users", json!({})).await
+    }
+
+    /// Everything AuthIt! shows about a user on their page.
+    pub async fn get_user_detail(&self, user_id: PersonId) -> Result<UserDetail> {
+        self.call("/api/users/detail", json!({ "user_id": user_id }))
+            .await
+    }
+
+    /// Create a user. Retrying with the same `idempotency_key` won't create
+    /// them twice.
+    pub async fn create_user(
+        &self,
+        name: &str,
+        display_name: &str,
+        email_address: &str,
+        setup: &NewPersonSetup,
+        idempotency_key: Option<Uuid>,
+    ) -> Result<()> {
+        self.call(
+            "/api/users/create",
+            json!({
+                "name": name,
+                "display_name": display_name,
+                "email_address": email_address,
+                "setup": setup,
+                "idempotency_key": idempotency_key,
+            }),
+        )
+        .await
+    }
+
+    /// Delete a user. This needs the admin to have signed in recently.
+    pub async fn delete_user(&self, user_id: PersonId) -> Result<()> {
+        self.call("/api/users/delete", json!({ "user_id": user_id }))
+            .await
+    }
+
+    pub async fn list_groups(&self) -> Result<Vec<Group>> {
+        self.call("/api/groups", json!({})).await
+    }
+
+    /// Add a user to a group, or remove them from it.
+    pub async fn update_user_group(
+        &self,
+        user_id: PersonId,
+        group_id: GroupId,
+        add: bool,
+    ) -> Result<()> {
+        self.call(
+            "/api/users/groups",
+            json!({
+                "user_id": user_id,
+                "group_id": group_id,
+                "add": add,
+                "modified": None::<String>,
+            }),
+        )
+        .await
+    }
+
+    /// Make a user's groups exactly `group_ids`. Changes that fail are
+    /// reported, rather than stopping the rest.
+    pub async fn set_user_groups(
+        &self,
+        user_id: PersonId,
+        group_ids: &[GroupId],
+    ) -> Result<GroupMembershipChanges> {
+        self.call(
+            "/api/users/set-groups",
+            json!({
+                "user_id": user_id,
+                "group_ids": group_ids,
+                "modified": None::<String>,
+            }),
+        )
+        .await
+    }
+
+    async fn call<A: Serialize, T: DeserializeOwned>(&self, path: &str, args: A) -> Result<T> {
+        let response = self
+            .http
+            .post(self.base_url.join(path)?)
+            .header(
+                header::COOKIE,
+                format!("{SESSION_COOKIE_NAME}={}", self.session.expose_secret()),
+            )
+            .json(&args)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(match status {
+            StatusCode::UNAUTHORIZED => err!("the session isn't valid; sign in again"),
+            _ => err!("{path} failed with {status}: {}", error_message(&body)),
+        })
+    }
+}
+
+/// The message in a server function's error, or the whole body if it isn't
+/// one.
+fn error_message(body: &str) -> String {
+    let message = serde_json::from_str::<Value>(body).ok().and_then(|value| {
+        value
+            .get("message")
+            .or_else(|| value.as_object()?.values().next()?.get("message"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    });
+    message.unwrap_or_else(|| body.trim().to_string())
+}
@@ -73,6 +73,8 @@
             ];
             buildInputs = [ pkgs.openssl ];
             SQLX_OFFLINE = "true";
+            # There's no .git to read it from in the build.
+            AUTHIT_GIT_HASH = self.shortRev or self.dirtyShortRev or "";
             buildPhase = ''
               export HOME=$(mktemp -d)
               dx build --release --platform web --package web
@@ -0,0 +1,6 @@
+-- Existing sessions have no recorded lifetime, so sign everyone out once.
+DELETE FROM sessions;
+
+ALTER TABLE sessions ADD COLUMN user_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE sessions ADD COLUMN expires_at DATETIME NOT NULL DEFAULT '1970-01-01T00:00:00Z';
+ALTER TABLE sessions ADD COLUMN persistent BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Set when the user last re-entered their credentials with Kanidm (via
+-- prompt=login), as opposed to being signed in through an existing SSO session.
+ALTER TABLE sessions ADD COLUMN auth_time DATETIME;
@@ -0,0 +1,13 @@
+-- There is at most one break-glass credential.
+CREATE TABLE break_glass_credential (
+    id INTEGER PRIMARY KEY NOT NULL CHECK(id = 1),
+    password_hash TEXT NOT NULL,
+    updated_at DATETIME NOT NULL,
+    updated_by TEXT NOT NULL
+);
+
+CREATE TABLE break_glass_attempts (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    attempted_at DATETIME NOT NULL,
+    success BOOLEAN NOT NULL
+);
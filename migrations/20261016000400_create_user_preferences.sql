@@ -0,0 +1,5 @@
+CREATE TABLE user_preferences (
+    -- The admin's Kanidm sub.
+    user_id TEXT PRIMARY KEY NOT NULL,
+    preferences TEXT NOT NULL
+);
@@ -0,0 +1,5 @@
+-- Site-wide settings that admins can change at runtime, as JSON values.
+CREATE TABLE settings (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- JSON spec of which optional fields the provision form asks for.
+ALTER TABLE provision_links ADD COLUMN fields TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,13 @@
+-- Requests from visitors without a provision link for an account.
+CREATE TABLE account_requests (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    justification TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    reason TEXT,
+    decided_by TEXT,
+    decided_at DATETIME
+);
+
+CREATE INDEX account_requests_email ON account_requests (email);
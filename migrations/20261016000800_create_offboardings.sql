@@ -0,0 +1,13 @@
+-- Offboarding checklists. Steps are stored as JSON.
+CREATE TABLE offboardings (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    started_by TEXT NOT NULL,
+    steps TEXT NOT NULL,
+    delete_at DATETIME,
+    deleted_at DATETIME,
+    completed_at DATETIME
+);
+
+CREATE INDEX offboardings_user_id ON offboardings (user_id);
@@ -0,0 +1,13 @@
+-- Tracks new users' progress after they create an account from a provision
+-- link.
+CREATE TABLE onboardings (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    required_groups TEXT NOT NULL,
+    credentials_set_at DATETIME,
+    groups_added_at DATETIME,
+    first_login_at DATETIME
+);
+
+CREATE INDEX onboardings_user_id ON onboardings (user_id);
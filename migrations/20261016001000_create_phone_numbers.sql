@@ -0,0 +1,5 @@
+-- Kanidm has no phone number attribute, so we keep them here.
+CREATE TABLE phone_numbers (
+    user_id BLOB PRIMARY KEY NOT NULL CHECK(length(user_id) = 16),
+    phone_number TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Logins that have been sent to Kanidm but haven't come back yet, keyed by the
+-- OAuth state parameter. Kept here rather than in memory so they survive
+-- restarts.
+CREATE TABLE pending_logins (
+    state TEXT PRIMARY KEY NOT NULL,
+    pkce_verifier TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    remember BOOLEAN NOT NULL,
+    reauth BOOLEAN NOT NULL,
+    return_to TEXT
+);
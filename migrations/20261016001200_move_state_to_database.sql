@@ -0,0 +1,17 @@
+-- Everything needed for correctness lives in the database, so that several
+-- replicas can share it.
+
+-- Avatars, as 256x256 PNGs.
+CREATE TABLE avatars (
+    user_id BLOB PRIMARY KEY NOT NULL CHECK(length(user_id) = 16),
+    image BLOB NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+-- Which replica is running each background job. A replica keeps its lease by
+-- renewing it before it expires.
+CREATE TABLE job_leases (
+    name TEXT PRIMARY KEY NOT NULL,
+    holder BLOB NOT NULL CHECK(length(holder) = 16),
+    expires_at DATETIME NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Bulk operations, like group imports, run in the background and record
+-- their progress here for the UI to poll.
+CREATE TABLE jobs (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    kind TEXT NOT NULL,
+    started_by TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    done INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    finished_at DATETIME,
+    -- Set if the job as a whole failed, rather than individual items.
+    error TEXT
+);
+
+-- The result of each item in a job, once it's been tried.
+CREATE TABLE job_items (
+    job_id BLOB NOT NULL REFERENCES jobs (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    error TEXT,
+    PRIMARY KEY (job_id, position)
+);
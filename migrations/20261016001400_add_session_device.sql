@@ -0,0 +1,5 @@
+-- Where each session signed in from, so users can spot ones they don't
+-- recognize.
+ALTER TABLE sessions ADD COLUMN user_agent TEXT;
+ALTER TABLE sessions ADD COLUMN ip_address TEXT;
+ALTER TABLE sessions ADD COLUMN location TEXT;
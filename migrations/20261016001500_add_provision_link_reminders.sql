@@ -0,0 +1,4 @@
+-- The admin to remind when a link is about to lapse unused or is used up, if
+-- they asked to be.
+ALTER TABLE provision_links ADD COLUMN remind_to TEXT;
+ALTER TABLE provision_links ADD COLUMN reminded_at DATETIME;
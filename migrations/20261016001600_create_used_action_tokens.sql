@@ -0,0 +1,6 @@
+-- One-click action links that have been used, so each works only once. Kept
+-- until the link would have expired anyway.
+CREATE TABLE used_action_tokens (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    expires_at DATETIME NOT NULL
+);
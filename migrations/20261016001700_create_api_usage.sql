@@ -0,0 +1,11 @@
+-- How often each admin has called each server function, to spot automation
+-- using a person's session.
+CREATE TABLE api_usage (
+    user_id TEXT NOT NULL,
+    user_name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    first_used_at DATETIME NOT NULL,
+    last_used_at DATETIME NOT NULL,
+    PRIMARY KEY (user_id, path)
+);
@@ -0,0 +1,11 @@
+-- Local labels for users, like "contractor", that don't belong in Kanidm.
+CREATE TABLE tags (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE user_tags (
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    tag_id BLOB NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, tag_id)
+);
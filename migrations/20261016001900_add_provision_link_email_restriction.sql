@@ -0,0 +1,2 @@
+-- Whose email addresses can use a link, as JSON. Empty allows anyone.
+ALTER TABLE provision_links ADD COLUMN email_restriction TEXT NOT NULL DEFAULT '{}';
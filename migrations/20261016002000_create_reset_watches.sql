@@ -0,0 +1,10 @@
+-- Credential reset links an admin generated, to tell them once the user has
+-- used theirs. `fingerprint` is a digest of the user's credentials when the
+-- link was made, so a change shows the link was used.
+CREATE TABLE reset_watches (
+    user_id BLOB PRIMARY KEY NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    admin TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    expires_at DATETIME NOT NULL
+);
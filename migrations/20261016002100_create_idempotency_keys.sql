@@ -0,0 +1,9 @@
+-- Mutating requests made with an idempotency key, so a retry gets the first
+-- request's result rather than doing it again. `response` is null while the
+-- first request is still running.
+CREATE TABLE idempotency_keys (
+    key BLOB PRIMARY KEY NOT NULL CHECK(length(key) = 16),
+    endpoint TEXT NOT NULL,
+    response TEXT,
+    created_at DATETIME NOT NULL
+);
@@ -0,0 +1,15 @@
+-- Recent things admins and users did, for the dashboard's activity stream.
+-- `actor_id` is nil for the break-glass account.
+CREATE TABLE activity (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    at DATETIME NOT NULL,
+    event TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    actor_id BLOB CHECK(length(actor_id) = 16),
+    actor_name TEXT,
+    user_id BLOB CHECK(length(user_id) = 16),
+    user_name TEXT,
+    group_name TEXT
+);
+
+CREATE INDEX activity_at ON activity (at);
@@ -0,0 +1,13 @@
+-- The admin who made each link, so accounts created from it count towards
+-- their provisioning quota. Links made before this have none.
+ALTER TABLE provision_links ADD COLUMN created_by TEXT;
+
+-- Accounts created from provision links, by the admin who made the link.
+-- Only the last day matters.
+CREATE TABLE provisioned_accounts (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    created_by TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX provisioned_accounts_created_by ON provisioned_accounts (created_by, created_at);
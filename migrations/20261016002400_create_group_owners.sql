@@ -0,0 +1,9 @@
+-- People who may invite others into a group, without being admins. Both are
+-- Kanidm UUIDs.
+CREATE TABLE group_owners (
+    group_id BLOB NOT NULL CHECK(length(group_id) = 16),
+    owner_id BLOB NOT NULL CHECK(length(owner_id) = 16),
+    PRIMARY KEY (group_id, owner_id)
+);
+
+CREATE INDEX group_owners_owner_id ON group_owners (owner_id);
@@ -0,0 +1,12 @@
+-- Emails sent to the person a provision link is for, and whether each was
+-- delivered. `id` is a UUIDv7, so it also records when it was queued.
+CREATE TABLE email_outbox (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    link_id BLOB NOT NULL REFERENCES provision_links (id) ON DELETE CASCADE,
+    recipient TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    sent_at DATETIME,
+    error TEXT
+);
+
+CREATE INDEX email_outbox_link_id ON email_outbox (link_id);
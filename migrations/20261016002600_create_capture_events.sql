@@ -0,0 +1,7 @@
+-- What happened while a diagnostic capture was running, for bug reports.
+-- `id` is a UUIDv7, so it also records when it happened. `event` is a JSON
+-- `CaptureEvent`.
+CREATE TABLE capture_events (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    event TEXT NOT NULL
+);
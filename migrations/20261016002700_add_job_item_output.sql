@@ -0,0 +1,3 @@
+-- What a job item produced, as JSON, like the credential reset link it made
+-- for the report.
+ALTER TABLE job_items ADD COLUMN output TEXT;
@@ -0,0 +1,22 @@
+-- Campaigns asking people to change their credentials by a deadline.
+CREATE TABLE rotation_campaigns (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    name TEXT NOT NULL,
+    started_by TEXT NOT NULL,
+    deadline DATETIME NOT NULL,
+    lock_at_deadline BOOLEAN NOT NULL,
+    closed_at DATETIME
+);
+
+-- The fingerprint is a digest of the person's credentials when the campaign
+-- started, so we can tell when they've changed.
+CREATE TABLE rotation_members (
+    campaign_id BLOB NOT NULL REFERENCES rotation_campaigns (id) ON DELETE CASCADE,
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    rotated_at DATETIME,
+    reminded_at DATETIME,
+    locked_at DATETIME,
+    PRIMARY KEY (campaign_id, user_id)
+);
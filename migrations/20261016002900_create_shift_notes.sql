@@ -0,0 +1,9 @@
+-- Notes admins leave for each other on the dashboard. Content is markdown.
+CREATE TABLE shift_notes (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    author TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX shift_notes_created_at ON shift_notes (created_at);
@@ -0,0 +1,3 @@
+-- The ticket the admin said they were working on, for tracing changes back to
+-- the request that prompted them.
+ALTER TABLE activity ADD COLUMN ticket TEXT;
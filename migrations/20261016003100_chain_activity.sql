@@ -0,0 +1,33 @@
+-- Each activity record holds a hash of itself and the record before it, so
+-- changing or removing a record can be detected. Records from before this
+-- migration aren't chained.
+--
+-- Deleted users' records are redacted rather than removed, which keeps the
+-- chain intact; only the oldest records are ever deleted, by retention.
+ALTER TABLE activity ADD COLUMN prev_hash TEXT;
+ALTER TABLE activity ADD COLUMN hash TEXT;
+ALTER TABLE activity ADD COLUMN redacted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Two records can't follow the same one.
+CREATE UNIQUE INDEX activity_prev_hash ON activity (prev_hash);
+
+CREATE TRIGGER activity_chained
+BEFORE INSERT ON activity
+WHEN NEW.hash IS NULL
+BEGIN
+    SELECT RAISE(ABORT, 'activity records must be chained');
+END;
+
+CREATE TRIGGER activity_append_only
+BEFORE UPDATE OF id, at, event, prev_hash, hash ON activity
+BEGIN
+    SELECT RAISE(ABORT, 'activity records can''t be changed');
+END;
+
+CREATE TRIGGER activity_redact_only
+BEFORE UPDATE OF summary, actor_id, actor_name, user_id, user_name, group_name, ticket, redacted
+ON activity
+WHEN NOT NEW.redacted
+BEGIN
+    SELECT RAISE(ABORT, 'activity records can only be redacted');
+END;
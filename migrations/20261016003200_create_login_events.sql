@@ -0,0 +1,16 @@
+-- Sign-ins to AuthIt! itself, successful or not, since Kanidm's own logs are
+-- often out of admins' reach. `failure` is null for successful sign-ins, and
+-- `user_id` is null when we couldn't tell who was signing in.
+CREATE TABLE login_events (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    at DATETIME NOT NULL,
+    user_id TEXT,
+    username TEXT,
+    failure TEXT,
+    ip_address TEXT,
+    user_agent TEXT,
+    location TEXT
+);
+
+CREATE INDEX login_events_user_id ON login_events (user_id);
+CREATE INDEX login_events_at ON login_events (at);
@@ -0,0 +1,17 @@
+-- Changes people asked for to their own display name or email, for an admin
+-- to approve. Changes made straight away aren't kept here.
+CREATE TABLE profile_changes (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    field TEXT NOT NULL,
+    old_value TEXT NOT NULL,
+    new_value TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    reason TEXT,
+    decided_by TEXT,
+    decided_at DATETIME
+);
+
+CREATE INDEX profile_changes_user_id ON profile_changes (user_id);
+CREATE INDEX profile_changes_status ON profile_changes (status);
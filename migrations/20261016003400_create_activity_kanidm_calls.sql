@@ -0,0 +1,6 @@
+-- The Kanidm API calls made for each activity, to explain what it did. Kept
+-- apart from the activity table so they don't change its hash chain.
+CREATE TABLE activity_kanidm_calls (
+    activity_id BLOB PRIMARY KEY NOT NULL CHECK(length(activity_id) = 16),
+    calls TEXT NOT NULL
+);
@@ -0,0 +1,29 @@
+-- Webhooks called when people join or leave a group, so downstream systems
+-- can provision them.
+CREATE TABLE group_webhooks (
+    group_id BLOB PRIMARY KEY NOT NULL CHECK(length(group_id) = 16),
+    group_name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    on_add BOOLEAN NOT NULL DEFAULT TRUE,
+    on_remove BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_by TEXT NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+-- Each call to a group's webhook. Failed calls are retried until they
+-- succeed or run out of attempts.
+CREATE TABLE webhook_deliveries (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    group_id BLOB NOT NULL CHECK(length(group_id) = 16),
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    action TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME,
+    delivered_at DATETIME,
+    error TEXT
+);
+
+CREATE INDEX webhook_deliveries_group_id ON webhook_deliveries (group_id);
+CREATE INDEX webhook_deliveries_next_attempt_at ON webhook_deliveries (next_attempt_at);
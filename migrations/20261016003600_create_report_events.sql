@@ -0,0 +1,10 @@
+-- Things counted in period reports. They name no one, only the group where
+-- there is one, so they outlive the activity log and aren't purged.
+CREATE TABLE report_events (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    at DATETIME NOT NULL,
+    kind TEXT NOT NULL,
+    group_name TEXT
+);
+
+CREATE INDEX report_events_at ON report_events (at);
@@ -0,0 +1,12 @@
+-- What happened each time someone opened or tried to complete a provision
+-- link, so admins can see where invitees get stuck. They go with the link
+-- once it's purged.
+CREATE TABLE provision_link_events (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    link_id BLOB NOT NULL CHECK(length(link_id) = 16),
+    at DATETIME NOT NULL,
+    kind TEXT NOT NULL,
+    reason TEXT
+);
+
+CREATE INDEX provision_link_events_link_id ON provision_link_events (link_id);
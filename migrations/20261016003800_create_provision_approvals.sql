@@ -0,0 +1,18 @@
+-- Links can make accounts that wait, locked, for an admin to approve them.
+ALTER TABLE provision_links ADD COLUMN require_approval BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE provision_approvals (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    link_id BLOB NOT NULL CHECK(length(link_id) = 16),
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    groups TEXT NOT NULL,
+    link_created_by TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    decided_by TEXT,
+    decided_at DATETIME
+);
+
+CREATE INDEX provision_approvals_status ON provision_approvals (status);
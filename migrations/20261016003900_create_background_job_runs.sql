@@ -0,0 +1,9 @@
+-- The latest run of each background job, so admins can see whether it
+-- worked without reading the logs.
+CREATE TABLE background_job_runs (
+    name TEXT PRIMARY KEY NOT NULL,
+    started_at DATETIME NOT NULL,
+    finished_at DATETIME,
+    error TEXT,
+    started_by TEXT
+);
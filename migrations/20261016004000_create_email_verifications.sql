@@ -0,0 +1,11 @@
+-- Which of a person's email addresses they've shown are theirs, and the code
+-- they were last sent to do it.
+CREATE TABLE email_verifications (
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    email TEXT NOT NULL,
+    code_hash TEXT,
+    code_expires_at DATETIME,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    verified_at DATETIME,
+    PRIMARY KEY (user_id, email)
+);
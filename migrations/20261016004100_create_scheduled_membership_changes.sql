@@ -0,0 +1,16 @@
+-- Group membership changes to make at a set time.
+CREATE TABLE scheduled_membership_changes (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    user_id BLOB NOT NULL CHECK(length(user_id) = 16),
+    user_name TEXT NOT NULL,
+    group_id BLOB NOT NULL CHECK(length(group_id) = 16),
+    group_name TEXT NOT NULL,
+    action TEXT NOT NULL,
+    run_at DATETIME NOT NULL,
+    created_by TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    finished_at DATETIME,
+    note TEXT
+);
+
+CREATE INDEX scheduled_membership_changes_due ON scheduled_membership_changes (status, run_at);
@@ -0,0 +1,44 @@
+-- Full-text search over the activity stream, for the Audit page. It indexes
+-- `activity` itself and is kept in step with it by triggers, including when
+-- records are redacted or deleted by retention.
+CREATE VIRTUAL TABLE activity_search USING fts5(
+    summary,
+    actor_name,
+    user_name,
+    group_name,
+    ticket,
+    content = 'activity',
+    content_rowid = 'rowid'
+);
+
+CREATE TRIGGER activity_search_insert
+AFTER INSERT ON activity
+BEGIN
+    INSERT INTO activity_search (rowid, summary, actor_name, user_name, group_name, ticket)
+    VALUES (NEW.rowid, NEW.summary, NEW.actor_name, NEW.user_name, NEW.group_name, NEW.ticket);
+END;
+
+CREATE TRIGGER activity_search_delete
+AFTER DELETE ON activity
+BEGIN
+    INSERT INTO activity_search
+        (activity_search, rowid, summary, actor_name, user_name, group_name, ticket)
+    VALUES
+        ('delete', OLD.rowid, OLD.summary, OLD.actor_name, OLD.user_name, OLD.group_name,
+         OLD.ticket);
+END;
+
+CREATE TRIGGER activity_search_update
+AFTER UPDATE ON activity
+BEGIN
+    INSERT INTO activity_search
+        (activity_search, rowid, summary, actor_name, user_name, group_name, ticket)
+    VALUES
+        ('delete', OLD.rowid, OLD.summary, OLD.actor_name, OLD.user_name, OLD.group_name,
+         OLD.ticket);
+    INSERT INTO activity_search (rowid, summary, actor_name, user_name, group_name, ticket)
+    VALUES (NEW.rowid, NEW.summary, NEW.actor_name, NEW.user_name, NEW.group_name, NEW.ticket);
+END;
+
+-- Index what's already there.
+INSERT INTO activity_search (activity_search) VALUES ('rebuild');
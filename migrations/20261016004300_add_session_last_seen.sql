@@ -0,0 +1,5 @@
+-- When a browser using the session last checked in, so admins can see who
+-- else is online.
+ALTER TABLE sessions ADD COLUMN last_seen_at DATETIME;
+
+CREATE INDEX sessions_last_seen_at ON sessions (last_seen_at);
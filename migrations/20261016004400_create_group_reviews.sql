@@ -0,0 +1,15 @@
+-- When groups should be reviewed or stop being used. Dates are YYYY-MM-DD.
+CREATE TABLE group_reviews (
+    group_id BLOB PRIMARY KEY NOT NULL CHECK(length(group_id) = 16),
+    group_name TEXT NOT NULL,
+    review_by TEXT,
+    expires_on TEXT,
+    lock_when_overdue BOOLEAN NOT NULL,
+    reviewed_at DATETIME,
+    reviewed_by TEXT,
+    flagged_at DATETIME,
+    updated_by TEXT NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+CREATE INDEX group_reviews_group_name ON group_reviews (group_name);
@@ -0,0 +1,3 @@
+-- What happens once someone has made their account with a link: where
+-- they're sent, what they're told, and which webhook is called. JSON.
+ALTER TABLE provision_links ADD COLUMN follow_up TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,7 @@
+-- What was last read from Kanidm about each person, to find what's changed.
+CREATE TABLE kanidm_event_syncs (
+    user_id BLOB PRIMARY KEY NOT NULL CHECK(length(user_id) = 16),
+    fingerprint TEXT NOT NULL,
+    -- JSON array of the sign-in session ids already recorded.
+    session_ids TEXT NOT NULL DEFAULT '[]'
+);
@@ -0,0 +1,15 @@
+-- Every email AuthIt! sends, kept until it's sent or given up on so a
+-- hiccup at the SMTP relay doesn't lose it.
+CREATE TABLE email_queue (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    -- Blanked once it's sent.
+    body TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME,
+    sent_at DATETIME,
+    error TEXT
+);
+
+CREATE INDEX email_queue_next_attempt_at ON email_queue (next_attempt_at);
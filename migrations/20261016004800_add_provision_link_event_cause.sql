@@ -0,0 +1,3 @@
+-- What kind of problem a failed attempt to use a provision link ran into,
+-- so failures can be counted by cause. Older failures count as 'other'.
+ALTER TABLE provision_link_events ADD COLUMN cause TEXT;
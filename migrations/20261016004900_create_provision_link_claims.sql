@@ -0,0 +1,17 @@
+-- Uses taken from provision links for accounts that are still being made.
+-- A use counts against its link from when it's claimed, and is kept once the
+-- account exists or given back if making it fails. Claims left behind by
+-- AuthIt! stopping partway are settled by a background job.
+CREATE TABLE provision_link_claims (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    link_id BLOB NOT NULL REFERENCES provision_links (id) ON DELETE CASCADE,
+    -- The name of the account the use is for.
+    name TEXT NOT NULL,
+    claimed_at DATETIME NOT NULL
+);
+
+CREATE INDEX provision_link_claims_claimed_at ON provision_link_claims (claimed_at);
+
+-- The claim that most recently took one of the link's uses, so taking a use
+-- can say whether it did in the same statement.
+ALTER TABLE provision_links ADD COLUMN last_claim BLOB;
@@ -0,0 +1,9 @@
+-- Requests to public forms, by client address, for rate limiting.
+CREATE TABLE request_attempts (
+    id BLOB PRIMARY KEY NOT NULL CHECK(length(id) = 16),
+    kind TEXT NOT NULL,
+    client_ip TEXT NOT NULL,
+    attempted_at DATETIME NOT NULL
+);
+
+CREATE INDEX request_attempts_client ON request_attempts (kind, client_ip, attempted_at);
@@ -0,0 +1,78 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+fn main() {
+    changelog();
+    assets();
+}
+
+/// Turn `CHANGELOG.md` into a list of `(version, changes)`, to embed.
+fn changelog() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../CHANGELOG.md");
+    println!("cargo:rerun-if-changed={}", path.display());
+    let changelog = fs::read_to_string(&path).expect("failed to read CHANGELOG.md");
+
+    let mut releases: Vec<(String, Vec<String>)> = Vec::new();
+    for line in changelog.lines() {
+        if let Some(version) = line.strip_prefix("## ") {
+            // Allow headings like `## 1.2.0 - 2026-01-01`.
+            let version = version.split_whitespace().next().unwrap_or_default();
+            releases.push((version.to_string(), Vec::new()));
+        } else if let Some(change) = line.strip_prefix("- ")
+            && let Some((_, changes)) = releases.last_mut()
+        {
+            changes.push(change.trim().to_string());
+        }
+    }
+
+    let mut out = String::from("&[\n");
+    for (version, changes) in releases {
+        out.push_str(&format!("    ({version:?}, &{changes:?}),\n"));
+    }
+    out.push_str("]\n");
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("changelog.rs");
+    fs::write(out_path, out).expect("failed to write changelog.rs");
+}
+
+/// With `AUTHIT_ASSETS_DIR` set to the `public` directory `dx build` makes,
+/// list its files as `(path, contents)`, to embed so the binary can run on its
+/// own. Without it, the list is empty and the assets are served from disk.
+fn assets() {
+    println!("cargo:rerun-if-env-changed=AUTHIT_ASSETS_DIR");
+    let mut files = Vec::new();
+    if let Ok(dir) = env::var("AUTHIT_ASSETS_DIR") {
+        let dir = fs::canonicalize(&dir).expect("AUTHIT_ASSETS_DIR doesn't exist");
+        println!("cargo:rerun-if-changed={}", dir.display());
+        collect_files(&dir, &dir, &mut files);
+    }
+
+    let mut out = String::from("&[\n");
+    for (name, path) in files {
+        out.push_str(&format!("    ({name:?}, include_bytes!({path:?})),\n"));
+    }
+    out.push_str("]\n");
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("assets.rs");
+    fs::write(out_path, out).expect("failed to write assets.rs");
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    for entry in fs::read_dir(dir).expect("failed to read assets") {
+        let path = entry.expect("failed to read assets").path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+            continue;
+        }
+        // Forward slashes, as in URLs, whatever the platform.
+        let name: Vec<String> = path
+            .strip_prefix(root)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        files.push((name.join("/"), path));
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use types::{
+    Result,
+    account_request::{AccountRequestApproval, AccountRequestStatus},
+    err,
+    notification::NotificationEvent,
+    provision::{EmailRestriction, FieldRequirement, ProvisionFields, ProvisionFollowUp},
+    validation::{MAX_DISPLAY_NAME_LENGTH, MAX_NOTES_LENGTH, check_length, validate_email},
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, ProvisionLink, UserData,
+    action_link::{self, Action},
+    email, invite_email,
+    notify::{self, Notification},
+    shutdown,
+    storage::{AccountRequests, OutboxKind},
+};
+
+/// How long the approve link emailed to admins lasts.
+const ACTION_LINK_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// How long the provision link sent to an approved requester lasts.
+const APPROVAL_LINK_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Submit a request for an account. This is public, so it only works when
+/// account requests are enabled.
+pub async fn submit(name: &str, email_address: &str, justification: &str) -> Result<()> {
+    if !CONFIG.account_requests_enabled {
+        return Err(err!("account requests are not enabled"));
+    }
+
+    let name = name.trim();
+    let email_address = email_address.trim();
+    let justification = justification.trim();
+
+    if name.is_empty() || justification.is_empty() {
+        return Err(err!("name and justification are required"));
+    }
+    validate_email(email_address)?;
+    check_length("name", name, MAX_DISPLAY_NAME_LENGTH)?;
+    check_length("justification", justification, MAX_NOTES_LENGTH)?;
+    if AccountRequests::has_pending(email_address).await? {
+        return Err(err!("there is already a pending request for this email"));
+    }
+
+    let id = AccountRequests::create(name, email_address, justification).await?;
+    tracing::info!(email = email_address, "account requested");
+    notify::send(
+        Notification::new(
+            NotificationEvent::AccountRequested,
+            format!("{name} requested an account."),
+        )
+        .detail("Email", email_address)
+        .detail("Justification", justification),
+    );
+    email_admins(id, name.to_string(), email_address.to_string());
+
+    Ok(())
+}
+
+/// Email each admin about a new request, with a link to approve it. This
+/// runs in the background; failures are logged.
+fn email_admins(id: Uuid, name: String, email_address: String) {
+    if !email::is_configured() {
+        return;
+    }
+
+    shutdown::spawn(async move {
+        let persons = match IDENTITY.list_persons().await {
+            Ok(p) => p,
+            Err(error) => {
+                tracing::warn!(?error, "failed to list admins to email");
+                return;
+            }
+        };
+        let admins = persons
+            .iter()
+            .filter(|p| p.is_in_group(CONFIG.admin_group()));
+
+        for admin in admins {
+            let Some(to) = admin.email_addresses.first() else {
+                continue;
+            };
+            let approve_url =
+                match action_link::url(Action::ApproveRequest, id, &admin.name, ACTION_LINK_TTL) {
+                    Ok(url) => url,
+                    Err(error) => {
+                        tracing::warn!(
+                            ?error,
+                            admin = admin.name,
+                            "failed to create approval link"
+                        );
+                        continue;
+                    }
+                };
+            let body = format!(
+                "Hi {},\n\n\
+                {name} ({email_address}) requested an account. Review it on the \
+                Requests page, or approve it now:\n\n\
+                {approve_url}\n",
+                admin.display_name,
+            );
+            email::try_send(to, "New account request", body).await;
+        }
+    });
+}
+
+/// Approve a request, creating a single-use provision link and emailing it to
+/// the requester.
+pub async fn approve(id: Uuid, admin: &str) -> Result<AccountRequestApproval> {
+    let request = AccountRequests::find(id).await?;
+    if request.status != AccountRequestStatus::Pending {
+        return Err(err!("account request has already been decided"));
+    }
+
+    let fields = ProvisionFields {
+        email: FieldRequirement::Required,
+        ..Default::default()
+    };
+    let restriction = EmailRestriction {
+        address: Some(request.email.clone()),
+        ..Default::default()
+    };
+    let link = ProvisionLink::create(
+        APPROVAL_LINK_TTL,
+        Some(1),
+        Vec::new(),
+        fields,
+        None,
+        restriction,
+        admin,
+        false,
+        ProvisionFollowUp::default(),
+    )
+    .await?;
+    let provision_url = CONFIG.provision_url(link.as_token()?)?;
+
+    // Another admin may have decided the request in the meantime.
+    let decided = AccountRequests::decide(id, AccountRequestStatus::Approved, None, admin).await;
+    if let Err(error) = decided {
+        let _ = link.delete().await;
+        return Err(error);
+    }
+
+    let body = format!(
+        "Hi {},\n\n\
+        Your request for an account has been approved. Use this link within \
+        the next 7 days to create it:\n\n\
+        {provision_url}\n",
+        request.name,
+    );
+    let emailed = invite_email::deliver(
+        &link,
+        OutboxKind::Invite,
+        "Your account request was approved",
+        body,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        tracing::warn!(?error, "failed to email approved requester");
+        false
+    });
+
+    Ok(AccountRequestApproval {
+        provision_url,
+        emailed,
+    })
+}
+
+/// Deny a request, emailing the reason to the requester. Returns whether the
+/// email was sent.
+pub async fn deny(id: Uuid, reason: &str, admin: &UserData) -> Result<bool> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(err!("a reason is required"));
+    }
+    check_length("reason", reason, MAX_NOTES_LENGTH)?;
+
+    let request = AccountRequests::find(id).await?;
+    AccountRequests::decide(
+        id,
+        AccountRequestStatus::Denied,
+        Some(reason),
+        &admin.username,
+    )
+    .await?;
+
+    let body = format!(
+        "Hi {},\n\n\
+        Your request for an account has been denied, for this reason:\n\n\
+        {reason}\n",
+        request.name,
+    );
+    Ok(email::try_send(&request.email, "Your account request was denied", body).await)
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use axum::{
+    Router,
+    extract::Path,
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use jiff::Timestamp;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use types::{Result, err};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, ProvisionLink, account_request, kanidm,
+    notify::escape_html,
+    signed_token::{Purpose, SignedToken},
+    storage::UsedActionTokens,
+};
+
+/// Something an admin can do by following a link in an email.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ApproveRequest,
+    RevokeLink,
+}
+
+impl Action {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::ApproveRequest => "Approve account request",
+            Self::RevokeLink => "Revoke provision link",
+        }
+    }
+}
+
+/// What an action link is signed over.
+#[derive(Debug, Serialize, Deserialize)]
+struct ActionToken {
+    /// Unique to this link, so it can only be used once.
+    id: Uuid,
+    action: Action,
+    /// What the action applies to, like the account request's ID.
+    target: Uuid,
+    /// The admin the link was sent to, who the action is done as.
+    actor: String,
+}
+
+fn decode(token: &str) -> Result<SignedToken<ActionToken>> {
+    SignedToken::decode(token, Purpose::EmailAction)
+}
+
+/// A link that does `action` to `target` as `actor` when followed, once,
+/// within `ttl`.
+pub fn url(action: Action, target: Uuid, actor: &str, ttl: Duration) -> Result<Url> {
+    let token = SignedToken::new(
+        Purpose::EmailAction,
+        ActionToken {
+            id: Uuid::now_v7(),
+            action,
+            target,
+            actor: actor.to_string(),
+        },
+        Some(Timestamp::now() + ttl),
+    );
+    Ok(CONFIG
+        .authit_url
+        .join("/actions/")?
+        .join(&token.encode()?)?)
+}
+
+pub fn router() -> Router {
+    Router::new().route("/actions/{token}", get(confirm).post(perform))
+}
+
+/// Ask before acting, as mail scanners follow links in emails.
+async fn confirm(Path(token): Path<String>) -> Response {
+    match decode(&token) {
+        Ok(token) => page(
+            StatusCode::OK,
+            token.data.action.label(),
+            &format!(
+                r#"<form method="post"><button type="submit">{}</button></form>"#,
+                token.data.action.label()
+            ),
+        ),
+        Err(error) => page(
+            StatusCode::BAD_REQUEST,
+            "Link not valid",
+            &escape_html(&error.to_string()),
+        ),
+    }
+}
+
+async fn perform(Path(token): Path<String>) -> Response {
+    // The admin who sent the link has no session here, so it's done as us.
+    match kanidm::as_service(run(&token)).await {
+        Ok(done) => page(StatusCode::OK, "Done", done),
+        Err(error) => page(
+            StatusCode::BAD_REQUEST,
+            "Couldn't do that",
+            &escape_html(&error.to_string()),
+        ),
+    }
+}
+
+async fn run(token: &str) -> Result<&'static str> {
+    let signed = decode(token)?;
+    let expires_at = signed
+        .expires_at
+        .ok_or_else(|| err!("action links must expire"))?;
+    let token = signed.data;
+
+    // The actor may have lost admin access since the link was sent.
+    let actor = IDENTITY.get_person(&token.actor).await?;
+    if !actor.is_in_group(CONFIG.admin_group()) {
+        return Err(err!("{} is no longer an admin", token.actor));
+    }
+
+    // Claimed first so two clicks can't both act, and given back if the
+    // action fails so the link can be tried again.
+    if !UsedActionTokens::claim(token.id, expires_at).await? {
+        return Err(err!("this link has already been used"));
+    }
+    let result = act(&token).await;
+    if result.is_err()
+        && let Err(error) = UsedActionTokens::release(token.id).await
+    {
+        tracing::warn!(?error, "failed to give back action link");
+    }
+    result
+}
+
+async fn act(token: &ActionToken) -> Result<&'static str> {
+    match token.action {
+        Action::ApproveRequest => {
+            account_request::approve(token.target, &token.actor).await?;
+            Ok("The account request was approved.")
+        }
+        Action::RevokeLink => {
+            ProvisionLink::find(token.target).await?.delete().await?;
+            tracing::info!(by = token.actor, link = %token.target, "provision link revoked");
+            Ok("The provision link was revoked.")
+        }
+    }
+}
+
+/// A bare page for links followed from outside the app.
+pub(crate) fn page(status: StatusCode, title: &str, body: &str) -> Response {
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title} - AuthIt!</title></head>\
+        <body><h1>{title}</h1><p>{body}</p></body></html>"
+    );
+    (status, Html(html)).into_response()
+}
@@ -0,0 +1,137 @@
+use jiff::Timestamp;
+use pulldown_cmark::{CowStr, Event, Parser, Tag, html};
+use types::{
+    Result,
+    announcement::{ActiveAnnouncement, Announcement},
+    validation::{MAX_NOTES_LENGTH, check_length},
+};
+
+use crate::storage::Settings;
+
+pub async fn get_announcement() -> Result<Option<Announcement>> {
+    Settings::get(Settings::ANNOUNCEMENT).await
+}
+
+pub async fn set_announcement(announcement: Option<Announcement>) -> Result<()> {
+    match announcement {
+        Some(a) => {
+            check_length("announcement", &a.content, MAX_NOTES_LENGTH)?;
+            Settings::set(Settings::ANNOUNCEMENT, &a).await
+        }
+        None => Settings::delete(Settings::ANNOUNCEMENT).await,
+    }
+}
+
+/// The announcement to show right now, if any.
+pub async fn active_announcement() -> Result<Option<ActiveAnnouncement>> {
+    Ok(get_announcement()
+        .await?
+        .filter(|a| a.is_active(Timestamp::now()))
+        .map(|a| ActiveAnnouncement {
+            severity: a.severity,
+            html: render_markdown(&a.content),
+        }))
+}
+
+/// Schemes links and images may use. Anything else, like `javascript:`, is
+/// dropped.
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Render markdown to HTML, dropping any raw HTML and unsafe link and image
+/// URLs so announcements can't inject markup or script into the page.
+pub(crate) fn render_markdown(content: &str) -> String {
+    let parser = Parser::new(content)
+        .filter(|event| !matches!(event, Event::Html(_) | Event::InlineHtml(_)))
+        .map(|event| match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: CowStr::Borrowed(""),
+                title,
+                id,
+            }),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: CowStr::Borrowed(""),
+                title,
+                id,
+            }),
+            event => event,
+        });
+
+    let mut out = String::new();
+    html::push_html(&mut out, parser);
+    out
+}
+
+/// Whether a URL is relative or uses one of [`ALLOWED_SCHEMES`].
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters in a scheme.
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => ALLOWED_SCHEMES
+            .iter()
+            .any(|scheme| url[..i].eq_ignore_ascii_case(scheme)),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn javascript_links_are_dropped() {
+        for markdown in [
+            "[x](javascript:alert(1))",
+            "[x](JavaScript:alert(1))",
+            "[x](javascript&#58;alert(1))",
+            "[x](<java\tscript:alert(1)>)",
+            "<javascript:alert(1)>",
+            "[x][ref]\n\n[ref]: javascript:alert(1)",
+        ] {
+            let html = render_markdown(markdown);
+            assert!(html.contains(r#"href="""#), "{markdown} gave {html}");
+        }
+    }
+
+    #[test]
+    fn javascript_images_are_dropped() {
+        let html = render_markdown("![x](javascript:alert(1))");
+        assert!(html.contains(r#"src="""#), "{html}");
+        assert!(!render_markdown("![x](data:text/html,hi)").contains("data:"));
+    }
+
+    #[test]
+    fn safe_links_are_kept() {
+        for (markdown, href) in [
+            ("[x](https://example.com/a)", "https://example.com/a"),
+            ("[x](http://example.com)", "http://example.com"),
+            ("[x](mailto:it@example.com)", "mailto:it@example.com"),
+            ("[x](/users?id=1)", "/users?id=1"),
+            ("[x](help#a:b)", "help#a:b"),
+        ] {
+            let html = render_markdown(markdown);
+            assert!(html.contains(&format!(r#"href="{href}""#)), "{html}");
+        }
+    }
+
+    #[test]
+    fn raw_html_is_dropped() {
+        let html = render_markdown("<script>alert(1)</script>\n\nhi <b onclick=x>there</b>");
+        assert!(!html.contains("<script"), "{html}");
+        assert!(!html.contains("<b"), "{html}");
+    }
+}
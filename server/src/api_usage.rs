@@ -0,0 +1,37 @@
+use axum::http::Uri;
+use dioxus::fullstack::FullstackContext;
+use types::{
+    Result,
+    api_usage::{AdminApiUsage, ApiUsage},
+};
+
+use crate::{UserData, shutdown, storage::ApiUsages};
+
+/// Count a server function call by an admin, in the background so it doesn't
+/// slow the call down.
+pub(crate) async fn record(user: &UserData) {
+    let uri: Result<Uri, _> = FullstackContext::extract().await;
+    let Ok(uri) = uri else {
+        return;
+    };
+    let user_id = user.user_id.clone();
+    let user_name = user.username.clone();
+    let path = uri.path().to_string();
+
+    shutdown::spawn(async move {
+        if let Err(error) = ApiUsages::record(&user_id, &user_name, &path).await {
+            tracing::warn!(?error, "failed to record API usage");
+        }
+    });
+}
+
+/// How much each admin has used the API, busiest first.
+pub async fn by_admin() -> Result<Vec<AdminApiUsage>> {
+    let usage: Vec<ApiUsage> = ApiUsages::list().await?;
+    Ok(AdminApiUsage::by_admin(usage))
+}
+
+/// Start counting again from nothing.
+pub async fn clear() -> Result<()> {
+    ApiUsages::clear().await
+}
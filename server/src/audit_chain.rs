@@ -0,0 +1,80 @@
+use jiff::Timestamp;
+use types::{
+    Result,
+    audit::{ChainBreak, ChainStatus},
+};
+
+use crate::storage::{Activities, ChainedActivity, chain_hash};
+
+/// Check every record in the activity stream against the one before it.
+///
+/// The oldest remaining record is trusted as the start of the chain, as
+/// retention deletes records from the start.
+pub async fn verify() -> Result<ChainStatus> {
+    let mut status = ChainStatus {
+        checked_at: Timestamp::now(),
+        records: 0,
+        redacted: 0,
+        unchained: 0,
+        broken_at: None,
+    };
+
+    let mut prev_hash: Option<String> = None;
+    for record in Activities::chain().await? {
+        if let Some(reason) = check(&record, prev_hash.as_deref(), status.records > 0) {
+            status.broken_at = Some(ChainBreak {
+                id: record.activity.id,
+                at: record.activity.at,
+                reason: reason.to_string(),
+            });
+            break;
+        }
+        match record.hash {
+            Some(hash) => {
+                status.records += 1;
+                if record.redacted {
+                    status.redacted += 1;
+                }
+                prev_hash = Some(hash);
+            }
+            None => status.unchained += 1,
+        }
+    }
+
+    if let Some(broken) = &status.broken_at {
+        tracing::error!(id = %broken.id, reason = broken.reason, "activity hash chain is broken");
+    }
+    Ok(status)
+}
+
+/// Why `record` doesn't belong after `prev_hash`, if it doesn't.
+fn check(record: &ChainedActivity, prev_hash: Option<&str>, chained: bool) -> Option<&'static str> {
+    let Some(hash) = &record.hash else {
+        return chained.then_some("isn't chained, but comes after records that are");
+    };
+    if chained && record.prev_hash.as_deref() != prev_hash {
+        return Some("doesn't follow the record before it; records may have been removed");
+    }
+    if !record.redacted && chain_hash(record.prev_hash.as_deref(), &record.activity) != *hash {
+        return Some("has been changed since it was recorded");
+    }
+    None
+}
+
+/// Check the chain, for `authit audit verify`.
+pub fn run() -> Result<(String, bool)> {
+    let status = tokio::runtime::Runtime::new()?.block_on(verify())?;
+
+    let mut out = format!(
+        "{} records checked, {} of them redacted. {} records from before the chain weren't checked.\n",
+        status.records, status.redacted, status.unchained
+    );
+    match &status.broken_at {
+        Some(broken) => out.push_str(&format!(
+            "\nThe chain is broken at record {} from {}: it {}.\n",
+            broken.id, broken.at, broken.reason
+        )),
+        None => out.push_str("The chain is intact.\n"),
+    }
+    Ok((out, status.is_intact()))
+}
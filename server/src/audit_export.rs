@@ -0,0 +1,177 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use types::{
+    Result,
+    activity::{Activity, ActivitySubject},
+    audit::{AuditExport, AuditFormat, AuditManifest},
+    err,
+    notification::NotificationEvent,
+};
+
+use crate::{
+    UserData,
+    notify::{self, Notification},
+    signed_token::{Purpose, SignedToken},
+    storage::Activities,
+};
+
+/// What a manifest's signature covers.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Signed {
+    filename: String,
+    format: AuditFormat,
+    from: Timestamp,
+    to: Timestamp,
+    records: usize,
+    sha256: String,
+    exported_at: Timestamp,
+    exported_by: String,
+}
+
+impl Signed {
+    fn of(manifest: &AuditManifest) -> Self {
+        Self {
+            filename: manifest.filename.clone(),
+            format: manifest.format,
+            from: manifest.from,
+            to: manifest.to,
+            records: manifest.records,
+            sha256: manifest.sha256.clone(),
+            exported_at: manifest.exported_at,
+            exported_by: manifest.exported_by.clone(),
+        }
+    }
+}
+
+/// Export the activity stream from `from` up to `to`, optionally with a
+/// signed manifest. The export is itself recorded in the activity stream.
+pub async fn export(
+    from: Timestamp,
+    to: Timestamp,
+    format: AuditFormat,
+    sign: bool,
+    admin: &UserData,
+) -> Result<AuditExport> {
+    if from >= to {
+        return Err(err!("the start of the range must be before the end"));
+    }
+
+    let activity = Activities::list_between(from, to).await?;
+    let content = match format {
+        AuditFormat::Csv => to_csv(&activity),
+        AuditFormat::Jsonl => to_jsonl(&activity)?,
+    };
+    let exported_at = Timestamp::now();
+    let filename = format!(
+        "audit-{}-{}.{}",
+        from.strftime("%Y%m%dT%H%M%SZ"),
+        to.strftime("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    );
+
+    let manifest = if sign {
+        let mut manifest = AuditManifest {
+            filename: filename.clone(),
+            format,
+            from,
+            to,
+            records: activity.len(),
+            sha256: sha256(&content),
+            exported_at,
+            exported_by: admin.username.clone(),
+            signature: String::new(),
+        };
+        manifest.signature =
+            SignedToken::new(Purpose::AuditExport, Signed::of(&manifest), None).encode()?;
+        Some(manifest)
+    } else {
+        None
+    };
+
+    tracing::info!(
+        %from,
+        %to,
+        records = activity.len(),
+        admin = admin.username,
+        "exported audit log"
+    );
+    notify::send(
+        Notification::new(
+            NotificationEvent::AuditLogExported,
+            format!(
+                "{} exported {} audit log records from {from} to {to}.",
+                admin.username,
+                activity.len()
+            ),
+        )
+        .by(admin)
+        .detail("Format", format.extension())
+        .detail("Signed", if sign { "yes" } else { "no" }),
+    );
+
+    Ok(AuditExport {
+        filename,
+        content,
+        manifest,
+    })
+}
+
+/// Check that `manifest` was signed by us, and that `content` is the export
+/// it describes.
+pub fn verify(content: &str, manifest: &AuditManifest) -> Result<()> {
+    let token = SignedToken::<Signed>::decode(&manifest.signature, Purpose::AuditExport)
+        .map_err(|_| err!("the manifest's signature isn't valid"))?;
+    if token.data != Signed::of(manifest) {
+        return Err(err!("the manifest has been changed since it was signed"));
+    }
+    if sha256(content) != manifest.sha256 {
+        return Err(err!("the export has been changed since it was signed"));
+    }
+    Ok(())
+}
+
+fn sha256(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn to_csv(activity: &[Activity]) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+
+    let mut csv = String::from("id,at,event,summary,actor_id,actor,user_id,user,group,ticket\n");
+    for a in activity {
+        let (actor_id, actor) = match &a.actor {
+            Some(actor) => (actor.user_id.to_string(), actor.name.as_str()),
+            None => (String::new(), ""),
+        };
+        let (user_id, user, group) = match &a.subject {
+            Some(ActivitySubject::User { id, name }) => (id.to_string(), name.as_str(), ""),
+            Some(ActivitySubject::Group { name }) => (String::new(), "", name.as_str()),
+            None => (String::new(), "", ""),
+        };
+        csv.push_str(&format!(
+            "{},{},{},{},{actor_id},{},{user_id},{},{},{}\n",
+            a.id,
+            a.at,
+            a.event.as_str(),
+            quote(&a.summary),
+            quote(actor),
+            quote(user),
+            quote(group),
+            quote(a.ticket.as_deref().unwrap_or_default()),
+        ));
+    }
+    csv
+}
+
+fn to_jsonl(activity: &[Activity]) -> Result<String> {
+    let mut jsonl = String::new();
+    for a in activity {
+        jsonl.push_str(&serde_json::to_string(a)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
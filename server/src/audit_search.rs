@@ -0,0 +1,24 @@
+use types::{Result, activity::ActivityMatch, err};
+
+use crate::storage::Activities;
+
+/// The most matches a search returns.
+const LIMIT: u32 = 200;
+
+/// Search the audit history for activity mentioning every word of `text`, in
+/// its summary, actor, subject or ticket. Words match as prefixes, so `jsm`
+/// finds `jsmith`.
+pub async fn search(text: &str) -> Result<Vec<ActivityMatch>> {
+    let query = fts_query(text).ok_or_else(|| err!("enter something to search for"))?;
+    Activities::search(&query, LIMIT).await
+}
+
+/// Turn what someone typed into an FTS5 query, quoting each word so nothing
+/// in it is taken as query syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
@@ -1,29 +1,42 @@
 use axum::{
     Router,
-    extract::{Query, State},
-    http::HeaderMap,
-    response::{IntoResponse, Redirect},
-    routing::get,
+    extract::{Form, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
 };
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
 use cookie::Cookie;
 use dioxus::server::ServerFnError;
+use jiff::Timestamp;
 use oauth2::{
     AuthUrl, ClientId, CsrfToken, EndpointNotSet, EndpointSet, PkceCodeChallenge, PkceCodeVerifier,
     RedirectUrl, Scope, StandardErrorResponse, TokenUrl, basic::BasicClient,
 };
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
-use std::{
-    collections::HashMap,
-    sync::Arc,
-    time::{Duration, Instant},
+use std::time::Duration;
+use types::{err, login_event::LoginFailure, notification::NotificationEvent};
+use uuid::Uuid;
+
+use crate::{SESSION_COOKIE_NAME, session_token, user_data::UserData};
+
+use crate::{
+    CONFIG, ReqwestExt,
+    device::Device,
+    notify::{self, Notification},
+    proxy,
+    storage::{BreakGlass, LoginEvents, LoginUser, PendingLogin, Session},
 };
-use tokio::sync::RwLock;
-use types::err;
 
-use crate::{SESSION_COOKIE_NAME, user_data::UserData};
+/// Break-glass sessions are deliberately short, as they bypass Kanidm.
+const BREAK_GLASS_SESSION_TTL: Duration = Duration::from_secs(3600);
 
-use crate::{CONFIG, ReqwestExt, storage::Session};
+/// If Kanidm's clock differs from ours by more than this, failed logins are
+/// blamed on clock skew.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
 
 type ConfiguredClient = oauth2::Client<
     StandardErrorResponse<oauth2::basic::BasicErrorResponseType>,
@@ -44,7 +57,6 @@ type ConfiguredClient = oauth2::Client<
 #[derive(Clone)]
 pub struct AuthState {
     pub oauth_client: ConfiguredClient,
-    pub pkce_verifiers: Arc<RwLock<HashMap<String, (String, Instant)>>>,
 }
 
 impl AuthState {
@@ -57,62 +69,154 @@ impl AuthState {
             .set_token_uri(TokenUrl::from_url(kanidm_url.join("/oauth2/token")?))
             .set_redirect_uri(RedirectUrl::from_url(authit_url.join("/auth/callback")?));
 
-        Ok(Self {
-            oauth_client,
-            pkce_verifiers: Arc::new(RwLock::new(HashMap::new())),
-        })
-    }
-
-    async fn cleanup_old_verifiers(&self) {
-        let mut verifiers = self.pkce_verifiers.write().await;
-        let now = Instant::now();
-        let ttl = Duration::from_secs(600); // 10 minutes
-        verifiers.retain(|_, (_, created)| now.duration_since(*created) < ttl);
+        Ok(Self { oauth_client })
     }
 }
 
 pub fn auth_router(state: AuthState) -> Router {
-    Router::new()
+    let router = Router::new()
         .route("/auth/login", get(login))
         .route("/auth/callback", get(callback))
         .route("/auth/logout", get(logout))
-        .with_state(state)
+        .route("/auth/break-glass", post(break_glass_login));
+    #[cfg(feature = "test-login")]
+    let router = router.route("/auth/test-login", post(test_login));
+    router.with_state(state)
+}
+
+/// Only allow local paths to return to, so we can't be used as an open
+/// redirect.
+fn local_path(path: Option<String>) -> Option<String> {
+    path.filter(|p| is_local_path(p))
 }
 
-async fn login(State(state): State<AuthState>) -> impl IntoResponse {
-    state.cleanup_old_verifiers().await;
+/// Browsers treat `\` like `/` and drop tabs and newlines, so a path with
+/// any of them, percent-encoded or not, could be read as another host.
+fn is_local_path(path: &str) -> bool {
+    let suspicious = |p: &str| {
+        !p.starts_with('/') || p.starts_with("//") || p.chars().any(|c| c == '\\' || c.is_control())
+    };
+    let decoded = percent_decode_str(path).decode_utf8_lossy();
+    if suspicious(path) || suspicious(&decoded) {
+        return false;
+    }
+
+    // Whatever's left must still be on our own origin once resolved.
+    let base = Url::parse("http://authit.invalid/").expect("valid base URL");
+    base.join(path)
+        .is_ok_and(|url| url.origin() == base.origin())
+}
+
+#[derive(Deserialize)]
+struct LoginParams {
+    #[serde(default)]
+    remember: bool,
+    /// Force Kanidm to ask for credentials, even if the user has an SSO
+    /// session. Used for step-up authentication.
+    #[serde(default)]
+    reauth: bool,
+    /// Local path to send the user back to after logging in.
+    return_to: Option<String>,
+}
 
+async fn login(
+    State(state): State<AuthState>,
+    Query(params): Query<LoginParams>,
+) -> Result<impl IntoResponse, ServerFnError> {
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
     let csrf_token = CsrfToken::new_random();
 
-    // Store verifier with timestamp
-    state.pkce_verifiers.write().await.insert(
-        csrf_token.secret().clone(),
-        (pkce_verifier.secret().clone(), Instant::now()),
-    );
+    PendingLogin {
+        started_at: Timestamp::now(),
+        pkce_verifier: pkce_verifier.secret().clone(),
+        remember: params.remember,
+        reauth: params.reauth,
+        return_to: local_path(params.return_to),
+    }
+    .insert(csrf_token.secret())
+    .await?;
 
-    let (auth_url, _csrf) = state
+    let mut request = state
         .oauth_client
         .authorize_url(|| csrf_token)
         .add_scope(Scope::new("openid".to_string()))
         .add_scope(Scope::new("profile".to_string()))
         .add_scope(Scope::new("email".to_string()))
         .add_scope(Scope::new("groups".to_string()))
-        .set_pkce_challenge(pkce_challenge)
-        .url();
+        .set_pkce_challenge(pkce_challenge);
+
+    if params.reauth {
+        request = request.add_extra_param("prompt", "login");
+    }
+
+    let (auth_url, _csrf) = request.url();
 
-    Redirect::to(auth_url.as_str())
+    Ok(Redirect::to(auth_url.as_str()))
 }
 
+/// Kanidm sends either `code` or `error`. Everything is optional so that a
+/// malformed callback still gets a friendly error.
 #[derive(Deserialize)]
 struct AuthCallback {
-    code: String,
-    state: String,
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Why a login failed, in terms that are safe to show the user.
+fn failure_message(failure: LoginFailure) -> &'static str {
+    match failure {
+        LoginFailure::StateExpired => {
+            "Your sign-in took too long or was already used. Please try again."
+        }
+        LoginFailure::ProviderRejected => "Kanidm didn't accept the sign-in. Please try again.",
+        LoginFailure::ClockSkew => {
+            "AuthIt!'s clock doesn't match Kanidm's, so the sign-in couldn't be verified. \
+             Please contact an administrator."
+        }
+        LoginFailure::Internal => "Something went wrong signing you in. Please try again.",
+    }
+}
+
+struct LoginError {
+    failure: LoginFailure,
+    cause: types::Error,
+}
+
+trait LoginResultExt<T> {
+    fn or_fail(self, failure: LoginFailure) -> Result<T, LoginError>;
+}
+
+impl<T, E: Into<types::Error>> LoginResultExt<T> for Result<T, E> {
+    fn or_fail(self, failure: LoginFailure) -> Result<T, LoginError> {
+        self.map_err(|e| LoginError {
+            failure,
+            cause: e.into(),
+        })
+    }
 }
 
 #[derive(Deserialize)]
 struct TokenResponse {
     access_token: SecretString,
+    id_token: Option<String>,
+}
+
+/// When Kanidm says the user last entered their credentials, from the ID
+/// token's `auth_time` claim. The token comes straight from Kanidm's token
+/// endpoint over TLS, so, as OpenID Connect allows, its signature isn't
+/// checked.
+fn id_token_auth_time(id_token: &str) -> Option<Timestamp> {
+    #[derive(Deserialize)]
+    struct Claims {
+        auth_time: Option<i64>,
+    }
+
+    let payload = id_token.split('.').nth(1)?;
+    let payload = BASE64_URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: Claims = serde_json::from_slice(&payload).ok()?;
+    Timestamp::from_second(claims.auth_time?).ok()
 }
 
 #[derive(Deserialize)]
@@ -123,99 +227,305 @@ struct UserInfoResponse {
     groups: Vec<String>,
 }
 
-async fn callback(
-    State(state): State<AuthState>,
-    Query(params): Query<AuthCallback>,
-) -> Result<impl IntoResponse, ServerFnError> {
-    callback_inner(state, params).await.map_err(Into::into)
+/// On failure, send the user back to the login page with a safe message, and
+/// log the details under an ID they can report. Either way, the attempt is
+/// recorded.
+async fn callback(Query(params): Query<AuthCallback>, headers: HeaderMap) -> Response {
+    let device = Device::from_headers(&headers);
+    match callback_inner(params, &headers, &device).await {
+        Ok((response, user)) => {
+            record_login(Some(&user), None, &device).await;
+            response
+        }
+        Err(LoginError { failure, cause }) => {
+            let correlation_id = Uuid::now_v7();
+            tracing::warn!(%correlation_id, ?failure, error = ?cause, "login failed");
+            let user = reauthenticating_user(&headers).await;
+            record_login(user.as_ref(), Some(failure), &device).await;
+
+            let mut url = CONFIG.authit_url.clone();
+            url.set_path("/login");
+            url.query_pairs_mut().append_pair(
+                "error",
+                &format!("{} (Reference: {correlation_id})", failure_message(failure)),
+            );
+            Redirect::to(url.as_str()).into_response()
+        }
+    }
+}
+
+async fn record_login(user: Option<&LoginUser>, failure: Option<LoginFailure>, device: &Device) {
+    if let Err(error) = LoginEvents::record(user, failure, device).await {
+        tracing::warn!(?error, "failed to record login");
+    }
+}
+
+/// Failed logins usually end before we know who was signing in, but someone
+/// re-authenticating is already signed in.
+async fn reauthenticating_user(headers: &HeaderMap) -> Option<LoginUser> {
+    let session = Session::find_token(session_token(headers)?).await.ok()?;
+    Some(LoginUser {
+        user_id: session.user_data.user_id,
+        username: session.user_data.username,
+    })
 }
 
 async fn callback_inner(
-    state: AuthState,
     params: AuthCallback,
-) -> types::Result<impl IntoResponse> {
+    headers: &HeaderMap,
+    device: &Device,
+) -> Result<(Response, LoginUser), LoginError> {
+    if let Some(error) = params.error {
+        let description = params.error_description.unwrap_or_default();
+        return Err(LoginError {
+            failure: LoginFailure::ProviderRejected,
+            cause: err!("kanidm returned error '{error}': {description}"),
+        });
+    }
+
     // Retrieve and remove the PKCE verifier
-    let (verifier_secret, _) = state
-        .pkce_verifiers
-        .write()
+    let csrf_state = params.state.unwrap_or_default();
+    let pending = PendingLogin::take(&csrf_state)
         .await
-        .remove(&params.state)
-        .ok_or_else(|| err!("missing pkce verifier"))?;
+        .or_fail(LoginFailure::Internal)?
+        .ok_or_else(|| err!("missing pkce verifier"))
+        .or_fail(LoginFailure::StateExpired)?;
+    let code = params
+        .code
+        .ok_or_else(|| err!("missing authorization code"))
+        .or_fail(LoginFailure::ProviderRejected)?;
 
-    let pkce_verifier = PkceCodeVerifier::new(verifier_secret);
+    let pkce_verifier = PkceCodeVerifier::new(pending.pkce_verifier);
 
     // Exchange authorization code for token (public client, no secret)
     let client = reqwest::Client::new();
-    let token_url = CONFIG.kanidm_url.join("oauth2/token")?;
+    let token_url = CONFIG
+        .kanidm_url
+        .join("oauth2/token")
+        .or_fail(LoginFailure::Internal)?;
+    let redirect_uri = CONFIG
+        .authit_url
+        .join("/auth/callback")
+        .or_fail(LoginFailure::Internal)?;
 
-    let token_response: TokenResponse = client
+    let response = client
         .post(token_url)
         .form(&[
             ("grant_type", "authorization_code"),
-            ("code", &params.code),
-            (
-                "redirect_uri",
-                CONFIG.authit_url.join("/auth/callback")?.as_str(),
-            ),
+            ("code", &code),
+            ("redirect_uri", redirect_uri.as_str()),
             ("client_id", &CONFIG.oauth_client_id),
             ("client_secret", CONFIG.oauth_client_secret.expose_secret()),
             ("code_verifier", pkce_verifier.secret()),
         ])
-        .try_send()
-        .await?;
+        .send()
+        .await
+        .or_fail(LoginFailure::Internal)?;
+
+    // A rejected code exchange is most likely a problem with Kanidm, unless
+    // our clocks disagree.
+    let rejected = if clock_skew(&response).is_some_and(|skew| skew > MAX_CLOCK_SKEW) {
+        LoginFailure::ClockSkew
+    } else {
+        LoginFailure::ProviderRejected
+    };
+    let token_response: TokenResponse = response
+        .error_for_status()
+        .or_fail(rejected)?
+        .json()
+        .await
+        .or_fail(rejected)?;
 
     // Fetch user info
-    let userinfo_url = CONFIG.kanidm_url.join(&format!(
-        "oauth2/openid/{}/userinfo",
-        CONFIG.oauth_client_id
-    ))?;
+    let userinfo_url = CONFIG
+        .kanidm_url
+        .join(&format!(
+            "oauth2/openid/{}/userinfo",
+            CONFIG.oauth_client_id
+        ))
+        .or_fail(LoginFailure::Internal)?;
     let user_info_response: UserInfoResponse = client
         .get(userinfo_url)
         .bearer_auth(token_response.access_token.expose_secret())
         .try_send()
-        .await?;
+        .await
+        .or_fail(rejected)?;
 
-    let user_data = UserData {
+    let mut user_data = UserData {
         user_id: user_info_response.sub,
         username: user_info_response.preferred_username,
         display_name: user_info_response.name,
         groups: user_info_response.groups,
         access_token: token_response.access_token,
+        break_glass: false,
+        test_login: false,
+        ticket: None,
+    };
+
+    // Asking Kanidm to re-authenticate is up to the browser, so it only
+    // counts if Kanidm says the user entered their credentials since. Its
+    // clock may be a little behind ours.
+    let auth_time = token_response
+        .id_token
+        .as_deref()
+        .and_then(id_token_auth_time);
+    let reauthenticated =
+        pending.reauth && auth_time.is_some_and(|t| t + MAX_CLOCK_SKEW >= pending.started_at);
+    if pending.reauth && !reauthenticated {
+        tracing::warn!(
+            ?auth_time,
+            started_at = %pending.started_at,
+            "kanidm didn't re-authenticate"
+        );
+    }
+
+    // A re-authentication replaces the existing session, keeping its choice
+    // of lifetime and the ticket being worked on.
+    let mut remember = pending.remember;
+    if pending.reauth
+        && let Some(token) = session_token(headers)
+        && let Ok(old) = Session::find_token(token).await
+    {
+        remember = old.is_persistent();
+        user_data.ticket = old.user_data.ticket.clone();
+        old.delete().await.or_fail(LoginFailure::Internal)?;
+    }
+
+    let user = LoginUser {
+        user_id: user_data.user_id.clone(),
+        username: user_data.username.clone(),
     };
 
     // Store session server-side and get signed token
-    let session = Session::create(user_data).await?;
+    let lifetime = CONFIG.session_ttl(remember);
+    let session = Session::create(
+        user_data,
+        lifetime,
+        remember,
+        reauthenticated,
+        device.clone(),
+    )
+    .await
+    .or_fail(LoginFailure::Internal)?;
+    let token = session.as_token().or_fail(LoginFailure::Internal)?;
+
+    // Only persist the cookie past the browser session if asked to.
+    let max_age = remember.then_some(lifetime);
+    let return_to = pending.return_to.as_deref().unwrap_or("/");
+    Ok((
+        set_session_cookie(headers, &token, max_age, return_to).into_response(),
+        user,
+    ))
+}
+
+/// How far Kanidm's clock is from ours, judging by a response's `Date` header.
+fn clock_skew(response: &reqwest::Response) -> Option<Duration> {
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)?
+        .to_str()
+        .ok()?;
+    let theirs = jiff::fmt::rfc2822::parse(date).ok()?.timestamp();
+    let skew = jiff::Timestamp::now().duration_since(theirs).unsigned_abs();
+    Some(skew)
+}
+
+#[derive(Deserialize)]
+struct BreakGlassForm {
+    password: SecretString,
+}
+
+async fn break_glass_login(
+    headers: HeaderMap,
+    Form(form): Form<BreakGlassForm>,
+) -> Result<Response, ServerFnError> {
+    if !CONFIG.break_glass_enabled {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    if !BreakGlass::attempt_login(&form.password).await? {
+        return Ok(Redirect::to("/break-glass?error=Invalid%20password").into_response());
+    }
+
+    notify::send(Notification::new(
+        NotificationEvent::BreakGlassLogin,
+        "Someone signed in with the break-glass account.",
+    ));
+
+    let session = Session::create(
+        UserData::break_glass(),
+        BREAK_GLASS_SESSION_TTL,
+        false,
+        true,
+        Device::from_headers(&headers),
+    )
+    .await?;
+    let token = session.as_token()?;
+
+    Ok(set_session_cookie(&headers, &token, None, "/").into_response())
+}
+
+#[cfg(feature = "test-login")]
+#[derive(Deserialize)]
+struct TestLoginParams {
+    return_to: Option<String>,
+}
+
+/// Sign in as the configured fake admin without going through Kanidm, so
+/// end-to-end tests can run without its UI. Only built with the `test-login`
+/// feature.
+#[cfg(feature = "test-login")]
+async fn test_login(
+    headers: HeaderMap,
+    Query(params): Query<TestLoginParams>,
+) -> Result<Response, ServerFnError> {
+    tracing::warn!(username = %CONFIG.test_login_username, "test login");
+
+    let session = Session::create(
+        UserData::test_login(),
+        CONFIG.session_ttl(false),
+        false,
+        true,
+        Device::from_headers(&headers),
+    )
+    .await?;
     let token = session.as_token()?;
 
-    Ok(set_session_cookie(&token))
+    let return_to = local_path(params.return_to);
+    Ok(
+        set_session_cookie(&headers, &token, None, return_to.as_deref().unwrap_or("/"))
+            .into_response(),
+    )
 }
 
 async fn logout(headers: HeaderMap) -> impl IntoResponse {
     // Try to delete session from DB
-    if let Some(cookie_header) = headers.get(axum::http::header::COOKIE)
-        && let Ok(cookie_str) = cookie_header.to_str()
-    {
-        for part in cookie_str.split(';') {
-            let part = part.trim();
-            if let Some(token) = part.strip_prefix(&format!("{}=", SESSION_COOKIE_NAME)) {
-                let _ = Session::delete_token(token).await;
-            }
-        }
+    if let Some(token) = session_token(&headers) {
+        let _ = Session::delete_token(token).await;
     }
 
     // Clear the session cookie
-    set_session_cookie("")
+    set_session_cookie(&headers, "", Some(Duration::ZERO), "/")
 }
 
-fn set_session_cookie(value: &str) -> impl IntoResponse + use<> {
-    let cookie = Cookie::build((SESSION_COOKIE_NAME, value))
+fn set_session_cookie(
+    headers: &HeaderMap,
+    value: &str,
+    max_age: Option<Duration>,
+    redirect_to: &str,
+) -> impl IntoResponse + use<> {
+    let mut cookie = Cookie::build((SESSION_COOKIE_NAME, value))
         .path("/")
         .http_only(true)
-        .secure(true)
+        .secure(!CONFIG.insecure_cookies || proxy::is_https(headers))
         .same_site(cookie::SameSite::Strict)
         .build();
 
-    let mut response = Redirect::to("/").into_response();
+    if let Some(max_age) = max_age {
+        cookie.set_max_age(cookie::time::Duration::seconds(max_age.as_secs() as i64));
+    }
+
+    let mut response = Redirect::to(redirect_to).into_response();
     response.headers_mut().insert(
         axum::http::header::SET_COOKIE,
         cookie.to_string().parse().unwrap(),
@@ -223,3 +533,73 @@ fn set_session_cookie(value: &str) -> impl IntoResponse + use<> {
 
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(path: &str) -> bool {
+        local_path(Some(path.to_string())).is_some()
+    }
+
+    #[test]
+    fn local_paths_are_allowed() {
+        assert!(allowed("/"));
+        assert!(allowed("/users"));
+        assert!(allowed("/users?search=a%20b#top"));
+        assert_eq!(local_path(None), None);
+    }
+
+    #[test]
+    fn other_hosts_are_refused() {
+        assert!(!allowed("https://evil.com"));
+        assert!(!allowed("evil.com"));
+        assert!(!allowed("//x"));
+        assert!(!allowed("///x"));
+    }
+
+    #[test]
+    fn backslashes_are_refused() {
+        assert!(!allowed("/\\x"));
+        assert!(!allowed("\\\\x"));
+        assert!(!allowed("/%5Cx"));
+        assert!(!allowed("/%5cx"));
+    }
+
+    #[test]
+    fn control_characters_are_refused() {
+        assert!(!allowed("/%09/x"));
+        assert!(!allowed("/\t/x"));
+        assert!(!allowed("/\n/x"));
+        assert!(!allowed("/%0D%0A/x"));
+    }
+
+    fn id_token(claims: &str) -> String {
+        format!(
+            "e30.{}.c2ln",
+            BASE64_URL_SAFE_NO_PAD.encode(claims.as_bytes())
+        )
+    }
+
+    #[test]
+    fn auth_time_is_read_from_the_id_token() {
+        let token = id_token(r#"{"sub":"x","auth_time":1700000000}"#);
+        assert_eq!(
+            id_token_auth_time(&token),
+            Some(Timestamp::from_second(1700000000).unwrap())
+        );
+    }
+
+    #[test]
+    fn id_tokens_without_auth_time_have_none() {
+        assert_eq!(id_token_auth_time(&id_token(r#"{"sub":"x"}"#)), None);
+        assert_eq!(id_token_auth_time("not a token"), None);
+        assert_eq!(id_token_auth_time("e30.!!!.c2ln"), None);
+    }
+
+    #[test]
+    fn encoded_slashes_are_refused() {
+        assert!(!allowed("/%2Fx"));
+        assert!(!allowed("/%2f%2fx"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::Cursor;
+
+use axum::{
+    Router,
+    extract::Path,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use image::{ImageFormat, imageops::FilterType};
+use types::{Result, err};
+use uuid::Uuid;
+
+use crate::{
+    session_token,
+    storage::{Avatars, Session},
+};
+
+/// Uploads larger than this are rejected before decoding.
+pub const MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// Avatars are stored as squares of this size.
+const SIZE: u32 = 256;
+
+/// Resize an uploaded image and store it as the user's avatar.
+pub async fn save(user_id: Uuid, data: Vec<u8>) -> Result<()> {
+    if data.len() > MAX_UPLOAD_BYTES {
+        return Err(err!("avatar must be smaller than 5 MB"));
+    }
+
+    let png = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let image = image::load_from_memory(&data)?;
+        let resized = image.resize_to_fill(SIZE, SIZE, FilterType::Lanczos3);
+        let mut png = Vec::new();
+        resized.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    })
+    .await??;
+
+    Avatars::set(user_id, &png).await
+}
+
+pub async fn delete(user_id: Uuid) -> Result<()> {
+    Avatars::delete(user_id).await
+}
+
+pub fn router() -> Router {
+    Router::new().route("/avatars/{user_id}", get(serve))
+}
+
+/// Serve an avatar to signed-in users. Browsers revalidate with the ETag, so
+/// new uploads show up right away.
+async fn serve(Path(user_id): Path<Uuid>, headers: HeaderMap) -> Response {
+    let signed_in = match session_token(&headers) {
+        Some(token) => Session::find_token(token).await.is_ok(),
+        None => false,
+    };
+    if !signed_in {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let avatar = match Avatars::get(user_id).await {
+        Ok(Some(avatar)) => avatar,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(error) => {
+            tracing::warn!(?error, %user_id, "failed to load avatar");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let etag = format!("\"{:x}\"", avatar.updated_at.as_millisecond());
+
+    let cache_headers = [
+        (header::CACHE_CONTROL, "private, no-cache".to_string()),
+        (header::ETAG, etag.clone()),
+    ];
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == etag);
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+
+    (
+        cache_headers,
+        [(header::CONTENT_TYPE, "image/png".to_string())],
+        avatar.image,
+    )
+        .into_response()
+}
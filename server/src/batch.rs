@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use types::{
+    Result,
+    batch::{BatchResult, ChangeOutcome, ChangeResult, PendingChange},
+    err,
+    group_webhook::MembershipAction,
+    ids::{GroupId, PersonId},
+    validation::{normalize_name, normalize_phone, validate_email},
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, UserData, can_see_legal_names, email_verification, group_review, membership,
+    storage::PhoneNumbers,
+};
+
+/// Check every change before any are applied, and fill in the user and group
+/// names from Kanidm so they can be trusted.
+///
+/// `modified` has each user's [`Person::modified`] as the client read it. If
+/// someone else has changed them since, nothing is applied.
+///
+/// [`Person::modified`]: types::kanidm::Person::modified
+pub async fn prepare(
+    admin: &UserData,
+    changes: Vec<PendingChange>,
+    modified: &HashMap<Uuid, String>,
+) -> Result<Vec<PendingChange>> {
+    if changes.is_empty() {
+        return Err(err!("there are no changes to apply"));
+    }
+    if !can_see_legal_names(admin).await?
+        && changes
+            .iter()
+            .any(|c| matches!(c, PendingChange::SetLegalName { .. }))
+    {
+        return Err(err!("you aren't allowed to change legal names"));
+    }
+
+    let mut prepared = Vec::with_capacity(changes.len());
+    for change in changes {
+        let user_id = change.user_id();
+        let person = IDENTITY
+            .get_unmodified_person(
+                &PersonId(user_id),
+                modified.get(&user_id).map(String::as_str),
+            )
+            .await?;
+        let user_id = person.uuid;
+        let user_name = person.name;
+
+        prepared.push(match change {
+            PendingChange::AddToGroup { group_id, .. } => PendingChange::AddToGroup {
+                user_id,
+                user_name,
+                group_id,
+                group_name: IDENTITY.get_group(&GroupId(group_id)).await?.name,
+            },
+            PendingChange::RemoveFromGroup { group_id, .. } => PendingChange::RemoveFromGroup {
+                user_id,
+                user_name,
+                group_id,
+                group_name: IDENTITY.get_group(&GroupId(group_id)).await?.name,
+            },
+            PendingChange::SetEmailAddresses { addresses, .. } => {
+                let mut cleaned: Vec<String> = Vec::with_capacity(addresses.len());
+                for address in addresses {
+                    let address = address.trim().to_string();
+                    validate_email(&address)?;
+                    if cleaned.iter().any(|a| a.eq_ignore_ascii_case(&address)) {
+                        return Err(err!("'{address}' is listed more than once"));
+                    }
+                    cleaned.push(address);
+                }
+                PendingChange::SetEmailAddresses {
+                    user_id,
+                    user_name,
+                    addresses: cleaned,
+                }
+            }
+            PendingChange::SetPhoneNumber { phone_number, .. } => PendingChange::SetPhoneNumber {
+                user_id,
+                user_name,
+                phone_number: phone_number
+                    .filter(|p| !p.trim().is_empty())
+                    .map(|p| normalize_phone(&p))
+                    .transpose()?,
+            },
+            PendingChange::SetDisplayName { display_name, .. } => PendingChange::SetDisplayName {
+                user_id,
+                user_name,
+                display_name: normalize_name("display name", &display_name)?,
+            },
+            PendingChange::SetLegalName { legal_name, .. } => PendingChange::SetLegalName {
+                user_id,
+                user_name,
+                legal_name: legal_name
+                    .filter(|n| !n.trim().is_empty())
+                    .map(|n| normalize_name("legal name", &n))
+                    .transpose()?,
+            },
+        });
+    }
+
+    Ok(prepared)
+}
+
+/// Apply prepared changes in order. If one fails, the ones before it are
+/// undone and the rest are skipped, so the batch applies as a whole or not at
+/// all.
+///
+/// Kanidm has no transactions, so a rollback can itself fail; that's
+/// reported per change.
+pub async fn apply(changes: Vec<PendingChange>) -> BatchResult {
+    let mut results = Vec::with_capacity(changes.len());
+    let mut undo = Vec::new();
+    let mut failed = false;
+
+    for change in changes {
+        let outcome = if failed {
+            ChangeOutcome::Skipped
+        } else {
+            match apply_one(&change).await {
+                Ok(inverse) => {
+                    undo.push((results.len(), inverse));
+                    ChangeOutcome::Applied
+                }
+                Err(e) => {
+                    failed = true;
+                    ChangeOutcome::Failed(e.to_string())
+                }
+            }
+        };
+        results.push(ChangeResult { change, outcome });
+    }
+
+    if failed {
+        for (i, inverse) in undo.into_iter().rev() {
+            let outcome = match inverse {
+                Some(inverse) => match apply_one(&inverse).await {
+                    Ok(_) => ChangeOutcome::RolledBack,
+                    Err(e) => {
+                        tracing::error!(?e, ?inverse, "failed to roll back change");
+                        ChangeOutcome::RollbackFailed(e.to_string())
+                    }
+                },
+                // It didn't change anything, so there's nothing to undo.
+                None => ChangeOutcome::RolledBack,
+            };
+            results[i].outcome = outcome;
+        }
+    }
+
+    BatchResult { results }
+}
+
+/// Apply a change, returning the change that would undo it, if it did
+/// anything.
+async fn apply_one(change: &PendingChange) -> Result<Option<PendingChange>> {
+    match change {
+        PendingChange::AddToGroup {
+            user_id,
+            user_name,
+            group_id,
+            group_name,
+        }
+        | PendingChange::RemoveFromGroup {
+            user_id,
+            user_name,
+            group_id,
+            group_name,
+        } => {
+            let add = matches!(change, PendingChange::AddToGroup { .. });
+            let person = IDENTITY.get_person(&user_id.to_string()).await?;
+            let group = IDENTITY.get_group(&GroupId(*group_id)).await?;
+            if person.is_member_of(&group) == add {
+                return Ok(None);
+            }
+
+            if add {
+                email_verification::check_groups(*user_id, [group.name.as_str()]).await?;
+                group_review::check_groups([group.name.as_str()]).await?;
+                IDENTITY
+                    .add_user_to_group(&group.id(), &person.id())
+                    .await?;
+                membership::changed(&group, person.id(), MembershipAction::Added).await;
+                Ok(Some(PendingChange::RemoveFromGroup {
+                    user_id: *user_id,
+                    user_name: user_name.clone(),
+                    group_id: *group_id,
+                    group_name: group_name.clone(),
+                }))
+            } else {
+                IDENTITY
+                    .remove_user_from_group(&group.id(), &person.id())
+                    .await?;
+                membership::changed(&group, person.id(), MembershipAction::Removed).await;
+                Ok(Some(PendingChange::AddToGroup {
+                    user_id: *user_id,
+                    user_name: user_name.clone(),
+                    group_id: *group_id,
+                    group_name: group_name.clone(),
+                }))
+            }
+        }
+        PendingChange::SetEmailAddresses {
+            user_id,
+            user_name,
+            addresses,
+        } => {
+            let previous = IDENTITY
+                .get_person(&user_id.to_string())
+                .await?
+                .email_addresses;
+            IDENTITY
+                .set_email_addresses(&PersonId(*user_id), addresses)
+                .await?;
+            Ok(Some(PendingChange::SetEmailAddresses {
+                user_id: *user_id,
+                user_name: user_name.clone(),
+                addresses: previous,
+            }))
+        }
+        PendingChange::SetPhoneNumber {
+            user_id,
+            user_name,
+            phone_number,
+        } => {
+            let previous = PhoneNumbers::get(*user_id).await?;
+            match phone_number {
+                Some(p) => PhoneNumbers::set(*user_id, p).await?,
+                None => PhoneNumbers::delete(*user_id).await?,
+            }
+            Ok(Some(PendingChange::SetPhoneNumber {
+                user_id: *user_id,
+                user_name: user_name.clone(),
+                phone_number: previous,
+            }))
+        }
+        PendingChange::SetDisplayName {
+            user_id,
+            user_name,
+            display_name,
+        } => {
+            let previous = IDENTITY
+                .get_person(&user_id.to_string())
+                .await?
+                .display_name;
+            IDENTITY
+                .set_display_name(&PersonId(*user_id), display_name)
+                .await?;
+            Ok(Some(PendingChange::SetDisplayName {
+                user_id: *user_id,
+                user_name: user_name.clone(),
+                display_name: previous,
+            }))
+        }
+        PendingChange::SetLegalName {
+            user_id,
+            user_name,
+            legal_name,
+        } => {
+            let previous = IDENTITY.get_person(&user_id.to_string()).await?.legal_name;
+            IDENTITY
+                .set_legal_name(&PersonId(*user_id), legal_name.as_deref())
+                .await?;
+            Ok(Some(PendingChange::SetLegalName {
+                user_id: *user_id,
+                user_name: user_name.clone(),
+                legal_name: previous,
+            }))
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use tokio::task::JoinSet;
+use tracing::Instrument;
+use types::{
+    Result, err,
+    group_webhook::MembershipAction,
+    ids::{GroupId, GroupName, PersonId},
+    job::{JobKind, JobOutput},
+    kanidm::{Group, Person, Spn},
+    report::ReportEventKind,
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, email, email_verification, forget, group_import, group_review, kanidm, membership,
+    report, reset_watch, shutdown, storage::Jobs,
+};
+
+/// One thing a bulk job does.
+enum Step {
+    /// Runs before any other steps, as they may depend on the group.
+    CreateGroup {
+        name: GroupName,
+    },
+    /// By the group's name, as it may not exist until the job creates it.
+    AddToGroup {
+        group: GroupName,
+        user_id: PersonId,
+    },
+    RemoveFromGroup {
+        group_id: GroupId,
+        user_id: PersonId,
+    },
+    DeleteUser {
+        person: Person,
+    },
+    /// Make a credential reset link, and email it to the person's primary
+    /// address if `email` is set.
+    ResetCredentials {
+        person: Person,
+        email: bool,
+        started_by: String,
+    },
+}
+
+impl Step {
+    async fn run(&self) -> Result<Option<JobOutput>> {
+        match self {
+            Self::CreateGroup { name } => IDENTITY.create_group(name, None).await?,
+            Self::AddToGroup { group, user_id } => {
+                email_verification::check_groups(user_id.0, [group.as_str()]).await?;
+                group_review::check_groups([group.as_str()]).await?;
+                let group = IDENTITY.find_group(group).await?;
+                IDENTITY.add_user_to_group(&group.id(), user_id).await?;
+                membership::changed(&group, *user_id, MembershipAction::Added).await;
+            }
+            Self::RemoveFromGroup { group_id, user_id } => {
+                let group = IDENTITY.get_group(group_id).await?;
+                IDENTITY.remove_user_from_group(group_id, user_id).await?;
+                membership::changed(&group, *user_id, MembershipAction::Removed).await;
+            }
+            Self::DeleteUser { person } => {
+                IDENTITY.delete_person(&person.id()).await?;
+                report::record(ReportEventKind::AccountDeleted);
+                forget::forget_user(person).await?
+            }
+            Self::ResetCredentials {
+                person,
+                email,
+                started_by,
+            } => {
+                return reset_credentials(person, *email, started_by)
+                    .await
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+async fn reset_credentials(person: &Person, email: bool, started_by: &str) -> Result<JobOutput> {
+    let link = IDENTITY
+        .generate_credential_reset_link(&person.id())
+        .await?;
+    report::record(ReportEventKind::ResetLinkIssued);
+    reset_watch::start(person.uuid, started_by, &link).await;
+
+    let emailed = match person.email_addresses.first() {
+        Some(address) if email => {
+            let body = format!(
+                "Hi {},\n\n\
+                An administrator has asked you to set up new credentials for your \
+                account. Use this link before {}:\n\n\
+                {}\n",
+                person.display_name, link.expires_at, link.url
+            );
+            email::try_send(address, "Reset your credentials", body).await
+        }
+        _ => false,
+    };
+
+    Ok(JobOutput::ResetLink {
+        user_name: person.name.clone(),
+        url: link.url,
+        expires_at: link.expires_at,
+        emailed,
+    })
+}
+
+struct Item {
+    label: String,
+    step: Step,
+}
+
+/// Start a job running `items` in the background, returning its ID to poll.
+///
+/// Items run concurrently; the Kanidm client limits how many are in flight.
+async fn start(kind: JobKind, started_by: &str, items: Vec<Item>) -> Result<Uuid> {
+    if items.is_empty() {
+        return Err(err!("there's nothing to do"));
+    }
+
+    let id = Jobs::create(kind, started_by, items.len() as u32).await?;
+
+    let span = tracing::info_span!("bulk job", %id, kind = kind.as_str());
+    shutdown::spawn(kanidm::keep_writer(
+        async move {
+            let (first, rest): (Vec<_>, Vec<_>) = items
+                .into_iter()
+                .enumerate()
+                .partition(|(_, item)| matches!(item.step, Step::CreateGroup { .. }));
+
+            // Once Kanidm turns down the admin's token, the rest would fail
+            // the same way, so they're skipped.
+            let token_expired = Arc::new(AtomicBool::new(false));
+            for batch in [first, rest] {
+                let mut tasks = JoinSet::new();
+                for (position, item) in batch {
+                    let token_expired = token_expired.clone();
+                    tasks.spawn(kanidm::keep_writer(
+                        async move {
+                            let result = if token_expired.load(Ordering::Relaxed) {
+                                Err(kanidm::AdminTokenExpired.into())
+                            } else {
+                                item.step.run().await
+                            };
+                            let (output, error) = match result {
+                                Ok(output) => (output, None),
+                                Err(e) => {
+                                    if e.downcast_ref::<kanidm::AdminTokenExpired>().is_some() {
+                                        token_expired.store(true, Ordering::Relaxed);
+                                    }
+                                    (None, Some(e.to_string()))
+                                }
+                            };
+                            if let Err(error) = Jobs::record_item(
+                                id,
+                                position as u32,
+                                &item.label,
+                                error.as_deref(),
+                                output.as_ref(),
+                            )
+                            .await
+                            {
+                                tracing::warn!(?error, job = %id, "failed to record job progress");
+                            }
+                        }
+                        .in_current_span(),
+                    ));
+                }
+                while let Some(result) = tasks.join_next().await {
+                    if let Err(error) = result {
+                        tracing::warn!(?error, job = %id, "job item panicked");
+                    }
+                }
+            }
+
+            if let Err(error) = Jobs::finish(id, None).await {
+                tracing::warn!(?error, job = %id, "failed to finish job");
+            }
+        }
+        .instrument(span),
+    ));
+
+    Ok(id)
+}
+
+/// Apply a group import in the background.
+pub async fn start_group_import(csv: &str, started_by: &str) -> Result<Uuid> {
+    let plan = group_import::plan(csv).await?;
+
+    let mut items = Vec::new();
+    for change in plan.import.changes {
+        let group_id = plan.group_ids.get(&change.group);
+        let group = GroupName::from(change.group.as_str());
+        if group_id.is_none() {
+            items.push(Item {
+                label: format!("Create {}", change.group),
+                step: Step::CreateGroup {
+                    name: group.clone(),
+                },
+            });
+        }
+
+        for username in &change.add {
+            items.push(Item {
+                label: format!("Add {username} to {}", change.group),
+                step: Step::AddToGroup {
+                    group: group.clone(),
+                    user_id: plan.person_ids[username],
+                },
+            });
+        }
+        // Only existing groups have members to remove.
+        if let Some(group_id) = group_id {
+            for username in &change.remove {
+                items.push(Item {
+                    label: format!("Remove {username} from {}", change.group),
+                    step: Step::RemoveFromGroup {
+                        group_id: *group_id,
+                        user_id: plan.person_ids[username],
+                    },
+                });
+            }
+        }
+    }
+
+    start(JobKind::GroupImport, started_by, items).await
+}
+
+/// Delete several users in the background.
+pub async fn start_delete_users(user_ids: &[Uuid], started_by: &str) -> Result<Uuid> {
+    let mut persons: HashMap<Uuid, Person> = IDENTITY
+        .list_persons()
+        .await?
+        .into_iter()
+        .map(|p| (p.uuid, p))
+        .collect();
+
+    let items = user_ids
+        .iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|id| {
+            let person = persons
+                .remove(id)
+                .ok_or_else(|| err!("user {id} does not exist"))?;
+            Ok(Item {
+                label: format!("Delete {}", person.name),
+                step: Step::DeleteUser { person },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    start(JobKind::DeleteUsers, started_by, items).await
+}
+
+/// Make credential reset links for several users in the background, like
+/// after a security incident. The links are in the job's report.
+pub async fn start_reset_credentials(
+    user_ids: &[Uuid],
+    email: bool,
+    started_by: &str,
+) -> Result<Uuid> {
+    if email && !email::is_configured() {
+        return Err(err!("email is not configured"));
+    }
+
+    let mut persons: HashMap<Uuid, Person> = IDENTITY
+        .list_persons()
+        .await?
+        .into_iter()
+        .map(|p| (p.uuid, p))
+        .collect();
+
+    let items = user_ids
+        .iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|id| {
+            let person = persons
+                .remove(id)
+                .ok_or_else(|| err!("user {id} does not exist"))?;
+            Ok(Item {
+                label: format!("Reset credentials for {}", person.name),
+                step: Step::ResetCredentials {
+                    person,
+                    email,
+                    started_by: started_by.to_string(),
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    start(JobKind::ResetCredentials, started_by, items).await
+}
+
+/// The changes needed to give a group exactly a set of direct members.
+pub struct ReconcilePlan {
+    pub group: Group,
+    pub add: Vec<Person>,
+    pub remove: Vec<Person>,
+}
+
+/// Work out how to make `user_ids` the group's only direct members.
+///
+/// Only people are reconciled; nested groups and service accounts are left
+/// alone.
+pub async fn plan_group_reconcile(
+    group_id: GroupId,
+    user_ids: &[PersonId],
+) -> Result<ReconcilePlan> {
+    let group = IDENTITY.get_group(&group_id).await?;
+    let persons = IDENTITY.list_persons().await?;
+    if let Some(missing) = user_ids
+        .iter()
+        .find(|id| !persons.iter().any(|p| p.id() == **id))
+    {
+        return Err(err!("user {missing} does not exist"));
+    }
+
+    // Members are listed by SPN, like `name@domain`.
+    let current: BTreeSet<String> = IDENTITY
+        .list_group_members(&group_id)
+        .await?
+        .into_iter()
+        .map(|spn| Spn::parse(&spn).name.to_string())
+        .collect();
+
+    let (add, remove) = persons
+        .into_iter()
+        .filter(|p| user_ids.contains(&p.id()) != current.contains(&p.name))
+        .partition(|p| user_ids.contains(&p.id()));
+
+    Ok(ReconcilePlan { group, add, remove })
+}
+
+/// Apply a [`ReconcilePlan`] in the background.
+pub async fn start_group_reconcile(plan: ReconcilePlan, started_by: &str) -> Result<Uuid> {
+    let group = plan.group;
+    let adds = plan.add.into_iter().map(|p| Item {
+        label: format!("Add {} to {}", p.name, group.name),
+        step: Step::AddToGroup {
+            group: group.group_name(),
+            user_id: p.id(),
+        },
+    });
+    let removes = plan.remove.into_iter().map(|p| Item {
+        label: format!("Remove {} from {}", p.name, group.name),
+        step: Step::RemoveFromGroup {
+            group_id: group.id(),
+            user_id: p.id(),
+        },
+    });
+
+    start(
+        JobKind::ReconcileGroup,
+        started_by,
+        adds.chain(removes).collect(),
+    )
+    .await
+}
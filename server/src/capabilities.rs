@@ -0,0 +1,74 @@
+use std::{
+    collections::BTreeSet,
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use types::{
+    capability::{Capability, TokenCapabilities},
+    kanidm::Spn,
+};
+
+use crate::{KANIDM_CLIENT, identity::IdentityProvider};
+
+/// How often to check again, in case Kanidm's permissions changed.
+const PROBE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+static CAPABILITIES: LazyLock<RwLock<TokenCapabilities>> = LazyLock::new(RwLock::default);
+
+/// What the service token could do when last checked.
+pub fn current() -> TokenCapabilities {
+    CAPABILITIES.read().unwrap().clone()
+}
+
+/// Check what the service token can do now, and periodically after. Call this
+/// once, at startup.
+pub async fn spawn_probes() {
+    probe().await;
+    tokio::spawn(async {
+        let mut ticker = tokio::time::interval(PROBE_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            probe().await;
+        }
+    });
+}
+
+/// Check what the service token can do, keeping what we knew before if Kanidm
+/// can't be reached.
+pub async fn probe() -> TokenCapabilities {
+    let groups = match KANIDM_CLIENT.own_groups().await {
+        Ok(groups) => groups,
+        Err(error) => {
+            tracing::warn!(?error, "failed to check the Kanidm token's permissions");
+            return current();
+        }
+    };
+    let in_group = |group: &str| {
+        let group = Spn::parse(group);
+        groups.iter().any(|g| Spn::parse(g).matches(&group))
+    };
+
+    let mut granted: BTreeSet<Capability> = Capability::ALL
+        .into_iter()
+        .filter(|c| *c == Capability::ReadPersons || in_group(c.granted_by()))
+        .collect();
+    // Reading people can be granted in several ways, so just try it.
+    if KANIDM_CLIENT.list_persons().await.is_err() {
+        granted.remove(&Capability::ReadPersons);
+    }
+
+    let capabilities = TokenCapabilities { granted };
+    let previous = std::mem::replace(&mut *CAPABILITIES.write().unwrap(), capabilities.clone());
+    if capabilities != previous {
+        for missing in Capability::ALL.iter().filter(|c| !capabilities.allows(**c)) {
+            tracing::warn!(
+                capability = missing.label(),
+                group = missing.granted_by(),
+                "the Kanidm token is missing a permission"
+            );
+        }
+    }
+    capabilities
+}
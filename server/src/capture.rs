@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+use axum::http::Uri;
+use dioxus::{fullstack::FullstackContext, prelude::ServerFnError};
+use jiff::{SignedDuration, Timestamp};
+use reqwest::{Method, Url};
+use types::{
+    Result,
+    capture::{Capture, CaptureBundle, CaptureEvent, CaptureStatus, MAX_CAPTURE_MINUTES},
+    err,
+    server_info::ServerInfo,
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, shutdown,
+    storage::{CaptureEvents, Settings},
+};
+
+pub async fn status() -> Result<CaptureStatus> {
+    Ok(CaptureStatus {
+        capture: Settings::get(Settings::CAPTURE).await?,
+        events: CaptureEvents::count().await?,
+    })
+}
+
+/// Start recording for `minutes`, throwing away what the last capture
+/// recorded.
+pub async fn start(minutes: u32, admin: &str) -> Result<()> {
+    if !(1..=MAX_CAPTURE_MINUTES).contains(&minutes) {
+        return Err(err!(
+            "a capture must run for 1 to {MAX_CAPTURE_MINUTES} minutes"
+        ));
+    }
+
+    CaptureEvents::clear().await?;
+    let now = Timestamp::now();
+    let capture = Capture {
+        started_at: now,
+        until: now + SignedDuration::from_mins(minutes.into()),
+        started_by: admin.to_string(),
+    };
+    Settings::set(Settings::CAPTURE, &capture).await?;
+    tracing::info!(by = admin, minutes, "diagnostic capture started");
+    Ok(())
+}
+
+/// Stop recording early, keeping what was recorded to download.
+pub async fn stop(admin: &str) -> Result<()> {
+    if let Some(mut capture) = Settings::get::<Capture>(Settings::CAPTURE).await?
+        && capture.is_active()
+    {
+        capture.until = Timestamp::now();
+        Settings::set(Settings::CAPTURE, &capture).await?;
+        tracing::info!(by = admin, "diagnostic capture stopped");
+    }
+    Ok(())
+}
+
+/// Stop recording, and throw away what was recorded.
+pub async fn discard() -> Result<()> {
+    Settings::delete(Settings::CAPTURE).await?;
+    CaptureEvents::clear().await
+}
+
+/// Everything the last capture recorded, to attach to a bug report.
+pub async fn bundle() -> Result<CaptureBundle> {
+    Ok(CaptureBundle {
+        build: ServerInfo::current(),
+        identity_backend: format!("{:?}", CONFIG.identity_backend).to_lowercase(),
+        kanidm_replicas: CONFIG.kanidm_replica_urls()?.len() as u32,
+        capture: Settings::get(Settings::CAPTURE).await?,
+        events: CaptureEvents::list().await?,
+    })
+}
+
+/// Record a request to Kanidm, if a capture is running.
+pub(crate) fn record_kanidm(
+    method: &Method,
+    url: &Url,
+    duration: Duration,
+    error: Option<&types::Error>,
+) {
+    record(CaptureEvent::Kanidm {
+        method: method.to_string(),
+        path: sanitize_path(url.path()),
+        duration_ms: duration.as_millis() as u64,
+        error: error.map(|e| scrub(&format!("{e:#}"))),
+    });
+}
+
+/// Record a server function that failed, if a capture is running.
+pub(crate) async fn record_server_fn_failure(error: &ServerFnError) {
+    let ServerFnError::ServerError {
+        message,
+        code,
+        details,
+    } = error
+    else {
+        return;
+    };
+    let uri: Result<Uri, _> = FullstackContext::extract().await;
+    let Ok(uri) = uri else {
+        return;
+    };
+
+    let detail = |key: &str| details.as_ref().and_then(|d| d.get(key));
+    let chain = detail("chain")
+        .and_then(|c| c.as_array())
+        .map(|c| c.iter().filter_map(|v| v.as_str()).map(scrub).collect())
+        .unwrap_or_else(|| vec![scrub(message)]);
+    let backtrace = detail("backtrace")
+        .and_then(|b| b.as_str())
+        .map(String::from);
+
+    record(CaptureEvent::ServerFn {
+        path: uri.path().to_string(),
+        code: *code,
+        chain,
+        backtrace,
+    });
+}
+
+/// Store an event in the background, so recording doesn't slow anything
+/// down. Checking whether a capture is running is a single read from the
+/// settings table, like the maintenance check every request makes.
+fn record(event: CaptureEvent) {
+    shutdown::spawn(async move {
+        let result = async {
+            let capture: Option<Capture> = Settings::get(Settings::CAPTURE).await?;
+            if capture.is_some_and(|c| c.is_active()) {
+                CaptureEvents::record(&event).await?;
+            }
+            Ok::<_, types::Error>(())
+        }
+        .await;
+        if let Err(error) = result {
+            tracing::warn!(?error, "failed to record capture event");
+        }
+    });
+}
+
+/// A Kanidm API path with whatever identifies a person or group replaced, so
+/// `/v1/person/alice/_credential/_status` becomes
+/// `/v1/person/{id}/_credential/_status`.
+fn sanitize_path(path: &str) -> String {
+    path.split('/')
+        .enumerate()
+        // Paths look like `/v1/<kind>/<id>/_<action>`.
+        .map(|(i, segment)| {
+            if i <= 2 || segment.starts_with('_') {
+                segment
+            } else {
+                "{id}"
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Replace the URLs, email addresses and UUIDs in an error message. Other
+/// names can't be told apart from the rest of the message, so they're left.
+fn scrub(text: &str) -> String {
+    text.split_inclusive(is_separator)
+        .map(|piece| {
+            let word = piece.trim_end_matches(is_separator);
+            let separator = &piece[word.len()..];
+            let bare = word.trim_end_matches(['.', ':', ';']);
+            let punctuation = &word[bare.len()..];
+
+            let scrubbed = match Url::parse(bare) {
+                Ok(url) if url.has_host() => sanitize_path(url.path()),
+                _ if bare.contains('@') => "{email}".to_string(),
+                _ if Uuid::parse_str(bare).is_ok() => "{id}".to_string(),
+                _ => bare.to_string(),
+            };
+            format!("{scrubbed}{punctuation}{separator}")
+        })
+        .collect()
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '\'' | '"' | '(' | ')' | ',')
+}
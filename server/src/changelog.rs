@@ -0,0 +1,56 @@
+use types::{
+    Result,
+    changelog::{Release, WhatsNew},
+};
+
+use crate::storage::Settings;
+
+/// `(version, changes)` from `CHANGELOG.md`, newest first.
+static CHANGELOG: &[(&str, &[&str])] = include!(concat!(env!("OUT_DIR"), "/changelog.rs"));
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// The releases up to this one that admins haven't dismissed. On a fresh
+/// install, that's just this one.
+pub async fn whats_new() -> Result<Option<WhatsNew>> {
+    let last_seen: Option<String> = Settings::get(Settings::LAST_SEEN_VERSION).await?;
+    let last_seen = last_seen.as_deref().map(parse_version);
+    let current = parse_version(VERSION);
+
+    let releases: Vec<Release> = CHANGELOG
+        .iter()
+        .filter(|(version, _)| {
+            let version = parse_version(version);
+            version <= current
+                && match &last_seen {
+                    Some(seen) => version > *seen,
+                    None => version == current,
+                }
+        })
+        .map(|(version, changes)| Release {
+            version: version.to_string(),
+            changes: changes.iter().map(|c| c.to_string()).collect(),
+        })
+        .collect();
+
+    if releases.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(WhatsNew {
+        version: VERSION.to_string(),
+        releases,
+    }))
+}
+
+/// Hide the release notes until the next upgrade.
+pub async fn dismiss() -> Result<()> {
+    Settings::set(Settings::LAST_SEEN_VERSION, &VERSION).await
+}
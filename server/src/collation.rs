@@ -0,0 +1,42 @@
+use std::{cmp::Ordering, sync::LazyLock};
+
+use icu_collator::{
+    Collator, CollatorBorrowed, CollatorPreferences,
+    options::{CollatorOptions, Strength},
+};
+use icu_locale_core::Locale;
+use types::kanidm::{Group, Person};
+
+use crate::CONFIG;
+
+/// Compares names as people expect them sorted in the configured
+/// `sort_locale`, ignoring case.
+static COLLATOR: LazyLock<CollatorBorrowed<'static>> = LazyLock::new(|| {
+    let locale = match CONFIG.sort_locale.parse::<Locale>() {
+        Ok(locale) => locale,
+        Err(error) => {
+            tracing::warn!(?error, locale = CONFIG.sort_locale, "invalid sort locale");
+            Locale::UNKNOWN
+        }
+    };
+    let mut options = CollatorOptions::default();
+    options.strength = Some(Strength::Secondary);
+    // Locales without their own rules fall back to the root collation.
+    Collator::try_new(CollatorPreferences::from(&locale), options)
+        .expect("collation data is built in")
+});
+
+/// Compare two names for sorting.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    COLLATOR.compare(a, b)
+}
+
+/// Sort people by display name.
+pub fn sort_persons(persons: &mut [Person]) {
+    persons.sort_unstable_by(|a, b| compare(&a.display_name, &b.display_name));
+}
+
+/// Sort groups by name.
+pub fn sort_groups(groups: &mut [Group]) {
+    groups.sort_unstable_by(|a, b| compare(&a.name, &b.name));
+}
@@ -1,40 +1,404 @@
 use reqwest::Url;
-use secrecy::SecretString;
-use serde::Deserialize;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, Serializer};
 use std::env;
-use std::path::PathBuf;
-use std::sync::LazyLock;
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, OnceLock};
+use std::time::Duration;
 use tracing::Level;
-use types::provision::ProvisionToken;
+use types::{
+    break_glass::PasswordPolicy,
+    kanidm::GroupClassification,
+    profile_change::{ChangePolicy, ProfilePolicy},
+    provision::ProvisionToken,
+};
+
+use crate::{IdentityBackend, proxy::Cidr, secret_source};
 
 pub static CONFIG: LazyLock<Config> = LazyLock::new(|| Config::new().unwrap());
 
-#[derive(Debug, Deserialize)]
+/// The admin group picked in the first-run setup, when it isn't configured.
+static SETUP_ADMIN_GROUP: OnceLock<String> = OnceLock::new();
+
+pub(crate) fn use_setup_admin_group(group: String) {
+    let _ = SETUP_ADMIN_GROUP.set(group);
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
+    #[serde(serialize_with = "serialize_display")]
     pub kanidm_url: Url,
+    #[serde(default)]
+    pub kanidm_replica_urls: String,
+    #[serde(default = "default_kanidm_max_concurrency")]
+    pub kanidm_max_concurrency: usize,
+    #[serde(serialize_with = "serialize_secret")]
     pub kanidm_token: SecretString,
+    #[serde(default)]
+    pub kanidm_per_admin_tokens: bool,
+    #[serde(default)]
+    pub identity_backend: IdentityBackend,
     pub oauth_client_id: String,
+    #[serde(serialize_with = "serialize_secret")]
     pub oauth_client_secret: SecretString,
+    #[serde(serialize_with = "serialize_display")]
     pub authit_url: Url,
+    #[serde(default, serialize_with = "serialize_optional_display")]
+    pub provision_base_url: Option<Url>,
+    #[serde(default, serialize_with = "serialize_optional_display")]
+    pub public_listen_address: Option<SocketAddr>,
+    #[serde(default)]
+    pub trusted_proxies: String,
+    #[serde(default)]
+    pub insecure_cookies: bool,
+    #[serde(serialize_with = "serialize_secret")]
     pub signing_secret: SecretString,
-    pub admin_group: String,
+    #[serde(default, serialize_with = "serialize_optional_secret")]
+    pub previous_signing_secret: Option<SecretString>,
+    #[serde(default = "default_true")]
+    pub accept_legacy_tokens: bool,
+    #[serde(default)]
+    admin_group: String,
+    #[serde(default = "default_data_dir")]
     pub data_dir: PathBuf,
-    pub db_secret: SecretString,
-    #[serde(default = "default_log_level", deserialize_with = "deserialize_level")]
+    #[serde(default = "default_true")]
+    pub db_encryption: bool,
+    #[serde(default, serialize_with = "serialize_optional_secret")]
+    pub db_secret: Option<SecretString>,
+    #[serde(default)]
+    pub secret_command: Option<String>,
+    #[serde(default = "default_db_journal_mode")]
+    pub db_journal_mode: String,
+    #[serde(default = "default_db_busy_timeout_ms")]
+    pub db_busy_timeout_ms: u64,
+    #[serde(default = "default_db_max_connections")]
+    pub db_max_connections: u32,
+    #[serde(
+        default = "default_log_level",
+        deserialize_with = "deserialize_level",
+        serialize_with = "serialize_display"
+    )]
     pub log_level: Level,
+    #[serde(default = "default_session_ttl_hours")]
+    pub session_ttl_hours: u64,
+    #[serde(default = "default_remember_me_ttl_days")]
+    pub remember_me_ttl_days: u64,
+    #[serde(default = "default_step_up_max_age_minutes")]
+    pub step_up_max_age_minutes: u64,
+    #[serde(default = "default_max_request_bytes")]
+    pub max_request_bytes: usize,
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    #[serde(default)]
+    pub anonymize_on_delete: bool,
+    #[serde(default)]
+    pub break_glass_enabled: bool,
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: u32,
+    #[serde(default = "default_password_min_entropy_bits")]
+    pub password_min_entropy_bits: u32,
+    #[serde(default = "default_password_rotation_days")]
+    pub password_rotation_days: u32,
+    #[serde(default = "default_invite_reminder_hours")]
+    pub invite_reminder_hours: u64,
+    #[serde(default = "default_rotation_reminder_hours")]
+    pub rotation_reminder_hours: u64,
+    #[serde(default = "default_true")]
+    pub provision_links_enabled: bool,
+    #[serde(default)]
+    pub provision_quota_per_day: u32,
+    #[serde(default)]
+    pub provision_quota_overrides: String,
+    #[serde(default)]
+    pub account_requests_enabled: bool,
+    #[serde(default)]
+    pub profile_display_name_changes: ChangePolicy,
+    #[serde(default)]
+    pub profile_email_changes: ChangePolicy,
+    #[serde(default)]
+    pub ldap_enabled: bool,
+    #[serde(default)]
+    pub lazy_group_membership: bool,
+    #[serde(default)]
+    pub directory_enabled: bool,
+    #[serde(default = "default_true")]
+    pub directory_show_email: bool,
+    #[serde(default)]
+    pub directory_groups: String,
+    pub builtin_group_prefixes: Option<String>,
+    #[serde(default)]
+    pub builtin_groups: String,
+    #[serde(default)]
+    pub not_builtin_groups: String,
+    #[serde(default)]
+    pub key_groups: String,
+    #[serde(default)]
+    pub legal_name_group: String,
+    #[serde(default)]
+    pub verified_email_groups: String,
+    #[serde(default = "default_sort_locale")]
+    pub sort_locale: String,
+    #[serde(serialize_with = "serialize_optional_secret")]
+    pub smtp_url: Option<SecretString>,
+    pub smtp_from: Option<String>,
+    pub otlp_endpoint: Option<String>,
+    pub geoip_database: Option<PathBuf>,
+    #[cfg(feature = "test-login")]
+    #[serde(default = "default_test_login_username")]
+    pub test_login_username: String,
+    #[cfg(feature = "test-login")]
+    #[serde(default)]
+    pub test_login_groups: String,
+    #[cfg(feature = "test-login")]
+    #[serde(default)]
+    pub test_login_allow_remote: bool,
 }
 
 impl Config {
+    /// The group a user needs to be in to use AuthIt!. It's empty, so nobody
+    /// is an admin, until it's configured or picked in the first-run setup.
+    pub fn admin_group(&self) -> &str {
+        if self.admin_group.is_empty() {
+            SETUP_ADMIN_GROUP.get().map_or("", String::as_str)
+        } else {
+            &self.admin_group
+        }
+    }
+
     pub fn provision_url(&self, token: ProvisionToken) -> types::Result<Url> {
-        let url = self.authit_url.join("/provision/")?.join(token.as_str())?;
+        let base = self.provision_base_url.as_ref().unwrap_or(&self.authit_url);
+        let url = base.join("/provision/")?.join(token.as_str())?;
         Ok(url)
     }
+
+    /// How long a session lasts, depending on whether the user asked to stay
+    /// signed in.
+    pub fn session_ttl(&self, persistent: bool) -> Duration {
+        if persistent {
+            Duration::from_secs(self.remember_me_ttl_days * 24 * 3600)
+        } else {
+            Duration::from_secs(self.session_ttl_hours * 3600)
+        }
+    }
+
+    /// Kanidm replicas to send reads to, alongside the primary at
+    /// `kanidm_url`.
+    pub fn kanidm_replica_urls(&self) -> types::Result<Vec<Url>> {
+        comma_separated(&self.kanidm_replica_urls)
+            .map(|url| Ok(url.parse::<Url>()?))
+            .collect()
+    }
+
+    /// The reverse proxies whose forwarding headers are believed.
+    pub fn trusted_proxies(&self) -> types::Result<Vec<Cidr>> {
+        comma_separated(&self.trusted_proxies)
+            .map(str::parse)
+            .collect()
+    }
+
+    /// What people may change on their own profile.
+    pub fn profile_policy(&self) -> ProfilePolicy {
+        ProfilePolicy {
+            display_name: self.profile_display_name_changes,
+            email: self.profile_email_changes,
+        }
+    }
+
+    /// How many accounts can be created from `admin`'s provision links in a
+    /// day, or `None` for no limit.
+    pub fn provision_quota(&self, admin: &str) -> Option<u32> {
+        let quota = comma_separated(&self.provision_quota_overrides)
+            .filter_map(|entry| entry.split_once(':'))
+            .find(|(name, _)| name.trim() == admin)
+            .and_then(|(_, quota)| quota.trim().parse().ok())
+            .unwrap_or(self.provision_quota_per_day);
+        (quota > 0).then_some(quota)
+    }
+
+    /// The groups shown in the people directory.
+    pub fn directory_groups(&self) -> impl Iterator<Item = &str> {
+        comma_separated(&self.directory_groups)
+    }
+
+    /// The groups only people with a verified email address can be added to.
+    pub fn verified_email_groups(&self) -> impl Iterator<Item = &str> {
+        comma_separated(&self.verified_email_groups)
+    }
+
+    pub fn group_classification(&self) -> GroupClassification {
+        let list = |s: &str| comma_separated(s).map(String::from).collect();
+        let default = GroupClassification::default();
+
+        GroupClassification {
+            builtin_prefixes: self
+                .builtin_group_prefixes
+                .as_deref()
+                .map(list)
+                .unwrap_or(default.builtin_prefixes),
+            builtin_groups: list(&self.builtin_groups),
+            not_builtin_groups: list(&self.not_builtin_groups),
+            key_groups: self.key_groups(),
+        }
+    }
+
+    /// The groups to flag in the user list. The admin group always is.
+    fn key_groups(&self) -> Vec<String> {
+        let mut groups: Vec<String> = Vec::new();
+        for group in std::iter::once(self.admin_group()).chain(comma_separated(&self.key_groups)) {
+            if !group.is_empty() && !groups.iter().any(|g| g == group) {
+                groups.push(group.to_string());
+            }
+        }
+        groups
+    }
+
+    /// How recently an admin must have signed in to perform dangerous
+    /// operations.
+    pub fn step_up_max_age(&self) -> Duration {
+        Duration::from_secs(self.step_up_max_age_minutes * 60)
+    }
+
+    /// What local passwords, like the break-glass account's, must look like.
+    pub fn password_policy(&self) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: self.password_min_length,
+            min_entropy_bits: self.password_min_entropy_bits,
+            rotation_days: self.password_rotation_days,
+        }
+    }
+
+    /// Whether AuthIt! may start with test login built in: only when it's
+    /// served from this machine, unless the tests say otherwise.
+    #[cfg(feature = "test-login")]
+    pub fn test_login_allowed(&self) -> bool {
+        let local = match self.authit_url.host_str() {
+            Some("localhost") => true,
+            Some(host) => host
+                .trim_matches(['[', ']'])
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback()),
+            None => false,
+        };
+        local || self.test_login_allow_remote
+    }
+
+    /// The groups the test-login user is in, besides the admin group.
+    #[cfg(feature = "test-login")]
+    pub fn test_login_groups(&self) -> impl Iterator<Item = &str> {
+        comma_separated(&self.test_login_groups)
+    }
+
+    /// How long a database connection waits for another's lock before
+    /// giving up with "database is locked".
+    pub fn db_busy_timeout(&self) -> Duration {
+        Duration::from_millis(self.db_busy_timeout_ms)
+    }
+}
+
+fn comma_separated(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Where to keep data when `data_dir` isn't set: the directory systemd made
+/// for us, or the usual place for app data on this platform.
+pub fn default_data_dir() -> PathBuf {
+    if let Some(dirs) = env::var_os("STATE_DIRECTORY")
+        && let Some(dir) = env::split_paths(&dirs).next()
+    {
+        return dir;
+    }
+
+    let base = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    match base {
+        Some(base) => base.join("authit"),
+        None => PathBuf::from("data"),
+    }
+}
+
+fn default_kanidm_max_concurrency() -> usize {
+    8
+}
+
+fn default_sort_locale() -> String {
+    "en".to_string()
+}
+
+fn default_db_journal_mode() -> String {
+    "wal".to_string()
+}
+
+fn default_db_busy_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_db_max_connections() -> u32 {
+    10
 }
 
 fn default_log_level() -> Level {
     Level::INFO
 }
 
+fn default_session_ttl_hours() -> u64 {
+    12
+}
+
+fn default_remember_me_ttl_days() -> u64 {
+    30
+}
+
+fn default_step_up_max_age_minutes() -> u64 {
+    5
+}
+
+fn default_invite_reminder_hours() -> u64 {
+    72
+}
+
+fn default_rotation_reminder_hours() -> u64 {
+    24
+}
+
+fn default_password_min_length() -> u32 {
+    16
+}
+
+fn default_password_min_entropy_bits() -> u32 {
+    60
+}
+
+fn default_password_rotation_days() -> u32 {
+    365
+}
+
+#[cfg(feature = "test-login")]
+fn default_test_login_username() -> String {
+    "test-admin".to_string()
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_request_bytes() -> usize {
+    // Room for a maximum size avatar, which is sent as a JSON array of bytes.
+    24 * 1024 * 1024
+}
+
 fn deserialize_level<'de, D>(deserializer: D) -> Result<Level, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -43,16 +407,118 @@ where
     s.parse().map_err(serde::de::Error::custom)
 }
 
+fn serialize_display<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    serializer.collect_str(value)
+}
+
+fn serialize_optional_display<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    match value {
+        Some(v) => serializer.collect_str(v),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Secrets are serialized tagged, so [`show_config`] can find them to redact.
+#[derive(Serialize)]
+struct TaggedSecret<'a> {
+    secret: &'a str,
+}
+
+fn serialize_secret<S>(secret: &SecretString, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    TaggedSecret {
+        secret: secret.expose_secret(),
+    }
+    .serialize(serializer)
+}
+
+fn serialize_optional_secret<S>(
+    secret: &Option<SecretString>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match secret {
+        Some(secret) => serialize_secret(secret, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
 impl Config {
+    /// Load the config, from lowest to highest precedence, from:
+    ///
+    /// 1. The file at `AUTHIT_CONFIG_PATH`, or `authit.toml` if it exists.
+    /// 2. If `AUTHIT_ENV` is set, a profile next to it, like
+    ///    `authit.production.toml`.
+    /// 3. `AUTHIT_*` environment variables.
+    ///
+    /// Secrets none of these set are then looked for in files and the
+    /// external secret manager.
     fn new() -> types::Result<Self> {
-        let cfg = config::Config::builder().add_source(config::Environment::with_prefix("AUTHIT"));
+        let explicit_path = env::var("AUTHIT_CONFIG_PATH").ok();
+        let path = PathBuf::from(explicit_path.as_deref().unwrap_or("authit.toml"));
 
-        let cfg = if let Ok(path) = env::var("AUTHIT_CONFIG_PATH") {
-            cfg.add_source(config::File::with_name(&path))
-        } else {
-            cfg
-        };
+        let mut cfg = config::Config::builder().add_source(
+            config::File::with_name(&path.to_string_lossy()).required(explicit_path.is_some()),
+        );
+        if let Ok(profile) = env::var("AUTHIT_ENV") {
+            cfg = cfg.add_source(config::File::from(profile_path(&path, &profile)));
+        }
+        let cfg = cfg.add_source(config::Environment::with_prefix("AUTHIT"));
+        let cfg = secret_source::resolve(cfg.build()?)?;
 
-        Ok(cfg.build()?.try_deserialize()?)
+        Ok(cfg.try_deserialize()?)
+    }
+}
+
+/// The path of a config profile, like `authit.production.toml` for
+/// `authit.toml`.
+fn profile_path(path: &Path, profile: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy())
+        .unwrap_or("toml".into());
+    path.with_file_name(format!("{stem}.{profile}.{extension}"))
+}
+
+/// The effective config, after merging all sources, in TOML form. Secrets are
+/// hidden if `redacted` is set.
+pub fn show_config(redacted: bool) -> types::Result<String> {
+    let config = serde_json::to_value(Config::new()?)?;
+    let serde_json::Value::Object(fields) = config else {
+        return Err(types::err!("config didn't serialize to an object"));
+    };
+
+    let mut out = String::new();
+    for (key, value) in fields {
+        let value = match value {
+            serde_json::Value::Null => {
+                out.push_str(&format!("# {key} is not set\n"));
+                continue;
+            }
+            serde_json::Value::Object(secret) => match secret.get("secret") {
+                Some(_) if redacted => serde_json::Value::from("[REDACTED]"),
+                Some(secret) => secret.clone(),
+                None => serde_json::Value::Object(secret),
+            },
+            value => value,
+        };
+        out.push_str(&format!("{key} = {value}\n"));
     }
+    Ok(out)
 }
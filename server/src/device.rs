@@ -0,0 +1,101 @@
+use std::{net::IpAddr, sync::LazyLock};
+
+use axum::http::{HeaderMap, header};
+use maxminddb::{Reader, geoip2};
+
+use crate::CONFIG;
+
+/// The optional GeoIP database, like MaxMind's GeoLite2 City.
+static GEOIP: LazyLock<Option<Reader<Vec<u8>>>> = LazyLock::new(|| {
+    let path = CONFIG.geoip_database.as_ref()?;
+    match Reader::open_readfile(path) {
+        Ok(reader) => Some(reader),
+        Err(error) => {
+            tracing::warn!(?error, ?path, "failed to open GeoIP database");
+            None
+        }
+    }
+});
+
+/// Where a sign-in came from, as best we can tell.
+#[derive(Debug, Clone, Default)]
+pub struct Device {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    /// A coarse location, like "Portland, United States".
+    pub location: Option<String>,
+}
+
+impl Device {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let ip = client_ip(headers);
+
+        Self {
+            user_agent,
+            ip_address: ip.map(|ip| ip.to_string()),
+            location: ip.and_then(locate),
+        }
+    }
+}
+
+/// The client's IP, which [`crate::proxy::resolve_forwarded`] has already
+/// worked out from the reverse proxy's headers and left in
+/// `X-Forwarded-For`.
+pub(crate) fn client_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+fn locate(ip: IpAddr) -> Option<String> {
+    let city: geoip2::City = GEOIP.as_ref()?.lookup(ip).ok()?;
+    let english = |names: Option<std::collections::BTreeMap<&str, &str>>| {
+        names.and_then(|n| n.get("en").map(|s| s.to_string()))
+    };
+
+    let city_name = city.city.and_then(|c| english(c.names));
+    let country_name = city.country.and_then(|c| english(c.names));
+    match (city_name, country_name) {
+        (Some(city), Some(country)) => Some(format!("{city}, {country}")),
+        (city, country) => city.or(country),
+    }
+}
+
+/// Summarize a User-Agent, like "Firefox on Linux".
+pub fn describe_user_agent(user_agent: &str) -> Option<String> {
+    // Order matters, as most browsers claim to be several others.
+    const BROWSERS: &[(&str, &str)] = &[
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ];
+    const SYSTEMS: &[(&str, &str)] = &[
+        ("Windows", "Windows"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Android", "Android"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "ChromeOS"),
+        ("Linux", "Linux"),
+    ];
+
+    let find = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(needle, _)| user_agent.contains(needle))
+            .map(|(_, name)| *name)
+    };
+
+    match (find(BROWSERS), find(SYSTEMS)) {
+        (Some(browser), Some(system)) => Some(format!("{browser} on {system}")),
+        (browser, system) => browser.or(system).map(str::to_string),
+    }
+}
@@ -0,0 +1,45 @@
+use types::{Result, directory::DirectoryEntry, err, kanidm::Person, visibility::FieldVisibility};
+
+use crate::{CONFIG, IDENTITY, UserData, collation, visibility};
+
+/// List everyone in Kanidm, showing `viewer` only the fields they may see.
+pub async fn list(viewer: &UserData) -> Result<Vec<DirectoryEntry>> {
+    if !CONFIG.directory_enabled {
+        return Err(err!("the people directory is not enabled"));
+    }
+
+    let visibility = visibility::get().await?;
+    let mut persons = IDENTITY.list_persons().await?;
+    collation::sort_persons(&mut persons);
+
+    Ok(persons
+        .into_iter()
+        .map(|person| entry(person, &visibility, viewer))
+        .collect())
+}
+
+fn entry(person: Person, visibility: &FieldVisibility, viewer: &UserData) -> DirectoryEntry {
+    let groups = if visibility::allows(&visibility.groups, viewer) {
+        CONFIG
+            .directory_groups()
+            .filter(|group| person.is_in_group(group))
+            .map(str::to_string)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    DirectoryEntry {
+        uuid: person.uuid,
+        display_name: person.display_name,
+        legal_name: person
+            .legal_name
+            .filter(|_| visibility::allows(&visibility.legal_name, viewer)),
+        email_addresses: if visibility::allows(&visibility.email_addresses, viewer) {
+            person.email_addresses
+        } else {
+            Vec::new()
+        },
+        groups,
+    }
+}
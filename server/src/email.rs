@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox};
+use secrecy::ExposeSecret;
+use types::{
+    Result,
+    email_queue::{MAX_EMAIL_ATTEMPTS, QueuedEmail},
+    err,
+};
+use uuid::Uuid;
+
+use crate::{CONFIG, shutdown, storage::EmailQueue};
+
+/// How long an email is held by whoever is sending it. If they stop partway,
+/// it's tried again after this.
+const CLAIM_HOLD: Duration = Duration::from_secs(60);
+
+/// How many emails the Email page shows.
+const LIST_LIMIT: u32 = 200;
+
+/// Whether we have what we need to send email.
+pub fn is_configured() -> bool {
+    CONFIG.smtp_url.is_some() && CONFIG.smtp_from.is_some()
+}
+
+/// Queue a plain-text email and try sending it straight away. If that fails,
+/// it's retried with backoff, so this only fails if it can never be sent,
+/// like for a malformed address.
+pub async fn send(to: &str, subject: &str, body: String) -> Result<()> {
+    if !is_configured() {
+        return Err(err!("email is not configured"));
+    }
+    to.parse::<Mailbox>()?;
+
+    let id = EmailQueue::enqueue(to, subject, &body).await?;
+    if let Err(error) = attempt(id).await {
+        tracing::warn!(?error, %id, "failed to attempt email");
+    }
+    Ok(())
+}
+
+/// Send an email if possible, logging rather than failing if we can't. Returns
+/// whether it was sent or queued to retry.
+pub async fn try_send(to: &str, subject: &str, body: String) -> bool {
+    if !is_configured() {
+        return false;
+    }
+
+    match send(to, subject, body).await {
+        Ok(()) => true,
+        Err(error) => {
+            tracing::warn!(?error, "failed to send email");
+            false
+        }
+    }
+}
+
+/// The latest emails, newest first, or only those given up on.
+pub async fn list(failed_only: bool) -> Result<Vec<QueuedEmail>> {
+    EmailQueue::list(failed_only, LIST_LIMIT).await
+}
+
+/// Try an email that was given up on again.
+pub async fn retry(id: Uuid) -> Result<()> {
+    EmailQueue::retry(id).await?;
+    shutdown::spawn(async move {
+        if let Err(error) = attempt(id).await {
+            tracing::warn!(?error, %id, "failed to attempt email");
+        }
+    });
+    Ok(())
+}
+
+/// Retry every email that's due.
+pub(crate) async fn send_due() -> Result<()> {
+    for id in EmailQueue::list_due().await? {
+        if let Err(error) = attempt(id).await {
+            tracing::warn!(?error, %id, "failed to attempt email");
+        }
+    }
+    Ok(())
+}
+
+/// Send a queued email, if it's due and nobody else is, recording how it
+/// went.
+async fn attempt(id: Uuid) -> Result<()> {
+    let Some(email) = EmailQueue::claim(id, Timestamp::now() + CLAIM_HOLD).await? else {
+        return Ok(());
+    };
+
+    match deliver(&email.recipient, &email.subject, email.body).await {
+        Ok(()) => EmailQueue::mark_sent(id).await,
+        Err(error) => {
+            let retry_at = (email.attempts < MAX_EMAIL_ATTEMPTS)
+                .then(|| Timestamp::now() + backoff(email.attempts));
+            tracing::warn!(?error, attempts = email.attempts, "failed to send email");
+            EmailQueue::mark_failed(id, &format!("{error:#}"), retry_at).await
+        }
+    }
+}
+
+/// How long to wait after `attempts` tries: a minute, doubling each time.
+fn backoff(attempts: u32) -> Duration {
+    Duration::from_secs(60 << attempts.saturating_sub(1).min(10))
+}
+
+async fn deliver(to: &str, subject: &str, body: String) -> Result<()> {
+    let (Some(url), Some(from)) = (&CONFIG.smtp_url, &CONFIG.smtp_from) else {
+        return Err(err!("email is not configured"));
+    };
+
+    let message = Message::builder()
+        .from(from.parse::<Mailbox>()?)
+        .to(to.parse::<Mailbox>()?)
+        .subject(subject)
+        .body(body)?;
+
+    let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url.expose_secret())?.build();
+    transport.send(message).await?;
+
+    Ok(())
+}
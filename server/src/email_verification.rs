@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use jiff::Timestamp;
+use sha2::Sha256;
+use types::{
+    Result,
+    email_verification::{CODE_LENGTH, EmailAddressStatus},
+    err,
+    kanidm::{Person, Spn},
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, UserData, email,
+    signed_token::{Purpose, derive_key},
+    storage::EmailVerifications,
+};
+
+/// How long an emailed code can be used for.
+const CODE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// How many wrong codes can be entered before a new one has to be sent.
+const MAX_ATTEMPTS: i64 = 5;
+
+/// A person's email addresses, and which they've verified. Verifications of
+/// addresses they no longer have don't count.
+pub async fn statuses(person: &Person) -> Result<Vec<EmailAddressStatus>> {
+    let verified = EmailVerifications::verified(person.uuid).await?;
+    Ok(person
+        .email_addresses
+        .iter()
+        .map(|address| EmailAddressStatus {
+            address: address.clone(),
+            verified_at: verified.get(&address.to_lowercase()).copied(),
+        })
+        .collect())
+}
+
+/// Email a code to one of the signed-in user's addresses, to show it's theirs.
+pub async fn send_code(user: &UserData, address: &str) -> Result<()> {
+    let person = IDENTITY.get_person(&user.user_id).await?;
+    let address = own_address(&person, address)?;
+    if !email::is_configured() {
+        return Err(err!("email isn't set up, so addresses can't be verified"));
+    }
+
+    let code = format!(
+        "{:0width$}",
+        Uuid::new_v4().as_u128() % 10u128.pow(CODE_LENGTH as u32),
+        width = CODE_LENGTH
+    );
+    let code_hash = hash(person.uuid, address, &code)?;
+    EmailVerifications::start(
+        person.uuid,
+        address,
+        &code_hash,
+        Timestamp::now() + CODE_TTL,
+    )
+    .await?;
+
+    let body = format!(
+        "Hi {},\n\n\
+        Enter this code on your AuthIt! profile to verify {address}:\n\n\
+        {code}\n\n\
+        It works for {} minutes. If you didn't ask for it, you can ignore this email.\n",
+        person.display_name,
+        CODE_TTL.as_secs() / 60,
+    );
+    email::send(address, "Verify your email address", body).await?;
+    tracing::info!(user = person.name, "sent email verification code");
+    Ok(())
+}
+
+/// Mark one of the signed-in user's addresses verified, if `code` is the one
+/// last sent to it.
+pub async fn confirm(user: &UserData, address: &str, code: &str) -> Result<()> {
+    let person = IDENTITY.get_person(&user.user_id).await?;
+    let address = own_address(&person, address)?;
+    let Some(pending) = EmailVerifications::pending_code(person.uuid, address).await? else {
+        return Err(err!("send a code to {address} first"));
+    };
+    if pending.expires_at <= Timestamp::now() || pending.attempts >= MAX_ATTEMPTS {
+        return Err(err!("that code has expired; send a new one"));
+    }
+
+    let expected = BASE64_URL_SAFE_NO_PAD.decode(&pending.code_hash)?;
+    if mac(person.uuid, address, code.trim())?
+        .verify_slice(&expected)
+        .is_err()
+    {
+        EmailVerifications::record_attempt(person.uuid, address).await?;
+        return Err(err!("that code isn't right"));
+    }
+
+    EmailVerifications::verify(person.uuid, address).await?;
+    tracing::info!(user = person.name, "verified email address");
+    Ok(())
+}
+
+/// Refuse to add a person to any of `groups`, given by name, that need a
+/// verified email address, unless they have one.
+pub async fn check_groups<'a>(
+    user_id: Uuid,
+    groups: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let Some(group) = groups.into_iter().find(|g| is_restricted(g)) else {
+        return Ok(());
+    };
+    let person = IDENTITY.get_person(&user_id.to_string()).await?;
+    if statuses(&person).await?.iter().any(|s| s.is_verified()) {
+        return Ok(());
+    }
+    Err(err!(
+        "{} needs a verified email address to be added to '{group}'",
+        person.name
+    ))
+}
+
+/// Refuse provision links that would grant groups needing a verified email
+/// address, as the accounts they create won't have one.
+pub fn check_link_groups(groups: &[String]) -> Result<()> {
+    match groups.iter().find(|g| is_restricted(g)) {
+        Some(group) => Err(err!(
+            "provision links can't grant '{group}', as it needs a verified email address"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Refuse to create a person straight into groups needing a verified email
+/// address, as they can't have verified one yet.
+pub fn check_new_person_groups<'a>(groups: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    match groups.into_iter().find(|g| is_restricted(g)) {
+        Some(group) => Err(err!(
+            "new people can't be added to '{group}', as it needs a verified email address"
+        )),
+        None => Ok(()),
+    }
+}
+
+fn is_restricted(group: &str) -> bool {
+    let group = Spn::parse(group);
+    CONFIG
+        .verified_email_groups()
+        .any(|g| Spn::parse(g).matches(&group))
+}
+
+/// The person's own copy of `address`, so someone can't verify an address
+/// that isn't on their account.
+fn own_address<'a>(person: &'a Person, address: &str) -> Result<&'a str> {
+    person
+        .email_addresses
+        .iter()
+        .find(|a| a.eq_ignore_ascii_case(address.trim()))
+        .map(String::as_str)
+        .ok_or_else(|| err!("{address} isn't one of your email addresses"))
+}
+
+fn mac(user_id: Uuid, address: &str, code: &str) -> Result<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&derive_key(Purpose::EmailVerification))?;
+    mac.update(user_id.as_bytes());
+    mac.update(address.to_lowercase().as_bytes());
+    mac.update(code.as_bytes());
+    Ok(mac)
+}
+
+fn hash(user_id: Uuid, address: &str, code: &str) -> Result<String> {
+    let hash = mac(user_id, address, code)?.finalize().into_bytes();
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(hash))
+}
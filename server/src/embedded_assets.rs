@@ -0,0 +1,32 @@
+use std::{fs, path::PathBuf};
+
+use types::Result;
+
+use crate::CONFIG;
+
+/// The web assets built into the binary as `(path, contents)`, if it was
+/// built with `AUTHIT_ASSETS_DIR`.
+static ASSETS: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+/// Where Dioxus should serve the web assets from, if they're built into the
+/// binary and there's no `public` directory beside it to use instead. Dioxus
+/// serves them from disk, so they're written to `public` in `data_dir`.
+pub fn unpack() -> Result<Option<PathBuf>> {
+    if ASSETS.is_empty() || std::env::current_exe()?.with_file_name("public").is_dir() {
+        return Ok(None);
+    }
+
+    let dir = CONFIG.data_dir.join("public");
+    for (name, contents) in ASSETS {
+        let path = dir.join(name);
+        // Only write what an upgrade changed.
+        if fs::read(&path).is_ok_and(|existing| existing == *contents) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+    }
+    Ok(Some(dir))
+}
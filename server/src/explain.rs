@@ -0,0 +1,107 @@
+use std::{cell::RefCell, time::Duration};
+
+use axum::{
+    Router,
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+};
+use reqwest::{Method, Url};
+use serde_json::Value;
+use types::{Result, activity::KanidmCall};
+use uuid::Uuid;
+
+use crate::storage::ActivityKanidmCalls;
+
+/// Payloads longer than this are cut short, so listing every person doesn't
+/// store the whole directory.
+const MAX_PAYLOAD_CHARS: usize = 16 * 1024;
+
+/// Keys whose values are replaced, wherever they appear in a payload.
+const SECRET_KEYS: [&str; 5] = ["password", "token", "secret", "credential", "totp"];
+
+tokio::task_local! {
+    static CALLS: RefCell<Vec<KanidmCall>>;
+}
+
+/// Keep the Kanidm calls each request makes, so the activity it records can
+/// explain them.
+pub fn record_kanidm_calls(router: Router) -> Router {
+    router.layer(middleware::from_fn(scope))
+}
+
+async fn scope(request: Request, next: Next) -> Response {
+    CALLS.scope(RefCell::default(), next.run(request)).await
+}
+
+/// Whether calls are being kept, so the client can skip the work of
+/// recording them when they aren't.
+pub(crate) fn is_recording() -> bool {
+    CALLS.try_with(|_| ()).is_ok()
+}
+
+/// Keep a call made to Kanidm, if it's for a request.
+pub(crate) fn record(
+    method: &Method,
+    url: &Url,
+    duration: Duration,
+    request: Option<&Value>,
+    response: std::result::Result<&Value, &types::Error>,
+) {
+    let call = KanidmCall {
+        method: method.to_string(),
+        path: url.path().to_string(),
+        request: request.map(payload),
+        response: response.ok().map(payload),
+        error: response.err().map(|e| format!("{e:#}")),
+        duration_ms: duration.as_millis() as u64,
+    };
+    let _ = CALLS.try_with(|calls| calls.borrow_mut().push(call));
+}
+
+/// The calls made since the last time this was called. Each activity takes
+/// the calls made before it, so a request that records several splits its
+/// calls between them.
+pub(crate) fn take() -> Vec<KanidmCall> {
+    CALLS.try_with(|calls| calls.take()).unwrap_or_default()
+}
+
+/// The Kanidm calls made for an activity, in the order they were made.
+/// Activities from before this was recorded, or that made no calls, have
+/// none.
+pub async fn explain(activity_id: Uuid) -> Result<Vec<KanidmCall>> {
+    ActivityKanidmCalls::get(activity_id).await
+}
+
+/// A payload as pretty JSON, with its secrets replaced.
+fn payload(value: &Value) -> String {
+    let mut value = value.clone();
+    redact(&mut value);
+    let mut text = serde_json::to_string_pretty(&value).unwrap_or_default();
+    if text.len() > MAX_PAYLOAD_CHARS {
+        let mut end = MAX_PAYLOAD_CHARS;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n…");
+    }
+    text
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+                    *value = Value::String("[redacted]".to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
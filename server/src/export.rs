@@ -0,0 +1,54 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use jiff::Timestamp;
+use types::{Result, export::UserExport};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, KANIDM_CLIENT,
+    storage::{
+        AccountRequests, Avatars, Offboardings, Onboardings, PhoneNumbers, Preferences, Session,
+    },
+};
+
+/// Gather everything we hold about a person.
+pub async fn export_user(user_id: Uuid, exported_by: &str) -> Result<UserExport> {
+    let person = IDENTITY.get_person(&user_id.to_string()).await?;
+    let kanidm_attributes = KANIDM_CLIENT.get_person_attributes(&user_id).await?;
+
+    let sessions = Session::list_for_user(&user_id.to_string())
+        .await?
+        .iter()
+        .map(|s| s.info(false))
+        .collect();
+    let offboardings = Offboardings::list()
+        .await?
+        .into_iter()
+        .filter(|o| o.user_id == user_id)
+        .collect();
+    let account_requests = AccountRequests::list()
+        .await?
+        .into_iter()
+        .filter(|r| {
+            person
+                .email_addresses
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(&r.email))
+        })
+        .collect();
+
+    Ok(UserExport {
+        user_id,
+        exported_at: Timestamp::now(),
+        exported_by: exported_by.to_string(),
+        kanidm_attributes,
+        phone_number: PhoneNumbers::get(user_id).await?,
+        avatar: Avatars::get(user_id)
+            .await?
+            .map(|a| BASE64_STANDARD.encode(a.image)),
+        preferences: Preferences::load(&user_id.to_string()).await?,
+        sessions,
+        onboardings: Onboardings::list_for_user(user_id).await?,
+        offboardings,
+        account_requests,
+    })
+}
@@ -0,0 +1,236 @@
+use std::sync::{
+    LazyLock,
+    atomic::{AtomicBool, Ordering},
+};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use types::{
+    Result, err,
+    group_webhook::MembershipAction,
+    ids::GroupName,
+    kanidm::{Group, Spn},
+    setup::SetupCheck,
+    validation::{MAX_GROUP_NAME_LENGTH, check_length},
+};
+
+use crate::{
+    CONFIG, IDENTITY, KANIDM_CLIENT, collation, config::use_setup_admin_group, membership, setup,
+    storage::Settings,
+};
+
+/// Proves whoever's running the setup can read AuthIt!'s logs, so a stranger
+/// can't pick the admin group before the operator does.
+static SETUP_CODE: LazyLock<String> = LazyLock::new(|| {
+    let mut bytes = [0u8; 6];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+});
+
+/// Set at startup when the admin group is missing from Kanidm or has nobody
+/// in it, so nobody could sign in to finish setting it up.
+static BOOTSTRAP_NEEDED: AtomicBool = AtomicBool::new(false);
+
+/// Use the admin group picked in an earlier setup, or, if there isn't one or
+/// nobody is in it, log the code needed to run the setup. Call this once, at
+/// startup.
+pub async fn init() -> Result<()> {
+    if CONFIG.admin_group().is_empty()
+        && let Some(group) = Settings::get::<String>(Settings::ADMIN_GROUP).await?
+    {
+        use_setup_admin_group(group);
+    }
+    let admin_group = CONFIG.admin_group();
+    if !admin_group.is_empty() {
+        match has_members(admin_group).await {
+            Ok(true) => return Ok(()),
+            Ok(false) => {
+                tracing::warn!(
+                    admin_group,
+                    "nobody is in the admin group, so nobody can sign in"
+                );
+                BOOTSTRAP_NEEDED.store(true, Ordering::Relaxed);
+            }
+            Err(error) => {
+                tracing::warn!(%error, admin_group, "couldn't check the admin group in Kanidm");
+                return Ok(());
+            }
+        }
+    }
+
+    let url = CONFIG.authit_url.join("/setup")?;
+    tracing::warn!(
+        code = SETUP_CODE.as_str(),
+        "AuthIt! isn't set up yet. Finish setting it up at {url} with this code."
+    );
+    Ok(())
+}
+
+/// Whether AuthIt! still needs an admin group with someone in it.
+pub fn is_needed() -> bool {
+    CONFIG.admin_group().is_empty() || BOOTSTRAP_NEEDED.load(Ordering::Relaxed)
+}
+
+/// The admin group, if Kanidm has it.
+async fn find_admin_group(name: &str) -> Result<Option<Group>> {
+    let spn = Spn::parse(name);
+    Ok(IDENTITY
+        .list_groups()
+        .await?
+        .into_iter()
+        .find(|g| g.spn().matches(&spn)))
+}
+
+/// Whether the admin group exists and someone is in it.
+async fn has_members(name: &str) -> Result<bool> {
+    match find_admin_group(name).await? {
+        Some(group) => Ok(!IDENTITY.list_group_members(&group.id()).await?.is_empty()),
+        None => Ok(false),
+    }
+}
+
+fn check_code(code: &str) -> Result<()> {
+    if !is_needed() {
+        return Err(err!("AuthIt! is already set up"));
+    }
+    // Compare hashes, so how long this takes says nothing about the code.
+    if Sha256::digest(code.trim()) != Sha256::digest(SETUP_CODE.as_bytes()) {
+        return Err(err!("that isn't the setup code"));
+    }
+    Ok(())
+}
+
+/// Check that AuthIt! can reach Kanidm, and that its service account can do
+/// what AuthIt! needs.
+pub async fn check(code: &str) -> Result<Vec<SetupCheck>> {
+    check_code(code)?;
+
+    let mut checks = vec![SetupCheck {
+        label: format!("Connect to Kanidm at {}", CONFIG.kanidm_url),
+        error: KANIDM_CLIENT
+            .get_domain()
+            .await
+            .err()
+            .map(|e| e.to_string()),
+    }];
+    match KANIDM_CLIENT.own_groups().await {
+        Ok(groups) => checks.extend(setup::SERVICE_ACCOUNT_GROUPS.iter().map(|group| {
+            let group_spn = Spn::parse(group);
+            let member = groups.iter().any(|g| Spn::parse(g).matches(&group_spn));
+            SetupCheck {
+                label: format!("Service account is in {group}"),
+                error: (!member).then(|| format!("add the service account to {group}")),
+            }
+        })),
+        Err(error) => checks.push(SetupCheck {
+            label: "Read the service account".to_string(),
+            error: Some(error.to_string()),
+        }),
+    }
+    Ok(checks)
+}
+
+/// The groups that could be the admin group.
+pub async fn candidate_groups(code: &str) -> Result<Vec<String>> {
+    check_code(code)?;
+
+    let classification = CONFIG.group_classification();
+    let mut names: Vec<String> = IDENTITY
+        .list_groups()
+        .await?
+        .into_iter()
+        .map(|g| g.name)
+        .filter(|name| !classification.is_builtin(name))
+        .collect();
+    names.sort_unstable_by(|a, b| collation::compare(a, b));
+    Ok(names)
+}
+
+/// The admin group the setup has to fill, when it's already chosen, in the
+/// config or an earlier setup, but nobody is in it.
+pub fn chosen_group(code: &str) -> Result<Option<String>> {
+    check_code(code)?;
+    Ok(Some(CONFIG.admin_group().to_string()).filter(|g| !g.is_empty()))
+}
+
+/// Finish the setup, making `admin_group` the group that can use AuthIt!.
+///
+/// With `create_missing`, the group is created if Kanidm doesn't have it.
+/// `bootstrap_user` is added to it, so someone can sign in; it can only be
+/// left out when the group already has members.
+pub async fn complete(
+    code: &str,
+    admin_group: &str,
+    create_missing: bool,
+    bootstrap_user: Option<&str>,
+) -> Result<()> {
+    check_code(code)?;
+
+    let admin_group = admin_group.trim();
+    if admin_group.is_empty() {
+        return Err(err!("choose an admin group"));
+    }
+    let chosen = CONFIG.admin_group();
+    if !chosen.is_empty() && !Spn::parse(chosen).matches(&Spn::parse(admin_group)) {
+        return Err(err!("the admin group is already set to '{chosen}'"));
+    }
+    let bootstrap_user = bootstrap_user.map(str::trim).filter(|u| !u.is_empty());
+    // Look the person up first, so a typo doesn't leave an empty group.
+    let person = match bootstrap_user {
+        Some(user) => Some(IDENTITY.get_person(user).await?),
+        None => None,
+    };
+
+    let group = match find_admin_group(admin_group).await? {
+        Some(group) => group,
+        None if create_missing => {
+            check_length("group name", admin_group, MAX_GROUP_NAME_LENGTH)?;
+            if CONFIG.group_classification().is_builtin(admin_group) {
+                return Err(err!("'{admin_group}' would be a built-in group name"));
+            }
+            if person.is_none() {
+                return Err(err!(
+                    "name someone to add to '{admin_group}', so they can sign in"
+                ));
+            }
+            let name = GroupName::from(admin_group);
+            IDENTITY
+                .create_group(&name, Some("People who can use AuthIt!"))
+                .await?;
+            tracing::info!(admin_group, "created the admin group");
+            IDENTITY.find_group(&name).await?
+        }
+        None => return Err(err!("there's no group called '{admin_group}'")),
+    };
+
+    match person {
+        Some(person) if !person.is_member_of(&group) => {
+            IDENTITY
+                .add_user_to_group(&group.id(), &person.id())
+                .await?;
+            membership::changed(&group, person.id(), MembershipAction::Added).await;
+            tracing::info!(
+                admin_group = group.name,
+                user = person.name,
+                "added to the admin group"
+            );
+        }
+        Some(_) => {}
+        None => {
+            if IDENTITY.list_group_members(&group.id()).await?.is_empty() {
+                return Err(err!(
+                    "nobody is in '{}' yet, so name someone to add to it",
+                    group.name
+                ));
+            }
+        }
+    }
+
+    if chosen.is_empty() {
+        Settings::set(Settings::ADMIN_GROUP, &group.name).await?;
+        use_setup_admin_group(group.name.clone());
+    }
+    BOOTSTRAP_NEEDED.store(false, Ordering::Relaxed);
+    tracing::info!(admin_group = group.name, "first-run setup complete");
+    Ok(())
+}
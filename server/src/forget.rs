@@ -0,0 +1,99 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use types::{Result, kanidm::Person};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, avatar,
+    signed_token::{Purpose, derive_key},
+    storage::{
+        AccountRequests, Activities, ActivityKanidmCalls, ApiUsages, BackgroundJobRuns, EmailQueue,
+        EmailVerifications, GroupOwners, Jobs, KanidmEventSyncs, LoginEvents, Offboardings,
+        Onboardings, Outbox, PhoneNumbers, Preferences, ProfileChanges, ProvisionApprovals,
+        ProvisionedAccounts, ResetWatches, RotationCampaigns, ScheduledMembershipChanges, Session,
+        ShiftNotes, Tags, WebhookDeliveries,
+    },
+};
+
+/// Clean up what we hold about a person once they're deleted from Kanidm.
+///
+/// With `anonymize_on_delete`, records that mention them are kept so they
+/// still count towards totals, but their name is replaced by a pseudonym.
+pub async fn forget_user(person: &Person) -> Result<()> {
+    forget_user_id(person.uuid).await?;
+
+    if !CONFIG.anonymize_on_delete {
+        return Ok(());
+    }
+
+    let user_id = person.uuid.to_string();
+    Session::delete_for_user(&user_id).await?;
+    Preferences::delete(&user_id).await?;
+    ApiUsages::delete_for_user(&user_id).await?;
+    LoginEvents::delete_for_user(&user_id).await?;
+    Activities::redact_for_user(person.uuid).await?;
+    ActivityKanidmCalls::delete_redacted().await?;
+
+    let alias = pseudonym(&person.name)?;
+    Offboardings::rename_person(&person.name, &alias).await?;
+    for mut offboarding in Offboardings::list().await? {
+        let mut changed = false;
+        for step in &mut offboarding.steps {
+            if step.done_by.as_deref() == Some(person.name.as_str()) {
+                step.done_by = Some(alias.clone());
+                changed = true;
+            }
+        }
+        if changed {
+            Offboardings::update(&offboarding).await?;
+        }
+    }
+    AccountRequests::rename_decider(&person.name, &alias).await?;
+    ProfileChanges::rename_decider(&person.name, &alias).await?;
+    ProvisionApprovals::rename_admin(&person.name, &alias).await?;
+    for email in &person.email_addresses {
+        let alias = pseudonym(email)?;
+        AccountRequests::anonymize_requester(email, &alias).await?;
+        Outbox::anonymize_recipient(email, &alias).await?;
+        EmailQueue::forget_recipient(email, &alias).await?;
+    }
+    BackgroundJobRuns::rename_starter(&person.name, &alias).await?;
+    Jobs::rename_starter(&person.name, &alias).await?;
+    ProvisionedAccounts::rename_creator(&person.name, &alias).await?;
+    RotationCampaigns::rename_starter(&person.name, &alias).await?;
+    ScheduledMembershipChanges::rename_creator(&person.name, &alias).await?;
+    ShiftNotes::rename_author(&person.name, &alias).await?;
+
+    tracing::info!(user = alias, "anonymized deleted user");
+    Ok(())
+}
+
+/// Delete the records that are only about a person, for when all we know of
+/// them is their id.
+pub async fn forget_user_id(user_id: Uuid) -> Result<()> {
+    Onboardings::delete_for_user(user_id).await?;
+    EmailVerifications::delete_for_user(user_id).await?;
+    PhoneNumbers::delete(user_id).await?;
+    ProfileChanges::delete_for_user(user_id).await?;
+    ProvisionApprovals::delete_for_user(user_id).await?;
+    ScheduledMembershipChanges::delete_for_user(user_id).await?;
+    ResetWatches::delete(user_id).await?;
+    KanidmEventSyncs::delete(user_id).await?;
+    RotationCampaigns::delete_for_user(user_id).await?;
+    Tags::delete_for_user(user_id).await?;
+    GroupOwners::delete_for_owner(user_id).await?;
+    WebhookDeliveries::delete_delivered_for_user(user_id).await?;
+    avatar::delete(user_id).await
+}
+
+/// A stand-in for `value` that's the same every time, so records about one
+/// person can still be counted together without saying who they were.
+fn pseudonym(value: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&derive_key(Purpose::Pseudonym))?;
+    mac.update(value.to_lowercase().as_bytes());
+    let hash: String = mac.finalize().into_bytes()[..6]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(format!("deleted-{hash}"))
+}
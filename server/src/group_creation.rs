@@ -0,0 +1,50 @@
+use types::{
+    Result, err,
+    ids::{GroupId, GroupName, PersonId},
+    kanidm::Group,
+    validation::{MAX_GROUP_NAME_LENGTH, MAX_NOTES_LENGTH, check_length},
+};
+
+use crate::{CONFIG, IDENTITY};
+
+/// Create a group and add `user_id` to it, returning the group. With a
+/// `template`, the group also starts with that group's direct members.
+pub async fn create_with_member(
+    name: &str,
+    description: Option<&str>,
+    template: Option<GroupId>,
+    user_id: PersonId,
+) -> Result<Group> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(err!("group name can't be empty"));
+    }
+    check_length("group name", name, MAX_GROUP_NAME_LENGTH)?;
+    let description = description.map(str::trim).filter(|d| !d.is_empty());
+    if let Some(description) = description {
+        check_length("description", description, MAX_NOTES_LENGTH)?;
+    }
+    if CONFIG.group_classification().is_builtin(name) {
+        return Err(err!("'{name}' would be a built-in group name"));
+    }
+
+    let existing = IDENTITY.list_groups().await?;
+    if existing.iter().any(|g| g.name.eq_ignore_ascii_case(name)) {
+        return Err(err!("there's already a group called '{name}'"));
+    }
+    // Read the template's members first, so a bad template creates nothing.
+    let members = match template {
+        Some(template) => IDENTITY.list_group_members(&template).await?,
+        None => Vec::new(),
+    };
+
+    let name = GroupName::from(name);
+    IDENTITY.create_group(&name, description).await?;
+    let group = IDENTITY.find_group(&name).await?;
+    if !members.is_empty() {
+        IDENTITY.add_group_members(&group.id(), &members).await?;
+    }
+    IDENTITY.add_user_to_group(&group.id(), &user_id).await?;
+
+    Ok(group)
+}
@@ -0,0 +1,83 @@
+use types::{Result, err, group_deletion::GroupDeletionImpact, ids::GroupId};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, ProvisionLink,
+    setup::SERVICE_ACCOUNT_GROUPS,
+    storage::{GroupOwners, GroupReviews, GroupWebhooks, Onboardings, ScheduledMembershipChanges},
+};
+
+/// Work out what deleting a group would affect.
+pub async fn impact(group_id: Uuid) -> Result<GroupDeletionImpact> {
+    let group = IDENTITY.get_group(&GroupId(group_id)).await?;
+    let member_count = IDENTITY.list_group_members(&GroupId(group_id)).await?.len() as u32;
+
+    let blocked = if group.name == CONFIG.admin_group() {
+        Some("It's AuthIt!'s admin group. Deleting it would lock every admin out.".to_string())
+    } else if SERVICE_ACCOUNT_GROUPS.contains(&group.name.as_str()) {
+        Some("AuthIt!'s service account needs it to manage Kanidm.".to_string())
+    } else if CONFIG.group_classification().is_builtin(&group.name) {
+        Some("It's one of Kanidm's built-in groups.".to_string())
+    } else {
+        None
+    };
+
+    let mut references = Vec::new();
+    let links = ProvisionLink::list_unexpired()
+        .await?
+        .into_iter()
+        .filter(|link| !link.is_exhausted() && link.groups().contains(&group.name))
+        .count();
+    if links > 0 {
+        references.push(format!(
+            "{links} provision link{} will add people to it.",
+            if links == 1 { "" } else { "s" }
+        ));
+    }
+    let onboardings = Onboardings::list_incomplete()
+        .await?
+        .into_iter()
+        .filter(|o| o.groups_added_at.is_none() && o.required_groups.contains(&group.name))
+        .count();
+    if onboardings > 0 {
+        references.push(format!(
+            "{onboardings} onboarding{} waiting for someone to join it.",
+            if onboardings == 1 { " is" } else { "s are" }
+        ));
+    }
+
+    Ok(GroupDeletionImpact {
+        group,
+        member_count,
+        references,
+        blocked,
+    })
+}
+
+/// Delete a group, unless AuthIt! can't do without it. Groups AuthIt! still
+/// refers to are only deleted if `force` is set.
+pub async fn delete(group_id: Uuid, force: bool) -> Result<GroupDeletionImpact> {
+    let impact = impact(group_id).await?;
+    if let Some(reason) = &impact.blocked {
+        return Err(err!("can't delete '{}': {reason}", impact.group.name));
+    }
+    if impact.needs_force() && !force {
+        return Err(err!(
+            "'{}' is still used by AuthIt!: {}",
+            impact.group.name,
+            impact.references.join(" ")
+        ));
+    }
+
+    IDENTITY.delete_group(&GroupId(group_id)).await?;
+    forget_group(group_id).await?;
+    Ok(impact)
+}
+
+/// Clean up what we hold about a group once it's deleted from Kanidm.
+pub async fn forget_group(group_id: Uuid) -> Result<()> {
+    GroupOwners::delete_for_group(group_id).await?;
+    GroupWebhooks::delete(group_id).await?;
+    GroupReviews::delete(group_id).await?;
+    ScheduledMembershipChanges::cancel_for_group(group_id, "The group was deleted").await
+}
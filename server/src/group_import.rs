@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use types::{
+    Result, err,
+    group_import::{GroupImport, GroupImportChange},
+    ids::{GroupId, PersonId},
+    kanidm::Spn,
+};
+
+use crate::{CONFIG, IDENTITY};
+
+/// Parse a CSV of `group,username` rows into each group's members.
+///
+/// An optional `group,username` header and blank lines are skipped. A row with
+/// no username lists a group without adding anyone to it.
+fn parse(csv: &str) -> Result<BTreeMap<String, BTreeSet<String>>> {
+    let mut groups: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+    for (i, line) in csv.lines().enumerate() {
+        let fields: Vec<&str> = line
+            .split(',')
+            .map(|f| f.trim().trim_matches('"').trim())
+            .collect();
+
+        match fields.as_slice() {
+            [""] => continue,
+            [group, _] if i == 0 && group.eq_ignore_ascii_case("group") => continue,
+            [group] | [group, ""] => {
+                check_group(i, group)?;
+                groups.entry(group.to_string()).or_default();
+            }
+            [group, username] => {
+                check_group(i, group)?;
+                groups
+                    .entry(group.to_string())
+                    .or_default()
+                    .insert(username.to_string());
+            }
+            _ => {
+                return Err(err!(
+                    "line {}: expected `group,username`, got {} columns",
+                    i + 1,
+                    fields.len()
+                ));
+            }
+        }
+    }
+
+    Ok(groups)
+}
+
+fn check_group(i: usize, group: &str) -> Result<()> {
+    if group.is_empty() {
+        return Err(err!("line {}: missing group name", i + 1));
+    }
+    if CONFIG.group_classification().is_builtin(group) {
+        return Err(err!(
+            "line {}: can't import built-in group '{group}'",
+            i + 1
+        ));
+    }
+    Ok(())
+}
+
+/// A group import, worked out but not yet applied.
+pub struct ImportPlan {
+    pub import: GroupImport,
+    /// The groups that already exist, by name.
+    pub group_ids: HashMap<String, GroupId>,
+    /// The people in Kanidm, by username.
+    pub person_ids: HashMap<String, PersonId>,
+}
+
+/// Work out how to make each group in the CSV have exactly the listed people
+/// as members.
+///
+/// Only people are reconciled; nested groups and service accounts are left
+/// alone.
+pub async fn plan(csv: &str) -> Result<ImportPlan> {
+    let wanted = parse(csv)?;
+    if wanted.is_empty() {
+        return Err(err!("the CSV doesn't list any groups"));
+    }
+
+    let person_ids: HashMap<String, PersonId> = IDENTITY
+        .list_persons()
+        .await?
+        .into_iter()
+        .map(|p| (p.name.clone(), p.id()))
+        .collect();
+    let group_ids: HashMap<String, GroupId> = IDENTITY
+        .list_groups()
+        .await?
+        .into_iter()
+        .map(|g| (g.name.clone(), g.id()))
+        .collect();
+
+    let mut import = GroupImport {
+        unknown_users: wanted
+            .values()
+            .flatten()
+            .filter(|u| !person_ids.contains_key(u.as_str()))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+
+    for (group, members) in &wanted {
+        let current: BTreeSet<String> = match group_ids.get(group) {
+            Some(id) => IDENTITY
+                .list_group_members(id)
+                .await?
+                .into_iter()
+                // Members are listed by SPN, like `name@domain`.
+                .map(|spn| Spn::parse(&spn).name.to_string())
+                .filter(|name| person_ids.contains_key(name.as_str()))
+                .collect(),
+            None => BTreeSet::new(),
+        };
+
+        let change = GroupImportChange {
+            group: group.clone(),
+            create: !group_ids.contains_key(group),
+            add: members
+                .iter()
+                .filter(|m| person_ids.contains_key(m.as_str()) && !current.contains(*m))
+                .cloned()
+                .collect(),
+            remove: current.difference(members).cloned().collect(),
+        };
+        if !change.is_empty() {
+            import.changes.push(change);
+        }
+    }
+
+    Ok(ImportPlan {
+        import,
+        group_ids,
+        person_ids,
+    })
+}
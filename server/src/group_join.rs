@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use axum::{
+    Router,
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+};
+use jiff::Timestamp;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use types::{
+    Result, err, group_webhook::MembershipAction, ids::GroupId, notification::NotificationEvent,
+    provision::EmailRestriction,
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, KANIDM_CLIENT, UserData,
+    action_link::page,
+    email_verification, group_owner, group_review, kanidm, membership,
+    notify::{self, Notification, escape_html},
+    session_token,
+    signed_token::{Purpose, SignedToken},
+    storage::Session,
+};
+
+/// The longest a join link can last.
+pub const MAX_JOIN_HOURS: u32 = 7 * 24;
+
+/// What a join link is signed over.
+#[derive(Debug, Serialize, Deserialize)]
+struct JoinToken {
+    group_id: Uuid,
+    /// Shown before signing in, so it's kept in the link rather than looked
+    /// up for whoever has it.
+    group_name: String,
+    /// Who may join, by their email addresses in Kanidm.
+    restriction: EmailRestriction,
+    /// The admin who made the link, who must still be one when it's used.
+    created_by: String,
+}
+
+fn decode(token: &str) -> Result<SignedToken<JoinToken>> {
+    SignedToken::decode(token, Purpose::GroupJoin)
+}
+
+/// A link that adds whoever signs in and follows it to `group_id`, within
+/// `duration_hours`. Like a provision link, but for people who already have
+/// an account.
+pub async fn create(
+    group_id: Uuid,
+    duration_hours: u32,
+    restriction: EmailRestriction,
+    admin: &UserData,
+) -> Result<Url> {
+    if duration_hours == 0 || duration_hours > MAX_JOIN_HOURS {
+        return Err(err!(
+            "join links must last between 1 and {MAX_JOIN_HOURS} hours"
+        ));
+    }
+    let restriction = restriction.normalized()?;
+
+    let group = IDENTITY.get_group(&GroupId(group_id)).await?;
+    if let Some(reason) = group_owner::not_ownable(&group) {
+        return Err(err!("nobody can join '{}' by link: {reason}", group.name));
+    }
+
+    let token = SignedToken::new(
+        Purpose::GroupJoin,
+        JoinToken {
+            group_id,
+            group_name: group.name.clone(),
+            restriction,
+            created_by: admin.username.clone(),
+        },
+        Some(Timestamp::now() + Duration::from_secs(u64::from(duration_hours) * 3600)),
+    );
+    tracing::info!(
+        group = group.name,
+        duration_hours,
+        admin = admin.username,
+        "created join link"
+    );
+    Ok(CONFIG.authit_url.join("/join/")?.join(&token.encode()?)?)
+}
+
+pub fn router() -> Router {
+    Router::new().route("/join/{token}", get(confirm).post(perform))
+}
+
+/// Ask before joining. The session cookie isn't sent when the link is
+/// followed from another site, like a mail client, so joining happens on the
+/// form's POST from this page.
+async fn confirm(Path(token): Path<String>) -> Response {
+    match decode(&token) {
+        Ok(token) => {
+            let group = escape_html(&token.data.group_name);
+            page(
+                StatusCode::OK,
+                &format!("Join {group}"),
+                &format!(
+                    r#"<form method="post"><button type="submit">Join {group}</button></form>"#
+                ),
+            )
+        }
+        Err(error) => page(
+            StatusCode::BAD_REQUEST,
+            "Link not valid",
+            &escape_html(&error.to_string()),
+        ),
+    }
+}
+
+async fn perform(Path(token): Path<String>, headers: HeaderMap) -> Response {
+    if let Err(error) = decode(&token) {
+        return page(
+            StatusCode::BAD_REQUEST,
+            "Link not valid",
+            &escape_html(&error.to_string()),
+        );
+    }
+    // Tokens are URL-safe, so the path needs no escaping.
+    let Some(user) = signed_in(&headers).await else {
+        return Redirect::to(&format!("/auth/login?return_to=/join/{token}")).into_response();
+    };
+
+    // Joining needs more access than the user has, so it's done as us.
+    match kanidm::as_service(join(&token, &user)).await {
+        Ok(group) => page(
+            StatusCode::OK,
+            "Done",
+            &format!("You're now in {}.", escape_html(&group)),
+        ),
+        Err(error) => page(
+            StatusCode::BAD_REQUEST,
+            "Couldn't join",
+            &escape_html(&error.to_string()),
+        ),
+    }
+}
+
+/// The signed-in user, if their session is still good with Kanidm.
+async fn signed_in(headers: &HeaderMap) -> Option<UserData> {
+    let session = Session::find_token(session_token(headers)?).await.ok()?;
+    if session.user_data.is_local() {
+        return Some(session.user_data);
+    }
+    KANIDM_CLIENT
+        .verify_access_token(&session.user_data.access_token)
+        .await
+        .ok()?;
+    Some(session.user_data)
+}
+
+/// Add `user` to the link's group, returning its name.
+async fn join(token: &str, user: &UserData) -> Result<String> {
+    let token = decode(token)?.data;
+    if user.is_local() {
+        return Err(err!("this session has no Kanidm account to add"));
+    }
+
+    // The admin may have lost admin access since making the link.
+    let creator = IDENTITY.get_person(&token.created_by).await?;
+    if !creator.is_in_group(CONFIG.admin_group()) {
+        return Err(err!("{} is no longer an admin", token.created_by));
+    }
+
+    let group = IDENTITY.get_group(&GroupId(token.group_id)).await?;
+    if let Some(reason) = group_owner::not_ownable(&group) {
+        return Err(err!("nobody can join '{}' by link: {reason}", group.name));
+    }
+
+    let person = IDENTITY.get_person(&user.user_id).await?;
+    if person.is_member_of(&group) {
+        return Ok(group.name);
+    }
+    if !token.restriction.is_empty()
+        && !person
+            .email_addresses
+            .iter()
+            .any(|a| token.restriction.allows(a))
+    {
+        return Err(err!(
+            "this link is only for {}",
+            token.restriction.describe()
+        ));
+    }
+
+    email_verification::check_groups(person.uuid, [group.name.as_str()]).await?;
+    group_review::check_groups([group.name.as_str()]).await?;
+    IDENTITY
+        .add_user_to_group(&group.id(), &person.id())
+        .await?;
+    membership::changed(&group, person.id(), MembershipAction::Added).await;
+    tracing::info!(
+        group = group.name,
+        user = person.name,
+        created_by = token.created_by,
+        "joined group by link"
+    );
+    notify::send(
+        Notification::new(
+            NotificationEvent::GroupMembershipChanged,
+            format!("{} joined {} with a join link.", person.name, group.name),
+        )
+        .detail("Link from", token.created_by)
+        .involving_groups([group.name.clone()])
+        .by(user)
+        .about_user(person.uuid, &person.name),
+    );
+    Ok(group.name)
+}
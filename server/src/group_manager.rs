@@ -0,0 +1,60 @@
+use types::{Result, err, ids::GroupId, notification::NotificationEvent};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, KANIDM_CLIENT, UserData,
+    notify::{self, Notification},
+};
+
+/// Who Kanidm lets manage a group, by SPN.
+///
+/// Unlike the owners in [`crate::group_owner`], this is enforced by Kanidm
+/// itself, so it holds however the group is changed.
+pub async fn get(group_id: Uuid) -> Result<Option<String>> {
+    KANIDM_CLIENT.get_entry_managed_by(&group_id).await
+}
+
+/// Let the members of `manager`, a group or service account, manage a group
+/// in Kanidm. With `None`, only Kanidm's own admins can.
+pub async fn set(group_id: Uuid, manager: Option<&str>, admin: &UserData) -> Result<()> {
+    let group = IDENTITY.get_group(&GroupId(group_id)).await?;
+    let manager = manager.map(str::trim).filter(|m| !m.is_empty());
+
+    let manager_id = match manager {
+        Some(name) => Some(
+            KANIDM_CLIENT
+                .find_manager(name)
+                .await?
+                .ok_or_else(|| err!("there's no group or service account called '{name}'"))?,
+        ),
+        None => None,
+    };
+
+    KANIDM_CLIENT
+        .set_entry_managed_by(&group_id, manager_id.as_ref())
+        .await?;
+
+    let summary = match manager {
+        Some(name) => format!(
+            "{} let {name} manage {} in Kanidm.",
+            admin.username, group.name
+        ),
+        None => format!(
+            "{} left {} to Kanidm's admins to manage.",
+            admin.username, group.name
+        ),
+    };
+    tracing::info!(
+        group = group.name,
+        manager,
+        admin = admin.username,
+        "set group manager"
+    );
+    notify::send(
+        Notification::new(NotificationEvent::GroupManagerChanged, summary)
+            .by(admin)
+            .about_group(group.name),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use reqwest::Url;
+use types::{
+    Result, err,
+    ids::GroupId,
+    kanidm::Group,
+    provision::{EmailRestriction, ProvisionFields, ProvisionFollowUp},
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, ProvisionLink, UserData, email_verification, provision_quota,
+    setup::SERVICE_ACCOUNT_GROUPS, storage::GroupOwners,
+};
+
+/// The longest an owner's invite link can last.
+pub const MAX_INVITE_HOURS: u32 = 7 * 24;
+
+/// Why nobody may own `group`, if they may not. Owning these would be a way
+/// around admin powers.
+pub(crate) fn not_ownable(group: &Group) -> Option<&'static str> {
+    if group.name == CONFIG.admin_group() {
+        Some("it's AuthIt!'s admin group")
+    } else if SERVICE_ACCOUNT_GROUPS.contains(&group.name.as_str()) {
+        Some("AuthIt!'s service account uses it")
+    } else if CONFIG.group_classification().is_builtin(&group.name) {
+        Some("it's one of Kanidm's built-in groups")
+    } else {
+        None
+    }
+}
+
+/// Make `user_id` an owner of `group_id`, or stop them being one.
+pub async fn set_owner(group_id: Uuid, user_id: Uuid, owner: bool) -> Result<()> {
+    if !owner {
+        return GroupOwners::remove(group_id, user_id).await;
+    }
+
+    let group = IDENTITY.get_group(&GroupId(group_id)).await?;
+    if let Some(reason) = not_ownable(&group) {
+        return Err(err!("nobody can own '{}': {reason}", group.name));
+    }
+    GroupOwners::add(group_id, user_id).await
+}
+
+/// The groups `user_id` owns.
+pub async fn groups_of(user_id: Uuid) -> Result<Vec<Uuid>> {
+    GroupOwners::groups_of(user_id).await
+}
+
+/// The groups the signed-in user owns, skipping any that have since been
+/// deleted or become unownable.
+pub async fn owned_groups(user: &UserData) -> Result<Vec<Group>> {
+    let Ok(user_id) = user.user_id.parse::<Uuid>() else {
+        return Ok(Vec::new());
+    };
+    let ids = GroupOwners::groups_of(user_id).await?;
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut groups: Vec<Group> = IDENTITY
+        .list_groups()
+        .await?
+        .into_iter()
+        .filter(|g| ids.contains(&g.uuid) && not_ownable(g).is_none())
+        .collect();
+    crate::collation::sort_groups(&mut groups);
+    Ok(groups)
+}
+
+/// Whether the signed-in user owns any groups.
+pub async fn is_owner(user: &UserData) -> Result<bool> {
+    let Ok(user_id) = user.user_id.parse::<Uuid>() else {
+        return Ok(false);
+    };
+    Ok(!GroupOwners::groups_of(user_id).await?.is_empty())
+}
+
+/// Make a provision link that adds people to some of the signed-in user's
+/// own groups, and nothing else.
+pub async fn create_invite(
+    user: &UserData,
+    duration_hours: u32,
+    max_uses: Option<u8>,
+    group_names: Vec<String>,
+) -> Result<Url> {
+    if group_names.is_empty() {
+        return Err(err!("pick at least one group to invite people to"));
+    }
+    if duration_hours == 0 || duration_hours > MAX_INVITE_HOURS {
+        return Err(err!(
+            "invite links can last at most {MAX_INVITE_HOURS} hours"
+        ));
+    }
+
+    let owned = owned_groups(user).await?;
+    if let Some(group) = group_names
+        .iter()
+        .find(|name| !owned.iter().any(|g| &g.name == *name))
+    {
+        return Err(err!("you don't own '{group}'"));
+    }
+    email_verification::check_link_groups(&group_names)?;
+
+    if provision_quota::is_reached(&user.username).await? {
+        return Err(err!(
+            "your provision links have already created as many accounts as allowed today"
+        ));
+    }
+
+    let link = ProvisionLink::create(
+        Duration::from_secs(u64::from(duration_hours) * 3600),
+        max_uses,
+        group_names,
+        ProvisionFields::default(),
+        None,
+        EmailRestriction::default(),
+        &user.username,
+        false,
+        ProvisionFollowUp::default(),
+    )
+    .await?;
+    tracing::info!(
+        owner = user.username,
+        groups = ?link.groups(),
+        "group owner created an invite link"
+    );
+    Ok(CONFIG.provision_url(link.as_token()?)?)
+}
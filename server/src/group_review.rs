@@ -0,0 +1,136 @@
+use jiff::{Timestamp, civil::Date, tz::TimeZone};
+use types::{
+    Result, err, group_review::GroupReview, ids::GroupId, notification::NotificationEvent,
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, UserData,
+    notify::{self, Notification},
+    storage::GroupReviews,
+};
+
+/// Today, in UTC like the dates themselves.
+fn today() -> Date {
+    Timestamp::now().to_zoned(TimeZone::UTC).date()
+}
+
+pub async fn get(group_id: Uuid) -> Result<Option<GroupReview>> {
+    GroupReviews::get(group_id).await
+}
+
+/// Set when a group should be reviewed and when it expires, and whether no
+/// one can be added to it once either passes. Clearing both dates removes
+/// them.
+pub async fn set(
+    group_id: Uuid,
+    review_by: Option<Date>,
+    expires_on: Option<Date>,
+    lock_when_overdue: bool,
+    admin: &UserData,
+) -> Result<()> {
+    if review_by.is_none() && expires_on.is_none() {
+        GroupReviews::delete(group_id).await?;
+        tracing::info!(%group_id, admin = admin.username, "removed group review dates");
+        return Ok(());
+    }
+    let group = IDENTITY.get_group(&GroupId(group_id)).await?;
+    let existing = GroupReviews::get(group_id).await?;
+
+    GroupReviews::set(&GroupReview {
+        group_id,
+        group_name: group.name.clone(),
+        review_by,
+        expires_on,
+        lock_when_overdue,
+        reviewed_at: existing.as_ref().and_then(|r| r.reviewed_at),
+        reviewed_by: existing.and_then(|r| r.reviewed_by),
+        flagged_at: None,
+        updated_by: admin.username.clone(),
+        updated_at: Timestamp::now(),
+    })
+    .await?;
+    tracing::info!(
+        group = group.name,
+        ?review_by,
+        ?expires_on,
+        admin = admin.username,
+        "set group review dates"
+    );
+    Ok(())
+}
+
+/// Record that the group was reviewed and is still needed, setting when it
+/// should next be.
+pub async fn mark_reviewed(
+    group_id: Uuid,
+    next_review_by: Option<Date>,
+    admin: &UserData,
+) -> Result<()> {
+    let Some(mut review) = GroupReviews::get(group_id).await? else {
+        return Err(err!("this group has no review date"));
+    };
+    if next_review_by.is_some_and(|d| d <= today()) {
+        return Err(err!("pick a next review date in the future"));
+    }
+
+    let now = Timestamp::now();
+    review.review_by = next_review_by;
+    review.reviewed_at = Some(now);
+    review.reviewed_by = Some(admin.username.clone());
+    review.updated_by = admin.username.clone();
+    review.updated_at = now;
+    GroupReviews::set(&review).await?;
+    tracing::info!(
+        group = review.group_name,
+        admin = admin.username,
+        "reviewed group"
+    );
+    Ok(())
+}
+
+/// Groups due for review or past their expiry, soonest first.
+pub async fn list_overdue() -> Result<Vec<GroupReview>> {
+    GroupReviews::list_due(today()).await
+}
+
+/// Refuse to add anyone to any of `groups`, given by id or name, that's
+/// overdue and locked until it's reviewed.
+pub async fn check_groups<'a>(groups: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    let today = today();
+    for group in groups {
+        let Some(review) = GroupReviews::find(group).await? else {
+            continue;
+        };
+        if let Some(reason) = review.locked(today) {
+            return Err(err!(
+                "'{}' {reason}, so no one can be added to it until it's reviewed",
+                review.group_name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Tell admins about groups that have become overdue, once each.
+pub async fn flag_overdue() -> Result<()> {
+    let today = today();
+    for review in GroupReviews::list_due(today).await? {
+        if review.flagged_at.is_some() {
+            continue;
+        }
+        let Some(reason) = review.overdue(today) else {
+            continue;
+        };
+        GroupReviews::mark_flagged(review.group_id, Timestamp::now()).await?;
+        let mut notification = Notification::new(
+            NotificationEvent::GroupReviewDue,
+            format!("{} {reason}.", review.group_name),
+        );
+        if review.lock_when_overdue {
+            notification = notification.detail("Locked", "No one can be added until it's reviewed");
+        }
+        notify::send(notification.about_group(review.group_name));
+    }
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::{sync::LazyLock, time::Duration};
+
+use hmac::{Hmac, Mac};
+use jiff::Timestamp;
+use reqwest::{Client, Url};
+use serde_json::json;
+use sha2::Sha256;
+use types::{
+    Result, err,
+    group_webhook::{GroupWebhook, MAX_WEBHOOK_ATTEMPTS, MembershipAction, WebhookDelivery},
+    ids::{GroupId, PersonId},
+    server_info::ServerInfo,
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, UserData, shutdown,
+    storage::{GroupWebhooks, WebhookDeliveries},
+    telemetry::TraceContextExt,
+};
+
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// How long a downstream system has to answer.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a delivery is held by whoever is attempting it. If they stop
+/// partway, it's tried again after this.
+const CLAIM_HOLD: Duration = Duration::from_secs(60);
+
+/// How many deliveries the Groups page shows.
+const DELIVERY_LIMIT: u32 = 50;
+
+/// A group's webhook, with its secret blanked.
+pub async fn get(group_id: Uuid) -> Result<Option<GroupWebhook>> {
+    Ok(GroupWebhooks::get(group_id).await?.map(|mut webhook| {
+        webhook.secret.clear();
+        webhook
+    }))
+}
+
+/// Call `url` whenever someone is added to or removed from the group, as
+/// `on_add` and `on_remove` say. A blank secret keeps the existing one.
+pub async fn set(
+    group_id: Uuid,
+    url: &str,
+    secret: &str,
+    on_add: bool,
+    on_remove: bool,
+    admin: &UserData,
+) -> Result<()> {
+    let url = url.trim();
+    let parsed = Url::parse(url).map_err(|_| err!("'{url}' is not a URL"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(err!("webhook URLs must be http or https"));
+    }
+    if !on_add && !on_remove {
+        return Err(err!(
+            "a webhook must be called for additions, removals, or both"
+        ));
+    }
+
+    let secret = match (secret.trim(), GroupWebhooks::get(group_id).await?) {
+        ("", Some(existing)) => existing.secret,
+        ("", None) => return Err(err!("a secret is needed to sign deliveries")),
+        (secret, _) => secret.to_string(),
+    };
+    let group = IDENTITY.get_group(&group_id.into()).await?;
+
+    GroupWebhooks::set(&GroupWebhook {
+        group_id,
+        group_name: group.name.clone(),
+        url: url.to_string(),
+        secret,
+        on_add,
+        on_remove,
+        updated_by: admin.username.clone(),
+        updated_at: Timestamp::now(),
+    })
+    .await?;
+    tracing::info!(
+        group = group.name,
+        admin = admin.username,
+        "set group webhook"
+    );
+    Ok(())
+}
+
+/// Stop calling a group's webhook, dropping deliveries not yet made.
+pub async fn remove(group_id: Uuid, admin: &UserData) -> Result<()> {
+    GroupWebhooks::delete(group_id).await?;
+    tracing::info!(%group_id, admin = admin.username, "removed group webhook");
+    Ok(())
+}
+
+/// A group's latest deliveries, newest first.
+pub async fn deliveries(group_id: Uuid) -> Result<Vec<WebhookDelivery>> {
+    WebhookDeliveries::list_for_group(group_id, DELIVERY_LIMIT).await
+}
+
+/// Try a delivery that was given up on again.
+pub async fn retry(delivery_id: Uuid) -> Result<()> {
+    WebhookDeliveries::retry(delivery_id).await?;
+    spawn_attempt(delivery_id);
+    Ok(())
+}
+
+/// Queue a call to the group's webhook, if it has one for `action`, and try
+/// it straight away. Failures are logged.
+pub(crate) async fn queue(group_id: GroupId, user_id: PersonId, action: MembershipAction) {
+    let result = async {
+        let Some(webhook) = GroupWebhooks::get(group_id.into()).await? else {
+            return Ok(None);
+        };
+        if !webhook.sends(action) {
+            return Ok(None);
+        }
+        let person = IDENTITY.get_person(&user_id.to_string()).await?;
+        WebhookDeliveries::enqueue(webhook.group_id, user_id.into(), &person.name, action)
+            .await
+            .map(Some)
+    }
+    .await;
+
+    match result {
+        Ok(Some(id)) => spawn_attempt(id),
+        Ok(None) => {}
+        Err(error) => tracing::warn!(?error, %group_id, "failed to queue group webhook"),
+    }
+}
+
+/// Retry every delivery that's due.
+pub(crate) async fn deliver_due() -> Result<()> {
+    for id in WebhookDeliveries::list_due().await? {
+        if let Err(error) = attempt(id).await {
+            tracing::warn!(?error, %id, "failed to attempt group webhook");
+        }
+    }
+    Ok(())
+}
+
+fn spawn_attempt(id: Uuid) {
+    shutdown::spawn(async move {
+        if let Err(error) = attempt(id).await {
+            tracing::warn!(?error, %id, "failed to attempt group webhook");
+        }
+    });
+}
+
+/// Make a delivery, if it's due and nobody else is, recording how it went.
+async fn attempt(id: Uuid) -> Result<()> {
+    let Some(group_id) = WebhookDeliveries::claim(id, Timestamp::now() + CLAIM_HOLD).await? else {
+        return Ok(());
+    };
+    let Some(webhook) = GroupWebhooks::get(group_id).await? else {
+        return WebhookDeliveries::mark_failed(id, "the group's webhook was removed", None).await;
+    };
+    let delivery = WebhookDeliveries::find(id).await?;
+
+    match send(&webhook, &delivery).await {
+        Ok(()) => WebhookDeliveries::mark_delivered(id).await,
+        Err(error) => {
+            let retry_at = (delivery.attempts < MAX_WEBHOOK_ATTEMPTS)
+                .then(|| Timestamp::now() + backoff(delivery.attempts));
+            tracing::warn!(
+                ?error,
+                group = webhook.group_name,
+                attempts = delivery.attempts,
+                "group webhook failed"
+            );
+            WebhookDeliveries::mark_failed(id, &format!("{error:#}"), retry_at).await
+        }
+    }
+}
+
+/// How long to wait after `attempts` tries: a minute, doubling each time.
+fn backoff(attempts: u32) -> Duration {
+    Duration::from_secs(60 << attempts.saturating_sub(1).min(10))
+}
+
+/// POST the change as JSON, signed with the webhook's secret so the
+/// receiver can check it came from us. Receivers should use the delivery id
+/// to ignore repeats, as a delivery may be made more than once.
+async fn send(webhook: &GroupWebhook, delivery: &WebhookDelivery) -> Result<()> {
+    let body = json!({
+        "id": delivery.id,
+        "action": delivery.action,
+        "group": { "id": webhook.group_id, "name": webhook.group_name },
+        "user": { "id": delivery.user_id, "name": delivery.user_name },
+        "authit": ServerInfo::current(),
+    })
+    .to_string();
+
+    let signature = sign(&webhook.secret, &body)?;
+
+    CLIENT
+        .post(&webhook.url)
+        .timeout(DELIVERY_TIMEOUT)
+        .with_trace_context()
+        .header("content-type", "application/json")
+        .header("x-authit-delivery", delivery.id.to_string())
+        .header("x-authit-signature", signature)
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// The `X-AuthIt-Signature` header for `body`: its HMAC-SHA256 with
+/// `secret`, in hex.
+pub(crate) fn sign(secret: &str, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(body.as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(format!("sha256={signature}"))
+}
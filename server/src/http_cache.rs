@@ -0,0 +1,98 @@
+use axum::{
+    Router,
+    body::{self, Body},
+    extract::Request,
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tower_http::compression::CompressionLayer;
+
+/// Dioxus puts this, then a content hash, in the names of bundled assets, so
+/// a changed file always gets a new URL.
+const ASSET_HASH_MARKER: &str = "-dxh";
+
+/// Compress responses, and tell browsers how long they can keep them, which
+/// matters most for admins on slow or distant connections.
+///
+/// Hashed assets are cached for a year. Other static files, like the wasm
+/// bundle if it isn't hashed, are revalidated with an ETag each time. Pages
+/// and API responses hold private data, so they're never stored.
+pub fn cache_and_compress(router: Router) -> Router {
+    router
+        .layer(middleware::from_fn(cache))
+        .layer(CompressionLayer::new())
+}
+
+async fn cache(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let is_get = request.method() == Method::GET;
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let mut response = next.run(request).await;
+    if response.headers().contains_key(header::CACHE_CONTROL) {
+        return response;
+    }
+
+    let is_static = path.starts_with("/assets/") || path.starts_with("/wasm/");
+    if !is_static || !is_get || response.status() != StatusCode::OK {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        return response;
+    }
+
+    if path.contains(ASSET_HASH_MARKER) {
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=31536000, immutable"),
+        );
+        return response;
+    }
+
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if response.headers().contains_key(header::ETAG) {
+        return response;
+    }
+    with_etag(response, if_none_match).await
+}
+
+/// Tag a response with a digest of its body, answering 304 if the browser
+/// already has it. The tag is weak, as compression changes the bytes sent.
+async fn with_etag(response: Response, if_none_match: Option<HeaderValue>) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = match body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            tracing::warn!(?error, "failed to read static file for its ETag");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let digest: String = Sha256::digest(&bytes)[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    let etag = format!("W/\"{digest}\"");
+    let Ok(etag) = HeaderValue::from_str(&etag) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    if if_none_match.is_some_and(|v| v == etag) {
+        let cache_control = parts.headers.get(header::CACHE_CONTROL).cloned();
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+        not_modified.headers_mut().insert(header::ETAG, etag);
+        if let Some(cache_control) = cache_control {
+            not_modified
+                .headers_mut()
+                .insert(header::CACHE_CONTROL, cache_control);
+        }
+        return not_modified;
+    }
+
+    parts.headers.insert(header::ETAG, etag);
+    Response::from_parts(parts, Body::from(bytes))
+}
@@ -0,0 +1,62 @@
+use std::{future::Future, time::Duration};
+
+use serde::{Serialize, de::DeserializeOwned};
+use types::{Result, err};
+use uuid::Uuid;
+
+use crate::storage::{IdempotencyClaim, IdempotencyKeys};
+
+/// How often a retry checks whether the request it repeats has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a retry waits for the request it repeats to finish. Kanidm can be
+/// slow, but not this slow.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Run a mutating request at most once per idempotency key, so a client
+/// retrying after a dropped connection doesn't do it twice. A retry gets the
+/// first response, waiting for it if the first is still running, like after
+/// a double-click or a timeout while Kanidm is slow. Failures aren't
+/// remembered, so they can be retried.
+///
+/// Without a key, `f` just runs.
+pub async fn once<T, F, Fut>(key: Option<Uuid>, endpoint: &str, f: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let Some(key) = key else {
+        return f().await;
+    };
+
+    let mut waited = Duration::ZERO;
+    loop {
+        match IdempotencyKeys::claim(key, endpoint).await? {
+            IdempotencyClaim::New => break,
+            IdempotencyClaim::Done(response) => return Ok(serde_json::from_str(&response)?),
+            IdempotencyClaim::InProgress if waited < MAX_WAIT => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                waited += POLL_INTERVAL;
+            }
+            IdempotencyClaim::InProgress => {
+                return Err(err!(
+                    "this request is still being processed; try again later"
+                ));
+            }
+        }
+    }
+
+    let result = f().await;
+    let recorded = match &result {
+        Ok(value) => match serde_json::to_string(value) {
+            Ok(response) => IdempotencyKeys::finish(key, &response).await,
+            Err(error) => Err(error.into()),
+        },
+        Err(_) => IdempotencyKeys::release(key).await,
+    };
+    if let Err(error) = recorded {
+        tracing::warn!(?error, endpoint, "failed to record idempotency key");
+    }
+    result
+}
@@ -0,0 +1,301 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use types::{
+    EditConflict, ResetLink, Result, err,
+    ids::{GroupId, GroupName, PersonId},
+    kanidm::{Group, GroupChangeFailure, GroupMembershipChanges, NewPersonSetup, Person},
+};
+use uuid::Uuid;
+
+use crate::{KANIDM_CLIENT, config::CONFIG};
+
+mod memory;
+
+pub use memory::MemoryProvider;
+
+/// Where people and groups live, as picked by `identity_backend`.
+pub static IDENTITY: LazyLock<&'static dyn IdentityProvider> =
+    LazyLock::new(|| match CONFIG.identity_backend {
+        IdentityBackend::Kanidm => &*KANIDM_CLIENT,
+        IdentityBackend::Memory => Box::leak(Box::new(MemoryProvider::default())),
+    });
+
+/// The identity backends AuthIt! can manage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityBackend {
+    #[default]
+    Kanidm,
+    /// People and groups kept in memory, and lost on restart. For trying
+    /// AuthIt! out, and for testing.
+    Memory,
+}
+
+/// The group membership changes needed to give a person an exact set of
+/// groups.
+pub struct GroupPlan {
+    pub add: Vec<Group>,
+    pub remove: Vec<Group>,
+}
+
+impl GroupPlan {
+    /// Whether the plan changes membership of the named group.
+    pub fn touches(&self, group_name: &str) -> bool {
+        self.add
+            .iter()
+            .chain(&self.remove)
+            .any(|g| g.name == group_name)
+    }
+}
+
+/// The people and groups AuthIt! manages, and what it can do with them.
+///
+/// Anything only Kanidm can do, like LDAP or replica health, stays on
+/// [`KanidmClient`](crate::kanidm::KanidmClient).
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    async fn list_persons(&self) -> Result<Vec<Person>>;
+
+    async fn get_person(&self, id_or_name: &str) -> Result<Person>;
+
+    /// A person, or `None` if there's no such person. Unlike
+    /// [`Self::get_person`], failing to ask is still an error.
+    async fn find_person(&self, id_or_name: &str) -> Result<Option<Person>>;
+
+    /// The groups a person is directly in, without the rest of their entry.
+    async fn get_person_groups(&self, user_id: &PersonId) -> Result<Vec<String>>;
+
+    async fn create_person(
+        &self,
+        user_name: &str,
+        display_name: &str,
+        email_address: &str,
+    ) -> Result<()>;
+
+    async fn delete_person(&self, user_id: &PersonId) -> Result<()>;
+
+    /// Replace a person's email addresses. The first is their primary
+    /// address.
+    async fn set_email_addresses(&self, user_id: &PersonId, addresses: &[String]) -> Result<()>;
+
+    async fn set_display_name(&self, user_id: &PersonId, display_name: &str) -> Result<()>;
+
+    /// Set or clear a person's legal name.
+    async fn set_legal_name(&self, user_id: &PersonId, legal_name: Option<&str>) -> Result<()>;
+
+    /// Turn on POSIX for a person, so they can sign in to Unix hosts.
+    async fn enable_posix(
+        &self,
+        user_id: &PersonId,
+        gid_number: Option<u32>,
+        login_shell: Option<&str>,
+    ) -> Result<()>;
+
+    /// Set when a person's account expires, after which they can't sign in.
+    async fn expire_account(&self, user_id: &PersonId, at: jiff::Timestamp) -> Result<()>;
+
+    /// Stop a person's account expiring, letting them sign in again if it
+    /// already has.
+    async fn unexpire_account(&self, user_id: &PersonId) -> Result<()>;
+
+    /// A link for the person to set up or replace their credentials.
+    async fn generate_credential_reset_link(&self, user_id: &PersonId) -> Result<ResetLink>;
+
+    async fn list_groups(&self) -> Result<Vec<Group>>;
+
+    async fn get_group(&self, group_id: &GroupId) -> Result<Group>;
+
+    /// Look a group up by name, like one from the config or a provision link.
+    async fn find_group(&self, name: &GroupName) -> Result<Group>;
+
+    async fn create_group(&self, name: &GroupName, description: Option<&str>) -> Result<()>;
+
+    async fn delete_group(&self, group_id: &GroupId) -> Result<()>;
+
+    /// List a group's direct members, by SPN.
+    async fn list_group_members(&self, group_id: &GroupId) -> Result<Vec<String>>;
+
+    /// Add members to a group, by name, SPN, or UUID.
+    async fn add_group_members(&self, group_id: &GroupId, members: &[String]) -> Result<()>;
+
+    async fn add_user_to_group(&self, group_id: &GroupId, user_id: &PersonId) -> Result<()>;
+
+    async fn remove_user_from_group(&self, group_id: &GroupId, user_id: &PersonId) -> Result<()>;
+
+    /// Get a person, failing with [`EditConflict`] if their entry changed
+    /// since the caller read it as `modified`. With no `modified`, or if
+    /// the backend doesn't say, there's nothing to compare and it's allowed.
+    ///
+    /// Writes aren't conditional, so a change landing between
+    /// this and the write still goes unnoticed.
+    async fn get_unmodified_person(
+        &self,
+        user_id: &PersonId,
+        modified: Option<&str>,
+    ) -> Result<Person> {
+        let person = self.get_person(&user_id.to_string()).await?;
+        if let (Some(expected), Some(current)) = (modified, person.modified.as_deref())
+            && expected != current
+        {
+            return Err(EditConflict {
+                name: person.display_name,
+            }
+            .into());
+        }
+        Ok(person)
+    }
+
+    /// Count how many people are in each group, directly or through nested
+    /// groups.
+    async fn group_member_counts(&self) -> Result<HashMap<Uuid, u32>> {
+        let persons = self.list_persons().await?;
+        Ok(self
+            .list_groups()
+            .await?
+            .into_iter()
+            .map(|g| {
+                let count = persons.iter().filter(|p| p.is_member_of(&g)).count();
+                (g.uuid, count as u32)
+            })
+            .collect())
+    }
+
+    /// Work out which groups to add the user to or remove them from, so that
+    /// they end up in exactly `group_ids`.
+    async fn plan_user_groups(
+        &self,
+        user_id: &PersonId,
+        group_ids: &[GroupId],
+    ) -> Result<GroupPlan> {
+        let person = self.get_person(&user_id.to_string()).await?;
+        let groups = self.list_groups().await?;
+
+        if let Some(missing) = group_ids
+            .iter()
+            .find(|id| !groups.iter().any(|g| g.id() == **id))
+        {
+            return Err(err!("group {missing} does not exist"));
+        }
+
+        let (member, not_member): (Vec<Group>, Vec<Group>) =
+            groups.into_iter().partition(|g| person.is_member_of(g));
+
+        Ok(GroupPlan {
+            add: not_member
+                .into_iter()
+                .filter(|g| group_ids.contains(&g.id()))
+                .collect(),
+            remove: member
+                .into_iter()
+                .filter(|g| !group_ids.contains(&g.id()))
+                .collect(),
+        })
+    }
+
+    /// Apply a [`GroupPlan`], carrying on past individual failures so they
+    /// can be reported together.
+    async fn apply_group_plan(
+        &self,
+        user_id: &PersonId,
+        plan: GroupPlan,
+    ) -> GroupMembershipChanges {
+        let mut changes = GroupMembershipChanges::default();
+
+        for group in plan.add {
+            match self.add_user_to_group(&group.id(), user_id).await {
+                Ok(()) => changes.added.push(group.uuid),
+                Err(e) => changes.failed.push(GroupChangeFailure {
+                    group_id: group.uuid,
+                    group_name: group.name,
+                    add: true,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        for group in plan.remove {
+            match self.remove_user_from_group(&group.id(), user_id).await {
+                Ok(()) => changes.removed.push(group.uuid),
+                Err(e) => changes.failed.push(GroupChangeFailure {
+                    group_id: group.uuid,
+                    group_name: group.name,
+                    add: false,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        changes
+    }
+
+    /// Create a person and set them up as `setup` says, in one go. If any of
+    /// it fails they're deleted again, rather than left half set up.
+    async fn create_person_with_setup(
+        &self,
+        user_name: &str,
+        display_name: &str,
+        email_address: &str,
+        setup: &NewPersonSetup,
+    ) -> Result<Person> {
+        // Check the groups exist before there's anything to undo.
+        let all_groups = self.list_groups().await?;
+        let groups = setup
+            .group_ids
+            .iter()
+            .map(|id| {
+                all_groups
+                    .iter()
+                    .find(|g| g.uuid == *id)
+                    .ok_or_else(|| err!("group {id} does not exist"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.create_person(user_name, display_name, email_address)
+            .await?;
+        let person = self.get_person(user_name).await?;
+
+        let result: Result<()> = async {
+            if let Some(legal_name) = &setup.legal_name {
+                self.set_legal_name(&person.id(), Some(legal_name)).await?;
+            }
+            if let Some(posix) = &setup.posix {
+                self.enable_posix(&person.id(), posix.gid_number, posix.login_shell.as_deref())
+                    .await
+                    .map_err(|e| err!("failed to turn on POSIX: {e}"))?;
+            }
+            for group in &groups {
+                self.add_user_to_group(&group.id(), &person.id())
+                    .await
+                    .map_err(|e| err!("failed to add them to {}: {e}", group.name))?;
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(error) = result {
+            if let Err(error) = self.delete_person(&person.id()).await {
+                tracing::warn!(
+                    ?error,
+                    user = user_name,
+                    "failed to delete half-created person"
+                );
+            }
+            return Err(error);
+        }
+        self.get_person(&person.uuid.to_string()).await
+    }
+
+    async fn create_person_with_link(
+        &self,
+        user_name: &str,
+        display_name: &str,
+        email_address: &str,
+    ) -> Result<ResetLink> {
+        self.create_person(user_name, display_name, email_address)
+            .await?;
+        let person = self.get_person(user_name).await?;
+        self.generate_credential_reset_link(&person.id()).await
+    }
+}
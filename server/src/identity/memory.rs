@@ -0,0 +1,231 @@
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use jiff::{SignedDuration, Timestamp};
+use types::{
+    ResetLink, Result, err,
+    ids::{GroupId, GroupName, PersonId},
+    kanidm::{Group, Person},
+};
+use uuid::Uuid;
+
+use super::IdentityProvider;
+
+/// The domain in the SPNs of people and groups kept in memory.
+const DOMAIN: &str = "localhost";
+
+/// How long the reset links it hands out claim to last.
+const RESET_LINK_TTL: SignedDuration = SignedDuration::from_hours(24);
+
+/// People and groups kept in memory, so AuthIt! can run without an identity
+/// server. Nothing is persisted, and reset links lead nowhere.
+#[derive(Default)]
+pub struct MemoryProvider {
+    persons: RwLock<Vec<Person>>,
+    groups: RwLock<Vec<Group>>,
+}
+
+impl MemoryProvider {
+    fn matches(id_or_name: &str, uuid: &Uuid, name: &str) -> bool {
+        id_or_name == uuid.to_string() || id_or_name == name || id_or_name == spn(name)
+    }
+
+    fn with_person<T>(&self, user_id: &PersonId, f: impl FnOnce(&mut Person) -> T) -> Result<T> {
+        let mut persons = self.persons.write().unwrap();
+        let person = persons
+            .iter_mut()
+            .find(|p| p.id() == *user_id)
+            .ok_or_else(|| err!("person {user_id} does not exist"))?;
+        let result = f(person);
+        person.modified = Some(Uuid::now_v7().to_string());
+        Ok(result)
+    }
+}
+
+fn spn(name: &str) -> String {
+    format!("{name}@{DOMAIN}")
+}
+
+#[async_trait]
+impl IdentityProvider for MemoryProvider {
+    async fn list_persons(&self) -> Result<Vec<Person>> {
+        Ok(self.persons.read().unwrap().clone())
+    }
+
+    async fn get_person(&self, id_or_name: &str) -> Result<Person> {
+        self.persons
+            .read()
+            .unwrap()
+            .iter()
+            .find(|p| Self::matches(id_or_name, &p.uuid, &p.name))
+            .cloned()
+            .ok_or_else(|| err!("person {id_or_name} does not exist"))
+    }
+
+    async fn find_person(&self, id_or_name: &str) -> Result<Option<Person>> {
+        Ok(self
+            .persons
+            .read()
+            .unwrap()
+            .iter()
+            .find(|p| Self::matches(id_or_name, &p.uuid, &p.name))
+            .cloned())
+    }
+
+    async fn get_person_groups(&self, user_id: &PersonId) -> Result<Vec<String>> {
+        self.with_person(user_id, |p| p.groups.clone())
+    }
+
+    async fn create_person(
+        &self,
+        user_name: &str,
+        display_name: &str,
+        email_address: &str,
+    ) -> Result<()> {
+        let mut persons = self.persons.write().unwrap();
+        if persons.iter().any(|p| p.name == user_name) {
+            return Err(err!("person {user_name} already exists"));
+        }
+        persons.push(Person {
+            uuid: Uuid::now_v7(),
+            name: user_name.to_string(),
+            display_name: display_name.to_string(),
+            legal_name: None,
+            email_addresses: if email_address.is_empty() {
+                Vec::new()
+            } else {
+                vec![email_address.to_string()]
+            },
+            groups: Vec::new(),
+            spn: Some(spn(user_name)),
+            created_at: Some(Timestamp::now()),
+            modified: Some(Uuid::now_v7().to_string()),
+        });
+        Ok(())
+    }
+
+    async fn delete_person(&self, user_id: &PersonId) -> Result<()> {
+        self.persons.write().unwrap().retain(|p| p.id() != *user_id);
+        Ok(())
+    }
+
+    async fn set_email_addresses(&self, user_id: &PersonId, addresses: &[String]) -> Result<()> {
+        self.with_person(user_id, |p| p.email_addresses = addresses.to_vec())
+    }
+
+    async fn set_display_name(&self, user_id: &PersonId, display_name: &str) -> Result<()> {
+        self.with_person(user_id, |p| p.display_name = display_name.to_string())
+    }
+
+    async fn set_legal_name(&self, user_id: &PersonId, legal_name: Option<&str>) -> Result<()> {
+        self.with_person(user_id, |p| p.legal_name = legal_name.map(String::from))
+    }
+
+    async fn enable_posix(
+        &self,
+        user_id: &PersonId,
+        _gid_number: Option<u32>,
+        _login_shell: Option<&str>,
+    ) -> Result<()> {
+        // Nothing reads POSIX attributes from this backend.
+        self.with_person(user_id, |_| ())
+    }
+
+    async fn expire_account(&self, user_id: &PersonId, _at: Timestamp) -> Result<()> {
+        // Nobody signs in through this backend, so there's nothing to stop.
+        self.with_person(user_id, |_| ())
+    }
+
+    async fn unexpire_account(&self, user_id: &PersonId) -> Result<()> {
+        self.with_person(user_id, |_| ())
+    }
+
+    async fn generate_credential_reset_link(&self, user_id: &PersonId) -> Result<ResetLink> {
+        self.get_person(&user_id.to_string()).await?;
+        Ok(ResetLink {
+            url: format!("memory:reset/{user_id}").parse()?,
+            expires_at: Timestamp::now() + RESET_LINK_TTL,
+        })
+    }
+
+    async fn list_groups(&self) -> Result<Vec<Group>> {
+        Ok(self.groups.read().unwrap().clone())
+    }
+
+    async fn get_group(&self, group_id: &GroupId) -> Result<Group> {
+        self.groups
+            .read()
+            .unwrap()
+            .iter()
+            .find(|g| g.id() == *group_id)
+            .cloned()
+            .ok_or_else(|| err!("group {group_id} does not exist"))
+    }
+
+    async fn find_group(&self, name: &GroupName) -> Result<Group> {
+        self.groups
+            .read()
+            .unwrap()
+            .iter()
+            .find(|g| g.name == name.as_str() || spn(&g.name) == name.as_str())
+            .cloned()
+            .ok_or_else(|| err!("group {name} does not exist"))
+    }
+
+    async fn create_group(&self, name: &GroupName, description: Option<&str>) -> Result<()> {
+        let mut groups = self.groups.write().unwrap();
+        if groups.iter().any(|g| g.name == name.as_str()) {
+            return Err(err!("group {name} already exists"));
+        }
+        groups.push(Group {
+            uuid: Uuid::now_v7(),
+            name: name.to_string(),
+            spn: Some(spn(name.as_str())),
+            description: description.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    async fn delete_group(&self, group_id: &GroupId) -> Result<()> {
+        let group = self.get_group(group_id).await?;
+        self.groups.write().unwrap().retain(|g| g.id() != *group_id);
+        for person in self.persons.write().unwrap().iter_mut() {
+            person.groups.retain(|g| *g != spn(&group.name));
+        }
+        Ok(())
+    }
+
+    async fn list_group_members(&self, group_id: &GroupId) -> Result<Vec<String>> {
+        let group = self.get_group(group_id).await?;
+        Ok(self
+            .persons
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|p| p.is_member_of(&group))
+            .map(|p| spn(&p.name))
+            .collect())
+    }
+
+    async fn add_group_members(&self, group_id: &GroupId, members: &[String]) -> Result<()> {
+        for member in members {
+            let person = self.get_person(member).await?;
+            self.add_user_to_group(group_id, &person.id()).await?;
+        }
+        Ok(())
+    }
+
+    async fn add_user_to_group(&self, group_id: &GroupId, user_id: &PersonId) -> Result<()> {
+        let group = self.get_group(group_id).await?;
+        self.with_person(user_id, |p| {
+            if !p.is_member_of(&group) {
+                p.groups.push(spn(&group.name));
+            }
+        })
+    }
+
+    async fn remove_user_from_group(&self, group_id: &GroupId, user_id: &PersonId) -> Result<()> {
+        let group = self.get_group(group_id).await?;
+        self.with_person(user_id, |p| p.groups.retain(|g| *g != spn(&group.name)))
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use jiff::Timestamp;
+use types::{
+    Result, err,
+    integrity::{IntegrityFinding, IntegrityIssue, IntegrityReport, IntegrityUser, OrphanedEntry},
+    kanidm::{EntryKind, Person},
+};
+use unicode_security::skeleton;
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, forget, group_deletion,
+    storage::{LocalReference, LocalReferences, Settings},
+};
+
+/// Look for people who could be mistaken for each other, and for local
+/// records about users and groups that are gone from Kanidm, and save what's
+/// found for admins to review.
+pub async fn check() -> Result<IntegrityReport> {
+    let persons = IDENTITY.list_persons().await?;
+    let mut report = scan(&persons);
+    report.orphans = orphans(&persons).await?;
+    if !report.findings.is_empty() {
+        tracing::info!(
+            findings = report.findings.len(),
+            "integrity check found possible duplicates"
+        );
+    }
+    if !report.orphans.is_empty() {
+        tracing::info!(
+            orphans = report.orphans.len(),
+            "integrity check found records about deleted entries"
+        );
+    }
+    Settings::set(Settings::INTEGRITY, &report).await?;
+    Ok(report)
+}
+
+/// The findings of the last check, if there's been one.
+pub async fn report() -> Result<Option<IntegrityReport>> {
+    Settings::get(Settings::INTEGRITY).await
+}
+
+/// Delete the local records about a user or group that's gone from Kanidm,
+/// and drop it from the last report.
+pub async fn clean_up(kind: EntryKind, id: Uuid) -> Result<IntegrityReport> {
+    // Make sure it's really gone, rather than trusting an old report.
+    let exists = match kind {
+        EntryKind::Person => IDENTITY.list_persons().await?.iter().any(|p| p.uuid == id),
+        EntryKind::Group => IDENTITY.list_groups().await?.iter().any(|g| g.uuid == id),
+    };
+    if exists {
+        return Err(err!("{id} is still in Kanidm"));
+    }
+
+    match kind {
+        EntryKind::Person => forget::forget_user_id(id).await?,
+        EntryKind::Group => group_deletion::forget_group(id).await?,
+    }
+    tracing::info!(?kind, %id, "cleaned up records about deleted entry");
+
+    let mut report = report()
+        .await?
+        .ok_or_else(|| err!("there's no integrity report"))?;
+    report.orphans.retain(|o| !(o.kind == kind && o.id == id));
+    Settings::set(Settings::INTEGRITY, &report).await?;
+    Ok(report)
+}
+
+/// Users and groups that local records refer to but Kanidm doesn't have.
+async fn orphans(persons: &[Person]) -> Result<Vec<OrphanedEntry>> {
+    let person_ids: HashSet<Uuid> = persons.iter().map(|p| p.uuid).collect();
+    let group_ids: HashSet<Uuid> = IDENTITY
+        .list_groups()
+        .await?
+        .into_iter()
+        .map(|g| g.uuid)
+        .collect();
+
+    let user_references = LocalReferences::users().await?;
+    let group_references = LocalReferences::groups().await?;
+    let mut orphans = missing(EntryKind::Person, user_references, &person_ids);
+    orphans.extend(missing(EntryKind::Group, group_references, &group_ids));
+    Ok(orphans)
+}
+
+fn missing(
+    kind: EntryKind,
+    references: Vec<LocalReference>,
+    existing: &HashSet<Uuid>,
+) -> Vec<OrphanedEntry> {
+    let mut by_id: BTreeMap<Uuid, (Option<String>, BTreeSet<String>)> = BTreeMap::new();
+    for reference in references {
+        if existing.contains(&reference.id) {
+            continue;
+        }
+        let (name, places) = by_id.entry(reference.id).or_default();
+        if name.is_none() {
+            *name = reference.name;
+        }
+        places.insert(reference.place);
+    }
+    by_id
+        .into_iter()
+        .map(|(id, (name, places))| OrphanedEntry {
+            kind,
+            id,
+            name,
+            found_in: places.into_iter().collect(),
+        })
+        .collect()
+}
+
+fn scan(persons: &[Person]) -> IntegrityReport {
+    let mut by_email: BTreeMap<String, Vec<&Person>> = BTreeMap::new();
+    let mut by_skeleton: BTreeMap<String, Vec<&Person>> = BTreeMap::new();
+    for person in persons {
+        for email in &person.email_addresses {
+            let users = by_email.entry(email.to_lowercase()).or_default();
+            // Someone listing an address twice isn't sharing it.
+            if !users.iter().any(|p| p.uuid == person.uuid) {
+                users.push(person);
+            }
+        }
+        by_skeleton
+            .entry(name_skeleton(&person.display_name))
+            .or_default()
+            .push(person);
+    }
+
+    let shared_emails = by_email
+        .into_iter()
+        .map(|(email, users)| (IntegrityIssue::SharedEmail { email }, users));
+    let similar_names = by_skeleton
+        .into_values()
+        .map(|users| (IntegrityIssue::SimilarDisplayNames, users));
+    let findings = shared_emails
+        .chain(similar_names)
+        .filter(|(_, users)| users.len() > 1)
+        .map(|(issue, users)| IntegrityFinding {
+            issue,
+            users: users.into_iter().map(user).collect(),
+        })
+        .collect();
+
+    IntegrityReport {
+        checked_at: Timestamp::now(),
+        findings,
+        orphans: Vec::new(),
+    }
+}
+
+/// What a display name looks like, ignoring case, spacing, and characters
+/// that are easily confused with each other.
+fn name_skeleton(display_name: &str) -> String {
+    let words: Vec<&str> = display_name.split_whitespace().collect();
+    skeleton(&words.join(" ").to_lowercase()).collect()
+}
+
+fn user(person: &Person) -> IntegrityUser {
+    IntegrityUser {
+        uuid: person.uuid,
+        name: person.name.clone(),
+        display_name: person.display_name.clone(),
+    }
+}
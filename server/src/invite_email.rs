@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use types::{Result, err, provision::ProvisionLinkSummary};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, email,
+    storage::{Outbox, OutboxKind, ProvisionLink, ProvisionLinkEvents},
+    uuid_v7::UuidV7Ext,
+};
+
+/// Email a link to the one address it's for. Returns whether it was sent.
+pub async fn send(link: &ProvisionLink, kind: OutboxKind) -> Result<bool> {
+    let url = CONFIG.provision_url(link.as_token()?)?;
+    let expires_at = link.expires_at();
+    let (subject, body) = match kind {
+        OutboxKind::Invite | OutboxKind::Resend => (
+            "Create your account",
+            format!(
+                "Hi,\n\n\
+                Use this link to create your account before {expires_at}:\n\n\
+                {url}\n"
+            ),
+        ),
+        OutboxKind::Reminder => (
+            "Your account invitation expires soon",
+            format!(
+                "Hi,\n\n\
+                You haven't created your account yet. The link expires at \
+                {expires_at}:\n\n\
+                {url}\n"
+            ),
+        ),
+    };
+
+    deliver(link, kind, subject, body).await
+}
+
+/// Email `body` to the one address a link is for, recording the delivery in
+/// the outbox. Returns whether it was sent or queued to retry.
+pub async fn deliver(
+    link: &ProvisionLink,
+    kind: OutboxKind,
+    subject: &str,
+    body: String,
+) -> Result<bool> {
+    let Some(to) = link.email_restriction().address.as_deref() else {
+        return Err(err!("this link isn't for a particular email address"));
+    };
+    if !email::is_configured() {
+        return Ok(false);
+    }
+
+    let id = Outbox::enqueue(link.id(), to, kind).await?;
+    match email::send(to, subject, body).await {
+        Ok(()) => {
+            Outbox::mark_sent(id).await?;
+            Ok(true)
+        }
+        Err(error) => {
+            tracing::warn!(?error, kind = kind.as_str(), "failed to send invite email");
+            Outbox::mark_failed(id, &error.to_string()).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// A link's summary, with when it was last emailed.
+pub async fn summary(link: &ProvisionLink) -> Result<ProvisionLinkSummary> {
+    Ok(ProvisionLinkSummary {
+        last_emailed_at: Outbox::last_sent(link.id()).await?,
+        funnel: ProvisionLinkEvents::funnel(link.id()).await?,
+        ..link.summary()
+    })
+}
+
+/// Email a link to its address again, at an admin's request.
+pub async fn resend(link_id: Uuid) -> Result<()> {
+    let link = ProvisionLink::find(link_id).await?;
+    link.verify()?;
+    if !email::is_configured() {
+        return Err(err!("email is not configured"));
+    }
+    if !send(&link, OutboxKind::Resend).await? {
+        return Err(err!("the email couldn't be sent; check the server logs"));
+    }
+    Ok(())
+}
+
+/// Remind people whose links are about to expire that they haven't used
+/// them. Each link gets one reminder, and links that never lasted longer than
+/// the reminder window get none.
+pub async fn send_due_reminders() -> Result<()> {
+    if CONFIG.invite_reminder_hours == 0 || !email::is_configured() {
+        return Ok(());
+    }
+    let window = Duration::from_secs(CONFIG.invite_reminder_hours * 3600);
+    let due_by = Timestamp::now() + window;
+
+    for link in ProvisionLink::list_unexpired().await? {
+        if link.email_restriction().address.is_none()
+            || link.use_count() > 0
+            || link.expires_at() > due_by
+            || link.id().jiff_timestamp() + window >= link.expires_at()
+            || Outbox::was_sent(link.id(), OutboxKind::Reminder).await?
+        {
+            continue;
+        }
+        send(&link, OutboxKind::Reminder).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use std::{future::Future, sync::LazyLock, time::Duration};
+
+use futures_util::{FutureExt, future::BoxFuture};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::Instrument;
+use types::{Result, background_job::BackgroundJob, err};
+use uuid::Uuid;
+
+use crate::{
+    UserData, email, group_review, group_webhook, integrity, invite_email, kanidm, kanidm_events,
+    offboarding, onboarding, provision_claims, provision_reminder, report, reset_watch, retention,
+    rotation, scheduled_membership, shutdown,
+    storage::{
+        BackgroundJobRuns, IdempotencyKeys, JobLeases, PendingLogin, ProvisionedAccounts,
+        RequestAttempts, UsedActionTokens,
+    },
+};
+
+/// Identifies this replica when taking job leases.
+static INSTANCE_ID: LazyLock<Uuid> = LazyLock::new(Uuid::now_v7);
+
+/// Every background job, in the order they're listed to admins.
+static JOBS: LazyLock<Vec<Definition>> = LazyLock::new(|| {
+    vec![
+        job(
+            "offboarding deletions",
+            Duration::from_secs(15 * 60),
+            offboarding::run_due_deletions,
+        ),
+        job(
+            "scheduled membership changes",
+            Duration::from_secs(60),
+            scheduled_membership::run_due,
+        ),
+        job(
+            "group reviews",
+            Duration::from_secs(60 * 60),
+            group_review::flag_overdue,
+        ),
+        job(
+            "onboarding progress",
+            Duration::from_secs(15 * 60),
+            onboarding::refresh_all,
+        ),
+        job(
+            "expired logins",
+            Duration::from_secs(10 * 60),
+            PendingLogin::delete_expired,
+        ),
+        job(
+            "pending provision link uses",
+            Duration::from_secs(15 * 60),
+            provision_claims::settle_stale,
+        ),
+        job(
+            "provision link reminders",
+            Duration::from_secs(15 * 60),
+            provision_reminder::send_due,
+        ),
+        job(
+            "invite reminders",
+            Duration::from_secs(60 * 60),
+            invite_email::send_due_reminders,
+        ),
+        job(
+            "reset link use",
+            Duration::from_secs(5 * 60),
+            reset_watch::check_all,
+        ),
+        job(
+            "Kanidm sign-ins and credential changes",
+            Duration::from_secs(15 * 60),
+            kanidm_events::sync,
+        ),
+        job(
+            "credential rotation",
+            Duration::from_secs(15 * 60),
+            rotation::check_all,
+        ),
+        job("integrity check", Duration::from_secs(60 * 60), || async {
+            integrity::check().await.map(|_| ())
+        }),
+        job(
+            "idempotency keys",
+            Duration::from_secs(60 * 60),
+            IdempotencyKeys::delete_expired,
+        ),
+        job(
+            "provision quotas",
+            Duration::from_secs(60 * 60),
+            ProvisionedAccounts::delete_expired,
+        ),
+        job(
+            "used action links",
+            Duration::from_secs(60 * 60),
+            UsedActionTokens::delete_expired,
+        ),
+        job(
+            "request limits",
+            Duration::from_secs(60 * 60),
+            RequestAttempts::delete_expired,
+        ),
+        job("email", Duration::from_secs(60), email::send_due),
+        job(
+            "group webhooks",
+            Duration::from_secs(60),
+            group_webhook::deliver_due,
+        ),
+        job(
+            "monthly report",
+            Duration::from_secs(60 * 60),
+            report::send_monthly,
+        ),
+        job(
+            "activity digest",
+            Duration::from_secs(60 * 60),
+            report::send_digest,
+        ),
+        job("data retention", Duration::from_secs(60 * 60), || async {
+            retention::purge().await.map(|_| ())
+        }),
+    ]
+});
+
+struct Definition {
+    name: &'static str,
+    interval: Duration,
+    run: Box<dyn Fn() -> BoxFuture<'static, Result<()>> + Send + Sync>,
+    /// Held while it runs on this replica, so running it by hand can't
+    /// overlap a scheduled run.
+    running: Mutex<()>,
+}
+
+fn job<F, Fut>(name: &'static str, interval: Duration, run: F) -> Definition
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    Definition {
+        name,
+        interval,
+        run: Box::new(move || run().boxed()),
+        running: Mutex::new(()),
+    }
+}
+
+impl Definition {
+    /// Run the job once, recording when it started and how it went.
+    async fn run(&self, _running: MutexGuard<'_, ()>, started_by: Option<&str>) {
+        if let Err(error) = BackgroundJobRuns::start(self.name, started_by).await {
+            tracing::warn!(?error, job = self.name, "failed to record job start");
+        }
+        let span = tracing::info_span!("job", name = self.name);
+        let error = match kanidm::as_service((self.run)().instrument(span)).await {
+            Ok(()) => None,
+            Err(error) => {
+                tracing::warn!(?error, job = self.name, "background job failed");
+                Some(error.to_string())
+            }
+        };
+        if let Err(error) = BackgroundJobRuns::finish(self.name, error.as_deref()).await {
+            tracing::warn!(?error, job = self.name, "failed to record job result");
+        }
+    }
+}
+
+/// Start the background jobs. Call this once, at startup.
+pub fn spawn() {
+    for definition in JOBS.iter() {
+        every(definition);
+    }
+}
+
+/// Every job, with its latest run on any replica.
+pub async fn list() -> Result<Vec<BackgroundJob>> {
+    let mut runs = BackgroundJobRuns::list().await?;
+    Ok(JOBS
+        .iter()
+        .map(|definition| BackgroundJob {
+            name: definition.name.to_string(),
+            interval_secs: definition.interval.as_secs(),
+            last_run: runs.remove(definition.name),
+        })
+        .collect())
+}
+
+/// Run a job now, on this replica, without waiting for it to finish. Its
+/// schedule carries on as before.
+pub fn run_now(name: &str, admin: &UserData) -> Result<()> {
+    let Some(definition) = JOBS.iter().find(|d| d.name == name) else {
+        return Err(err!("there's no background job called '{name}'"));
+    };
+    let Ok(running) = definition.running.try_lock() else {
+        return Err(err!("'{name}' is already running"));
+    };
+
+    tracing::info!(job = name, admin = admin.username, "running background job");
+    let started_by = admin.username.clone();
+    shutdown::spawn(async move { definition.run(running, Some(&started_by)).await });
+    Ok(())
+}
+
+/// Run a job every `interval`. When several replicas share a database, only
+/// the one holding the job's lease runs it.
+fn every(definition: &'static Definition) {
+    let (name, interval) = (definition.name, definition.interval);
+    shutdown::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            // A run that's started finishes, but none start after shutdown.
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown::started() => return,
+            }
+            match JobLeases::acquire(name, *INSTANCE_ID, interval).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(error) => {
+                    tracing::warn!(?error, job = name, "failed to take job lease");
+                    continue;
+                }
+            }
+            let running = definition.running.lock().await;
+            run_leased(definition, running).await;
+        }
+    });
+}
+
+/// Run a job, renewing its lease as it goes, so that a run that takes longer
+/// than the job's interval doesn't let another replica start it too.
+async fn run_leased(definition: &'static Definition, running: MutexGuard<'static, ()>) {
+    let (name, interval) = (definition.name, definition.interval);
+    let run = definition.run(running, None);
+    tokio::pin!(run);
+
+    let mut renew = tokio::time::interval(interval / 2);
+    // The first tick is immediate, and the lease was only just taken.
+    renew.tick().await;
+    loop {
+        tokio::select! {
+            () = &mut run => return,
+            _ = renew.tick() => match JobLeases::acquire(name, *INSTANCE_ID, interval).await {
+                Ok(true) => {}
+                Ok(false) => tracing::warn!(job = name, "lost job lease while running"),
+                Err(error) => tracing::warn!(?error, job = name, "failed to renew job lease"),
+            },
+        }
+    }
+}
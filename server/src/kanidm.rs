@@ -1,58 +1,667 @@
-use std::sync::LazyLock;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc, LazyLock, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
+use async_trait::async_trait;
 use jiff::Timestamp;
-use reqwest::{Client, Method, RequestBuilder, Url};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
 use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+use tracing::Instrument;
 use types::{
-    ResetLink, Result,
-    kanidm::{Group, Person, RawGroup, RawPerson},
+    ResetLink, Result, err,
+    ids::{GroupId, GroupName, PersonId},
+    kanidm::{
+        DomainInfo, EntryKind, Group, KanidmLoad, KanidmNode, LdapStatus, Person,
+        RawCredentialStatus, RawDomain, RawGroup, RawLdapEntry, RawOnboardingEntry, RawPerson,
+    },
+    onboarding::OnboardingSignals,
 };
 use uuid::Uuid;
 
-use crate::{ReqwestExt, config::CONFIG};
+use crate::{
+    ReqwestExt, UserData, capture, config::CONFIG, explain, identity::IdentityProvider,
+    telemetry::TraceContextExt,
+};
+
+pub static KANIDM_CLIENT: LazyLock<KanidmClient> = LazyLock::new(|| {
+    KanidmClient::new(
+        CONFIG.kanidm_url.clone(),
+        CONFIG
+            .kanidm_replica_urls()
+            .expect("invalid kanidm_replica_urls"),
+        CONFIG.kanidm_token.clone(),
+        CONFIG.kanidm_max_concurrency,
+    )
+});
+
+/// Who changes in Kanidm are made as, with `kanidm_per_admin_tokens`.
+#[derive(Clone)]
+enum Writer {
+    /// The signed-in admin, with the access token they signed in with.
+    Admin(SecretString),
+    /// AuthIt! itself, with `kanidm_token`.
+    Service,
+}
+
+tokio::task_local! {
+    static WRITER: Writer;
+}
+
+/// Run `f` for `admin`, so that with `kanidm_per_admin_tokens` the changes it
+/// makes in Kanidm use their token, and Kanidm's own access controls apply.
+/// Reads still use ours.
+///
+/// Break-glass and test-login sessions have no token, so they use ours.
+pub async fn acting_as<T, Fut, F>(admin: UserData, f: F) -> T
+where
+    F: FnOnce(UserData) -> Fut,
+    Fut: Future<Output = T>,
+{
+    if !CONFIG.kanidm_per_admin_tokens {
+        return f(admin).await;
+    }
+    let writer = if admin.is_local() {
+        Writer::Service
+    } else {
+        Writer::Admin(admin.access_token.clone())
+    };
+    WRITER.scope(writer, f(admin)).await
+}
+
+/// Run `f` as AuthIt! itself, so that the changes it makes in Kanidm use our
+/// token. With `kanidm_per_admin_tokens`, changes made outside this and
+/// [`acting_as`] fail, so none are made with our token by accident; every
+/// use of this is listed under that setting in the README.
+pub async fn as_service<F: Future>(f: F) -> F::Output {
+    WRITER.scope(Writer::Service, f).await
+}
+
+/// Carry who `f` is spawned by, if anyone, into it, for work that runs in
+/// the background on an admin's behalf.
+pub(crate) fn keep_writer<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    let writer = WRITER.try_with(Clone::clone).ok();
+    async move {
+        match writer {
+            Some(writer) => WRITER.scope(writer, f).await,
+            None => f.await,
+        }
+    }
+}
+
+/// Kanidm turned down the signed-in admin's access token, most likely because
+/// it expired.
+#[derive(Debug)]
+pub struct AdminTokenExpired;
+
+impl std::fmt::Display for AdminTokenExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Kanidm no longer accepts your sign-in; sign in to AuthIt! again")
+    }
+}
+
+impl std::error::Error for AdminTokenExpired {}
+
+/// How often to check whether each Kanidm server is up.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Attributes never shown in raw entries. Kanidm mostly doesn't return them
+/// anyway, but that's not worth relying on.
+const SECRET_ATTRIBUTES: &[&str] = &[
+    "api_token_session",
+    "oauth2_rs_basic_secret",
+    "oauth2_session",
+    "primary_credential",
+    "radius_secret",
+    "sync_token_session",
+    "unix_password",
+    "user_auth_token_session",
+];
+
+/// A Kanidm server, and whether it answered its last health check.
+struct Node {
+    url: Url,
+    health: RwLock<NodeHealth>,
+}
+
+#[derive(Clone, Default)]
+struct NodeHealth {
+    /// Nodes are assumed healthy until a check fails.
+    unhealthy: bool,
+    checked_at: Option<Timestamp>,
+    error: Option<String>,
+}
+
+impl Node {
+    fn new(url: Url) -> Self {
+        Self {
+            url,
+            health: RwLock::default(),
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        !self.health.read().unwrap().unhealthy
+    }
+
+    async fn check(&self, client: &Client) {
+        let result = async {
+            client
+                .get(self.url.join("/status")?)
+                .timeout(HEALTH_CHECK_TIMEOUT)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok::<_, types::Error>(())
+        }
+        .await;
+
+        let mut health = self.health.write().unwrap();
+        match result {
+            Ok(()) if health.unhealthy => {
+                tracing::info!(url = %self.url, "kanidm server is back up");
+            }
+            Err(ref error) if !health.unhealthy => {
+                tracing::warn!(url = %self.url, ?error, "kanidm server is down");
+            }
+            _ => {}
+        }
+        *health = NodeHealth {
+            unhealthy: result.is_err(),
+            checked_at: Some(Timestamp::now()),
+            error: result.err().map(|e| e.to_string()),
+        };
+    }
+}
+
+/// Limits how many requests we have open to Kanidm at once, so bulk
+/// operations queue up rather than flooding it with connections.
+struct Limiter {
+    max: usize,
+    permits: Semaphore,
+    queued: AtomicUsize,
+}
+
+impl Limiter {
+    fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            permits: Semaphore::new(max),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// A request to Kanidm that waits for a free slot before it's sent.
+struct KanidmRequest {
+    builder: RequestBuilder,
+    limiter: Arc<Limiter>,
+    span: tracing::Span,
+    method: Method,
+    url: Url,
+    /// The JSON body, kept only while calls are being explained.
+    payload: Option<serde_json::Value>,
+    /// Whether it's made with the signed-in admin's token, rather than ours.
+    as_admin: bool,
+}
+
+impl KanidmRequest {
+    fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        Self {
+            builder: self.builder.json(json),
+            payload: explain::is_recording()
+                .then(|| serde_json::to_value(json).ok())
+                .flatten(),
+            ..self
+        }
+    }
+
+    async fn try_send<T: DeserializeOwned>(self) -> Result<T> {
+        let as_admin = self.as_admin;
+        self.send_limited().await.map_err(|error| {
+            let unauthorized = error
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.status() == Some(StatusCode::UNAUTHORIZED));
+            if as_admin && unauthorized {
+                AdminTokenExpired.into()
+            } else {
+                error
+            }
+        })
+    }
+
+    async fn send_limited<T: DeserializeOwned>(self) -> Result<T> {
+        self.limiter.queued.fetch_add(1, Ordering::Relaxed);
+        let permit = self.limiter.permits.acquire().await;
+        self.limiter.queued.fetch_sub(1, Ordering::Relaxed);
+        let _permit = permit?;
+
+        let started = Instant::now();
+        if !explain::is_recording() {
+            let result = self.builder.try_send().instrument(self.span).await;
+            capture::record_kanidm(
+                &self.method,
+                &self.url,
+                started.elapsed(),
+                result.as_ref().err(),
+            );
+            return result;
+        }
 
-pub static KANIDM_CLIENT: LazyLock<KanidmClient> =
-    LazyLock::new(|| KanidmClient::new(CONFIG.kanidm_url.clone(), CONFIG.kanidm_token.clone()));
+        // Go through a `Value` so the response can be kept as well.
+        let result: Result<serde_json::Value> = self.builder.try_send().instrument(self.span).await;
+        let elapsed = started.elapsed();
+        capture::record_kanidm(&self.method, &self.url, elapsed, result.as_ref().err());
+        explain::record(
+            &self.method,
+            &self.url,
+            elapsed,
+            self.payload.as_ref(),
+            result.as_ref(),
+        );
+        Ok(serde_json::from_value(result?)?)
+    }
+}
 
+/// Talks to Kanidm. Writes go to the primary server at `base_url`. Reads are
+/// spread over healthy replicas, if there are any, falling back to the
+/// primary.
 #[derive(Clone)]
 pub struct KanidmClient {
     client: Client,
     base_url: Url,
+    primary: Arc<Node>,
+    replicas: Arc<Vec<Node>>,
+    next_replica: Arc<AtomicUsize>,
+    limiter: Arc<Limiter>,
     token: SecretString,
 }
 
 impl KanidmClient {
-    fn new(base_url: Url, token: SecretString) -> Self {
+    fn new(
+        base_url: Url,
+        replica_urls: Vec<Url>,
+        token: SecretString,
+        max_concurrency: usize,
+    ) -> Self {
         Self {
             client: Client::new(),
+            primary: Arc::new(Node::new(base_url.clone())),
             base_url,
+            replicas: Arc::new(replica_urls.into_iter().map(Node::new).collect()),
+            next_replica: Arc::default(),
+            limiter: Arc::new(Limiter::new(max_concurrency)),
             token,
         }
     }
 
-    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
-        let url = self.base_url.join(path)?;
+    /// Keep checking whether each server is up, so reads can fail over to
+    /// another replica. Call this once, at startup.
+    pub fn spawn_health_checks(&'static self) {
+        if self.replicas.is_empty() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                for node in std::iter::once(&*self.primary).chain(self.replicas.iter()) {
+                    node.check(&self.client).await;
+                }
+            }
+        });
+    }
+
+    /// The server to send a read to: the next healthy replica in turn, or the
+    /// primary if none are healthy.
+    fn read_node(&self) -> &Node {
+        let start = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        (0..self.replicas.len())
+            .map(|i| &self.replicas[(start + i) % self.replicas.len()])
+            .find(|node| node.is_healthy())
+            .unwrap_or(&self.primary)
+    }
+
+    /// Where requests are currently being sent, for diagnostics.
+    pub fn nodes(&self) -> Vec<KanidmNode> {
+        // The primary only serves reads when no replica can.
+        let primary_serves_reads = !self.replicas.iter().any(Node::is_healthy);
+
+        std::iter::once((&*self.primary, true))
+            .chain(self.replicas.iter().map(|node| (node, false)))
+            .map(|(node, primary)| {
+                let health = node.health.read().unwrap().clone();
+                KanidmNode {
+                    url: node.url.to_string(),
+                    primary,
+                    healthy: !health.unhealthy,
+                    serving_reads: if primary {
+                        primary_serves_reads
+                    } else {
+                        !health.unhealthy
+                    },
+                    checked_at: health.checked_at,
+                    error: health.error,
+                }
+            })
+            .collect()
+    }
+
+    /// Check whether the primary server answers right now.
+    pub async fn is_up(&self) -> bool {
+        self.primary.check(&self.client).await;
+        self.primary.is_healthy()
+    }
+
+    /// How busy our connection to Kanidm is, for diagnostics.
+    pub fn load(&self) -> KanidmLoad {
+        KanidmLoad {
+            max_concurrency: self.limiter.max as u32,
+            in_flight: (self.limiter.max - self.limiter.permits.available_permits()) as u32,
+            queued: self.limiter.queued.load(Ordering::Relaxed) as u32,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<KanidmRequest> {
+        self.request_to(&self.base_url, method, path)
+    }
+
+    fn request_to(&self, base_url: &Url, method: Method, path: &str) -> Result<KanidmRequest> {
+        let url = base_url.join(path)?;
+        let span = tracing::info_span!("kanidm", %method, %url);
+        let admin_token = match WRITER.try_with(Clone::clone) {
+            _ if method == Method::GET => None,
+            Ok(Writer::Admin(token)) => Some(token),
+            Ok(Writer::Service) => None,
+            Err(_) if CONFIG.kanidm_per_admin_tokens => {
+                return Err(err!(
+                    "refusing to {method} {path} in Kanidm with AuthIt!'s own token outside \
+                     an admin's session; this is a bug"
+                ));
+            }
+            Err(_) => None,
+        };
+        let token = admin_token.as_ref().unwrap_or(&self.token);
+        let builder = span.in_scope(|| {
+            self.client
+                .request(method.clone(), url.clone())
+                .bearer_auth(token.expose_secret())
+                .with_trace_context()
+        });
 
-        Ok(self
-            .client
-            .request(method, url)
-            .bearer_auth(self.token.expose_secret()))
+        Ok(KanidmRequest {
+            builder,
+            limiter: self.limiter.clone(),
+            span,
+            method,
+            url,
+            payload: None,
+            as_admin: admin_token.is_some(),
+        })
     }
 
-    fn get(&self, path: impl AsRef<str>) -> Result<RequestBuilder> {
-        self.request(Method::GET, path.as_ref())
+    /// Read from Kanidm. This may go to a replica, so it might not see very
+    /// recent writes.
+    fn get(&self, path: impl AsRef<str>) -> Result<KanidmRequest> {
+        self.request_to(&self.read_node().url, Method::GET, path.as_ref())
     }
 
-    fn post(&self, path: impl AsRef<str>) -> Result<RequestBuilder> {
+    fn post(&self, path: impl AsRef<str>) -> Result<KanidmRequest> {
         self.request(Method::POST, path.as_ref())
     }
 
-    fn delete(&self, path: impl AsRef<str>) -> Result<RequestBuilder> {
+    fn put(&self, path: impl AsRef<str>) -> Result<KanidmRequest> {
+        self.request(Method::PUT, path.as_ref())
+    }
+
+    fn delete(&self, path: impl AsRef<str>) -> Result<KanidmRequest> {
         self.request(Method::DELETE, path.as_ref())
     }
 
-    pub async fn list_persons(&self) -> Result<Vec<Person>> {
+    /// A person's raw Kanidm entry, with every attribute we can read.
+    pub async fn get_person_attributes(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<BTreeMap<String, Vec<String>>> {
+        #[derive(Deserialize)]
+        struct RawEntry {
+            attrs: BTreeMap<String, Vec<String>>,
+        }
+
+        let entry: RawEntry = self
+            .get(format!("/v1/person/{user_id}"))?
+            .try_send()
+            .await?;
+        Ok(entry.attrs)
+    }
+
+    /// An entry exactly as Kanidm returns it, with secrets redacted.
+    pub async fn get_raw_entry(&self, kind: EntryKind, id: &Uuid) -> Result<serde_json::Value> {
+        let path = match kind {
+            EntryKind::Person => format!("/v1/person/{id}"),
+            EntryKind::Group => format!("/v1/group/{id}"),
+        };
+        let mut entry: serde_json::Value = self.get(path)?.try_send().await?;
+        if let Some(attrs) = entry.get_mut("attrs").and_then(|a| a.as_object_mut()) {
+            for (name, value) in attrs.iter_mut() {
+                if SECRET_ATTRIBUTES.contains(&name.as_str()) {
+                    *value = json!(["(redacted)"]);
+                }
+            }
+        }
+        Ok(entry)
+    }
+
+    pub async fn get_domain(&self) -> Result<DomainInfo> {
+        self.get("/v1/domain")?
+            .try_send::<Vec<RawDomain>>()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| err!("kanidm returned no domain entry"))?
+            .try_into()
+    }
+
+    /// The groups AuthIt!'s own service account is in, by SPN.
+    pub async fn own_groups(&self) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct RawEntry {
+            attrs: BTreeMap<String, Vec<String>>,
+        }
+        #[derive(Deserialize)]
+        struct Whoami {
+            youare: RawEntry,
+        }
+
+        let mut whoami: Whoami = self.get("/v1/self")?.try_send().await?;
+        Ok(whoami.youare.attrs.remove("memberof").unwrap_or_default())
+    }
+
+    pub async fn get_ldap_status(&self, user_id: &Uuid) -> Result<LdapStatus> {
+        let domain = self.get_domain().await?;
+        let entry = self
+            .get(format!("/v1/person/{user_id}"))?
+            .try_send::<RawLdapEntry>()
+            .await?;
+        LdapStatus::new(entry, &domain.ldap_basedn)
+    }
+
+    /// Who Kanidm lets manage a group, by SPN.
+    pub async fn get_entry_managed_by(&self, group_id: &Uuid) -> Result<Option<String>> {
+        let managers = self
+            .get(format!("/v1/group/{group_id}/_attr/entry_managed_by"))?
+            .try_send::<Option<Vec<String>>>()
+            .await?;
+        Ok(managers.and_then(|m| m.into_iter().next()))
+    }
+
+    /// Let `manager`, a group or service account, manage a group in Kanidm,
+    /// or with `None`, leave it to Kanidm's own admins.
+    pub async fn set_entry_managed_by(
+        &self,
+        group_id: &Uuid,
+        manager: Option<&Uuid>,
+    ) -> Result<()> {
+        let Some(manager) = manager else {
+            return self
+                .delete(format!("/v1/group/{group_id}/_attr/entry_managed_by"))?
+                .try_send()
+                .await;
+        };
+
+        self.put(format!("/v1/group/{group_id}/_attr/entry_managed_by"))?
+            .json(&[manager])
+            .try_send()
+            .await
+    }
+
+    /// The UUID of the group or service account called `id_or_name`, if
+    /// there is one. Only these can manage other entries.
+    pub async fn find_manager(&self, id_or_name: &str) -> Result<Option<Uuid>> {
+        #[derive(Deserialize)]
+        struct RawEntry {
+            attrs: RawAttrs,
+        }
+
+        #[derive(Deserialize)]
+        struct RawAttrs {
+            uuid: Vec<Uuid>,
+        }
+
+        for kind in ["group", "service_account"] {
+            let entry = self
+                .get(format!("/v1/{kind}/{id_or_name}"))?
+                .try_send::<Option<RawEntry>>()
+                .await?;
+            if let Some(uuid) = entry.and_then(|e| e.attrs.uuid.into_iter().next()) {
+                return Ok(Some(uuid));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check how far a new user has got with setting up their account.
+    pub async fn get_onboarding_signals(&self, user_id: &Uuid) -> Result<OnboardingSignals> {
+        let entry = self
+            .get(format!("/v1/person/{user_id}"))?
+            .try_send::<RawOnboardingEntry>()
+            .await?;
+        let credentials = self
+            .get(format!("/v1/person/{user_id}/_credential/_status"))?
+            .try_send::<RawCredentialStatus>()
+            .await?;
+        Ok(entry.into_signals(credentials))
+    }
+
+    /// Whether each person has set up credentials. People we couldn't check
+    /// are left out.
+    pub async fn credential_status(&'static self, user_ids: &[Uuid]) -> HashMap<Uuid, bool> {
+        let mut checks = tokio::task::JoinSet::new();
+        for &user_id in user_ids {
+            checks.spawn(async move {
+                let signals = self.get_onboarding_signals(&user_id).await;
+                (user_id, signals.map(|s| s.has_credentials))
+            });
+        }
+
+        let mut status = HashMap::new();
+        while let Some(result) = checks.join_next().await {
+            if let Ok((user_id, Ok(has_credentials))) = result {
+                status.insert(user_id, has_credentials);
+            }
+        }
+        status
+    }
+
+    /// A digest of a person's credentials and passkeys, which changes when
+    /// they set up or change any.
+    pub async fn credential_fingerprint(&self, user_id: &Uuid) -> Result<String> {
+        #[derive(Deserialize)]
+        struct RawEntry {
+            attrs: RawAttrs,
+        }
+
+        #[derive(Deserialize)]
+        struct RawAttrs {
+            #[serde(default)]
+            passkeys: Vec<serde_json::Value>,
+        }
+
+        #[derive(Deserialize)]
+        struct RawStatus {
+            #[serde(default)]
+            creds: Vec<serde_json::Value>,
+        }
+
+        let entry: RawEntry = self
+            .get(format!("/v1/person/{user_id}"))?
+            .try_send()
+            .await?;
+        let status: RawStatus = self
+            .get(format!("/v1/person/{user_id}/_credential/_status"))?
+            .try_send()
+            .await?;
+
+        let digest = Sha256::digest(serde_json::to_vec(&(status.creds, entry.attrs.passkeys))?);
+        Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+    }
+
+    /// A person's sign-in sessions, by id, with when each started. Kanidm
+    /// forgets them once they expire.
+    pub async fn sign_in_sessions(&self, user_id: &Uuid) -> Result<Vec<(Uuid, Timestamp)>> {
+        #[derive(Deserialize)]
+        struct RawSession {
+            session_id: Uuid,
+            issued_at: Timestamp,
+        }
+
+        let sessions: Vec<RawSession> = self
+            .get(format!("/v1/account/{user_id}/_user_auth_token"))?
+            .try_send()
+            .await?;
+        Ok(sessions
+            .into_iter()
+            .map(|s| (s.session_id, s.issued_at))
+            .collect())
+    }
+
+    /// How many OAuth2 sessions a person has, across every client.
+    pub async fn oauth2_session_count(&self, user_id: &Uuid) -> Result<u32> {
+        let attrs = self.get_person_attributes(user_id).await?;
+        Ok(attrs.get("oauth2_session").map_or(0, |s| s.len() as u32))
+    }
+
+    /// Verify that the user's OAuth2 access token is still valid with Kanidm.
+    pub async fn verify_access_token(&self, access_token: &SecretString) -> Result<()> {
+        let url = self
+            .base_url
+            .join(&format!("oauth2/openid/{}/userinfo", CONFIG.oauth_client_id))?;
+
+        self.client
+            .get(url)
+            .bearer_auth(access_token.expose_secret())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for KanidmClient {
+    async fn list_persons(&self) -> Result<Vec<Person>> {
         self.get("/v1/person")?
             .try_send::<Vec<RawPerson>>()
             .await?
@@ -61,14 +670,34 @@ impl KanidmClient {
             .collect()
     }
 
-    pub async fn get_person(&self, id_or_name: &str) -> Result<Person> {
+    async fn get_person(&self, id_or_name: &str) -> Result<Person> {
         self.get(format!("/v1/person/{}", id_or_name))?
             .try_send::<RawPerson>()
             .await?
             .try_into()
     }
 
-    pub async fn list_groups(&self) -> Result<Vec<Group>> {
+    async fn find_person(&self, id_or_name: &str) -> Result<Option<Person>> {
+        let person = self
+            .get(format!("/v1/person/{}", id_or_name))?
+            .try_send::<Option<RawPerson>>()
+            .await;
+        match person {
+            Ok(person) => person.map(TryInto::try_into).transpose(),
+            Err(error) if is_not_found(&error) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn get_person_groups(&self, user_id: &PersonId) -> Result<Vec<String>> {
+        let groups = self
+            .get(format!("/v1/person/{user_id}/_attr/memberof"))?
+            .try_send::<Option<Vec<String>>>()
+            .await?;
+        Ok(groups.unwrap_or_default())
+    }
+
+    async fn list_groups(&self) -> Result<Vec<Group>> {
         self.get("/v1/group")?
             .try_send::<Vec<RawGroup>>()
             .await?
@@ -77,65 +706,165 @@ impl KanidmClient {
             .collect()
     }
 
-    pub async fn add_user_to_group(&self, id_or_name: &str, user_id: &Uuid) -> Result<()> {
-        self.post(format!("/v1/group/{id_or_name}/_attr/member"))?
+    async fn get_group(&self, group_id: &GroupId) -> Result<Group> {
+        self.get(format!("/v1/group/{group_id}"))?
+            .try_send::<RawGroup>()
+            .await?
+            .try_into()
+    }
+
+    async fn find_group(&self, name: &GroupName) -> Result<Group> {
+        self.get(format!("/v1/group/{name}"))?
+            .try_send::<RawGroup>()
+            .await?
+            .try_into()
+    }
+
+    async fn create_group(&self, name: &GroupName, description: Option<&str>) -> Result<()> {
+        let mut attrs = json!({ "name": [name] });
+        if let Some(description) = description {
+            attrs["description"] = json!([description]);
+        }
+        self.post("/v1/group")?
+            .json(&json!({ "attrs": attrs }))
+            .try_send()
+            .await
+    }
+
+    async fn delete_group(&self, group_id: &GroupId) -> Result<()> {
+        self.delete(format!("/v1/group/{group_id}"))?
+            .try_send()
+            .await
+    }
+
+    async fn list_group_members(&self, group_id: &GroupId) -> Result<Vec<String>> {
+        let members = self
+            .get(format!("/v1/group/{group_id}/_attr/member"))?
+            .try_send::<Option<Vec<String>>>()
+            .await?;
+        Ok(members.unwrap_or_default())
+    }
+
+    async fn add_user_to_group(&self, group_id: &GroupId, user_id: &PersonId) -> Result<()> {
+        self.post(format!("/v1/group/{group_id}/_attr/member"))?
             .json(&vec![user_id])
             .try_send()
             .await
     }
 
-    pub async fn remove_user_from_group(&self, group_id: &Uuid, user_id: &Uuid) -> Result<()> {
+    async fn add_group_members(&self, group_id: &GroupId, members: &[String]) -> Result<()> {
+        self.post(format!("/v1/group/{group_id}/_attr/member"))?
+            .json(&members)
+            .try_send()
+            .await
+    }
+
+    async fn remove_user_from_group(&self, group_id: &GroupId, user_id: &PersonId) -> Result<()> {
         self.delete(format!("/v1/group/{group_id}/_attr/member"))?
             .json(&vec![user_id])
             .try_send()
             .await
     }
 
-    pub async fn delete_person(&self, user_id: &Uuid) -> Result<()> {
-        self.delete(format!("/v1/person/{user_id}"))?
+    async fn set_email_addresses(&self, user_id: &PersonId, addresses: &[String]) -> Result<()> {
+        if addresses.is_empty() {
+            return self
+                .delete(format!("/v1/person/{user_id}/_attr/mail"))?
+                .try_send()
+                .await;
+        }
+
+        self.put(format!("/v1/person/{user_id}/_attr/mail"))?
+            .json(addresses)
+            .try_send()
+            .await
+    }
+
+    async fn set_display_name(&self, user_id: &PersonId, display_name: &str) -> Result<()> {
+        self.put(format!("/v1/person/{user_id}/_attr/displayname"))?
+            .json(&[display_name])
+            .try_send()
+            .await
+    }
+
+    async fn set_legal_name(&self, user_id: &PersonId, legal_name: Option<&str>) -> Result<()> {
+        let Some(legal_name) = legal_name else {
+            return self
+                .delete(format!("/v1/person/{user_id}/_attr/legalname"))?
+                .try_send()
+                .await;
+        };
+
+        self.put(format!("/v1/person/{user_id}/_attr/legalname"))?
+            .json(&[legal_name])
             .try_send()
             .await
     }
 
-    pub async fn create_person_with_link(
+    async fn enable_posix(
         &self,
-        user_name: &str,
-        display_name: &str,
-        email_address: &str,
-    ) -> Result<ResetLink> {
-        self.create_person(user_name, display_name, email_address)
-            .await?;
-        let person = self.get_person(user_name).await?;
-        self.generate_credential_reset_link(&person.uuid).await
+        user_id: &PersonId,
+        gid_number: Option<u32>,
+        login_shell: Option<&str>,
+    ) -> Result<()> {
+        self.post(format!("/v1/person/{user_id}/_unix"))?
+            .json(&json!({ "gidnumber": gid_number, "shell": login_shell }))
+            .try_send()
+            .await
     }
 
-    pub async fn create_person(
+    async fn expire_account(&self, user_id: &PersonId, at: Timestamp) -> Result<()> {
+        self.put(format!("/v1/person/{user_id}/_attr/account_expire"))?
+            .json(&vec![at.strftime("%Y-%m-%dT%H:%M:%SZ").to_string()])
+            .try_send()
+            .await
+    }
+
+    async fn unexpire_account(&self, user_id: &PersonId) -> Result<()> {
+        self.delete(format!("/v1/person/{user_id}/_attr/account_expire"))?
+            .try_send()
+            .await
+    }
+
+    async fn delete_person(&self, user_id: &PersonId) -> Result<()> {
+        self.delete(format!("/v1/person/{user_id}"))?
+            .try_send()
+            .await
+    }
+
+    async fn create_person(
         &self,
         user_name: &str,
         display_name: &str,
         email_address: &str,
     ) -> Result<()> {
+        let mut attrs = json!({
+            "name": [user_name],
+            "displayname": [display_name],
+        });
+        if !email_address.is_empty() {
+            attrs["mail"] = json!([email_address]);
+        }
+
         self.post("/v1/person")?
-            .json(&json!({
-                "attrs": {
-                    "name": [user_name],
-                    "displayname": [display_name],
-                    "mail": [email_address]
-                }
-            }))
+            .json(&json!({ "attrs": attrs }))
             .try_send()
             .await
     }
 
-    pub async fn generate_credential_reset_link(&self, user_id: &Uuid) -> Result<ResetLink> {
+    async fn generate_credential_reset_link(&self, user_id: &PersonId) -> Result<ResetLink> {
         #[derive(serde::Deserialize)]
         struct TokenResponse {
             token: String,
             expiry_time: i64,
         }
 
+        // This creates the intent, so it has to go to the primary.
         let response: TokenResponse = self
-            .get(format!("/v1/person/{user_id}/_credential/_update_intent"))?
+            .request(
+                Method::GET,
+                &format!("/v1/person/{user_id}/_credential/_update_intent"),
+            )?
             .try_send()
             .await?;
 
@@ -147,20 +876,11 @@ impl KanidmClient {
             expires_at: Timestamp::new(response.expiry_time, 0)?,
         })
     }
+}
 
-    /// Verify that the user's OAuth2 access token is still valid with Kanidm.
-    pub async fn verify_access_token(&self, access_token: &SecretString) -> Result<()> {
-        let url = self
-            .base_url
-            .join(&format!("oauth2/openid/{}/userinfo", CONFIG.oauth_client_id))?;
-
-        self.client
-            .get(url)
-            .bearer_auth(access_token.expose_secret())
-            .send()
-            .await?
-            .error_for_status()?;
-
-        Ok(())
-    }
+/// Whether Kanidm answered that the entry a request was about doesn't exist.
+pub(crate) fn is_not_found(error: &types::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.status() == Some(StatusCode::NOT_FOUND))
 }
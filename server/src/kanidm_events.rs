@@ -0,0 +1,77 @@
+use types::{Result, kanidm::Person, notification::NotificationEvent};
+
+use crate::{
+    IDENTITY, KANIDM_CLIENT,
+    notify::{self, Notification},
+    storage::KanidmEventSyncs,
+};
+
+/// Record sign-ins and credential changes made in Kanidm as activity, so
+/// people's timelines show more than what was done through AuthIt!.
+///
+/// Kanidm has no audit log to read, so this compares what it says now with
+/// what it said last time: sessions it hasn't seen before are sign-ins, and a
+/// different digest of someone's credentials is a change. Sessions that start
+/// and expire between syncs are missed. If Kanidm won't list sessions to our
+/// token, only credential changes are recorded.
+pub async fn sync() -> Result<()> {
+    let mut sessions_readable = true;
+    for person in IDENTITY.list_persons().await? {
+        if let Err(error) = sync_person(&person, &mut sessions_readable).await {
+            tracing::warn!(?error, user = person.name, "failed to sync Kanidm events");
+        }
+    }
+    Ok(())
+}
+
+async fn sync_person(person: &Person, sessions_readable: &mut bool) -> Result<()> {
+    let fingerprint = KANIDM_CLIENT.credential_fingerprint(&person.uuid).await?;
+    let sessions = if *sessions_readable {
+        match KANIDM_CLIENT.sign_in_sessions(&person.uuid).await {
+            Ok(sessions) => Some(sessions),
+            Err(error) => {
+                tracing::warn!(?error, "can't list Kanidm sessions; skipping sign-ins");
+                *sessions_readable = false;
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let previous = KanidmEventSyncs::get(person.uuid).await?;
+    let mut sync = previous.clone().unwrap_or_default();
+
+    if let Some(sessions) = sessions {
+        for &(id, issued_at) in &sessions {
+            if sync.session_ids.contains(&id) {
+                continue;
+            }
+            notify::send(
+                Notification::new(
+                    NotificationEvent::KanidmSignIn,
+                    format!("{} signed in to Kanidm.", person.name),
+                )
+                .happened_at(issued_at)
+                .about_user(person.uuid, person.name.clone()),
+            );
+        }
+        // Expired sessions won't be listed again, so there's no need to keep
+        // their ids.
+        sync.session_ids = sessions.into_iter().map(|(id, _)| id).collect();
+    }
+
+    // The first sync has nothing to compare with.
+    if previous.is_some_and(|p| p.fingerprint != fingerprint) {
+        notify::send(
+            Notification::new(
+                NotificationEvent::KanidmCredentialsChanged,
+                format!("{}'s credentials changed in Kanidm.", person.name),
+            )
+            .about_user(person.uuid, person.name.clone()),
+        );
+    }
+    sync.fingerprint = fingerprint;
+
+    KanidmEventSyncs::set(person.uuid, &sync).await
+}
@@ -1,43 +1,128 @@
+pub mod account_request;
+mod action_link;
+pub mod announcement;
+pub mod api_usage;
+pub mod audit_chain;
+pub mod audit_export;
+pub mod audit_search;
 mod auth_routes;
+pub mod avatar;
+pub mod batch;
+pub mod bulk;
+pub mod capabilities;
+pub mod capture;
+pub mod changelog;
+pub mod collation;
 mod config;
+mod device;
+pub mod directory;
+pub mod email;
+pub mod embedded_assets;
+pub mod email_verification;
+pub mod explain;
+pub mod export;
+pub mod first_run;
+pub mod forget;
+pub mod group_creation;
+pub mod group_deletion;
+pub mod group_import;
+pub mod group_join;
+pub mod group_manager;
+pub mod group_owner;
+pub mod group_review;
+pub mod group_webhook;
+mod http_cache;
+pub mod idempotency;
+mod identity;
+pub mod integrity;
+pub mod invite_email;
+pub mod jobs;
 mod kanidm;
+pub mod kanidm_events;
+pub mod maintenance;
+pub mod membership;
+pub mod migrations;
+pub mod notify;
+pub mod offboarding;
+pub mod onboarding;
+pub mod password_policy;
+pub mod presence;
+pub mod profile_change;
+pub mod provision_approval;
+pub mod provision_claims;
+pub mod provision_follow_up;
+mod provision_host;
+pub mod provision_funnel;
+pub mod provision_quota;
+mod provision_reminder;
+mod proxy;
+mod public_listener;
+pub mod report;
+mod request_limit;
+pub mod required_email;
+pub mod reset_watch;
+pub mod retention;
+pub mod rotation;
+pub mod scheduled_membership;
+mod secret_source;
+pub mod settings_export;
+pub mod setup;
+pub mod shift_log;
+mod session_events;
+mod shutdown;
+mod signed_token;
+pub mod stats;
+mod status;
 pub mod storage;
+pub mod systemd;
+pub mod tags;
+pub mod ticket;
+mod telemetry;
 mod user_data;
+pub mod user_deletion;
+pub mod user_detail;
 pub mod uuid_v7;
+pub mod visibility;
 
 pub use user_data::UserData;
 
 use axum::Router;
 use axum::http::HeaderMap;
 use dioxus::fullstack::FullstackContext;
-use reqwest::RequestBuilder;
+use jiff::Timestamp;
+use reqwest::{RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
 use serde::de::DeserializeOwned;
-use types::{Result, err};
+use types::{Result, RetryAfter, err, ticket::TicketStatus};
 
 use crate::auth_routes::{AuthState, auth_router};
-pub use crate::config::CONFIG;
-pub use crate::kanidm::KANIDM_CLIENT;
+pub use crate::config::{CONFIG, show_config};
+pub use crate::explain::record_kanidm_calls;
+pub use crate::http_cache::cache_and_compress;
+pub use crate::identity::{GroupPlan, IDENTITY, IdentityBackend, IdentityProvider};
+pub use crate::kanidm::{KANIDM_CLIENT, as_service};
+pub use crate::maintenance::maintenance_gate;
+pub use crate::provision_host::{disable_provision_links, restrict_provision_host};
+pub use crate::proxy::resolve_forwarded;
+pub use crate::public_listener::serve_public;
+pub use crate::request_limit::{limit_request_size, throttle_account_request};
+pub use crate::shutdown::track_requests;
 pub use crate::storage::ProvisionLink;
 use crate::storage::Session;
-use tracing_subscriber::EnvFilter;
+pub use crate::telemetry::{init_tracing, trace_requests};
 
 pub const SESSION_COOKIE_NAME: &str = "authit_session";
 
-pub fn init_tracing() {
-    let filter = EnvFilter::builder()
-        .with_default_directive(CONFIG.log_level.into())
-        .from_env_lossy();
-
-    tracing_subscriber::fmt().with_env_filter(filter).init();
-}
-
 trait ReqwestExt {
     async fn try_send<T: DeserializeOwned>(self) -> Result<T>;
 }
 
 impl ReqwestExt for RequestBuilder {
     async fn try_send<T: DeserializeOwned>(self) -> Result<T> {
-        let response = self.send().await?.error_for_status()?;
+        let response = self.send().await?;
+        if let Some(retry) = retry_after(&response) {
+            return Err(retry.into());
+        }
+        let response = response.error_for_status()?;
         let body = response.bytes().await?;
 
         match serde_json::from_slice(&body) {
@@ -52,52 +137,221 @@ impl ReqwestExt for RequestBuilder {
         }
     }
 }
+
+/// How long to wait when Kanidm is too busy but doesn't say for how long.
+const DEFAULT_RETRY_AFTER: u64 = 30;
+
+/// How long a response asked us to wait, if it was a 429 or 503.
+/// `Retry-After` is either a number of seconds or a date.
+fn retry_after(response: &Response) -> Option<RetryAfter> {
+    if !matches!(
+        response.status(),
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        return None;
+    }
+    let value = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim);
+    let seconds = value
+        .and_then(|value| {
+            value.parse::<u64>().ok().or_else(|| {
+                let at = jiff::fmt::rfc2822::parse(value).ok()?.timestamp();
+                u64::try_from(at.duration_since(Timestamp::now()).as_secs()).ok()
+            })
+        })
+        .unwrap_or(DEFAULT_RETRY_AFTER);
+    Some(RetryAfter {
+        seconds: seconds.max(1),
+    })
+}
+
 pub async fn init() -> Result<Router> {
     storage::migrate().await?;
+    first_run::init().await?;
+    shutdown::handle_signals();
+    jobs::spawn();
+    KANIDM_CLIENT.spawn_health_checks();
+    capabilities::spawn_probes().await;
+
+    #[cfg(feature = "test-login")]
+    {
+        if !CONFIG.test_login_allowed() {
+            return Err(err!(
+                "built with test-login, which lets anyone sign in as an admin, but authit_url \
+                 isn't localhost; set test_login_allow_remote if this is really a test"
+            ));
+        }
+        tracing::warn!(
+            username = %CONFIG.test_login_username,
+            "built with test-login: anyone can sign in as an admin at /auth/test-login"
+        );
+    }
 
     let auth_state = AuthState::new()?;
-    Ok(auth_router(auth_state))
+    Ok(auth_router(auth_state)
+        .merge(avatar::router())
+        .merge(action_link::router())
+        .merge(group_join::router())
+        .merge(session_events::router())
+        .merge(status::router()))
 }
 
-async fn get_session_from_cookie() -> Result<Session> {
-    let headers: HeaderMap = FullstackContext::extract().await?;
-
+/// Find the session token in a request's cookies, if there is one.
+fn session_token(headers: &HeaderMap) -> Option<&str> {
     let cookie_header = headers
         .get(axum::http::header::COOKIE)
-        .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| err!("no cookies in request"))?;
+        .and_then(|v| v.to_str().ok())?;
 
-    for cookie_str in cookie_header.split(';') {
-        let cookie_str = cookie_str.trim();
-        if let Some(token) = cookie_str.strip_prefix(&format!("{}=", SESSION_COOKIE_NAME)) {
-            return Session::find_token(token).await;
-        }
-    }
+    cookie_header.split(';').find_map(|c| {
+        c.trim()
+            .strip_prefix(SESSION_COOKIE_NAME)?
+            .strip_prefix('=')
+    })
+}
 
-    Err(err!("session cookie not found"))
+async fn get_session_from_cookie() -> Result<Session> {
+    let headers: HeaderMap = FullstackContext::extract().await?;
+    let token = session_token(&headers).ok_or_else(|| err!("session cookie not found"))?;
+    Session::find_token(token).await
 }
 
 pub async fn get_current_user() -> Result<types::kanidm::Person> {
     let session = get_session_from_cookie().await?;
-    KANIDM_CLIENT.get_person(&session.user_data.username).await
+
+    // The break-glass and test-login accounts don't exist in Kanidm.
+    if session.user_data.is_local() {
+        return Ok(types::kanidm::Person {
+            uuid: uuid::Uuid::nil(),
+            name: session.user_data.username,
+            display_name: session.user_data.display_name,
+            legal_name: None,
+            email_addresses: Vec::new(),
+            groups: Vec::new(),
+            spn: None,
+            created_at: None,
+            modified: None,
+        });
+    }
+
+    IDENTITY.get_person(&session.user_data.username).await
+}
+
+/// List the current user's sessions, marking the one making this request.
+pub async fn list_current_user_sessions() -> Result<Vec<types::session::SessionInfo>> {
+    let current = get_session_from_cookie().await?;
+    let sessions = Session::list_for_user(&current.user_data.user_id).await?;
+    Ok(sessions
+        .iter()
+        .map(|s| s.info(s.id() == current.id()))
+        .collect())
 }
 
-async fn require_admin_session() -> dioxus::prelude::ServerFnResult<UserData> {
+/// Sign out one of the current user's other sessions. The browser using it
+/// locks straight away.
+pub async fn revoke_current_user_session(id: uuid::Uuid) -> Result<()> {
+    let current = get_session_from_cookie().await?;
+    if id == current.id() {
+        return Err(err!("sign out to end this session"));
+    }
+    let session = Session::find(id).await?;
+    if session.user_data.user_id != current.user_data.user_id {
+        return Err(err!("that session isn't yours"));
+    }
+    session.delete().await?;
+    tracing::info!(user = current.user_data.username, "revoked a session");
+    Ok(())
+}
+
+/// What the current user is allowed to see. This is only a hint for the UI;
+/// every server function still checks for itself.
+pub async fn current_access() -> Result<types::directory::Access> {
     let session = get_session_from_cookie().await?;
+    Ok(types::directory::Access {
+        admin: session.user_data.is_in_group(CONFIG.admin_group()),
+        directory: CONFIG.directory_enabled,
+        legal_names: can_see_legal_names(&session.user_data).await?,
+        group_owner: CONFIG.provision_links_enabled
+            && group_owner::is_owner(&session.user_data).await?,
+        provision_links: CONFIG.provision_links_enabled,
+        profile: (CONFIG.profile_policy().any() || email::is_configured())
+            && !session.user_data.is_local(),
+    })
+}
+
+/// Whether the user may see and edit people's legal names, as set in the
+/// field visibility settings.
+pub async fn can_see_legal_names(user: &UserData) -> Result<bool> {
+    let visibility = visibility::get().await?;
+    Ok(visibility::allows(&visibility.legal_name, user))
+}
+
+/// Hide people's legal names from a user who may not see them.
+pub async fn redact_legal_names(
+    user: &UserData,
+    persons: &mut [types::kanidm::Person],
+) -> Result<()> {
+    if !can_see_legal_names(user).await? {
+        for person in persons {
+            person.legal_name = None;
+        }
+    }
+    Ok(())
+}
+
+/// A Kanidm entry as pretty-printed JSON, without secrets or anything else
+/// `user` may not see.
+pub async fn raw_entry(
+    user: &UserData,
+    kind: types::kanidm::EntryKind,
+    id: &uuid::Uuid,
+) -> Result<String> {
+    let mut entry = KANIDM_CLIENT.get_raw_entry(kind, id).await?;
+    if !can_see_legal_names(user).await?
+        && let Some(attrs) = entry.get_mut("attrs").and_then(|a| a.as_object_mut())
+    {
+        attrs.remove("legalname");
+    }
+    Ok(serde_json::to_string_pretty(&entry)?)
+}
 
-    if !session.user_data.is_in_group(&CONFIG.admin_group) {
+/// What the current user can do, given their role and what AuthIt!'s Kanidm
+/// token can do. Like [`current_access`], this is only a hint for the UI.
+pub async fn current_capabilities() -> Result<types::capability::Capabilities> {
+    Ok(types::capability::Capabilities {
+        access: current_access().await?,
+        token: capabilities::current(),
+    })
+}
+
+async fn require_admin_session() -> dioxus::prelude::ServerFnResult<Session> {
+    let session = require_session().await?;
+
+    if !session.user_data.is_in_group(CONFIG.admin_group()) {
         return Err(err!(
             "access denied: user '{}' must be in '{}' group",
             session.user_data.username,
-            CONFIG.admin_group
+            CONFIG.admin_group()
         )
         .into());
     }
 
-    if KANIDM_CLIENT
-        .verify_access_token(&session.user_data.access_token)
-        .await
-        .is_err()
+    api_usage::record(&session.user_data).await;
+    Ok(session)
+}
+
+async fn require_session() -> dioxus::prelude::ServerFnResult<Session> {
+    let session = get_session_from_cookie().await?;
+
+    // Break-glass and test-login sessions have no access token. Break-glass
+    // ones are short-lived instead.
+    if !session.user_data.is_local()
+        && KANIDM_CLIENT
+            .verify_access_token(&session.user_data.access_token)
+            .await
+            .is_err()
     {
         session.delete().await?;
         return Err(dioxus::prelude::ServerFnError::ServerError {
@@ -107,7 +361,73 @@ async fn require_admin_session() -> dioxus::prelude::ServerFnResult<UserData> {
         });
     }
 
-    Ok(session.user_data)
+    Ok(session)
+}
+
+fn check_recent_auth(session: &Session) -> dioxus::prelude::ServerFnResult<()> {
+    if session.is_recently_authenticated(CONFIG.step_up_max_age()) {
+        Ok(())
+    } else {
+        Err(types::step_up_required_error())
+    }
+}
+
+/// Refuse to let admins lock themselves out by acting on their own account,
+/// like removing themselves from the admin group or deleting themselves.
+pub async fn forbid_self_lockout(
+    user_id: uuid::Uuid,
+    message: &str,
+) -> dioxus::prelude::ServerFnResult<()> {
+    let session = get_session_from_cookie().await?;
+    // Kanidm uses the person's UUID as the OAuth2 subject.
+    if session.user_data.user_id == user_id.to_string() {
+        return Err(types::admin_lockout_error(message));
+    }
+    Ok(())
+}
+
+/// Require that the current user re-entered their credentials recently, and
+/// entered a ticket if that's required.
+///
+/// Use this for dangerous operations that are only conditionally dangerous;
+/// otherwise prefer [`with_recent_admin_session`].
+pub async fn require_recent_auth() -> dioxus::prelude::ServerFnResult<()> {
+    let session = get_session_from_cookie().await?;
+    check_recent_auth(&session)?;
+    check_ticket(&session).await
+}
+
+async fn check_ticket(session: &Session) -> dioxus::prelude::ServerFnResult<()> {
+    if session.user_data.ticket.is_none() && ticket::is_required().await? {
+        return Err(types::ticket_required_error());
+    }
+    Ok(())
+}
+
+/// The ticket the current admin is working on, and whether one is required.
+pub async fn get_ticket_status() -> dioxus::prelude::ServerFnResult<TicketStatus> {
+    let session = require_admin_session().await?;
+    Ok(TicketStatus {
+        ticket: session.user_data.ticket,
+        required: ticket::is_required().await?,
+    })
+}
+
+/// Set the ticket the current admin is working on, or clear it with `None`.
+/// It's kept until they clear it or sign out.
+pub async fn set_ticket(ticket: Option<String>) -> dioxus::prelude::ServerFnResult<()> {
+    let mut session = require_admin_session().await?;
+    session.user_data.ticket = ticket::normalize(ticket.as_deref())?;
+    session.save_user_data().await?;
+    Ok(())
+}
+
+/// Turn an error from inside a server function into a rich one, recording it
+/// if a diagnostic capture is running.
+async fn rich_error(error: types::Error) -> dioxus::prelude::ServerFnError {
+    let error = error.into_rich_server_error();
+    capture::record_server_fn_failure(&error).await;
+    error
 }
 
 /// Require admin session and return rich errors with backtraces for the inner block.
@@ -117,6 +437,39 @@ where
     F: FnOnce(UserData) -> Fut,
     Fut: std::future::Future<Output = Result<T>>,
 {
-    let user_data = require_admin_session().await?;
-    f(user_data).await.map_err(|e| e.into_rich_server_error())
+    let session = require_admin_session().await?;
+    match kanidm::acting_as(session.user_data, f).await {
+        Ok(value) => Ok(value),
+        Err(e) => Err(rich_error(e).await),
+    }
+}
+
+/// Like [`with_admin_session`], but for things any signed-in user may do.
+/// AuthIt! decides what they may change, so changes are made as us.
+pub async fn with_session<T, Fut, F>(f: F) -> dioxus::prelude::ServerFnResult<T>
+where
+    F: FnOnce(UserData) -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let session = require_session().await?;
+    match kanidm::as_service(f(session.user_data)).await {
+        Ok(value) => Ok(value),
+        Err(e) => Err(rich_error(e).await),
+    }
+}
+
+/// Like [`with_admin_session`], but for dangerous operations that also require
+/// the admin to have signed in again recently.
+pub async fn with_recent_admin_session<T, Fut, F>(f: F) -> dioxus::prelude::ServerFnResult<T>
+where
+    F: FnOnce(UserData) -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let session = require_admin_session().await?;
+    check_recent_auth(&session)?;
+    check_ticket(&session).await?;
+    match kanidm::acting_as(session.user_data, f).await {
+        Ok(value) => Ok(value),
+        Err(e) => Err(rich_error(e).await),
+    }
 }
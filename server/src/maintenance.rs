@@ -0,0 +1,118 @@
+use axum::{
+    Json, Router,
+    extract::Request,
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+};
+use jiff::Timestamp;
+use types::{
+    Result,
+    maintenance::Maintenance,
+    validation::{MAX_NOTES_LENGTH, check_length},
+};
+
+use crate::{
+    CONFIG,
+    notify::escape_html,
+    session_token, status,
+    storage::{Session, Settings},
+};
+
+/// What non-admins can still reach during maintenance: signing in, so admins
+/// can, and what the app needs to load and tell who's signed in.
+const OPEN_PATHS: &[&str] = &[
+    "/auth/",
+    "/login",
+    "/break-glass",
+    "/assets/",
+    "/wasm/",
+    "/api/current-user",
+    "/api/announcement/active",
+    status::PATH,
+];
+
+pub async fn get() -> Result<Option<Maintenance>> {
+    Settings::get(Settings::MAINTENANCE).await
+}
+
+pub async fn start(message: &str, admin: &str) -> Result<()> {
+    let message = message.trim();
+    check_length("message", message, MAX_NOTES_LENGTH)?;
+
+    let maintenance = Maintenance {
+        message: message.to_string(),
+        started_at: Timestamp::now(),
+        started_by: admin.to_string(),
+    };
+    Settings::set(Settings::MAINTENANCE, &maintenance).await?;
+    tracing::info!(by = admin, "maintenance mode started");
+    Ok(())
+}
+
+pub async fn end(admin: &str) -> Result<()> {
+    Settings::delete(Settings::MAINTENANCE).await?;
+    tracing::info!(by = admin, "maintenance mode ended");
+    Ok(())
+}
+
+/// During maintenance, show non-admins a maintenance page instead, and refuse
+/// their server function calls.
+pub fn maintenance_gate(router: Router) -> Router {
+    router.layer(middleware::from_fn(gate))
+}
+
+async fn gate(request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if OPEN_PATHS.iter().any(|p| path.starts_with(p)) {
+        return next.run(request).await;
+    }
+
+    let maintenance = match get().await {
+        Ok(Some(m)) => m,
+        Ok(None) => return next.run(request).await,
+        Err(error) => {
+            tracing::warn!(?error, "failed to check for maintenance mode");
+            return next.run(request).await;
+        }
+    };
+    if is_admin(request.headers()).await {
+        return next.run(request).await;
+    }
+
+    if path.starts_with("/api/") {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "message": "AuthIt! is down for maintenance",
+                "code": StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+                "details": { "maintenance": maintenance.message },
+            })),
+        )
+            .into_response();
+    }
+    page(&maintenance)
+}
+
+async fn is_admin(headers: &HeaderMap) -> bool {
+    let Some(token) = session_token(headers) else {
+        return false;
+    };
+    Session::find_token(token)
+        .await
+        .is_ok_and(|s| s.user_data.is_in_group(CONFIG.admin_group()))
+}
+
+fn page(maintenance: &Maintenance) -> Response {
+    let message = if maintenance.message.is_empty() {
+        "We'll be back soon.".to_string()
+    } else {
+        escape_html(&maintenance.message)
+    };
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Down for maintenance - AuthIt!</title></head>\
+        <body><h1>Down for maintenance</h1><p>{message}</p>\
+        <p><a href=\"/login\">Admins can still sign in.</a></p></body></html>"
+    );
+    (StatusCode::SERVICE_UNAVAILABLE, Html(html)).into_response()
+}
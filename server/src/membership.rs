@@ -0,0 +1,13 @@
+use types::{group_webhook::MembershipAction, ids::PersonId, kanidm::Group};
+
+use crate::{group_webhook, report};
+
+/// Follow up on someone having been added to or removed from a group:
+/// count it for reports and call the group's webhook.
+///
+/// The change has already been made, so failures are logged rather than
+/// returned.
+pub async fn changed(group: &Group, user_id: PersonId, action: MembershipAction) {
+    report::membership_changed(&group.group_name(), action);
+    group_webhook::queue(group.id(), user_id, action).await;
+}
@@ -0,0 +1,37 @@
+use types::{Result, migration::MigrationState};
+
+use crate::storage;
+
+/// Print every migration and where it stands.
+pub fn status() -> Result<String> {
+    let status = tokio::runtime::Runtime::new()?.block_on(storage::migration_status())?;
+
+    let mut out = String::new();
+    for m in &status.migrations {
+        out.push_str(&format!(
+            "{:<16} {:<20} {}\n",
+            m.version,
+            m.state.label(),
+            m.description
+        ));
+    }
+    out.push_str(&format!(
+        "\n{} applied, {} pending, {} with problems\n",
+        status.count(MigrationState::Applied),
+        status.count(MigrationState::Pending),
+        status.problems().count(),
+    ));
+    Ok(out)
+}
+
+/// Accept this build's checksums for applied migrations that have changed.
+pub fn repair() -> Result<String> {
+    let repaired =
+        tokio::runtime::Runtime::new()?.block_on(storage::repair_migration_checksums())?;
+
+    if repaired.is_empty() {
+        return Ok("No checksums needed repairing.\n".to_string());
+    }
+    let versions: Vec<String> = repaired.iter().map(i64::to_string).collect();
+    Ok(format!("Repaired checksums for {}.\n", versions.join(", ")))
+}
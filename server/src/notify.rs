@@ -0,0 +1,342 @@
+use std::{collections::BTreeSet, sync::LazyLock};
+
+use jiff::Timestamp;
+use reqwest::{Client, Url};
+use serde_json::json;
+use tracing::Instrument;
+use types::{
+    Result,
+    activity::{Activity, ActivityActor, ActivitySubject},
+    err,
+    notification::{
+        MatrixNotifier, NotificationChannel, NotificationEvent, NotificationSettings, SlackNotifier,
+    },
+    validation::{check_length, validate_email},
+};
+use uuid::Uuid;
+
+use crate::{
+    UserData, email, explain, shutdown,
+    storage::{Activities, ActivityKanidmCalls, Settings},
+    telemetry::TraceContextExt,
+};
+
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// The longest a notification rule's group or user pattern can be.
+const MAX_PATTERN_LENGTH: usize = 256;
+
+/// A message about something that happened. It's also recorded in the
+/// dashboard's activity stream.
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub summary: String,
+    pub details: Vec<(&'static str, String)>,
+    pub actor: Option<ActivityActor>,
+    pub subject: Option<ActivitySubject>,
+    pub ticket: Option<String>,
+    /// Every group it involved, for notification rules to match on.
+    pub groups: Vec<String>,
+    /// When it happened, if not now.
+    pub at: Option<Timestamp>,
+}
+
+impl Notification {
+    pub fn new(event: NotificationEvent, summary: impl Into<String>) -> Self {
+        Self {
+            event,
+            summary: summary.into(),
+            details: Vec::new(),
+            actor: None,
+            subject: None,
+            ticket: None,
+            groups: Vec::new(),
+            at: None,
+        }
+    }
+
+    /// Who did it, and the ticket they were working on, if any.
+    pub fn by(mut self, user: &UserData) -> Self {
+        self.actor = Some(ActivityActor {
+            user_id: user.user_id.parse().unwrap_or_default(),
+            name: user.username.clone(),
+        });
+        if let Some(ticket) = &user.ticket {
+            self.ticket = Some(ticket.clone());
+            self.details.push(("Ticket", ticket.clone()));
+        }
+        self
+    }
+
+    /// Which user it was about.
+    pub fn about_user(mut self, id: Uuid, name: impl Into<String>) -> Self {
+        self.subject = Some(ActivitySubject::User {
+            id,
+            name: name.into(),
+        });
+        self
+    }
+
+    /// Which group it was about.
+    pub fn about_group(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.groups.push(name.clone());
+        self.subject = Some(ActivitySubject::Group { name });
+        self
+    }
+
+    /// Groups it involved without being about them, like the groups someone
+    /// was added to.
+    pub fn involving_groups<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.groups.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// When it happened, for things found out about later, like sign-ins
+    /// read from Kanidm.
+    pub fn happened_at(mut self, at: Timestamp) -> Self {
+        self.at = Some(at);
+        self
+    }
+
+    pub fn detail(mut self, label: &'static str, value: impl Into<String>) -> Self {
+        self.details.push((label, value.into()));
+        self
+    }
+
+    fn slack_text(&self) -> String {
+        let mut text = format!("*{}*: {}", self.event.label(), self.summary);
+        for (label, value) in &self.details {
+            text.push_str(&format!("\n• {label}: {value}"));
+        }
+        text
+    }
+
+    fn plain_text(&self) -> String {
+        let mut text = format!("{}: {}", self.event.label(), self.summary);
+        for (label, value) in &self.details {
+            text.push_str(&format!("\n- {label}: {value}"));
+        }
+        text
+    }
+
+    fn html(&self) -> String {
+        let mut html = format!(
+            "<strong>{}</strong>: {}",
+            escape_html(self.event.label()),
+            escape_html(&self.summary)
+        );
+        if !self.details.is_empty() {
+            html.push_str("<ul>");
+            for (label, value) in &self.details {
+                html.push_str(&format!(
+                    "<li>{}: {}</li>",
+                    escape_html(label),
+                    escape_html(value)
+                ));
+            }
+            html.push_str("</ul>");
+        }
+        html
+    }
+}
+
+pub async fn get_settings() -> Result<NotificationSettings> {
+    Ok(Settings::get(Settings::NOTIFICATIONS)
+        .await?
+        .unwrap_or_default())
+}
+
+pub async fn set_settings(settings: NotificationSettings) -> Result<()> {
+    let settings = settings.keep_secrets_from(&get_settings().await?);
+    check_rules(&settings)?;
+    Settings::set(Settings::NOTIFICATIONS, &settings).await
+}
+
+/// Check that every rule can be delivered.
+pub fn check_rules(settings: &NotificationSettings) -> Result<()> {
+    for rule in &settings.rules {
+        check_length("group pattern", &rule.group, MAX_PATTERN_LENGTH)?;
+        check_length("user pattern", &rule.user, MAX_PATTERN_LENGTH)?;
+        match &rule.channel {
+            NotificationChannel::Email { to } => {
+                validate_email(to)?;
+                if !email::is_configured() {
+                    return Err(err!("rules can't send email, as email isn't configured"));
+                }
+            }
+            NotificationChannel::Slack if settings.slack.is_none() => {
+                return Err(err!("rules can't send to Slack until it's set up"));
+            }
+            NotificationChannel::Matrix if settings.matrix.is_none() => {
+                return Err(err!("rules can't send to Matrix until it's set up"));
+            }
+            NotificationChannel::Slack | NotificationChannel::Matrix => {}
+        }
+    }
+    Ok(())
+}
+
+/// Record a notification in the activity stream, and send it to every
+/// notifier subscribed to its event. This runs in the background; failures are
+/// logged.
+pub fn send(notification: Notification) {
+    let span = tracing::info_span!("notification", event = ?notification.event);
+    let calls = explain::take();
+    shutdown::spawn(
+        async move {
+            let activity = Activity {
+                id: Uuid::now_v7(),
+                at: notification.at.unwrap_or_else(Timestamp::now),
+                event: notification.event,
+                summary: notification.summary.clone(),
+                actor: notification.actor.clone(),
+                subject: notification.subject.clone(),
+                ticket: notification.ticket.clone(),
+            };
+            match Activities::record(&activity).await {
+                Ok(()) if !calls.is_empty() => {
+                    if let Err(error) = ActivityKanidmCalls::record(activity.id, &calls).await {
+                        tracing::warn!(?error, "failed to record activity's Kanidm calls");
+                    }
+                }
+                Ok(()) => {}
+                Err(error) => tracing::warn!(?error, "failed to record activity"),
+            }
+
+            let settings = match get_settings().await {
+                Ok(s) => s,
+                Err(error) => {
+                    tracing::warn!(?error, "failed to load notification settings");
+                    return;
+                }
+            };
+
+            if let Some(slack) = &settings.slack
+                && slack.events.contains(&notification.event)
+                && let Err(error) = send_slack(slack, &notification).await
+            {
+                tracing::warn!(?error, "failed to send Slack notification");
+            }
+
+            if let Some(matrix) = &settings.matrix
+                && matrix.events.contains(&notification.event)
+                && let Err(error) = send_matrix(matrix, &notification).await
+            {
+                tracing::warn!(?error, "failed to send Matrix notification");
+            }
+
+            send_by_rules(&settings, &notification).await;
+        }
+        .instrument(span),
+    );
+}
+
+/// Send a notification wherever the rules say, skipping notifiers that
+/// already got it by subscribing to its event.
+async fn send_by_rules(settings: &NotificationSettings, notification: &Notification) {
+    let user = match &notification.subject {
+        Some(ActivitySubject::User { name, .. }) => Some(name.as_str()),
+        _ => None,
+    };
+    let channels: BTreeSet<&NotificationChannel> = settings
+        .rules
+        .iter()
+        .filter(|rule| rule.matches(notification.event, user, &notification.groups))
+        .map(|rule| &rule.channel)
+        .collect();
+
+    for channel in channels {
+        match channel {
+            NotificationChannel::Email { to } => {
+                let subject = format!("AuthIt!: {}", notification.event.label());
+                email::try_send(to, &subject, notification.plain_text()).await;
+            }
+            NotificationChannel::Slack => {
+                if let Some(slack) = &settings.slack
+                    && !slack.events.contains(&notification.event)
+                    && let Err(error) = send_slack(slack, notification).await
+                {
+                    tracing::warn!(?error, "failed to send Slack notification");
+                }
+            }
+            NotificationChannel::Matrix => {
+                if let Some(matrix) = &settings.matrix
+                    && !matrix.events.contains(&notification.event)
+                    && let Err(error) = send_matrix(matrix, notification).await
+                {
+                    tracing::warn!(?error, "failed to send Matrix notification");
+                }
+            }
+        }
+    }
+}
+
+/// Send a notification to every configured notifier, regardless of event
+/// subscriptions, reporting any failure.
+pub async fn send_test(summary: String) -> Result<()> {
+    let settings = get_settings().await?;
+    let notification = Notification::new(NotificationEvent::UserCreated, summary);
+
+    if let Some(slack) = &settings.slack {
+        send_slack(slack, &notification).await?;
+    }
+    if let Some(matrix) = &settings.matrix {
+        send_matrix(matrix, &notification).await?;
+    }
+
+    Ok(())
+}
+
+async fn send_slack(slack: &SlackNotifier, notification: &Notification) -> Result<()> {
+    CLIENT
+        .post(&slack.webhook_url)
+        .with_trace_context()
+        .json(&json!({ "text": notification.slack_text() }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+async fn send_matrix(matrix: &MatrixNotifier, notification: &Notification) -> Result<()> {
+    let mut url = Url::parse(&matrix.homeserver)?;
+    let txn_id = Uuid::now_v7().to_string();
+    url.path_segments_mut()
+        .map_err(|_| types::err!("invalid Matrix homeserver URL"))?
+        .pop_if_empty()
+        .extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            &matrix.room_id,
+            "send",
+            "m.room.message",
+            &txn_id,
+        ]);
+
+    CLIENT
+        .put(url)
+        .bearer_auth(&matrix.access_token)
+        .with_trace_context()
+        .json(&json!({
+            "msgtype": "m.notice",
+            "body": notification.plain_text(),
+            "format": "org.matrix.custom.html",
+            "formatted_body": notification.html(),
+        }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,156 @@
+use jiff::Timestamp;
+use types::{
+    Result, err,
+    ids::PersonId,
+    notification::NotificationEvent,
+    offboarding::{ChecklistStep, ChecklistTemplate, Offboarding},
+    report::ReportEventKind,
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, UserData, forget, kanidm,
+    notify::{self, Notification},
+    report,
+    storage::{Offboardings, Settings},
+};
+
+pub async fn get_template() -> Result<ChecklistTemplate> {
+    Ok(Settings::get(Settings::OFFBOARDING_TEMPLATE)
+        .await?
+        .unwrap_or_else(ChecklistTemplate::offboarding_default))
+}
+
+pub async fn set_template(template: ChecklistTemplate) -> Result<()> {
+    let steps: Vec<String> = template
+        .steps
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    Settings::set(Settings::OFFBOARDING_TEMPLATE, &ChecklistTemplate { steps }).await
+}
+
+/// Start offboarding a user with a checklist from the current template.
+pub async fn start(user_id: Uuid, admin: &UserData) -> Result<Offboarding> {
+    if Offboardings::has_active(user_id).await? {
+        return Err(err!("this user is already being offboarded"));
+    }
+
+    let person = IDENTITY.get_person(&user_id.to_string()).await?;
+    let steps = get_template()
+        .await?
+        .steps
+        .into_iter()
+        .map(ChecklistStep::new)
+        .collect();
+
+    Offboardings::create(user_id, &person.name, &admin.username, steps).await
+}
+
+/// Mark a step as done or not done.
+pub async fn set_step(id: Uuid, index: usize, done: bool, admin: &UserData) -> Result<Offboarding> {
+    let mut offboarding = Offboardings::find(id).await?;
+    let step = offboarding
+        .steps
+        .get_mut(index)
+        .ok_or_else(|| err!("no step {index}"))?;
+
+    if done {
+        step.done_by = Some(admin.username.clone());
+        step.done_at = Some(Timestamp::now());
+    } else {
+        step.done_by = None;
+        step.done_at = None;
+    }
+
+    offboarding.completed_at = offboarding
+        .steps
+        .iter()
+        .all(ChecklistStep::is_done)
+        .then(Timestamp::now);
+
+    Offboardings::update(&offboarding).await?;
+    Ok(offboarding)
+}
+
+/// Schedule the user's Kanidm account for deletion, or cancel a scheduled
+/// deletion.
+pub async fn schedule_deletion(id: Uuid, delete_at: Option<Timestamp>) -> Result<Offboarding> {
+    let mut offboarding = Offboardings::find(id).await?;
+    if offboarding.deleted_at.is_some() {
+        return Err(err!("this account has already been deleted"));
+    }
+
+    offboarding.delete_at = delete_at;
+    Offboardings::update(&offboarding).await?;
+    Ok(offboarding)
+}
+
+/// Stop the user from signing in by expiring their account now.
+pub async fn disable_account(id: Uuid) -> Result<()> {
+    let offboarding = Offboardings::find(id).await?;
+    IDENTITY
+        .expire_account(&PersonId(offboarding.user_id), Timestamp::now())
+        .await
+}
+
+/// Delete the accounts of offboarded users whose scheduled deletion has come
+/// due.
+pub async fn run_due_deletions() -> Result<()> {
+    for mut offboarding in Offboardings::due_for_deletion(Timestamp::now()).await? {
+        let user_id = offboarding.user_id.to_string();
+        // Someone may have deleted them already; that still finishes the
+        // offboarding, rather than failing it every time this runs.
+        let person = match IDENTITY.find_person(&user_id).await {
+            Ok(Some(person)) => match IDENTITY.delete_person(&person.id()).await {
+                Ok(()) => Some(person),
+                Err(error) if kanidm::is_not_found(&error) => None,
+                Err(error) => {
+                    tracing::warn!(
+                        ?error,
+                        user = offboarding.user_name,
+                        "failed scheduled deletion"
+                    );
+                    continue;
+                }
+            },
+            Ok(None) => None,
+            Err(error) => {
+                tracing::warn!(
+                    ?error,
+                    user = offboarding.user_name,
+                    "failed scheduled deletion"
+                );
+                continue;
+            }
+        };
+
+        if person.is_some() {
+            tracing::info!(user = offboarding.user_name, "deleted offboarded user");
+            report::record(ReportEventKind::AccountDeleted);
+            notify::send(
+                Notification::new(
+                    NotificationEvent::UserDeleted,
+                    format!("{} was deleted as scheduled.", offboarding.user_name),
+                )
+                .detail("Offboarded by", offboarding.started_by.clone()),
+            );
+        } else {
+            tracing::info!(
+                user = offboarding.user_name,
+                "offboarded user was already deleted"
+            );
+        }
+
+        offboarding.deleted_at = Some(Timestamp::now());
+        Offboardings::update(&offboarding).await?;
+        match person {
+            Some(person) => forget::forget_user(&person).await?,
+            None => forget::forget_user_id(offboarding.user_id).await?,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use jiff::Timestamp;
+use types::{Result, onboarding::Onboarding};
+use uuid::Uuid;
+
+use crate::{KANIDM_CLIENT, storage::Onboardings};
+
+/// Start tracking a user who just created their account from a provision
+/// link. Failures are logged, as they shouldn't fail provisioning.
+pub async fn start(user_id: Uuid, user_name: &str, required_groups: &[String]) {
+    let result = match Onboardings::create(user_id, user_name, required_groups).await {
+        Ok(mut onboarding) => refresh(&mut onboarding).await,
+        Err(error) => Err(error),
+    };
+    if let Err(error) = result {
+        tracing::warn!(?error, user = user_name, "failed to start onboarding");
+    }
+}
+
+/// Check Kanidm for progress on every incomplete onboarding, returning them.
+pub async fn refresh_incomplete() -> Result<Vec<Onboarding>> {
+    let mut onboardings = Onboardings::list_incomplete().await?;
+    for onboarding in &mut onboardings {
+        if let Err(error) = refresh(onboarding).await {
+            tracing::warn!(
+                ?error,
+                user = onboarding.user_name,
+                "failed to check onboarding"
+            );
+        }
+    }
+    Ok(onboardings)
+}
+
+/// Used as a background job.
+pub async fn refresh_all() -> Result<()> {
+    refresh_incomplete().await?;
+    Ok(())
+}
+
+async fn refresh(onboarding: &mut Onboarding) -> Result<()> {
+    let signals = KANIDM_CLIENT
+        .get_onboarding_signals(&onboarding.user_id)
+        .await?;
+    let now = Timestamp::now();
+    let before = onboarding.clone();
+
+    if signals.has_credentials {
+        onboarding.credentials_set_at.get_or_insert(now);
+    }
+    if onboarding
+        .required_groups
+        .iter()
+        .all(|g| signals.groups.contains(g))
+    {
+        onboarding.groups_added_at.get_or_insert(now);
+    }
+    if signals.has_signed_in {
+        onboarding.first_login_at.get_or_insert(now);
+    }
+
+    if *onboarding != before {
+        Onboardings::update(onboarding).await?;
+    }
+    Ok(())
+}
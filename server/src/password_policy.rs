@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use jiff::Timestamp;
+use secrecy::{ExposeSecret, SecretString};
+use types::{Result, err};
+
+use crate::CONFIG;
+
+fn argon2() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+}
+
+/// Check a new password against the configured policy. `user_inputs` are
+/// words a guesser would try first, like the username.
+pub fn check(password: &SecretString, user_inputs: &[&str]) -> Result<()> {
+    let policy = CONFIG.password_policy();
+    let password = password.expose_secret();
+
+    if password.chars().count() < policy.min_length as usize {
+        return Err(err!(
+            "password must be at least {} characters",
+            policy.min_length
+        ));
+    }
+
+    let entropy = zxcvbn::zxcvbn(password, user_inputs);
+    let bits = entropy.guesses_log10() * std::f64::consts::LOG2_10;
+    if bits < f64::from(policy.min_entropy_bits) {
+        let mut message = format!(
+            "password is too easy to guess: about {bits:.0} bits of entropy, but {} are required",
+            policy.min_entropy_bits
+        );
+        if let Some(warning) = entropy.feedback().and_then(|f| f.warning()) {
+            message.push_str(&format!(". {warning}"));
+        }
+        return Err(err!("{message}"));
+    }
+
+    Ok(())
+}
+
+/// Hash a password with Argon2id, for storage.
+pub fn hash(password: &SecretString) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(argon2()
+        .hash_password(password.expose_secret().as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Whether `password` matches a hash from [`hash`]. The hash's own parameters
+/// are used, so older hashes keep working.
+pub fn verify(password: &SecretString, hash: &str) -> Result<bool> {
+    let hash = PasswordHash::new(hash)?;
+    Ok(argon2()
+        .verify_password(password.expose_secret().as_bytes(), &hash)
+        .is_ok())
+}
+
+/// When a password set at `updated_at` must be changed by, if ever.
+pub fn expires_at(updated_at: Timestamp) -> Option<Timestamp> {
+    let days = CONFIG.password_policy().rotation_days;
+    (days > 0).then(|| updated_at + Duration::from_secs(u64::from(days) * 24 * 3600))
+}
@@ -0,0 +1,26 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use jiff::Timestamp;
+use types::Result;
+use uuid::Uuid;
+
+use crate::{CONFIG, session_events::RECHECK_INTERVAL, storage::Session};
+
+/// How long after a browser last checked in its user still counts as online.
+/// A few check-ins, so one slow one doesn't make them flicker.
+const ONLINE_FOR: Duration = Duration::from_secs(RECHECK_INTERVAL.as_secs() * 3);
+
+/// The Kanidm ids of admins with AuthIt! open in a browser right now.
+///
+/// The break-glass and test-login accounts aren't in Kanidm, so they're never
+/// listed.
+pub async fn online_admins() -> Result<Vec<Uuid>> {
+    let cutoff = Timestamp::now() - ONLINE_FOR;
+    let online: BTreeSet<Uuid> = Session::seen_since(cutoff)
+        .await?
+        .into_iter()
+        .filter(|user| user.is_in_group(CONFIG.admin_group()))
+        .filter_map(|user| user.user_id.parse().ok())
+        .collect();
+    Ok(online.into_iter().collect())
+}
@@ -0,0 +1,211 @@
+use types::{
+    Result, err,
+    kanidm::Person,
+    notification::NotificationEvent,
+    profile_change::{ChangePolicy, MyProfile, ProfileChange, ProfileChangeStatus, ProfileField},
+    validation::{MAX_DISPLAY_NAME_LENGTH, MAX_NOTES_LENGTH, check_length, validate_email},
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, UserData, email, email_verification,
+    notify::{self, Notification},
+    storage::ProfileChanges,
+};
+
+/// The signed-in user's profile, what they may change, and the changes
+/// they've asked for.
+pub async fn my_profile(user: &UserData) -> Result<MyProfile> {
+    let user_id = kanidm_id(user)?;
+    let person = IDENTITY.get_person(&user.user_id).await?;
+    let email_addresses = email_verification::statuses(&person).await?;
+    Ok(MyProfile {
+        display_name: person.display_name,
+        email: person.email_addresses.into_iter().next(),
+        policy: CONFIG.profile_policy(),
+        changes: ProfileChanges::list_for_user(user_id).await?,
+        email_addresses,
+        can_verify_email: email::is_configured(),
+    })
+}
+
+/// Change a field on the user's own profile, as far as the policy for it
+/// allows. Returns whether it was made straight away; otherwise it waits for
+/// an admin.
+pub async fn propose(user: &UserData, field: ProfileField, value: &str) -> Result<bool> {
+    let user_id = kanidm_id(user)?;
+    let policy = CONFIG.profile_policy().of(field);
+    if policy == ChangePolicy::Off {
+        return Err(err!("you can't change your {}", field_name(field)));
+    }
+
+    let value = value.trim();
+    validate(field, value)?;
+
+    let person = IDENTITY.get_person(&user.user_id).await?;
+    let old_value = current(&person, field);
+    if old_value == value {
+        return Err(err!("that's already your {}", field_name(field)));
+    }
+
+    if policy == ChangePolicy::Immediate {
+        apply(&person, field, value).await?;
+        tracing::info!(
+            user = person.name,
+            field = field.as_str(),
+            "profile changed"
+        );
+        notify::send(
+            Notification::new(
+                NotificationEvent::ProfileChanged,
+                format!("{} changed their {}.", person.name, field_name(field)),
+            )
+            .by(user)
+            .about_user(user_id, &person.name)
+            .detail("From", old_value)
+            .detail("To", value),
+        );
+        return Ok(true);
+    }
+
+    if ProfileChanges::has_pending(user_id, field).await? {
+        return Err(err!(
+            "you've already asked to change your {}",
+            field_name(field)
+        ));
+    }
+    ProfileChanges::create(user_id, &person.name, field, &old_value, value).await?;
+    tracing::info!(
+        user = person.name,
+        field = field.as_str(),
+        "profile change requested"
+    );
+    notify::send(
+        Notification::new(
+            NotificationEvent::ProfileChangeRequested,
+            format!(
+                "{} asked to change their {}.",
+                person.name,
+                field_name(field)
+            ),
+        )
+        .by(user)
+        .about_user(user_id, &person.name)
+        .detail("From", old_value)
+        .detail("To", value),
+    );
+    Ok(false)
+}
+
+/// Approve a change, making it in Kanidm.
+pub async fn approve(id: Uuid, admin: &UserData) -> Result<()> {
+    let change = ProfileChanges::find(id).await?;
+    if change.status != ProfileChangeStatus::Pending {
+        return Err(err!("profile change has already been decided"));
+    }
+
+    // Decide first, so two admins can't both apply it.
+    ProfileChanges::decide(id, ProfileChangeStatus::Approved, None, &admin.username).await?;
+    if let Err(error) = apply_change(&change).await {
+        ProfileChanges::reopen(id).await?;
+        return Err(error);
+    }
+
+    tracing::info!(
+        user = change.user_name,
+        field = change.field.as_str(),
+        admin = admin.username,
+        "approved profile change"
+    );
+    notify::send(
+        Notification::new(
+            NotificationEvent::ProfileChanged,
+            format!(
+                "{} approved {}'s change to their {}.",
+                admin.username,
+                change.user_name,
+                field_name(change.field)
+            ),
+        )
+        .by(admin)
+        .about_user(change.user_id, &change.user_name)
+        .detail("From", change.old_value)
+        .detail("To", change.new_value),
+    );
+    Ok(())
+}
+
+/// Deny a change. The reason is shown on the user's profile page.
+pub async fn deny(id: Uuid, reason: &str, admin: &UserData) -> Result<()> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(err!("a reason is required"));
+    }
+    check_length("reason", reason, MAX_NOTES_LENGTH)?;
+
+    ProfileChanges::decide(
+        id,
+        ProfileChangeStatus::Denied,
+        Some(reason),
+        &admin.username,
+    )
+    .await
+}
+
+async fn apply_change(change: &ProfileChange) -> Result<()> {
+    let person = IDENTITY.get_person(&change.user_id.to_string()).await?;
+    validate(change.field, &change.new_value)?;
+    apply(&person, change.field, &change.new_value).await
+}
+
+async fn apply(person: &Person, field: ProfileField, value: &str) -> Result<()> {
+    match field {
+        ProfileField::DisplayName => IDENTITY.set_display_name(&person.id(), value).await,
+        ProfileField::Email => {
+            // The new address becomes their primary one; any others stay.
+            let addresses: Vec<String> = std::iter::once(value.to_string())
+                .chain(
+                    person
+                        .email_addresses
+                        .iter()
+                        .skip(1)
+                        .filter(|a| !a.eq_ignore_ascii_case(value))
+                        .cloned(),
+                )
+                .collect();
+            IDENTITY.set_email_addresses(&person.id(), &addresses).await
+        }
+    }
+}
+
+fn validate(field: ProfileField, value: &str) -> Result<()> {
+    match field {
+        ProfileField::DisplayName => {
+            if value.is_empty() {
+                return Err(err!("a display name is required"));
+            }
+            check_length("display name", value, MAX_DISPLAY_NAME_LENGTH)
+        }
+        ProfileField::Email => validate_email(value),
+    }
+}
+
+fn current(person: &Person, field: ProfileField) -> String {
+    match field {
+        ProfileField::DisplayName => person.display_name.clone(),
+        ProfileField::Email => person.email_addresses.first().cloned().unwrap_or_default(),
+    }
+}
+
+fn field_name(field: ProfileField) -> String {
+    field.label().to_lowercase()
+}
+
+/// The user's Kanidm ID. Sessions that didn't come from Kanidm have no
+/// profile to change.
+fn kanidm_id(user: &UserData) -> Result<Uuid> {
+    if user.is_local() {
+        return Err(err!("this session has no Kanidm account"));
+    }
+    Ok(user.user_id.parse()?)
+}
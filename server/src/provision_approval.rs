@@ -0,0 +1,171 @@
+use jiff::Timestamp;
+use types::{
+    Result, err,
+    group_webhook::MembershipAction,
+    ids::PersonId,
+    kanidm::Person,
+    notification::NotificationEvent,
+    provision::{ProvisionApprovalOutcome, ProvisionApprovalStatus},
+    report::ReportEventKind,
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, ProvisionLink, UserData, email, forget, group_review, membership,
+    notify::{self, Notification},
+    onboarding, provision_follow_up, report,
+    storage::ProvisionApprovals,
+};
+
+/// Create an account that can't be signed in to until it's approved. If it
+/// can't be locked, it's deleted again rather than left open.
+pub async fn create_locked(user_name: &str, display_name: &str, email_address: &str) -> Result<()> {
+    IDENTITY
+        .create_person(user_name, display_name, email_address)
+        .await?;
+    let person = IDENTITY.get_person(user_name).await?;
+    if let Err(error) = IDENTITY
+        .expire_account(&person.id(), Timestamp::now())
+        .await
+    {
+        IDENTITY.delete_person(&person.id()).await?;
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Queue a locked account made with `link` for an admin to approve.
+pub async fn request(link: &ProvisionLink, person: &Person, email_address: &str) -> Result<()> {
+    ProvisionApprovals::create(
+        link.id(),
+        person.uuid,
+        &person.name,
+        &person.display_name,
+        email_address,
+        link.groups(),
+        link.created_by(),
+    )
+    .await?;
+    tracing::info!(user = person.name, "provisioned account awaiting approval");
+
+    let mut notification = Notification::new(
+        NotificationEvent::ProvisionApprovalRequested,
+        format!(
+            "{} created an account from a provision link, and it's waiting for approval.",
+            person.name
+        ),
+    )
+    .detail("Display name", person.display_name.clone())
+    .detail("Groups", link.groups().join(", "))
+    .about_user(person.uuid, person.name.clone());
+    if let Some(creator) = link.created_by() {
+        notification = notification.detail("Link made by", creator);
+    }
+    notify::send(notification);
+    Ok(())
+}
+
+/// Approve an account, unlocking it, adding it to its link's groups, and
+/// emailing its owner a link to set up their credentials.
+pub async fn approve(id: Uuid, admin: &UserData) -> Result<ProvisionApprovalOutcome> {
+    let approval = ProvisionApprovals::find(id).await?;
+    if approval.status != ProvisionApprovalStatus::Pending {
+        return Err(err!("this account has already been approved or rejected"));
+    }
+
+    group_review::check_groups(approval.groups.iter().map(String::as_str)).await?;
+
+    // Decide first, so two admins can't both act on it.
+    ProvisionApprovals::decide(id, ProvisionApprovalStatus::Approved, &admin.username).await?;
+    let user_id = PersonId(approval.user_id);
+    if let Err(error) = IDENTITY.unexpire_account(&user_id).await {
+        ProvisionApprovals::reopen(id).await?;
+        return Err(error);
+    }
+
+    for group_name in &approval.groups {
+        let group = IDENTITY.find_group(&group_name.as_str().into()).await?;
+        IDENTITY.add_user_to_group(&group.id(), &user_id).await?;
+        membership::changed(&group, user_id, MembershipAction::Added).await;
+    }
+    onboarding::start(approval.user_id, &approval.user_name, &approval.groups).await;
+
+    let reset_link = IDENTITY.generate_credential_reset_link(&user_id).await?;
+    // The link may have been deleted since, taking its follow-up with it.
+    let link = ProvisionLink::find(approval.link_id).await.ok();
+    let mut body = format!(
+        "Hi {},\n\n\
+        Your account, {}, has been approved. Use this link to set up how you \
+        sign in:\n\n\
+        {}\n",
+        approval.display_name, approval.user_name, reset_link.url,
+    );
+    if let Some(link) = &link {
+        let follow_up = link.follow_up();
+        if !follow_up.next_steps.is_empty() {
+            body.push_str(&format!("\n{}\n", follow_up.next_steps));
+        }
+        if let Some(url) = &follow_up.redirect_url {
+            body.push_str(&format!("\nOnce you're set up, continue to {url}\n"));
+        }
+        let person = IDENTITY.get_person(&approval.user_id.to_string()).await?;
+        provision_follow_up::account_ready(link, &person);
+    }
+    let emailed = email::is_configured()
+        && email::try_send(&approval.email, "Your account was approved", body).await;
+
+    tracing::info!(
+        user = approval.user_name,
+        admin = admin.username,
+        "approved provisioned account"
+    );
+    notify::send(
+        Notification::new(
+            NotificationEvent::UserProvisioned,
+            format!(
+                "{} approved {}'s account from a provision link.",
+                admin.username, approval.user_name
+            ),
+        )
+        .by(admin)
+        .detail("Display name", approval.display_name)
+        .detail("Groups", approval.groups.join(", "))
+        .about_user(approval.user_id, approval.user_name),
+    );
+
+    Ok(ProvisionApprovalOutcome {
+        reset_link,
+        emailed,
+    })
+}
+
+/// Reject an account, deleting it.
+pub async fn reject(id: Uuid, admin: &UserData) -> Result<()> {
+    let approval = ProvisionApprovals::find(id).await?;
+    ProvisionApprovals::decide(id, ProvisionApprovalStatus::Rejected, &admin.username).await?;
+
+    let person = IDENTITY.get_person(&approval.user_id.to_string()).await?;
+    if let Err(error) = IDENTITY.delete_person(&person.id()).await {
+        ProvisionApprovals::reopen(id).await?;
+        return Err(error);
+    }
+    report::record(ReportEventKind::AccountDeleted);
+    forget::forget_user(&person).await?;
+
+    tracing::info!(
+        user = person.name,
+        admin = admin.username,
+        "rejected provisioned account"
+    );
+    notify::send(
+        Notification::new(
+            NotificationEvent::UserDeleted,
+            format!(
+                "{} rejected and deleted {}'s account from a provision link.",
+                admin.username, person.name
+            ),
+        )
+        .by(admin),
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use types::Result;
+
+use crate::{
+    IDENTITY,
+    storage::{ProvisionLinkClaim, ProvisionLinkClaims},
+};
+
+/// How long making an account from a provision link can take before its
+/// claim is taken to have been left behind.
+const STALE_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// The account a claim was taken for was made. If this fails the claim stays
+/// pending, and [`settle_stale`] keeps it later.
+pub async fn keep(claim: ProvisionLinkClaim) {
+    if let Err(error) = claim.keep().await {
+        tracing::warn!(?error, "failed to keep provision link use");
+    }
+}
+
+/// The account a claim was taken for couldn't be made. If this fails the
+/// claim stays pending, and [`settle_stale`] gives it back later.
+pub async fn release(claim: ProvisionLinkClaim) {
+    if let Err(error) = claim.release().await {
+        tracing::warn!(?error, "failed to give back provision link use");
+    }
+}
+
+/// Settle claims left pending by AuthIt! stopping, or failing to reach the
+/// database, partway through making an account. Each use is kept if its
+/// account was made, and given back if it wasn't.
+pub async fn settle_stale() -> Result<()> {
+    let stale = ProvisionLinkClaims::claimed_before(Timestamp::now() - STALE_AFTER).await?;
+    for (claim, name) in stale {
+        match IDENTITY.find_person(&name).await {
+            Ok(Some(_)) => claim.keep().await?,
+            Ok(None) => {
+                tracing::info!(name, "giving back provision link use for unmade account");
+                claim.release().await?;
+            }
+            Err(error) => tracing::warn!(?error, name, "failed to settle provision link use"),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::{sync::LazyLock, time::Duration};
+
+use reqwest::Client;
+use serde_json::json;
+use types::{Result, kanidm::Person, server_info::ServerInfo};
+use uuid::Uuid;
+
+use crate::{ProvisionLink, group_webhook, shutdown, telemetry::TraceContextExt};
+
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// How long the link's webhook has to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait before each retry of a failed call.
+const RETRY_DELAYS: [Duration; 2] = [Duration::from_secs(30), Duration::from_secs(5 * 60)];
+
+/// Call the link's webhook, if it has one, now that `person`'s account is
+/// ready. It's called in the background, and retried a couple of times if it
+/// fails; failures are logged.
+pub fn account_ready(link: &ProvisionLink, person: &Person) {
+    let follow_up = link.follow_up();
+    let Some(url) = follow_up.webhook_url.clone() else {
+        return;
+    };
+    let secret = follow_up.webhook_secret.clone();
+    let id = Uuid::now_v7();
+    let body = json!({
+        "id": id,
+        "event": "account_ready",
+        "link": { "id": link.id() },
+        "user": {
+            "id": person.uuid,
+            "name": person.name,
+            "display_name": person.display_name,
+            "email_addresses": person.email_addresses,
+        },
+        "groups": link.groups(),
+        "authit": ServerInfo::current(),
+    })
+    .to_string();
+
+    shutdown::spawn(async move {
+        let mut delays = RETRY_DELAYS.into_iter();
+        loop {
+            let Err(error) = send(&url, &secret, id, &body).await else {
+                return;
+            };
+            let Some(delay) = delays.next() else {
+                tracing::warn!(?error, %id, "gave up on provision link webhook");
+                return;
+            };
+            tracing::warn!(?error, %id, "provision link webhook failed; retrying");
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown::started() => return,
+            }
+        }
+    });
+}
+
+/// POST the call, signed like group webhooks, so receivers can check both
+/// the same way. Receivers should use its id to ignore repeats.
+async fn send(url: &str, secret: &str, id: Uuid, body: &str) -> Result<()> {
+    CLIENT
+        .post(url)
+        .timeout(TIMEOUT)
+        .with_trace_context()
+        .header("content-type", "application/json")
+        .header("x-authit-delivery", id.to_string())
+        .header("x-authit-signature", group_webhook::sign(secret, body)?)
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
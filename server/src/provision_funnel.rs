@@ -0,0 +1,67 @@
+use jiff::{Timestamp, ToSpan};
+use reqwest::StatusCode;
+use types::{
+    Result, RetryAfter,
+    provision::{NameTaken, ProvisionFailureCause, ProvisionFailureCount, ProvisionLinkUnusable},
+    validation::{FieldErrors, TooLong},
+};
+use uuid::Uuid;
+
+use crate::storage::{ProvisionLinkEvent, ProvisionLinkEvents};
+
+/// How many days of failures [`recent_failure_causes`] counts.
+const RECENT_DAYS: i64 = 30;
+
+/// Note how far someone got with a provision link. Failures are logged, as
+/// they shouldn't stop the link being used.
+pub async fn record(link_id: Uuid, event: ProvisionLinkEvent) {
+    if let Err(error) = ProvisionLinkEvents::record(link_id, &event).await {
+        tracing::warn!(?error, %link_id, ?event, "failed to record provision link event");
+    }
+}
+
+/// Note that someone couldn't use a provision link, and why.
+pub async fn failed(link_id: Uuid, error: &types::Error) {
+    let cause = classify(error);
+    tracing::info!(%link_id, cause = cause.as_str(), %error, "provision link failed");
+    let event = ProvisionLinkEvent::Failed {
+        cause,
+        reason: error.to_string(),
+    };
+    record(link_id, event).await;
+}
+
+/// Why provision links have failed in the last [`RECENT_DAYS`] days, most
+/// common first.
+pub async fn recent_failure_causes() -> Result<Vec<ProvisionFailureCount>> {
+    let since = Timestamp::now().checked_sub((RECENT_DAYS * 24).hours())?;
+    ProvisionLinkEvents::failure_causes_since(since).await
+}
+
+/// What kind of problem an attempt to use a provision link ran into.
+fn classify(error: &types::Error) -> ProvisionFailureCause {
+    if error.downcast_ref::<NameTaken>().is_some() {
+        return ProvisionFailureCause::NameTaken;
+    }
+    if error.downcast_ref::<FieldErrors>().is_some() || error.downcast_ref::<TooLong>().is_some() {
+        return ProvisionFailureCause::Invalid;
+    }
+    if error.downcast_ref::<ProvisionLinkUnusable>().is_some() {
+        return ProvisionFailureCause::LinkExhausted;
+    }
+    if error.downcast_ref::<RetryAfter>().is_some() {
+        return ProvisionFailureCause::KanidmUnavailable;
+    }
+    match error.downcast_ref::<reqwest::Error>() {
+        // Kanidm refuses to create an entry whose name is taken.
+        Some(e) if e.status() == Some(StatusCode::CONFLICT) => ProvisionFailureCause::NameTaken,
+        Some(e)
+            if e.is_connect()
+                || e.is_timeout()
+                || e.status().is_some_and(|s| s.is_server_error()) =>
+        {
+            ProvisionFailureCause::KanidmUnavailable
+        }
+        _ => ProvisionFailureCause::Other,
+    }
+}
@@ -0,0 +1,81 @@
+use axum::{
+    Router,
+    extract::Request,
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+
+use crate::{CONFIG, public_listener};
+
+/// What the provision page needs: the page itself, the server functions it
+/// calls, and the app's static files.
+pub(crate) const PROVISION_PATHS: &[&str] = &[
+    "/provision/",
+    "/api/provision/verify",
+    "/api/provision/complete",
+    "/api/announcement/active",
+    "/assets/",
+    "/wasm/",
+];
+
+/// Where provision links are made, used, and their accounts approved, all of
+/// which go away when they're turned off.
+const ALL_PROVISION_PATHS: &[&str] = &["/provision/", "/api/provision/", "/api/my-groups/invite"];
+
+/// With `provision_links_enabled` off, answer every provision route with a
+/// 404, so the only unauthenticated routes left are for signing in.
+pub fn disable_provision_links(router: Router) -> Router {
+    if CONFIG.provision_links_enabled {
+        return router;
+    }
+    tracing::info!("provision links are turned off");
+    router.layer(middleware::from_fn(refuse_provision))
+}
+
+async fn refuse_provision(request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if ALL_PROVISION_PATHS.iter().any(|p| path.starts_with(p)) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    next.run(request).await
+}
+
+/// When provision links use their own domain, serve only the provision page
+/// there, and the admin UI, its API and signing in only on `authit_url`'s
+/// host. Other hosts that route here get the public pages, like the public
+/// listener serves.
+pub fn restrict_provision_host(router: Router) -> Router {
+    router.layer(middleware::from_fn(restrict))
+}
+
+async fn restrict(request: Request, next: Next) -> Response {
+    let Some(provision_host) = CONFIG
+        .provision_base_url
+        .as_ref()
+        .and_then(|u| u.host_str())
+    else {
+        return next.run(request).await;
+    };
+    if CONFIG.authit_url.host_str() == Some(provision_host) {
+        return next.run(request).await;
+    }
+
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .map(|h| h.rsplit_once(':').map_or(h, |(host, _)| host));
+    let is_host = |expected: &str| host.is_some_and(|h| h.eq_ignore_ascii_case(expected));
+    let path = request.uri().path();
+
+    let allowed = if is_host(provision_host) {
+        PROVISION_PATHS.iter().any(|p| path.starts_with(p))
+    } else {
+        CONFIG.authit_url.host_str().is_none_or(is_host) || public_listener::is_public(path)
+    };
+    if !allowed {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    next.run(request).await
+}
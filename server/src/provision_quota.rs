@@ -0,0 +1,50 @@
+use types::{Result, notification::NotificationEvent};
+
+use crate::{
+    CONFIG,
+    notify::{self, Notification},
+    storage::ProvisionedAccounts,
+};
+
+/// Whether `admin`'s links have created as many accounts as they're allowed
+/// today. This is a brake on links that are leaked or handed out too freely.
+pub async fn is_reached(admin: &str) -> Result<bool> {
+    let Some(quota) = CONFIG.provision_quota(admin) else {
+        return Ok(false);
+    };
+    Ok(ProvisionedAccounts::count_recent(admin).await? >= quota)
+}
+
+/// Count an account created from one of `admin`'s links, notifying when they
+/// near or reach their quota. Failures are logged, since the account exists
+/// either way.
+pub async fn record(admin: &str, user_name: &str) {
+    if let Err(error) = try_record(admin, user_name).await {
+        tracing::warn!(?error, admin, "failed to count provisioned account");
+    }
+}
+
+async fn try_record(admin: &str, user_name: &str) -> Result<()> {
+    ProvisionedAccounts::record(admin).await?;
+
+    let Some(quota) = CONFIG.provision_quota(admin) else {
+        return Ok(());
+    };
+    let count = ProvisionedAccounts::count_recent(admin).await?;
+    let warn_at = quota * 4 / 5;
+
+    let summary = if count == quota {
+        format!("{admin}'s provision links have reached their quota of {quota} accounts a day.")
+    } else if warn_at > 0 && count == warn_at {
+        format!("{admin}'s provision links have created {count} of their {quota} accounts today.")
+    } else {
+        return Ok(());
+    };
+    tracing::warn!(admin, count, quota, "provision quota threshold crossed");
+    notify::send(
+        Notification::new(NotificationEvent::ProvisionQuota, summary)
+            .detail("Latest account", user_name),
+    );
+
+    Ok(())
+}
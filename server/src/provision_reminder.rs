@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use types::{Result, notification::NotificationEvent};
+
+use crate::{
+    IDENTITY,
+    action_link::{self, Action},
+    email,
+    notify::{self, Notification},
+    storage::ProvisionLink,
+};
+
+/// How long before an unused link expires to remind its creator.
+const REMINDER_WINDOW: Duration = Duration::from_secs(24 * 3600);
+
+/// Remind admins about their links that are about to lapse unused or have
+/// been used up, if they asked to be reminded.
+pub async fn send_due() -> Result<()> {
+    for link in ProvisionLink::due_reminders(Timestamp::now() + REMINDER_WINDOW).await? {
+        let Some(admin) = link.remind_to() else {
+            continue;
+        };
+
+        let (subject, summary) = if link.is_exhausted() {
+            (
+                "Your provision link has been used up",
+                format!("A provision link {admin} created has been used up."),
+            )
+        } else {
+            (
+                "Your provision link is about to expire",
+                format!(
+                    "A provision link {admin} created expires at {} without having been used.",
+                    link.expires_at()
+                ),
+            )
+        };
+
+        let mut notification =
+            Notification::new(NotificationEvent::ProvisionLinkReminder, summary.clone())
+                .detail("Uses", link.use_count().to_string());
+        if !link.groups().is_empty() {
+            notification = notification.detail("Groups", link.groups().join(", "));
+        }
+        notify::send(notification);
+
+        match IDENTITY.get_person(admin).await {
+            Ok(person) => {
+                if let Some(to) = person.email_addresses.first() {
+                    let mut body = format!("Hi {},\n\n{summary}\n", person.display_name);
+                    // A used-up link can't be used again anyway. Without a
+                    // revoke link, the reminder is still worth sending.
+                    if !link.is_exhausted() {
+                        match action_link::url(
+                            Action::RevokeLink,
+                            link.id(),
+                            admin,
+                            REMINDER_WINDOW,
+                        ) {
+                            Ok(revoke_url) => body.push_str(&format!(
+                                "\nIf it's no longer needed, revoke it now:\n\n{revoke_url}\n"
+                            )),
+                            Err(error) => {
+                                tracing::warn!(?error, admin, "failed to create revoke link");
+                            }
+                        }
+                    }
+                    email::try_send(to, subject, body).await;
+                }
+            }
+            Err(error) => tracing::warn!(?error, admin, "failed to look up admin to remind"),
+        }
+
+        link.mark_reminded().await?;
+    }
+
+    Ok(())
+}
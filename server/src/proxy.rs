@@ -0,0 +1,245 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::LazyLock,
+};
+
+use axum::{
+    Router,
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+};
+use types::err;
+
+use crate::CONFIG;
+
+static TRUSTED_PROXIES: LazyLock<Vec<Cidr>> =
+    LazyLock::new(|| CONFIG.trusted_proxies().expect("invalid trusted_proxies"));
+
+const FORWARDED_FOR: &str = "x-forwarded-for";
+const FORWARDED_PROTO: &str = "x-forwarded-proto";
+const REAL_IP: &str = "x-real-ip";
+
+/// A range of addresses, like `10.0.0.0/8`. A bare address is a range of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = types::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (network, prefix) = match s.split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = network
+            .parse()
+            .map_err(|_| err!("'{s}' isn't an IP address or CIDR range"))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| err!("'{s}' has an invalid prefix length"))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+/// Replace the forwarding headers with what we actually believe, before
+/// anything reads them: `X-Forwarded-For` becomes just the client's address,
+/// and `X-Forwarded-Proto` is dropped unless a trusted proxy set it.
+///
+/// The headers are only believed from `trusted_proxies`, and the client is
+/// the last address in `X-Forwarded-For` that isn't one of them. Without any,
+/// every client is the address it connected from.
+pub fn resolve_forwarded(router: Router) -> Router {
+    router.layer(middleware::from_fn(resolve))
+}
+
+async fn resolve(mut request: Request, next: Next) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0.ip().to_canonical());
+    let headers = request.headers_mut();
+    let (client, proto) = forwarded(peer, headers, &TRUSTED_PROXIES);
+
+    headers.remove(REAL_IP);
+    headers.remove(FORWARDED_FOR);
+    headers.remove(FORWARDED_PROTO);
+    if let Some(client) = client.and_then(|ip| HeaderValue::from_str(&ip.to_string()).ok()) {
+        headers.insert(FORWARDED_FOR, client);
+    }
+    if let Some(proto) = proto.and_then(|p| HeaderValue::from_str(&p).ok()) {
+        headers.insert(FORWARDED_PROTO, proto);
+    }
+    next.run(request).await
+}
+
+/// The client's address and the scheme they used, as far as `peer` can be
+/// trusted to tell us.
+fn forwarded(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted: &[Cidr],
+) -> (Option<IpAddr>, Option<String>) {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let proto = header(FORWARDED_PROTO)
+        .and_then(|v| v.split(',').next())
+        .map(|p| p.trim().to_lowercase());
+    let chain: Vec<IpAddr> = header(FORWARDED_FOR)
+        .into_iter()
+        .flat_map(|v| v.split(','))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+    let real_ip = header(REAL_IP).and_then(|ip| ip.trim().parse().ok());
+
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|cidr| cidr.contains(ip));
+    if !peer.is_some_and(is_trusted) {
+        return (peer, None);
+    }
+
+    // Each proxy appends who it heard from, so walk back past our own.
+    let client = chain
+        .iter()
+        .rev()
+        .find(|ip| !is_trusted(**ip))
+        .or(chain.first())
+        .copied()
+        .or(real_ip)
+        .or(peer);
+    (client, proto)
+}
+
+/// Whether the client reached us over HTTPS, so that with `insecure_cookies`
+/// cookies are still marked secure when they can be. Always true when
+/// `authit_url` is HTTPS.
+pub fn is_https(headers: &HeaderMap) -> bool {
+    CONFIG.authit_url.scheme() == "https"
+        || headers
+            .get(FORWARDED_PROTO)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|p| p == "https")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn cidrs(s: &[&str]) -> Vec<Cidr> {
+        s.iter().map(|c| c.parse().unwrap()).collect()
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_cidrs() {
+        assert_eq!(
+            "10.0.0.0/8".parse::<Cidr>().unwrap(),
+            Cidr {
+                network: ip("10.0.0.0"),
+                prefix: 8
+            }
+        );
+        assert_eq!("::1".parse::<Cidr>().unwrap().prefix, 128);
+        assert_eq!("127.0.0.1".parse::<Cidr>().unwrap().prefix, 32);
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("10.0.0.0/x".parse::<Cidr>().is_err());
+        assert!("example.com".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn cidr_contains() {
+        let private: Cidr = "10.0.0.0/8".parse().unwrap();
+        assert!(private.contains(ip("10.1.2.3")));
+        assert!(!private.contains(ip("11.0.0.1")));
+        assert!(private.contains(ip("::ffff:10.0.0.1")));
+        assert!(!private.contains(ip("fd00::1")));
+
+        let everything: Cidr = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains(ip("203.0.113.9")));
+
+        let v6: Cidr = "fd00::/8".parse().unwrap();
+        assert!(v6.contains(ip("fd12::1")));
+        assert!(!v6.contains(ip("fe80::1")));
+    }
+
+    #[test]
+    fn without_trusted_proxies_ignores_headers() {
+        let headers = headers(&[
+            (FORWARDED_FOR, "198.51.100.1"),
+            (REAL_IP, "198.51.100.2"),
+            (FORWARDED_PROTO, "https"),
+        ]);
+        let peer = Some(ip("203.0.113.9"));
+        assert_eq!(forwarded(peer, &headers, &[]), (peer, None));
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let headers = headers(&[(FORWARDED_FOR, "198.51.100.1"), (FORWARDED_PROTO, "https")]);
+        let peer = Some(ip("203.0.113.9"));
+        assert_eq!(
+            forwarded(peer, &headers, &cidrs(&["10.0.0.0/8"])),
+            (peer, None)
+        );
+    }
+
+    #[test]
+    fn walks_back_past_trusted_proxies() {
+        let headers = headers(&[
+            (FORWARDED_FOR, "1.2.3.4, 198.51.100.1, 10.0.0.2"),
+            (FORWARDED_PROTO, "HTTPS, http"),
+        ]);
+        assert_eq!(
+            forwarded(Some(ip("10.0.0.1")), &headers, &cidrs(&["10.0.0.0/8"])),
+            (Some(ip("198.51.100.1")), Some("https".to_string()))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_real_ip_then_peer() {
+        let trusted = cidrs(&["10.0.0.1"]);
+        let peer = Some(ip("10.0.0.1"));
+        let real_ip = headers(&[(REAL_IP, "198.51.100.1")]);
+        assert_eq!(
+            forwarded(peer, &real_ip, &trusted),
+            (Some(ip("198.51.100.1")), None)
+        );
+        assert_eq!(forwarded(peer, &HeaderMap::new(), &trusted), (peer, None));
+    }
+}
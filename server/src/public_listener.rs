@@ -0,0 +1,61 @@
+use std::net::SocketAddr;
+
+use axum::{
+    Router,
+    extract::Request,
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use tokio::net::TcpListener;
+use types::Result;
+
+use crate::{CONFIG, provision_host::PROVISION_PATHS, status};
+
+/// The public account request page and the server functions it calls, on
+/// top of [`PROVISION_PATHS`].
+const REQUEST_ACCOUNT_PATHS: &[&str] = &[
+    "/request-account",
+    "/api/account-requests/enabled",
+    "/api/account-requests/submit",
+];
+
+/// With `public_listen_address` set, also listen there, serving only the
+/// pages people without an account use, provision links and account
+/// requests, and the status endpoint for uptime monitors. The admin UI, its
+/// API and signing in stay on the main listener, which can then be kept on
+/// an internal interface.
+///
+/// Binds before returning, so a bad address stops startup.
+pub async fn serve_public(router: Router) -> Result<()> {
+    let Some(address) = CONFIG.public_listen_address else {
+        return Ok(());
+    };
+
+    let listener = TcpListener::bind(address).await?;
+    tracing::info!(%address, "serving public pages");
+    let router = router.layer(middleware::from_fn(only_public));
+    tokio::spawn(async move {
+        let service = router.into_make_service_with_connect_info::<SocketAddr>();
+        if let Err(error) = axum::serve(listener, service).await {
+            tracing::error!(?error, "public listener failed");
+        }
+    });
+    Ok(())
+}
+
+async fn only_public(request: Request, next: Next) -> Response {
+    if !is_public(request.uri().path()) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    next.run(request).await
+}
+
+/// Whether `path` is for people without an account, or uptime monitors.
+pub(crate) fn is_public(path: &str) -> bool {
+    PROVISION_PATHS
+        .iter()
+        .chain(REQUEST_ACCOUNT_PATHS)
+        .chain(&[status::PATH])
+        .any(|p| path.starts_with(p))
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+
+use jiff::{Timestamp, ToSpan, civil::Date, tz::TimeZone};
+use types::{
+    Result, err,
+    group_webhook::MembershipAction,
+    ids::GroupName,
+    kanidm::Spn,
+    report::{GroupChurn, Report, ReportCounts, ReportDay, ReportEventKind, ReportSettings},
+    validation::validate_email,
+};
+
+use crate::{
+    CONFIG, email, shutdown,
+    storage::{ProvisionLink, ReportEvents, Settings},
+};
+
+/// The longest period a report can cover, so one can't be asked to count
+/// years of events at once.
+const MAX_REPORT_DAYS: i32 = 366;
+
+/// Count something for reports. This runs in the background; failures are
+/// logged.
+pub fn record(kind: ReportEventKind) {
+    record_for_group(kind, None);
+}
+
+/// Count someone being added to or removed from a group.
+pub fn membership_changed(group: &GroupName, action: MembershipAction) {
+    let kind = match action {
+        MembershipAction::Added => ReportEventKind::MemberAdded,
+        MembershipAction::Removed => ReportEventKind::MemberRemoved,
+    };
+    record_for_group(kind, Some(group.to_string()));
+}
+
+fn record_for_group(kind: ReportEventKind, group: Option<String>) {
+    shutdown::spawn(async move {
+        // Reports group churn by name, which is what people recognize.
+        if let Err(error) = ReportEvents::record(kind, group.as_deref()).await {
+            tracing::warn!(
+                ?error,
+                kind = kind.as_str(),
+                "failed to record report event"
+            );
+        }
+    });
+}
+
+/// Summarize what happened from the start of `from` to the end of `to`, in
+/// UTC.
+pub async fn generate(from: Date, to: Date) -> Result<Report> {
+    if to < from {
+        return Err(err!("a report must end on or after the day it starts"));
+    }
+    if (to - from).get_days() >= MAX_REPORT_DAYS {
+        return Err(err!("a report can cover at most {MAX_REPORT_DAYS} days"));
+    }
+
+    let start = from.to_zoned(TimeZone::UTC)?.timestamp();
+    let end = to.tomorrow()?.to_zoned(TimeZone::UTC)?.timestamp();
+
+    let mut days = BTreeMap::new();
+    let mut day = from;
+    while day <= to {
+        days.insert(day, ReportCounts::default());
+        day = day.tomorrow()?;
+    }
+
+    let mut totals = ReportCounts::default();
+    let mut groups: BTreeMap<String, GroupChurn> = BTreeMap::new();
+    for event in ReportEvents::list_between(start, end).await? {
+        totals.add(event.kind);
+        days.entry(event.at.to_zoned(TimeZone::UTC).date())
+            .or_default()
+            .add(event.kind);
+
+        if let Some(group) = event.group {
+            let churn = groups.entry(group.clone()).or_insert(GroupChurn {
+                group,
+                added: 0,
+                removed: 0,
+            });
+            match event.kind {
+                ReportEventKind::MemberAdded => churn.added += 1,
+                ReportEventKind::MemberRemoved => churn.removed += 1,
+                _ => {}
+            }
+        }
+    }
+
+    let mut groups: Vec<GroupChurn> = groups.into_values().collect();
+    groups.sort_by_key(|churn| std::cmp::Reverse(churn.added + churn.removed));
+
+    Ok(Report {
+        from,
+        to,
+        totals,
+        days: days
+            .into_iter()
+            .map(|(date, counts)| ReportDay { date, counts })
+            .collect(),
+        groups,
+    })
+}
+
+pub async fn get_settings() -> Result<ReportSettings> {
+    Ok(Settings::get(Settings::REPORTS).await?.unwrap_or_default())
+}
+
+pub async fn set_settings(settings: ReportSettings) -> Result<()> {
+    check_settings(&settings)?;
+    Settings::set(Settings::REPORTS, &settings).await
+}
+
+/// Check report settings are usable, before saving or importing them.
+pub fn check_settings(settings: &ReportSettings) -> Result<()> {
+    let digest_recipients = match settings.digest.days() {
+        Some(_) => settings.digest_recipients.as_slice(),
+        None => &[],
+    };
+    if settings.recipients.is_empty() && digest_recipients.is_empty() {
+        return Ok(());
+    }
+    if !email::is_configured() {
+        return Err(err!("reports can't be emailed, as email isn't configured"));
+    }
+    for recipient in settings.recipients.iter().chain(digest_recipients) {
+        validate_email(recipient)?;
+    }
+    Ok(())
+}
+
+/// Email last month's report to everyone who wants it, once it's over.
+pub(crate) async fn send_monthly() -> Result<()> {
+    let settings = get_settings().await?;
+    if settings.recipients.is_empty() || !email::is_configured() {
+        return Ok(());
+    }
+
+    let this_month = Timestamp::now()
+        .to_zoned(TimeZone::UTC)
+        .date()
+        .first_of_month();
+    let last_month = this_month.checked_sub(1.month())?;
+    let month = last_month.strftime("%Y-%m").to_string();
+    let last_sent: Option<String> = Settings::get(Settings::REPORT_LAST_SENT).await?;
+    if last_sent.as_ref() == Some(&month) {
+        return Ok(());
+    }
+
+    let report = generate(last_month, this_month.yesterday()?).await?;
+    let subject = format!("AuthIt!: report for {}", last_month.strftime("%B %Y"));
+    let body = plain_text(&report);
+    for recipient in &settings.recipients {
+        email::try_send(recipient, &subject, body.clone()).await;
+    }
+
+    Settings::set(Settings::REPORT_LAST_SENT, &month).await?;
+    tracing::info!(month, "sent monthly report");
+    Ok(())
+}
+
+/// Email the activity digest to everyone who wants it, once a day or once a
+/// week, covering the days since the last one.
+pub(crate) async fn send_digest() -> Result<()> {
+    let settings = get_settings().await?;
+    let Some(days) = settings.digest.days() else {
+        return Ok(());
+    };
+    if settings.digest_recipients.is_empty() || !email::is_configured() {
+        return Ok(());
+    }
+
+    let today = Timestamp::now().to_zoned(TimeZone::UTC).date();
+    let last_sent: Option<Date> = Settings::get(Settings::DIGEST_LAST_SENT).await?;
+    if let Some(last_sent) = last_sent
+        && (today - last_sent).get_days() < days
+    {
+        return Ok(());
+    }
+
+    let from = today.checked_sub(days.days())?;
+    let report = generate(from, today.yesterday()?).await?;
+    // Links lapsing before the next digest, so someone can extend them.
+    let next_digest = today
+        .checked_add(days.days())?
+        .to_zoned(TimeZone::UTC)?
+        .timestamp();
+    let mut expiring: Vec<ProvisionLink> = ProvisionLink::list_unexpired()
+        .await?
+        .into_iter()
+        .filter(|link| !link.is_exhausted() && link.expires_at() < next_digest)
+        .collect();
+    expiring.sort_by_key(ProvisionLink::expires_at);
+
+    let subject = format!(
+        "AuthIt!: {} digest for {}",
+        settings.digest.label().to_lowercase(),
+        today.yesterday()?
+    );
+    let body = digest_text(&report, &expiring)?;
+    for recipient in &settings.digest_recipients {
+        email::try_send(recipient, &subject, body.clone()).await;
+    }
+
+    Settings::set(Settings::DIGEST_LAST_SENT, &today).await?;
+    tracing::info!(%today, "sent activity digest");
+    Ok(())
+}
+
+fn digest_text(report: &Report, expiring: &[ProvisionLink]) -> Result<String> {
+    let mut text = format!("From {} to {}, in UTC:\n\n", report.from, report.to);
+
+    text.push_str("Accounts\n");
+    for kind in [
+        ReportEventKind::AccountCreated,
+        ReportEventKind::AccountDeleted,
+    ] {
+        text.push_str(&format!("  {}: {}\n", kind.label(), report.totals.of(kind)));
+    }
+
+    let admin_group = CONFIG.admin_group();
+    text.push_str(&format!("\nAdmin group ({admin_group})\n"));
+    let admin_spn = Spn::parse(admin_group);
+    match report
+        .groups
+        .iter()
+        .find(|churn| Spn::parse(&churn.group).matches(&admin_spn))
+    {
+        Some(churn) => text.push_str(&format!(
+            "  {} added, {} removed\n",
+            churn.added, churn.removed
+        )),
+        None => text.push_str("  No changes\n"),
+    }
+
+    text.push_str("\nProvision links expiring before the next digest\n");
+    if expiring.is_empty() {
+        text.push_str("  None\n");
+    }
+    for link in expiring {
+        text.push_str(&format!(
+            "  Expires {}, made by {}, used {} times",
+            link.expires_at(),
+            link.created_by().unwrap_or("someone"),
+            link.use_count()
+        ));
+        if !link.groups().is_empty() {
+            text.push_str(&format!(", for {}", link.groups().join(", ")));
+        }
+        text.push('\n');
+    }
+
+    let url = CONFIG.authit_url.join("/reports")?;
+    text.push_str(&format!("\nSee more at {url}\n"));
+    Ok(text)
+}
+
+fn plain_text(report: &Report) -> String {
+    let mut text = format!("From {} to {}, in UTC:\n\n", report.from, report.to);
+    for kind in ReportEventKind::ALL {
+        text.push_str(&format!("{}: {}\n", kind.label(), report.totals.of(kind)));
+    }
+    if !report.groups.is_empty() {
+        text.push_str("\nMembership changes by group:\n");
+        for churn in &report.groups {
+            text.push_str(&format!(
+                "{}: {} added, {} removed\n",
+                churn.group, churn.added, churn.removed
+            ));
+        }
+    }
+    text
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use axum::{
+    Json, Router,
+    body::{self, Body},
+    extract::Request,
+    http::{HeaderMap, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use dioxus::fullstack::FullstackContext;
+use jiff::Timestamp;
+use types::{Result, TooManyRequests, err};
+
+use crate::{CONFIG, device::client_ip, storage::RequestAttempts};
+
+/// How many account requests one address may submit in
+/// [`ACCOUNT_REQUEST_WINDOW`].
+const ACCOUNT_REQUESTS_PER_WINDOW: usize = 5;
+const ACCOUNT_REQUEST_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Refuse request bodies over `max_request_bytes`, before they reach a server
+/// function, sqlite, or Kanidm.
+pub fn limit_request_size(router: Router) -> Router {
+    router.layer(middleware::from_fn(limit))
+}
+
+async fn limit(request: Request, next: Next) -> Response {
+    let max = CONFIG.max_request_bytes;
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+
+    let request = match declared {
+        Some(length) if length > max => return too_large(max),
+        Some(_) => request,
+        // Without a length, read the body up to the limit to find out.
+        None => {
+            let (parts, body) = request.into_parts();
+            match body::to_bytes(body, max).await {
+                Ok(bytes) => Request::from_parts(parts, Body::from(bytes)),
+                Err(_) => return too_large(max),
+            }
+        }
+    };
+    next.run(request).await
+}
+
+fn too_large(max: usize) -> Response {
+    let message = format!("request body must be at most {max} bytes");
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(serde_json::json!({
+            "message": message,
+            "code": StatusCode::PAYLOAD_TOO_LARGE.as_u16(),
+            "details": { "max_request_bytes": max },
+        })),
+    )
+        .into_response()
+}
+
+/// Refuse an account request if too many have come from the same address
+/// lately, so the public form can't be used to flood admins with email.
+/// Requests whose address isn't known are refused, rather than sharing one
+/// count that anyone could use up.
+pub async fn throttle_account_request() -> Result<()> {
+    let headers: HeaderMap = FullstackContext::extract().await?;
+    let Some(ip) = client_ip(&headers) else {
+        return Err(err!("can't tell where this request came from"));
+    };
+    let refused = RequestAttempts::start(
+        "account_request",
+        ip,
+        ACCOUNT_REQUESTS_PER_WINDOW,
+        ACCOUNT_REQUEST_WINDOW,
+    )
+    .await?;
+    if let Some(retry_at) = refused {
+        let wait = retry_at.as_second() - Timestamp::now().as_second();
+        return Err(TooManyRequests {
+            seconds: wait.max(1) as u64,
+        }
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use types::Result;
+
+use crate::storage::Settings;
+
+/// Whether every new account must have an email address, whoever creates it
+/// and however.
+pub async fn is_required() -> Result<bool> {
+    Ok(Settings::get(Settings::REQUIRE_EMAIL)
+        .await?
+        .unwrap_or(false))
+}
+
+pub async fn set_required(required: bool) -> Result<()> {
+    Settings::set(Settings::REQUIRE_EMAIL, &required).await
+}
@@ -0,0 +1,75 @@
+use types::{ResetLink, Result, notification::NotificationEvent};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, KANIDM_CLIENT, email,
+    notify::{self, Notification},
+    storage::{ResetWatch, ResetWatches},
+};
+
+/// Start watching for a user to use the reset link `admin` just generated for
+/// them. Failures are logged, as they shouldn't fail generating the link.
+pub async fn start(user_id: Uuid, admin: &str, link: &ResetLink) {
+    let result = async {
+        let person = IDENTITY.get_person(&user_id.to_string()).await?;
+        let fingerprint = KANIDM_CLIENT.credential_fingerprint(&user_id).await?;
+        ResetWatches::set(&ResetWatch {
+            user_id,
+            user_name: person.name,
+            admin: admin.to_string(),
+            fingerprint,
+            expires_at: link.expires_at,
+        })
+        .await
+    }
+    .await;
+    if let Err(error) = result {
+        tracing::warn!(?error, %user_id, "failed to watch reset link");
+    }
+}
+
+/// Tell admins whose users have changed their credentials since their reset
+/// link was generated, then stop watching links that have expired.
+///
+/// Kanidm doesn't say whether a particular link was used, so any change to
+/// the user's credentials counts.
+pub async fn check_all() -> Result<()> {
+    for watch in ResetWatches::list().await? {
+        let fingerprint = match KANIDM_CLIENT.credential_fingerprint(&watch.user_id).await {
+            Ok(f) => f,
+            Err(error) => {
+                tracing::warn!(?error, user = watch.user_name, "failed to check reset link");
+                continue;
+            }
+        };
+        if fingerprint == watch.fingerprint {
+            continue;
+        }
+
+        notify_admin(&watch).await;
+        ResetWatches::delete(watch.user_id).await?;
+    }
+
+    ResetWatches::delete_expired().await
+}
+
+async fn notify_admin(watch: &ResetWatch) {
+    let summary = format!(
+        "{} used the credential reset link {} generated.",
+        watch.user_name, watch.admin
+    );
+    notify::send(
+        Notification::new(NotificationEvent::ResetLinkUsed, summary.clone())
+            .about_user(watch.user_id, watch.user_name.clone()),
+    );
+
+    match IDENTITY.get_person(&watch.admin).await {
+        Ok(person) => {
+            if let Some(to) = person.email_addresses.first() {
+                let body = format!("Hi {},\n\n{summary}\n", person.display_name);
+                email::try_send(to, "Your reset link has been used", body).await;
+            }
+        }
+        Err(error) => tracing::warn!(?error, admin = watch.admin, "failed to look up admin"),
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use types::{
+    Result,
+    retention::{PurgeResult, RetentionPolicy},
+};
+
+use crate::storage::{
+    AccountRequests, Activities, ActivityKanidmCalls, EmailQueue, Jobs, LoginEvents, ProvisionLink,
+    ProvisionLinkEvents, Session, Settings, ShiftNotes, WebhookDeliveries,
+};
+
+pub async fn get_policy() -> Result<RetentionPolicy> {
+    Ok(Settings::get(Settings::RETENTION)
+        .await?
+        .unwrap_or_default())
+}
+
+pub async fn set_policy(policy: RetentionPolicy) -> Result<()> {
+    Settings::set(Settings::RETENTION, &policy).await
+}
+
+/// Delete everything older than the retention policy allows.
+pub async fn purge() -> Result<PurgeResult> {
+    let policy = get_policy().await?;
+    let now = Timestamp::now();
+    let cutoff = |days: u32| now - Duration::from_secs(u64::from(days) * 24 * 3600);
+
+    let result = PurgeResult {
+        sessions: Session::delete_expired_before(cutoff(policy.expired_sessions_days)).await?,
+        provision_links: ProvisionLink::delete_expired_before(cutoff(policy.provision_links_days))
+            .await?,
+        // Webhook deliveries and sent emails are background work too.
+        jobs: Jobs::delete_finished_before(cutoff(policy.jobs_days)).await?
+            + WebhookDeliveries::delete_delivered_before(cutoff(policy.jobs_days)).await?
+            + EmailQueue::delete_sent_before(cutoff(policy.jobs_days)).await?,
+        account_requests: AccountRequests::delete_decided_before(cutoff(
+            policy.account_requests_days,
+        ))
+        .await?,
+        activity: Activities::delete_before(cutoff(policy.activity_days)).await?,
+        shift_notes: ShiftNotes::delete_before(cutoff(policy.shift_notes_days)).await?,
+        login_events: LoginEvents::delete_before(cutoff(policy.login_events_days)).await?,
+    };
+    // Counted with the activity they explain, and the links they're about.
+    ActivityKanidmCalls::delete_orphaned().await?;
+    ProvisionLinkEvents::delete_orphaned().await?;
+
+    if result.total() > 0 {
+        tracing::info!(?result, "purged old data");
+    }
+    Ok(result)
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use types::{
+    Result, err, ids::PersonId, notification::NotificationEvent, rotation::RotationCampaign,
+};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, KANIDM_CLIENT, UserData, email, kanidm,
+    notify::{self, Notification},
+    shutdown,
+    storage::{PendingRotation, RotationCampaigns, RotationTarget},
+};
+
+/// Ask `user_ids` to change their credentials by `deadline`. They're emailed
+/// now, and reminded until they do.
+pub async fn start(
+    name: &str,
+    user_ids: Vec<Uuid>,
+    deadline: Timestamp,
+    lock_at_deadline: bool,
+    admin: &UserData,
+) -> Result<RotationCampaign> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(err!("give the campaign a name"));
+    }
+    if user_ids.is_empty() {
+        return Err(err!("choose at least one user"));
+    }
+    if deadline <= Timestamp::now() {
+        return Err(err!("the deadline must be in the future"));
+    }
+
+    let mut targets = Vec::with_capacity(user_ids.len());
+    for user_id in user_ids {
+        let person = IDENTITY.get_person(&user_id.to_string()).await?;
+        targets.push(RotationTarget {
+            user_id,
+            user_name: person.name,
+            fingerprint: KANIDM_CLIENT.credential_fingerprint(&user_id).await?,
+        });
+    }
+
+    let id = RotationCampaigns::create(name, &admin.username, deadline, lock_at_deadline, &targets)
+        .await?;
+    tracing::info!(
+        campaign = name,
+        users = targets.len(),
+        admin = admin.username,
+        "started credential rotation"
+    );
+
+    // Send the first emails without waiting for the next check.
+    shutdown::spawn(kanidm::as_service(async move {
+        if let Err(error) = check(id).await {
+            tracing::warn!(?error, %id, "failed to check credential rotation");
+        }
+    }));
+
+    RotationCampaigns::find(id).await
+}
+
+/// Check every open campaign.
+pub async fn check_all() -> Result<()> {
+    for id in RotationCampaigns::list_open().await? {
+        check(id).await?;
+    }
+    Ok(())
+}
+
+/// Note who has changed their credentials, remind those who haven't, and
+/// close the campaign once everyone has or the deadline passes, locking
+/// stragglers' accounts if asked to.
+///
+/// As with reset links, any change to someone's credentials counts.
+async fn check(id: Uuid) -> Result<()> {
+    let campaign = RotationCampaigns::find(id).await?;
+    if campaign.is_closed() {
+        return Ok(());
+    }
+
+    let now = Timestamp::now();
+    let past_deadline = now >= campaign.deadline;
+    let mut pending = Vec::new();
+    for member in RotationCampaigns::pending(id).await? {
+        let fingerprint = match KANIDM_CLIENT.credential_fingerprint(&member.user_id).await {
+            Ok(f) => f,
+            Err(error) => {
+                tracing::warn!(?error, user = member.user_name, "failed to check rotation");
+                pending.push(member);
+                continue;
+            }
+        };
+        if fingerprint != member.fingerprint {
+            RotationCampaigns::mark_rotated(id, member.user_id, now).await?;
+            continue;
+        }
+        if !past_deadline && is_due_reminder(&member, now) && remind(&campaign, &member).await {
+            RotationCampaigns::mark_reminded(id, member.user_id, now).await?;
+        }
+        pending.push(member);
+    }
+
+    if !pending.is_empty() && !past_deadline {
+        return Ok(());
+    }
+
+    let mut locked = 0;
+    if campaign.lock_at_deadline {
+        for member in &pending {
+            match IDENTITY
+                .expire_account(&PersonId(member.user_id), now)
+                .await
+            {
+                Ok(()) => {
+                    RotationCampaigns::mark_locked(id, member.user_id, now).await?;
+                    locked += 1;
+                }
+                Err(error) => {
+                    tracing::warn!(?error, user = member.user_name, "failed to lock account");
+                }
+            }
+        }
+    }
+    RotationCampaigns::close(id, now).await?;
+
+    let total = campaign.members.len();
+    let mut summary = format!(
+        "Credential rotation \"{}\" ended. {} of {total} people changed their credentials.",
+        campaign.name,
+        total - pending.len(),
+    );
+    if locked > 0 {
+        summary.push_str(&format!(" {locked} accounts were locked."));
+    }
+    notify::send(
+        Notification::new(NotificationEvent::RotationDeadline, summary)
+            .detail("Started by", campaign.started_by.clone()),
+    );
+
+    Ok(())
+}
+
+/// Whether `member` should be emailed. Everyone is emailed once when the
+/// campaign starts, then every `rotation_reminder_hours`.
+fn is_due_reminder(member: &PendingRotation, now: Timestamp) -> bool {
+    let Some(reminded_at) = member.reminded_at else {
+        return true;
+    };
+    if CONFIG.rotation_reminder_hours == 0 {
+        return false;
+    }
+    let interval = Duration::from_secs(CONFIG.rotation_reminder_hours * 3600);
+    reminded_at + interval <= now
+}
+
+/// Email someone asking them to change their credentials. Returns whether it
+/// was sent.
+async fn remind(campaign: &RotationCampaign, member: &PendingRotation) -> bool {
+    let person = match IDENTITY.get_person(&member.user_id.to_string()).await {
+        Ok(person) => person,
+        Err(error) => {
+            tracing::warn!(?error, user = member.user_name, "failed to look up user");
+            return false;
+        }
+    };
+    let Some(to) = person.email_addresses.first() else {
+        return false;
+    };
+
+    let subject = if member.reminded_at.is_none() {
+        "Please change your credentials"
+    } else {
+        "Reminder: please change your credentials"
+    };
+    let mut body = format!(
+        "Hi {},\n\n\
+        Please change your password or passkeys at {} before {}.\n",
+        person.display_name, CONFIG.kanidm_url, campaign.deadline
+    );
+    if campaign.lock_at_deadline {
+        body.push_str("\nIf you haven't by then, your account will be locked.\n");
+    }
+
+    email::try_send(to, subject, body).await
+}
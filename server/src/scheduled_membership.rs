@@ -0,0 +1,151 @@
+use jiff::Timestamp;
+use types::{
+    Result, err,
+    group_webhook::MembershipAction,
+    ids::GroupId,
+    notification::NotificationEvent,
+    scheduled_membership::{ScheduledChangeStatus, ScheduledMembershipChange},
+};
+use uuid::Uuid;
+
+use crate::{
+    IDENTITY, UserData, email_verification, group_review, membership,
+    notify::{self, Notification},
+    storage::ScheduledMembershipChanges,
+};
+
+/// Schedule adding a user to or removing them from a group at `run_at`.
+pub async fn schedule(
+    user_id: Uuid,
+    group_id: Uuid,
+    action: MembershipAction,
+    run_at: Timestamp,
+    admin: &UserData,
+) -> Result<ScheduledMembershipChange> {
+    if run_at <= Timestamp::now() {
+        return Err(err!("pick a time in the future"));
+    }
+    let person = IDENTITY.get_person(&user_id.to_string()).await?;
+    let group = IDENTITY.get_group(&GroupId(group_id)).await?;
+
+    let id = ScheduledMembershipChanges::create(
+        person.uuid,
+        &person.name,
+        group.uuid,
+        &group.name,
+        action,
+        run_at,
+        &admin.username,
+    )
+    .await?;
+    tracing::info!(
+        user = person.name,
+        group = group.name,
+        action = action.as_str(),
+        %run_at,
+        admin = admin.username,
+        "scheduled membership change"
+    );
+    ScheduledMembershipChanges::find(id).await
+}
+
+/// Cancel a change that hasn't been made yet.
+pub async fn cancel(id: Uuid, admin: &UserData) -> Result<()> {
+    let note = format!("Cancelled by {}", admin.username);
+    ScheduledMembershipChanges::finish(id, ScheduledChangeStatus::Cancelled, Some(&note)).await?;
+    tracing::info!(%id, admin = admin.username, "cancelled scheduled membership change");
+    Ok(())
+}
+
+/// Make the scheduled changes whose time has come, notifying admins of each
+/// one made or failed.
+pub async fn run_due() -> Result<()> {
+    for change in ScheduledMembershipChanges::due(Timestamp::now()).await? {
+        match apply(&change).await {
+            Ok(()) => {
+                ScheduledMembershipChanges::finish(change.id, ScheduledChangeStatus::Done, None)
+                    .await?;
+                tracing::info!(
+                    user = change.user_name,
+                    group = change.group_name,
+                    "made scheduled membership change"
+                );
+                let detail = match change.action {
+                    MembershipAction::Added => "Added to",
+                    MembershipAction::Removed => "Removed from",
+                };
+                notify::send(
+                    Notification::new(
+                        NotificationEvent::GroupMembershipChanged,
+                        format!(
+                            "{}'s groups were changed as {} scheduled.",
+                            change.user_name, change.created_by
+                        ),
+                    )
+                    .detail(detail, change.group_name.clone())
+                    .involving_groups([change.group_name])
+                    .about_user(change.user_id, change.user_name),
+                );
+            }
+            Err(error) => {
+                tracing::warn!(
+                    ?error,
+                    user = change.user_name,
+                    group = change.group_name,
+                    "failed scheduled membership change"
+                );
+                let reason = error.to_string();
+                ScheduledMembershipChanges::finish(
+                    change.id,
+                    ScheduledChangeStatus::Failed,
+                    Some(&reason),
+                )
+                .await?;
+                notify::send(
+                    Notification::new(
+                        NotificationEvent::ScheduledChangeFailed,
+                        format!(
+                            "A change to {}'s groups that {} scheduled failed.",
+                            change.user_name, change.created_by
+                        ),
+                    )
+                    .detail(
+                        match change.action {
+                            MembershipAction::Added => "Adding to",
+                            MembershipAction::Removed => "Removing from",
+                        },
+                        change.group_name.clone(),
+                    )
+                    .detail("Error", reason)
+                    .involving_groups([change.group_name])
+                    .about_user(change.user_id, change.user_name),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Make one change, unless it's already how things are.
+async fn apply(change: &ScheduledMembershipChange) -> Result<()> {
+    let person = IDENTITY.get_person(&change.user_id.to_string()).await?;
+    let group = IDENTITY.get_group(&GroupId(change.group_id)).await?;
+    let add = change.action == MembershipAction::Added;
+    if person.is_member_of(&group) == add {
+        return Ok(());
+    }
+
+    if add {
+        email_verification::check_groups(person.uuid, [group.name.as_str()]).await?;
+        group_review::check_groups([group.name.as_str()]).await?;
+        IDENTITY
+            .add_user_to_group(&group.id(), &person.id())
+            .await?;
+    } else {
+        IDENTITY
+            .remove_user_from_group(&group.id(), &person.id())
+            .await?;
+    }
+    membership::changed(&group, person.id(), change.action).await;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::{fs, process::Command};
+
+use secrecy::{ExposeSecret, SecretString};
+use types::{Result, err};
+
+/// The config keys that hold secrets, which can also come from a
+/// [`SecretProvider`].
+const SECRET_KEYS: &[&str] = &[
+    "kanidm_token",
+    "oauth_client_secret",
+    "signing_secret",
+    "previous_signing_secret",
+    "db_secret",
+    "smtp_url",
+];
+
+/// Somewhere besides the config itself that secrets can be read from.
+pub trait SecretProvider {
+    /// The secret for the config key `name`, if this has it.
+    fn get(&self, config: &config::Config, name: &str) -> Result<Option<SecretString>>;
+}
+
+/// Reads a secret from the file named by its `_file` key, like
+/// `AUTHIT_KANIDM_TOKEN_FILE`, as Docker and Kubernetes mount them.
+pub struct Files;
+
+impl SecretProvider for Files {
+    fn get(&self, config: &config::Config, name: &str) -> Result<Option<SecretString>> {
+        let Ok(path) = config.get_string(&format!("{name}_file")) else {
+            return Ok(None);
+        };
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| err!("failed to read {name}_file at {path}: {e}"))?;
+        Ok(Some(trim_newline(&contents).to_string().into()))
+    }
+}
+
+/// Asks an external secret manager by running `secret_command` with `{name}`
+/// replaced by the key, taking what it prints as the secret.
+pub struct ExternalCommand;
+
+impl SecretProvider for ExternalCommand {
+    fn get(&self, config: &config::Config, name: &str) -> Result<Option<SecretString>> {
+        let command = config.get_string("secret_command").unwrap_or_default();
+        if command.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let output = Command::new(shell)
+            .arg(flag)
+            .arg(command.replace("{name}", name))
+            .output()
+            .map_err(|e| err!("failed to run secret_command for {name}: {e}"))?;
+        // Managers fail for secrets they don't have, like a
+        // previous_signing_secret that isn't needed.
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let secret = String::from_utf8(output.stdout)
+            .map_err(|_| err!("secret_command printed a {name} that isn't UTF-8"))?;
+        let secret = trim_newline(&secret);
+        Ok((!secret.is_empty()).then(|| secret.to_string().into()))
+    }
+}
+
+/// Fill in the secrets `config` doesn't set itself from the first provider
+/// that has them: a file, then the external secret manager.
+///
+/// Setting a secret both directly and with a file is refused, as it isn't
+/// clear which was meant.
+pub fn resolve(config: config::Config) -> Result<config::Config> {
+    let providers: [&dyn SecretProvider; 2] = [&Files, &ExternalCommand];
+
+    let mut builder = config::Config::builder().add_source(config.clone());
+    for &name in SECRET_KEYS {
+        let is_set = config.get_string(name).is_ok();
+        if is_set && config.get_string(&format!("{name}_file")).is_ok() {
+            return Err(err!("both {name} and {name}_file are set; set only one"));
+        }
+        if is_set {
+            continue;
+        }
+        for provider in providers {
+            if let Some(secret) = provider.get(&config, name)? {
+                builder = builder.set_override(name, secret.expose_secret())?;
+                break;
+            }
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Files and command output usually end with a newline that isn't part of
+/// the secret.
+fn trim_newline(s: &str) -> &str {
+    s.trim_end_matches(['\r', '\n'])
+}
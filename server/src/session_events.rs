@@ -0,0 +1,97 @@
+use std::{convert::Infallible, sync::LazyLock, time::Duration};
+
+use axum::{
+    Router,
+    http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use futures_util::stream;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{session_token, shutdown, storage::Session};
+
+/// How often to check the session is still there without being told, for
+/// sessions ended by another replica or by expiring.
+/// Open browsers also check in this often, which is how admins are shown as
+/// online.
+pub(crate) const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sent whenever any session is deleted. Listeners check whether it was
+/// theirs.
+static ENDED: LazyLock<broadcast::Sender<()>> = LazyLock::new(|| broadcast::channel(16).0);
+
+/// Tell listeners that sessions were deleted, so the browsers using them can
+/// lock straight away.
+pub(crate) fn sessions_ended() {
+    // Nobody listening isn't an error.
+    let _ = ENDED.send(());
+}
+
+pub fn router() -> Router {
+    Router::new().route("/session/events", get(events))
+}
+
+/// A stream of server-sent events that sends `ended` once the session making
+/// the request is revoked, signed out or expires, then closes. Comments keep
+/// the connection alive in between.
+async fn events(headers: HeaderMap) -> Response {
+    let session = match session_token(&headers) {
+        Some(token) => Session::find_token(token).await.ok(),
+        None => None,
+    };
+    let Some(session) = session else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    touch(session.id()).await;
+    let state = Some((session.id(), ENDED.subscribe()));
+    let events = stream::unfold(state, |state| async move {
+        let (id, mut ended) = state?;
+        let mut recheck = tokio::time::interval(RECHECK_INTERVAL);
+        recheck.tick().await;
+        loop {
+            let rechecking = tokio::select! {
+                _ = ended.recv() => false,
+                _ = recheck.tick() => true,
+                // Close, rather than hold up shutdown.
+                _ = shutdown::started() => return None,
+            };
+            if rechecking {
+                touch(id).await;
+            }
+            if !is_active(id).await {
+                let event = Event::default().event("ended").data("");
+                return Some((Ok::<_, Infallible>(event), None));
+            }
+        }
+    });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Whether a session is still usable. If we can't tell, it's assumed to be,
+/// so a database hiccup doesn't lock everyone out.
+async fn is_active(id: Uuid) -> bool {
+    match Session::is_active(id).await {
+        Ok(active) => active,
+        Err(error) => {
+            tracing::warn!(?error, "failed to check session");
+            true
+        }
+    }
+}
+
+/// Note that the session's browser is open. Failing to only makes its user
+/// look offline for a bit, so it's just logged.
+async fn touch(id: Uuid) {
+    if let Err(error) = Session::touch(id).await {
+        tracing::warn!(?error, "failed to record session as seen");
+    }
+}
@@ -0,0 +1,181 @@
+use jiff::Timestamp;
+use types::{
+    Result, err,
+    settings_export::{SETTINGS_EXPORT_VERSION, SettingsExport, SettingsImport},
+    validation::normalize_tag,
+};
+use uuid::Uuid;
+
+use crate::{
+    UserData, notify, offboarding, report, required_email, retention, storage::Tags, ticket,
+    visibility,
+};
+
+/// AuthIt!'s settings as a document. Notifier secrets are left blank unless
+/// `include_secrets` is set.
+pub async fn export(admin: &UserData, include_secrets: bool) -> Result<SettingsExport> {
+    let notifications = notify::get_settings().await?;
+    let notifications = if include_secrets {
+        notifications
+    } else {
+        notifications.redacted()
+    };
+
+    tracing::info!(admin = admin.username, include_secrets, "exported settings");
+    Ok(SettingsExport {
+        version: SETTINGS_EXPORT_VERSION,
+        exported_at: Some(Timestamp::now()),
+        exported_by: Some(admin.username.clone()),
+        notifications: Some(notifications),
+        field_visibility: Some(visibility::get().await?),
+        retention: Some(retention::get_policy().await?),
+        offboarding_template: Some(offboarding::get_template().await?),
+        require_ticket: Some(ticket::is_required().await?),
+        require_email: Some(required_email::is_required().await?),
+        reports: Some(report::get_settings().await?),
+        tags: Some(
+            Tags::list()
+                .await?
+                .into_iter()
+                .map(|(_, name)| name)
+                .collect(),
+        ),
+    })
+}
+
+/// Compare a document with the current settings, and with `apply`, change
+/// them to match. Sections left out of the document are left alone.
+pub async fn import(
+    document: SettingsExport,
+    apply: bool,
+    admin: &UserData,
+) -> Result<SettingsImport> {
+    if document.version > SETTINGS_EXPORT_VERSION {
+        return Err(err!(
+            "this document is from a newer version of AuthIt! (format {}, this reads up to {SETTINGS_EXPORT_VERSION})",
+            document.version
+        ));
+    }
+
+    let mut changes = Vec::new();
+
+    let notifications = match document.notifications {
+        Some(new) => {
+            let current = notify::get_settings().await?;
+            let new = new.keep_secrets_from(&current);
+            notify::check_rules(&new)?;
+            (new != current).then(|| {
+                changes.push("Notifications".to_string());
+                new
+            })
+        }
+        None => None,
+    };
+    let field_visibility = match document.field_visibility {
+        Some(new) if new != visibility::get().await? => {
+            changes.push("Field visibility".to_string());
+            Some(new)
+        }
+        _ => None,
+    };
+    let retention_policy = match document.retention {
+        Some(new) if new != retention::get_policy().await? => {
+            changes.push("Retention policy".to_string());
+            Some(new)
+        }
+        _ => None,
+    };
+    let offboarding_template = match document.offboarding_template {
+        Some(new) if new != offboarding::get_template().await? => {
+            changes.push("Offboarding checklist".to_string());
+            Some(new)
+        }
+        _ => None,
+    };
+    let require_ticket = match document.require_ticket {
+        Some(new) if new != ticket::is_required().await? => {
+            changes.push(if new {
+                "Require ticket references".to_string()
+            } else {
+                "Don't require ticket references".to_string()
+            });
+            Some(new)
+        }
+        _ => None,
+    };
+    let require_email = match document.require_email {
+        Some(new) if new != required_email::is_required().await? => {
+            changes.push(if new {
+                "Require email addresses".to_string()
+            } else {
+                "Don't require email addresses".to_string()
+            });
+            Some(new)
+        }
+        _ => None,
+    };
+    let reports = match document.reports {
+        Some(new) if new != report::get_settings().await? => {
+            report::check_settings(&new)?;
+            changes.push("Report emails".to_string());
+            Some(new)
+        }
+        _ => None,
+    };
+
+    let existing: Vec<String> = Tags::list().await?.into_iter().map(|(_, n)| n).collect();
+    let mut new_tags: Vec<String> = Vec::new();
+    for name in document.tags.unwrap_or_default() {
+        let name = normalize_tag(&name)?;
+        if !existing.contains(&name) && !new_tags.contains(&name) {
+            new_tags.push(name);
+        }
+    }
+    if !new_tags.is_empty() {
+        changes.push(format!("Add tags: {}", new_tags.join(", ")));
+    }
+
+    if !apply || changes.is_empty() {
+        return Ok(SettingsImport {
+            applied: false,
+            changes,
+        });
+    }
+
+    // Field visibility is the only section that can still be refused, so
+    // it goes first.
+    if let Some(new) = field_visibility {
+        visibility::set(new).await?;
+    }
+    if let Some(new) = notifications {
+        notify::set_settings(new).await?;
+    }
+    if let Some(new) = retention_policy {
+        retention::set_policy(new).await?;
+    }
+    if let Some(new) = offboarding_template {
+        offboarding::set_template(new).await?;
+    }
+    if let Some(new) = require_ticket {
+        ticket::set_required(new).await?;
+    }
+    if let Some(new) = require_email {
+        required_email::set_required(new).await?;
+    }
+    if let Some(new) = reports {
+        report::set_settings(new).await?;
+    }
+    for name in &new_tags {
+        Tags::create(Uuid::now_v7(), name).await?;
+    }
+
+    tracing::info!(
+        admin = admin.username,
+        changes = changes.len(),
+        "imported settings"
+    );
+    Ok(SettingsImport {
+        applied: true,
+        changes,
+    })
+}
@@ -0,0 +1,236 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use reqwest::{Client, RequestBuilder, Url};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use types::{Result, err};
+
+use crate::ReqwestExt;
+
+/// The name of the service account and OAuth2 client we create.
+const NAME: &str = "authit";
+
+/// Groups that give the service account the permissions AuthIt! needs.
+pub(crate) const SERVICE_ACCOUNT_GROUPS: &[&str] = &["idm_people_admins", "idm_group_admins"];
+
+/// Interactively set up Kanidm for AuthIt!, then write a config file to
+/// `path`. Run with `authit setup [path]`.
+///
+/// Using Kanidm admin credentials, this creates the service account and its
+/// API token, the OAuth2 client, and the admin group.
+pub fn run(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Err(err!("{} already exists", path.display()));
+    }
+
+    let kanidm_url: Url = prompt("Kanidm URL", None)?.parse()?;
+    let authit_url: Url = prompt("AuthIt! URL", None)?.parse()?;
+    let username = prompt("Kanidm admin username", Some("idm_admin"))?;
+    let password: SecretString = match std::env::var("AUTHIT_SETUP_PASSWORD") {
+        Ok(password) => password.into(),
+        Err(_) => prompt(
+            "Kanidm admin password (visible; or set AUTHIT_SETUP_PASSWORD)",
+            None,
+        )?
+        .into(),
+    };
+    let admin_group = prompt("Group allowed to use AuthIt!", Some("authit_admins"))?;
+    let default_data_dir = crate::config::default_data_dir();
+    let data_dir = prompt("Data directory", Some(&default_data_dir.to_string_lossy()))?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let config = runtime.block_on(async {
+        let kanidm = Setup::login(kanidm_url.clone(), &username, &password).await?;
+        kanidm.bootstrap(&authit_url, &admin_group).await
+    })?;
+
+    let toml = format!(
+        r#"kanidm_url = "{kanidm_url}"
+kanidm_token = "{kanidm_token}"
+oauth_client_id = "{NAME}"
+oauth_client_secret = "{oauth_client_secret}"
+authit_url = "{authit_url}"
+signing_secret = "{signing_secret}"
+admin_group = "{admin_group}"
+data_dir = {data_dir:?}
+db_secret = "{db_secret}"
+"#,
+        kanidm_token = config.kanidm_token.expose_secret(),
+        oauth_client_secret = config.oauth_client_secret.expose_secret(),
+        signing_secret = random_secret(),
+        db_secret = random_secret(),
+    );
+    std::fs::write(path, toml)?;
+
+    println!(
+        "Wrote {}. It contains secrets, so keep it private. Add people to the '{admin_group}' group to let them use AuthIt!.",
+        path.display()
+    );
+    Ok(())
+}
+
+fn prompt(label: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) => print!("{label} [{default}]: "),
+        None => print!("{label}: "),
+    }
+    io::stdout().flush()?;
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let line = line.trim();
+
+    match (line, default) {
+        ("", Some(default)) => Ok(default.to_string()),
+        ("", None) => Err(err!("{label} is required")),
+        (line, _) => Ok(line.to_string()),
+    }
+}
+
+/// 32 random bytes, hex encoded.
+fn random_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+struct BootstrappedConfig {
+    kanidm_token: SecretString,
+    oauth_client_secret: SecretString,
+}
+
+#[derive(Deserialize)]
+struct AuthResponse {
+    sessionid: String,
+    state: serde_json::Value,
+}
+
+/// A Kanidm client authenticated as an admin.
+struct Setup {
+    client: Client,
+    base_url: Url,
+    token: SecretString,
+}
+
+impl Setup {
+    /// Sign in to Kanidm with a password.
+    async fn login(base_url: Url, username: &str, password: &SecretString) -> Result<Self> {
+        let client = Client::new();
+        let url = base_url.join("/v1/auth")?;
+
+        let init: AuthResponse = client
+            .post(url.clone())
+            .json(&json!({ "step": { "init": username } }))
+            .try_send()
+            .await?;
+        let step = |body: serde_json::Value| {
+            client
+                .post(url.clone())
+                .header("X-KANIDM-AUTH-SESSION-ID", &init.sessionid)
+                .json(&body)
+        };
+
+        step(json!({ "step": { "begin": "password" } }))
+            .try_send::<AuthResponse>()
+            .await?;
+        let response: AuthResponse = step(json!({
+            "step": { "cred": { "password": password.expose_secret() } }
+        }))
+        .try_send()
+        .await?;
+
+        let token = response
+            .state
+            .get("success")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| err!("Kanidm login failed: {}", response.state))?;
+
+        Ok(Self {
+            client,
+            base_url,
+            token: token.to_string().into(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<RequestBuilder> {
+        Ok(self
+            .client
+            .request(method, self.base_url.join(path)?)
+            .bearer_auth(self.token.expose_secret()))
+    }
+
+    async fn bootstrap(&self, authit_url: &Url, admin_group: &str) -> Result<BootstrappedConfig> {
+        use reqwest::Method;
+
+        println!("Creating service account '{NAME}'...");
+        self.request(Method::POST, "/v1/service_account")?
+            .json(&json!({ "attrs": { "name": [NAME], "displayname": ["AuthIt!"] } }))
+            .try_send::<()>()
+            .await?;
+        for group in SERVICE_ACCOUNT_GROUPS {
+            self.request(Method::POST, &format!("/v1/group/{group}/_attr/member"))?
+                .json(&[NAME])
+                .try_send::<()>()
+                .await?;
+        }
+
+        println!("Generating API token...");
+        let kanidm_token: String = self
+            .request(
+                Method::POST,
+                &format!("/v1/service_account/{NAME}/_api_token"),
+            )?
+            .json(&json!({ "label": NAME, "expiry": null, "read_write": true }))
+            .try_send()
+            .await?;
+
+        println!("Creating group '{admin_group}'...");
+        self.request(Method::POST, "/v1/group")?
+            .json(&json!({ "attrs": { "name": [admin_group] } }))
+            .try_send::<()>()
+            .await?;
+
+        println!("Creating OAuth2 client '{NAME}'...");
+        self.request(Method::POST, "/v1/oauth2/_basic")?
+            .json(&json!({
+                "attrs": {
+                    "name": [NAME],
+                    "displayname": ["AuthIt!"],
+                    "oauth2_rs_origin_landing": [authit_url.as_str()],
+                }
+            }))
+            .try_send::<()>()
+            .await?;
+        self.request(
+            Method::POST,
+            &format!("/v1/oauth2/{NAME}/_attr/oauth2_rs_origin"),
+        )?
+        .json(&[authit_url.join("/auth/callback")?.as_str()])
+        .try_send::<()>()
+        .await?;
+        // Anyone may sign in, so the people directory works; management is
+        // still limited to the admin group.
+        self.request(
+            Method::POST,
+            &format!("/v1/oauth2/{NAME}/_scopemap/idm_all_persons"),
+        )?
+        .json(&["openid", "profile", "email", "groups"])
+        .try_send::<()>()
+        .await?;
+        let oauth_client_secret: String = self
+            .request(Method::GET, &format!("/v1/oauth2/{NAME}/_basic_secret"))?
+            .try_send::<Option<String>>()
+            .await?
+            .ok_or_else(|| err!("Kanidm didn't return an OAuth2 client secret"))?;
+
+        Ok(BootstrappedConfig {
+            kanidm_token: kanidm_token.into(),
+            oauth_client_secret: oauth_client_secret.into(),
+        })
+    }
+}
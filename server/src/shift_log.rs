@@ -0,0 +1,41 @@
+use types::{
+    Result, err,
+    shift_note::ShiftNote,
+    validation::{MAX_NOTES_LENGTH, check_length},
+};
+
+use crate::{
+    UserData,
+    announcement::render_markdown,
+    storage::{ShiftNotes, StoredShiftNote},
+};
+
+/// The shift log, newest first. Old notes are purged by the retention policy.
+pub async fn list() -> Result<Vec<ShiftNote>> {
+    Ok(ShiftNotes::list()
+        .await?
+        .into_iter()
+        .map(rendered)
+        .collect())
+}
+
+/// Leave a note for the other admins.
+pub async fn add(content: &str, admin: &UserData) -> Result<ShiftNote> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(err!("the note is empty"));
+    }
+    check_length("note", content, MAX_NOTES_LENGTH)?;
+
+    let note = ShiftNotes::create(&admin.username, content).await?;
+    Ok(rendered(note))
+}
+
+fn rendered(note: StoredShiftNote) -> ShiftNote {
+    ShiftNote {
+        id: note.id,
+        author: note.author,
+        created_at: note.created_at,
+        html: render_markdown(&note.content),
+    }
+}
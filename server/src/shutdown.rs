@@ -0,0 +1,102 @@
+use std::{future::Future, sync::LazyLock, time::Duration};
+
+use axum::{
+    Router,
+    extract::Request,
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::{CONFIG, storage};
+
+/// Cancelled when shutdown starts.
+static SHUTDOWN: LazyLock<CancellationToken> = LazyLock::new(CancellationToken::new);
+
+/// Requests and background work that shutdown waits for.
+static TRACKER: LazyLock<TaskTracker> = LazyLock::new(TaskTracker::new);
+
+/// Run `task` in the background, and have shutdown wait for it to finish.
+pub fn spawn<F>(task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    TRACKER.spawn(task);
+}
+
+/// Resolves once shutdown starts, for loops that should stop then.
+pub async fn started() {
+    SHUTDOWN.cancelled().await
+}
+
+/// Turn away new requests once shutdown starts, and have it wait for the ones
+/// already in flight.
+pub fn track_requests(router: Router) -> Router {
+    router.layer(middleware::from_fn(track))
+}
+
+async fn track(request: Request, next: Next) -> Response {
+    if SHUTDOWN.is_cancelled() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::CONNECTION, "close")],
+            "AuthIt! is shutting down",
+        )
+            .into_response();
+    }
+    TRACKER.track_future(next.run(request)).await
+}
+
+/// On SIGTERM or Ctrl-C, stop taking requests, wait up to
+/// `shutdown_timeout_secs` for in-flight work, close the database, and exit.
+pub fn handle_signals() {
+    tokio::spawn(async {
+        wait_for_signal().await;
+        tracing::info!("shutting down");
+        SHUTDOWN.cancel();
+        TRACKER.close();
+
+        let timeout = Duration::from_secs(CONFIG.shutdown_timeout_secs);
+        if tokio::time::timeout(timeout, TRACKER.wait()).await.is_err() {
+            tracing::warn!(
+                remaining = TRACKER.len(),
+                "gave up waiting for in-flight work"
+            );
+        }
+
+        storage::close().await;
+        tracing::info!("shut down");
+        std::process::exit(0);
+    });
+}
+
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::warn!(?error, "failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(error) => {
+                tracing::warn!(?error, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
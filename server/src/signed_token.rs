@@ -0,0 +1,329 @@
+use std::sync::LazyLock;
+
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use jiff::Timestamp;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use types::{Result, err};
+use uuid::Uuid;
+
+use crate::CONFIG;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What a token or key is for. Each gets its own key, derived from the
+/// signing secret, so one kind can't be passed off as another, and a leaked
+/// key for one says nothing about the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Purpose {
+    Session,
+    ProvisionLink,
+    EmailAction,
+    /// Stand-ins for the names of deleted users.
+    Pseudonym,
+    AuditExport,
+    GroupJoin,
+    /// Hashes of codes emailed to verify addresses.
+    EmailVerification,
+}
+
+impl Purpose {
+    /// The HKDF info for this purpose's key. Changing one invalidates every
+    /// token signed for that purpose.
+    fn info(&self) -> &'static [u8] {
+        match self {
+            Self::Session => b"authit session v1",
+            Self::ProvisionLink => b"authit provision link v1",
+            Self::EmailAction => b"authit email action v1",
+            Self::Pseudonym => b"authit pseudonym v1",
+            Self::AuditExport => b"authit audit export v1",
+            Self::GroupJoin => b"authit group join v1",
+            Self::EmailVerification => b"authit email verification v1",
+        }
+    }
+
+    /// Whether tokens for this purpose were issued before [`SignedToken`].
+    /// Those don't say what they're for, so they're only accepted where they
+    /// were issued, and not at all once `accept_legacy_tokens` is off.
+    fn had_legacy_tokens(&self) -> bool {
+        matches!(self, Self::Session | Self::ProvisionLink)
+    }
+}
+
+/// A signing secret, from which the key for each purpose is derived.
+struct Key {
+    secret: SecretString,
+}
+
+impl Key {
+    fn derive(&self, purpose: Purpose) -> [u8; 32] {
+        let mut key = [0; 32];
+        Hkdf::<Sha256>::new(None, self.secret.expose_secret().as_bytes())
+            .expand(purpose.info(), &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        key
+    }
+
+    /// Identifies the derived key, so tokens can say which one signed them
+    /// without giving anything away.
+    fn id(&self, purpose: Purpose) -> String {
+        let hash = Sha256::digest(self.derive(purpose));
+        BASE64_URL_SAFE_NO_PAD.encode(&hash[..6])
+    }
+
+    fn mac(&self, purpose: Purpose, payload: &[u8]) -> Result<HmacSha256> {
+        let mut mac = HmacSha256::new_from_slice(&self.derive(purpose))?;
+        mac.update(payload);
+        Ok(mac)
+    }
+
+    /// Tokens from before [`SignedToken`] were signed with the secret itself.
+    fn legacy_mac(&self, payload: &[u8]) -> Result<HmacSha256> {
+        let mut mac = HmacSha256::new_from_slice(self.secret.expose_secret().as_bytes())?;
+        mac.update(payload);
+        Ok(mac)
+    }
+}
+
+/// The current key first, then the previous one, if it's still accepted.
+static KEYS: LazyLock<Vec<Key>> = LazyLock::new(|| {
+    std::iter::once(CONFIG.signing_secret.clone())
+        .chain(CONFIG.previous_signing_secret.clone())
+        .map(|secret| Key { secret })
+        .collect()
+});
+
+/// The current key for `purpose`, for uses other than signing tokens.
+pub fn derive_key(purpose: Purpose) -> [u8; 32] {
+    KEYS[0].derive(purpose)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    purpose: Purpose,
+    #[serde(rename = "kid")]
+    key_id: String,
+    #[serde(rename = "exp")]
+    expires_at: Option<Timestamp>,
+    data: T,
+}
+
+/// Data signed with the signing secret, along with what it's for and when it
+/// stops being accepted.
+///
+/// Encoded, it's `<payload>.<signature>`, both URL-safe base64, so it can go
+/// in cookies and URLs.
+pub struct SignedToken<T> {
+    pub purpose: Purpose,
+    pub expires_at: Option<Timestamp>,
+    pub data: T,
+}
+
+impl<T: Serialize + DeserializeOwned> SignedToken<T> {
+    pub fn new(purpose: Purpose, data: T, expires_at: Option<Timestamp>) -> Self {
+        Self {
+            purpose,
+            expires_at,
+            data,
+        }
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        self.encode_with(&KEYS[0])
+    }
+
+    /// Check a token's signature, purpose and expiry.
+    pub fn decode(token: &str, purpose: Purpose) -> Result<Self> {
+        Self::decode_with(&KEYS, token, purpose)
+    }
+
+    fn encode_with(&self, key: &Key) -> Result<String> {
+        let envelope = Envelope {
+            purpose: self.purpose,
+            key_id: key.id(self.purpose),
+            expires_at: self.expires_at,
+            data: &self.data,
+        };
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&envelope)?);
+        let signature = BASE64_URL_SAFE_NO_PAD.encode(
+            key.mac(self.purpose, payload.as_bytes())?
+                .finalize()
+                .into_bytes(),
+        );
+        Ok(format!("{payload}.{signature}"))
+    }
+
+    fn decode_with(keys: &[Key], token: &str, purpose: Purpose) -> Result<Self> {
+        let (payload, signature) = token
+            .split_once('.')
+            .ok_or_else(|| err!("invalid token format"))?;
+        let envelope: Envelope<T> =
+            serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(payload)?)?;
+
+        if envelope.purpose != purpose {
+            return Err(err!("token is for something else"));
+        }
+
+        let key = keys
+            .iter()
+            .find(|k| k.id(purpose) == envelope.key_id)
+            .ok_or_else(|| err!("token was signed with an unknown key"))?;
+        key.mac(purpose, payload.as_bytes())?
+            .verify_slice(&BASE64_URL_SAFE_NO_PAD.decode(signature)?)?;
+        if envelope.expires_at.is_some_and(|at| Timestamp::now() >= at) {
+            return Err(err!("token has expired"));
+        }
+
+        Ok(Self {
+            purpose: envelope.purpose,
+            expires_at: envelope.expires_at,
+            data: envelope.data,
+        })
+    }
+}
+
+/// Decode a token that identifies a record, like a session or provision
+/// link, by its ID. It must have been issued for `purpose`.
+pub fn decode_id(token: &str, purpose: Purpose) -> Result<Uuid> {
+    decode_id_with(&KEYS, token, purpose, CONFIG.accept_legacy_tokens)
+}
+
+fn decode_id_with(
+    keys: &[Key],
+    token: &str,
+    purpose: Purpose,
+    accept_legacy: bool,
+) -> Result<Uuid> {
+    match SignedToken::<Uuid>::decode_with(keys, token, purpose) {
+        Ok(token) => Ok(token.data),
+        Err(error) if purpose.had_legacy_tokens() && accept_legacy => {
+            legacy_id(keys, token).map_err(|_| error)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Parse a `<uuid>.<signature>` token, as sessions and provision links used
+/// before [`SignedToken`]. These stop mattering once they've all expired.
+fn legacy_id(keys: &[Key], token: &str) -> Result<Uuid> {
+    let (uuid, signature) = token
+        .split_once('.')
+        .ok_or_else(|| err!("invalid token format"))?;
+    let signature = BASE64_URL_SAFE_NO_PAD.decode(signature)?;
+    let signed = keys.iter().any(|key| {
+        key.legacy_mac(uuid.as_bytes())
+            .is_ok_and(|mac| mac.verify_slice(&signature).is_ok())
+    });
+    if !signed {
+        return Err(err!("invalid token signature"));
+    }
+    Ok(Uuid::parse_str(uuid)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::ToSpan;
+
+    use super::*;
+
+    fn key(secret: &str) -> Key {
+        Key {
+            secret: SecretString::from(secret),
+        }
+    }
+
+    fn encode(key: &Key, purpose: Purpose, expires_at: Option<Timestamp>) -> String {
+        SignedToken::new(purpose, Uuid::nil(), expires_at)
+            .encode_with(key)
+            .unwrap()
+    }
+
+    fn legacy(key: &Key, id: Uuid) -> String {
+        let id = id.to_string();
+        let signature = key
+            .legacy_mac(id.as_bytes())
+            .unwrap()
+            .finalize()
+            .into_bytes();
+        format!("{id}.{}", BASE64_URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    #[test]
+    fn round_trips() {
+        let keys = [key("current")];
+        let expires_at = Timestamp::now() + 1.hour();
+        let token = encode(&keys[0], Purpose::GroupJoin, Some(expires_at));
+
+        let decoded = SignedToken::<Uuid>::decode_with(&keys, &token, Purpose::GroupJoin).unwrap();
+        assert_eq!(decoded.purpose, Purpose::GroupJoin);
+        assert_eq!(decoded.expires_at, Some(expires_at));
+        assert_eq!(decoded.data, Uuid::nil());
+    }
+
+    #[test]
+    fn accepts_the_previous_key() {
+        let token = encode(&key("previous"), Purpose::Session, None);
+        let keys = [key("current"), key("previous")];
+        assert!(SignedToken::<Uuid>::decode_with(&keys, &token, Purpose::Session).is_ok());
+        assert!(SignedToken::<Uuid>::decode_with(&keys[..1], &token, Purpose::Session).is_err());
+    }
+
+    #[test]
+    fn rejects_another_purpose() {
+        let keys = [key("current")];
+        let token = encode(&keys[0], Purpose::EmailAction, None);
+        assert!(SignedToken::<Uuid>::decode_with(&keys, &token, Purpose::GroupJoin).is_err());
+    }
+
+    #[test]
+    fn rejects_expired_tokens() {
+        let keys = [key("current")];
+        let token = encode(
+            &keys[0],
+            Purpose::GroupJoin,
+            Some(Timestamp::now() - 1.second()),
+        );
+        assert!(SignedToken::<Uuid>::decode_with(&keys, &token, Purpose::GroupJoin).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_tokens() {
+        let keys = [key("current")];
+        let token = encode(&keys[0], Purpose::GroupJoin, None);
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = SignedToken::new(Purpose::GroupJoin, Uuid::max(), None::<Timestamp>)
+            .encode_with(&keys[0])
+            .unwrap();
+        let (payload, _) = forged.split_once('.').unwrap();
+        let tampered = format!("{payload}.{signature}");
+        assert!(SignedToken::<Uuid>::decode_with(&keys, &tampered, Purpose::GroupJoin).is_err());
+    }
+
+    #[test]
+    fn accepts_legacy_tokens_only_where_they_were_issued() {
+        let keys = [key("current")];
+        let id = Uuid::new_v4();
+        let token = legacy(&keys[0], id);
+
+        assert_eq!(
+            decode_id_with(&keys, &token, Purpose::Session, true).unwrap(),
+            id
+        );
+        assert_eq!(
+            decode_id_with(&keys, &token, Purpose::ProvisionLink, true).unwrap(),
+            id
+        );
+        assert!(decode_id_with(&keys, &token, Purpose::GroupJoin, true).is_err());
+        assert!(decode_id_with(&keys, &token, Purpose::Session, false).is_err());
+    }
+
+    #[test]
+    fn rejects_legacy_tokens_signed_with_another_secret() {
+        let token = legacy(&key("someone else"), Uuid::new_v4());
+        assert!(decode_id_with(&[key("current")], &token, Purpose::Session, true).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use jiff::{Timestamp, ToSpan, tz::TimeZone};
+use types::{
+    Result,
+    stats::{DashboardStats, StatsDay},
+};
+
+use crate::{report, storage::LoginEvents};
+
+/// How many days the dashboard's trends cover, including today.
+const STATS_DAYS: i64 = 30;
+
+/// The last few weeks' account changes, provisioning and sign-ins, by UTC
+/// day.
+pub async fn dashboard() -> Result<DashboardStats> {
+    let today = Timestamp::now().to_zoned(TimeZone::UTC).date();
+    let from = today.checked_sub((STATS_DAYS - 1).days())?;
+
+    let mut days: Vec<StatsDay> = report::generate(from, today)
+        .await?
+        .days
+        .into_iter()
+        .map(|day| StatsDay {
+            date: day.date,
+            counts: day.counts,
+            sign_ins: 0,
+            failed_sign_ins: 0,
+        })
+        .collect();
+
+    let start = from.to_zoned(TimeZone::UTC)?.timestamp();
+    for (at, succeeded) in LoginEvents::list_outcomes_between(start, Timestamp::now()).await? {
+        let date = at.to_zoned(TimeZone::UTC).date();
+        if let Some(day) = days.iter_mut().find(|day| day.date == date) {
+            if succeeded {
+                day.sign_ins += 1;
+            } else {
+                day.failed_sign_ins += 1;
+            }
+        }
+    }
+
+    Ok(DashboardStats { days })
+}
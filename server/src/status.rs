@@ -0,0 +1,71 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json, Router,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{KANIDM_CLIENT, maintenance, storage};
+
+/// Where uptime monitors can check on us, without signing in.
+pub const PATH: &str = "/status";
+
+/// How long a check is reused for, so requests to this unauthenticated
+/// endpoint can't be turned into a flood of requests to Kanidm.
+const CACHE_FOR: Duration = Duration::from_secs(10);
+
+/// When we last checked, and whether everything was fine.
+static LAST_CHECK: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+pub fn router() -> Router {
+    Router::new().route(PATH, get(status))
+}
+
+/// Our version, and whether we're fully working: `ok` with a 200, or
+/// `degraded` with a 503 so monitors that only look at the status code
+/// notice. Why it's degraded is left for the Diagnostics page.
+async fn status() -> Response {
+    let (code, status) = if is_ok().await {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "degraded")
+    };
+    (
+        code,
+        Json(serde_json::json!({
+            "status": status,
+            "version": env!("CARGO_PKG_VERSION"),
+        })),
+    )
+        .into_response()
+}
+
+async fn is_ok() -> bool {
+    if let Some((at, ok)) = *LAST_CHECK.lock().unwrap()
+        && at.elapsed() < CACHE_FOR
+    {
+        return ok;
+    }
+
+    let ok = check().await;
+    *LAST_CHECK.lock().unwrap() = Some((Instant::now(), ok));
+    ok
+}
+
+/// Whether the database and Kanidm answer, and we aren't down for
+/// maintenance.
+async fn check() -> bool {
+    if let Err(error) = storage::ping().await {
+        tracing::warn!(?error, "status check couldn't reach the database");
+        return false;
+    }
+    if !KANIDM_CLIENT.is_up().await {
+        return false;
+    }
+    !matches!(maintenance::get().await, Ok(Some(_)))
+}
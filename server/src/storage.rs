@@ -1,27 +1,348 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+use std::sync::LazyLock;
+
 use dioxus::fullstack::Lazy;
 use secrecy::ExposeSecret;
-use sqlx::SqlitePool;
-use sqlx::sqlite::SqliteConnectOptions;
-use types::Result;
+use sqlx::{Connection, SqlitePool};
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use types::{
+    Result, err,
+    migration::{MigrationInfo, MigrationState, MigrationStatus},
+};
 
 use crate::CONFIG;
+pub use account_request::AccountRequests;
+pub use activity::{Activities, ChainedActivity, chain_hash};
+pub use activity_kanidm_call::ActivityKanidmCalls;
+pub use api_usage::ApiUsages;
+pub use avatar::{Avatar, Avatars};
+pub use background_job_run::BackgroundJobRuns;
+pub use break_glass::BreakGlass;
+pub use capture_event::CaptureEvents;
+pub use email_queue::{ClaimedEmail, EmailQueue};
+pub use email_verification::{EmailVerifications, PendingCode};
+pub use group_owner::GroupOwners;
+pub use group_review::GroupReviews;
+pub use group_webhook::{GroupWebhooks, WebhookDeliveries};
+pub use idempotency_key::{IdempotencyClaim, IdempotencyKeys};
+pub use job::Jobs;
+pub use job_lease::JobLeases;
+pub use kanidm_event_sync::{KanidmEventSync, KanidmEventSyncs};
+pub use local_reference::{LocalReference, LocalReferences};
+pub use login_event::{LoginEvents, LoginUser};
+pub use offboarding::Offboardings;
+pub use onboarding::Onboardings;
+pub use outbox::{Outbox, OutboxKind};
+pub use pending_login::PendingLogin;
+pub use phone_number::PhoneNumbers;
+pub use preferences::Preferences;
+pub use profile_change::ProfileChanges;
+pub use provision_approval::ProvisionApprovals;
 pub use provision_link::ProvisionLink;
+pub use provision_link_claim::{ProvisionLinkClaim, ProvisionLinkClaims};
+pub use provision_link_event::{ProvisionLinkEvent, ProvisionLinkEvents};
+pub use provisioned_account::ProvisionedAccounts;
+pub use report_event::{ReportEvent, ReportEvents};
+pub use request_attempt::RequestAttempts;
+pub use reset_watch::{ResetWatch, ResetWatches};
+pub use rotation::{PendingRotation, RotationCampaigns, RotationTarget};
+pub use scheduled_membership::ScheduledMembershipChanges;
 pub use session::Session;
+pub use settings::Settings;
+pub use shift_note::{ShiftNotes, StoredShiftNote};
+pub use tag::Tags;
+pub use used_action_token::UsedActionTokens;
 
+mod account_request;
+mod activity;
+mod activity_kanidm_call;
+mod api_usage;
+mod avatar;
+mod background_job_run;
+mod break_glass;
+mod capture_event;
+mod email_queue;
+mod email_verification;
+mod group_owner;
+mod group_review;
+mod group_webhook;
+mod idempotency_key;
+mod job;
+mod job_lease;
+mod kanidm_event_sync;
+mod local_reference;
+mod login_event;
+mod offboarding;
+mod onboarding;
+mod outbox;
+mod pending_login;
+mod phone_number;
+mod preferences;
+mod profile_change;
+mod provision_approval;
 mod provision_link;
+mod provision_link_claim;
+mod provision_link_event;
+mod provisioned_account;
+mod report_event;
+mod request_attempt;
+mod reset_watch;
+mod rotation;
+mod scheduled_membership;
 mod session;
+mod settings;
+mod shift_note;
+mod tag;
+mod used_action_token;
 
 static POOL: Lazy<SqlitePool> = Lazy::new(|| async {
+    std::fs::create_dir_all(&CONFIG.data_dir)?;
     let db_path = CONFIG.data_dir.join("db.sqlite");
+    let journal_mode: SqliteJournalMode = CONFIG.db_journal_mode.parse()?;
+
+    let mut options = SqliteConnectOptions::new().filename(&db_path);
+    match (CONFIG.db_encryption, is_encrypted(&db_path)?) {
+        (true, Some(false)) => {
+            return Err(sqlx::Error::Configuration(
+                format!(
+                    "{} isn't encrypted, but db_encryption is on. Set db_encryption to false \
+                     to keep using it unencrypted, or move it aside to start a new, encrypted one",
+                    db_path.display()
+                )
+                .into(),
+            ));
+        }
+        (false, Some(true)) => {
+            return Err(sqlx::Error::Configuration(
+                format!(
+                    "{} is encrypted, but db_encryption is off. Turn it back on and set db_secret",
+                    db_path.display()
+                )
+                .into(),
+            ));
+        }
+        (true, _) => {
+            let secret = CONFIG.db_secret.as_ref().ok_or_else(|| {
+                sqlx::Error::Configuration("db_secret is required when db_encryption is on".into())
+            })?;
+            options = options.pragma("key", secret.expose_secret().to_string());
+        }
+        (false, _) => {}
+    }
 
-    let options = SqliteConnectOptions::new()
-        .filename(&db_path)
-        .pragma("key", CONFIG.db_secret.expose_secret())
+    let options = options
+        .journal_mode(journal_mode)
+        .busy_timeout(CONFIG.db_busy_timeout())
         .create_if_missing(true);
 
-    SqlitePool::connect_with(options).await
+    SqlitePoolOptions::new()
+        .max_connections(CONFIG.db_max_connections)
+        .connect_with(options)
+        .await
+});
+
+/// Migrations follow an expand/contract convention, so that two versions of
+/// AuthIt! can share a database during a rolling deploy:
+///
+/// - Expand migrations only add to the schema: new tables, columns with
+///   defaults, and indexes. The previous version, not knowing about them, can
+///   keep running. [`check_expand_only`] rejects anything else.
+/// - Contract migrations remove or tighten what the previous version still
+///   used, like dropping a column the current code no longer reads. Their
+///   file names start with `<version>_contract_`, and they must only ship in
+///   a release after the one that stopped using what they remove. Once one is
+///   applied, older versions refuse to start.
+///
+/// Applied migrations this build doesn't have are ignored, as long as none
+/// of them is a contract migration.
+static MIGRATOR: LazyLock<Migrator> = LazyLock::new(|| {
+    let mut migrator = sqlx::migrate!("../migrations");
+    migrator.set_ignore_missing(true);
+    migrator
 });
 
+/// Migrations before this one predate the expand/contract convention.
+const EXPAND_CONTRACT_SINCE: i64 = 20261016003200;
+
+/// Whether a migration, by its description, is a contract migration.
+/// sqlx makes the description from the file name, with spaces for
+/// underscores.
+fn is_contract(description: &str) -> bool {
+    description.starts_with("contract ")
+}
+
+/// Why a migration isn't safe to apply while the previous version of
+/// AuthIt! is still running, if it isn't.
+fn check_expand_only(sql: &str) -> Option<&'static str> {
+    let sql: String = sql
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .map(|line| line.to_uppercase() + "\n")
+        .collect();
+    let forbidden = [
+        ("DROP ", "drops something"),
+        ("RENAME ", "renames something"),
+        ("CREATE TRIGGER", "adds a trigger"),
+        ("CREATE UNIQUE INDEX", "adds a unique index"),
+    ];
+    forbidden
+        .into_iter()
+        .find(|(keyword, _)| sql.contains(keyword))
+        .map(|(_, reason)| reason)
+}
+
+/// Whether the database file at `path` is encrypted, or `None` if there isn't
+/// one yet. Unencrypted sqlite files start with a well-known header, which
+/// SQLCipher encrypts along with everything else.
+fn is_encrypted(path: &Path) -> std::io::Result<Option<bool>> {
+    const HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut header = [0; 16];
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(Some(&header != HEADER)),
+        // An empty file is as good as none; sqlite will set it up.
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Apply pending migrations, refusing to if the database doesn't match the
+/// migrations this build has, as after a bad deploy or a downgrade past a
+/// contract migration.
 pub async fn migrate() -> Result<()> {
-    Ok(sqlx::migrate!("../migrations").run(&*POOL).await?)
+    let status = migration_status().await?;
+    let problems: Vec<String> = status
+        .problems()
+        .map(|m| format!("{} ({})", m.version, m.state.label().to_lowercase()))
+        .collect();
+    if !problems.is_empty() {
+        return Err(err!(
+            "the database's migrations don't match this build: {}. Deploy the version of \
+             AuthIt! that applied them, or see \"Database migrations\" in the README to \
+             repair them",
+            problems.join(", ")
+        ));
+    }
+
+    for info in &status.migrations {
+        if info.state != MigrationState::Pending
+            || info.version <= EXPAND_CONTRACT_SINCE
+            || is_contract(&info.description)
+        {
+            continue;
+        }
+        let Some(migration) = MIGRATOR.iter().find(|m| m.version == info.version) else {
+            continue;
+        };
+        if let Some(reason) = check_expand_only(&migration.sql) {
+            return Err(err!(
+                "migration {} {reason}, so it isn't safe while the previous version of AuthIt! \
+                 is running. Make it a contract migration, named \"{}_contract_...\"",
+                info.version,
+                info.version
+            ));
+        }
+    }
+
+    Ok(MIGRATOR.run(&*POOL).await?)
+}
+
+/// Compare the migrations applied to the database with those in this build.
+pub async fn migration_status() -> Result<MigrationStatus> {
+    let mut conn = POOL.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let failed = conn.dirty_version().await?;
+    let mut applied: HashMap<i64, Vec<u8>> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| (m.version, m.checksum.into_owned()))
+        .collect();
+    // The migrations table is sqlx's, so it isn't known to the query macros.
+    let descriptions: HashMap<i64, String> =
+        sqlx::query_as("SELECT version, description FROM _sqlx_migrations")
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut migrations: Vec<MigrationInfo> = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| {
+            let state = match applied.remove(&m.version) {
+                _ if failed == Some(m.version) => MigrationState::Failed,
+                None => MigrationState::Pending,
+                Some(checksum) if checksum == *m.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::ChecksumMismatch,
+            };
+            MigrationInfo {
+                version: m.version,
+                description: m.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+    migrations.extend(applied.into_keys().map(|version| {
+        let description = descriptions.get(&version).cloned().unwrap_or_default();
+        let state = if is_contract(&description) {
+            MigrationState::Incompatible
+        } else {
+            MigrationState::Unknown
+        };
+        MigrationInfo {
+            version,
+            description,
+            state,
+        }
+    }));
+    migrations.sort_by_key(|m| m.version);
+
+    Ok(MigrationStatus { migrations })
+}
+
+/// Record this build's checksums for applied migrations that have changed
+/// since, returning their versions.
+///
+/// Only do this once sure the changes don't matter, like edits to comments;
+/// the migrations aren't run again.
+pub async fn repair_migration_checksums() -> Result<Vec<i64>> {
+    let status = migration_status().await?;
+    let mut repaired = Vec::new();
+    for info in status.migrations {
+        if info.state != MigrationState::ChecksumMismatch {
+            continue;
+        }
+        let Some(migration) = MIGRATOR.iter().find(|m| m.version == info.version) else {
+            continue;
+        };
+        // The migrations table is sqlx's, so it isn't known to the query
+        // macros.
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = ? WHERE version = ?")
+            .bind(&*migration.checksum)
+            .bind(migration.version)
+            .execute(&*POOL)
+            .await?;
+        repaired.push(info.version);
+    }
+    Ok(repaired)
+}
+
+/// Check the database answers.
+pub async fn ping() -> Result<()> {
+    Ok(POOL.acquire().await?.ping().await?)
+}
+
+/// Close the pool, letting open connections finish first.
+pub async fn close() {
+    POOL.close().await
 }
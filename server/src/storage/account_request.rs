@@ -0,0 +1,216 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    account_request::{AccountRequest, AccountRequestStatus},
+    err,
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct AccountRequestRow {
+    id: Uuid,
+    name: String,
+    email: String,
+    justification: String,
+    status: String,
+    reason: Option<String>,
+    decided_by: Option<String>,
+    decided_at: Option<SqlxTimestamp>,
+}
+
+impl AccountRequestRow {
+    fn into_request(self) -> Result<AccountRequest> {
+        Ok(AccountRequest {
+            id: self.id,
+            name: self.name,
+            email: self.email,
+            justification: self.justification,
+            created_at: self.id.jiff_timestamp(),
+            status: self.status.parse()?,
+            decided_by: self.decided_by,
+            decided_at: self.decided_at.map(|t| t.to_jiff()),
+            reason: self.reason,
+        })
+    }
+}
+
+/// Storage for [`AccountRequest`]s.
+pub struct AccountRequests;
+
+impl AccountRequests {
+    pub async fn create(name: &str, email: &str, justification: &str) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO account_requests (id, name, email, justification)
+            VALUES (?, ?, ?, ?)
+            "#,
+            id,
+            name,
+            email,
+            justification,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<AccountRequest> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            AccountRequestRow,
+            r#"
+            SELECT
+                id as "id: _",
+                name,
+                email,
+                justification,
+                status,
+                reason,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM account_requests
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_request()
+    }
+
+    /// List all requests, newest first.
+    pub async fn list() -> Result<Vec<AccountRequest>> {
+        let rows = sqlx::query_as!(
+            AccountRequestRow,
+            r#"
+            SELECT
+                id as "id: _",
+                name,
+                email,
+                justification,
+                status,
+                reason,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM account_requests
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(AccountRequestRow::into_request)
+            .collect()
+    }
+
+    /// Whether there's already an undecided request from `email`.
+    pub async fn has_pending(email: &str) -> Result<bool> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM account_requests
+            WHERE email = ? AND status = 'pending'
+            "#,
+            email,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Approve or deny a pending request. Fails if it has already been
+    /// decided.
+    pub async fn decide(
+        id: Uuid,
+        status: AccountRequestStatus,
+        reason: Option<&str>,
+        decided_by: &str,
+    ) -> Result<()> {
+        let id_bytes = id.as_bytes().as_slice();
+        let status = status.as_str();
+        let decided_at = Timestamp::now().to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE account_requests
+            SET status = ?, reason = ?, decided_by = ?, decided_at = ?
+            WHERE id = ? AND status = 'pending'
+            "#,
+            status,
+            reason,
+            decided_by,
+            decided_at,
+            id_bytes,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(err!("account request has already been decided"));
+        }
+
+        Ok(())
+    }
+
+    /// Delete requests decided before `cutoff`, returning how many.
+    pub async fn delete_decided_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM account_requests
+            WHERE status != 'pending' AND decided_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Replace the requester's details on requests from `email` with
+    /// `pseudonym`, and drop their justification.
+    pub async fn anonymize_requester(email: &str, pseudonym: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE account_requests
+            SET name = ?, email = ?, justification = ''
+            WHERE email = ? COLLATE NOCASE
+            "#,
+            pseudonym,
+            pseudonym,
+            email,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace an admin's name on the requests they decided.
+    pub async fn rename_decider(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE account_requests
+            SET decided_by = ?
+            WHERE decided_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,477 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use types::{
+    Result,
+    activity::{Activity, ActivityActor, ActivityMatch, ActivitySubject, MatchRun},
+};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Held while appending, so two records from this replica can't follow the
+/// same one. Across replicas, the unique index on `prev_hash` refuses the
+/// second.
+static APPEND: Mutex<()> = Mutex::const_new(());
+
+struct ActivityRow {
+    id: Uuid,
+    at: SqlxTimestamp,
+    event: String,
+    summary: String,
+    actor_id: Option<Uuid>,
+    actor_name: Option<String>,
+    user_id: Option<Uuid>,
+    user_name: Option<String>,
+    group_name: Option<String>,
+    ticket: Option<String>,
+}
+
+impl ActivityRow {
+    fn into_activity(self) -> Result<Activity> {
+        let actor = match (self.actor_id, self.actor_name) {
+            (Some(user_id), Some(name)) => Some(ActivityActor { user_id, name }),
+            _ => None,
+        };
+        let subject = match (self.user_id, self.user_name, self.group_name) {
+            (Some(id), Some(name), _) => Some(ActivitySubject::User { id, name }),
+            (_, _, Some(name)) => Some(ActivitySubject::Group { name }),
+            _ => None,
+        };
+
+        Ok(Activity {
+            id: self.id,
+            at: self.at.to_jiff(),
+            event: self.event.parse()?,
+            summary: self.summary,
+            actor,
+            subject,
+            ticket: self.ticket,
+        })
+    }
+}
+
+struct ActivityMatchRow {
+    id: Uuid,
+    at: SqlxTimestamp,
+    event: String,
+    summary: String,
+    actor_id: Option<Uuid>,
+    actor_name: Option<String>,
+    user_id: Option<Uuid>,
+    user_name: Option<String>,
+    group_name: Option<String>,
+    ticket: Option<String>,
+    summary_marked: String,
+    actor_marked: Option<String>,
+    user_marked: Option<String>,
+    group_marked: Option<String>,
+    ticket_marked: Option<String>,
+}
+
+impl ActivityMatchRow {
+    fn into_match(self) -> Result<ActivityMatch> {
+        let summary = runs(&self.summary_marked);
+        let actor = runs(self.actor_marked.as_deref().unwrap_or_default());
+        let subject = runs(
+            self.user_marked
+                .as_deref()
+                .or(self.group_marked.as_deref())
+                .unwrap_or_default(),
+        );
+        let ticket = runs(self.ticket_marked.as_deref().unwrap_or_default());
+        let activity = ActivityRow {
+            id: self.id,
+            at: self.at,
+            event: self.event,
+            summary: self.summary,
+            actor_id: self.actor_id,
+            actor_name: self.actor_name,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            group_name: self.group_name,
+            ticket: self.ticket,
+        }
+        .into_activity()?;
+
+        Ok(ActivityMatch {
+            activity,
+            summary,
+            actor,
+            subject,
+            ticket,
+        })
+    }
+}
+
+/// Split text marked by FTS5's `highlight()` with [`MATCH_START`] and
+/// [`MATCH_END`] into runs.
+fn runs(marked: &str) -> Vec<MatchRun> {
+    let mut runs = Vec::new();
+    let mut matched = false;
+    for (i, text) in marked.split([MATCH_START, MATCH_END]).enumerate() {
+        if i > 0 {
+            matched = !matched;
+        }
+        if !text.is_empty() {
+            runs.push(MatchRun {
+                text: text.to_string(),
+                matched,
+            });
+        }
+    }
+    runs
+}
+
+/// What `highlight()` puts around matches: control characters, which
+/// recorded names and summaries don't contain.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+struct ChainRow {
+    id: Uuid,
+    at: SqlxTimestamp,
+    event: String,
+    summary: String,
+    actor_id: Option<Uuid>,
+    actor_name: Option<String>,
+    user_id: Option<Uuid>,
+    user_name: Option<String>,
+    group_name: Option<String>,
+    ticket: Option<String>,
+    prev_hash: Option<String>,
+    hash: Option<String>,
+    redacted: bool,
+}
+
+/// An activity record, with its place in the hash chain.
+#[derive(Debug, Clone)]
+pub struct ChainedActivity {
+    pub activity: Activity,
+    /// `None` for records from before the chain.
+    pub hash: Option<String>,
+    pub prev_hash: Option<String>,
+    /// Whether it was about a deleted user, and so blanked out. Its hash can't
+    /// be checked, but the chain still runs through it.
+    pub redacted: bool,
+}
+
+/// The hash of `activity` and everything before it.
+///
+/// Changing what this covers breaks the chain for every existing record.
+pub fn chain_hash(prev_hash: Option<&str>, activity: &Activity) -> String {
+    let (actor_id, actor_name) = match &activity.actor {
+        Some(actor) => (Some(actor.user_id), Some(actor.name.as_str())),
+        None => (None, None),
+    };
+    let (user_id, user_name, group_name) = match &activity.subject {
+        Some(ActivitySubject::User { id, name }) => (Some(*id), Some(name.as_str()), None),
+        Some(ActivitySubject::Group { name }) => (None, None, Some(name.as_str())),
+        None => (None, None, None),
+    };
+    let fields = json!([
+        prev_hash,
+        activity.id,
+        activity.at.as_millisecond(),
+        activity.event.as_str(),
+        activity.summary,
+        actor_id,
+        actor_name,
+        user_id,
+        user_name,
+        group_name,
+        activity.ticket,
+    ]);
+
+    Sha256::digest(fields.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// The activity stream shown on the dashboard. It's append-only: each record
+/// is chained to the one before by [`chain_hash`].
+pub struct Activities;
+
+impl Activities {
+    pub async fn record(activity: &Activity) -> Result<()> {
+        let _append = APPEND.lock().await;
+        let mut tx = POOL.begin().await?;
+
+        let prev_hash = sqlx::query_scalar!(
+            r#"
+            SELECT hash as "hash!"
+            FROM activity
+            WHERE hash IS NOT NULL
+            ORDER BY rowid DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let hash = chain_hash(prev_hash.as_deref(), activity);
+
+        let id = activity.id.as_bytes().as_slice();
+        let at = activity.at.to_sqlx();
+        let event = activity.event.as_str();
+        let actor_id = activity.actor.as_ref().map(|a| a.user_id);
+        let actor_id = actor_id.as_ref().map(|id| id.as_bytes().as_slice());
+        let actor_name = activity.actor.as_ref().map(|a| a.name.as_str());
+        let (user_id, user_name, group_name) = match &activity.subject {
+            Some(ActivitySubject::User { id, name }) => (Some(*id), Some(name.as_str()), None),
+            Some(ActivitySubject::Group { name }) => (None, None, Some(name.as_str())),
+            None => (None, None, None),
+        };
+        let user_id = user_id.as_ref().map(|id| id.as_bytes().as_slice());
+
+        sqlx::query!(
+            r#"
+            INSERT INTO activity
+                (id, at, event, summary, actor_id, actor_name, user_id, user_name, group_name,
+                 ticket, prev_hash, hash)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            at,
+            event,
+            activity.summary,
+            actor_id,
+            actor_name,
+            user_id,
+            user_name,
+            group_name,
+            activity.ticket,
+            prev_hash,
+            hash,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// The latest activity, newest first.
+    pub async fn list_recent(limit: u32) -> Result<Vec<Activity>> {
+        let rows = sqlx::query_as!(
+            ActivityRow,
+            r#"
+            SELECT
+                id as "id: _",
+                at as "at: _",
+                event,
+                summary,
+                actor_id as "actor_id: _",
+                actor_name,
+                user_id as "user_id: _",
+                user_name,
+                group_name,
+                ticket
+            FROM activity
+            WHERE NOT redacted
+            ORDER BY at DESC
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(ActivityRow::into_activity).collect()
+    }
+
+    /// Activity matching an FTS5 `query`, newest first.
+    pub async fn search(query: &str, limit: u32) -> Result<Vec<ActivityMatch>> {
+        let rows = sqlx::query_as!(
+            ActivityMatchRow,
+            r#"
+            SELECT
+                activity.id as "id: _",
+                activity.at as "at: _",
+                activity.event,
+                activity.summary,
+                activity.actor_id as "actor_id: _",
+                activity.actor_name,
+                activity.user_id as "user_id: _",
+                activity.user_name,
+                activity.group_name,
+                activity.ticket,
+                highlight(activity_search, 0, char(2), char(3)) as "summary_marked!: String",
+                highlight(activity_search, 1, char(2), char(3)) as "actor_marked?: String",
+                highlight(activity_search, 2, char(2), char(3)) as "user_marked?: String",
+                highlight(activity_search, 3, char(2), char(3)) as "group_marked?: String",
+                highlight(activity_search, 4, char(2), char(3)) as "ticket_marked?: String"
+            FROM activity_search
+            JOIN activity ON activity.rowid = activity_search.rowid
+            WHERE activity_search MATCH ? AND NOT activity.redacted
+            ORDER BY activity.at DESC
+            LIMIT ?
+            "#,
+            query,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(ActivityMatchRow::into_match).collect()
+    }
+
+    /// Activity from `from` up to `to`, oldest first.
+    pub async fn list_between(from: Timestamp, to: Timestamp) -> Result<Vec<Activity>> {
+        let from = from.to_sqlx();
+        let to = to.to_sqlx();
+
+        let rows = sqlx::query_as!(
+            ActivityRow,
+            r#"
+            SELECT
+                id as "id: _",
+                at as "at: _",
+                event,
+                summary,
+                actor_id as "actor_id: _",
+                actor_name,
+                user_id as "user_id: _",
+                user_name,
+                group_name,
+                ticket
+            FROM activity
+            WHERE at >= ? AND at < ? AND NOT redacted
+            ORDER BY at, id
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(ActivityRow::into_activity).collect()
+    }
+
+    /// Everything done by or to a user, oldest first.
+    pub async fn list_for_user(user_id: Uuid) -> Result<Vec<Activity>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            ActivityRow,
+            r#"
+            SELECT
+                id as "id: _",
+                at as "at: _",
+                event,
+                summary,
+                actor_id as "actor_id: _",
+                actor_name,
+                user_id as "user_id: _",
+                user_name,
+                group_name,
+                ticket
+            FROM activity
+            WHERE (actor_id = ? OR user_id = ?) AND NOT redacted
+            ORDER BY at, id
+            "#,
+            user_id,
+            user_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(ActivityRow::into_activity).collect()
+    }
+
+    pub async fn delete_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM activity
+            WHERE at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Every record, in the order they were added, for checking the chain.
+    pub async fn chain() -> Result<Vec<ChainedActivity>> {
+        let rows = sqlx::query_as!(
+            ChainRow,
+            r#"
+            SELECT
+                id as "id: _",
+                at as "at: _",
+                event,
+                summary,
+                actor_id as "actor_id: _",
+                actor_name,
+                user_id as "user_id: _",
+                user_name,
+                group_name,
+                ticket,
+                prev_hash,
+                hash,
+                redacted
+            FROM activity
+            ORDER BY rowid
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let activity = ActivityRow {
+                    id: row.id,
+                    at: row.at,
+                    event: row.event,
+                    summary: row.summary,
+                    actor_id: row.actor_id,
+                    actor_name: row.actor_name,
+                    user_id: row.user_id,
+                    user_name: row.user_name,
+                    group_name: row.group_name,
+                    ticket: row.ticket,
+                }
+                .into_activity()?;
+                Ok(ChainedActivity {
+                    activity,
+                    hash: row.hash,
+                    prev_hash: row.prev_hash,
+                    redacted: row.redacted,
+                })
+            })
+            .collect()
+    }
+
+    /// Blank out everything done by or to a user. The records stay, so the
+    /// chain isn't broken.
+    pub async fn redact_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            UPDATE activity
+            SET redacted = TRUE,
+                summary = '',
+                actor_id = NULL,
+                actor_name = NULL,
+                user_id = NULL,
+                user_name = NULL,
+                group_name = NULL,
+                ticket = NULL
+            WHERE actor_id = ? OR user_id = ?
+            "#,
+            user_id,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
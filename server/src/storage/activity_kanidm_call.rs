@@ -0,0 +1,77 @@
+use types::{Result, activity::KanidmCall};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// The Kanidm API calls behind each activity, for explaining it.
+pub struct ActivityKanidmCalls;
+
+impl ActivityKanidmCalls {
+    pub async fn record(activity_id: Uuid, calls: &[KanidmCall]) -> Result<()> {
+        let activity_id = activity_id.as_bytes().as_slice();
+        let calls = serde_json::to_string(calls)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO activity_kanidm_calls (activity_id, calls)
+            VALUES (?, ?)
+            "#,
+            activity_id,
+            calls,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The calls recorded for an activity, or none if it didn't make any.
+    pub async fn get(activity_id: Uuid) -> Result<Vec<KanidmCall>> {
+        let activity_id = activity_id.as_bytes().as_slice();
+
+        let calls = sqlx::query_scalar!(
+            r#"
+            SELECT calls
+            FROM activity_kanidm_calls
+            WHERE activity_id = ?
+            "#,
+            activity_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        match calls {
+            Some(calls) => Ok(serde_json::from_str(&calls)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Remove calls whose activity has been purged.
+    pub async fn delete_orphaned() -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM activity_kanidm_calls
+            WHERE activity_id NOT IN (SELECT id FROM activity)
+            "#,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Remove calls for activities that have been redacted, since their
+    /// payloads name who they were about.
+    pub async fn delete_redacted() -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM activity_kanidm_calls
+            WHERE activity_id IN (SELECT id FROM activity WHERE redacted)
+            "#,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{Result, api_usage::ApiUsage};
+
+use crate::storage::POOL;
+
+struct ApiUsageRow {
+    user_id: String,
+    user_name: String,
+    path: String,
+    count: i64,
+    first_used_at: SqlxTimestamp,
+    last_used_at: SqlxTimestamp,
+}
+
+/// Counts of server function calls, per admin.
+pub struct ApiUsages;
+
+impl ApiUsages {
+    pub async fn record(user_id: &str, user_name: &str, path: &str) -> Result<()> {
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO api_usage (user_id, user_name, path, count, first_used_at, last_used_at)
+            VALUES (?, ?, ?, 1, ?, ?)
+            ON CONFLICT (user_id, path) DO UPDATE SET
+                user_name = excluded.user_name,
+                count = api_usage.count + 1,
+                last_used_at = excluded.last_used_at
+            "#,
+            user_id,
+            user_name,
+            path,
+            now,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list() -> Result<Vec<ApiUsage>> {
+        let rows = sqlx::query_as!(
+            ApiUsageRow,
+            r#"
+            SELECT
+                user_id,
+                user_name,
+                path,
+                count,
+                first_used_at as "first_used_at: _",
+                last_used_at as "last_used_at: _"
+            FROM api_usage
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ApiUsage {
+                user_id: row.user_id,
+                user_name: row.user_name,
+                path: row.path,
+                count: row.count.max(0) as u64,
+                first_used_at: row.first_used_at.to_jiff(),
+                last_used_at: row.last_used_at.to_jiff(),
+            })
+            .collect())
+    }
+
+    pub async fn delete_for_user(user_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM api_usage
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear() -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM api_usage
+            "#,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct AvatarRow {
+    image: Vec<u8>,
+    updated_at: SqlxTimestamp,
+}
+
+pub struct Avatar {
+    /// A PNG.
+    pub image: Vec<u8>,
+    pub updated_at: Timestamp,
+}
+
+/// Users' avatars. They're kept in the database, rather than under
+/// `data_dir`, so replicas see the same ones.
+pub struct Avatars;
+
+impl Avatars {
+    pub async fn get(user_id: Uuid) -> Result<Option<Avatar>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            AvatarRow,
+            r#"
+            SELECT
+                image,
+                updated_at as "updated_at: _"
+            FROM avatars
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(row.map(|r| Avatar {
+            image: r.image,
+            updated_at: r.updated_at.to_jiff(),
+        }))
+    }
+
+    pub async fn set(user_id: Uuid, image: &[u8]) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+        let updated_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO avatars (user_id, image, updated_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET
+                image = excluded.image,
+                updated_at = excluded.updated_at
+            "#,
+            user_id,
+            image,
+            updated_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM avatars
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{Result, background_job::BackgroundJobRun};
+
+use crate::storage::POOL;
+
+struct BackgroundJobRunRow {
+    name: String,
+    started_at: SqlxTimestamp,
+    finished_at: Option<SqlxTimestamp>,
+    error: Option<String>,
+    started_by: Option<String>,
+}
+
+/// The latest run of each background job.
+pub struct BackgroundJobRuns;
+
+impl BackgroundJobRuns {
+    /// Record that a job started, replacing its previous run.
+    pub async fn start(name: &str, started_by: Option<&str>) -> Result<()> {
+        let started_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO background_job_runs (name, started_at, finished_at, error, started_by)
+            VALUES (?, ?, NULL, NULL, ?)
+            ON CONFLICT (name) DO UPDATE SET
+                started_at = excluded.started_at,
+                finished_at = NULL,
+                error = NULL,
+                started_by = excluded.started_by
+            "#,
+            name,
+            started_at,
+            started_by,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record that a job finished, with why it failed if it did.
+    pub async fn finish(name: &str, error: Option<&str>) -> Result<()> {
+        let finished_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE background_job_runs
+            SET finished_at = ?, error = ?
+            WHERE name = ?
+            "#,
+            finished_at,
+            error,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Each job's latest run, by name.
+    pub async fn list() -> Result<HashMap<String, BackgroundJobRun>> {
+        let rows = sqlx::query_as!(
+            BackgroundJobRunRow,
+            r#"
+            SELECT
+                name,
+                started_at as "started_at: _",
+                finished_at as "finished_at: _",
+                error,
+                started_by
+            FROM background_job_runs
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let run = BackgroundJobRun {
+                    started_at: row.started_at.to_jiff(),
+                    finished_at: row.finished_at.map(|t| t.to_jiff()),
+                    error: row.error,
+                    started_by: row.started_by,
+                };
+                (row.name, run)
+            })
+            .collect())
+    }
+
+    pub async fn rename_starter(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE background_job_runs
+            SET started_by = ?
+            WHERE started_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use secrecy::SecretString;
+use sqlx::SqlitePool;
+use types::{Result, break_glass::BreakGlassStatus};
+use uuid::Uuid;
+
+use crate::{CONFIG, UserData, password_policy, storage::POOL};
+
+/// How many failed attempts are allowed within [`FAILURE_WINDOW`] before we
+/// stop checking passwords entirely.
+const MAX_FAILURES: usize = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(3600);
+
+struct CredentialRow {
+    password_hash: String,
+    updated_at: SqlxTimestamp,
+    updated_by: String,
+}
+
+struct AttemptRow {
+    attempted_at: SqlxTimestamp,
+    success: bool,
+}
+
+/// The break-glass local admin account, for when the Kanidm OAuth flow is
+/// broken.
+pub struct BreakGlass;
+
+impl BreakGlass {
+    /// Set the password, if it meets the password policy.
+    pub async fn set_password(password: &SecretString, updated_by: &str) -> Result<()> {
+        password_policy::check(password, &[updated_by, UserData::BREAK_GLASS_USERNAME])?;
+        let password_hash = password_policy::hash(password)?;
+        let updated_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO break_glass_credential (id, password_hash, updated_at, updated_by)
+            VALUES (1, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                password_hash = excluded.password_hash,
+                updated_at = excluded.updated_at,
+                updated_by = excluded.updated_by
+            "#,
+            password_hash,
+            updated_at,
+            updated_by,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Check a login attempt against the stored credential, recording the
+    /// attempt. Returns whether the login succeeded.
+    ///
+    /// Once too many attempts have failed recently, or the password is past
+    /// its rotation date, this fails without checking the password.
+    pub async fn attempt_login(password: &SecretString) -> Result<bool> {
+        // The attempt counts as a failure until the password checks out, so
+        // attempts made at once can't all get in under the limit.
+        let Some(attempt) = Self::start_attempt(&*POOL).await? else {
+            tracing::warn!("break-glass login rejected: too many recent failures");
+            return Ok(false);
+        };
+
+        let success = match Self::credential().await? {
+            Some(row)
+                if password_policy::expires_at(row.updated_at.to_jiff())
+                    .is_some_and(|t| t <= Timestamp::now()) =>
+            {
+                tracing::warn!("break-glass login rejected: password has expired");
+                false
+            }
+            Some(row) => password_policy::verify(password, &row.password_hash)?,
+            None => false,
+        };
+
+        if success {
+            Self::mark_succeeded(attempt).await?;
+            tracing::warn!("break-glass login succeeded");
+        } else {
+            tracing::warn!("break-glass login failed");
+        }
+
+        Ok(success)
+    }
+
+    pub async fn status() -> Result<BreakGlassStatus> {
+        let credential = Self::credential().await?;
+        let attempts = Self::recent_attempts().await?;
+
+        let updated_at = credential.as_ref().map(|c| c.updated_at.to_jiff());
+
+        Ok(BreakGlassStatus {
+            enabled: CONFIG.break_glass_enabled,
+            updated_at,
+            updated_by: credential.map(|c| c.updated_by),
+            last_success: attempts
+                .iter()
+                .find(|a| a.success)
+                .map(|a| a.attempted_at.to_jiff()),
+            recent_failures: Self::count_recent_failures(&attempts) as u32,
+            expires_at: updated_at.and_then(password_policy::expires_at),
+            policy: CONFIG.password_policy(),
+        })
+    }
+
+    async fn credential() -> Result<Option<CredentialRow>> {
+        let row = sqlx::query_as!(
+            CredentialRow,
+            r#"
+            SELECT
+                password_hash,
+                updated_at as "updated_at: _",
+                updated_by
+            FROM break_glass_credential
+            WHERE id = 1
+            "#,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Record a failed attempt, unless there have already been too many
+    /// recently. It's counted and recorded in one statement. Returns the
+    /// attempt, if it was recorded.
+    async fn start_attempt(pool: &SqlitePool) -> Result<Option<Uuid>> {
+        let id = Uuid::now_v7();
+        let now = Timestamp::now();
+        let cutoff = (now - FAILURE_WINDOW).to_sqlx();
+        let attempted_at = now.to_sqlx();
+        let max_failures = MAX_FAILURES as i64;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO break_glass_attempts (id, attempted_at, success)
+            SELECT ?, ?, FALSE
+            WHERE (
+                SELECT COUNT(*)
+                FROM break_glass_attempts
+                WHERE success = FALSE AND attempted_at > ?
+            ) < ?
+            "#,
+            id,
+            attempted_at,
+            cutoff,
+            max_failures,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok((result.rows_affected() == 1).then_some(id))
+    }
+
+    async fn mark_succeeded(attempt: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE break_glass_attempts
+            SET success = TRUE
+            WHERE id = ?
+            "#,
+            attempt,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn recent_attempts() -> Result<Vec<AttemptRow>> {
+        // Only the last few attempts can matter for rate limiting.
+        let limit = MAX_FAILURES as i64 * 4;
+
+        let rows = sqlx::query_as!(
+            AttemptRow,
+            r#"
+            SELECT
+                attempted_at as "attempted_at: _",
+                success
+            FROM break_glass_attempts
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows)
+    }
+
+    fn count_recent_failures(attempts: &[AttemptRow]) -> usize {
+        let cutoff = Timestamp::now() - FAILURE_WINDOW;
+        attempts
+            .iter()
+            .filter(|a| !a.success && a.attempted_at.to_jiff() > cutoff)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn attempts_at_once_cant_get_past_the_limit(pool: SqlitePool) {
+        let tasks: Vec<_> = (0..MAX_FAILURES * 3)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move { BreakGlass::start_attempt(&pool).await })
+            })
+            .collect();
+
+        let mut started = 0;
+        for task in tasks {
+            if task.await.unwrap().unwrap().is_some() {
+                started += 1;
+            }
+        }
+        assert_eq!(started, MAX_FAILURES);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn old_failures_dont_count(pool: SqlitePool) {
+        let long_ago = Timestamp::now() - FAILURE_WINDOW - Duration::from_secs(60);
+        for _ in 0..MAX_FAILURES {
+            sqlx::query(
+                "INSERT INTO break_glass_attempts (id, attempted_at, success) VALUES (?, ?, FALSE)",
+            )
+            .bind(Uuid::now_v7())
+            .bind(long_ago.to_sqlx())
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        assert!(BreakGlass::start_attempt(&pool).await.unwrap().is_some());
+    }
+}
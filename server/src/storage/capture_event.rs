@@ -0,0 +1,86 @@
+use types::{
+    Result,
+    capture::{CaptureEvent, CapturedEvent},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct CaptureEventRow {
+    id: Uuid,
+    event: String,
+}
+
+/// What the current or last diagnostic capture recorded.
+pub struct CaptureEvents;
+
+impl CaptureEvents {
+    pub async fn record(event: &CaptureEvent) -> Result<()> {
+        let id = Uuid::now_v7();
+        let id = id.as_bytes().as_slice();
+        let event = serde_json::to_string(event)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO capture_events (id, event)
+            VALUES (?, ?)
+            "#,
+            id,
+            event,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Everything recorded, oldest first.
+    pub async fn list() -> Result<Vec<CapturedEvent>> {
+        let rows = sqlx::query_as!(
+            CaptureEventRow,
+            r#"
+            SELECT
+                id as "id: _",
+                event
+            FROM capture_events
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(CapturedEvent {
+                    at: row.id.jiff_timestamp(),
+                    event: serde_json::from_str(&row.event)?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn count() -> Result<u32> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM capture_events
+            "#,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count as u32)
+    }
+
+    pub async fn clear() -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM capture_events
+            "#,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    email_queue::{MAX_EMAIL_ATTEMPTS, QueuedEmail},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct QueuedEmailRow {
+    id: Uuid,
+    recipient: String,
+    subject: String,
+    attempts: i64,
+    next_attempt_at: Option<SqlxTimestamp>,
+    sent_at: Option<SqlxTimestamp>,
+    error: Option<String>,
+}
+
+impl QueuedEmailRow {
+    fn into_email(self) -> QueuedEmail {
+        QueuedEmail {
+            id: self.id,
+            recipient: self.recipient,
+            subject: self.subject,
+            queued_at: self.id.jiff_timestamp(),
+            attempts: self.attempts as u32,
+            next_attempt_at: self.next_attempt_at.map(|t| t.to_jiff()),
+            sent_at: self.sent_at.map(|t| t.to_jiff()),
+            error: self.error,
+        }
+    }
+}
+
+/// An email taken from the queue to send.
+#[derive(Debug, Clone)]
+pub struct ClaimedEmail {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    /// Including this one.
+    pub attempts: u32,
+}
+
+/// Every email we send, kept until it's sent or given up on so it survives
+/// restarts and the SMTP relay being down.
+pub struct EmailQueue;
+
+impl EmailQueue {
+    /// Queue an email, due straight away.
+    pub async fn enqueue(recipient: &str, subject: &str, body: &str) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let id_bytes = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO email_queue (id, recipient, subject, body, next_attempt_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+            id_bytes,
+            recipient,
+            subject,
+            body,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Take a due email to send, holding it until `hold_until` so another
+    /// replica doesn't send it at the same time. Returns `None` if it isn't
+    /// due.
+    pub async fn claim(id: Uuid, hold_until: Timestamp) -> Result<Option<ClaimedEmail>> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+        let hold_until = hold_until.to_sqlx();
+
+        let row = sqlx::query!(
+            r#"
+            UPDATE email_queue
+            SET next_attempt_at = ?, attempts = attempts + 1
+            WHERE id = ? AND next_attempt_at <= ?
+            RETURNING recipient, subject, body, attempts
+            "#,
+            hold_until,
+            id,
+            now,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(row.map(|row| ClaimedEmail {
+            recipient: row.recipient,
+            subject: row.subject,
+            body: row.body,
+            attempts: row.attempts as u32,
+        }))
+    }
+
+    /// Emails waiting for their next attempt, oldest first.
+    pub async fn list_due() -> Result<Vec<Uuid>> {
+        let now = Timestamp::now().to_sqlx();
+
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id as "id: Uuid"
+            FROM email_queue
+            WHERE next_attempt_at <= ?
+            ORDER BY id
+            "#,
+            now,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// The latest emails, newest first, or only those given up on.
+    pub async fn list(failed_only: bool, limit: u32) -> Result<Vec<QueuedEmail>> {
+        let max_attempts = MAX_EMAIL_ATTEMPTS;
+
+        let rows = sqlx::query_as!(
+            QueuedEmailRow,
+            r#"
+            SELECT
+                id as "id: _",
+                recipient,
+                subject,
+                attempts,
+                next_attempt_at as "next_attempt_at: _",
+                sent_at as "sent_at: _",
+                error
+            FROM email_queue
+            WHERE NOT ? OR (sent_at IS NULL AND attempts >= ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            failed_only,
+            max_attempts,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows.into_iter().map(QueuedEmailRow::into_email).collect())
+    }
+
+    /// Mark an email sent, blanking its body.
+    pub async fn mark_sent(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE email_queue
+            SET sent_at = ?, body = '', next_attempt_at = NULL, error = NULL
+            WHERE id = ?
+            "#,
+            now,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt, to try again at `retry_at`, or never if
+    /// that's `None`.
+    pub async fn mark_failed(id: Uuid, error: &str, retry_at: Option<Timestamp>) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let retry_at = retry_at.map(|t| t.to_sqlx());
+
+        sqlx::query!(
+            r#"
+            UPDATE email_queue
+            SET error = ?, next_attempt_at = ?
+            WHERE id = ?
+            "#,
+            error,
+            retry_at,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Try an email that was given up on again, straight away.
+    pub async fn retry(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+        let max_attempts = MAX_EMAIL_ATTEMPTS;
+
+        sqlx::query!(
+            r#"
+            UPDATE email_queue
+            SET attempts = 0, next_attempt_at = ?
+            WHERE id = ? AND sent_at IS NULL AND attempts >= ?
+            "#,
+            now,
+            id,
+            max_attempts,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop emails to a deleted person that haven't been sent, and replace
+    /// their address on the rest.
+    pub async fn forget_recipient(email: &str, alias: &str) -> Result<()> {
+        let mut tx = POOL.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM email_queue
+            WHERE recipient = ? AND sent_at IS NULL
+            "#,
+            email,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE email_queue
+            SET recipient = ?
+            WHERE recipient = ?
+            "#,
+            alias,
+            email,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Delete emails sent before `cutoff`. Those given up on are kept, to be
+    /// retried or looked into.
+    pub async fn delete_sent_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM email_queue
+            WHERE sent_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
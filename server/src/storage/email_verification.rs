@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// The code last sent to verify an address, if there's one outstanding.
+pub struct PendingCode {
+    pub code_hash: String,
+    pub expires_at: Timestamp,
+    pub attempts: i64,
+}
+
+struct PendingCodeRow {
+    code_hash: Option<String>,
+    code_expires_at: Option<SqlxTimestamp>,
+    attempts: i64,
+}
+
+struct VerifiedRow {
+    email: String,
+    verified_at: Option<SqlxTimestamp>,
+}
+
+/// Storage for which email addresses people have verified. Addresses are
+/// stored lowercased.
+pub struct EmailVerifications;
+
+impl EmailVerifications {
+    /// Record a newly sent code, replacing any earlier one. An address that
+    /// was already verified stays verified.
+    pub async fn start(
+        user_id: Uuid,
+        email: &str,
+        code_hash: &str,
+        expires_at: Timestamp,
+    ) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+        let email = email.to_lowercase();
+        let expires_at = expires_at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO email_verifications (user_id, email, code_hash, code_expires_at, attempts)
+            VALUES (?, ?, ?, ?, 0)
+            ON CONFLICT (user_id, email) DO UPDATE SET
+                code_hash = excluded.code_hash,
+                code_expires_at = excluded.code_expires_at,
+                attempts = 0
+            "#,
+            user_id,
+            email,
+            code_hash,
+            expires_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn pending_code(user_id: Uuid, email: &str) -> Result<Option<PendingCode>> {
+        let user_id = user_id.as_bytes().as_slice();
+        let email = email.to_lowercase();
+
+        let row = sqlx::query_as!(
+            PendingCodeRow,
+            r#"
+            SELECT
+                code_hash,
+                code_expires_at as "code_expires_at: _",
+                attempts
+            FROM email_verifications
+            WHERE user_id = ? AND email = ?
+            "#,
+            user_id,
+            email,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            Some(PendingCode {
+                code_hash: row.code_hash?,
+                expires_at: row.code_expires_at?.to_jiff(),
+                attempts: row.attempts,
+            })
+        }))
+    }
+
+    /// Count a wrong guess at an address's code.
+    pub async fn record_attempt(user_id: Uuid, email: &str) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+        let email = email.to_lowercase();
+
+        sqlx::query!(
+            r#"
+            UPDATE email_verifications
+            SET attempts = attempts + 1
+            WHERE user_id = ? AND email = ?
+            "#,
+            user_id,
+            email,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark an address verified, using up its code.
+    pub async fn verify(user_id: Uuid, email: &str) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+        let email = email.to_lowercase();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE email_verifications
+            SET verified_at = ?, code_hash = NULL, code_expires_at = NULL
+            WHERE user_id = ? AND email = ?
+            "#,
+            now,
+            user_id,
+            email,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// When each of a person's verified addresses was verified, by address.
+    pub async fn verified(user_id: Uuid) -> Result<HashMap<String, Timestamp>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            VerifiedRow,
+            r#"
+            SELECT
+                email,
+                verified_at as "verified_at: _"
+            FROM email_verifications
+            WHERE user_id = ? AND verified_at IS NOT NULL
+            "#,
+            user_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| Some((row.email, row.verified_at?.to_jiff())))
+            .collect())
+    }
+
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM email_verifications
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
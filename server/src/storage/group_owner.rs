@@ -0,0 +1,96 @@
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Who owns which groups. Owners can make provision links for their groups.
+pub struct GroupOwners;
+
+impl GroupOwners {
+    pub async fn add(group_id: Uuid, owner_id: Uuid) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+        let owner_id = owner_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO group_owners (group_id, owner_id)
+            VALUES (?, ?)
+            ON CONFLICT DO NOTHING
+            "#,
+            group_id,
+            owner_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(group_id: Uuid, owner_id: Uuid) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+        let owner_id = owner_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM group_owners
+            WHERE group_id = ? AND owner_id = ?
+            "#,
+            group_id,
+            owner_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The groups `owner_id` owns.
+    pub async fn groups_of(owner_id: Uuid) -> Result<Vec<Uuid>> {
+        let owner_id = owner_id.as_bytes().as_slice();
+
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT group_id as "group_id: Uuid"
+            FROM group_owners
+            WHERE owner_id = ?
+            "#,
+            owner_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(ids)
+    }
+
+    pub async fn delete_for_group(group_id: Uuid) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM group_owners
+            WHERE group_id = ?
+            "#,
+            group_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_for_owner(owner_id: Uuid) -> Result<()> {
+        let owner_id = owner_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM group_owners
+            WHERE owner_id = ?
+            "#,
+            owner_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
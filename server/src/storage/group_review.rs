@@ -0,0 +1,211 @@
+use jiff::{Timestamp, civil::Date};
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{Result, group_review::GroupReview, kanidm::Spn};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct GroupReviewRow {
+    group_id: Uuid,
+    group_name: String,
+    review_by: Option<String>,
+    expires_on: Option<String>,
+    lock_when_overdue: bool,
+    reviewed_at: Option<SqlxTimestamp>,
+    reviewed_by: Option<String>,
+    flagged_at: Option<SqlxTimestamp>,
+    updated_by: String,
+    updated_at: SqlxTimestamp,
+}
+
+impl GroupReviewRow {
+    fn into_review(self) -> Result<GroupReview> {
+        Ok(GroupReview {
+            group_id: self.group_id,
+            group_name: self.group_name,
+            review_by: self.review_by.as_deref().map(str::parse).transpose()?,
+            expires_on: self.expires_on.as_deref().map(str::parse).transpose()?,
+            lock_when_overdue: self.lock_when_overdue,
+            reviewed_at: self.reviewed_at.map(|t| t.to_jiff()),
+            reviewed_by: self.reviewed_by,
+            flagged_at: self.flagged_at.map(|t| t.to_jiff()),
+            updated_by: self.updated_by,
+            updated_at: self.updated_at.to_jiff(),
+        })
+    }
+}
+
+/// The review and expiry dates set on groups.
+pub struct GroupReviews;
+
+impl GroupReviews {
+    pub async fn get(group_id: Uuid) -> Result<Option<GroupReview>> {
+        let group_id = group_id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            GroupReviewRow,
+            r#"
+            SELECT
+                group_id as "group_id: _",
+                group_name,
+                review_by,
+                expires_on,
+                lock_when_overdue,
+                reviewed_at as "reviewed_at: _",
+                reviewed_by,
+                flagged_at as "flagged_at: _",
+                updated_by,
+                updated_at as "updated_at: _"
+            FROM group_reviews
+            WHERE group_id = ?
+            "#,
+            group_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        row.map(GroupReviewRow::into_review).transpose()
+    }
+
+    /// The review for a group given by id or by name, which is how
+    /// membership changes name their group.
+    pub async fn find(id_or_name: &str) -> Result<Option<GroupReview>> {
+        if let Ok(group_id) = id_or_name.parse() {
+            return Self::get(group_id).await;
+        }
+        let name = Spn::parse(id_or_name).name;
+
+        let row = sqlx::query_as!(
+            GroupReviewRow,
+            r#"
+            SELECT
+                group_id as "group_id: _",
+                group_name,
+                review_by,
+                expires_on,
+                lock_when_overdue,
+                reviewed_at as "reviewed_at: _",
+                reviewed_by,
+                flagged_at as "flagged_at: _",
+                updated_by,
+                updated_at as "updated_at: _"
+            FROM group_reviews
+            WHERE group_name = ?
+            "#,
+            name,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        row.map(GroupReviewRow::into_review).transpose()
+    }
+
+    /// Reviews with a date on or before `today`, soonest first.
+    pub async fn list_due(today: Date) -> Result<Vec<GroupReview>> {
+        let today = today.to_string();
+
+        let rows = sqlx::query_as!(
+            GroupReviewRow,
+            r#"
+            SELECT
+                group_id as "group_id: _",
+                group_name,
+                review_by,
+                expires_on,
+                lock_when_overdue,
+                reviewed_at as "reviewed_at: _",
+                reviewed_by,
+                flagged_at as "flagged_at: _",
+                updated_by,
+                updated_at as "updated_at: _"
+            FROM group_reviews
+            WHERE review_by <= ?1 OR expires_on <= ?1
+            ORDER BY min(coalesce(review_by, expires_on), coalesce(expires_on, review_by))
+            "#,
+            today,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(GroupReviewRow::into_review).collect()
+    }
+
+    /// Save a group's dates. Changing them means admins are told again when
+    /// the new ones pass.
+    pub async fn set(review: &GroupReview) -> Result<()> {
+        let group_id = review.group_id.as_bytes().as_slice();
+        let review_by = review.review_by.map(|d| d.to_string());
+        let expires_on = review.expires_on.map(|d| d.to_string());
+        let reviewed_at = review.reviewed_at.map(|t| t.to_sqlx());
+        let updated_at = review.updated_at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO group_reviews (
+                group_id, group_name, review_by, expires_on, lock_when_overdue,
+                reviewed_at, reviewed_by, updated_by, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (group_id) DO UPDATE SET
+                group_name = excluded.group_name,
+                review_by = excluded.review_by,
+                expires_on = excluded.expires_on,
+                lock_when_overdue = excluded.lock_when_overdue,
+                reviewed_at = excluded.reviewed_at,
+                reviewed_by = excluded.reviewed_by,
+                flagged_at = NULL,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+            "#,
+            group_id,
+            review.group_name,
+            review_by,
+            expires_on,
+            review.lock_when_overdue,
+            reviewed_at,
+            review.reviewed_by,
+            review.updated_by,
+            updated_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Note that admins were told the group is overdue.
+    pub async fn mark_flagged(group_id: Uuid, at: Timestamp) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+        let at = at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE group_reviews
+            SET flagged_at = ?
+            WHERE group_id = ?
+            "#,
+            at,
+            group_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(group_id: Uuid) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM group_reviews
+            WHERE group_id = ?
+            "#,
+            group_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    group_webhook::{GroupWebhook, MAX_WEBHOOK_ATTEMPTS, MembershipAction, WebhookDelivery},
+};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct GroupWebhookRow {
+    group_id: Uuid,
+    group_name: String,
+    url: String,
+    secret: String,
+    on_add: bool,
+    on_remove: bool,
+    updated_by: String,
+    updated_at: SqlxTimestamp,
+}
+
+impl GroupWebhookRow {
+    fn into_webhook(self) -> GroupWebhook {
+        GroupWebhook {
+            group_id: self.group_id,
+            group_name: self.group_name,
+            url: self.url,
+            secret: self.secret,
+            on_add: self.on_add,
+            on_remove: self.on_remove,
+            updated_by: self.updated_by,
+            updated_at: self.updated_at.to_jiff(),
+        }
+    }
+}
+
+/// The webhook each group calls when its members change, if it has one.
+pub struct GroupWebhooks;
+
+impl GroupWebhooks {
+    pub async fn get(group_id: Uuid) -> Result<Option<GroupWebhook>> {
+        let group_id = group_id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            GroupWebhookRow,
+            r#"
+            SELECT
+                group_id as "group_id: _",
+                group_name,
+                url,
+                secret,
+                on_add,
+                on_remove,
+                updated_by,
+                updated_at as "updated_at: _"
+            FROM group_webhooks
+            WHERE group_id = ?
+            "#,
+            group_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(row.map(GroupWebhookRow::into_webhook))
+    }
+
+    pub async fn set(webhook: &GroupWebhook) -> Result<()> {
+        let group_id = webhook.group_id.as_bytes().as_slice();
+        let updated_at = webhook.updated_at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO group_webhooks
+                (group_id, group_name, url, secret, on_add, on_remove, updated_by, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (group_id) DO UPDATE SET
+                group_name = excluded.group_name,
+                url = excluded.url,
+                secret = excluded.secret,
+                on_add = excluded.on_add,
+                on_remove = excluded.on_remove,
+                updated_by = excluded.updated_by,
+                updated_at = excluded.updated_at
+            "#,
+            group_id,
+            webhook.group_name,
+            webhook.url,
+            webhook.secret,
+            webhook.on_add,
+            webhook.on_remove,
+            webhook.updated_by,
+            updated_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a group's webhook, and any deliveries still waiting to be made.
+    pub async fn delete(group_id: Uuid) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+        let mut tx = POOL.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM group_webhooks
+            WHERE group_id = ?
+            "#,
+            group_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM webhook_deliveries
+            WHERE group_id = ? AND delivered_at IS NULL
+            "#,
+            group_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+struct WebhookDeliveryRow {
+    id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    action: String,
+    attempts: i64,
+    next_attempt_at: Option<SqlxTimestamp>,
+    delivered_at: Option<SqlxTimestamp>,
+    error: Option<String>,
+}
+
+impl WebhookDeliveryRow {
+    fn into_delivery(self) -> Result<WebhookDelivery> {
+        Ok(WebhookDelivery {
+            id: self.id,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            action: self.action.parse()?,
+            attempts: self.attempts as u32,
+            next_attempt_at: self.next_attempt_at.map(|t| t.to_jiff()),
+            delivered_at: self.delivered_at.map(|t| t.to_jiff()),
+            error: self.error,
+        })
+    }
+}
+
+/// Calls to group webhooks, kept until they're delivered or given up on so
+/// they survive restarts.
+pub struct WebhookDeliveries;
+
+impl WebhookDeliveries {
+    /// Queue a call, due straight away.
+    pub async fn enqueue(
+        group_id: Uuid,
+        user_id: Uuid,
+        user_name: &str,
+        action: MembershipAction,
+    ) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let id_bytes = id.as_bytes().as_slice();
+        let group_id = group_id.as_bytes().as_slice();
+        let user_id = user_id.as_bytes().as_slice();
+        let action = action.as_str();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries
+                (id, group_id, user_id, user_name, action, next_attempt_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            id_bytes,
+            group_id,
+            user_id,
+            user_name,
+            action,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Take a due delivery to attempt, holding it until `hold_until` so
+    /// another replica doesn't try it at the same time. Returns its group,
+    /// or `None` if it isn't due.
+    pub async fn claim(id: Uuid, hold_until: Timestamp) -> Result<Option<Uuid>> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+        let hold_until = hold_until.to_sqlx();
+
+        let group_id = sqlx::query_scalar!(
+            r#"
+            UPDATE webhook_deliveries
+            SET next_attempt_at = ?, attempts = attempts + 1
+            WHERE id = ? AND next_attempt_at <= ?
+            RETURNING group_id as "group_id: Uuid"
+            "#,
+            hold_until,
+            id,
+            now,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(group_id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<WebhookDelivery> {
+        let id = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            WebhookDeliveryRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                action,
+                attempts,
+                next_attempt_at as "next_attempt_at: _",
+                delivered_at as "delivered_at: _",
+                error
+            FROM webhook_deliveries
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_delivery()
+    }
+
+    /// Deliveries waiting for their next attempt, oldest first.
+    pub async fn list_due() -> Result<Vec<Uuid>> {
+        let now = Timestamp::now().to_sqlx();
+
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id as "id: Uuid"
+            FROM webhook_deliveries
+            WHERE next_attempt_at <= ?
+            ORDER BY id
+            "#,
+            now,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// A group's deliveries, newest first.
+    pub async fn list_for_group(group_id: Uuid, limit: u32) -> Result<Vec<WebhookDelivery>> {
+        let group_id = group_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            WebhookDeliveryRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                action,
+                attempts,
+                next_attempt_at as "next_attempt_at: _",
+                delivered_at as "delivered_at: _",
+                error
+            FROM webhook_deliveries
+            WHERE group_id = ?
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            group_id,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(WebhookDeliveryRow::into_delivery)
+            .collect()
+    }
+
+    pub async fn mark_delivered(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET delivered_at = ?, next_attempt_at = NULL, error = NULL
+            WHERE id = ?
+            "#,
+            now,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt, to try again at `retry_at`, or never if
+    /// that's `None`.
+    pub async fn mark_failed(id: Uuid, error: &str, retry_at: Option<Timestamp>) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let retry_at = retry_at.map(|t| t.to_sqlx());
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET error = ?, next_attempt_at = ?
+            WHERE id = ?
+            "#,
+            error,
+            retry_at,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Try a delivery that was given up on again, straight away.
+    pub async fn retry(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+        let max_attempts = MAX_WEBHOOK_ATTEMPTS;
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET attempts = 0, next_attempt_at = ?
+            WHERE id = ? AND delivered_at IS NULL AND attempts >= ?
+            "#,
+            now,
+            id,
+            max_attempts,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete the deliveries about a user that were made. Those still to be
+    /// made are kept, so downstream systems still hear about them.
+    pub async fn delete_delivered_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM webhook_deliveries
+            WHERE user_id = ? AND delivered_at IS NOT NULL
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete deliveries made before `cutoff`. Those given up on are kept,
+    /// to be retried or looked into.
+    pub async fn delete_delivered_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM webhook_deliveries
+            WHERE delivered_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
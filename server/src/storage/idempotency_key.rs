@@ -0,0 +1,120 @@
+use jiff::{SignedDuration, Timestamp};
+use jiff_sqlx::ToSqlx;
+use types::{Result, err};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// How long a key is remembered. Retries come within moments, but a client
+/// can queue a request while offline for longer.
+const KEY_TTL: SignedDuration = SignedDuration::from_hours(24);
+
+/// What to do with a request, given its idempotency key.
+pub enum IdempotencyClaim {
+    /// The key is new, so the request should run.
+    New,
+    /// A request with the key already succeeded, with this response.
+    Done(String),
+    /// A request with the key is still running.
+    InProgress,
+}
+
+/// Idempotency keys of mutating requests, and what they returned.
+pub struct IdempotencyKeys;
+
+impl IdempotencyKeys {
+    /// Claim `key` for a request to `endpoint`, unless it's been used before.
+    pub async fn claim(key: Uuid, endpoint: &str) -> Result<IdempotencyClaim> {
+        let id = key.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (key, endpoint, created_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT (key) DO NOTHING
+            "#,
+            id,
+            endpoint,
+            now,
+        )
+        .execute(&*POOL)
+        .await?
+        .rows_affected()
+            == 1;
+        if inserted {
+            return Ok(IdempotencyClaim::New);
+        }
+
+        let row = sqlx::query!(
+            r#"
+            SELECT endpoint, response
+            FROM idempotency_keys
+            WHERE key = ?
+            "#,
+            id,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        if row.endpoint != endpoint {
+            return Err(err!("this idempotency key was used for another request"));
+        }
+        match row.response {
+            Some(response) => Ok(IdempotencyClaim::Done(response)),
+            None => Ok(IdempotencyClaim::InProgress),
+        }
+    }
+
+    /// Record the response to the request that claimed `key`.
+    pub async fn finish(key: Uuid, response: &str) -> Result<()> {
+        let id = key.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET response = ?
+            WHERE key = ?
+            "#,
+            response,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forget `key`, so the request can be tried again.
+    pub async fn release(key: Uuid) -> Result<()> {
+        let id = key.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE key = ?
+            "#,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_expired() -> Result<()> {
+        let cutoff = (Timestamp::now() - KEY_TTL).to_sqlx();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE created_at <= ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    job::{Job, JobItem, JobKind, JobOutput},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct JobRow {
+    id: Uuid,
+    kind: String,
+    started_by: String,
+    total: i64,
+    done: i64,
+    failed: i64,
+    finished_at: Option<SqlxTimestamp>,
+    error: Option<String>,
+}
+
+impl JobRow {
+    fn into_job(self) -> Result<Job> {
+        Ok(Job {
+            id: self.id,
+            kind: self.kind.parse()?,
+            started_by: self.started_by,
+            created_at: self.id.jiff_timestamp(),
+            total: self.total as u32,
+            done: self.done as u32,
+            failed: self.failed as u32,
+            finished_at: self.finished_at.map(|t| t.to_jiff()),
+            error: self.error,
+        })
+    }
+}
+
+struct JobItemRow {
+    label: String,
+    error: Option<String>,
+    output: Option<String>,
+}
+
+/// Storage for bulk [`Job`]s and their per-item results.
+pub struct Jobs;
+
+impl Jobs {
+    pub async fn create(kind: JobKind, started_by: &str, total: u32) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let kind = kind.as_str();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO jobs (id, kind, started_by, total)
+            VALUES (?, ?, ?, ?)
+            "#,
+            id,
+            kind,
+            started_by,
+            total,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<Job> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT
+                id as "id: _",
+                kind,
+                started_by,
+                total,
+                done,
+                failed,
+                finished_at as "finished_at: _",
+                error
+            FROM jobs
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_job()
+    }
+
+    /// List jobs, newest first.
+    pub async fn list() -> Result<Vec<Job>> {
+        let rows = sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT
+                id as "id: _",
+                kind,
+                started_by,
+                total,
+                done,
+                failed,
+                finished_at as "finished_at: _",
+                error
+            FROM jobs
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(JobRow::into_job).collect()
+    }
+
+    /// The results of the items tried so far, in order.
+    pub async fn items(id: Uuid) -> Result<Vec<JobItem>> {
+        let id = id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            JobItemRow,
+            r#"
+            SELECT label, error, output
+            FROM job_items
+            WHERE job_id = ?
+            ORDER BY position
+            "#,
+            id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(JobItem {
+                    label: row.label,
+                    error: row.error,
+                    output: row
+                        .output
+                        .as_deref()
+                        .map(serde_json::from_str)
+                        .transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Record the result of one item, and count it towards the job's
+    /// progress.
+    pub async fn record_item(
+        id: Uuid,
+        position: u32,
+        label: &str,
+        error: Option<&str>,
+        output: Option<&JobOutput>,
+    ) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let failed = error.is_some();
+        let output = output.map(serde_json::to_string).transpose()?;
+
+        let mut tx = POOL.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO job_items (job_id, position, label, error, output)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+            id,
+            position,
+            label,
+            error,
+            output,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET done = done + 1, failed = failed + ?
+            WHERE id = ?
+            "#,
+            failed,
+            id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Mark a job as finished, with an error if it failed as a whole.
+    pub async fn finish(id: Uuid, error: Option<&str>) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let finished_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET finished_at = ?, error = ?
+            WHERE id = ?
+            "#,
+            finished_at,
+            error,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete jobs that finished before `cutoff`, returning how many.
+    pub async fn delete_finished_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM jobs
+            WHERE finished_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Replace an admin's name on the jobs they started.
+    pub async fn rename_starter(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET started_by = ?
+            WHERE started_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use jiff_sqlx::ToSqlx;
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Leases on background jobs, so only one replica runs each.
+pub struct JobLeases;
+
+impl JobLeases {
+    /// Take or renew the lease on a job for `holder`. Returns whether
+    /// `holder` now has it.
+    pub async fn acquire(name: &str, holder: Uuid, ttl: Duration) -> Result<bool> {
+        let holder = holder.as_bytes().as_slice();
+        let now = Timestamp::now();
+        let expires_at = (now + ttl).to_sqlx();
+        let now = now.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO job_leases (name, holder, expires_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT (name) DO UPDATE SET
+                holder = excluded.holder,
+                expires_at = excluded.expires_at
+            WHERE job_leases.holder = excluded.holder OR job_leases.expires_at <= ?
+            "#,
+            name,
+            holder,
+            expires_at,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
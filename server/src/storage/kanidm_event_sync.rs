@@ -0,0 +1,88 @@
+use std::collections::BTreeSet;
+
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct KanidmEventSyncRow {
+    fingerprint: String,
+    session_ids: String,
+}
+
+/// What was last read from Kanidm about a person.
+#[derive(Debug, Clone, Default)]
+pub struct KanidmEventSync {
+    /// A digest of their credentials.
+    pub fingerprint: String,
+    /// The sign-in sessions already recorded as activity.
+    pub session_ids: BTreeSet<Uuid>,
+}
+
+/// Where the sync of Kanidm's sign-ins and credential changes got to for each
+/// person.
+pub struct KanidmEventSyncs;
+
+impl KanidmEventSyncs {
+    pub async fn get(user_id: Uuid) -> Result<Option<KanidmEventSync>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            KanidmEventSyncRow,
+            r#"
+            SELECT fingerprint, session_ids
+            FROM kanidm_event_syncs
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        row.map(|row| {
+            Ok(KanidmEventSync {
+                fingerprint: row.fingerprint,
+                session_ids: serde_json::from_str(&row.session_ids)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn set(user_id: Uuid, sync: &KanidmEventSync) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+        let session_ids = serde_json::to_string(&sync.session_ids)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO kanidm_event_syncs (user_id, fingerprint, session_ids)
+            VALUES (?, ?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET
+                fingerprint = excluded.fingerprint,
+                session_ids = excluded.session_ids
+            "#,
+            user_id,
+            sync.fingerprint,
+            session_ids,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM kanidm_event_syncs
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
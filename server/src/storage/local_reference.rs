@@ -0,0 +1,77 @@
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// A local record that refers to a Kanidm user or group.
+pub struct LocalReference {
+    pub id: Uuid,
+    /// What kind of record it is, like "tags".
+    pub place: String,
+    /// Their name, if the record keeps a copy of it.
+    pub name: Option<String>,
+}
+
+/// The users and groups that local records refer to, to find ones left behind
+/// by entries deleted from Kanidm directly.
+pub struct LocalReferences;
+
+impl LocalReferences {
+    pub async fn users() -> Result<Vec<LocalReference>> {
+        let references = sqlx::query_as!(
+            LocalReference,
+            r#"
+            SELECT user_id AS "id!: _", 'onboarding' AS "place!: String", user_name AS "name: String"
+            FROM onboardings
+            UNION ALL
+            SELECT user_id, 'email verification', NULL FROM email_verifications
+            UNION ALL
+            SELECT user_id, 'phone number', NULL FROM phone_numbers
+            UNION ALL
+            SELECT user_id, 'profile change', user_name FROM profile_changes
+            UNION ALL
+            SELECT user_id, 'provision approval', user_name FROM provision_approvals
+            UNION ALL
+            SELECT user_id, 'scheduled change', user_name FROM scheduled_membership_changes
+            UNION ALL
+            SELECT user_id, 'reset watch', user_name FROM reset_watches
+            UNION ALL
+            SELECT user_id, 'Kanidm activity sync', NULL FROM kanidm_event_syncs
+            UNION ALL
+            SELECT user_id, 'rotation', user_name FROM rotation_members
+            UNION ALL
+            SELECT user_id, 'tags', NULL FROM user_tags
+            UNION ALL
+            SELECT owner_id, 'group ownership', NULL FROM group_owners
+            UNION ALL
+            SELECT user_id, 'avatar', NULL FROM avatars
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(references)
+    }
+
+    pub async fn groups() -> Result<Vec<LocalReference>> {
+        let references = sqlx::query_as!(
+            LocalReference,
+            r#"
+            SELECT group_id AS "id!: _", 'owners' AS "place!: String", NULL AS "name: String"
+            FROM group_owners
+            UNION ALL
+            SELECT group_id, 'webhook', group_name FROM group_webhooks
+            UNION ALL
+            SELECT group_id, 'review date', group_name FROM group_reviews
+            UNION ALL
+            SELECT group_id, 'scheduled change', group_name
+            FROM scheduled_membership_changes
+            WHERE status = 'pending'
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(references)
+    }
+}
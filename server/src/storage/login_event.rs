@@ -0,0 +1,211 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    login_event::{LoginEvent, LoginFailure},
+};
+use uuid::Uuid;
+
+use crate::{
+    device::{Device, describe_user_agent},
+    storage::POOL,
+};
+
+struct LoginEventRow {
+    id: Uuid,
+    at: SqlxTimestamp,
+    user_id: Option<String>,
+    username: Option<String>,
+    failure: Option<String>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    location: Option<String>,
+}
+
+impl LoginEventRow {
+    fn into_event(self) -> Result<LoginEvent> {
+        Ok(LoginEvent {
+            id: self.id,
+            at: self.at.to_jiff(),
+            user_id: self.user_id,
+            username: self.username,
+            failure: self.failure.as_deref().map(str::parse).transpose()?,
+            ip_address: self.ip_address,
+            device: self.user_agent.as_deref().and_then(describe_user_agent),
+            user_agent: self.user_agent,
+            location: self.location,
+        })
+    }
+}
+
+/// Who was signing in, if we know.
+#[derive(Debug, Clone)]
+pub struct LoginUser {
+    pub user_id: String,
+    pub username: String,
+}
+
+/// Sign-ins to AuthIt!, successful or not.
+pub struct LoginEvents;
+
+impl LoginEvents {
+    pub async fn record(
+        user: Option<&LoginUser>,
+        failure: Option<LoginFailure>,
+        device: &Device,
+    ) -> Result<()> {
+        let id = Uuid::now_v7();
+        let at = Timestamp::now().to_sqlx();
+        let user_id = user.map(|u| u.user_id.as_str());
+        let username = user.map(|u| u.username.as_str());
+        let failure = failure.map(|f| f.as_str());
+
+        sqlx::query!(
+            r#"
+            INSERT INTO login_events
+                (id, at, user_id, username, failure, ip_address, user_agent, location)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            at,
+            user_id,
+            username,
+            failure,
+            device.ip_address,
+            device.user_agent,
+            device.location,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The latest sign-ins by anyone, newest first.
+    pub async fn list_recent(limit: u32) -> Result<Vec<LoginEvent>> {
+        let rows = sqlx::query_as!(
+            LoginEventRow,
+            r#"
+            SELECT
+                id as "id: _",
+                at as "at: _",
+                user_id,
+                username,
+                failure,
+                ip_address,
+                user_agent,
+                location
+            FROM login_events
+            ORDER BY at DESC
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(LoginEventRow::into_event).collect()
+    }
+
+    /// A user's latest sign-ins, newest first.
+    pub async fn list_for_user(user_id: &str, limit: u32) -> Result<Vec<LoginEvent>> {
+        let rows = sqlx::query_as!(
+            LoginEventRow,
+            r#"
+            SELECT
+                id as "id: _",
+                at as "at: _",
+                user_id,
+                username,
+                failure,
+                ip_address,
+                user_agent,
+                location
+            FROM login_events
+            WHERE user_id = ?
+            ORDER BY at DESC
+            LIMIT ?
+            "#,
+            user_id,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(LoginEventRow::into_event).collect()
+    }
+
+    /// When each sign-in from `from` up to `to` was, and whether it
+    /// succeeded.
+    pub async fn list_outcomes_between(
+        from: Timestamp,
+        to: Timestamp,
+    ) -> Result<Vec<(Timestamp, bool)>> {
+        let from = from.to_sqlx();
+        let to = to.to_sqlx();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                at as "at: SqlxTimestamp",
+                failure
+            FROM login_events
+            WHERE at >= ? AND at < ?
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.at.to_jiff(), row.failure.is_none()))
+            .collect())
+    }
+
+    pub async fn count_for_user(user_id: &str) -> Result<u32> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM login_events
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count as u32)
+    }
+
+    pub async fn delete_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM login_events
+            WHERE at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete_for_user(user_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM login_events
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    offboarding::{ChecklistStep, Offboarding},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct OffboardingRow {
+    id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    started_by: String,
+    steps: String,
+    delete_at: Option<SqlxTimestamp>,
+    deleted_at: Option<SqlxTimestamp>,
+    completed_at: Option<SqlxTimestamp>,
+}
+
+impl OffboardingRow {
+    fn into_offboarding(self) -> Result<Offboarding> {
+        Ok(Offboarding {
+            id: self.id,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            started_by: self.started_by,
+            started_at: self.id.jiff_timestamp(),
+            steps: serde_json::from_str(&self.steps)?,
+            delete_at: self.delete_at.map(|t| t.to_jiff()),
+            deleted_at: self.deleted_at.map(|t| t.to_jiff()),
+            completed_at: self.completed_at.map(|t| t.to_jiff()),
+        })
+    }
+}
+
+/// Storage for [`Offboarding`] checklists.
+pub struct Offboardings;
+
+impl Offboardings {
+    pub async fn create(
+        user_id: Uuid,
+        user_name: &str,
+        started_by: &str,
+        steps: Vec<ChecklistStep>,
+    ) -> Result<Offboarding> {
+        let id = Uuid::now_v7();
+        let steps_json = serde_json::to_string(&steps)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO offboardings (id, user_id, user_name, started_by, steps)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+            id,
+            user_id,
+            user_name,
+            started_by,
+            steps_json,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(Offboarding {
+            id,
+            user_id,
+            user_name: user_name.to_string(),
+            started_by: started_by.to_string(),
+            started_at: id.jiff_timestamp(),
+            steps,
+            delete_at: None,
+            deleted_at: None,
+            completed_at: None,
+        })
+    }
+
+    pub async fn find(id: Uuid) -> Result<Offboarding> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            OffboardingRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                started_by,
+                steps,
+                delete_at as "delete_at: _",
+                deleted_at as "deleted_at: _",
+                completed_at as "completed_at: _"
+            FROM offboardings
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_offboarding()
+    }
+
+    /// List all offboardings, newest first.
+    pub async fn list() -> Result<Vec<Offboarding>> {
+        let rows = sqlx::query_as!(
+            OffboardingRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                started_by,
+                steps,
+                delete_at as "delete_at: _",
+                deleted_at as "deleted_at: _",
+                completed_at as "completed_at: _"
+            FROM offboardings
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(OffboardingRow::into_offboarding)
+            .collect()
+    }
+
+    /// Whether the user has an offboarding that isn't complete.
+    pub async fn has_active(user_id: Uuid) -> Result<bool> {
+        let user_id_bytes = user_id.as_bytes().as_slice();
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM offboardings
+            WHERE user_id = ? AND completed_at IS NULL
+            "#,
+            user_id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Offboardings whose scheduled deletion has come due.
+    pub async fn due_for_deletion(now: Timestamp) -> Result<Vec<Offboarding>> {
+        let now = now.to_sqlx();
+
+        let rows = sqlx::query_as!(
+            OffboardingRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                started_by,
+                steps,
+                delete_at as "delete_at: _",
+                deleted_at as "deleted_at: _",
+                completed_at as "completed_at: _"
+            FROM offboardings
+            WHERE delete_at <= ? AND deleted_at IS NULL
+            "#,
+            now,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(OffboardingRow::into_offboarding)
+            .collect()
+    }
+
+    /// Save an offboarding's steps, schedule, and completion.
+    pub async fn update(offboarding: &Offboarding) -> Result<()> {
+        let id = offboarding.id.as_bytes().as_slice();
+        let steps = serde_json::to_string(&offboarding.steps)?;
+        let delete_at = offboarding.delete_at.map(|t| t.to_sqlx());
+        let deleted_at = offboarding.deleted_at.map(|t| t.to_sqlx());
+        let completed_at = offboarding.completed_at.map(|t| t.to_sqlx());
+
+        sqlx::query!(
+            r#"
+            UPDATE offboardings
+            SET steps = ?, delete_at = ?, deleted_at = ?, completed_at = ?
+            WHERE id = ?
+            "#,
+            steps,
+            delete_at,
+            deleted_at,
+            completed_at,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM offboardings
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace a person's name wherever it's recorded as the offboarded user
+    /// or the admin who started an offboarding.
+    pub async fn rename_person(name: &str, new_name: &str) -> Result<()> {
+        let mut tx = POOL.begin().await?;
+        sqlx::query!(
+            r#"
+            UPDATE offboardings
+            SET user_name = ?
+            WHERE user_name = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE offboardings
+            SET started_by = ?
+            WHERE started_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
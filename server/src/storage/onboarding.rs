@@ -0,0 +1,182 @@
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{Result, onboarding::Onboarding};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct OnboardingRow {
+    id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    required_groups: String,
+    credentials_set_at: Option<SqlxTimestamp>,
+    groups_added_at: Option<SqlxTimestamp>,
+    first_login_at: Option<SqlxTimestamp>,
+}
+
+impl OnboardingRow {
+    fn into_onboarding(self) -> Result<Onboarding> {
+        Ok(Onboarding {
+            id: self.id,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            started_at: self.id.jiff_timestamp(),
+            required_groups: serde_json::from_str(&self.required_groups)?,
+            credentials_set_at: self.credentials_set_at.map(|t| t.to_jiff()),
+            groups_added_at: self.groups_added_at.map(|t| t.to_jiff()),
+            first_login_at: self.first_login_at.map(|t| t.to_jiff()),
+        })
+    }
+}
+
+/// Storage for [`Onboarding`] progress.
+pub struct Onboardings;
+
+impl Onboardings {
+    pub async fn create(
+        user_id: Uuid,
+        user_name: &str,
+        required_groups: &[String],
+    ) -> Result<Onboarding> {
+        let id = Uuid::now_v7();
+        let groups = serde_json::to_string(required_groups)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO onboardings (id, user_id, user_name, required_groups)
+            VALUES (?, ?, ?, ?)
+            "#,
+            id,
+            user_id,
+            user_name,
+            groups,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(Onboarding {
+            id,
+            user_id,
+            user_name: user_name.to_string(),
+            started_at: id.jiff_timestamp(),
+            required_groups: required_groups.to_vec(),
+            credentials_set_at: None,
+            groups_added_at: None,
+            first_login_at: None,
+        })
+    }
+
+    /// List onboardings with steps left to do, oldest first.
+    pub async fn list_incomplete() -> Result<Vec<Onboarding>> {
+        let rows = sqlx::query_as!(
+            OnboardingRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                required_groups,
+                credentials_set_at as "credentials_set_at: _",
+                groups_added_at as "groups_added_at: _",
+                first_login_at as "first_login_at: _"
+            FROM onboardings
+            WHERE credentials_set_at IS NULL
+                OR groups_added_at IS NULL
+                OR first_login_at IS NULL
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(OnboardingRow::into_onboarding)
+            .collect()
+    }
+
+    /// List a user's onboardings, oldest first.
+    pub async fn list_for_user(user_id: Uuid) -> Result<Vec<Onboarding>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            OnboardingRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                required_groups,
+                credentials_set_at as "credentials_set_at: _",
+                groups_added_at as "groups_added_at: _",
+                first_login_at as "first_login_at: _"
+            FROM onboardings
+            WHERE user_id = ?
+            ORDER BY id
+            "#,
+            user_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(OnboardingRow::into_onboarding)
+            .collect()
+    }
+
+    /// Save which steps are done.
+    pub async fn update(onboarding: &Onboarding) -> Result<()> {
+        let id = onboarding.id.as_bytes().as_slice();
+        let credentials_set_at = onboarding.credentials_set_at.map(|t| t.to_sqlx());
+        let groups_added_at = onboarding.groups_added_at.map(|t| t.to_sqlx());
+        let first_login_at = onboarding.first_login_at.map(|t| t.to_sqlx());
+
+        sqlx::query!(
+            r#"
+            UPDATE onboardings
+            SET credentials_set_at = ?, groups_added_at = ?, first_login_at = ?
+            WHERE id = ?
+            "#,
+            credentials_set_at,
+            groups_added_at,
+            first_login_at,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM onboardings
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stop tracking a user, e.g. because they've been deleted.
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM onboardings
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Why an invite email was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxKind {
+    /// The first email, with the link.
+    Invite,
+    /// Sent automatically as the link is about to expire unused.
+    Reminder,
+    /// Sent again by an admin.
+    Resend,
+}
+
+impl OutboxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Invite => "invite",
+            Self::Reminder => "reminder",
+            Self::Resend => "resend",
+        }
+    }
+}
+
+/// Emails to the people provision links are for, with whether each was
+/// delivered.
+pub struct Outbox;
+
+impl Outbox {
+    /// Queue an email, returning its id to mark it sent or failed.
+    pub async fn enqueue(link_id: Uuid, recipient: &str, kind: OutboxKind) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let link_id = link_id.as_bytes().as_slice();
+        let kind = kind.as_str();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO email_outbox (id, link_id, recipient, kind)
+            VALUES (?, ?, ?, ?)
+            "#,
+            id,
+            link_id,
+            recipient,
+            kind,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn mark_sent(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE email_outbox
+            SET sent_at = ?, error = NULL
+            WHERE id = ?
+            "#,
+            now,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_failed(id: Uuid, error: &str) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            UPDATE email_outbox
+            SET error = ?
+            WHERE id = ?
+            "#,
+            error,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Whether an email of this kind was delivered for the link.
+    pub async fn was_sent(link_id: Uuid, kind: OutboxKind) -> Result<bool> {
+        let link_id = link_id.as_bytes().as_slice();
+        let kind = kind.as_str();
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM email_outbox
+            WHERE link_id = ? AND kind = ? AND sent_at IS NOT NULL
+            "#,
+            link_id,
+            kind,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// When an email was last delivered for the link, of any kind.
+    pub async fn last_sent(link_id: Uuid) -> Result<Option<Timestamp>> {
+        let link_id = link_id.as_bytes().as_slice();
+
+        let sent_at = sqlx::query_scalar!(
+            r#"
+            SELECT sent_at as "sent_at!: SqlxTimestamp"
+            FROM email_outbox
+            WHERE link_id = ? AND sent_at IS NOT NULL
+            ORDER BY sent_at DESC
+            LIMIT 1
+            "#,
+            link_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(sent_at.map(|t| t.to_jiff()))
+    }
+
+    /// Replace a recipient's address with a pseudonym.
+    pub async fn anonymize_recipient(recipient: &str, alias: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE email_outbox
+            SET recipient = ?
+            WHERE recipient = ?
+            "#,
+            alias,
+            recipient,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::Result;
+
+use crate::storage::POOL;
+
+/// How long someone has to finish logging in with Kanidm.
+const TTL: Duration = Duration::from_secs(600);
+
+struct PendingLoginRow {
+    pkce_verifier: String,
+    expires_at: SqlxTimestamp,
+    remember: bool,
+    reauth: bool,
+    return_to: Option<String>,
+}
+
+/// A login that has been redirected to Kanidm but hasn't come back yet.
+#[derive(Debug)]
+pub struct PendingLogin {
+    /// When the user was sent to Kanidm. A re-authentication only counts if
+    /// Kanidm says they entered their credentials after this.
+    pub started_at: Timestamp,
+    pub pkce_verifier: String,
+    pub remember: bool,
+    pub reauth: bool,
+    pub return_to: Option<String>,
+}
+
+impl PendingLogin {
+    /// Store this login under the OAuth state parameter Kanidm will send back.
+    pub async fn insert(&self, state: &str) -> Result<()> {
+        let expires_at = (self.started_at + TTL).to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO pending_logins (state, pkce_verifier, expires_at, remember, reauth, return_to)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            state,
+            self.pkce_verifier,
+            expires_at,
+            self.remember,
+            self.reauth,
+            self.return_to,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Find and remove the login for an OAuth state parameter, so it can only
+    /// be used once. Expired logins are treated as missing.
+    pub async fn take(state: &str) -> Result<Option<Self>> {
+        let row = sqlx::query_as!(
+            PendingLoginRow,
+            r#"
+            DELETE FROM pending_logins
+            WHERE state = ?
+            RETURNING
+                pkce_verifier,
+                expires_at as "expires_at: _",
+                remember,
+                reauth,
+                return_to
+            "#,
+            state,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(row
+            .filter(|r| Timestamp::now() < r.expires_at.to_jiff())
+            .map(|r| Self {
+                started_at: r.expires_at.to_jiff() - TTL,
+                pkce_verifier: r.pkce_verifier,
+                remember: r.remember,
+                reauth: r.reauth,
+                return_to: r.return_to,
+            }))
+    }
+
+    pub async fn delete_expired() -> Result<()> {
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM pending_logins
+            WHERE expires_at <= ?
+            "#,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Users' phone numbers, in E.164 form. Kanidm has no attribute for these.
+pub struct PhoneNumbers;
+
+impl PhoneNumbers {
+    pub async fn get(user_id: Uuid) -> Result<Option<String>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let phone_number = sqlx::query_scalar!(
+            r#"
+            SELECT phone_number
+            FROM phone_numbers
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        Ok(phone_number)
+    }
+
+    pub async fn set(user_id: Uuid, phone_number: &str) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO phone_numbers (user_id, phone_number)
+            VALUES (?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET phone_number = excluded.phone_number
+            "#,
+            user_id,
+            phone_number,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM phone_numbers
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
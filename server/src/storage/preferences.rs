@@ -0,0 +1,74 @@
+use types::{
+    Result,
+    preferences::{UserPreferences, UserShortcuts},
+};
+
+use crate::storage::POOL;
+
+/// Storage for each admin's [`UserPreferences`].
+pub struct Preferences;
+
+impl Preferences {
+    /// Load an admin's preferences, falling back to the defaults if they
+    /// haven't saved any.
+    pub async fn load(user_id: &str) -> Result<UserPreferences> {
+        let preferences = sqlx::query_scalar!(
+            r#"
+            SELECT preferences
+            FROM user_preferences
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        match preferences {
+            Some(p) => Ok(serde_json::from_str(&p)?),
+            None => Ok(UserPreferences::default()),
+        }
+    }
+
+    pub async fn save(user_id: &str, preferences: &UserPreferences) -> Result<()> {
+        let preferences = serde_json::to_string(preferences)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_preferences (user_id, preferences)
+            VALUES (?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET preferences = excluded.preferences
+            "#,
+            user_id,
+            preferences,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Change an admin's sidebar shortcuts, returning them.
+    pub async fn update_shortcuts(
+        user_id: &str,
+        update: impl FnOnce(&mut UserShortcuts),
+    ) -> Result<UserShortcuts> {
+        let mut preferences = Self::load(user_id).await?;
+        update(&mut preferences.shortcuts);
+        Self::save(user_id, &preferences).await?;
+        Ok(preferences.shortcuts)
+    }
+
+    pub async fn delete(user_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM user_preferences
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result, err,
+    profile_change::{ProfileChange, ProfileChangeStatus, ProfileField},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct ProfileChangeRow {
+    id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    field: String,
+    old_value: String,
+    new_value: String,
+    status: String,
+    reason: Option<String>,
+    decided_by: Option<String>,
+    decided_at: Option<SqlxTimestamp>,
+}
+
+impl ProfileChangeRow {
+    fn into_change(self) -> Result<ProfileChange> {
+        Ok(ProfileChange {
+            id: self.id,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            field: self.field.parse()?,
+            old_value: self.old_value,
+            new_value: self.new_value,
+            created_at: self.id.jiff_timestamp(),
+            status: self.status.parse()?,
+            decided_by: self.decided_by,
+            decided_at: self.decided_at.map(|t| t.to_jiff()),
+            reason: self.reason,
+        })
+    }
+}
+
+/// Storage for [`ProfileChange`]s.
+pub struct ProfileChanges;
+
+impl ProfileChanges {
+    pub async fn create(
+        user_id: Uuid,
+        user_name: &str,
+        field: ProfileField,
+        old_value: &str,
+        new_value: &str,
+    ) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let field = field.as_str();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO profile_changes (id, user_id, user_name, field, old_value, new_value)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            user_id,
+            user_name,
+            field,
+            old_value,
+            new_value,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<ProfileChange> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            ProfileChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                field,
+                old_value,
+                new_value,
+                status,
+                reason,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM profile_changes
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_change()
+    }
+
+    /// List all changes, newest first.
+    pub async fn list() -> Result<Vec<ProfileChange>> {
+        let rows = sqlx::query_as!(
+            ProfileChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                field,
+                old_value,
+                new_value,
+                status,
+                reason,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM profile_changes
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(ProfileChangeRow::into_change)
+            .collect()
+    }
+
+    /// List a user's own changes, newest first.
+    pub async fn list_for_user(user_id: Uuid) -> Result<Vec<ProfileChange>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            ProfileChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                field,
+                old_value,
+                new_value,
+                status,
+                reason,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM profile_changes
+            WHERE user_id = ?
+            ORDER BY id DESC
+            "#,
+            user_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(ProfileChangeRow::into_change)
+            .collect()
+    }
+
+    /// Whether the user already has an undecided change to `field`.
+    pub async fn has_pending(user_id: Uuid, field: ProfileField) -> Result<bool> {
+        let user_id = user_id.as_bytes().as_slice();
+        let field = field.as_str();
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM profile_changes
+            WHERE user_id = ? AND field = ? AND status = 'pending'
+            "#,
+            user_id,
+            field,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Approve or deny a pending change. Fails if it has already been
+    /// decided.
+    pub async fn decide(
+        id: Uuid,
+        status: ProfileChangeStatus,
+        reason: Option<&str>,
+        decided_by: &str,
+    ) -> Result<()> {
+        let id_bytes = id.as_bytes().as_slice();
+        let status = status.as_str();
+        let decided_at = Timestamp::now().to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE profile_changes
+            SET status = ?, reason = ?, decided_by = ?, decided_at = ?
+            WHERE id = ? AND status = 'pending'
+            "#,
+            status,
+            reason,
+            decided_by,
+            decided_at,
+            id_bytes,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(err!("profile change has already been decided"));
+        }
+
+        Ok(())
+    }
+
+    /// Put a change back to pending, when applying it failed after it was
+    /// approved.
+    pub async fn reopen(id: Uuid) -> Result<()> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            UPDATE profile_changes
+            SET status = 'pending', reason = NULL, decided_by = NULL, decided_at = NULL
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a user's changes, when they're forgotten.
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM profile_changes
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace an admin's name on the changes they decided.
+    pub async fn rename_decider(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE profile_changes
+            SET decided_by = ?
+            WHERE decided_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result, err,
+    provision::{ProvisionApproval, ProvisionApprovalStatus},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct ProvisionApprovalRow {
+    id: Uuid,
+    link_id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    display_name: String,
+    email: String,
+    groups: String,
+    link_created_by: Option<String>,
+    status: String,
+    decided_by: Option<String>,
+    decided_at: Option<SqlxTimestamp>,
+}
+
+impl ProvisionApprovalRow {
+    fn into_approval(self) -> Result<ProvisionApproval> {
+        Ok(ProvisionApproval {
+            id: self.id,
+            link_id: self.link_id,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            display_name: self.display_name,
+            email: self.email,
+            groups: serde_json::from_str(&self.groups)?,
+            link_created_by: self.link_created_by,
+            created_at: self.id.jiff_timestamp(),
+            status: self.status.parse()?,
+            decided_by: self.decided_by,
+            decided_at: self.decided_at.map(|t| t.to_jiff()),
+        })
+    }
+}
+
+/// Storage for [`ProvisionApproval`]s.
+pub struct ProvisionApprovals;
+
+impl ProvisionApprovals {
+    pub async fn create(
+        link_id: Uuid,
+        user_id: Uuid,
+        user_name: &str,
+        display_name: &str,
+        email: &str,
+        groups: &[String],
+        link_created_by: Option<&str>,
+    ) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let link_id = link_id.as_bytes().as_slice();
+        let user_id = user_id.as_bytes().as_slice();
+        let groups = serde_json::to_string(groups)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO provision_approvals (
+                id, link_id, user_id, user_name, display_name, email, groups, link_created_by
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            link_id,
+            user_id,
+            user_name,
+            display_name,
+            email,
+            groups,
+            link_created_by,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<ProvisionApproval> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            ProvisionApprovalRow,
+            r#"
+            SELECT
+                id as "id: _",
+                link_id as "link_id: _",
+                user_id as "user_id: _",
+                user_name,
+                display_name,
+                email,
+                groups,
+                link_created_by,
+                status,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM provision_approvals
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_approval()
+    }
+
+    /// List all approvals, newest first.
+    pub async fn list() -> Result<Vec<ProvisionApproval>> {
+        let rows = sqlx::query_as!(
+            ProvisionApprovalRow,
+            r#"
+            SELECT
+                id as "id: _",
+                link_id as "link_id: _",
+                user_id as "user_id: _",
+                user_name,
+                display_name,
+                email,
+                groups,
+                link_created_by,
+                status,
+                decided_by,
+                decided_at as "decided_at: _"
+            FROM provision_approvals
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(ProvisionApprovalRow::into_approval)
+            .collect()
+    }
+
+    /// Approve or reject a pending account. Fails if it has already been
+    /// decided.
+    pub async fn decide(id: Uuid, status: ProvisionApprovalStatus, decided_by: &str) -> Result<()> {
+        let id_bytes = id.as_bytes().as_slice();
+        let status = status.as_str();
+        let decided_at = Timestamp::now().to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE provision_approvals
+            SET status = ?, decided_by = ?, decided_at = ?
+            WHERE id = ? AND status = 'pending'
+            "#,
+            status,
+            decided_by,
+            decided_at,
+            id_bytes,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(err!("this account has already been approved or rejected"));
+        }
+
+        Ok(())
+    }
+
+    /// Put an approval back to pending, when carrying out the decision
+    /// failed.
+    pub async fn reopen(id: Uuid) -> Result<()> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            UPDATE provision_approvals
+            SET status = 'pending', decided_by = NULL, decided_at = NULL
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace an admin's name on the links they made and the approvals
+    /// they decided.
+    pub async fn rename_admin(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE provision_approvals
+            SET decided_by = CASE WHEN decided_by = ? THEN ? ELSE decided_by END,
+                link_created_by = CASE WHEN link_created_by = ? THEN ? ELSE link_created_by END
+            WHERE decided_by = ? OR link_created_by = ?
+            "#,
+            name,
+            new_name,
+            name,
+            new_name,
+            name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete the approval for an account, when it's forgotten.
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM provision_approvals
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -2,10 +2,21 @@ use std::time::Duration;
 
 use jiff::Timestamp;
 use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
-use types::{Result, err, provision::ProvisionToken};
+use sqlx::SqlitePool;
+use types::{
+    Result,
+    provision::{
+        EmailRestriction, ProvisionFields, ProvisionFollowUp, ProvisionLinkSummary,
+        ProvisionLinkUnusable, ProvisionToken,
+    },
+};
 use uuid::Uuid;
 
-use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+use crate::{
+    signed_token::{Purpose, SignedToken, decode_id},
+    storage::{POOL, ProvisionLinkClaim},
+    uuid_v7::UuidV7Ext,
+};
 
 struct ProvisionLinkRow {
     id: Uuid,
@@ -13,6 +24,12 @@ struct ProvisionLinkRow {
     max_uses: Option<i32>,
     use_count: i32,
     groups: String,
+    fields: String,
+    remind_to: Option<String>,
+    email_restriction: String,
+    created_by: Option<String>,
+    require_approval: bool,
+    follow_up: String,
 }
 
 #[derive(Debug)]
@@ -22,10 +39,31 @@ pub struct ProvisionLink {
     max_uses: Option<i32>,
     use_count: i32,
     groups: Vec<String>,
+    fields: ProvisionFields,
+    /// The admin to remind before the link lapses unused, or once it's used
+    /// up.
+    remind_to: Option<String>,
+    email_restriction: EmailRestriction,
+    /// The admin who made the link, if it was made after we started keeping
+    /// track.
+    created_by: Option<String>,
+    /// Whether accounts it makes wait, locked, for an admin to approve them.
+    require_approval: bool,
+    follow_up: ProvisionFollowUp,
 }
 
 impl ProvisionLink {
-    pub fn new(duration: Duration, max_uses: Option<u8>, groups: Vec<String>) -> Self {
+    pub fn new(
+        duration: Duration,
+        max_uses: Option<u8>,
+        groups: Vec<String>,
+        fields: ProvisionFields,
+        remind_to: Option<String>,
+        email_restriction: EmailRestriction,
+        created_by: &str,
+        require_approval: bool,
+        follow_up: ProvisionFollowUp,
+    ) -> Self {
         let id = Uuid::now_v7();
 
         Self {
@@ -34,6 +72,12 @@ impl ProvisionLink {
             max_uses: max_uses.map(Into::into),
             use_count: 0,
             groups,
+            fields,
+            remind_to,
+            email_restriction,
+            created_by: Some(created_by.to_string()),
+            require_approval,
+            follow_up,
         }
     }
 
@@ -41,8 +85,24 @@ impl ProvisionLink {
         duration: Duration,
         max_uses: Option<u8>,
         groups: Vec<String>,
+        fields: ProvisionFields,
+        remind_to: Option<String>,
+        email_restriction: EmailRestriction,
+        created_by: &str,
+        require_approval: bool,
+        follow_up: ProvisionFollowUp,
     ) -> Result<Self> {
-        let this = Self::new(duration, max_uses, groups);
+        let this = Self::new(
+            duration,
+            max_uses,
+            groups,
+            fields,
+            remind_to,
+            email_restriction,
+            created_by,
+            require_approval,
+            follow_up,
+        );
         this.insert().await?;
         Ok(this)
     }
@@ -58,7 +118,13 @@ impl ProvisionLink {
                 expires_at as "expires_at: _",
                 max_uses as "max_uses: _",
                 use_count as "use_count: _",
-                groups
+                groups,
+                fields,
+                remind_to,
+                email_restriction,
+                created_by,
+                require_approval as "require_approval: bool",
+                follow_up
             FROM provision_links
             WHERE id = ?
             "#,
@@ -67,51 +133,46 @@ impl ProvisionLink {
         .fetch_one(&*POOL)
         .await?;
 
+        Self::from_row(row)
+    }
+
+    fn from_row(row: ProvisionLinkRow) -> Result<Self> {
         Ok(Self {
             id: row.id,
             expires_at: row.expires_at.to_jiff(),
             max_uses: row.max_uses,
             use_count: row.use_count,
             groups: serde_json::from_str(&row.groups)?,
+            fields: serde_json::from_str(&row.fields)?,
+            remind_to: row.remind_to,
+            email_restriction: serde_json::from_str(&row.email_restriction)?,
+            created_by: row.created_by,
+            require_approval: row.require_approval,
+            follow_up: serde_json::from_str(&row.follow_up)?,
         })
     }
 
     pub async fn find_token(token: String) -> Result<Self> {
-        let uuid = Uuid::from_token(&token)?;
-        Self::find(uuid).await
+        Self::find(decode_id(&token, Purpose::ProvisionLink)?).await
     }
 
-    pub async fn consume(token: String) -> Result<Self> {
-        let record = Self::find_token(token).await?;
-        record.verify()?;
-        record.try_increment().await?;
-        Ok(record)
-    }
-
-    pub async fn decrement(&self) -> Result<()> {
-        let id = self.id.as_bytes().as_slice();
-
-        sqlx::query!(
-            r#"
-            UPDATE provision_links
-            SET use_count = use_count - 1
-            WHERE id = ? AND use_count > 0
-            "#,
-            id,
-        )
-        .execute(&*POOL)
-        .await?;
-
-        Ok(())
+    /// Take one of the link's uses for an account called `name`. Until the
+    /// account is made the use is only pending: keep it with
+    /// [`ProvisionLinkClaim::keep`], or give it back with
+    /// [`ProvisionLinkClaim::release`].
+    pub async fn consume(token: String, name: &str) -> Result<(Self, ProvisionLinkClaim)> {
+        let mut record = Self::find_token(token).await?;
+        let claim = record.take_use(&*POOL, name).await?;
+        Ok((record, claim))
     }
 
     pub fn verify(&self) -> Result<()> {
         if self.is_expired() {
-            return Err(err!("provision link has expired"));
+            return Err(ProvisionLinkUnusable::Expired.into());
         }
 
         if self.is_exhausted() {
-            return Err(err!("provision link has already been used"));
+            return Err(ProvisionLinkUnusable::UsedUp.into());
         }
 
         Ok(())
@@ -121,59 +182,208 @@ impl ProvisionLink {
         Timestamp::now() >= self.expires_at
     }
 
-    fn is_exhausted(&self) -> bool {
+    pub fn is_exhausted(&self) -> bool {
         self.max_uses.is_some_and(|max| self.use_count >= max)
     }
 
+    /// The link's token. It doesn't carry the expiry, which is checked
+    /// against the database so that it can be changed after the link is
+    /// sent. Links made before that still expire when they first would have.
     pub fn as_token(&self) -> Result<ProvisionToken> {
-        let signed_uuid = self.id.as_token()?;
+        let token = SignedToken::new(Purpose::ProvisionLink, self.id, None);
 
-        Ok(ProvisionToken::new(signed_uuid))
+        Ok(ProvisionToken::new(token.encode()?))
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn expires_at(&self) -> Timestamp {
+        self.expires_at
+    }
+
+    pub fn use_count(&self) -> i32 {
+        self.use_count
+    }
+
+    /// How many more accounts the link can make, if it's limited.
+    pub fn uses_remaining(&self) -> Option<u32> {
+        self.max_uses
+            .map(|max| (max - self.use_count).max(0) as u32)
+    }
+
+    pub fn remind_to(&self) -> Option<&str> {
+        self.remind_to.as_deref()
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
     }
 
     pub fn groups(&self) -> &[String] {
         &self.groups
     }
 
+    pub fn fields(&self) -> &ProvisionFields {
+        &self.fields
+    }
+
+    pub fn email_restriction(&self) -> &EmailRestriction {
+        &self.email_restriction
+    }
+
+    pub fn require_approval(&self) -> bool {
+        self.require_approval
+    }
+
+    pub fn follow_up(&self) -> &ProvisionFollowUp {
+        &self.follow_up
+    }
+
+    pub fn summary(&self) -> ProvisionLinkSummary {
+        ProvisionLinkSummary {
+            id: self.id,
+            created_at: self.id.jiff_timestamp(),
+            expires_at: self.expires_at,
+            max_uses: self.max_uses.and_then(|n| n.try_into().ok()),
+            use_count: self.use_count.max(0) as u32,
+            groups: self.groups.clone(),
+            fields: self.fields.clone(),
+            email_restriction: self.email_restriction.clone(),
+            last_emailed_at: None,
+            funnel: Default::default(),
+            require_approval: self.require_approval,
+            follow_up: self.follow_up.redacted(),
+        }
+    }
+
     pub async fn insert(&self) -> Result<()> {
+        self.insert_in(&*POOL).await
+    }
+
+    async fn insert_in(&self, pool: &SqlitePool) -> Result<()> {
         let expires_at = self.expires_at.to_sqlx();
         let groups = serde_json::to_string(&self.groups)?;
+        let fields = serde_json::to_string(&self.fields)?;
+        let email_restriction = serde_json::to_string(&self.email_restriction)?;
+        let follow_up = serde_json::to_string(&self.follow_up)?;
 
         sqlx::query!(
             r#"
-            INSERT INTO provision_links (id, expires_at, max_uses, use_count, groups)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO provision_links (
+                id, expires_at, max_uses, use_count, groups, fields, remind_to,
+                email_restriction, created_by, require_approval, follow_up
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             self.id,
             expires_at,
             self.max_uses,
             self.use_count,
             groups,
+            fields,
+            self.remind_to,
+            email_restriction,
+            self.created_by,
+            self.require_approval,
+            follow_up,
         )
-        .execute(&*POOL)
+        .execute(pool)
         .await?;
 
         Ok(())
     }
 
-    async fn try_increment(&self) -> Result<()> {
+    /// Count a use, if the link can still be used, and claim it for `name`.
+    /// It's checked and counted in one statement, so people using a link at
+    /// once can't take more uses than it has, or use it after it expired or
+    /// was changed to allow fewer, whatever this copy of it says. The same
+    /// statement says why, if it couldn't be used.
+    async fn take_use(&mut self, pool: &SqlitePool, name: &str) -> Result<ProvisionLinkClaim> {
         let id = self.id.as_bytes().as_slice();
+        let claim_id = Uuid::now_v7();
+        let claim = claim_id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
 
-        let result = sqlx::query!(
+        let mut tx = pool.begin().await?;
+        let row = sqlx::query!(
             r#"
             UPDATE provision_links
-            SET use_count = use_count + 1
-            WHERE id = ? AND (max_uses IS NULL OR use_count < max_uses)
+            SET
+                use_count = CASE
+                    WHEN expires_at > ?1 AND (max_uses IS NULL OR use_count < max_uses)
+                    THEN use_count + 1
+                    ELSE use_count
+                END,
+                last_claim = CASE
+                    WHEN expires_at > ?1 AND (max_uses IS NULL OR use_count < max_uses)
+                    THEN ?2
+                    ELSE last_claim
+                END
+            WHERE id = ?3
+            RETURNING
+                use_count as "use_count: i32",
+                last_claim IS ?2 as "taken!: bool",
+                expires_at <= ?1 as "expired!: bool"
             "#,
+            now,
+            claim,
             id,
         )
-        .execute(&*POOL)
+        .fetch_one(&mut *tx)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(err!("link already used up"));
+        if !row.taken {
+            return Err(if row.expired {
+                ProvisionLinkUnusable::Expired
+            } else {
+                ProvisionLinkUnusable::UsedUp
+            }
+            .into());
         }
 
+        sqlx::query!(
+            r#"
+            INSERT INTO provision_link_claims (id, link_id, name, claimed_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+            claim,
+            id,
+            name,
+            now,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.use_count = row.use_count;
+        Ok(ProvisionLinkClaim::new(claim_id))
+    }
+
+    /// Change when the link expires and how many times it can be used, so
+    /// it needn't be replaced. Its creator is reminded about it again, if
+    /// they asked to be.
+    pub async fn update(&mut self, expires_at: Timestamp, max_uses: Option<u8>) -> Result<()> {
+        let id = self.id.as_bytes().as_slice();
+        let sqlx_expires_at = expires_at.to_sqlx();
+        let max_uses: Option<i32> = max_uses.map(Into::into);
+
+        sqlx::query!(
+            r#"
+            UPDATE provision_links
+            SET expires_at = ?, max_uses = ?, reminded_at = NULL
+            WHERE id = ?
+            "#,
+            sqlx_expires_at,
+            max_uses,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        self.expires_at = expires_at;
+        self.max_uses = max_uses;
         Ok(())
     }
 
@@ -192,4 +402,266 @@ impl ProvisionLink {
 
         Ok(())
     }
+
+    /// The most recently created links, expired or not, newest first.
+    pub async fn list_recent(limit: u32) -> Result<Vec<Self>> {
+        let rows = sqlx::query_as!(
+            ProvisionLinkRow,
+            r#"
+            SELECT
+                id as "id: _",
+                expires_at as "expires_at: _",
+                max_uses as "max_uses: _",
+                use_count as "use_count: _",
+                groups,
+                fields,
+                remind_to,
+                email_restriction,
+                created_by,
+                require_approval as "require_approval: bool",
+                follow_up
+            FROM provision_links
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(Self::from_row).collect()
+    }
+
+    /// Links that haven't expired yet, used up or not.
+    pub async fn list_unexpired() -> Result<Vec<Self>> {
+        let now = Timestamp::now().to_sqlx();
+
+        let rows = sqlx::query_as!(
+            ProvisionLinkRow,
+            r#"
+            SELECT
+                id as "id: _",
+                expires_at as "expires_at: _",
+                max_uses as "max_uses: _",
+                use_count as "use_count: _",
+                groups,
+                fields,
+                remind_to,
+                email_restriction,
+                created_by,
+                require_approval as "require_approval: bool",
+                follow_up
+            FROM provision_links
+            WHERE expires_at > ?
+            "#,
+            now,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(Self::from_row).collect()
+    }
+
+    /// Links whose creator hasn't been reminded yet, and that are either
+    /// used up or unused and expiring before `soon`.
+    pub async fn due_reminders(soon: Timestamp) -> Result<Vec<Self>> {
+        let now = Timestamp::now().to_sqlx();
+        let soon = soon.to_sqlx();
+
+        let rows = sqlx::query_as!(
+            ProvisionLinkRow,
+            r#"
+            SELECT
+                id as "id: _",
+                expires_at as "expires_at: _",
+                max_uses as "max_uses: _",
+                use_count as "use_count: _",
+                groups,
+                fields,
+                remind_to,
+                email_restriction,
+                created_by,
+                require_approval as "require_approval: bool",
+                follow_up
+            FROM provision_links
+            WHERE remind_to IS NOT NULL
+                AND reminded_at IS NULL
+                AND (
+                    (max_uses IS NOT NULL AND use_count >= max_uses)
+                    OR (use_count = 0 AND expires_at > ? AND expires_at <= ?)
+                )
+            "#,
+            now,
+            soon,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter().map(Self::from_row).collect()
+    }
+
+    pub async fn mark_reminded(&self) -> Result<()> {
+        let id = self.id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE provision_links
+            SET reminded_at = ?
+            WHERE id = ?
+            "#,
+            now,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete links that expired before `cutoff`, returning how many.
+    pub async fn delete_expired_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM provision_links
+            WHERE expires_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn link(max_uses: Option<u8>) -> ProvisionLink {
+        ProvisionLink::new(
+            Duration::from_secs(3600),
+            max_uses,
+            Vec::new(),
+            ProvisionFields::default(),
+            None,
+            EmailRestriction::default(),
+            "admin",
+            false,
+            ProvisionFollowUp::default(),
+        )
+    }
+
+    /// Another copy of `original`, as a separate request would read it.
+    fn copy(original: &ProvisionLink) -> ProvisionLink {
+        ProvisionLink {
+            id: original.id,
+            ..link(original.max_uses.map(|max| max as u8))
+        }
+    }
+
+    async fn use_count(pool: &SqlitePool, link: &ProvisionLink) -> i64 {
+        sqlx::query_scalar("SELECT use_count FROM provision_links WHERE id = ?")
+            .bind(link.id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn claim_count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM provision_link_claims")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn unusable(error: &types::Error) -> Option<ProvisionLinkUnusable> {
+        error.downcast_ref::<ProvisionLinkUnusable>().copied()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn only_one_of_many_racing_requests_takes_the_last_use(pool: SqlitePool) {
+        let link = link(Some(1));
+        link.insert_in(&pool).await.unwrap();
+
+        let barrier = Arc::new(tokio::sync::Barrier::new(10));
+        let tasks: Vec<_> = (0..10)
+            .map(|i| {
+                let (pool, barrier, mut link) = (pool.clone(), barrier.clone(), copy(&link));
+                tokio::spawn(async move {
+                    barrier.wait().await;
+                    link.take_use(&pool, &format!("user{i}")).await
+                })
+            })
+            .collect();
+
+        let mut taken = 0;
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(_claim) => taken += 1,
+                Err(error) => assert_eq!(unusable(&error), Some(ProvisionLinkUnusable::UsedUp)),
+            }
+        }
+        assert_eq!(taken, 1);
+        assert_eq!(use_count(&pool, &link).await, 1);
+        assert_eq!(claim_count(&pool).await, 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn an_expired_link_cant_be_used(pool: SqlitePool) {
+        let mut link = link(None);
+        link.expires_at = Timestamp::now() - Duration::from_secs(1);
+        link.insert_in(&pool).await.unwrap();
+
+        let error = link.take_use(&pool, "user").await.unwrap_err();
+        assert_eq!(unusable(&error), Some(ProvisionLinkUnusable::Expired));
+        assert_eq!(use_count(&pool, &link).await, 0);
+        assert_eq!(claim_count(&pool).await, 0);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn a_link_changed_to_allow_fewer_uses_cant_be_used(pool: SqlitePool) {
+        let mut link = link(Some(2));
+        link.insert_in(&pool).await.unwrap();
+        let mut stale = copy(&link);
+        link.take_use(&pool, "first")
+            .await
+            .unwrap()
+            .keep_in(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE provision_links SET max_uses = 1 WHERE id = ?")
+            .bind(link.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let error = stale.take_use(&pool, "second").await.unwrap_err();
+        assert_eq!(unusable(&error), Some(ProvisionLinkUnusable::UsedUp));
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn a_released_use_can_be_taken_again(pool: SqlitePool) {
+        let mut link = link(Some(1));
+        link.insert_in(&pool).await.unwrap();
+
+        let claim = link.take_use(&pool, "first").await.unwrap();
+        assert!(copy(&link).take_use(&pool, "second").await.is_err());
+        claim.release_in(&pool).await.unwrap();
+        assert_eq!(use_count(&pool, &link).await, 0);
+
+        link.take_use(&pool, "second")
+            .await
+            .unwrap()
+            .keep_in(&pool)
+            .await
+            .unwrap();
+        assert_eq!(use_count(&pool, &link).await, 1);
+        assert_eq!(claim_count(&pool).await, 0);
+    }
 }
@@ -0,0 +1,110 @@
+use jiff::Timestamp;
+use jiff_sqlx::ToSqlx;
+use sqlx::SqlitePool;
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// A use taken from a provision link for an account that's still being made.
+/// It counts against the link until it's kept or released.
+#[must_use = "a claim holds one of the link's uses until it's kept or released"]
+#[derive(Debug)]
+pub struct ProvisionLinkClaim {
+    id: Uuid,
+}
+
+impl ProvisionLinkClaim {
+    pub(super) fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    /// The account was made, so the use stays taken.
+    pub async fn keep(self) -> Result<()> {
+        self.keep_in(&*POOL).await
+    }
+
+    /// The account wasn't made, so give the use back.
+    pub async fn release(self) -> Result<()> {
+        self.release_in(&*POOL).await
+    }
+
+    pub(super) async fn keep_in(self, pool: &SqlitePool) -> Result<()> {
+        let id = self.id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM provision_link_claims
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The use is only given back if the claim was still pending, so a claim
+    /// settled twice can't give back someone else's use.
+    pub(super) async fn release_in(self, pool: &SqlitePool) -> Result<()> {
+        let id = self.id.as_bytes().as_slice();
+        let mut tx = pool.begin().await?;
+
+        let link_id = sqlx::query_scalar!(
+            r#"
+            DELETE FROM provision_link_claims
+            WHERE id = ?
+            RETURNING link_id as "link_id: Uuid"
+            "#,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(link_id) = link_id {
+            let link_id = link_id.as_bytes().as_slice();
+            sqlx::query!(
+                r#"
+                UPDATE provision_links
+                SET use_count = use_count - 1
+                WHERE id = ? AND use_count > 0
+                "#,
+                link_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Pending uses of provision links.
+pub struct ProvisionLinkClaims;
+
+impl ProvisionLinkClaims {
+    /// Claims made before `cutoff` and never settled, with the name of the
+    /// account each was for. They're left when AuthIt! stops partway through
+    /// making an account.
+    pub async fn claimed_before(cutoff: Timestamp) -> Result<Vec<(ProvisionLinkClaim, String)>> {
+        let cutoff = cutoff.to_sqlx();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id as "id: Uuid", name
+            FROM provision_link_claims
+            WHERE claimed_at < ?
+            "#,
+            cutoff,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (ProvisionLinkClaim::new(row.id), row.name))
+            .collect())
+    }
+}
@@ -0,0 +1,184 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result, err,
+    provision::{ProvisionFailure, ProvisionFailureCause, ProvisionFailureCount, ProvisionFunnel},
+};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// How many failures a link's funnel lists. The rest are only counted.
+const FAILURE_LIMIT: usize = 10;
+
+/// Something that happened with a provision link.
+#[derive(Debug, Clone)]
+pub enum ProvisionLinkEvent {
+    /// Its page was opened.
+    Opened,
+    /// It made an account.
+    Completed,
+    /// It couldn't be used, for the given reason.
+    Failed {
+        cause: ProvisionFailureCause,
+        reason: String,
+    },
+}
+
+impl ProvisionLinkEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Opened => "opened",
+            Self::Completed => "completed",
+            Self::Failed { .. } => "failed",
+        }
+    }
+}
+
+struct ProvisionLinkEventRow {
+    at: SqlxTimestamp,
+    kind: String,
+    cause: Option<String>,
+    reason: Option<String>,
+}
+
+/// What happened with each provision link, for its funnel.
+pub struct ProvisionLinkEvents;
+
+impl ProvisionLinkEvents {
+    pub async fn record(link_id: Uuid, event: &ProvisionLinkEvent) -> Result<()> {
+        let id = Uuid::now_v7();
+        let link_id = link_id.as_bytes().as_slice();
+        let at = Timestamp::now().to_sqlx();
+        let kind = event.kind();
+        let (cause, reason) = match event {
+            ProvisionLinkEvent::Failed { cause, reason } => {
+                (Some(cause.as_str()), Some(reason.as_str()))
+            }
+            _ => (None, None),
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO provision_link_events (id, link_id, at, kind, cause, reason)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            link_id,
+            at,
+            kind,
+            cause,
+            reason,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// How far people have got with a link.
+    pub async fn funnel(link_id: Uuid) -> Result<ProvisionFunnel> {
+        let link_id = link_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            ProvisionLinkEventRow,
+            r#"
+            SELECT
+                at as "at: _",
+                kind,
+                cause,
+                reason
+            FROM provision_link_events
+            WHERE link_id = ?
+            ORDER BY at DESC
+            "#,
+            link_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        let mut funnel = ProvisionFunnel::default();
+        for row in rows {
+            let at = row.at.to_jiff();
+            match row.kind.as_str() {
+                "opened" => {
+                    funnel.opens += 1;
+                    funnel.last_opened_at.get_or_insert(at);
+                }
+                "completed" => funnel.completions += 1,
+                "failed" => {
+                    let cause = parse_cause(row.cause.as_deref());
+                    funnel.failure_count += 1;
+                    count_cause(&mut funnel.failure_causes, cause, 1);
+                    if funnel.failures.len() < FAILURE_LIMIT {
+                        funnel.failures.push(ProvisionFailure {
+                            at,
+                            cause,
+                            reason: row.reason.unwrap_or_default(),
+                        });
+                    }
+                }
+                kind => return Err(err!("unknown provision link event '{kind}'")),
+            }
+        }
+        sort_causes(&mut funnel.failure_causes);
+        Ok(funnel)
+    }
+
+    /// How many times any provision link failed since `since`, by cause,
+    /// most common first.
+    pub async fn failure_causes_since(since: Timestamp) -> Result<Vec<ProvisionFailureCount>> {
+        let since = since.to_sqlx();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT cause, COUNT(*) as "count!: i64"
+            FROM provision_link_events
+            WHERE kind = 'failed' AND at >= ?
+            GROUP BY cause
+            "#,
+            since,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        let mut counts = Vec::new();
+        for row in rows {
+            let count = u32::try_from(row.count).unwrap_or(u32::MAX);
+            count_cause(&mut counts, parse_cause(row.cause.as_deref()), count);
+        }
+        sort_causes(&mut counts);
+        Ok(counts)
+    }
+
+    /// Remove events whose link has been purged.
+    pub async fn delete_orphaned() -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM provision_link_events
+            WHERE link_id NOT IN (SELECT id FROM provision_links)
+            "#,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// A failure's cause, counting ones recorded before causes were, or by a
+/// newer version, as other.
+fn parse_cause(cause: Option<&str>) -> ProvisionFailureCause {
+    cause.and_then(|c| c.parse().ok()).unwrap_or_default()
+}
+
+fn count_cause(counts: &mut Vec<ProvisionFailureCount>, cause: ProvisionFailureCause, n: u32) {
+    match counts.iter_mut().find(|c| c.cause == cause) {
+        Some(existing) => existing.count += n,
+        None => counts.push(ProvisionFailureCount { cause, count: n }),
+    }
+}
+
+fn sort_causes(counts: &mut [ProvisionFailureCount]) {
+    counts.sort_by_key(|c| std::cmp::Reverse(c.count));
+}
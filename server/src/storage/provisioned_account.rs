@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use jiff_sqlx::ToSqlx;
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Quotas are per day, so older records don't matter.
+const QUOTA_WINDOW: Duration = Duration::from_secs(24 * 3600);
+
+/// Accounts created from provision links, counted towards the quota of the
+/// admin who made the link.
+pub struct ProvisionedAccounts;
+
+impl ProvisionedAccounts {
+    pub async fn record(created_by: &str) -> Result<()> {
+        let id = Uuid::now_v7();
+        let created_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO provisioned_accounts (id, created_by, created_at)
+            VALUES (?, ?, ?)
+            "#,
+            id,
+            created_by,
+            created_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// How many accounts have been created from `created_by`'s links within
+    /// [`QUOTA_WINDOW`].
+    pub async fn count_recent(created_by: &str) -> Result<u32> {
+        let since = (Timestamp::now() - QUOTA_WINDOW).to_sqlx();
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM provisioned_accounts
+            WHERE created_by = ? AND created_at > ?
+            "#,
+            created_by,
+            since,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count as u32)
+    }
+
+    pub async fn delete_expired() -> Result<()> {
+        let cutoff = (Timestamp::now() - QUOTA_WINDOW).to_sqlx();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM provisioned_accounts
+            WHERE created_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace an admin's name on the accounts created from their links.
+    pub async fn rename_creator(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE provisioned_accounts
+            SET created_by = ?
+            WHERE created_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{Result, report::ReportEventKind};
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct ReportEventRow {
+    at: SqlxTimestamp,
+    kind: String,
+    group_name: Option<String>,
+}
+
+/// One thing counted in a report.
+#[derive(Debug, Clone)]
+pub struct ReportEvent {
+    pub at: Timestamp,
+    pub kind: ReportEventKind,
+    pub group: Option<String>,
+}
+
+/// Things counted in period reports.
+pub struct ReportEvents;
+
+impl ReportEvents {
+    pub async fn record(kind: ReportEventKind, group: Option<&str>) -> Result<()> {
+        let id = Uuid::now_v7();
+        let at = Timestamp::now().to_sqlx();
+        let kind = kind.as_str();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO report_events (id, at, kind, group_name)
+            VALUES (?, ?, ?, ?)
+            "#,
+            id,
+            at,
+            kind,
+            group,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Everything from `from` up to `to`, oldest first.
+    pub async fn list_between(from: Timestamp, to: Timestamp) -> Result<Vec<ReportEvent>> {
+        let from = from.to_sqlx();
+        let to = to.to_sqlx();
+
+        let rows = sqlx::query_as!(
+            ReportEventRow,
+            r#"
+            SELECT
+                at as "at: _",
+                kind,
+                group_name
+            FROM report_events
+            WHERE at >= ? AND at < ?
+            ORDER BY at
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(ReportEvent {
+                    at: row.at.to_jiff(),
+                    kind: row.kind.parse()?,
+                    group: row.group_name,
+                })
+            })
+            .collect()
+    }
+}
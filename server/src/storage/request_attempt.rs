@@ -0,0 +1,171 @@
+use std::{net::IpAddr, time::Duration};
+
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use sqlx::SqlitePool;
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// How long attempts are kept, longer than any limit's window.
+const KEEP: Duration = Duration::from_secs(24 * 3600);
+
+/// Requests to public forms, by client address, for rate limiting. They're
+/// shared by every replica.
+pub struct RequestAttempts;
+
+impl RequestAttempts {
+    /// Record a `kind` of request from `ip`, unless there have already been
+    /// `max` in `window`. It's counted and recorded in one statement, so
+    /// requests made at once can't all get in under the limit.
+    ///
+    /// Returns `None` if it was recorded, or else when the oldest of those
+    /// counted leaves the window.
+    pub async fn start(
+        kind: &str,
+        ip: IpAddr,
+        max: usize,
+        window: Duration,
+    ) -> Result<Option<Timestamp>> {
+        Self::start_in(&POOL, kind, ip, max, window, Timestamp::now()).await
+    }
+
+    async fn start_in(
+        pool: &SqlitePool,
+        kind: &str,
+        ip: IpAddr,
+        max: usize,
+        window: Duration,
+        now: Timestamp,
+    ) -> Result<Option<Timestamp>> {
+        let id = Uuid::now_v7();
+        let client_ip = ip.to_canonical().to_string();
+        let cutoff = (now - window).to_sqlx();
+        let attempted_at = now.to_sqlx();
+        let max = max as i64;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO request_attempts (id, kind, client_ip, attempted_at)
+            SELECT ?, ?, ?, ?
+            WHERE (
+                SELECT COUNT(*)
+                FROM request_attempts
+                WHERE kind = ? AND client_ip = ? AND attempted_at > ?
+            ) < ?
+            "#,
+            id,
+            kind,
+            client_ip,
+            attempted_at,
+            kind,
+            client_ip,
+            cutoff,
+            max,
+        )
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 1 {
+            return Ok(None);
+        }
+
+        let oldest = sqlx::query_scalar!(
+            r#"
+            SELECT attempted_at as "attempted_at: SqlxTimestamp"
+            FROM request_attempts
+            WHERE kind = ? AND client_ip = ? AND attempted_at > ?
+            ORDER BY attempted_at
+            LIMIT 1
+            "#,
+            kind,
+            client_ip,
+            cutoff,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        // It may have left the window since it was counted.
+        Ok(Some(oldest.map_or(now, |at| at.to_jiff() + window)))
+    }
+
+    pub async fn delete_expired() -> Result<()> {
+        let cutoff = (Timestamp::now() - KEEP).to_sqlx();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM request_attempts
+            WHERE attempted_at <= ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn refuses_requests_over_the_limit(pool: SqlitePool) {
+        let now = Timestamp::now();
+        let start = |addr| RequestAttempts::start_in(&pool, "test", ip(addr), 2, WINDOW, now);
+        assert_eq!(start("198.51.100.1").await.unwrap(), None);
+        assert_eq!(start("198.51.100.1").await.unwrap(), None);
+        assert_eq!(start("198.51.100.1").await.unwrap(), Some(now + WINDOW));
+
+        // Other addresses have their own count.
+        assert_eq!(start("198.51.100.2").await.unwrap(), None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn allows_requests_again_once_the_window_passes(pool: SqlitePool) {
+        let now = Timestamp::now();
+        let start = |at| RequestAttempts::start_in(&pool, "test", ip("::1"), 1, WINDOW, at);
+        assert_eq!(start(now).await.unwrap(), None);
+        assert_eq!(
+            start(now + Duration::from_secs(45)).await.unwrap(),
+            Some(now + WINDOW)
+        );
+        assert_eq!(start(now + WINDOW).await.unwrap(), None);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn requests_at_once_cant_get_past_the_limit(pool: SqlitePool) {
+        let tasks: Vec<_> = (0..15)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    RequestAttempts::start_in(
+                        &pool,
+                        "test",
+                        ip("203.0.113.7"),
+                        5,
+                        WINDOW,
+                        Timestamp::now(),
+                    )
+                    .await
+                })
+            })
+            .collect();
+
+        let mut recorded = 0;
+        for task in tasks {
+            if task.await.unwrap().unwrap().is_none() {
+                recorded += 1;
+            }
+        }
+        assert_eq!(recorded, 5);
+    }
+}
@@ -0,0 +1,118 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct ResetWatchRow {
+    user_id: Uuid,
+    user_name: String,
+    admin: String,
+    fingerprint: String,
+    expires_at: SqlxTimestamp,
+}
+
+/// A credential reset link waiting to be used.
+#[derive(Debug, Clone)]
+pub struct ResetWatch {
+    pub user_id: Uuid,
+    pub user_name: String,
+    /// The admin who generated the link.
+    pub admin: String,
+    /// A digest of the user's credentials when the link was generated.
+    pub fingerprint: String,
+    pub expires_at: Timestamp,
+}
+
+/// Reset links whose admins want to know when they're used. Only the latest
+/// link for each user is watched.
+pub struct ResetWatches;
+
+impl ResetWatches {
+    pub async fn set(watch: &ResetWatch) -> Result<()> {
+        let user_id = watch.user_id.as_bytes().as_slice();
+        let expires_at = watch.expires_at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO reset_watches (user_id, user_name, admin, fingerprint, expires_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET
+                user_name = excluded.user_name,
+                admin = excluded.admin,
+                fingerprint = excluded.fingerprint,
+                expires_at = excluded.expires_at
+            "#,
+            user_id,
+            watch.user_name,
+            watch.admin,
+            watch.fingerprint,
+            expires_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list() -> Result<Vec<ResetWatch>> {
+        let rows = sqlx::query_as!(
+            ResetWatchRow,
+            r#"
+            SELECT
+                user_id as "user_id: _",
+                user_name,
+                admin,
+                fingerprint,
+                expires_at as "expires_at: _"
+            FROM reset_watches
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ResetWatch {
+                user_id: row.user_id,
+                user_name: row.user_name,
+                admin: row.admin,
+                fingerprint: row.fingerprint,
+                expires_at: row.expires_at.to_jiff(),
+            })
+            .collect())
+    }
+
+    pub async fn delete(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM reset_watches
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_expired() -> Result<()> {
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM reset_watches
+            WHERE expires_at <= ?
+            "#,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
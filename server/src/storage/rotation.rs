@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result,
+    rotation::{RotationCampaign, RotationMember},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct CampaignRow {
+    id: Uuid,
+    name: String,
+    started_by: String,
+    deadline: SqlxTimestamp,
+    lock_at_deadline: bool,
+    closed_at: Option<SqlxTimestamp>,
+}
+
+impl CampaignRow {
+    fn into_campaign(self, members: Vec<RotationMember>) -> RotationCampaign {
+        RotationCampaign {
+            id: self.id,
+            name: self.name,
+            started_by: self.started_by,
+            started_at: self.id.jiff_timestamp(),
+            deadline: self.deadline.to_jiff(),
+            lock_at_deadline: self.lock_at_deadline,
+            closed_at: self.closed_at.map(|t| t.to_jiff()),
+            members,
+        }
+    }
+}
+
+struct MemberRow {
+    campaign_id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    rotated_at: Option<SqlxTimestamp>,
+    reminded_at: Option<SqlxTimestamp>,
+    locked_at: Option<SqlxTimestamp>,
+}
+
+impl MemberRow {
+    fn into_member(self) -> RotationMember {
+        RotationMember {
+            user_id: self.user_id,
+            user_name: self.user_name,
+            rotated_at: self.rotated_at.map(|t| t.to_jiff()),
+            reminded_at: self.reminded_at.map(|t| t.to_jiff()),
+            locked_at: self.locked_at.map(|t| t.to_jiff()),
+        }
+    }
+}
+
+struct PendingRow {
+    user_id: Uuid,
+    user_name: String,
+    fingerprint: String,
+    reminded_at: Option<SqlxTimestamp>,
+}
+
+/// Someone to add to a new campaign.
+#[derive(Debug, Clone)]
+pub struct RotationTarget {
+    pub user_id: Uuid,
+    pub user_name: String,
+    /// A digest of their credentials now.
+    pub fingerprint: String,
+}
+
+/// A campaign member who hasn't rotated yet.
+#[derive(Debug, Clone)]
+pub struct PendingRotation {
+    pub user_id: Uuid,
+    pub user_name: String,
+    /// A digest of their credentials when the campaign started.
+    pub fingerprint: String,
+    pub reminded_at: Option<Timestamp>,
+}
+
+/// Storage for [`RotationCampaign`]s.
+pub struct RotationCampaigns;
+
+impl RotationCampaigns {
+    pub async fn create(
+        name: &str,
+        started_by: &str,
+        deadline: Timestamp,
+        lock_at_deadline: bool,
+        targets: &[RotationTarget],
+    ) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let deadline = deadline.to_sqlx();
+
+        let mut tx = POOL.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO rotation_campaigns (id, name, started_by, deadline, lock_at_deadline)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+            id,
+            name,
+            started_by,
+            deadline,
+            lock_at_deadline,
+        )
+        .execute(&mut *tx)
+        .await?;
+        for target in targets {
+            sqlx::query!(
+                r#"
+                INSERT INTO rotation_members (campaign_id, user_id, user_name, fingerprint)
+                VALUES (?, ?, ?, ?)
+                "#,
+                id,
+                target.user_id,
+                target.user_name,
+                target.fingerprint,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<RotationCampaign> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            CampaignRow,
+            r#"
+            SELECT
+                id as "id: _",
+                name,
+                started_by,
+                deadline as "deadline: _",
+                lock_at_deadline,
+                closed_at as "closed_at: _"
+            FROM rotation_campaigns
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+        let members = sqlx::query_as!(
+            MemberRow,
+            r#"
+            SELECT
+                campaign_id as "campaign_id: _",
+                user_id as "user_id: _",
+                user_name,
+                rotated_at as "rotated_at: _",
+                reminded_at as "reminded_at: _",
+                locked_at as "locked_at: _"
+            FROM rotation_members
+            WHERE campaign_id = ?
+            ORDER BY user_name
+            "#,
+            id_bytes,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(row.into_campaign(members.into_iter().map(MemberRow::into_member).collect()))
+    }
+
+    /// List all campaigns, newest first.
+    pub async fn list() -> Result<Vec<RotationCampaign>> {
+        let rows = sqlx::query_as!(
+            CampaignRow,
+            r#"
+            SELECT
+                id as "id: _",
+                name,
+                started_by,
+                deadline as "deadline: _",
+                lock_at_deadline,
+                closed_at as "closed_at: _"
+            FROM rotation_campaigns
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+        let member_rows = sqlx::query_as!(
+            MemberRow,
+            r#"
+            SELECT
+                campaign_id as "campaign_id: _",
+                user_id as "user_id: _",
+                user_name,
+                rotated_at as "rotated_at: _",
+                reminded_at as "reminded_at: _",
+                locked_at as "locked_at: _"
+            FROM rotation_members
+            ORDER BY user_name
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        let mut members: HashMap<Uuid, Vec<RotationMember>> = HashMap::new();
+        for row in member_rows {
+            members
+                .entry(row.campaign_id)
+                .or_default()
+                .push(row.into_member());
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let members = members.remove(&row.id).unwrap_or_default();
+                row.into_campaign(members)
+            })
+            .collect())
+    }
+
+    /// The ids of campaigns that haven't closed.
+    pub async fn list_open() -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id as "id: Uuid"
+            FROM rotation_campaigns
+            WHERE closed_at IS NULL
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Members of a campaign who haven't rotated yet.
+    pub async fn pending(id: Uuid) -> Result<Vec<PendingRotation>> {
+        let id = id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            PendingRow,
+            r#"
+            SELECT
+                user_id as "user_id: _",
+                user_name,
+                fingerprint,
+                reminded_at as "reminded_at: _"
+            FROM rotation_members
+            WHERE campaign_id = ? AND rotated_at IS NULL
+            "#,
+            id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PendingRotation {
+                user_id: row.user_id,
+                user_name: row.user_name,
+                fingerprint: row.fingerprint,
+                reminded_at: row.reminded_at.map(|t| t.to_jiff()),
+            })
+            .collect())
+    }
+
+    pub async fn mark_rotated(id: Uuid, user_id: Uuid, at: Timestamp) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let user_id = user_id.as_bytes().as_slice();
+        let at = at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE rotation_members
+            SET rotated_at = ?
+            WHERE campaign_id = ? AND user_id = ?
+            "#,
+            at,
+            id,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_reminded(id: Uuid, user_id: Uuid, at: Timestamp) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let user_id = user_id.as_bytes().as_slice();
+        let at = at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE rotation_members
+            SET reminded_at = ?
+            WHERE campaign_id = ? AND user_id = ?
+            "#,
+            at,
+            id,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_locked(id: Uuid, user_id: Uuid, at: Timestamp) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let user_id = user_id.as_bytes().as_slice();
+        let at = at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE rotation_members
+            SET locked_at = ?
+            WHERE campaign_id = ? AND user_id = ?
+            "#,
+            at,
+            id,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn close(id: Uuid, at: Timestamp) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let at = at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE rotation_campaigns
+            SET closed_at = ?
+            WHERE id = ?
+            "#,
+            at,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        let mut tx = POOL.begin().await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM rotation_members
+            WHERE campaign_id = ?
+            "#,
+            id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM rotation_campaigns
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Take a user out of every campaign.
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM rotation_members
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace the name of the admin who started campaigns.
+    pub async fn rename_starter(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE rotation_campaigns
+            SET started_by = ?
+            WHERE started_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
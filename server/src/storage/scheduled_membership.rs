@@ -0,0 +1,296 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{
+    Result, err,
+    group_webhook::MembershipAction,
+    scheduled_membership::{ScheduledChangeStatus, ScheduledMembershipChange},
+};
+use uuid::Uuid;
+
+use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+
+struct ScheduledMembershipChangeRow {
+    id: Uuid,
+    user_id: Uuid,
+    user_name: String,
+    group_id: Uuid,
+    group_name: String,
+    action: String,
+    run_at: SqlxTimestamp,
+    created_by: String,
+    status: String,
+    finished_at: Option<SqlxTimestamp>,
+    note: Option<String>,
+}
+
+impl ScheduledMembershipChangeRow {
+    fn into_change(self) -> Result<ScheduledMembershipChange> {
+        Ok(ScheduledMembershipChange {
+            id: self.id,
+            user_id: self.user_id,
+            user_name: self.user_name,
+            group_id: self.group_id,
+            group_name: self.group_name,
+            action: self.action.parse()?,
+            run_at: self.run_at.to_jiff(),
+            created_by: self.created_by,
+            created_at: self.id.jiff_timestamp(),
+            status: self.status.parse()?,
+            finished_at: self.finished_at.map(|t| t.to_jiff()),
+            note: self.note,
+        })
+    }
+}
+
+/// Storage for [`ScheduledMembershipChange`]s.
+pub struct ScheduledMembershipChanges;
+
+impl ScheduledMembershipChanges {
+    pub async fn create(
+        user_id: Uuid,
+        user_name: &str,
+        group_id: Uuid,
+        group_name: &str,
+        action: MembershipAction,
+        run_at: Timestamp,
+        created_by: &str,
+    ) -> Result<Uuid> {
+        let id = Uuid::now_v7();
+        let user_id = user_id.as_bytes().as_slice();
+        let group_id = group_id.as_bytes().as_slice();
+        let action = action.as_str();
+        let run_at = run_at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO scheduled_membership_changes (
+                id, user_id, user_name, group_id, group_name, action, run_at, created_by
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            user_id,
+            user_name,
+            group_id,
+            group_name,
+            action,
+            run_at,
+            created_by,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn find(id: Uuid) -> Result<ScheduledMembershipChange> {
+        let id_bytes = id.as_bytes().as_slice();
+
+        let row = sqlx::query_as!(
+            ScheduledMembershipChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                group_id as "group_id: _",
+                group_name,
+                action,
+                run_at as "run_at: _",
+                created_by,
+                status,
+                finished_at as "finished_at: _",
+                note
+            FROM scheduled_membership_changes
+            WHERE id = ?
+            "#,
+            id_bytes,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        row.into_change()
+    }
+
+    /// A user's changes still waiting to be made, soonest first.
+    pub async fn pending_for_user(user_id: Uuid) -> Result<Vec<ScheduledMembershipChange>> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            ScheduledMembershipChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                group_id as "group_id: _",
+                group_name,
+                action,
+                run_at as "run_at: _",
+                created_by,
+                status,
+                finished_at as "finished_at: _",
+                note
+            FROM scheduled_membership_changes
+            WHERE user_id = ? AND status = 'pending'
+            ORDER BY run_at
+            "#,
+            user_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(ScheduledMembershipChangeRow::into_change)
+            .collect()
+    }
+
+    /// A group's changes still waiting to be made, soonest first.
+    pub async fn pending_for_group(group_id: Uuid) -> Result<Vec<ScheduledMembershipChange>> {
+        let group_id = group_id.as_bytes().as_slice();
+
+        let rows = sqlx::query_as!(
+            ScheduledMembershipChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                group_id as "group_id: _",
+                group_name,
+                action,
+                run_at as "run_at: _",
+                created_by,
+                status,
+                finished_at as "finished_at: _",
+                note
+            FROM scheduled_membership_changes
+            WHERE group_id = ? AND status = 'pending'
+            ORDER BY run_at
+            "#,
+            group_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(ScheduledMembershipChangeRow::into_change)
+            .collect()
+    }
+
+    /// Pending changes whose time has come, oldest first.
+    pub async fn due(now: Timestamp) -> Result<Vec<ScheduledMembershipChange>> {
+        let now = now.to_sqlx();
+
+        let rows = sqlx::query_as!(
+            ScheduledMembershipChangeRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_id as "user_id: _",
+                user_name,
+                group_id as "group_id: _",
+                group_name,
+                action,
+                run_at as "run_at: _",
+                created_by,
+                status,
+                finished_at as "finished_at: _",
+                note
+            FROM scheduled_membership_changes
+            WHERE status = 'pending' AND run_at <= ?
+            ORDER BY run_at
+            "#,
+            now,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.into_iter()
+            .map(ScheduledMembershipChangeRow::into_change)
+            .collect()
+    }
+
+    /// Mark a pending change done, failed or cancelled. Fails if it isn't
+    /// pending any more.
+    pub async fn finish(id: Uuid, status: ScheduledChangeStatus, note: Option<&str>) -> Result<()> {
+        let id_bytes = id.as_bytes().as_slice();
+        let status = status.as_str();
+        let finished_at = Timestamp::now().to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE scheduled_membership_changes
+            SET status = ?, finished_at = ?, note = ?
+            WHERE id = ? AND status = 'pending'
+            "#,
+            status,
+            finished_at,
+            note,
+            id_bytes,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(err!("this change has already been made or cancelled"));
+        }
+
+        Ok(())
+    }
+
+    /// Cancel a group's changes that haven't been made yet, once it's gone.
+    pub async fn cancel_for_group(group_id: Uuid, note: &str) -> Result<()> {
+        let group_id = group_id.as_bytes().as_slice();
+        let finished_at = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE scheduled_membership_changes
+            SET status = 'cancelled', finished_at = ?, note = ?
+            WHERE group_id = ? AND status = 'pending'
+            "#,
+            finished_at,
+            note,
+            group_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace an admin's name on the changes they scheduled.
+    pub async fn rename_creator(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE scheduled_membership_changes
+            SET created_by = ?
+            WHERE created_by = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a user's scheduled changes, when they're forgotten.
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM scheduled_membership_changes
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,30 +1,74 @@
-use types::Result;
+use std::time::Duration;
 
-use crate::user_data::UserData;
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::{Result, err, session::SessionInfo};
+
+use crate::{
+    device::{Device, describe_user_agent},
+    user_data::UserData,
+};
 use uuid::Uuid;
 
-use crate::{storage::POOL, uuid_v7::UuidV7Ext};
+use crate::{
+    session_events,
+    signed_token::{Purpose, SignedToken, decode_id},
+    storage::POOL,
+    uuid_v7::UuidV7Ext,
+};
 
 struct SessionRow {
     id: Uuid,
     user_data: String,
+    expires_at: SqlxTimestamp,
+    persistent: bool,
+    auth_time: Option<SqlxTimestamp>,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+    location: Option<String>,
 }
 
 #[derive(Debug)]
 pub struct Session {
     id: Uuid,
     pub user_data: UserData,
+    expires_at: Timestamp,
+    persistent: bool,
+    auth_time: Option<Timestamp>,
+    device: Device,
 }
 
 impl Session {
-    pub fn new(user_data: UserData) -> Self {
+    /// Create a new session. `reauthenticated` should be set if the user was
+    /// made to enter their credentials, rather than reusing an SSO session.
+    pub fn new(
+        user_data: UserData,
+        lifetime: Duration,
+        persistent: bool,
+        reauthenticated: bool,
+        device: Device,
+    ) -> Self {
         let id = Uuid::now_v7();
+        let now = id.jiff_timestamp();
 
-        Self { id, user_data }
+        Self {
+            id,
+            user_data,
+            expires_at: now + lifetime,
+            persistent,
+            auth_time: reauthenticated.then_some(now),
+            device,
+        }
     }
 
-    pub async fn create(user_data: UserData) -> Result<Self> {
-        let session = Self::new(user_data);
+    pub async fn create(
+        user_data: UserData,
+        lifetime: Duration,
+        persistent: bool,
+        reauthenticated: bool,
+        device: Device,
+    ) -> Result<Self> {
+        let session = Self::new(user_data, lifetime, persistent, reauthenticated, device);
         session.insert().await?;
         Ok(session)
     }
@@ -37,7 +81,13 @@ impl Session {
             r#"
             SELECT
                 id as "id: _",
-                user_data
+                user_data,
+                expires_at as "expires_at: _",
+                persistent,
+                auth_time as "auth_time: _",
+                user_agent,
+                ip_address,
+                location
             FROM sessions
             WHERE id = ?
             "#,
@@ -46,33 +96,215 @@ impl Session {
         .fetch_one(&*POOL)
         .await?;
 
+        let session = Self::from_row(row)?;
+        if session.is_expired() {
+            let _ = session.delete().await;
+            return Err(err!("session has expired"));
+        }
+
+        Ok(session)
+    }
+
+    /// Whether a session exists and hasn't expired, without loading it.
+    pub async fn is_active(id: Uuid) -> Result<bool> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM sessions
+            WHERE id = ? AND expires_at > ?
+            "#,
+            id,
+            now,
+        )
+        .fetch_one(&*POOL)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// List the unexpired sessions belonging to the given Kanidm user.
+    pub async fn list_for_user(user_id: &str) -> Result<Vec<Self>> {
+        let rows = sqlx::query_as!(
+            SessionRow,
+            r#"
+            SELECT
+                id as "id: _",
+                user_data,
+                expires_at as "expires_at: _",
+                persistent,
+                auth_time as "auth_time: _",
+                user_agent,
+                ip_address,
+                location
+            FROM sessions
+            WHERE user_id = ?
+            ORDER BY id DESC
+            "#,
+            user_id,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        let sessions = rows
+            .into_iter()
+            .map(Self::from_row)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(sessions.into_iter().filter(|s| !s.is_expired()).collect())
+    }
+
+    /// Record that a browser using the session is open now.
+    pub async fn touch(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET last_seen_at = ?
+            WHERE id = ?
+            "#,
+            now,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The users of unexpired sessions whose browsers checked in since
+    /// `cutoff`.
+    pub async fn seen_since(cutoff: Timestamp) -> Result<Vec<UserData>> {
+        let cutoff = cutoff.to_sqlx();
+        let now = Timestamp::now().to_sqlx();
+
+        let rows = sqlx::query_scalar!(
+            r#"
+            SELECT user_data
+            FROM sessions
+            WHERE last_seen_at >= ? AND expires_at > ?
+            "#,
+            cutoff,
+            now,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        rows.iter()
+            .map(|user_data| Ok(serde_json::from_str(user_data)?))
+            .collect()
+    }
+
+    fn from_row(row: SessionRow) -> Result<Self> {
         Ok(Self {
             id: row.id,
             user_data: serde_json::from_str(&row.user_data)?,
+            expires_at: row.expires_at.to_jiff(),
+            persistent: row.persistent,
+            auth_time: row.auth_time.map(|t| t.to_jiff()),
+            device: Device {
+                user_agent: row.user_agent,
+                ip_address: row.ip_address,
+                location: row.location,
+            },
         })
     }
 
     /// Find session by signed token (cookie value).
     pub async fn find_token(token: &str) -> Result<Self> {
-        let uuid = Uuid::from_token(token)?;
-        Self::find(uuid).await
+        Self::find(decode_id(token, Purpose::Session)?).await
     }
 
     pub fn as_token(&self) -> Result<String> {
-        self.id.as_token()
+        SignedToken::new(Purpose::Session, self.id, Some(self.expires_at)).encode()
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn expires_at(&self) -> Timestamp {
+        self.expires_at
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.persistent
+    }
+
+    /// Whether the user re-entered their credentials within `max_age`.
+    pub fn is_recently_authenticated(&self, max_age: Duration) -> bool {
+        self.auth_time
+            .is_some_and(|t| Timestamp::now() < t + max_age)
+    }
+
+    fn is_expired(&self) -> bool {
+        Timestamp::now() >= self.expires_at
+    }
+
+    pub fn info(&self, current: bool) -> SessionInfo {
+        SessionInfo {
+            id: self.id,
+            created_at: self.id.jiff_timestamp(),
+            expires_at: self.expires_at,
+            persistent: self.persistent,
+            current,
+            device: self
+                .device
+                .user_agent
+                .as_deref()
+                .and_then(describe_user_agent),
+            ip_address: self.device.ip_address.clone(),
+            location: self.device.location.clone(),
+        }
     }
 
     pub async fn insert(&self) -> Result<()> {
         let id = self.id.as_bytes().as_slice();
         let user_data = serde_json::to_string(&self.user_data)?;
+        let expires_at = self.expires_at.to_sqlx();
+        let auth_time = self.auth_time.map(|t| t.to_sqlx());
 
         sqlx::query!(
             r#"
-            INSERT INTO sessions (id, user_data)
-            VALUES (?, ?)
+            INSERT INTO sessions (
+                id, user_data, user_id, expires_at, persistent, auth_time,
+                user_agent, ip_address, location
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             id,
-            user_data
+            user_data,
+            self.user_data.user_id,
+            expires_at,
+            self.persistent,
+            auth_time,
+            self.device.user_agent,
+            self.device.ip_address,
+            self.device.location,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Save changes to the session's user data.
+    pub async fn save_user_data(&self) -> Result<()> {
+        let id = self.id.as_bytes().as_slice();
+        let user_data = serde_json::to_string(&self.user_data)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET user_data = ?
+            WHERE id = ?
+            "#,
+            user_data,
+            id,
         )
         .execute(&*POOL)
         .await?;
@@ -92,10 +324,44 @@ impl Session {
         )
         .execute(&*POOL)
         .await?;
+        session_events::sessions_ended();
 
         Ok(())
     }
 
+    /// Delete all of a Kanidm user's sessions.
+    pub async fn delete_for_user(user_id: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM sessions
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+        session_events::sessions_ended();
+
+        Ok(())
+    }
+
+    /// Delete sessions that expired before `cutoff`, returning how many.
+    pub async fn delete_expired_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM sessions
+            WHERE expires_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn delete_token(token: &str) -> Result<()> {
         if let Ok(session) = Self::find_token(token).await {
             session.delete().await?;
@@ -0,0 +1,75 @@
+use serde::{Serialize, de::DeserializeOwned};
+use types::Result;
+
+use crate::storage::POOL;
+
+/// Site-wide settings that admins can change at runtime.
+pub struct Settings;
+
+impl Settings {
+    pub const ADMIN_GROUP: &str = "admin_group";
+    pub const ANNOUNCEMENT: &str = "announcement";
+    pub const CAPTURE: &str = "capture";
+    pub const DIGEST_LAST_SENT: &str = "digest_last_sent";
+    pub const FIELD_VISIBILITY: &str = "field_visibility";
+    pub const INTEGRITY: &str = "integrity";
+    pub const LAST_SEEN_VERSION: &str = "last_seen_version";
+    pub const MAINTENANCE: &str = "maintenance";
+    pub const NOTIFICATIONS: &str = "notifications";
+    pub const OFFBOARDING_TEMPLATE: &str = "offboarding_template";
+    pub const REPORTS: &str = "reports";
+    pub const REPORT_LAST_SENT: &str = "report_last_sent";
+    pub const REQUIRE_EMAIL: &str = "require_email";
+    pub const REQUIRE_TICKET: &str = "require_ticket";
+    pub const RETENTION: &str = "retention";
+
+    pub async fn get<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+        let value = sqlx::query_scalar!(
+            r#"
+            SELECT value
+            FROM settings
+            WHERE key = ?
+            "#,
+            key,
+        )
+        .fetch_optional(&*POOL)
+        .await?;
+
+        match value {
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set<T: Serialize>(key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_string(value)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO settings (key, value)
+            VALUES (?, ?)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value
+            "#,
+            key,
+            value,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(key: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM settings
+            WHERE key = ?
+            "#,
+            key,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use jiff::Timestamp;
+use jiff_sqlx::{Timestamp as SqlxTimestamp, ToSqlx};
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct ShiftNoteRow {
+    id: Uuid,
+    author: String,
+    content: String,
+    created_at: SqlxTimestamp,
+}
+
+/// A shift log note, with its markdown source.
+#[derive(Debug, Clone)]
+pub struct StoredShiftNote {
+    pub id: Uuid,
+    pub author: String,
+    pub content: String,
+    pub created_at: Timestamp,
+}
+
+/// Notes admins leave for each other.
+pub struct ShiftNotes;
+
+impl ShiftNotes {
+    pub async fn create(author: &str, content: &str) -> Result<StoredShiftNote> {
+        let id = Uuid::now_v7();
+        let created_at = Timestamp::now();
+        let created_at_sqlx = created_at.to_sqlx();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO shift_notes (id, author, content, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+            id,
+            author,
+            content,
+            created_at_sqlx,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(StoredShiftNote {
+            id,
+            author: author.to_string(),
+            content: content.to_string(),
+            created_at,
+        })
+    }
+
+    /// List notes, newest first.
+    pub async fn list() -> Result<Vec<StoredShiftNote>> {
+        let rows = sqlx::query_as!(
+            ShiftNoteRow,
+            r#"
+            SELECT
+                id as "id: _",
+                author,
+                content,
+                created_at as "created_at: _"
+            FROM shift_notes
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| StoredShiftNote {
+                id: row.id,
+                author: row.author,
+                content: row.content,
+                created_at: row.created_at.to_jiff(),
+            })
+            .collect())
+    }
+
+    pub async fn delete(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM shift_notes
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_before(cutoff: Timestamp) -> Result<u64> {
+        let cutoff = cutoff.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM shift_notes
+            WHERE created_at < ?
+            "#,
+            cutoff,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Replace an author's name on their notes.
+    pub async fn rename_author(name: &str, new_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE shift_notes
+            SET author = ?
+            WHERE author = ?
+            "#,
+            new_name,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+struct TagRow {
+    id: Uuid,
+    name: String,
+}
+
+struct UserTagRow {
+    user_id: Uuid,
+    tag_id: Uuid,
+}
+
+/// Local tags, and which users have them.
+pub struct Tags;
+
+impl Tags {
+    /// List tags by name, as `(id, name)`.
+    pub async fn list() -> Result<Vec<(Uuid, String)>> {
+        let rows = sqlx::query_as!(
+            TagRow,
+            r#"
+            SELECT
+                id as "id: _",
+                name
+            FROM tags
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.name)).collect())
+    }
+
+    pub async fn create(id: Uuid, name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO tags (id, name)
+            VALUES (?, ?)
+            "#,
+            id,
+            name,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn rename(id: Uuid, name: &str) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            UPDATE tags
+            SET name = ?
+            WHERE id = ?
+            "#,
+            name,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(id: Uuid) -> Result<()> {
+        let id = id.as_bytes().as_slice();
+
+        let mut tx = POOL.begin().await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM user_tags
+            WHERE tag_id = ?
+            "#,
+            id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM tags
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Every tagged user, as `(user_id, tag_id)` pairs.
+    pub async fn list_assignments() -> Result<Vec<(Uuid, Uuid)>> {
+        let rows = sqlx::query_as!(
+            UserTagRow,
+            r#"
+            SELECT
+                user_id as "user_id: _",
+                tag_id as "tag_id: _"
+            FROM user_tags
+            "#,
+        )
+        .fetch_all(&*POOL)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.user_id, row.tag_id))
+            .collect())
+    }
+
+    /// Give a user exactly `tag_ids`.
+    pub async fn set_for_user(user_id: Uuid, tag_ids: &[Uuid]) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        let mut tx = POOL.begin().await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM user_tags
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        for tag_id in tag_ids {
+            let tag_id = tag_id.as_bytes().as_slice();
+            sqlx::query!(
+                r#"
+                INSERT INTO user_tags (user_id, tag_id)
+                VALUES (?, ?)
+                "#,
+                user_id,
+                tag_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_for_user(user_id: Uuid) -> Result<()> {
+        let user_id = user_id.as_bytes().as_slice();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM user_tags
+            WHERE user_id = ?
+            "#,
+            user_id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use jiff::Timestamp;
+use jiff_sqlx::ToSqlx;
+use types::Result;
+use uuid::Uuid;
+
+use crate::storage::POOL;
+
+/// Remembers which one-click action links have been used.
+pub struct UsedActionTokens;
+
+impl UsedActionTokens {
+    /// Mark a token as used, returning false if it already was.
+    pub async fn claim(id: Uuid, expires_at: Timestamp) -> Result<bool> {
+        let expires_at = expires_at.to_sqlx();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO used_action_tokens (id, expires_at)
+            VALUES (?, ?)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            id,
+            expires_at,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Forget that a token was used, so its link works again.
+    pub async fn release(id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM used_action_tokens
+            WHERE id = ?
+            "#,
+            id,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_expired() -> Result<()> {
+        let now = Timestamp::now().to_sqlx();
+
+        sqlx::query!(
+            r#"
+            DELETE FROM used_action_tokens
+            WHERE expires_at <= ?
+            "#,
+            now,
+        )
+        .execute(&*POOL)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::Write;
+
+use types::{Result, err};
+
+/// A systemd unit that runs this binary as a service, for
+/// `authit systemd-unit`. `args` can set `--config`, the config file to read,
+/// and `--user` to run as, rather than a user made up for it.
+pub fn unit(args: &[&str], data_dir: Option<&str>) -> Result<String> {
+    let mut config = "/etc/authit.toml";
+    let mut user = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| err!("{arg} needs a value"))?;
+        match *arg {
+            "--config" => config = value,
+            "--user" => user = Some(*value),
+            _ => return Err(err!("unknown option {arg}")),
+        }
+    }
+    let exe = std::env::current_exe()?;
+
+    let mut unit = String::new();
+    writeln!(unit, "[Unit]")?;
+    writeln!(unit, "Description=AuthIt! user management for Kanidm")?;
+    writeln!(unit, "Wants=network-online.target")?;
+    writeln!(unit, "After=network-online.target")?;
+    writeln!(unit)?;
+    writeln!(unit, "[Service]")?;
+    writeln!(unit, "ExecStart={}", exe.display())?;
+    // The config has secrets, so it's passed as a credential, which only the
+    // service can read, whoever it runs as.
+    writeln!(unit, "LoadCredential=config:{config}")?;
+    writeln!(unit, "Environment=AUTHIT_CONFIG_PATH=%d/config")?;
+    match data_dir {
+        Some(dir) => {
+            writeln!(unit, "Environment=AUTHIT_DATA_DIR={dir}")?;
+            writeln!(unit, "ReadWritePaths={dir}")?;
+        }
+        // This sets STATE_DIRECTORY, which AuthIt! uses by default.
+        None => writeln!(unit, "StateDirectory=authit")?,
+    }
+    match user {
+        Some(user) => writeln!(unit, "User={user}")?,
+        None => writeln!(unit, "DynamicUser=yes")?,
+    }
+    writeln!(unit, "Restart=on-failure")?;
+    writeln!(unit)?;
+    writeln!(unit, "# Hardening")?;
+    for setting in [
+        "NoNewPrivileges=yes",
+        "ProtectSystem=strict",
+        "ProtectHome=yes",
+        "PrivateTmp=yes",
+        "PrivateDevices=yes",
+        "ProtectKernelTunables=yes",
+        "ProtectKernelModules=yes",
+        "ProtectControlGroups=yes",
+        "RestrictNamespaces=yes",
+        "RestrictSUIDSGID=yes",
+        "LockPersonality=yes",
+    ] {
+        writeln!(unit, "{setting}")?;
+    }
+    writeln!(unit)?;
+    writeln!(unit, "[Install]")?;
+    writeln!(unit, "WantedBy=multi-user.target")?;
+    Ok(unit)
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use types::{Result, err, tag::Tag, validation::normalize_tag};
+use uuid::Uuid;
+
+use crate::storage::Tags;
+
+/// List tags by name, with how many users have each.
+pub async fn list() -> Result<Vec<Tag>> {
+    let assignments = Tags::list_assignments().await?;
+    Ok(Tags::list()
+        .await?
+        .into_iter()
+        .map(|(id, name)| Tag {
+            id,
+            name,
+            user_count: assignments.iter().filter(|(_, t)| *t == id).count() as u32,
+        })
+        .collect())
+}
+
+async fn check_unused(name: &str, except: Option<Uuid>) -> Result<()> {
+    if Tags::list()
+        .await?
+        .iter()
+        .any(|(id, n)| n == name && Some(*id) != except)
+    {
+        return Err(err!("there's already a tag called '{name}'"));
+    }
+    Ok(())
+}
+
+pub async fn create(name: &str) -> Result<Tag> {
+    let name = normalize_tag(name)?;
+    check_unused(&name, None).await?;
+
+    let id = Uuid::now_v7();
+    Tags::create(id, &name).await?;
+    Ok(Tag {
+        id,
+        name,
+        user_count: 0,
+    })
+}
+
+pub async fn rename(id: Uuid, name: &str) -> Result<()> {
+    let name = normalize_tag(name)?;
+    check_unused(&name, Some(id)).await?;
+    Tags::rename(id, &name).await
+}
+
+pub async fn delete(id: Uuid) -> Result<()> {
+    Tags::delete(id).await
+}
+
+/// Each tagged user's tags.
+pub async fn by_user() -> Result<HashMap<Uuid, Vec<Uuid>>> {
+    let mut tags: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for (user_id, tag_id) in Tags::list_assignments().await? {
+        tags.entry(user_id).or_default().push(tag_id);
+    }
+    Ok(tags)
+}
+
+/// Give a user exactly `tag_ids`.
+pub async fn set_for_user(user_id: Uuid, tag_ids: &[Uuid]) -> Result<()> {
+    let known: Vec<Uuid> = Tags::list().await?.into_iter().map(|(id, _)| id).collect();
+    if let Some(unknown) = tag_ids.iter().find(|id| !known.contains(id)) {
+        return Err(err!("no tag with id {unknown}"));
+    }
+    Tags::set_for_user(user_id, tag_ids).await
+}
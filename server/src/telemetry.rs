@@ -0,0 +1,127 @@
+use axum::{
+    Router,
+    extract::Request,
+    http::HeaderMap,
+    middleware::{self, Next},
+    response::Response,
+};
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::TracerProvider,
+};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use reqwest::{
+    RequestBuilder,
+    header::{HeaderName, HeaderValue},
+};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::CONFIG;
+
+pub fn init_tracing() {
+    let filter = EnvFilter::builder()
+        .with_default_directive(CONFIG.log_level.into())
+        .from_env_lossy();
+
+    let otel =
+        CONFIG
+            .otlp_endpoint
+            .as_deref()
+            .and_then(|endpoint| match tracer_provider(endpoint) {
+                Ok(provider) => {
+                    let tracer = provider.tracer("authit");
+                    global::set_text_map_propagator(TraceContextPropagator::new());
+                    global::set_tracer_provider(provider);
+                    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+                }
+                Err(error) => {
+                    eprintln!("Failed to set up OTLP export: {error}");
+                    None
+                }
+            });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel)
+        .init();
+}
+
+fn tracer_provider(endpoint: &str) -> types::Result<SdkTracerProvider> {
+    let endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("authit").build())
+        .build())
+}
+
+/// Give every request a span, continuing the caller's trace if it sent one.
+pub fn trace_requests(router: Router) -> Router {
+    router.layer(middleware::from_fn(trace_request))
+}
+
+async fn trace_request(request: Request, next: Next) -> Response {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = request.uri().path(),
+        status = tracing::field::Empty,
+    );
+    let parent =
+        global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(request.headers())));
+    span.set_parent(parent);
+
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+    response
+}
+
+pub trait TraceContextExt {
+    /// Pass the current trace on to the server we're calling.
+    fn with_trace_context(self) -> Self;
+}
+
+impl TraceContextExt for RequestBuilder {
+    fn with_trace_context(self) -> Self {
+        let mut headers = HeaderMap::new();
+        let context = tracing::Span::current().context();
+        global::get_text_map_propagator(|p| {
+            p.inject_context(&context, &mut HeaderInjector(&mut headers))
+        });
+        self.headers(headers)
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use types::{
+    Result,
+    validation::{MAX_TICKET_LENGTH, check_length},
+};
+
+use crate::storage::Settings;
+
+/// Whether dangerous changes are refused until the admin enters the ticket
+/// they're for.
+pub async fn is_required() -> Result<bool> {
+    Ok(Settings::get(Settings::REQUIRE_TICKET)
+        .await?
+        .unwrap_or(false))
+}
+
+pub async fn set_required(required: bool) -> Result<()> {
+    Settings::set(Settings::REQUIRE_TICKET, &required).await
+}
+
+/// Trim a ticket reference, treating a blank one as none.
+pub fn normalize(ticket: Option<&str>) -> Result<Option<String>> {
+    let Some(ticket) = ticket.map(str::trim).filter(|t| !t.is_empty()) else {
+        return Ok(None);
+    };
+    check_length("ticket", ticket, MAX_TICKET_LENGTH)?;
+    Ok(Some(ticket.to_string()))
+}
@@ -1,5 +1,8 @@
 use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
+use types::kanidm::Spn;
+
+use crate::CONFIG;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserData {
@@ -9,11 +12,67 @@ pub struct UserData {
     pub groups: Vec<String>,
     #[serde(with = "secret_string")]
     pub access_token: SecretString,
+    /// Set for sessions created through the break-glass local login, which
+    /// have no Kanidm access token.
+    #[serde(default)]
+    pub break_glass: bool,
+    /// Set for sessions created through the `test-login` route, which have
+    /// no Kanidm access token either.
+    #[serde(default)]
+    pub test_login: bool,
+    /// The ticket the admin said they're working on, recorded with their
+    /// changes.
+    #[serde(default)]
+    pub ticket: Option<String>,
 }
 
 impl UserData {
+    pub const BREAK_GLASS_USERNAME: &str = "break-glass";
+
+    pub fn break_glass() -> Self {
+        Self {
+            user_id: Self::BREAK_GLASS_USERNAME.to_string(),
+            username: Self::BREAK_GLASS_USERNAME.to_string(),
+            display_name: "Break-glass Admin".to_string(),
+            groups: vec![CONFIG.admin_group().to_string()],
+            access_token: String::new().into(),
+            break_glass: true,
+            test_login: false,
+            ticket: None,
+        }
+    }
+
+    /// The fake admin that end-to-end tests sign in as.
+    #[cfg(feature = "test-login")]
+    pub fn test_login() -> Self {
+        let username = CONFIG.test_login_username.clone();
+        let groups = std::iter::once(CONFIG.admin_group())
+            .chain(CONFIG.test_login_groups())
+            .map(String::from)
+            .collect();
+        Self {
+            user_id: username.clone(),
+            display_name: username.clone(),
+            username,
+            groups,
+            access_token: String::new().into(),
+            break_glass: false,
+            test_login: true,
+            ticket: None,
+        }
+    }
+
+    /// Whether the session was created without Kanidm, so there's no access
+    /// token to check and no Kanidm entry for the user.
+    pub fn is_local(&self) -> bool {
+        self.break_glass || self.test_login
+    }
+
+    /// Whether they're in a group given by name or SPN. Kanidm gives the
+    /// groups by SPN, unless its OAuth client prefers short names.
     pub fn is_in_group(&self, group: &str) -> bool {
-        self.groups.iter().any(|g| g == group)
+        let group = Spn::parse(group);
+        self.groups.iter().any(|g| Spn::parse(g).matches(&group))
     }
 }
 
@@ -0,0 +1,131 @@
+use types::{Result, notification::NotificationEvent, user_deletion::UserDeletionImpact};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, KANIDM_CLIENT,
+    storage::{
+        Activities, Avatars, GroupOwners, LoginEvents, Offboardings, Onboardings, PhoneNumbers,
+        ProfileChanges, ResetWatches, RotationCampaigns, Session, Tags,
+    },
+};
+
+/// Work out what deleting a user would lose, following what
+/// [`crate::forget::forget_user`] does once they're gone.
+pub async fn impact(user_id: Uuid) -> Result<UserDeletionImpact> {
+    let person = IDENTITY.get_person(&user_id.to_string()).await?;
+    let groups = person.group_names().map(str::to_string).collect();
+
+    let owned_ids = GroupOwners::groups_of(user_id).await?;
+    let owned_groups = if owned_ids.is_empty() {
+        Vec::new()
+    } else {
+        IDENTITY
+            .list_groups()
+            .await?
+            .into_iter()
+            .filter(|g| owned_ids.contains(&g.uuid))
+            .map(|g| g.name)
+            .collect()
+    };
+
+    let oauth2_sessions = match KANIDM_CLIENT.oauth2_session_count(&user_id).await {
+        Ok(count) => Some(count),
+        Err(error) => {
+            tracing::warn!(?error, %user_id, "failed to count OAuth2 sessions");
+            None
+        }
+    };
+
+    let mut records = Vec::new();
+    if PhoneNumbers::get(user_id).await?.is_some() {
+        records.push("Their phone number".to_string());
+    }
+    if Avatars::get(user_id).await?.is_some() {
+        records.push("Their avatar".to_string());
+    }
+    let tags = Tags::list_assignments()
+        .await?
+        .into_iter()
+        .filter(|(u, _)| *u == user_id)
+        .count();
+    if tags > 0 {
+        records.push(count(tags, "tag"));
+    }
+    let onboardings = Onboardings::list_for_user(user_id).await?.len();
+    if onboardings > 0 {
+        records.push(count(onboardings, "onboarding checklist"));
+    }
+    let profile_changes = ProfileChanges::list_for_user(user_id).await?.len();
+    if profile_changes > 0 {
+        records.push(count(profile_changes, "profile change request"));
+    }
+    if ResetWatches::list()
+        .await?
+        .iter()
+        .any(|w| w.user_id == user_id)
+    {
+        records.push("A watch on their credential reset link".to_string());
+    }
+    let mut rotations = 0;
+    for campaign in RotationCampaigns::list_open().await? {
+        if RotationCampaigns::pending(campaign)
+            .await?
+            .iter()
+            .any(|p| p.user_id == user_id)
+        {
+            rotations += 1;
+        }
+    }
+    if rotations > 0 {
+        records.push(format!(
+            "Their place in {}",
+            count(rotations, "open credential rotation campaign")
+        ));
+    }
+
+    let mut mentions = Vec::new();
+    let activity = Activities::list_for_user(user_id).await?;
+    let provisioned_at = activity
+        .iter()
+        .find(|a| a.event == NotificationEvent::UserProvisioned)
+        .map(|a| a.at);
+    if !activity.is_empty() {
+        mentions.push(count(activity.len(), "activity record"));
+    }
+    let offboardings = Offboardings::list()
+        .await?
+        .into_iter()
+        .filter(|o| o.user_id == user_id)
+        .count();
+    if offboardings > 0 {
+        mentions.push(count(offboardings, "offboarding checklist"));
+    }
+
+    let login_events = LoginEvents::count_for_user(&user_id.to_string()).await? as usize;
+    if CONFIG.anonymize_on_delete {
+        let sessions = Session::list_for_user(&user_id.to_string()).await?.len();
+        if sessions > 0 {
+            records.push(count(sessions, "AuthIt! session"));
+        }
+        if login_events > 0 {
+            records.push(count(login_events, "recorded sign-in"));
+        }
+    } else if login_events > 0 {
+        mentions.push(count(login_events, "recorded sign-in"));
+    }
+
+    Ok(UserDeletionImpact {
+        person,
+        groups,
+        owned_groups,
+        oauth2_sessions,
+        records,
+        mentions,
+        anonymized: CONFIG.anonymize_on_delete,
+        provisioned_at,
+    })
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{n} {noun}{}", if n == 1 { "" } else { "s" })
+}
@@ -0,0 +1,66 @@
+use types::{Result, user_detail::UserDetail};
+use uuid::Uuid;
+
+use crate::{
+    CONFIG, IDENTITY, KANIDM_CLIENT, UserData, collation, redact_legal_names,
+    storage::{
+        Activities, GroupOwners, LoginEvents, PhoneNumbers, ScheduledMembershipChanges, Tags,
+    },
+    tags,
+};
+
+/// How much of a user's activity their page shows.
+const RECENT_ACTIVITY: usize = 20;
+
+/// Gather everything a user's page shows about them, as `admin` may see it.
+pub async fn get(user_id: Uuid, admin: &UserData) -> Result<UserDetail> {
+    let mut person = IDENTITY.get_person(&user_id.to_string()).await?;
+    redact_legal_names(admin, std::slice::from_mut(&mut person)).await?;
+
+    let mut groups: Vec<_> = IDENTITY
+        .list_groups()
+        .await?
+        .into_iter()
+        .filter(|g| person.is_member_of(g))
+        .collect();
+    collation::sort_groups(&mut groups);
+
+    let has_credentials = KANIDM_CLIENT
+        .credential_status(&[user_id])
+        .await
+        .remove(&user_id);
+    let ldap_status = if CONFIG.ldap_enabled {
+        Some(KANIDM_CLIENT.get_ldap_status(&user_id).await?)
+    } else {
+        None
+    };
+
+    let tag_ids: Vec<Uuid> = Tags::list_assignments()
+        .await?
+        .into_iter()
+        .filter(|(u, _)| *u == user_id)
+        .map(|(_, t)| t)
+        .collect();
+    let tags = tags::list()
+        .await?
+        .into_iter()
+        .filter(|t| tag_ids.contains(&t.id))
+        .collect();
+
+    let mut recent_activity = Activities::list_for_user(user_id).await?;
+    recent_activity.reverse();
+    recent_activity.truncate(RECENT_ACTIVITY);
+
+    Ok(UserDetail {
+        person,
+        groups,
+        has_credentials,
+        ldap_status,
+        phone_number: PhoneNumbers::get(user_id).await?,
+        tags,
+        owned_groups: GroupOwners::groups_of(user_id).await?,
+        scheduled_changes: ScheduledMembershipChanges::pending_for_user(user_id).await?,
+        recent_activity,
+        login_events: LoginEvents::list_for_user(&user_id.to_string(), 20).await?,
+    })
+}
@@ -0,0 +1,55 @@
+use types::{
+    Result, err,
+    visibility::{Audience, FieldVisibility},
+};
+
+use crate::{CONFIG, UserData, storage::Settings};
+
+/// Who may see each field. Until it's set from the Settings page, this
+/// follows `directory_show_email` and `legal_name_group`.
+pub async fn get() -> Result<FieldVisibility> {
+    Ok(Settings::get(Settings::FIELD_VISIBILITY)
+        .await?
+        .unwrap_or_else(default))
+}
+
+pub async fn set(visibility: FieldVisibility) -> Result<()> {
+    for audience in [
+        &visibility.email_addresses,
+        &visibility.legal_name,
+        &visibility.groups,
+    ] {
+        if let Audience::Group(group) = audience
+            && group.trim().is_empty()
+        {
+            return Err(err!("pick a group to show the field to"));
+        }
+    }
+    Settings::set(Settings::FIELD_VISIBILITY, &visibility).await
+}
+
+fn default() -> FieldVisibility {
+    FieldVisibility {
+        email_addresses: if CONFIG.directory_show_email {
+            Audience::Everyone
+        } else {
+            Audience::Nobody
+        },
+        legal_name: if CONFIG.legal_name_group.is_empty() {
+            Audience::Admins
+        } else {
+            Audience::Group(CONFIG.legal_name_group.clone())
+        },
+        groups: Audience::Everyone,
+    }
+}
+
+/// Whether `user` is in `audience`.
+pub fn allows(audience: &Audience, user: &UserData) -> bool {
+    match audience {
+        Audience::Everyone => true,
+        Audience::Admins => user.is_in_group(CONFIG.admin_group()),
+        Audience::Group(group) => user.is_in_group(group.trim()),
+        Audience::Nobody => false,
+    }
+}
@@ -0,0 +1,40 @@
+use std::{
+    env,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Record which commit this is built from, and when, for `ServerInfo`.
+///
+/// Builds without a git checkout, like Nix's, can set `AUTHIT_GIT_HASH`
+/// themselves. `SOURCE_DATE_EPOCH` is used as the build time if it's set, so
+/// reproducible builds stay reproducible.
+fn main() {
+    println!("cargo:rerun-if-env-changed=AUTHIT_GIT_HASH");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // Changes when a commit is made or checked out.
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
+    let git_hash = env::var("AUTHIT_GIT_HASH").ok().or_else(|| {
+        let output = Command::new("git")
+            .args(["rev-parse", "--short=12", "HEAD"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())?;
+        Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+    });
+    println!(
+        "cargo:rustc-env=AUTHIT_GIT_HASH={}",
+        git_hash.unwrap_or_default()
+    );
+
+    let built_at = env::var("SOURCE_DATE_EPOCH").unwrap_or_else(|_| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+            .to_string()
+    });
+    println!("cargo:rustc-env=AUTHIT_BUILT_AT={built_at}");
+}
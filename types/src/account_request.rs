@@ -0,0 +1,62 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+/// A visitor's request for an account, submitted without a provision link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountRequest {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub justification: String,
+    pub created_at: Timestamp,
+    pub status: AccountRequestStatus,
+    /// The admin who approved or denied the request.
+    pub decided_by: Option<String>,
+    pub decided_at: Option<Timestamp>,
+    /// Why the request was denied, as sent to the requester.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountRequestStatus {
+    #[default]
+    Pending,
+    Approved,
+    Denied,
+}
+
+impl AccountRequestStatus {
+    pub const ALL: [Self; 3] = [Self::Pending, Self::Approved, Self::Denied];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+        }
+    }
+}
+
+impl std::str::FromStr for AccountRequestStatus {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown account request status '{s}'"))
+    }
+}
+
+/// The result of approving an account request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountRequestApproval {
+    /// A single-use provision link for the requester.
+    pub provision_url: Url,
+    /// Whether the link was emailed to the requester. If not, an admin needs
+    /// to pass it along themselves.
+    pub emailed: bool,
+}
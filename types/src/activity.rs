@@ -0,0 +1,69 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::notification::NotificationEvent;
+
+/// Something that happened, as shown on the dashboard's activity stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    pub id: Uuid,
+    pub at: Timestamp,
+    pub event: NotificationEvent,
+    pub summary: String,
+    /// Who did it, if it was someone signed in to AuthIt!.
+    pub actor: Option<ActivityActor>,
+    pub subject: Option<ActivitySubject>,
+    /// The ticket the actor said they were working on.
+    #[serde(default)]
+    pub ticket: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityActor {
+    /// Nil for the break-glass account.
+    pub user_id: Uuid,
+    pub name: String,
+}
+
+/// What an activity was about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActivitySubject {
+    User { id: Uuid, name: String },
+    Group { name: String },
+}
+
+/// An activity found by searching the audit history, with the words that
+/// matched marked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityMatch {
+    pub activity: Activity,
+    pub summary: Vec<MatchRun>,
+    /// Empty if there's no actor, subject or ticket.
+    pub actor: Vec<MatchRun>,
+    pub subject: Vec<MatchRun>,
+    pub ticket: Vec<MatchRun>,
+}
+
+/// A run of text in an [`ActivityMatch`], which either matched the search or
+/// didn't.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchRun {
+    pub text: String,
+    pub matched: bool,
+}
+
+/// A call AuthIt! made to Kanidm while doing what an activity records, to
+/// explain exactly what it changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KanidmCall {
+    pub method: String,
+    pub path: String,
+    /// The JSON sent, pretty-printed, with passwords, tokens and other
+    /// secrets replaced.
+    pub request: Option<String>,
+    /// The JSON Kanidm sent back, redacted the same way.
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
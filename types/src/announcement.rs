@@ -0,0 +1,59 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// A site-wide banner, for things like maintenance windows and policy notices.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub severity: Severity,
+    /// Markdown content. Raw HTML is not rendered.
+    pub content: String,
+    pub starts_at: Option<Timestamp>,
+    pub ends_at: Option<Timestamp>,
+}
+
+impl Announcement {
+    /// Whether the announcement should be shown at `now`.
+    pub fn is_active(&self, now: Timestamp) -> bool {
+        self.starts_at.is_none_or(|t| t <= now) && self.ends_at.is_none_or(|t| now < t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Self; 3] = [Self::Info, Self::Warning, Self::Critical];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|sev| sev.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown severity '{s}'"))
+    }
+}
+
+/// An announcement that is currently active, ready to display.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveAnnouncement {
+    pub severity: Severity,
+    /// The content, rendered from markdown.
+    pub html: String,
+}
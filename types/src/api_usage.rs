@@ -0,0 +1,53 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// How often an admin has called one server function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiUsage {
+    pub user_id: String,
+    pub user_name: String,
+    /// The server function's path, like `/api/users`.
+    pub path: String,
+    pub count: u64,
+    pub first_used_at: Timestamp,
+    pub last_used_at: Timestamp,
+}
+
+/// All of one admin's calls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminApiUsage {
+    pub user_id: String,
+    pub user_name: String,
+    pub total: u64,
+    pub last_used_at: Timestamp,
+    /// Most called first.
+    pub calls: Vec<ApiUsage>,
+}
+
+impl AdminApiUsage {
+    /// Group usage by admin, busiest first.
+    pub fn by_admin(usage: Vec<ApiUsage>) -> Vec<Self> {
+        let mut admins: Vec<Self> = Vec::new();
+        for call in usage {
+            match admins.iter_mut().find(|a| a.user_id == call.user_id) {
+                Some(admin) => {
+                    admin.total += call.count;
+                    admin.last_used_at = admin.last_used_at.max(call.last_used_at);
+                    admin.calls.push(call);
+                }
+                None => admins.push(Self {
+                    user_id: call.user_id.clone(),
+                    user_name: call.user_name.clone(),
+                    total: call.count,
+                    last_used_at: call.last_used_at,
+                    calls: vec![call],
+                }),
+            }
+        }
+        for admin in &mut admins {
+            admin.calls.sort_by(|a, b| b.count.cmp(&a.count));
+        }
+        admins.sort_by(|a, b| b.total.cmp(&a.total));
+        admins
+    }
+}
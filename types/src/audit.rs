@@ -0,0 +1,85 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The file format of an audit log export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl AuditFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Jsonl => "application/jsonl",
+        }
+    }
+}
+
+/// The activity stream between two times, ready to download.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditExport {
+    pub filename: String,
+    pub content: String,
+    /// Describes and signs `content`, if a signed export was asked for.
+    pub manifest: Option<AuditManifest>,
+}
+
+/// What an export holds, and a digest of it, signed so anyone we give both
+/// files to can later ask AuthIt! whether either has been changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditManifest {
+    pub filename: String,
+    pub format: AuditFormat,
+    pub from: Timestamp,
+    pub to: Timestamp,
+    pub records: usize,
+    /// The SHA-256 of the export, in hex.
+    pub sha256: String,
+    pub exported_at: Timestamp,
+    pub exported_by: String,
+    /// An HMAC-SHA256 over everything above, made with a key derived from
+    /// the signing secret.
+    pub signature: String,
+}
+
+/// The result of checking that the activity stream hasn't been tampered with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainStatus {
+    pub checked_at: Timestamp,
+    /// How many records are chained, and were checked.
+    pub records: usize,
+    /// How many of those were about deleted users, and so could only be
+    /// checked for their place in the chain.
+    pub redacted: usize,
+    /// How many records are from before the chain, and so can't be checked.
+    pub unchained: usize,
+    /// The first record found not to match, if any. Nothing after it was
+    /// checked.
+    pub broken_at: Option<ChainBreak>,
+}
+
+impl ChainStatus {
+    pub fn is_intact(&self) -> bool {
+        self.broken_at.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainBreak {
+    pub id: Uuid,
+    pub at: Timestamp,
+    pub reason: String,
+}
@@ -0,0 +1,30 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// A job the server runs on a schedule, like purging old data or delivering
+/// webhooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackgroundJob {
+    pub name: String,
+    /// How often it runs.
+    pub interval_secs: u64,
+    /// Its latest run on any replica, if it's run since this was added.
+    pub last_run: Option<BackgroundJobRun>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackgroundJobRun {
+    pub started_at: Timestamp,
+    /// `None` while it's running, or if the replica running it stopped first.
+    pub finished_at: Option<Timestamp>,
+    /// Why it failed, if it did.
+    pub error: Option<String>,
+    /// The admin who ran it by hand, if one did.
+    pub started_by: Option<String>,
+}
+
+impl BackgroundJobRun {
+    pub fn is_running(&self) -> bool {
+        self.finished_at.is_none()
+    }
+}
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A change to a user, staged so several can be reviewed and applied
+/// together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingChange {
+    AddToGroup {
+        user_id: Uuid,
+        user_name: String,
+        group_id: Uuid,
+        group_name: String,
+    },
+    RemoveFromGroup {
+        user_id: Uuid,
+        user_name: String,
+        group_id: Uuid,
+        group_name: String,
+    },
+    SetEmailAddresses {
+        user_id: Uuid,
+        user_name: String,
+        addresses: Vec<String>,
+    },
+    SetPhoneNumber {
+        user_id: Uuid,
+        user_name: String,
+        phone_number: Option<String>,
+    },
+    SetDisplayName {
+        user_id: Uuid,
+        user_name: String,
+        display_name: String,
+    },
+    SetLegalName {
+        user_id: Uuid,
+        user_name: String,
+        legal_name: Option<String>,
+    },
+}
+
+impl PendingChange {
+    pub fn user_id(&self) -> Uuid {
+        match self {
+            Self::AddToGroup { user_id, .. }
+            | Self::RemoveFromGroup { user_id, .. }
+            | Self::SetEmailAddresses { user_id, .. }
+            | Self::SetPhoneNumber { user_id, .. }
+            | Self::SetDisplayName { user_id, .. }
+            | Self::SetLegalName { user_id, .. } => *user_id,
+        }
+    }
+
+    pub fn user_name(&self) -> &str {
+        match self {
+            Self::AddToGroup { user_name, .. }
+            | Self::RemoveFromGroup { user_name, .. }
+            | Self::SetEmailAddresses { user_name, .. }
+            | Self::SetPhoneNumber { user_name, .. }
+            | Self::SetDisplayName { user_name, .. }
+            | Self::SetLegalName { user_name, .. } => user_name,
+        }
+    }
+
+    /// The group this adds the user to or removes them from, if any.
+    pub fn group_id(&self) -> Option<Uuid> {
+        match self {
+            Self::AddToGroup { group_id, .. } | Self::RemoveFromGroup { group_id, .. } => {
+                Some(*group_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// The name of the group this adds the user to or removes them from, if
+    /// any.
+    pub fn group_name(&self) -> Option<&str> {
+        match self {
+            Self::AddToGroup { group_name, .. } | Self::RemoveFromGroup { group_name, .. } => {
+                Some(group_name)
+            }
+            _ => None,
+        }
+    }
+
+    /// A short description of the change, for review.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::AddToGroup { group_name, .. } => format!("Add to {group_name}"),
+            Self::RemoveFromGroup { group_name, .. } => format!("Remove from {group_name}"),
+            Self::SetEmailAddresses { addresses, .. } if addresses.is_empty() => {
+                "Remove all email addresses".to_string()
+            }
+            Self::SetEmailAddresses { addresses, .. } => {
+                format!("Set email addresses to {}", addresses.join(", "))
+            }
+            Self::SetPhoneNumber {
+                phone_number: Some(p),
+                ..
+            } => format!("Set phone number to {p}"),
+            Self::SetPhoneNumber {
+                phone_number: None, ..
+            } => "Remove phone number".to_string(),
+            Self::SetDisplayName { display_name, .. } => {
+                format!("Set display name to {display_name}")
+            }
+            Self::SetLegalName {
+                legal_name: Some(n),
+                ..
+            } => format!("Set legal name to {n}"),
+            Self::SetLegalName {
+                legal_name: None, ..
+            } => "Remove legal name".to_string(),
+        }
+    }
+
+    /// Whether this and `other` change the same thing, so only one of them
+    /// should be kept.
+    fn overlaps(&self, other: &Self) -> bool {
+        if self.user_id() != other.user_id() {
+            return false;
+        }
+        match (self, other) {
+            (
+                Self::AddToGroup { group_id: a, .. } | Self::RemoveFromGroup { group_id: a, .. },
+                Self::AddToGroup { group_id: b, .. } | Self::RemoveFromGroup { group_id: b, .. },
+            ) => a == b,
+            (Self::SetEmailAddresses { .. }, Self::SetEmailAddresses { .. })
+            | (Self::SetPhoneNumber { .. }, Self::SetPhoneNumber { .. })
+            | (Self::SetDisplayName { .. }, Self::SetDisplayName { .. })
+            | (Self::SetLegalName { .. }, Self::SetLegalName { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Stage `change`, replacing any staged change to the same thing.
+///
+/// Toggling a group twice cancels out, leaving nothing staged for it.
+pub fn stage(changes: &mut Vec<PendingChange>, change: PendingChange) {
+    let cancels = changes.iter().any(|c| {
+        matches!(
+            (c, &change),
+            (
+                PendingChange::AddToGroup { .. },
+                PendingChange::RemoveFromGroup { .. }
+            ) | (
+                PendingChange::RemoveFromGroup { .. },
+                PendingChange::AddToGroup { .. }
+            )
+        ) && c.overlaps(&change)
+    });
+    changes.retain(|c| !c.overlaps(&change));
+    if !cancels {
+        changes.push(change);
+    }
+}
+
+/// Whether the staged changes add the user to the group (`Some(true)`),
+/// remove them from it (`Some(false)`), or leave it alone (`None`).
+pub fn staged_membership(changes: &[PendingChange], user_id: Uuid, group_id: Uuid) -> Option<bool> {
+    changes.iter().rev().find_map(|c| match c {
+        PendingChange::AddToGroup {
+            user_id: u,
+            group_id: g,
+            ..
+        } if *u == user_id && *g == group_id => Some(true),
+        PendingChange::RemoveFromGroup {
+            user_id: u,
+            group_id: g,
+            ..
+        } if *u == user_id && *g == group_id => Some(false),
+        _ => None,
+    })
+}
+
+/// What happened to one change in a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeOutcome {
+    Applied,
+    Failed(String),
+    /// It was applied, then undone because a later change failed.
+    RolledBack,
+    /// Undoing it after a later change failed also failed, so it's still in
+    /// effect.
+    RollbackFailed(String),
+    /// It wasn't attempted because an earlier change failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeResult {
+    pub change: PendingChange,
+    pub outcome: ChangeOutcome,
+}
+
+/// The outcome of applying a batch of changes. Either they all apply, or the
+/// ones that did are rolled back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub results: Vec<ChangeResult>,
+}
+
+impl BatchResult {
+    pub fn is_complete(&self) -> bool {
+        self.results
+            .iter()
+            .all(|r| r.outcome == ChangeOutcome::Applied)
+    }
+}
@@ -0,0 +1,53 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// The state of the break-glass local admin account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakGlassStatus {
+    /// Whether the break-glass login route is turned on in the config.
+    pub enabled: bool,
+    pub updated_at: Option<Timestamp>,
+    pub updated_by: Option<String>,
+    pub last_success: Option<Timestamp>,
+    pub recent_failures: u32,
+    /// When the password stops working, if passwords must be rotated.
+    pub expires_at: Option<Timestamp>,
+    pub policy: PasswordPolicy,
+}
+
+/// What a local password has to look like, from the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: u32,
+    /// The estimated number of guesses it'd take to crack, as bits.
+    pub min_entropy_bits: u32,
+    /// How often the password must be changed, or 0 for never.
+    pub rotation_days: u32,
+}
+
+impl PasswordPolicy {
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "At least {} characters and {} bits of estimated entropy.",
+            self.min_length, self.min_entropy_bits
+        );
+        if self.rotation_days > 0 {
+            text.push_str(&format!(
+                " Must be changed every {} days.",
+                self.rotation_days
+            ));
+        }
+        text
+    }
+}
+
+impl BreakGlassStatus {
+    /// Whether a password has been set.
+    pub fn is_configured(&self) -> bool {
+        self.updated_at.is_some()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|t| t <= Timestamp::now())
+    }
+}
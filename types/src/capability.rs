@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::directory::Access;
+
+/// Something AuthIt!'s Kanidm service token may or may not be allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    ReadPersons,
+    ManagePersons,
+    ManageGroups,
+    ResetCredentials,
+}
+
+impl Capability {
+    pub const ALL: [Self; 4] = [
+        Self::ReadPersons,
+        Self::ManagePersons,
+        Self::ManageGroups,
+        Self::ResetCredentials,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::ReadPersons => "List people",
+            Self::ManagePersons => "Create and delete people",
+            Self::ManageGroups => "Manage groups",
+            Self::ResetCredentials => "Issue credential resets",
+        }
+    }
+
+    /// The Kanidm group that gives a service account this capability.
+    pub fn granted_by(&self) -> &'static str {
+        match self {
+            Self::ReadPersons | Self::ManagePersons | Self::ResetCredentials => "idm_people_admins",
+            Self::ManageGroups => "idm_group_admins",
+        }
+    }
+}
+
+/// What AuthIt!'s Kanidm service token was found to be able to do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenCapabilities {
+    pub granted: BTreeSet<Capability>,
+}
+
+impl Default for TokenCapabilities {
+    /// Until they're checked, assume everything is allowed, and let Kanidm
+    /// say otherwise.
+    fn default() -> Self {
+        Self {
+            granted: Capability::ALL.into_iter().collect(),
+        }
+    }
+}
+
+impl TokenCapabilities {
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.granted.contains(&capability)
+    }
+
+    /// Why `capability` isn't available, for a tooltip, or `None` if it is.
+    pub fn why_not(&self, capability: Capability) -> Option<String> {
+        (!self.allows(capability)).then(|| {
+            format!(
+                "AuthIt!'s service account can't do this. Add it to {} in Kanidm.",
+                capability.granted_by()
+            )
+        })
+    }
+}
+
+/// What someone needs to use a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    Admin,
+    /// To be an admin, and for AuthIt!'s token to allow this.
+    Capability(Capability),
+    Directory,
+    GroupOwner,
+    Profile,
+}
+
+/// What the current user can do in AuthIt!: only what both their role and
+/// AuthIt!'s Kanidm token allow.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub access: Access,
+    pub token: TokenCapabilities,
+}
+
+impl Capabilities {
+    pub fn is_admin(&self) -> bool {
+        self.access.admin
+    }
+
+    /// Whether the user can do what needs `capability`. Only admins can use
+    /// the token.
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.access.admin && self.token.allows(capability)
+    }
+
+    /// Why the user can't do what needs `capability`, for a tooltip, or
+    /// `None` if they can.
+    pub fn why_not(&self, capability: Capability) -> Option<String> {
+        if !self.access.admin {
+            return Some("Only admins can do this.".to_string());
+        }
+        self.token.why_not(capability)
+    }
+
+    /// Whether the user can see the people directory.
+    pub fn directory(&self) -> bool {
+        self.access.directory && self.token.allows(Capability::ReadPersons)
+    }
+
+    /// Whether the user can see and edit people's legal names.
+    pub fn legal_names(&self) -> bool {
+        self.access.admin && self.access.legal_names
+    }
+
+    /// Whether the user can invite people to groups they own.
+    pub fn group_owner(&self) -> bool {
+        self.access.group_owner
+            && self.token.allows(Capability::ManagePersons)
+            && self.token.allows(Capability::ManageGroups)
+    }
+
+    /// Whether the user can change their own profile, or ask to.
+    pub fn profile(&self) -> bool {
+        self.access.profile
+    }
+
+    /// Whether admins can make provision links, as they aren't turned off.
+    pub fn provision_links(&self) -> bool {
+        self.access.admin && self.access.provision_links
+    }
+
+    /// Whether the user can use a page needing `requirement`.
+    pub fn meets(&self, requirement: Requirement) -> bool {
+        match requirement {
+            Requirement::Admin => self.is_admin(),
+            Requirement::Capability(capability) => self.allows(capability),
+            Requirement::Directory => self.directory(),
+            Requirement::GroupOwner => self.group_owner(),
+            Requirement::Profile => self.profile(),
+        }
+    }
+
+    /// Why the user can't use a page needing `requirement`, or `None` if
+    /// they can.
+    pub fn unmet(&self, requirement: Requirement) -> Option<String> {
+        if self.meets(requirement) {
+            return None;
+        }
+        let admin = "You need the admin role to use this page. An admin can add you to the admin \
+                     group in Kanidm.";
+        Some(match requirement {
+            Requirement::Admin => admin.to_string(),
+            Requirement::Capability(_) if !self.is_admin() => admin.to_string(),
+            Requirement::Capability(capability) => {
+                self.token.why_not(capability).unwrap_or_default()
+            }
+            Requirement::Directory if !self.access.directory => {
+                "The people directory isn't turned on.".to_string()
+            }
+            Requirement::Directory => self
+                .token
+                .why_not(Capability::ReadPersons)
+                .unwrap_or_default(),
+            Requirement::GroupOwner if self.access.group_owner => self
+                .token
+                .why_not(Capability::ManagePersons)
+                .or_else(|| self.token.why_not(Capability::ManageGroups))
+                .unwrap_or_default(),
+            Requirement::GroupOwner => "You need to own a group to use this page. An admin can \
+                                        make you an owner of one."
+                .to_string(),
+            Requirement::Profile => "Changing your own profile isn't turned on.".to_string(),
+        })
+    }
+
+    /// Everything the user can do.
+    pub fn granted(&self) -> BTreeSet<Capability> {
+        Capability::ALL
+            .into_iter()
+            .filter(|c| self.allows(*c))
+            .collect()
+    }
+}
@@ -0,0 +1,69 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::server_info::ServerInfo;
+
+/// The longest a capture can run for, so one left on by mistake stops on its
+/// own.
+pub const MAX_CAPTURE_MINUTES: u32 = 60;
+
+/// A time-limited recording of AuthIt!'s calls to Kanidm and its failed server
+/// functions, for attaching to bug reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capture {
+    pub started_at: Timestamp,
+    pub until: Timestamp,
+    pub started_by: String,
+}
+
+impl Capture {
+    pub fn is_active(&self) -> bool {
+        Timestamp::now() < self.until
+    }
+}
+
+/// The latest capture, if there's been one, and how much it recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureStatus {
+    pub capture: Option<Capture>,
+    pub events: u32,
+}
+
+/// Something recorded during a capture. Names, email addresses and UUIDs are
+/// replaced with placeholders, and tokens and request bodies are never
+/// recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureEvent {
+    /// A request AuthIt! sent to Kanidm.
+    Kanidm {
+        method: String,
+        path: String,
+        duration_ms: u64,
+        error: Option<String>,
+    },
+    /// A server function that failed after the user was signed in.
+    ServerFn {
+        path: String,
+        code: u16,
+        chain: Vec<String>,
+        backtrace: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedEvent {
+    pub at: Timestamp,
+    #[serde(flatten)]
+    pub event: CaptureEvent,
+}
+
+/// Everything a capture recorded, as downloaded for a bug report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureBundle {
+    pub build: ServerInfo,
+    pub identity_backend: String,
+    pub kanidm_replicas: u32,
+    pub capture: Option<Capture>,
+    pub events: Vec<CapturedEvent>,
+}
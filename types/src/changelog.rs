@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Release {
+    pub version: String,
+    pub changes: Vec<String>,
+}
+
+/// Releases an admin hasn't seen yet, newest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhatsNew {
+    /// The version running now.
+    pub version: String,
+    pub releases: Vec<Release>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A person as listed in the people directory, which any signed-in user can
+/// see. Only includes what the configured visibility rules allow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryEntry {
+    pub uuid: Uuid,
+    pub display_name: String,
+    #[serde(default)]
+    pub legal_name: Option<String>,
+    pub email_addresses: Vec<String>,
+    /// Group names, without the domain.
+    pub groups: Vec<String>,
+}
+
+/// What the current user is allowed to see in AuthIt!.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Access {
+    /// Whether the user is in the admin group, and so can manage users.
+    pub admin: bool,
+    /// Whether the people directory is enabled.
+    pub directory: bool,
+    /// Whether the user may see and edit people's legal names.
+    #[serde(default)]
+    pub legal_names: bool,
+    /// Whether the user owns any groups, and so can invite people to them.
+    #[serde(default)]
+    pub group_owner: bool,
+    /// Whether the user may change anything on their own profile.
+    #[serde(default)]
+    pub profile: bool,
+    /// Whether provision links are turned on.
+    #[serde(default)]
+    pub provision_links: bool,
+}
@@ -0,0 +1,43 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How many times an email is tried before it's given up on.
+pub const MAX_EMAIL_ATTEMPTS: u32 = 8;
+
+/// An email AuthIt! sent or is still trying to send. Its body isn't
+/// included, as it may hold links meant only for the recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedEmail {
+    pub id: Uuid,
+    pub recipient: String,
+    pub subject: String,
+    pub queued_at: Timestamp,
+    pub attempts: u32,
+    /// When it'll next be tried, unless it's been sent or given up on.
+    pub next_attempt_at: Option<Timestamp>,
+    pub sent_at: Option<Timestamp>,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+}
+
+impl QueuedEmail {
+    pub fn status(&self) -> EmailStatus {
+        if self.sent_at.is_some() {
+            EmailStatus::Sent
+        } else if self.attempts >= MAX_EMAIL_ATTEMPTS {
+            EmailStatus::Failed
+        } else {
+            EmailStatus::Pending
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailStatus {
+    Pending,
+    Sent,
+    /// Every attempt failed, and it won't be tried again unless requeued.
+    Failed,
+}
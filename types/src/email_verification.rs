@@ -0,0 +1,19 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// How many digits are in an emailed verification code.
+pub const CODE_LENGTH: usize = 6;
+
+/// One of a person's email addresses, and whether they've shown it's theirs
+/// by entering a code sent to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailAddressStatus {
+    pub address: String,
+    pub verified_at: Option<Timestamp>,
+}
+
+impl EmailAddressStatus {
+    pub fn is_verified(&self) -> bool {
+        self.verified_at.is_some()
+    }
+}
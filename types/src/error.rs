@@ -9,6 +9,90 @@ macro_rules! err {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Key set in a server error's details when the operation needs the user to
+/// have signed in again recently. Clients should send the user through the
+/// re-authentication flow and retry.
+pub const STEP_UP_REQUIRED: &str = "step_up_required";
+
+/// Key set in a server error's details when the operation was refused because
+/// it would lock the admin making it out of AuthIt!. Clients should explain
+/// why, rather than show it as a failure.
+pub const ADMIN_LOCKOUT: &str = "admin_lockout";
+
+/// Key set in a server error's details when the operation needs a ticket
+/// reference and the admin hasn't entered one. Clients should ask for it.
+pub const TICKET_REQUIRED: &str = "ticket_required";
+
+/// Key set in a server error's details when a field was too long. Its value
+/// has the field's name and the most characters it can have.
+pub const INPUT_TOO_LONG: &str = "input_too_long";
+
+/// Key set in a server error's details when some of a form's fields were
+/// invalid. Its value is a [`crate::validation::FieldErrors`], so clients can
+/// show each message next to its field.
+pub const FIELD_ERRORS: &str = "field_errors";
+
+/// Key set in a server error's details when a write was refused because the
+/// entry changed since the client read it. Clients should offer to reload.
+pub const EDIT_CONFLICT: &str = "edit_conflict";
+
+/// Key set in a server error's details when Kanidm was too busy to take the
+/// request. Its value is how many seconds to wait before trying again.
+pub const RETRY_AFTER: &str = "retry_after";
+
+/// Kanidm was too busy to take a request, and asked for it to be tried again
+/// later.
+#[derive(Debug)]
+pub struct RetryAfter {
+    pub seconds: u64,
+}
+
+impl fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Kanidm is busy, try again in {} seconds", self.seconds)
+    }
+}
+
+impl core::error::Error for RetryAfter {}
+
+/// Someone sent too many requests to a public page, and should wait before
+/// sending more.
+#[derive(Debug)]
+pub struct TooManyRequests {
+    pub seconds: u64,
+}
+
+impl fmt::Display for TooManyRequests {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "too many requests from your address; try again in {} minutes",
+            self.seconds.div_ceil(60)
+        )
+    }
+}
+
+impl core::error::Error for TooManyRequests {}
+
+/// Someone else changed an entry between when it was read and when it was
+/// to be written.
+#[derive(Debug)]
+pub struct EditConflict {
+    pub name: String,
+}
+
+impl fmt::Display for EditConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} was modified by someone else since you loaded it",
+            self.name
+        )
+    }
+}
+
+impl core::error::Error for EditConflict {}
+
 /// A simple wrapper around anyhow to provide richer errors to the client.
 ///
 /// It's probably not worth doing this way.
@@ -20,6 +104,11 @@ impl Error {
     pub fn new(err: impl Into<anyhow::Error>) -> Self {
         Self { inner: err.into() }
     }
+
+    /// The underlying error, if it's an `E`.
+    pub fn downcast_ref<E: fmt::Display + fmt::Debug + Send + Sync + 'static>(&self) -> Option<&E> {
+        self.inner.downcast_ref()
+    }
 }
 
 impl fmt::Debug for Error {
@@ -54,6 +143,10 @@ impl Error {
     /// Convert to a rich ServerFnError with full error chain and backtrace.
     /// Only use this for authenticated requests where exposing details is safe.
     pub fn into_rich_server_error(self) -> dioxus::server::ServerFnError {
+        if let Some(error) = self.invalid_input_error() {
+            return error;
+        }
+
         let mut chain: Vec<String> = Vec::new();
         chain.push(self.inner.to_string());
         let mut source = std::error::Error::source(&*self.inner);
@@ -75,14 +168,91 @@ impl Error {
             details: Some(serde_json::json!({
                 "chain": chain,
                 "backtrace": backtrace,
+                "build": crate::server_info::ServerInfo::current().describe(),
             })),
         }
     }
+
+    /// A 422 for errors caused by what the client sent, a 409 if it was
+    /// working from stale data, or a 429 or 503 if it should wait and try
+    /// again. These are safe to explain to anyone.
+    fn invalid_input_error(&self) -> Option<dioxus::server::ServerFnError> {
+        if let Some(throttled) = self.inner.downcast_ref::<TooManyRequests>() {
+            return Some(dioxus::server::ServerFnError::ServerError {
+                message: throttled.to_string(),
+                code: 429,
+                details: Some(serde_json::json!({ RETRY_AFTER: throttled.seconds })),
+            });
+        }
+        if let Some(retry) = self.inner.downcast_ref::<RetryAfter>() {
+            return Some(dioxus::server::ServerFnError::ServerError {
+                message: retry.to_string(),
+                code: 503,
+                details: Some(serde_json::json!({ RETRY_AFTER: retry.seconds })),
+            });
+        }
+        if let Some(conflict) = self.inner.downcast_ref::<EditConflict>() {
+            return Some(dioxus::server::ServerFnError::ServerError {
+                message: conflict.to_string(),
+                code: 409,
+                details: Some(serde_json::json!({ EDIT_CONFLICT: true })),
+            });
+        }
+        if let Some(errors) = self.inner.downcast_ref::<crate::validation::FieldErrors>() {
+            return Some(dioxus::server::ServerFnError::ServerError {
+                message: errors.to_string(),
+                code: 422,
+                details: Some(serde_json::json!({ FIELD_ERRORS: errors })),
+            });
+        }
+        let too_long = self.inner.downcast_ref::<crate::validation::TooLong>()?;
+        Some(dioxus::server::ServerFnError::ServerError {
+            message: too_long.to_string(),
+            code: 422,
+            details: Some(serde_json::json!({
+                INPUT_TOO_LONG: { "field": too_long.field, "max": too_long.max },
+            })),
+        })
+    }
+}
+
+/// The error returned when an operation requires a recent sign-in.
+#[cfg(feature = "server")]
+pub fn step_up_required_error() -> dioxus::server::ServerFnError {
+    dioxus::server::ServerFnError::ServerError {
+        message: "Please sign in again to continue".to_string(),
+        code: 403,
+        details: Some(serde_json::json!({ STEP_UP_REQUIRED: true })),
+    }
+}
+
+/// The error returned when an operation needs a ticket reference.
+#[cfg(feature = "server")]
+pub fn ticket_required_error() -> dioxus::server::ServerFnError {
+    dioxus::server::ServerFnError::ServerError {
+        message: "Enter the ticket this change is for first".to_string(),
+        code: 428,
+        details: Some(serde_json::json!({ TICKET_REQUIRED: true })),
+    }
+}
+
+/// The error returned when an admin tries to lock themselves out.
+#[cfg(feature = "server")]
+pub fn admin_lockout_error(message: impl Into<String>) -> dioxus::server::ServerFnError {
+    dioxus::server::ServerFnError::ServerError {
+        message: message.into(),
+        code: 409,
+        details: Some(serde_json::json!({ ADMIN_LOCKOUT: true })),
+    }
 }
 
 #[cfg(feature = "server")]
 impl From<Error> for dioxus::server::ServerFnError {
     fn from(value: Error) -> Self {
+        if let Some(error) = value.invalid_input_error() {
+            return error;
+        }
+
         // Default: return minimal error info for unauthenticated requests
         dioxus::server::ServerFnError::ServerError {
             message: value.inner.to_string(),
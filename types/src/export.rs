@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    account_request::AccountRequest, offboarding::Offboarding, onboarding::Onboarding,
+    preferences::UserPreferences, session::SessionInfo,
+};
+
+/// Everything AuthIt! and Kanidm hold about one person, for subject-access
+/// requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserExport {
+    pub user_id: Uuid,
+    pub exported_at: Timestamp,
+    pub exported_by: String,
+    /// Their Kanidm entry, as Kanidm returns it.
+    pub kanidm_attributes: BTreeMap<String, Vec<String>>,
+    pub phone_number: Option<String>,
+    /// Their avatar, as a base64 PNG.
+    pub avatar: Option<String>,
+    pub preferences: UserPreferences,
+    pub sessions: Vec<SessionInfo>,
+    pub onboardings: Vec<Onboarding>,
+    pub offboardings: Vec<Offboarding>,
+    /// Requests made from any of their email addresses.
+    pub account_requests: Vec<AccountRequest>,
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::kanidm::Group;
+
+/// What deleting a group would affect, for the admin to confirm first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupDeletionImpact {
+    pub group: Group,
+    /// How many direct members the group has.
+    pub member_count: u32,
+    /// Why AuthIt! still needs the group, if it does. Deleting it anyway
+    /// must be forced.
+    pub references: Vec<String>,
+    /// Why the group can't be deleted at all, if it can't.
+    pub blocked: Option<String>,
+}
+
+impl GroupDeletionImpact {
+    pub fn needs_force(&self) -> bool {
+        !self.references.is_empty()
+    }
+}
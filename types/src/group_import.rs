@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// What importing a group CSV does to one group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupImportChange {
+    pub group: String,
+    /// Whether the group doesn't exist yet, and will be created.
+    pub create: bool,
+    /// Usernames to add to the group.
+    pub add: Vec<String>,
+    /// Usernames to remove from the group, as the CSV doesn't list them.
+    pub remove: Vec<String>,
+}
+
+impl GroupImportChange {
+    pub fn is_empty(&self) -> bool {
+        !self.create && self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// What importing a group CSV will do. The import itself runs as a
+/// [`Job`](crate::job::Job).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupImport {
+    /// Only the groups that need changing.
+    pub changes: Vec<GroupImportChange>,
+    /// Usernames in the CSV that don't exist in Kanidm. They're skipped.
+    pub unknown_users: Vec<String>,
+}
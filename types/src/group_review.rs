@@ -0,0 +1,49 @@
+use jiff::{Timestamp, civil::Date};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// When a group should be looked at again or stop being used, so ad-hoc
+/// project groups don't outlive their projects. This is AuthIt!'s own
+/// record; Kanidm doesn't know about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupReview {
+    pub group_id: Uuid,
+    pub group_name: String,
+    /// When someone should check the group is still needed, and that its
+    /// members still belong in it.
+    pub review_by: Option<Date>,
+    /// When the group should no longer be needed.
+    pub expires_on: Option<Date>,
+    /// Refuse to add anyone to the group while it's overdue, until it's
+    /// reviewed or its dates are moved.
+    pub lock_when_overdue: bool,
+    pub reviewed_at: Option<Timestamp>,
+    pub reviewed_by: Option<String>,
+    /// When admins were told it's overdue, so they're only told once.
+    pub flagged_at: Option<Timestamp>,
+    pub updated_by: String,
+    pub updated_at: Timestamp,
+}
+
+impl GroupReview {
+    /// Why the group needs attention on `today`, if it does, like "expired
+    /// on 2026-03-01".
+    pub fn overdue(&self, today: Date) -> Option<String> {
+        if let Some(on) = self.expires_on
+            && on <= today
+        {
+            return Some(format!("expired on {on}"));
+        }
+        if let Some(by) = self.review_by
+            && by <= today
+        {
+            return Some(format!("was due for review by {by}"));
+        }
+        None
+    }
+
+    /// Why no one can be added to the group on `today`, if they can't.
+    pub fn locked(&self, today: Date) -> Option<String> {
+        self.overdue(today).filter(|_| self.lock_when_overdue)
+    }
+}
@@ -0,0 +1,84 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How many times a delivery is tried before it's given up on.
+pub const MAX_WEBHOOK_ATTEMPTS: u32 = 8;
+
+/// Tells a downstream system when people join or leave a group, so it can
+/// give them access there, like adding to `gitlab-users` creating a GitLab
+/// account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupWebhook {
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub url: String,
+    /// Signs each delivery. This is a secret: it's blank when sent to the
+    /// client, and saving a blank secret keeps the existing one.
+    pub secret: String,
+    pub on_add: bool,
+    pub on_remove: bool,
+    pub updated_by: String,
+    pub updated_at: Timestamp,
+}
+
+impl GroupWebhook {
+    /// Whether this webhook is called for `action`.
+    pub fn sends(&self, action: MembershipAction) -> bool {
+        match action {
+            MembershipAction::Added => self.on_add,
+            MembershipAction::Removed => self.on_remove,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipAction {
+    Added,
+    Removed,
+}
+
+impl MembershipAction {
+    pub const ALL: [Self; 2] = [Self::Added, Self::Removed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+impl std::str::FromStr for MembershipAction {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown membership action '{s}'"))
+    }
+}
+
+/// One call to a group's webhook, delivered or still being tried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub action: MembershipAction,
+    pub attempts: u32,
+    /// When it'll next be tried, unless it's been delivered or given up on.
+    pub next_attempt_at: Option<Timestamp>,
+    pub delivered_at: Option<Timestamp>,
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+}
+
+impl WebhookDelivery {
+    /// Whether it failed every attempt and won't be tried again.
+    pub fn gave_up(&self) -> bool {
+        self.delivered_at.is_none() && self.attempts >= MAX_WEBHOOK_ATTEMPTS
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The web UI's help text, kept as data rather than in the views so it can be
+/// translated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelpContent {
+    /// Explanations of concepts, by topic, like `provision_links`.
+    pub topics: BTreeMap<String, HelpTopic>,
+    /// The guided tour of the Users page, in order.
+    pub users_tour: Vec<TourStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelpTopic {
+    pub title: String,
+    /// Paragraphs, separated by blank lines.
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TourStep {
+    /// The id of the element the step points at. Steps whose element isn't
+    /// shown still explain it.
+    pub target: String,
+    pub title: String,
+    pub body: String,
+}
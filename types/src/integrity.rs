@@ -0,0 +1,72 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::kanidm::EntryKind;
+
+/// What the last integrity check found: people who could be mistaken for each
+/// other, which usually means an onboarding mistake or someone impersonating a
+/// colleague, and local records about users and groups that are gone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub checked_at: Timestamp,
+    pub findings: Vec<IntegrityFinding>,
+    #[serde(default)]
+    pub orphans: Vec<OrphanedEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityFinding {
+    pub issue: IntegrityIssue,
+    pub users: Vec<IntegrityUser>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityIssue {
+    /// They have the same email address.
+    SharedEmail { email: String },
+    /// Their display names look the same, though they may be spelled with
+    /// different characters, like a Latin "a" and a Cyrillic "а".
+    SimilarDisplayNames,
+}
+
+impl IntegrityIssue {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::SharedEmail { email } => format!("Share the email address {email}"),
+            Self::SimilarDisplayNames => "Have display names that look alike".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityUser {
+    pub uuid: Uuid,
+    pub name: String,
+    pub display_name: String,
+}
+
+/// A user or group that local records still refer to, though it's no longer
+/// in Kanidm, usually because it was deleted there directly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrphanedEntry {
+    pub kind: EntryKind,
+    pub id: Uuid,
+    /// Their name, if any of the records kept a copy of it.
+    pub name: Option<String>,
+    /// The kinds of record that refer to it, like "tags".
+    pub found_in: Vec<String>,
+}
+
+impl OrphanedEntry {
+    pub fn label(&self) -> String {
+        let kind = match self.kind {
+            EntryKind::Person => "User",
+            EntryKind::Group => "Group",
+        };
+        match &self.name {
+            Some(name) => format!("{kind} {name}"),
+            None => format!("{kind} {}", self.id),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+/// A bulk operation, run in the background so a big one doesn't time out a
+/// single request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: JobKind,
+    pub started_by: String,
+    pub created_at: Timestamp,
+    /// How many items the job has to get through.
+    pub total: u32,
+    /// How many items have been tried, whether or not they succeeded.
+    pub done: u32,
+    pub failed: u32,
+    pub finished_at: Option<Timestamp>,
+    /// Why the job as a whole failed, if it did.
+    pub error: Option<String>,
+}
+
+impl Job {
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    /// How far through the job is, from 0 to 100.
+    pub fn percent(&self) -> u32 {
+        if self.total == 0 {
+            if self.is_finished() { 100 } else { 0 }
+        } else {
+            self.done * 100 / self.total
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    GroupImport,
+    DeleteUsers,
+    ReconcileGroup,
+    ResetCredentials,
+}
+
+impl JobKind {
+    pub const ALL: [Self; 4] = [
+        Self::GroupImport,
+        Self::DeleteUsers,
+        Self::ReconcileGroup,
+        Self::ResetCredentials,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GroupImport => "group_import",
+            Self::DeleteUsers => "delete_users",
+            Self::ReconcileGroup => "reconcile_group",
+            Self::ResetCredentials => "reset_credentials",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::GroupImport => "Group import",
+            Self::DeleteUsers => "Delete users",
+            Self::ReconcileGroup => "Set group members",
+            Self::ResetCredentials => "Reset credentials",
+        }
+    }
+}
+
+impl std::str::FromStr for JobKind {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown job kind '{s}'"))
+    }
+}
+
+/// The result of one item in a job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobItem {
+    /// What the item did, like "Add alice to engineering".
+    pub label: String,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub output: Option<JobOutput>,
+}
+
+/// What a job item produced, for the report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobOutput {
+    ResetLink {
+        user_name: String,
+        url: Url,
+        expires_at: Timestamp,
+        /// Whether the link was emailed to the user.
+        emailed: bool,
+    },
+}
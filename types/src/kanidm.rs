@@ -1,7 +1,13 @@
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::err;
+use crate::{
+    err,
+    ids::{GroupId, GroupName, PersonId},
+    onboarding::OnboardingSignals,
+    validation::{FieldErrors, normalize_name},
+};
 
 #[derive(Deserialize)]
 pub struct RawPerson {
@@ -13,8 +19,16 @@ struct PersonAttrs {
     uuid: Vec<Uuid>,
     name: Vec<String>,
     displayname: Vec<String>,
+    #[serde(default)]
+    legalname: Vec<String>,
     mail: Vec<String>,
     memberof: Vec<String>,
+    #[serde(default)]
+    spn: Vec<String>,
+    #[serde(default)]
+    createdat: Vec<String>,
+    #[serde(default)]
+    last_modified_cid: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -26,6 +40,47 @@ pub struct RawGroup {
 struct GroupAttrs {
     uuid: Vec<Uuid>,
     name: Vec<String>,
+    #[serde(default)]
+    spn: Vec<String>,
+    #[serde(default)]
+    description: Vec<String>,
+}
+
+/// A security principal name, like `admins@idm.example.com`: an entry's name
+/// qualified by the Kanidm domain it's in. Kanidm refers to entries by these,
+/// like in `memberof` and in the groups it tells OAuth clients about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spn<'a> {
+    pub name: &'a str,
+    /// `None` for a bare name, which could be in any domain.
+    pub domain: Option<&'a str>,
+}
+
+impl<'a> Spn<'a> {
+    /// Parse either an SPN or a bare name. Names can't contain `@`, so
+    /// anything after it is the domain.
+    pub fn parse(value: &'a str) -> Self {
+        match value.split_once('@') {
+            Some((name, domain)) => Self {
+                name,
+                domain: Some(domain),
+            },
+            None => Self {
+                name: value,
+                domain: None,
+            },
+        }
+    }
+
+    /// Whether both could name the same entry. A bare name matches that name
+    /// in any domain; otherwise the domains must match too.
+    pub fn matches(&self, other: &Spn) -> bool {
+        self.name == other.name
+            && match (self.domain, other.domain) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                _ => true,
+            }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -33,21 +88,48 @@ pub struct Person {
     pub uuid: Uuid,
     pub name: String,
     pub display_name: String,
+    /// Their legal name, if Kanidm has one and the viewer may see it.
+    #[serde(default)]
+    pub legal_name: Option<String>,
+    /// In Kanidm's order. The first is the primary address.
     pub email_addresses: Vec<String>,
+    /// The SPNs of the groups they're in, like `admins@idm.example.com`.
     pub groups: Vec<String>,
+    /// Their name qualified by their domain, if Kanidm says.
+    #[serde(default)]
+    pub spn: Option<String>,
+    /// When Kanidm created the entry, if it says.
+    #[serde(default)]
+    pub created_at: Option<Timestamp>,
+    /// Kanidm's change id for the entry's last modification, if it says.
+    /// Writes send it back, so they're refused if someone else changed the
+    /// entry in the meantime.
+    #[serde(default)]
+    pub modified: Option<String>,
 }
 
-impl std::cmp::PartialOrd for Person {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl Person {
+    pub fn id(&self) -> PersonId {
+        PersonId(self.uuid)
+    }
+
+    /// The names of the groups the person is a member of, without the
+    /// domain.
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|g| Spn::parse(g).name)
     }
-}
 
-impl std::cmp::Ord for Person {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.display_name
-            .to_ascii_lowercase()
-            .cmp(&other.display_name.to_ascii_lowercase())
+    /// Check if the person is a member of the group.
+    pub fn is_member_of(&self, group: &Group) -> bool {
+        let group = group.spn();
+        self.groups.iter().any(|g| Spn::parse(g).matches(&group))
+    }
+
+    /// Check if the person is in a group given by name or SPN, like one from
+    /// the config.
+    pub fn is_in_group(&self, name_or_spn: &str) -> bool {
+        let group = Spn::parse(name_or_spn);
+        self.groups.iter().any(|g| Spn::parse(g).matches(&group))
     }
 }
 
@@ -72,8 +154,12 @@ impl TryFrom<RawPerson> for Person {
                 .into_iter()
                 .next()
                 .ok_or_else(|| err!("missing displayname for person"))?,
+            legal_name: attrs.legalname.into_iter().next(),
             email_addresses: attrs.mail,
             groups: attrs.memberof,
+            spn: attrs.spn.into_iter().next(),
+            created_at: attrs.createdat.first().and_then(|t| t.parse().ok()),
+            modified: attrs.last_modified_cid.into_iter().next(),
         })
     }
 }
@@ -82,19 +168,29 @@ impl TryFrom<RawPerson> for Person {
 pub struct Group {
     pub uuid: Uuid,
     pub name: String,
+    /// Its name qualified by its domain, if Kanidm says.
+    #[serde(default)]
+    pub spn: Option<String>,
+    /// What it's for, if whoever made it said.
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
-impl std::cmp::PartialOrd for Group {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl Group {
+    pub fn id(&self) -> GroupId {
+        GroupId(self.uuid)
     }
-}
 
-impl std::cmp::Ord for Group {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.name
-            .to_ascii_lowercase()
-            .cmp(&other.name.to_ascii_lowercase())
+    pub fn group_name(&self) -> GroupName {
+        GroupName::from(self.name.as_str())
+    }
+
+    /// The group's SPN, or just its name if Kanidm didn't give one.
+    pub fn spn(&self) -> Spn<'_> {
+        match &self.spn {
+            Some(spn) => Spn::parse(spn),
+            None => Spn::parse(&self.name),
+        }
     }
 }
 
@@ -114,6 +210,323 @@ impl TryFrom<RawGroup> for Group {
                 .into_iter()
                 .next()
                 .ok_or_else(|| err!("missing name for group"))?,
+            spn: attrs.spn.into_iter().next(),
+            description: attrs.description.into_iter().next(),
         })
     }
 }
+
+/// The kinds of Kanidm entry whose raw form can be looked at, for debugging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EntryKind {
+    Person,
+    Group,
+}
+
+/// Rules for which groups are Kanidm's built-ins, rather than ones admins
+/// made, and which are important enough to point out. Built-in groups are
+/// listed separately, and can't be granted by provision links or imported.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GroupClassification {
+    /// Groups whose names start with any of these are built-in.
+    pub builtin_prefixes: Vec<String>,
+    /// Groups that are built-in whatever their name.
+    pub builtin_groups: Vec<String>,
+    /// Groups that are never built-in, overriding the prefixes.
+    pub not_builtin_groups: Vec<String>,
+    /// Groups to flag in the user list, like the admin group, so it's clear
+    /// at a glance who has them.
+    #[serde(default)]
+    pub key_groups: Vec<String>,
+}
+
+impl Default for GroupClassification {
+    fn default() -> Self {
+        Self {
+            builtin_prefixes: ["idm_", "system_", "builtin_"].map(String::from).to_vec(),
+            builtin_groups: Vec::new(),
+            not_builtin_groups: Vec::new(),
+            key_groups: Vec::new(),
+        }
+    }
+}
+
+impl GroupClassification {
+    pub fn is_builtin(&self, name: &str) -> bool {
+        if self.not_builtin_groups.iter().any(|g| g == name) {
+            return false;
+        }
+        self.builtin_groups.iter().any(|g| g == name)
+            || self.builtin_prefixes.iter().any(|p| name.starts_with(p))
+    }
+}
+
+/// A Kanidm server AuthIt! talks to, and whether it's being used.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KanidmNode {
+    pub url: String,
+    /// Whether this is the primary server, which all writes go to.
+    pub primary: bool,
+    pub healthy: bool,
+    pub serving_reads: bool,
+    /// When the server was last health-checked. Never, if there are no
+    /// replicas to fail over to.
+    pub checked_at: Option<jiff::Timestamp>,
+    /// Why the last health check failed.
+    pub error: Option<String>,
+}
+
+/// How many requests AuthIt! has open to Kanidm, and how many are waiting
+/// for a free slot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KanidmLoad {
+    pub max_concurrency: u32,
+    pub in_flight: u32,
+    pub queued: u32,
+}
+
+/// What to set up on a person as they're created. If any of it fails, they
+/// aren't created.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NewPersonSetup {
+    pub group_ids: Vec<Uuid>,
+    pub legal_name: Option<String>,
+    /// `None` leaves POSIX off.
+    pub posix: Option<PosixSetup>,
+}
+
+impl NewPersonSetup {
+    /// Tidy it up, noting anything wrong with it in `errors`.
+    pub fn normalize(&mut self, errors: &mut FieldErrors) {
+        self.legal_name = match self.legal_name.take() {
+            Some(name) if !name.trim().is_empty() => match normalize_name("legal name", &name) {
+                Ok(name) => Some(name),
+                Err(e) => {
+                    errors.add("legal_name", e.to_string());
+                    None
+                }
+            },
+            _ => None,
+        };
+        if let Some(posix) = &mut self.posix {
+            posix.login_shell = posix
+                .login_shell
+                .take()
+                .map(|shell| shell.trim().to_string())
+                .filter(|shell| !shell.is_empty());
+            if posix
+                .login_shell
+                .as_ref()
+                .is_some_and(|shell| !shell.starts_with('/'))
+            {
+                errors.add(
+                    "login_shell",
+                    "the login shell must be a full path, like /bin/bash",
+                );
+            }
+        }
+    }
+}
+
+/// POSIX attributes for a new person, so they can sign in to Unix hosts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PosixSetup {
+    /// Kanidm picks one if it's `None`.
+    pub gid_number: Option<u32>,
+    pub login_shell: Option<String>,
+}
+
+/// The result of setting a person's group memberships in one go.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GroupMembershipChanges {
+    pub added: Vec<Uuid>,
+    pub removed: Vec<Uuid>,
+    pub failed: Vec<GroupChangeFailure>,
+}
+
+impl GroupMembershipChanges {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GroupChangeFailure {
+    pub group_id: Uuid,
+    pub group_name: String,
+    /// Whether we were trying to add the person to the group, rather than
+    /// remove them.
+    pub add: bool,
+    pub error: String,
+}
+
+#[derive(Deserialize)]
+pub struct RawDomain {
+    attrs: DomainAttrs,
+}
+
+#[derive(Deserialize)]
+struct DomainAttrs {
+    domain_uuid: Vec<Uuid>,
+    domain_name: Vec<String>,
+    #[serde(default)]
+    domain_display_name: Vec<String>,
+    #[serde(default)]
+    version: Vec<String>,
+    #[serde(default)]
+    domain_ldap_basedn: Vec<String>,
+}
+
+/// Identifying information about the Kanidm instance we're talking to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DomainInfo {
+    pub uuid: Uuid,
+    pub name: String,
+    pub display_name: String,
+    pub version: Option<String>,
+    /// The base DN of Kanidm's LDAP interface.
+    pub ldap_basedn: String,
+}
+
+impl TryFrom<RawDomain> for DomainInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: RawDomain) -> Result<Self, Self::Error> {
+        let attrs = value.attrs;
+        let name = attrs
+            .domain_name
+            .into_iter()
+            .next()
+            .ok_or_else(|| err!("missing domain_name for domain"))?;
+        Ok(Self {
+            uuid: attrs
+                .domain_uuid
+                .into_iter()
+                .next()
+                .ok_or_else(|| err!("missing domain_uuid for domain"))?,
+            // Kanidm falls back to the domain name when no display name is set.
+            display_name: attrs
+                .domain_display_name
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| name.clone()),
+            // Kanidm derives the base DN from the domain name unless it's
+            // been set explicitly.
+            ldap_basedn: attrs
+                .domain_ldap_basedn
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| {
+                    name.split('.')
+                        .map(|dc| format!("dc={dc}"))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+            name,
+            version: attrs.version.into_iter().next(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawLdapEntry {
+    attrs: LdapAttrs,
+}
+
+#[derive(Deserialize)]
+struct LdapAttrs {
+    #[serde(default)]
+    class: Vec<String>,
+    #[serde(default)]
+    spn: Vec<String>,
+    #[serde(default)]
+    gidnumber: Vec<String>,
+    #[serde(default)]
+    loginshell: Vec<String>,
+    #[serde(default)]
+    mail: Vec<String>,
+}
+
+/// How a person appears through Kanidm's read-only LDAP interface.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LdapStatus {
+    pub dn: String,
+    pub gid_number: Option<String>,
+    pub login_shell: Option<String>,
+    /// Attributes that LDAP clients commonly need but this person is missing.
+    pub missing: Vec<String>,
+}
+
+impl LdapStatus {
+    pub fn new(entry: RawLdapEntry, basedn: &str) -> Result<Self, crate::Error> {
+        let attrs = entry.attrs;
+        let spn = attrs
+            .spn
+            .into_iter()
+            .next()
+            .ok_or_else(|| err!("missing spn for person"))?;
+        let gid_number = attrs.gidnumber.into_iter().next();
+        let login_shell = attrs.loginshell.into_iter().next();
+
+        let mut missing = Vec::new();
+        if !attrs.class.iter().any(|c| c == "posixaccount") {
+            missing.push("posixaccount class (enable POSIX for this person)".to_string());
+        }
+        if gid_number.is_none() {
+            missing.push("gidnumber".to_string());
+        }
+        if login_shell.is_none() {
+            missing.push("loginshell".to_string());
+        }
+        if attrs.mail.is_empty() {
+            missing.push("mail".to_string());
+        }
+
+        Ok(Self {
+            dn: format!("spn={spn},{basedn}"),
+            gid_number,
+            login_shell,
+            missing,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RawOnboardingEntry {
+    attrs: OnboardingAttrs,
+}
+
+#[derive(Deserialize)]
+struct OnboardingAttrs {
+    #[serde(default)]
+    memberof: Vec<String>,
+    #[serde(default)]
+    passkeys: Vec<serde_json::Value>,
+    #[serde(default)]
+    user_auth_token_session: Vec<serde_json::Value>,
+    #[serde(default)]
+    oauth2_session: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct RawCredentialStatus {
+    #[serde(default)]
+    creds: Vec<serde_json::Value>,
+}
+
+impl RawOnboardingEntry {
+    pub fn into_signals(self, credentials: RawCredentialStatus) -> OnboardingSignals {
+        let attrs = self.attrs;
+        OnboardingSignals {
+            has_credentials: !credentials.creds.is_empty() || !attrs.passkeys.is_empty(),
+            groups: attrs
+                .memberof
+                .into_iter()
+                .map(|g| Spn::parse(&g).name.to_string())
+                .collect(),
+            has_signed_in: !attrs.user_auth_token_session.is_empty()
+                || !attrs.oauth2_session.is_empty(),
+        }
+    }
+}
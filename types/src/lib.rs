@@ -1,9 +1,61 @@
+pub mod account_request;
+pub mod activity;
+pub mod announcement;
+pub mod api_usage;
+pub mod audit;
+pub mod background_job;
+pub mod batch;
+pub mod break_glass;
+pub mod capability;
+pub mod capture;
+pub mod changelog;
+pub mod directory;
+pub mod email_queue;
+pub mod email_verification;
 mod error;
+pub mod export;
+pub mod group_deletion;
+pub mod group_import;
+pub mod group_review;
+pub mod group_webhook;
+pub mod help;
+pub mod ids;
+pub mod integrity;
+pub mod job;
 pub mod kanidm;
+pub mod login_event;
+pub mod maintenance;
+pub mod migration;
+pub mod notification;
+pub mod offboarding;
+pub mod onboarding;
+pub mod preferences;
+pub mod profile_change;
 pub mod provision;
+pub mod report;
 mod reset_link;
+pub mod retention;
+pub mod rotation;
+pub mod scheduled_membership;
+pub mod server_info;
+pub mod session;
+pub mod settings_export;
+pub mod setup;
+pub mod shift_note;
+pub mod stats;
+pub mod tag;
+pub mod ticket;
+pub mod user_deletion;
+pub mod user_detail;
+pub mod validation;
+pub mod visibility;
 
-pub use error::{Error, Result};
+pub use error::{
+    ADMIN_LOCKOUT, EDIT_CONFLICT, EditConflict, Error, FIELD_ERRORS, INPUT_TOO_LONG, RETRY_AFTER,
+    Result, RetryAfter, STEP_UP_REQUIRED, TICKET_REQUIRED, TooManyRequests,
+};
+#[cfg(feature = "server")]
+pub use error::{admin_lockout_error, step_up_required_error, ticket_required_error};
 pub use reset_link::ResetLink;
 
 // FIXME: We can do this better I think.
@@ -0,0 +1,79 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Why a sign-in to AuthIt! failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFailure {
+    /// The login took too long, was already used, or AuthIt! restarted.
+    StateExpired,
+    /// Kanidm refused the login, or wouldn't exchange the code.
+    ProviderRejected,
+    /// Kanidm's clock and ours disagree, so its tokens look invalid.
+    ClockSkew,
+    Internal,
+}
+
+impl LoginFailure {
+    pub const ALL: [Self; 4] = [
+        Self::StateExpired,
+        Self::ProviderRejected,
+        Self::ClockSkew,
+        Self::Internal,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::StateExpired => "state_expired",
+            Self::ProviderRejected => "provider_rejected",
+            Self::ClockSkew => "clock_skew",
+            Self::Internal => "internal",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::StateExpired => "Expired or reused",
+            Self::ProviderRejected => "Rejected by Kanidm",
+            Self::ClockSkew => "Clock skew",
+            Self::Internal => "Internal error",
+        }
+    }
+}
+
+impl std::str::FromStr for LoginFailure {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|failure| failure.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown login failure '{s}'"))
+    }
+}
+
+/// An attempt to sign in to AuthIt!.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginEvent {
+    pub id: Uuid,
+    pub at: Timestamp,
+    /// Who signed in. Failed sign-ins usually don't get far enough to know,
+    /// unless they were re-authenticating.
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    /// `None` if it worked.
+    pub failure: Option<LoginFailure>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    /// The browser and OS, like "Firefox on Linux".
+    pub device: Option<String>,
+    /// Roughly where it came from, if a GeoIP database is configured.
+    pub location: Option<String>,
+}
+
+impl LoginEvent {
+    pub fn succeeded(&self) -> bool {
+        self.failure.is_none()
+    }
+}
@@ -0,0 +1,10 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// While set, only admins can use AuthIt!. Everyone else sees `message`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Maintenance {
+    pub message: String,
+    pub started_at: Timestamp,
+    pub started_by: String,
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+/// Where one database migration stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MigrationState {
+    Applied,
+    /// In this build, but not applied to the database yet.
+    Pending,
+    /// Applied, but the migration has changed since, so the database may not
+    /// match what this build expects.
+    ChecksumMismatch,
+    /// Started, but didn't finish.
+    Failed,
+    /// Applied to the database by a newer version of AuthIt!, but only adds
+    /// to the schema, so this build can still run. This is expected during a
+    /// rolling deploy.
+    Unknown,
+    /// A contract migration applied by a newer version of AuthIt!. It may
+    /// have removed things this build uses, so this build can't run.
+    Incompatible,
+}
+
+impl MigrationState {
+    /// Whether AuthIt! refuses to start with a migration in this state.
+    pub fn is_problem(self) -> bool {
+        matches!(
+            self,
+            Self::ChecksumMismatch | Self::Failed | Self::Incompatible
+        )
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Applied => "Applied",
+            Self::Pending => "Pending",
+            Self::ChecksumMismatch => "Checksum mismatch",
+            Self::Failed => "Failed",
+            Self::Unknown => "From a newer version",
+            Self::Incompatible => "Needs a newer version",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Every migration this build has, or that the database has applied, oldest
+/// first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub migrations: Vec<MigrationInfo>,
+}
+
+impl MigrationStatus {
+    pub fn problems(&self) -> impl Iterator<Item = &MigrationInfo> {
+        self.migrations.iter().filter(|m| m.state.is_problem())
+    }
+
+    pub fn count(&self, state: MigrationState) -> usize {
+        self.migrations.iter().filter(|m| m.state == state).count()
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Events that can be sent to chat notifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    UserCreated,
+    UserDeleted,
+    UserProvisioned,
+    GroupMembershipChanged,
+    AccountRequested,
+    BreakGlassLogin,
+    ProvisionLinkReminder,
+    ResetLinkUsed,
+    ProvisionQuota,
+    RotationDeadline,
+    AuditLogExported,
+    GroupManagerChanged,
+    ProfileChangeRequested,
+    ProfileChanged,
+    ProvisionApprovalRequested,
+    ScheduledChangeFailed,
+    GroupReviewDue,
+    KanidmSignIn,
+    KanidmCredentialsChanged,
+}
+
+impl NotificationEvent {
+    pub const ALL: [Self; 19] = [
+        Self::UserCreated,
+        Self::UserDeleted,
+        Self::UserProvisioned,
+        Self::GroupMembershipChanged,
+        Self::AccountRequested,
+        Self::BreakGlassLogin,
+        Self::ProvisionLinkReminder,
+        Self::ResetLinkUsed,
+        Self::ProvisionQuota,
+        Self::RotationDeadline,
+        Self::AuditLogExported,
+        Self::GroupManagerChanged,
+        Self::ProfileChangeRequested,
+        Self::ProfileChanged,
+        Self::ProvisionApprovalRequested,
+        Self::ScheduledChangeFailed,
+        Self::GroupReviewDue,
+        Self::KanidmSignIn,
+        Self::KanidmCredentialsChanged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UserCreated => "user_created",
+            Self::UserDeleted => "user_deleted",
+            Self::UserProvisioned => "user_provisioned",
+            Self::GroupMembershipChanged => "group_membership_changed",
+            Self::AccountRequested => "account_requested",
+            Self::BreakGlassLogin => "break_glass_login",
+            Self::ProvisionLinkReminder => "provision_link_reminder",
+            Self::ResetLinkUsed => "reset_link_used",
+            Self::ProvisionQuota => "provision_quota",
+            Self::RotationDeadline => "rotation_deadline",
+            Self::AuditLogExported => "audit_log_exported",
+            Self::GroupManagerChanged => "group_manager_changed",
+            Self::ProfileChangeRequested => "profile_change_requested",
+            Self::ProfileChanged => "profile_changed",
+            Self::ProvisionApprovalRequested => "provision_approval_requested",
+            Self::ScheduledChangeFailed => "scheduled_change_failed",
+            Self::GroupReviewDue => "group_review_due",
+            Self::KanidmSignIn => "kanidm_sign_in",
+            Self::KanidmCredentialsChanged => "kanidm_credentials_changed",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::UserCreated => "User created",
+            Self::UserDeleted => "User deleted",
+            Self::UserProvisioned => "Account created from provision link",
+            Self::GroupMembershipChanged => "Group membership changed",
+            Self::AccountRequested => "Account requested",
+            Self::BreakGlassLogin => "Break-glass login",
+            Self::ProvisionLinkReminder => "Provision link expiring or used up",
+            Self::ResetLinkUsed => "Credential reset link used",
+            Self::ProvisionQuota => "Provisioning quota nearly or fully used",
+            Self::RotationDeadline => "Credential rotation campaign ended",
+            Self::AuditLogExported => "Audit log exported",
+            Self::GroupManagerChanged => "Who manages a group in Kanidm changed",
+            Self::ProfileChangeRequested => "Someone asked to change their profile",
+            Self::ProfileChanged => "Someone's own profile change was made",
+            Self::ProvisionApprovalRequested => {
+                "An account from a provision link is waiting for approval"
+            }
+            Self::ScheduledChangeFailed => "A scheduled group membership change failed",
+            Self::GroupReviewDue => "A group is due for review or has expired",
+            Self::KanidmSignIn => "Someone signed in to Kanidm",
+            Self::KanidmCredentialsChanged => "Someone's credentials changed in Kanidm",
+        }
+    }
+}
+
+impl std::str::FromStr for NotificationEvent {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown notification event '{s}'"))
+    }
+}
+
+/// Where to send notifications, and which events each destination gets.
+///
+/// Secrets are blanked when sent to the client. Saving a blank secret keeps
+/// the existing one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub slack: Option<SlackNotifier>,
+    pub matrix: Option<MatrixNotifier>,
+    /// Finer-grained routing, on top of the events each notifier gets.
+    pub rules: Vec<NotificationRule>,
+}
+
+/// Send an event to a channel when it matches, like membership changes to
+/// groups matching `prod-*` going to security@example.com.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationRule {
+    pub event: NotificationEvent,
+    /// Only events involving a group whose name matches this, where `*`
+    /// matches anything. Blank matches every event.
+    #[serde(default)]
+    pub group: String,
+    /// Only events about a user whose name matches this. Blank matches every
+    /// event.
+    #[serde(default)]
+    pub user: String,
+    pub channel: NotificationChannel,
+}
+
+impl NotificationRule {
+    /// Whether an event about `user`, involving `groups`, should go to this
+    /// rule's channel.
+    pub fn matches(&self, event: NotificationEvent, user: Option<&str>, groups: &[String]) -> bool {
+        self.event == event
+            && (self.user.is_empty() || user.is_some_and(|u| matches_pattern(&self.user, u)))
+            && (self.group.is_empty() || groups.iter().any(|g| matches_pattern(&self.group, g)))
+    }
+
+    /// e.g. "Group membership changed, groups matching prod-* → security@example.com".
+    pub fn describe(&self) -> String {
+        let mut text = self.event.label().to_string();
+        if !self.group.is_empty() {
+            text.push_str(&format!(", groups matching {}", self.group));
+        }
+        if !self.user.is_empty() {
+            text.push_str(&format!(", users matching {}", self.user));
+        }
+        format!("{text} → {}", self.channel.describe())
+    }
+}
+
+/// Where a [`NotificationRule`] sends what it matches.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Email {
+        to: String,
+    },
+    /// The configured Slack notifier.
+    Slack,
+    /// The configured Matrix notifier.
+    Matrix,
+}
+
+impl NotificationChannel {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Email { to } => to.clone(),
+            Self::Slack => "Slack".to_string(),
+            Self::Matrix => "Matrix".to_string(),
+        }
+    }
+}
+
+/// Whether `value` matches `pattern`, ignoring case, where `*` in the pattern
+/// matches any run of characters.
+pub fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let value = value.to_lowercase();
+    let mut parts = pattern.split('*');
+
+    // There's always a first part, even if it's empty.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`, so the whole value must match.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Posts to a Slack incoming webhook.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlackNotifier {
+    /// The incoming webhook URL. This is a secret.
+    pub webhook_url: String,
+    pub events: BTreeSet<NotificationEvent>,
+}
+
+/// Posts to a Matrix room as a bot user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatrixNotifier {
+    /// e.g. `https://matrix.example.com`.
+    pub homeserver: String,
+    /// The room id, like `!abc123:example.com`. The bot must already be in
+    /// the room.
+    pub room_id: String,
+    /// The bot's access token. This is a secret.
+    pub access_token: String,
+    pub events: BTreeSet<NotificationEvent>,
+}
+
+impl NotificationSettings {
+    /// Remove secrets, so settings can be sent to the client.
+    pub fn redacted(mut self) -> Self {
+        if let Some(slack) = &mut self.slack {
+            slack.webhook_url.clear();
+        }
+        if let Some(matrix) = &mut self.matrix {
+            matrix.access_token.clear();
+        }
+        self
+    }
+
+    /// Fill in any blank secrets from `existing`.
+    pub fn keep_secrets_from(mut self, existing: &Self) -> Self {
+        if let (Some(new), Some(old)) = (&mut self.slack, &existing.slack)
+            && new.webhook_url.is_empty()
+        {
+            new.webhook_url = old.webhook_url.clone();
+        }
+        if let (Some(new), Some(old)) = (&mut self.matrix, &existing.matrix)
+            && new.access_token.is_empty()
+        {
+            new.access_token = old.access_token.clone();
+        }
+        self
+    }
+}
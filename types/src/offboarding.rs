@@ -0,0 +1,71 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A checklist for removing someone's access.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Offboarding {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub started_by: String,
+    pub started_at: Timestamp,
+    pub steps: Vec<ChecklistStep>,
+    /// When the Kanidm account is scheduled to be deleted.
+    pub delete_at: Option<Timestamp>,
+    pub deleted_at: Option<Timestamp>,
+    /// Set once every step is done.
+    pub completed_at: Option<Timestamp>,
+}
+
+impl Offboarding {
+    pub fn steps_done(&self) -> usize {
+        self.steps.iter().filter(|s| s.is_done()).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed_at.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecklistStep {
+    pub label: String,
+    pub done_by: Option<String>,
+    pub done_at: Option<Timestamp>,
+}
+
+impl ChecklistStep {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            done_by: None,
+            done_at: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done_at.is_some()
+    }
+}
+
+/// The steps new offboarding checklists start with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecklistTemplate {
+    pub steps: Vec<String>,
+}
+
+impl ChecklistTemplate {
+    pub fn offboarding_default() -> Self {
+        Self {
+            steps: [
+                "Disable account",
+                "Remove from groups",
+                "Rotate shared secrets they had access to",
+                "Schedule deletion",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
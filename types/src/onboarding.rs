@@ -0,0 +1,50 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A new user's progress after creating their account from a provision link.
+///
+/// Each step records when we first noticed it was done.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Onboarding {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub started_at: Timestamp,
+    /// The groups the provision link added them to.
+    pub required_groups: Vec<String>,
+    pub credentials_set_at: Option<Timestamp>,
+    pub groups_added_at: Option<Timestamp>,
+    /// When we first saw a Kanidm or OAuth2 session for them.
+    pub first_login_at: Option<Timestamp>,
+}
+
+impl Onboarding {
+    pub fn is_complete(&self) -> bool {
+        self.credentials_set_at.is_some()
+            && self.groups_added_at.is_some()
+            && self.first_login_at.is_some()
+    }
+
+    /// Descriptions of the steps that aren't done yet.
+    pub fn pending_steps(&self) -> Vec<&'static str> {
+        [
+            (self.credentials_set_at, "Set up credentials"),
+            (self.groups_added_at, "Added to required groups"),
+            (self.first_login_at, "First sign-in"),
+        ]
+        .into_iter()
+        .filter(|(at, _)| at.is_none())
+        .map(|(_, step)| step)
+        .collect()
+    }
+}
+
+/// What Kanidm tells us about a new user's progress.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnboardingSignals {
+    pub has_credentials: bool,
+    /// Group names, like `memberof` but without the `@domain` suffix.
+    pub groups: Vec<String>,
+    pub has_signed_in: bool,
+}
@@ -0,0 +1,246 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::kanidm::{Group, Person};
+
+/// Per-admin UI preferences, stored server-side so they follow the admin
+/// across browsers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserPreferences {
+    pub theme: Theme,
+    /// Columns of the user list to hide.
+    pub hidden_columns: Vec<String>,
+    /// Groups listed first in group pickers.
+    pub favorite_groups: Vec<Uuid>,
+    pub provision_defaults: ProvisionDefaults,
+    /// Named filters, shown as tabs above the user list.
+    pub saved_views: Vec<SavedView>,
+    /// Mask emails, ids and tokens, for screen-sharing and screenshots.
+    pub mask_data: bool,
+    /// Guided tours already taken or turned down, like `users`.
+    pub seen_tours: Vec<String>,
+    /// Users in the sidebar. These are kept by the server as users are
+    /// viewed or pinned, not saved with the rest.
+    pub shortcuts: UserShortcuts,
+}
+
+impl UserPreferences {
+    pub fn hides_column(&self, column: &str) -> bool {
+        self.hidden_columns.iter().any(|c| c == column)
+    }
+
+    pub fn set_column_hidden(&mut self, column: &str, hidden: bool) {
+        self.hidden_columns.retain(|c| c != column);
+        if hidden {
+            self.hidden_columns.push(column.to_string());
+        }
+    }
+
+    pub fn set_favorite_group(&mut self, group_id: Uuid, favorite: bool) {
+        self.favorite_groups.retain(|g| *g != group_id);
+        if favorite {
+            self.favorite_groups.push(group_id);
+        }
+    }
+}
+
+/// A link to a user's page, with enough to label it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserShortcut {
+    pub user_id: Uuid,
+    pub display_name: String,
+}
+
+/// Users an admin pinned, or looked at recently.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserShortcuts {
+    pub pinned: Vec<UserShortcut>,
+    /// Most recent first.
+    pub recent: Vec<UserShortcut>,
+}
+
+impl UserShortcuts {
+    const MAX_RECENT: usize = 8;
+
+    /// Note that a user was viewed, moving them to the front of the recent
+    /// list.
+    pub fn viewed(&mut self, user: UserShortcut) {
+        self.recent.retain(|u| u.user_id != user.user_id);
+        self.recent.insert(0, user);
+        self.recent.truncate(Self::MAX_RECENT);
+    }
+
+    pub fn set_pinned(&mut self, user: UserShortcut, pinned: bool) {
+        self.pinned.retain(|u| u.user_id != user.user_id);
+        if pinned {
+            self.pinned.push(user);
+        }
+    }
+
+    pub fn is_pinned(&self, user_id: Uuid) -> bool {
+        self.pinned.iter().any(|u| u.user_id == user_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    /// The name it's saved under, also used as the layout's `data-theme`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::System => "Match system",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Theme::ALL.into_iter().find(|t| t.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedView {
+    pub name: String,
+    pub filter: UserFilter,
+}
+
+/// Which users to show in the user list. Every condition set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserFilter {
+    /// Users with any of these tags.
+    pub tags: Vec<Uuid>,
+    /// Users in any of these groups.
+    pub groups: Vec<Uuid>,
+    /// Whether users have set up credentials.
+    pub has_credentials: Option<bool>,
+    pub created_after: Option<Timestamp>,
+    pub created_before: Option<Timestamp>,
+}
+
+impl UserFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether `person` matches. `person_tags` are their tag ids, and
+    /// `has_credentials` is `None` if we don't know yet, which matches.
+    pub fn matches(
+        &self,
+        person: &Person,
+        person_tags: &[Uuid],
+        groups: &[Group],
+        has_credentials: Option<bool>,
+    ) -> bool {
+        let tags = self.tags.is_empty() || self.tags.iter().any(|t| person_tags.contains(t));
+        let groups = self.groups.is_empty()
+            || groups
+                .iter()
+                .any(|g| self.groups.contains(&g.uuid) && person.is_member_of(g));
+        let credentials = match (self.has_credentials, has_credentials) {
+            (Some(wanted), Some(has)) => wanted == has,
+            _ => true,
+        };
+        // People whose creation time we don't know only match without a range.
+        let created = match (self.created_after, self.created_before, person.created_at) {
+            (None, None, _) => true,
+            (_, _, None) => false,
+            (after, before, Some(at)) => {
+                after.is_none_or(|after| at >= after) && before.is_none_or(|before| at < before)
+            }
+        };
+        tags && groups && credentials && created
+    }
+
+    /// The filter as URL query pairs, so a filtered list survives a refresh
+    /// and can be shared.
+    pub fn to_query_pairs(&self) -> Vec<(&'static str, String)> {
+        let ids = |ids: &[Uuid]| {
+            ids.iter()
+                .map(Uuid::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let mut pairs = Vec::new();
+        if !self.tags.is_empty() {
+            pairs.push(("tags", ids(&self.tags)));
+        }
+        if !self.groups.is_empty() {
+            pairs.push(("groups", ids(&self.groups)));
+        }
+        if let Some(has) = self.has_credentials {
+            pairs.push(("credentials", if has { "set" } else { "unset" }.to_string()));
+        }
+        if let Some(after) = self.created_after {
+            pairs.push(("created_after", after.to_string()));
+        }
+        if let Some(before) = self.created_before {
+            pairs.push(("created_before", before.to_string()));
+        }
+        pairs
+    }
+
+    /// Read a filter back from URL query pairs, ignoring anything that isn't
+    /// part of one, or doesn't parse.
+    pub fn from_query_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let ids = |value: &str| value.split(',').filter_map(|id| id.parse().ok()).collect();
+        let mut filter = Self::default();
+        for (key, value) in pairs {
+            match key {
+                "tags" => filter.tags = ids(value),
+                "groups" => filter.groups = ids(value),
+                "credentials" => {
+                    filter.has_credentials = match value {
+                        "set" => Some(true),
+                        "unset" => Some(false),
+                        _ => None,
+                    }
+                }
+                "created_after" => filter.created_after = value.parse().ok(),
+                "created_before" => filter.created_before = value.parse().ok(),
+                _ => {}
+            }
+        }
+        filter
+    }
+}
+
+/// The settings the "Generate Provision Link" form starts with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProvisionDefaults {
+    pub duration_hours: u32,
+    pub max_uses: Option<u8>,
+    pub group_names: Vec<String>,
+}
+
+impl Default for ProvisionDefaults {
+    fn default() -> Self {
+        Self {
+            duration_hours: 24,
+            max_uses: Some(1),
+            group_names: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::email_verification::EmailAddressStatus;
+
+/// A field people can change on their own profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileField {
+    DisplayName,
+    /// Their primary email address.
+    Email,
+}
+
+impl ProfileField {
+    pub const ALL: [Self; 2] = [Self::DisplayName, Self::Email];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DisplayName => "display_name",
+            Self::Email => "email",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::DisplayName => "Display name",
+            Self::Email => "Email",
+        }
+    }
+}
+
+impl std::str::FromStr for ProfileField {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown profile field '{s}'"))
+    }
+}
+
+/// What happens when someone changes a field on their own profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangePolicy {
+    /// Only admins can change it.
+    #[default]
+    Off,
+    /// The change waits for an admin to approve it.
+    Review,
+    /// The change is made straight away.
+    Immediate,
+}
+
+/// What people may change on their own profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfilePolicy {
+    pub display_name: ChangePolicy,
+    pub email: ChangePolicy,
+}
+
+impl ProfilePolicy {
+    pub fn of(&self, field: ProfileField) -> ChangePolicy {
+        match field {
+            ProfileField::DisplayName => self.display_name,
+            ProfileField::Email => self.email,
+        }
+    }
+
+    /// Whether people can change anything at all.
+    pub fn any(&self) -> bool {
+        ProfileField::ALL
+            .into_iter()
+            .any(|field| self.of(field) != ChangePolicy::Off)
+    }
+}
+
+/// A change someone asked for to their own profile, waiting for or decided
+/// by an admin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileChange {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub field: ProfileField,
+    /// What it was when they asked.
+    pub old_value: String,
+    pub new_value: String,
+    pub created_at: Timestamp,
+    pub status: ProfileChangeStatus,
+    /// The admin who approved or denied it.
+    pub decided_by: Option<String>,
+    pub decided_at: Option<Timestamp>,
+    /// Why it was denied, as shown to the person who asked.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileChangeStatus {
+    #[default]
+    Pending,
+    Approved,
+    Denied,
+}
+
+impl ProfileChangeStatus {
+    pub const ALL: [Self; 3] = [Self::Pending, Self::Approved, Self::Denied];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+        }
+    }
+}
+
+impl std::str::FromStr for ProfileChangeStatus {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown profile change status '{s}'"))
+    }
+}
+
+/// The signed-in person's own profile, and what they may change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MyProfile {
+    pub display_name: String,
+    pub email: Option<String>,
+    pub policy: ProfilePolicy,
+    /// Their changes, newest first.
+    pub changes: Vec<ProfileChange>,
+    /// All their email addresses, and which they've verified.
+    #[serde(default)]
+    pub email_addresses: Vec<EmailAddressStatus>,
+    /// Whether verification codes can be emailed.
+    #[serde(default)]
+    pub can_verify_email: bool,
+}
@@ -1,4 +1,8 @@
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{ResetLink, validation::FieldErrors};
 
 #[derive(Deserialize, Serialize)]
 pub struct ProvisionToken {
@@ -14,3 +18,515 @@ impl ProvisionToken {
         &self.token
     }
 }
+
+/// A provision link as listed for admins, so they can make another like it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProvisionLinkSummary {
+    pub id: Uuid,
+    pub created_at: Timestamp,
+    pub expires_at: Timestamp,
+    pub max_uses: Option<u8>,
+    pub use_count: u32,
+    pub groups: Vec<String>,
+    pub fields: ProvisionFields,
+    #[serde(default)]
+    pub email_restriction: EmailRestriction,
+    /// When the link was last emailed to the address it's for.
+    #[serde(default)]
+    pub last_emailed_at: Option<Timestamp>,
+    #[serde(default)]
+    pub funnel: ProvisionFunnel,
+    /// Whether accounts made with it wait, locked, for an admin to approve
+    /// them.
+    #[serde(default)]
+    pub require_approval: bool,
+    #[serde(default)]
+    pub follow_up: ProvisionFollowUp,
+}
+
+/// What someone opening a provision link is told about it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VerifiedProvisionLink {
+    /// The fields its form should ask for.
+    pub fields: ProvisionFields,
+    pub expires_at: Timestamp,
+    /// How many more accounts it can make, if it's limited.
+    pub uses_remaining: Option<u32>,
+    /// The groups accounts it makes are added to.
+    #[serde(default)]
+    pub groups: Vec<GrantedGroup>,
+}
+
+/// A group a provision link adds people to, described so they know what
+/// they're being given.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GrantedGroup {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl ProvisionLinkSummary {
+    /// How long the link lasted, in whole hours.
+    pub fn duration_hours(&self) -> u32 {
+        let secs = self.expires_at.duration_since(self.created_at).as_secs();
+        ((secs + 1800) / 3600).max(1) as u32
+    }
+}
+
+/// How far people have got with a provision link, so admins can tell
+/// someone who never opened it from someone stuck partway.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProvisionFunnel {
+    /// How many times its page was opened.
+    pub opens: u32,
+    pub last_opened_at: Option<Timestamp>,
+    /// How many accounts were made with it.
+    pub completions: u32,
+    pub failure_count: u32,
+    /// How many failures had each cause, most common first.
+    pub failure_causes: Vec<ProvisionFailureCount>,
+    /// The latest failures, newest first.
+    pub failures: Vec<ProvisionFailure>,
+}
+
+/// Someone tried to use a provision link and couldn't.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProvisionFailure {
+    pub at: Timestamp,
+    #[serde(default)]
+    pub cause: ProvisionFailureCause,
+    pub reason: String,
+}
+
+/// What kind of problem stopped someone using a provision link, so admins
+/// can tell people's mistakes from AuthIt! or Kanidm having trouble.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisionFailureCause {
+    /// Someone already has the name they chose.
+    NameTaken,
+    /// Something they entered wasn't valid.
+    Invalid,
+    /// Kanidm couldn't be reached, or was too busy.
+    KanidmUnavailable,
+    /// The link had expired or been used up.
+    LinkExhausted,
+    #[default]
+    Other,
+}
+
+impl ProvisionFailureCause {
+    pub const ALL: [Self; 5] = [
+        Self::NameTaken,
+        Self::Invalid,
+        Self::KanidmUnavailable,
+        Self::LinkExhausted,
+        Self::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NameTaken => "name_taken",
+            Self::Invalid => "invalid",
+            Self::KanidmUnavailable => "kanidm_unavailable",
+            Self::LinkExhausted => "link_exhausted",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NameTaken => "Name already taken",
+            Self::Invalid => "Invalid details",
+            Self::KanidmUnavailable => "Kanidm unavailable",
+            Self::LinkExhausted => "Link expired or used up",
+            Self::Other => "Other",
+        }
+    }
+
+    /// Whether it's a problem on AuthIt!'s side, rather than the person's
+    /// or the link's.
+    pub fn is_system(&self) -> bool {
+        matches!(self, Self::KanidmUnavailable | Self::Other)
+    }
+}
+
+impl std::str::FromStr for ProvisionFailureCause {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|cause| cause.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown provision failure cause '{s}'"))
+    }
+}
+
+/// How many times provision links failed for one cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProvisionFailureCount {
+    pub cause: ProvisionFailureCause,
+    pub count: u32,
+}
+
+/// A provision link can't make any more accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvisionLinkUnusable {
+    Expired,
+    UsedUp,
+}
+
+impl std::fmt::Display for ProvisionLinkUnusable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Expired => f.write_str("provision link has expired"),
+            Self::UsedUp => f.write_str("provision link has already been used"),
+        }
+    }
+}
+
+impl core::error::Error for ProvisionLinkUnusable {}
+
+/// Someone tried to make an account with a name that's taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTaken {
+    pub name: String,
+}
+
+impl std::fmt::Display for NameTaken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "someone already has the name '{}'; choose another",
+            self.name
+        )
+    }
+}
+
+impl core::error::Error for NameTaken {}
+
+/// What someone gets for completing a provision link.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProvisionCompletion {
+    /// Their account is ready for them to set up their credentials, after
+    /// which they should do what the link's creator asked.
+    ResetLink {
+        link: ResetLink,
+        next_steps: String,
+        redirect_url: Option<String>,
+    },
+    /// Their account was made locked, and they'll be emailed a reset link
+    /// once an admin approves it.
+    AwaitingApproval,
+}
+
+/// An account made with a provision link that requires approval. It stays
+/// locked, out of the link's groups, until an admin approves it; rejecting it
+/// deletes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvisionApproval {
+    pub id: Uuid,
+    pub link_id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub display_name: String,
+    pub email: String,
+    /// The groups they're added to once approved.
+    pub groups: Vec<String>,
+    /// The admin who made the link.
+    pub link_created_by: Option<String>,
+    pub created_at: Timestamp,
+    pub status: ProvisionApprovalStatus,
+    /// The admin who approved or rejected it.
+    pub decided_by: Option<String>,
+    pub decided_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisionApprovalStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ProvisionApprovalStatus {
+    pub const ALL: [Self; 3] = [Self::Pending, Self::Approved, Self::Rejected];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+impl std::str::FromStr for ProvisionApprovalStatus {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown provision approval status '{s}'"))
+    }
+}
+
+/// The result of approving an account made with a provision link.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvisionApprovalOutcome {
+    /// A link for them to set up their credentials.
+    pub reset_link: ResetLink,
+    /// Whether the reset link was emailed to them. If not, an admin needs to
+    /// pass it along themselves.
+    pub emailed: bool,
+}
+
+/// Whose email addresses can use a provision link, so a leaked link is no use
+/// to outsiders. Empty allows anyone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmailRestriction {
+    /// Domains like `example.com`, or `*.example.com` to include its
+    /// subdomains.
+    pub domains: Vec<String>,
+    /// One person's address, for a link sent to them.
+    pub address: Option<String>,
+}
+
+impl EmailRestriction {
+    /// Build a restriction from comma-separated domains and an address, as
+    /// typed by an admin.
+    pub fn parse(domains: &str, address: &str) -> crate::Result<Self> {
+        let domains = domains
+            .split(',')
+            .map(|d| d.trim().trim_start_matches('@').to_ascii_lowercase())
+            .filter(|d| !d.is_empty())
+            .map(|d| {
+                let name = d.strip_prefix("*.").unwrap_or(&d);
+                if name.is_empty()
+                    || !name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                {
+                    return Err(crate::err!("'{d}' is not an email domain"));
+                }
+                Ok(d)
+            })
+            .collect::<crate::Result<_>>()?;
+
+        let address = address.trim();
+        let address = if address.is_empty() {
+            None
+        } else {
+            crate::validation::validate_email(address)?;
+            Some(address.to_string())
+        };
+
+        Ok(Self { domains, address })
+    }
+
+    /// Check and tidy a restriction from a client.
+    pub fn normalized(&self) -> crate::Result<Self> {
+        Self::parse(
+            &self.domains.join(","),
+            self.address.as_deref().unwrap_or_default(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty() && self.address.is_none()
+    }
+
+    /// Whether `email_address` may use the link: it's the invited address,
+    /// or in one of the domains.
+    pub fn allows(&self, email_address: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let email_address = email_address.trim();
+        if self
+            .address
+            .as_deref()
+            .is_some_and(|a| a.eq_ignore_ascii_case(email_address))
+        {
+            return true;
+        }
+        let Some((_, domain)) = email_address.rsplit_once('@') else {
+            return false;
+        };
+        let domain = domain.to_ascii_lowercase();
+        self.domains.iter().any(|d| match d.strip_prefix("*.") {
+            Some(parent) => domain == parent || domain.ends_with(&format!(".{parent}")),
+            None => domain == *d,
+        })
+    }
+
+    /// A short description, like "alice@example.com or @example.com".
+    pub fn describe(&self) -> String {
+        self.address
+            .iter()
+            .cloned()
+            .chain(self.domains.iter().map(|d| format!("@{d}")))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+}
+
+/// What happens once someone has made their account with a provision link,
+/// so different kinds of invite, like contractors and employees, each end up
+/// in the right place.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProvisionFollowUp {
+    /// Where to send them once they've set up their credentials, like the app
+    /// they were invited to use.
+    pub redirect_url: Option<String>,
+    /// Shown to them once their account is made, like who to ask for a
+    /// laptop.
+    pub next_steps: String,
+    /// Called once their account is ready, so another system can set them
+    /// up.
+    pub webhook_url: Option<String>,
+    /// Signs webhook calls. This is a secret: it's blank when sent to the
+    /// client.
+    pub webhook_secret: String,
+}
+
+impl ProvisionFollowUp {
+    /// Check and tidy follow-up from a client.
+    pub fn normalized(&self) -> crate::Result<Self> {
+        let url = |value: &Option<String>, what: &str| -> crate::Result<Option<String>> {
+            let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) else {
+                return Ok(None);
+            };
+            match url::Url::parse(value) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+                    Ok(Some(value.to_string()))
+                }
+                _ => Err(crate::err!("the {what} must be an http or https URL")),
+            }
+        };
+
+        let webhook_url = url(&self.webhook_url, "webhook")?;
+        let webhook_secret = self.webhook_secret.trim().to_string();
+        if webhook_url.is_some() && webhook_secret.is_empty() {
+            return Err(crate::err!("a secret is needed to sign webhook calls"));
+        }
+        Ok(Self {
+            redirect_url: url(&self.redirect_url, "redirect")?,
+            next_steps: self.next_steps.trim().to_string(),
+            webhook_secret: if webhook_url.is_some() {
+                webhook_secret
+            } else {
+                String::new()
+            },
+            webhook_url,
+        })
+    }
+
+    /// A copy that's safe to send to the client.
+    pub fn redacted(&self) -> Self {
+        Self {
+            webhook_secret: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Which optional fields a provision link's form asks for.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProvisionFields {
+    pub email: FieldRequirement,
+    pub phone: FieldRequirement,
+}
+
+impl Default for ProvisionFields {
+    fn default() -> Self {
+        Self {
+            email: FieldRequirement::Optional,
+            // Few people need phone numbers, so they're opt-in.
+            phone: FieldRequirement::Hidden,
+        }
+    }
+}
+
+impl ProvisionFields {
+    /// These fields, asking for an email address whatever the link says if
+    /// every account must have one.
+    pub fn requiring_email(mut self, required: bool) -> Self {
+        if required {
+            self.email = FieldRequirement::Required;
+        }
+        self
+    }
+
+    /// Check submitted values against the spec.
+    pub fn validate(&self, email_address: &str, phone_number: &str) -> crate::Result<()> {
+        let mut errors = FieldErrors::default();
+        self.check(&mut errors, email_address, phone_number);
+        errors.into_result()
+    }
+
+    /// Like [`Self::validate`], keeping what's wrong with each field.
+    pub fn check(&self, errors: &mut FieldErrors, email_address: &str, phone_number: &str) {
+        let email_required = self.email == FieldRequirement::Required;
+        crate::validation::check_new_email(errors, email_address, email_required);
+        errors.check("email", self.email.validate("email", email_address));
+        errors.check("phone", self.phone.validate("phone number", phone_number));
+        if !phone_number.is_empty() {
+            errors.check(
+                "phone",
+                crate::validation::normalize_phone(phone_number).map(|_| ()),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldRequirement {
+    Hidden,
+    #[default]
+    Optional,
+    Required,
+}
+
+impl FieldRequirement {
+    pub const ALL: [Self; 3] = [Self::Optional, Self::Required, Self::Hidden];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hidden => "hidden",
+            Self::Optional => "optional",
+            Self::Required => "required",
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        *self != Self::Hidden
+    }
+
+    fn validate(&self, field: &str, value: &str) -> crate::Result<()> {
+        match self {
+            Self::Required if value.trim().is_empty() => Err(crate::err!("{field} is required")),
+            Self::Hidden if !value.is_empty() => Err(crate::err!("{field} is not allowed")),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::str::FromStr for FieldRequirement {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown field requirement '{s}'"))
+    }
+}
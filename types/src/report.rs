@@ -0,0 +1,215 @@
+use jiff::civil::Date;
+use serde::{Deserialize, Serialize};
+
+/// Something counted in reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportEventKind {
+    AccountCreated,
+    AccountDeleted,
+    MemberAdded,
+    MemberRemoved,
+    ProvisionLinkUsed,
+    ResetLinkIssued,
+}
+
+impl ReportEventKind {
+    pub const ALL: [Self; 6] = [
+        Self::AccountCreated,
+        Self::AccountDeleted,
+        Self::MemberAdded,
+        Self::MemberRemoved,
+        Self::ProvisionLinkUsed,
+        Self::ResetLinkIssued,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AccountCreated => "account_created",
+            Self::AccountDeleted => "account_deleted",
+            Self::MemberAdded => "member_added",
+            Self::MemberRemoved => "member_removed",
+            Self::ProvisionLinkUsed => "provision_link_used",
+            Self::ResetLinkIssued => "reset_link_issued",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::AccountCreated => "Accounts created",
+            Self::AccountDeleted => "Accounts deleted",
+            Self::MemberAdded => "Added to groups",
+            Self::MemberRemoved => "Removed from groups",
+            Self::ProvisionLinkUsed => "Provision links used",
+            Self::ResetLinkIssued => "Reset links issued",
+        }
+    }
+}
+
+impl std::str::FromStr for ReportEventKind {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown report event '{s}'"))
+    }
+}
+
+/// How many of each [`ReportEventKind`] happened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportCounts {
+    pub accounts_created: u64,
+    pub accounts_deleted: u64,
+    pub members_added: u64,
+    pub members_removed: u64,
+    pub provision_links_used: u64,
+    pub reset_links_issued: u64,
+}
+
+impl ReportCounts {
+    pub fn of(&self, kind: ReportEventKind) -> u64 {
+        match kind {
+            ReportEventKind::AccountCreated => self.accounts_created,
+            ReportEventKind::AccountDeleted => self.accounts_deleted,
+            ReportEventKind::MemberAdded => self.members_added,
+            ReportEventKind::MemberRemoved => self.members_removed,
+            ReportEventKind::ProvisionLinkUsed => self.provision_links_used,
+            ReportEventKind::ResetLinkIssued => self.reset_links_issued,
+        }
+    }
+
+    pub fn add(&mut self, kind: ReportEventKind) {
+        let count = match kind {
+            ReportEventKind::AccountCreated => &mut self.accounts_created,
+            ReportEventKind::AccountDeleted => &mut self.accounts_deleted,
+            ReportEventKind::MemberAdded => &mut self.members_added,
+            ReportEventKind::MemberRemoved => &mut self.members_removed,
+            ReportEventKind::ProvisionLinkUsed => &mut self.provision_links_used,
+            ReportEventKind::ResetLinkIssued => &mut self.reset_links_issued,
+        };
+        *count += 1;
+    }
+}
+
+/// A summary of what happened from the start of `from` to the end of `to`,
+/// in UTC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    pub from: Date,
+    pub to: Date,
+    pub totals: ReportCounts,
+    /// Each day's counts, oldest first. Days with nothing are included, so
+    /// they chart evenly.
+    pub days: Vec<ReportDay>,
+    /// Membership churn in each group, busiest first.
+    pub groups: Vec<GroupChurn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportDay {
+    pub date: Date,
+    pub counts: ReportCounts,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupChurn {
+    pub group: String,
+    pub added: u64,
+    pub removed: u64,
+}
+
+impl Report {
+    /// One row a day, with a column for each count.
+    pub fn days_csv(&self) -> String {
+        let mut csv = String::from("date");
+        for kind in ReportEventKind::ALL {
+            csv.push(',');
+            csv.push_str(kind.as_str());
+        }
+        csv.push('\n');
+        for day in &self.days {
+            csv.push_str(&day.date.to_string());
+            for kind in ReportEventKind::ALL {
+                csv.push_str(&format!(",{}", day.counts.of(kind)));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// One row a group, with how many people were added and removed.
+    pub fn groups_csv(&self) -> String {
+        let mut csv = String::from("group,added,removed\n");
+        for churn in &self.groups {
+            csv.push_str(&format!(
+                "\"{}\",{},{}\n",
+                churn.group.replace('"', "\"\""),
+                churn.added,
+                churn.removed
+            ));
+        }
+        csv
+    }
+}
+
+/// Who gets the monthly report by email, and who gets the activity digest,
+/// how often.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportSettings {
+    pub recipients: Vec<String>,
+    pub digest: DigestFrequency,
+    pub digest_recipients: Vec<String>,
+}
+
+/// How often the activity digest is emailed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    #[default]
+    Never,
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub const ALL: [Self; 3] = [Self::Never, Self::Daily, Self::Weekly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Never => "never",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Never => "Never",
+            Self::Daily => "Daily",
+            Self::Weekly => "Weekly",
+        }
+    }
+
+    /// How many days each digest covers, if any are sent.
+    pub fn days(&self) -> Option<i32> {
+        match self {
+            Self::Never => None,
+            Self::Daily => Some(1),
+            Self::Weekly => Some(7),
+        }
+    }
+}
+
+impl std::str::FromStr for DigestFrequency {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|frequency| frequency.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown digest frequency '{s}'"))
+    }
+}
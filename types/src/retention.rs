@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// How long AuthIt! keeps data it no longer needs, in days. Anything older is
+/// purged in the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Counted from when the session expired.
+    pub expired_sessions_days: u32,
+    /// Counted from when the link expired. Used-up links are kept until then
+    /// too.
+    pub provision_links_days: u32,
+    /// Counted from when the job finished.
+    pub jobs_days: u32,
+    /// Counted from when the request was approved or denied. Pending requests
+    /// are never purged.
+    pub account_requests_days: u32,
+    /// Counted from when it happened.
+    pub activity_days: u32,
+    /// Counted from when it was written.
+    pub shift_notes_days: u32,
+    /// Counted from the sign-in.
+    pub login_events_days: u32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            expired_sessions_days: 7,
+            provision_links_days: 30,
+            jobs_days: 30,
+            account_requests_days: 90,
+            activity_days: 90,
+            shift_notes_days: 30,
+            login_events_days: 90,
+        }
+    }
+}
+
+/// How many records a purge deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeResult {
+    pub sessions: u64,
+    pub provision_links: u64,
+    pub jobs: u64,
+    pub account_requests: u64,
+    pub activity: u64,
+    pub shift_notes: u64,
+    pub login_events: u64,
+}
+
+impl PurgeResult {
+    pub fn total(&self) -> u64 {
+        self.sessions
+            + self.provision_links
+            + self.jobs
+            + self.account_requests
+            + self.activity
+            + self.shift_notes
+            + self.login_events
+    }
+}
@@ -0,0 +1,49 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A push for a set of people to change their credentials by a deadline,
+/// like after a leak.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationCampaign {
+    pub id: Uuid,
+    pub name: String,
+    pub started_by: String,
+    pub started_at: Timestamp,
+    pub deadline: Timestamp,
+    /// Whether to lock the accounts of people who haven't rotated by the
+    /// deadline.
+    pub lock_at_deadline: bool,
+    /// Set once everyone has rotated, or the deadline has passed.
+    pub closed_at: Option<Timestamp>,
+    pub members: Vec<RotationMember>,
+}
+
+impl RotationCampaign {
+    pub fn rotated(&self) -> usize {
+        self.members.iter().filter(|m| m.has_rotated()).count()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed_at.is_some()
+    }
+}
+
+/// Someone who has to rotate their credentials for a campaign.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationMember {
+    pub user_id: Uuid,
+    pub user_name: String,
+    /// When we first saw their credentials had changed.
+    pub rotated_at: Option<Timestamp>,
+    /// When they were last emailed about it.
+    pub reminded_at: Option<Timestamp>,
+    /// When their account was locked for missing the deadline.
+    pub locked_at: Option<Timestamp>,
+}
+
+impl RotationMember {
+    pub fn has_rotated(&self) -> bool {
+        self.rotated_at.is_some()
+    }
+}
@@ -0,0 +1,59 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::group_webhook::MembershipAction;
+
+/// Adding someone to or removing them from a group at a set time, like
+/// granting production access from Monday morning.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledMembershipChange {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub action: MembershipAction,
+    pub run_at: Timestamp,
+    pub created_by: String,
+    pub created_at: Timestamp,
+    pub status: ScheduledChangeStatus,
+    /// When it was made, failed or cancelled.
+    pub finished_at: Option<Timestamp>,
+    /// Why it failed, or who cancelled it.
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledChangeStatus {
+    #[default]
+    Pending,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl ScheduledChangeStatus {
+    pub const ALL: [Self; 4] = [Self::Pending, Self::Done, Self::Failed, Self::Cancelled];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Done => "done",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl std::str::FromStr for ScheduledChangeStatus {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| crate::err!("unknown scheduled change status '{s}'"))
+    }
+}
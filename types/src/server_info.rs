@@ -0,0 +1,35 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+/// Which build of AuthIt! is running, to tie what it does back to a release.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub version: String,
+    /// The commit it was built from, if that was known at build time.
+    pub git_hash: Option<String>,
+    pub built_at: Option<Timestamp>,
+}
+
+impl ServerInfo {
+    /// The build this is compiled into.
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: Some(env!("AUTHIT_GIT_HASH"))
+                .filter(|h| !h.is_empty())
+                .map(String::from),
+            built_at: env!("AUTHIT_BUILT_AT")
+                .parse()
+                .ok()
+                .and_then(|secs| Timestamp::from_second(secs).ok()),
+        }
+    }
+
+    /// Like `v1.2.0 (3f9c2a1b4d5e)`, for places with room for one line.
+    pub fn describe(&self) -> String {
+        match &self.git_hash {
+            Some(hash) => format!("v{} ({hash})", self.version),
+            None => format!("v{}", self.version),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A summary of one of the current user's AuthIt! sessions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub created_at: Timestamp,
+    pub expires_at: Timestamp,
+    /// Whether the user chose "keep me signed in" when logging in.
+    pub persistent: bool,
+    /// Whether this is the session making the request.
+    pub current: bool,
+    /// The browser and OS it signed in from, like "Firefox on Linux".
+    pub device: Option<String>,
+    pub ip_address: Option<String>,
+    /// Roughly where it signed in from, if a GeoIP database is configured.
+    pub location: Option<String>,
+}
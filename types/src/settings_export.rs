@@ -0,0 +1,54 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    notification::NotificationSettings, offboarding::ChecklistTemplate, report::ReportSettings,
+    retention::RetentionPolicy, visibility::FieldVisibility,
+};
+
+/// The version of [`SettingsExport`] this build writes. Imports from newer
+/// versions are refused.
+pub const SETTINGS_EXPORT_VERSION: u32 = 1;
+
+/// AuthIt!'s own settings as a JSON document, to review, keep in version
+/// control, or copy to another instance.
+///
+/// Each section is optional on import, so a document can be cut down to
+/// only what should change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingsExport {
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: Option<Timestamp>,
+    #[serde(default)]
+    pub exported_by: Option<String>,
+    /// Chat notifiers. Their secrets are blank unless they were asked for,
+    /// and blank secrets keep the existing ones on import.
+    #[serde(default)]
+    pub notifications: Option<NotificationSettings>,
+    #[serde(default)]
+    pub field_visibility: Option<FieldVisibility>,
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+    #[serde(default)]
+    pub offboarding_template: Option<ChecklistTemplate>,
+    #[serde(default)]
+    pub require_ticket: Option<bool>,
+    #[serde(default)]
+    pub require_email: Option<bool>,
+    #[serde(default)]
+    pub reports: Option<ReportSettings>,
+    /// Tag names. Importing adds any that are missing, and never removes
+    /// any.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+/// What importing a [`SettingsExport`] changed, or would change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingsImport {
+    /// Whether the changes were made, rather than only checked.
+    pub applied: bool,
+    /// A line for each section that differs, like "Retention policy".
+    pub changes: Vec<String>,
+}
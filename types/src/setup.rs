@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// One thing the first-run setup checks before AuthIt! can be used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupCheck {
+    pub label: String,
+    /// Why the check failed, if it did.
+    pub error: Option<String>,
+}
+
+impl SetupCheck {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
@@ -0,0 +1,14 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A note from one admin to the others, like "Kanidm upgrade Friday; don't
+/// create users after 5pm", shown on the dashboard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShiftNote {
+    pub id: Uuid,
+    pub author: String,
+    pub created_at: Timestamp,
+    /// The content, rendered from markdown.
+    pub html: String,
+}
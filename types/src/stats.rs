@@ -0,0 +1,19 @@
+use jiff::civil::Date;
+use serde::{Deserialize, Serialize};
+
+use crate::report::ReportCounts;
+
+/// Recent trends, for the dashboard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DashboardStats {
+    /// Each day's numbers, in UTC, oldest first, ending today.
+    pub days: Vec<StatsDay>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsDay {
+    pub date: Date,
+    pub counts: ReportCounts,
+    pub sign_ins: u64,
+    pub failed_sign_ins: u64,
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// The ticket an admin is working on, like `OPS-1234`. It's recorded with
+/// everything they change until they clear it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketStatus {
+    pub ticket: Option<String>,
+    /// Whether dangerous changes, like deleting users, are refused without
+    /// one.
+    pub required: bool,
+}
@@ -0,0 +1,25 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::kanidm::Person;
+
+/// What deleting a user would lose, for the admin to confirm first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserDeletionImpact {
+    pub person: Person,
+    /// The groups they're a direct member of.
+    pub groups: Vec<String>,
+    /// The groups they own in AuthIt!, which will be left without them.
+    pub owned_groups: Vec<String>,
+    /// How many OAuth2 sessions they have in Kanidm, or `None` if Kanidm
+    /// wouldn't say.
+    pub oauth2_sessions: Option<u32>,
+    /// What AuthIt! holds about them that will be deleted with them.
+    pub records: Vec<String>,
+    /// Records that mention them, which are kept. With
+    /// `anonymize_on_delete`, their name is replaced by a pseudonym.
+    pub mentions: Vec<String>,
+    pub anonymized: bool,
+    /// When they created their account from a provision link, if they did.
+    pub provisioned_at: Option<Timestamp>,
+}
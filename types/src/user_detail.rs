@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    activity::Activity,
+    kanidm::{Group, LdapStatus, Person},
+    login_event::LoginEvent,
+    scheduled_membership::ScheduledMembershipChange,
+    tag::Tag,
+};
+
+/// Everything a user's page shows about them, fetched in one go rather than
+/// worked out from the full user and group lists.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserDetail {
+    pub person: Person,
+    /// The groups they're in.
+    pub groups: Vec<Group>,
+    /// Whether they've set up credentials, if Kanidm could say.
+    pub has_credentials: Option<bool>,
+    /// `None` unless LDAP is enabled.
+    pub ldap_status: Option<LdapStatus>,
+    pub phone_number: Option<String>,
+    pub tags: Vec<Tag>,
+    /// The groups they can invite people to.
+    pub owned_groups: Vec<Uuid>,
+    pub scheduled_changes: Vec<ScheduledMembershipChange>,
+    /// What they did or had done to them lately, newest first.
+    pub recent_activity: Vec<Activity>,
+    /// Their latest sign-ins to AuthIt!, newest first.
+    pub login_events: Vec<LoginEvent>,
+}
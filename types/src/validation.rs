@@ -0,0 +1,176 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, err};
+
+pub const MAX_USERNAME_LENGTH: usize = 64;
+pub const MAX_DISPLAY_NAME_LENGTH: usize = 200;
+pub const MAX_EMAIL_LENGTH: usize = 254;
+/// For free text, like a justification, a reason, or an announcement.
+pub const MAX_NOTES_LENGTH: usize = 4000;
+pub const MAX_TAG_LENGTH: usize = 40;
+pub const MAX_GROUP_NAME_LENGTH: usize = 64;
+/// For ticket references, like `OPS-1234` or a link to the ticket.
+pub const MAX_TICKET_LENGTH: usize = 200;
+
+/// A field longer than we accept.
+#[derive(Debug)]
+pub struct TooLong {
+    pub field: &'static str,
+    pub max: usize,
+}
+
+impl fmt::Display for TooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} must be at most {} characters", self.field, self.max)
+    }
+}
+
+impl std::error::Error for TooLong {}
+
+/// What's wrong with each field of a form, so it can be shown next to the
+/// field. Fields are named as the server function's arguments are, like
+/// `name`, `display_name`, `email` and `phone`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldErrors(pub BTreeMap<String, String>);
+
+impl FieldErrors {
+    /// Keep the error from checking a field, if there was one. Only the first
+    /// error for each field is kept.
+    pub fn check(&mut self, field: &str, result: Result<()>) {
+        if let Err(error) = result {
+            self.add(field, error.to_string());
+        }
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0
+            .entry(field.to_string())
+            .or_insert_with(|| message.into());
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0.get(field).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// An error holding these, if there are any.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(self))
+        }
+    }
+}
+
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<&str> = self.0.values().map(String::as_str).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for FieldErrors {}
+
+/// Check that `value` is at most `max` characters.
+pub fn check_length(field: &'static str, value: &str, max: usize) -> Result<()> {
+    if value.chars().count() > max {
+        return Err(Error::new(TooLong { field, max }));
+    }
+    Ok(())
+}
+
+/// Check the lengths of a new person's names. Kanidm does the rest.
+pub fn validate_person(name: &str, display_name: &str) -> Result<()> {
+    let mut errors = FieldErrors::default();
+    check_person(&mut errors, name, display_name);
+    errors.into_result()
+}
+
+/// Like [`validate_person`], keeping what's wrong with each field.
+pub fn check_person(errors: &mut FieldErrors, name: &str, display_name: &str) {
+    if name.trim().is_empty() {
+        errors.add("name", "username can't be empty");
+    }
+    errors.check("name", check_length("username", name, MAX_USERNAME_LENGTH));
+    if display_name.trim().is_empty() {
+        errors.add("display_name", "display name can't be empty");
+    }
+    errors.check(
+        "display_name",
+        check_length("display name", display_name, MAX_DISPLAY_NAME_LENGTH),
+    );
+}
+
+/// Tidy a display or legal name, checking it's usable.
+pub fn normalize_name(field: &'static str, name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(err!("{field} can't be empty"));
+    }
+    check_length(field, name, MAX_DISPLAY_NAME_LENGTH)?;
+    Ok(name.to_string())
+}
+
+/// A loose check that `address` looks like an email address. Kanidm does the
+/// real validation.
+pub fn validate_email(address: &str) -> Result<()> {
+    check_length("email", address, MAX_EMAIL_LENGTH)?;
+    match address.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && !domain.is_empty() => Ok(()),
+        _ => Err(err!("'{address}' is not an email address")),
+    }
+}
+
+/// Check a new person's email address, which `required` says every account
+/// must have.
+pub fn check_new_email(errors: &mut FieldErrors, address: &str, required: bool) {
+    if address.trim().is_empty() {
+        if required {
+            errors.add("email", "an email address is required for every account");
+        }
+        return;
+    }
+    errors.check("email", validate_email(address));
+}
+
+/// Tidy a tag's name, checking it's usable. Tags are lowercase, so
+/// "Contractor" and "contractor" are the same tag.
+pub fn normalize_tag(name: &str) -> Result<String> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        return Err(err!("tag can't be empty"));
+    }
+    check_length("tag", &name, MAX_TAG_LENGTH)?;
+    Ok(name)
+}
+
+/// Validate a phone number, returning it in E.164 form (like `+15551234567`).
+///
+/// Spaces, dashes, dots, and parentheses are allowed and removed.
+pub fn normalize_phone(number: &str) -> Result<String> {
+    let normalized: String = number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+
+    let Some(digits) = normalized.strip_prefix('+') else {
+        return Err(err!(
+            "phone number must start with + and a country code, like +15551234567"
+        ));
+    };
+    if digits.is_empty()
+        || digits.len() > 15
+        || digits.starts_with('0')
+        || !digits.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(err!("'{number}' is not a valid phone number"));
+    }
+
+    Ok(normalized)
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Who may see a field of someone's profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Audience {
+    /// Anyone signed in to AuthIt!.
+    Everyone,
+    Admins,
+    /// Only members of this group, whether or not they're admins.
+    Group(String),
+    Nobody,
+}
+
+/// Who may see each field in the people directory and on user pages. Admins
+/// always see email addresses and groups on user pages, as they manage them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldVisibility {
+    /// Only applies to the directory.
+    pub email_addresses: Audience,
+    /// Applies to the directory and user pages. Only admins in this audience
+    /// may change legal names.
+    pub legal_name: Audience,
+    /// Which of the `directory_groups` someone is in. Only applies to the
+    /// directory.
+    pub groups: Audience,
+}
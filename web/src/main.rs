@@ -1,54 +1,249 @@
+use std::collections::HashSet;
+
 use dioxus::prelude::*;
 
 mod views;
 
+use types::{
+    capability::{Capabilities, Capability, Requirement},
+    preferences::{Theme, UserFilter, UserShortcuts},
+    ticket::TicketStatus,
+    validation::FieldErrors,
+};
 use uuid::Uuid;
-use views::{Dashboard, Login, Provision, Users};
+use views::{
+    AccountRequests, AnnouncementBanner, Audit, Avatar, BackgroundJobs, BreakGlass, Dashboard,
+    Diagnostics, Directory, EmailQueue, FavoriteGroups, Login, Masking, MyGroups, NewUserPrefill,
+    Offboardings, Presence, Profile, Provision, Reports, RequestAccount, Rotations, Sensitive,
+    Sessions, Settings, Setup, Users, UsersQuery, format_timestamp, sleep, use_masking,
+};
+
+/// How often admins check who else is online.
+const PRESENCE_POLL_MS: u32 = 30_000;
 
 #[derive(Debug, Clone, Routable, PartialEq)]
 #[rustfmt::skip]
 pub enum Route {
     #[route("/login?:error")]
     Login { error: Option<String> },
+    #[route("/break-glass?:error")]
+    BreakGlass { error: Option<String> },
     #[route("/provision/:token")]
     Provision { token: String },
+    #[route("/request-account")]
+    RequestAccount {},
+    #[route("/setup")]
+    Setup {},
     #[layout(AuthenticatedLayout)]
         #[route("/")]
         Dashboard {},
-        #[route("/users")]
-        UserList {},
-        #[route("/users/:user_id")]
-        UserDetail { user_id: Uuid },
+        #[route("/users?:..query")]
+        UserList { query: UsersQuery },
+        #[route("/users/new?:name&:display_name&:email")]
+        NewUser { name: Option<String>, display_name: Option<String>, email: Option<String> },
+        #[route("/users/:user_id?:..query")]
+        UserDetail { user_id: Uuid, query: UsersQuery },
+        #[route("/account-requests")]
+        AccountRequests {},
+        #[route("/offboarding")]
+        Offboardings {},
+        #[route("/rotation")]
+        Rotations {},
+        #[route("/reports")]
+        Reports {},
+        #[route("/sessions")]
+        Sessions {},
+        #[route("/directory")]
+        Directory {},
+        #[route("/my-groups")]
+        MyGroups {},
+        #[route("/profile")]
+        Profile {},
+        #[route("/settings")]
+        Settings {},
+        #[route("/diagnostics")]
+        Diagnostics {},
+        #[route("/jobs")]
+        BackgroundJobs {},
+        #[route("/email")]
+        EmailQueue {},
+        #[route("/audit")]
+        Audit {},
 }
 
 impl Route {
     pub fn users() -> Self {
-        Route::UserList {}
+        Route::UserList {
+            query: UsersQuery::default(),
+        }
     }
 
     pub fn user_detail(user_id: Uuid) -> Self {
-        Route::UserDetail { user_id }
+        Route::UserDetail {
+            user_id,
+            query: UsersQuery::default(),
+        }
     }
+
+    /// What someone needs to use this page.
+    fn requirement(&self) -> Requirement {
+        match self {
+            Route::Directory {} => Requirement::Directory,
+            Route::MyGroups {} => Requirement::GroupOwner,
+            Route::Profile {} => Requirement::Profile,
+            Route::UserList { .. } | Route::NewUser { .. } | Route::UserDetail { .. } => {
+                Requirement::Capability(Capability::ReadPersons)
+            }
+            _ => Requirement::Admin,
+        }
+    }
+
+    /// The first page the current user can use, if there's any.
+    fn home(capabilities: &Capabilities) -> Option<Self> {
+        [
+            Route::Dashboard {},
+            Route::Directory {},
+            Route::MyGroups {},
+            Route::Profile {},
+        ]
+        .into_iter()
+        .find(|route| capabilities.meets(route.requirement()))
+    }
+}
+
+/// The user list, filtered as the query says.
+#[component]
+fn UserList(query: UsersQuery) -> Element {
+    rsx! { Users { user_id: None, url_filter: query.filter } }
 }
 
+/// Open the create user form, filled in from the query, so other tools can
+/// link straight to it.
 #[component]
-fn UserList() -> Element {
-    rsx! { Users { user_id: None } }
+fn NewUser(name: Option<String>, display_name: Option<String>, email: Option<String>) -> Element {
+    let prefill = NewUserPrefill {
+        username: name.unwrap_or_default(),
+        display_name: display_name.unwrap_or_default(),
+        email: email.unwrap_or_default(),
+    };
+    rsx! { Users { user_id: None, url_filter: UserFilter::default(), prefill } }
 }
 
+/// A user's page, with the list beside it filtered as the query says. An
+/// `action` in the query, like `reset`, opens that action's dialog.
 #[component]
-fn UserDetail(user_id: Uuid) -> Element {
-    rsx! { Users { user_id: Some(user_id) } }
+fn UserDetail(user_id: Uuid, query: UsersQuery) -> Element {
+    rsx! {
+        Users { user_id: Some(user_id), url_filter: query.filter, action: query.action }
+    }
 }
 
 fn main() {
     #[cfg(feature = "server")]
     {
+        let mut args: Vec<String> = std::env::args().collect();
+        // `--data-dir` goes with any command, taking precedence over the config.
+        let data_dir = args.iter().position(|a| a == "--data-dir").map(|i| {
+            let Some(dir) = args.get(i + 1).cloned() else {
+                eprintln!("--data-dir needs a directory");
+                std::process::exit(1);
+            };
+            args.drain(i..=i + 1);
+            dir
+        });
+        if let Some(dir) = &data_dir {
+            // SAFETY: Nothing else is running yet.
+            unsafe { std::env::set_var("AUTHIT_DATA_DIR", dir) };
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match args.get(1..).unwrap_or_default() {
+            ["setup", rest @ ..] => {
+                let path = rest.first().copied().unwrap_or("authit.toml");
+                if let Err(e) = server::setup::run(std::path::Path::new(path)) {
+                    eprintln!("Setup failed: {e}");
+                    std::process::exit(1);
+                }
+                return;
+            }
+            ["config", "show", rest @ ..] => {
+                match server::show_config(rest.contains(&"--redacted")) {
+                    Ok(config) => print!("{config}"),
+                    Err(e) => {
+                        eprintln!("Invalid config: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            ["migrations", command] => {
+                let result = match *command {
+                    "status" => server::migrations::status(),
+                    "repair" => server::migrations::repair(),
+                    _ => {
+                        eprintln!("Unknown migrations command: {command}");
+                        std::process::exit(1);
+                    }
+                };
+                match result {
+                    Ok(out) => print!("{out}"),
+                    Err(e) => {
+                        eprintln!("Migrations {command} failed: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            ["systemd-unit", rest @ ..] => {
+                match server::systemd::unit(rest, data_dir.as_deref()) {
+                    Ok(unit) => print!("{unit}"),
+                    Err(e) => {
+                        eprintln!("Couldn't make a systemd unit: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            ["audit", "verify"] => {
+                match server::audit_chain::run() {
+                    Ok((out, intact)) => {
+                        print!("{out}");
+                        if !intact {
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Audit verify failed: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        match server::embedded_assets::unpack() {
+            // SAFETY: Nothing else is running yet. This is where Dioxus looks
+            // for its assets.
+            Ok(Some(dir)) => unsafe { std::env::set_var("DIOXUS_PUBLIC_PATH", dir) },
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Failed to unpack web assets: {e}");
+                std::process::exit(1);
+            }
+        }
         server::init_tracing();
         dioxus::serve(|| async move {
             let routes = server::init().await?;
 
-            Ok(dioxus::server::router(App).merge(routes))
+            let router = dioxus::server::router(App).merge(routes);
+            let router = server::track_requests(server::record_kanidm_calls(router));
+            let router = server::limit_request_size(server::maintenance_gate(router));
+            let router = server::disable_provision_links(router);
+            let router = server::resolve_forwarded(server::trace_requests(
+                server::cache_and_compress(server::restrict_provision_host(router)),
+            ));
+            server::serve_public(router.clone()).await?;
+            Ok(router)
         });
     }
 
@@ -73,8 +268,22 @@ fn NavLink(to: Route, children: Element) -> Element {
     let is_active = matches!(
         (&current_route, &to),
         (Route::Dashboard {}, Route::Dashboard {})
-            | (Route::UserList {}, Route::UserList {})
-            | (Route::UserDetail { .. }, Route::UserList {})
+            | (Route::UserList { .. }, Route::UserList { .. })
+            | (Route::NewUser { .. }, Route::UserList { .. })
+            | (Route::UserDetail { .. }, Route::UserList { .. })
+            | (Route::AccountRequests {}, Route::AccountRequests {})
+            | (Route::Offboardings {}, Route::Offboardings {})
+            | (Route::Rotations {}, Route::Rotations {})
+            | (Route::Reports {}, Route::Reports {})
+            | (Route::Sessions {}, Route::Sessions {})
+            | (Route::Directory {}, Route::Directory {})
+            | (Route::MyGroups {}, Route::MyGroups {})
+            | (Route::Profile {}, Route::Profile {})
+            | (Route::Settings {}, Route::Settings {})
+            | (Route::Diagnostics {}, Route::Diagnostics {})
+            | (Route::BackgroundJobs {}, Route::BackgroundJobs {})
+            | (Route::EmailQueue {}, Route::EmailQueue {})
+            | (Route::Audit {}, Route::Audit {})
     );
 
     rsx! {
@@ -92,6 +301,14 @@ pub struct ErrorInfo {
     pub message: String,
     pub chain: Vec<String>,
     pub backtrace: Option<String>,
+    /// The build of AuthIt! that failed, to include when reporting it.
+    pub build: Option<String>,
+    /// Set when the server refused an admin's change to their own account,
+    /// as it would lock them out. Shown as an explanation, not a failure.
+    pub lockout: bool,
+    /// Set when the server was too busy, to how many seconds to wait before
+    /// trying again.
+    pub retry_after: Option<u64>,
 }
 
 impl ErrorInfo {
@@ -115,16 +332,26 @@ impl ErrorInfo {
                         .get("backtrace")
                         .and_then(|b| b.as_str())
                         .map(String::from);
+                    let build = details
+                        .get("build")
+                        .and_then(|b| b.as_str())
+                        .map(String::from);
                     Self {
                         message: message.clone(),
                         chain,
                         backtrace,
+                        build,
+                        lockout: details.get(types::ADMIN_LOCKOUT).is_some(),
+                        retry_after: details.get(types::RETRY_AFTER).and_then(|s| s.as_u64()),
                     }
                 } else {
                     Self {
                         message: message.clone(),
                         chain: vec![message.clone()],
                         backtrace: None,
+                        build: None,
+                        lockout: false,
+                        retry_after: None,
                     }
                 }
             }
@@ -132,14 +359,21 @@ impl ErrorInfo {
                 message: other.to_string(),
                 chain: vec![other.to_string()],
                 backtrace: None,
+                build: None,
+                lockout: false,
+                retry_after: None,
             },
         }
     }
 }
 
 /// Global error state - use `use_error()` to access
+///
+/// The second signal counts down the seconds left before the server should
+/// be asked again, after it said it was too busy. Main buttons are disabled
+/// until it reaches zero.
 #[derive(Clone, Copy)]
-pub struct ErrorState(Signal<Option<ErrorInfo>>);
+pub struct ErrorState(Signal<Option<ErrorInfo>>, Signal<u64>);
 
 impl ErrorState {
     pub fn set_info(&mut self, error: ErrorInfo) {
@@ -152,6 +386,9 @@ impl ErrorState {
             message: msg.clone(),
             chain: vec![msg],
             backtrace: None,
+            build: None,
+            lockout: false,
+            retry_after: None,
         }));
     }
 
@@ -164,12 +401,89 @@ impl ErrorState {
             });
             return;
         }
-        self.0.set(Some(ErrorInfo::from_server_error(err)));
+        if is_step_up_required(err) {
+            reauthenticate();
+            return;
+        }
+        let info = ErrorInfo::from_server_error(err);
+        if let Some(seconds) = info.retry_after {
+            self.wait(seconds);
+        }
+        self.0.set(Some(info));
     }
 
     pub fn clear(&mut self) {
         self.0.set(None);
     }
+
+    /// Seconds left before the server should be asked again.
+    pub fn retry_in(&self) -> u64 {
+        (self.1)()
+    }
+
+    /// Count down `seconds`, then clear the error if it's still the one that
+    /// asked for the wait.
+    fn wait(&mut self, seconds: u64) {
+        let mut remaining = self.1;
+        let counting = remaining() > 0;
+        remaining.set(remaining().max(seconds));
+        if counting {
+            return;
+        }
+        let mut error = self.0;
+        spawn_forever(async move {
+            while remaining() > 0 {
+                sleep(1000).await;
+                remaining -= 1;
+            }
+            if error
+                .read()
+                .as_ref()
+                .is_some_and(|e| e.retry_after.is_some())
+            {
+                error.set(None);
+            }
+        });
+    }
+}
+
+fn is_step_up_required(err: &ServerFnError) -> bool {
+    matches!(
+        err,
+        ServerFnError::ServerError { details: Some(details), .. }
+            if details.get(types::STEP_UP_REQUIRED).is_some()
+    )
+}
+
+/// Whether a write was refused because someone else changed the entry since
+/// it was loaded.
+pub fn is_edit_conflict(err: &ServerFnError) -> bool {
+    matches!(
+        err,
+        ServerFnError::ServerError { details: Some(details), .. }
+            if details.get(types::EDIT_CONFLICT).is_some()
+    )
+}
+
+/// What was wrong with each field, if the error was for invalid fields, so
+/// forms can show the messages inline.
+pub fn field_errors(err: &ServerFnError) -> Option<FieldErrors> {
+    match err {
+        ServerFnError::ServerError {
+            details: Some(details),
+            ..
+        } => serde_json::from_value(details.get(types::FIELD_ERRORS)?.clone()).ok(),
+        _ => None,
+    }
+}
+
+/// Send the user back through Kanidm to re-enter their credentials, returning
+/// to the current page afterwards.
+fn reauthenticate() {
+    document::eval(
+        "window.location.href = '/auth/login?reauth=true&return_to=' \
+            + encodeURIComponent(window.location.pathname + window.location.search)",
+    );
 }
 
 /// Get the global error state for setting/clearing errors
@@ -177,6 +491,16 @@ pub fn use_error() -> ErrorState {
     use_context::<ErrorState>()
 }
 
+/// What the current user can do, for hiding or disabling what they can't.
+pub fn use_capabilities() -> Capabilities {
+    use_context::<Capabilities>()
+}
+
+/// The users pinned to, or recently viewed in, the sidebar.
+pub fn use_shortcuts() -> Signal<UserShortcuts> {
+    use_context::<Signal<UserShortcuts>>()
+}
+
 /// Filter backtrace to only show lines from this codebase
 fn filter_backtrace(backtrace: &str) -> String {
     backtrace
@@ -194,7 +518,35 @@ fn ErrorBanner() -> Element {
     let mut error_state = use_context::<ErrorState>();
     let error = error_state.0.read();
 
+    if let Some(err) = error.as_ref().filter(|e| e.lockout) {
+        return rsx! {
+            div { class: "modal-overlay",
+                onclick: move |_| error_state.clear(),
+                div { class: "modal",
+                    onclick: move |e| e.stop_propagation(),
+                    div { class: "modal-header",
+                        h2 { class: "modal-title", "Not Allowed" }
+                    }
+                    div { class: "modal-body",
+                        p { "{err.message}" }
+                        p { class: "text-muted",
+                            "This would take away your own access to AuthIt!. If it's really what you want, ask another admin to do it."
+                        }
+                    }
+                    div { class: "modal-footer",
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| error_state.clear(),
+                            "OK"
+                        }
+                    }
+                }
+            }
+        };
+    }
+
     if let Some(err) = error.as_ref() {
+        let retry_in = error_state.retry_in();
         let has_chain = err.chain.len() > 1;
         let filtered_backtrace = err.backtrace.as_ref().map(|bt| filter_backtrace(bt));
         let has_backtrace = filtered_backtrace
@@ -206,7 +558,13 @@ fn ErrorBanner() -> Element {
             div { class: "error-banner",
                 div { class: "error-banner-content",
                     div { class: "error-banner-header",
-                        span { class: "error-banner-message", "{err.message}" }
+                        span { class: "error-banner-message",
+                            if retry_in > 0 {
+                                "Kanidm is busy. Try again in {retry_in}s."
+                            } else {
+                                "{err.message}"
+                            }
+                        }
                         div { class: "error-banner-actions",
                             button {
                                 class: "error-banner-close",
@@ -239,6 +597,9 @@ fn ErrorBanner() -> Element {
                                     }
                                 }
                             }
+                            if let Some(build) = &err.build {
+                                p { class: "text-muted text-sm", "AuthIt! {build}" }
+                            }
                         }
                     }
                 }
@@ -249,48 +610,425 @@ fn ErrorBanner() -> Element {
     }
 }
 
+/// Covers the page as soon as the session ends, like when it's signed out
+/// from another tab or revoked, rather than waiting for the next request to
+/// fail.
+#[component]
+fn SessionLock() -> Element {
+    let mut ended = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            let js = r#"
+                const events = new EventSource("/session/events");
+                events.addEventListener("ended", () => {
+                    events.close();
+                    dioxus.send(null);
+                });
+            "#;
+            if document::eval(js).recv::<()>().await.is_ok() {
+                ended.set(true);
+            }
+        });
+    });
+
+    if !ended() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "modal-overlay",
+            div { class: "modal modal-sm",
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Signed Out" }
+                }
+                div { class: "modal-body",
+                    p { "This session has ended. Sign in again to carry on." }
+                }
+                div { class: "modal-footer",
+                    Link { to: Route::Login { error: None }, class: "btn btn-primary", "Sign In" }
+                }
+            }
+        }
+    }
+}
+
+/// Shows which Kanidm instance is being managed, so admins juggling several
+/// environments don't make changes to the wrong one.
+#[component]
+fn SidebarDomain() -> Element {
+    let domain = use_resource(api::get_domain_info);
+    let masking = use_masking();
+
+    match &*domain.read() {
+        Some(Ok(d)) => rsx! {
+            div {
+                class: "sidebar-domain",
+                title: "{d.name} ({masking.show(Sensitive::Id, &d.uuid.to_string())})",
+                div { class: "sidebar-domain-name", "{d.display_name}" }
+                div { class: "sidebar-domain-meta",
+                    "{d.name}"
+                    if let Some(version) = &d.version {
+                        " · v{version}"
+                    }
+                }
+            }
+        },
+        _ => rsx! {},
+    }
+}
+
+/// Which build of AuthIt! is running, to mention when reporting a problem.
+#[component]
+fn SidebarVersion() -> Element {
+    let info = use_resource(api::get_server_info);
+
+    match &*info.read() {
+        Some(Ok(info)) => {
+            let built = info
+                .built_at
+                .map(|at| format!("Built {}", format_timestamp(at)))
+                .unwrap_or_default();
+            rsx! {
+                div { class: "sidebar-version", title: "{built}", "AuthIt! {info.describe()}" }
+            }
+        }
+        _ => rsx! {},
+    }
+}
+
+/// The ticket the admin is working on, recorded with their changes so they
+/// can be traced back to the request that prompted them.
+#[component]
+fn SidebarTicket() -> Element {
+    let mut error_state = use_error();
+    let mut status = use_signal(|| None::<TicketStatus>);
+    let mut draft = use_signal(String::new);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(s) = api::get_ticket_status().await {
+                draft.set(s.ticket.clone().unwrap_or_default());
+                status.set(Some(s));
+            }
+        });
+    });
+
+    let Some(current) = status() else {
+        return rsx! {};
+    };
+
+    let save = move || {
+        let ticket = Some(draft().trim().to_string()).filter(|t| !t.is_empty());
+        if ticket == status().and_then(|s| s.ticket) {
+            return;
+        }
+        spawn(async move {
+            match api::set_ticket(ticket.clone()).await {
+                Ok(()) => {
+                    if let Some(s) = status.write().as_mut() {
+                        s.ticket = ticket;
+                    }
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "sidebar-ticket",
+            label { class: "sidebar-section-title", r#for: "sidebar_ticket", "Ticket" }
+            input {
+                id: "sidebar_ticket",
+                class: if current.required && current.ticket.is_none() { "form-input sidebar-ticket-missing" } else { "form-input" },
+                placeholder: if current.required { "Required for dangerous changes" } else { "e.g. OPS-1234" },
+                value: "{draft}",
+                oninput: move |e| draft.set(e.value()),
+                onchange: move |_| save(),
+            }
+        }
+    }
+}
+
+/// Masks emails, ids and tokens everywhere, for sharing the screen or taking
+/// screenshots. It's saved with the admin's preferences, so it stays on
+/// across reloads until they turn it off.
+#[component]
+fn SidebarMasking() -> Element {
+    let mut error_state = use_error();
+    let mut masking = use_masking();
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(prefs) = api::get_preferences().await {
+                masking.0.set(prefs.mask_data);
+            }
+        });
+    });
+
+    let toggle = move |e: FormEvent| {
+        let on = e.checked();
+        masking.0.set(on);
+        spawn(async move {
+            let result = match api::get_preferences().await {
+                Ok(mut prefs) => {
+                    prefs.mask_data = on;
+                    api::set_preferences(prefs).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error_state.set_server_error(&e);
+            }
+        });
+    };
+
+    rsx! {
+        label { class: "checkbox-label sidebar-masking",
+            input { r#type: "checkbox", checked: masking.is_on(), onchange: toggle }
+            span { "Mask personal data" }
+        }
+    }
+}
+
+/// The admin's color scheme, set on the layout so its colors follow.
+#[derive(Clone, Copy)]
+struct ThemeChoice(Signal<Theme>);
+
+/// Picks light or dark colors, or follows the system's. It's saved with the
+/// admin's preferences.
+#[component]
+fn SidebarTheme() -> Element {
+    let mut error_state = use_error();
+    let mut theme = use_context::<ThemeChoice>().0;
+
+    let choose = move |e: FormEvent| {
+        let Some(chosen) = Theme::from_name(&e.value()) else {
+            return;
+        };
+        theme.set(chosen);
+        spawn(async move {
+            let result = match api::get_preferences().await {
+                Ok(mut prefs) => {
+                    prefs.theme = chosen;
+                    api::set_preferences(prefs).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error_state.set_server_error(&e);
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "sidebar-section",
+            label { class: "sidebar-section-title", r#for: "sidebar_theme", "Theme" }
+            select {
+                id: "sidebar_theme",
+                class: "form-input",
+                onchange: choose,
+                for choice in Theme::ALL {
+                    option {
+                        value: choice.as_str(),
+                        selected: choice == theme(),
+                        "{choice.label()}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Users the admin pinned or viewed recently, for getting back to them.
+#[component]
+fn SidebarShortcuts() -> Element {
+    let mut shortcuts = use_shortcuts();
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(s) = api::get_shortcuts().await {
+                shortcuts.set(s);
+            }
+        });
+    });
+
+    let shortcuts = shortcuts.read();
+    rsx! {
+        for (label, users) in [("Pinned", &shortcuts.pinned), ("Recent", &shortcuts.recent)] {
+            if !users.is_empty() {
+                div { class: "sidebar-section", key: "{label}",
+                    div { class: "sidebar-section-title", "{label}" }
+                    for user in users.iter() {
+                        Link {
+                            key: "{user.user_id}",
+                            to: Route::user_detail(user.user_id),
+                            class: "sidebar-shortcut",
+                            "{user.display_name}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shown instead of a page the user can't use, saying why, so they don't
+/// land on one whose every request is refused.
+#[component]
+fn Forbidden(reason: String, home: Option<Route>) -> Element {
+    rsx! {
+        div { class: "card",
+            div { class: "empty-state",
+                p { "{reason}" }
+                if let Some(home) = home {
+                    Link { to: home, class: "btn btn-secondary", "Go to a page you can use" }
+                } else {
+                    p { class: "text-muted text-sm", "There's nothing in AuthIt! you can use yet." }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn AuthenticatedLayout() -> Element {
     let user = use_server_future(api::get_current_user)?;
+    let capabilities = use_server_future(api::get_capabilities)?;
+    let route: Route = use_route();
 
     match &*user.read() {
         Some(Ok(Some(person))) => {
             let person = person.clone();
-            use_context_provider(|| ErrorState(Signal::new(None)));
-            let initial = person
-                .display_name
-                .chars()
-                .next()
-                .unwrap_or('?')
-                .to_uppercase()
-                .to_string();
+            let error_state =
+                use_context_provider(|| ErrorState(Signal::new(None), Signal::new(0)));
+            let capabilities = capabilities
+                .read()
+                .as_ref()
+                .and_then(|c| c.as_ref().ok())
+                .cloned()
+                .unwrap_or_default();
+            use_context_provider(|| capabilities.clone());
+            use_context_provider(|| Signal::new(UserShortcuts::default()));
+            use_context_provider(|| Masking(Signal::new(false)));
+            let mut theme = use_context_provider(|| ThemeChoice(Signal::new(Theme::default()))).0;
+            let mut favorites = use_context_provider(|| FavoriteGroups(Signal::new(Vec::new()))).0;
+            let mut presence = use_context_provider(|| Presence(Signal::new(HashSet::new())));
+            let is_admin = capabilities.is_admin();
+            use_effect(move || {
+                // Only admins have preferences.
+                if !is_admin {
+                    return;
+                }
+                spawn(async move {
+                    if let Ok(prefs) = api::get_preferences().await {
+                        theme.set(prefs.theme);
+                        favorites.set(prefs.favorite_groups);
+                    }
+                });
+            });
+            use_effect(move || {
+                // Only admins are told who's online.
+                if !is_admin {
+                    return;
+                }
+                spawn(async move {
+                    loop {
+                        if let Ok(online) = api::get_online_admins().await {
+                            presence.0.set(online.into_iter().collect());
+                        }
+                        sleep(PRESENCE_POLL_MS).await;
+                    }
+                });
+            });
+            let unmet = capabilities.unmet(route.requirement());
+            let home = Route::home(&capabilities);
+
+            // Everyone lands on the dashboard after signing in, so send those
+            // who can't use it somewhere they can, rather than telling them
+            // why not.
+            if unmet.is_some()
+                && route == (Route::Dashboard {})
+                && let Some(home) = home.clone()
+            {
+                navigator().replace(home);
+            }
 
             rsx! {
-                div { class: "app-layout",
+                div { class: "app-layout", "data-theme": theme().as_str(),
                     // Sidebar
                     aside { class: "sidebar",
                         div { class: "sidebar-header",
                             span { class: "sidebar-logo", "AuthIt!" }
                         }
                         nav { class: "sidebar-nav",
-                            NavLink { to: Route::Dashboard {}, "Dashboard" }
-                            NavLink { to: Route::users(), "Users" }
+                            if capabilities.is_admin() {
+                                NavLink { to: Route::Dashboard {}, "Dashboard" }
+                            }
+                            if capabilities.allows(Capability::ReadPersons) {
+                                NavLink { to: Route::users(), "Users" }
+                            }
+                            if capabilities.is_admin() {
+                                NavLink { to: Route::AccountRequests {}, "Requests" }
+                                NavLink { to: Route::Offboardings {}, "Offboarding" }
+                                NavLink { to: Route::Rotations {}, "Rotation" }
+                                NavLink { to: Route::Reports {}, "Reports" }
+                            }
+                            if capabilities.directory() {
+                                NavLink { to: Route::Directory {}, "Directory" }
+                            }
+                            if capabilities.group_owner() {
+                                NavLink { to: Route::MyGroups {}, "My Groups" }
+                            }
+                            if capabilities.profile() {
+                                NavLink { to: Route::Profile {}, "Profile" }
+                            }
+                            if capabilities.is_admin() {
+                                NavLink { to: Route::Sessions {}, "Sessions" }
+                                NavLink { to: Route::Settings {}, "Settings" }
+                                NavLink { to: Route::Diagnostics {}, "Diagnostics" }
+                                NavLink { to: Route::BackgroundJobs {}, "Jobs" }
+                                NavLink { to: Route::EmailQueue {}, "Email" }
+                                NavLink { to: Route::Audit {}, "Audit" }
+                            }
+                            if capabilities.allows(Capability::ReadPersons) {
+                                SidebarShortcuts {}
+                            }
                         }
                         div { class: "sidebar-footer",
+                            if capabilities.is_admin() {
+                                SidebarTicket {}
+                                SidebarMasking {}
+                                SidebarTheme {}
+                            }
+                            SidebarDomain {}
                             div { class: "sidebar-user",
-                                div { class: "sidebar-avatar", "{initial}" }
+                                Avatar {
+                                    user_id: person.uuid,
+                                    name: person.display_name.clone(),
+                                    class: "sidebar-avatar",
+                                }
                                 div { class: "sidebar-user-info",
                                     div { class: "sidebar-user-name", "{person.display_name}" }
                                     div { class: "sidebar-user-role", "{person.name}" }
                                 }
                             }
                             a { href: "/auth/logout", rel: "external", class: "sidebar-logout", "Sign out" }
+                            SidebarVersion {}
                         }
                     }
                     // Main content
-                    main { class: "main-content",
+                    SessionLock {}
+                    main {
+                        class: "main-content",
+                        class: if error_state.retry_in() > 0 { "retry-waiting" },
+                        AnnouncementBanner {}
                         ErrorBanner {}
-                        Outlet::<Route> {}
+                        if let Some(reason) = unmet {
+                            Forbidden { reason, home }
+                        } else {
+                            Outlet::<Route> {}
+                        }
                     }
                 }
             }
@@ -0,0 +1,572 @@
+use super::{
+    components::{Masked, Sensitive, format_timestamp},
+    profile::ProfileChangeStatusBadge,
+};
+use crate::{use_capabilities, use_error};
+use dioxus::prelude::*;
+use types::{
+    account_request::{AccountRequest, AccountRequestApproval, AccountRequestStatus},
+    capability::Capability,
+    profile_change::{ProfileChange, ProfileChangeStatus},
+    provision::{ProvisionApproval, ProvisionApprovalOutcome, ProvisionApprovalStatus},
+};
+use uuid::Uuid;
+
+#[component]
+pub fn AccountRequests() -> Element {
+    let mut error_state = use_error();
+    let mut requests = use_signal(Vec::<AccountRequest>::new);
+    let mut loading = use_signal(|| true);
+    let mut busy = use_signal(|| None::<Uuid>);
+    let mut approval = use_signal(|| None::<(String, AccountRequestApproval)>);
+    let mut denying = use_signal(|| None::<AccountRequest>);
+    let capabilities = use_capabilities();
+
+    let refresh = move || {
+        spawn(async move {
+            match api::list_account_requests().await {
+                Ok(r) => requests.set(r),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loading.set(false);
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let approve = move |request: AccountRequest| {
+        spawn(async move {
+            busy.set(Some(request.id));
+            match api::approve_account_request(request.id).await {
+                Ok(a) => {
+                    approval.set(Some((request.email, a)));
+                    refresh();
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            busy.set(None);
+        });
+    };
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Account Requests" }
+                    p { class: "page-subtitle", "Requests from people without a provision link." }
+                }
+            }
+            if let Some((email, a)) = approval.read().as_ref() {
+                div { class: "alert alert-success",
+                    if a.emailed {
+                        "Approved. A provision link was emailed to {email}."
+                    } else {
+                        "Approved, but email isn't set up. Send this single-use link to {email}:"
+                        div { class: "code-block", "{a.provision_url}" }
+                    }
+                }
+            }
+            if loading() {
+                div { class: "loading", "Loading requests..." }
+            } else if requests.read().is_empty() {
+                div { class: "card",
+                    div { class: "empty-state", "No account requests yet." }
+                }
+            } else {
+                div { class: "card",
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Requested" }
+                                    th { "Name" }
+                                    th { "Email" }
+                                    th { "Justification" }
+                                    th { "Status" }
+                                    th {}
+                                }
+                            }
+                            tbody {
+                                for request in requests.read().iter().cloned() {
+                                    tr { key: "{request.id}",
+                                        td { {format_timestamp(request.created_at)} }
+                                        td { "{request.name}" }
+                                        td {
+                                            Masked { kind: Sensitive::Email, text: request.email.clone() }
+                                        }
+                                        td { "{request.justification}" }
+                                        td {
+                                            RequestStatus { request: request.clone() }
+                                        }
+                                        td {
+                                            if request.status == AccountRequestStatus::Pending {
+                                                div { class: "page-header-actions",
+                                                    button {
+                                                        class: "btn btn-primary",
+                                                        disabled: busy().is_some() || !capabilities.allows(Capability::ManagePersons),
+                                                        title: capabilities.why_not(Capability::ManagePersons),
+                                                        onclick: {
+                                                            let request = request.clone();
+                                                            move |_| approve(request.clone())
+                                                        },
+                                                        if busy() == Some(request.id) { "Approving..." } else { "Approve" }
+                                                    }
+                                                    button {
+                                                        class: "btn btn-danger",
+                                                        disabled: busy().is_some(),
+                                                        onclick: {
+                                                            let request = request.clone();
+                                                            move |_| denying.set(Some(request.clone()))
+                                                        },
+                                                        "Deny"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(request) = denying() {
+                DenyModal {
+                    request,
+                    on_close: move |_| denying.set(None),
+                    on_denied: move |_| {
+                        denying.set(None);
+                        refresh();
+                    },
+                }
+            }
+            if capabilities.provision_links() {
+                ProvisionApprovals {}
+            }
+            ProfileChanges {}
+        }
+    }
+}
+
+/// Accounts made with provision links that hold them for approval.
+#[component]
+fn ProvisionApprovals() -> Element {
+    let mut error_state = use_error();
+    let mut approvals = use_signal(Vec::<ProvisionApproval>::new);
+    let mut busy = use_signal(|| None::<Uuid>);
+    let mut outcome = use_signal(|| None::<(String, ProvisionApprovalOutcome)>);
+    let capabilities = use_capabilities();
+
+    let refresh = move || {
+        spawn(async move {
+            match api::list_provision_approvals().await {
+                Ok(a) => approvals.set(a),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let approve = move |approval: ProvisionApproval| {
+        spawn(async move {
+            busy.set(Some(approval.id));
+            match api::approve_provision_approval(approval.id).await {
+                Ok(o) => {
+                    outcome.set(Some((approval.email, o)));
+                    refresh();
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            busy.set(None);
+        });
+    };
+
+    let reject = move |id: Uuid| {
+        spawn(async move {
+            busy.set(Some(id));
+            match api::reject_provision_approval(id).await {
+                Ok(()) => refresh(),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            busy.set(None);
+        });
+    };
+
+    if approvals.read().is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Provisioned Accounts" }
+            }
+            if let Some((email, o)) = outcome.read().as_ref() {
+                div { class: "card-body",
+                    div { class: "alert alert-success",
+                        if o.emailed {
+                            "Approved. A link to set up their credentials was emailed to "
+                            Masked { kind: Sensitive::Email, text: email.clone() }
+                            "."
+                        } else {
+                            "Approved, but email isn't set up. Send this link to "
+                            Masked { kind: Sensitive::Email, text: email.clone() }
+                            ":"
+                            div { class: "code-block",
+                                Masked { kind: Sensitive::Token, text: o.reset_link.url.clone() }
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "table-container",
+                table {
+                    thead {
+                        tr {
+                            th { "Created" }
+                            th { "User" }
+                            th { "Email" }
+                            th { "Groups" }
+                            th { "Status" }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        for approval in approvals.read().iter().cloned() {
+                            tr { key: "{approval.id}",
+                                td { {format_timestamp(approval.created_at)} }
+                                td {
+                                    div { "{approval.user_name}" }
+                                    div { class: "text-muted text-sm", "{approval.display_name}" }
+                                }
+                                td {
+                                    Masked { kind: Sensitive::Email, text: approval.email.clone() }
+                                }
+                                td {
+                                    {approval.groups.join(", ")}
+                                    if let Some(by) = &approval.link_created_by {
+                                        div { class: "text-muted text-sm", "Link made by {by}" }
+                                    }
+                                }
+                                td {
+                                    ProvisionApprovalStatusBadge { approval: approval.clone() }
+                                }
+                                td {
+                                    if approval.status == ProvisionApprovalStatus::Pending {
+                                        div { class: "page-header-actions",
+                                            button {
+                                                class: "btn btn-primary",
+                                                disabled: busy().is_some() || !capabilities.allows(Capability::ManagePersons),
+                                                title: capabilities.why_not(Capability::ManagePersons),
+                                                onclick: {
+                                                    let approval = approval.clone();
+                                                    move |_| approve(approval.clone())
+                                                },
+                                                if busy() == Some(approval.id) { "Approving..." } else { "Approve" }
+                                            }
+                                            button {
+                                                class: "btn btn-danger",
+                                                disabled: busy().is_some() || !capabilities.allows(Capability::ManagePersons),
+                                                title: capabilities.why_not(Capability::ManagePersons),
+                                                onclick: move |_| reject(approval.id),
+                                                "Reject"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProvisionApprovalStatusBadge(approval: ProvisionApproval) -> Element {
+    let decided = match (&approval.decided_by, approval.decided_at) {
+        (Some(by), Some(at)) => format!("by {by}, {}", format_timestamp(at)),
+        _ => String::new(),
+    };
+
+    let (class, label) = match approval.status {
+        ProvisionApprovalStatus::Pending => ("badge badge-primary", "Pending"),
+        ProvisionApprovalStatus::Approved => ("badge", "Approved"),
+        ProvisionApprovalStatus::Rejected => ("badge", "Rejected"),
+    };
+
+    rsx! {
+        span { class, "{label}" }
+        if !decided.is_empty() {
+            div { class: "text-muted text-sm", "{decided}" }
+        }
+    }
+}
+
+/// Changes people asked for to their own display name or email.
+#[component]
+fn ProfileChanges() -> Element {
+    let mut error_state = use_error();
+    let mut changes = use_signal(Vec::<ProfileChange>::new);
+    let mut busy = use_signal(|| None::<Uuid>);
+    let mut denying = use_signal(|| None::<ProfileChange>);
+    let capabilities = use_capabilities();
+
+    let refresh = move || {
+        spawn(async move {
+            match api::list_profile_changes().await {
+                Ok(c) => changes.set(c),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let approve = move |id: Uuid| {
+        spawn(async move {
+            busy.set(Some(id));
+            match api::approve_profile_change(id).await {
+                Ok(()) => refresh(),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            busy.set(None);
+        });
+    };
+
+    if changes.read().is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Profile Changes" }
+            }
+            div { class: "table-container",
+                table {
+                    thead {
+                        tr {
+                            th { "Requested" }
+                            th { "User" }
+                            th { "Field" }
+                            th { "Change" }
+                            th { "Status" }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        for change in changes.read().iter().cloned() {
+                            tr { key: "{change.id}",
+                                td { {format_timestamp(change.created_at)} }
+                                td { "{change.user_name}" }
+                                td { "{change.field.label()}" }
+                                td { "{change.old_value} → {change.new_value}" }
+                                td {
+                                    ProfileChangeStatusBadge { change: change.clone() }
+                                }
+                                td {
+                                    if change.status == ProfileChangeStatus::Pending {
+                                        div { class: "page-header-actions",
+                                            button {
+                                                class: "btn btn-primary",
+                                                disabled: busy().is_some() || !capabilities.allows(Capability::ManagePersons),
+                                                title: capabilities.why_not(Capability::ManagePersons),
+                                                onclick: move |_| approve(change.id),
+                                                if busy() == Some(change.id) { "Approving..." } else { "Approve" }
+                                            }
+                                            button {
+                                                class: "btn btn-danger",
+                                                disabled: busy().is_some(),
+                                                onclick: {
+                                                    let change = change.clone();
+                                                    move |_| denying.set(Some(change.clone()))
+                                                },
+                                                "Deny"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(change) = denying() {
+            DenyProfileChangeModal {
+                change,
+                on_close: move |_| denying.set(None),
+                on_denied: move |_| {
+                    denying.set(None);
+                    refresh();
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn RequestStatus(request: AccountRequest) -> Element {
+    let decided = match (&request.decided_by, request.decided_at) {
+        (Some(by), Some(at)) => format!("by {by}, {}", format_timestamp(at)),
+        _ => String::new(),
+    };
+
+    let (class, label) = match request.status {
+        AccountRequestStatus::Pending => ("badge badge-primary", "Pending"),
+        AccountRequestStatus::Approved => ("badge", "Approved"),
+        AccountRequestStatus::Denied => ("badge", "Denied"),
+    };
+
+    rsx! {
+        span { class, "{label}" }
+        if !decided.is_empty() {
+            div { class: "text-muted text-sm", "{decided}" }
+        }
+        if let Some(reason) = &request.reason {
+            div { class: "text-muted text-sm", "{reason}" }
+        }
+    }
+}
+
+#[component]
+fn DenyModal(
+    request: AccountRequest,
+    on_close: EventHandler<()>,
+    on_denied: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut reason = use_signal(String::new);
+    let mut denying = use_signal(|| false);
+    let id = request.id;
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !denying() { on_close.call(()) },
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Deny Request" }
+                    if !denying() {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    p { "Deny the request from " strong { "{request.name}" } "?" }
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "deny_reason", "Reason" }
+                        textarea {
+                            id: "deny_reason",
+                            class: "form-input",
+                            rows: "3",
+                            value: "{reason}",
+                            oninput: move |e| reason.set(e.value()),
+                        }
+                        p { class: "text-muted text-sm", "This is emailed to {request.email}." }
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: denying(),
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-danger",
+                        disabled: denying() || reason.read().trim().is_empty(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                denying.set(true);
+                                match api::deny_account_request(id, reason()).await {
+                                    Ok(_) => on_denied.call(()),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                denying.set(false);
+                            });
+                        },
+                        if denying() { "Denying..." } else { "Deny" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn DenyProfileChangeModal(
+    change: ProfileChange,
+    on_close: EventHandler<()>,
+    on_denied: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut reason = use_signal(String::new);
+    let mut denying = use_signal(|| false);
+    let id = change.id;
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !denying() { on_close.call(()) },
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Deny Change" }
+                    if !denying() {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    p {
+                        "Deny " strong { "{change.user_name}" }
+                        "'s change to their {change.field.label().to_lowercase()}?"
+                    }
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "deny_profile_reason", "Reason" }
+                        textarea {
+                            id: "deny_profile_reason",
+                            class: "form-input",
+                            rows: "3",
+                            value: "{reason}",
+                            oninput: move |e| reason.set(e.value()),
+                        }
+                        p { class: "text-muted text-sm", "This is shown on their Profile page." }
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: denying(),
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-danger",
+                        disabled: denying() || reason.read().trim().is_empty(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                denying.set(true);
+                                match api::deny_profile_change(id, reason()).await {
+                                    Ok(()) => on_denied.call(()),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                denying.set(false);
+                            });
+                        },
+                        if denying() { "Denying..." } else { "Deny" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::components::{format_relative, format_timestamp};
+use crate::{Route, use_error};
+use dioxus::prelude::*;
+use types::activity::{ActivityMatch, ActivitySubject, MatchRun};
+
+#[component]
+pub fn Audit() -> Element {
+    let mut error_state = use_error();
+    let mut search = use_signal(String::new);
+    let mut results = use_signal(|| None::<Vec<ActivityMatch>>);
+    let mut searching = use_signal(|| false);
+
+    let mut run_search = move || {
+        let text = search();
+        if text.trim().is_empty() {
+            results.set(None);
+            return;
+        }
+        spawn(async move {
+            searching.set(true);
+            match api::search_activity(text).await {
+                Ok(r) => results.set(Some(r)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            searching.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Audit" }
+                    p { class: "page-subtitle",
+                        "Search everything that's happened by who did it, who or what it was done to, the ticket, or what was done."
+                    }
+                }
+            }
+            div { class: "card",
+                div { class: "card-header",
+                    input {
+                        class: "form-input",
+                        r#type: "search",
+                        aria_label: "Search the audit history",
+                        placeholder: "e.g. jsmith gitlab, or OPS-1234",
+                        value: "{search}",
+                        oninput: move |e| search.set(e.value()),
+                        onkeydown: move |e| {
+                            if e.key() == Key::Enter {
+                                run_search();
+                            }
+                        },
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: searching(),
+                        onclick: move |_| run_search(),
+                        if searching() { "Searching..." } else { "Search" }
+                    }
+                }
+                match results() {
+                    None => rsx! {
+                        div { class: "empty-state",
+                            "Every word must match, and words match the start of longer ones, so \"jsm\" finds jsmith."
+                        }
+                    },
+                    Some(results) if results.is_empty() => rsx! {
+                        div { class: "empty-state", "Nothing matches your search." }
+                    },
+                    Some(results) => rsx! {
+                        ul { class: "activity-list",
+                            for m in results {
+                                li { key: "{m.activity.id}", class: "activity-item",
+                                    div { class: "activity-summary",
+                                        div {
+                                            Highlighted { runs: m.summary }
+                                        }
+                                        if m.activity.actor.is_some() {
+                                            span { class: "text-muted text-sm",
+                                                "By "
+                                                Highlighted { runs: m.actor }
+                                            }
+                                            " "
+                                        }
+                                        if let Some(ActivitySubject::User { id, .. }) = &m.activity.subject {
+                                            Link { to: Route::user_detail(*id), class: "text-sm",
+                                                Highlighted { runs: m.subject }
+                                            }
+                                        } else if m.activity.subject.is_some() {
+                                            span { class: "text-muted text-sm",
+                                                "Group "
+                                                Highlighted { runs: m.subject }
+                                            }
+                                        }
+                                        if m.activity.ticket.is_some() {
+                                            span { class: "badge", title: "Ticket",
+                                                Highlighted { runs: m.ticket }
+                                            }
+                                        }
+                                    }
+                                    span {
+                                        class: "activity-time",
+                                        title: format_timestamp(m.activity.at),
+                                        {format_relative(m.activity.at)}
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Text with the parts that matched a search marked.
+#[component]
+fn Highlighted(runs: Vec<MatchRun>) -> Element {
+    rsx! {
+        for run in runs {
+            if run.matched {
+                mark { "{run.text}" }
+            } else {
+                "{run.text}"
+            }
+        }
+    }
+}
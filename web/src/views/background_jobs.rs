@@ -0,0 +1,138 @@
+use super::components::{format_relative, format_timestamp, sleep};
+use crate::use_error;
+use dioxus::prelude::*;
+use types::background_job::{BackgroundJob, BackgroundJobRun};
+
+#[component]
+pub fn BackgroundJobs() -> Element {
+    let mut error_state = use_error();
+    let mut jobs = use_signal(|| None::<Vec<BackgroundJob>>);
+    let mut starting = use_signal(|| None::<String>);
+
+    // Poll, so runs started here or by the schedule show as they finish.
+    use_effect(move || {
+        spawn(async move {
+            loop {
+                match api::list_background_jobs().await {
+                    Ok(j) => jobs.set(Some(j)),
+                    Err(e) => {
+                        error_state.set_server_error(&e);
+                        break;
+                    }
+                }
+                sleep(5_000).await;
+            }
+        });
+    });
+
+    let run = move |name: String| {
+        spawn(async move {
+            starting.set(Some(name.clone()));
+            match api::run_background_job(name).await {
+                Ok(()) => {
+                    if let Ok(j) = api::list_background_jobs().await {
+                        jobs.set(Some(j));
+                    }
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            starting.set(None);
+        });
+    };
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Background Jobs" }
+                    p { class: "page-subtitle",
+                        "What AuthIt! does on a schedule. Run one now to retry it without waiting or restarting."
+                    }
+                }
+            }
+            div { class: "card",
+                if let Some(jobs) = jobs() {
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Job" }
+                                    th { "Runs Every" }
+                                    th { "Last Run" }
+                                    th { "Result" }
+                                    th {}
+                                }
+                            }
+                            tbody {
+                                for job in jobs {
+                                    tr { key: "{job.name}",
+                                        td { "{job.name}" }
+                                        td { {format_interval(job.interval_secs)} }
+                                        td {
+                                            if let Some(last_run) = &job.last_run {
+                                                span { title: format_timestamp(last_run.started_at),
+                                                    {format_relative(last_run.started_at)}
+                                                }
+                                                if let Some(by) = &last_run.started_by {
+                                                    div { class: "text-muted text-sm", "Run by {by}" }
+                                                }
+                                            } else {
+                                                span { class: "text-muted", "Not yet" }
+                                            }
+                                        }
+                                        td {
+                                            if let Some(last_run) = job.last_run.clone() {
+                                                RunResult { last_run }
+                                            }
+                                        }
+                                        td {
+                                            button {
+                                                class: "btn btn-secondary",
+                                                disabled: starting().is_some()
+                                                    || job.last_run.as_ref().is_some_and(BackgroundJobRun::is_running),
+                                                onclick: {
+                                                    let name = job.name.clone();
+                                                    move |_| run(name.clone())
+                                                },
+                                                if starting().as_ref() == Some(&job.name) { "Starting..." } else { "Run Now" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    div { class: "card-body",
+                        div { class: "loading", "Loading jobs..." }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RunResult(last_run: BackgroundJobRun) -> Element {
+    rsx! {
+        if last_run.is_running() {
+            span { class: "badge badge-primary", "Running" }
+        } else if let Some(error) = &last_run.error {
+            span { class: "badge badge-warning", "Failed" }
+            div { class: "text-muted text-sm", "{error}" }
+        } else {
+            span { class: "badge", "Succeeded" }
+        }
+    }
+}
+
+/// Like "5 minutes" or "1 hour".
+fn format_interval(secs: u64) -> String {
+    let (n, unit) = match secs {
+        ..60 => (secs, "second"),
+        60..3600 => (secs / 60, "minute"),
+        _ => (secs / 3600, "hour"),
+    };
+    let s = if n == 1 { "" } else { "s" };
+    format!("{n} {unit}{s}")
+}
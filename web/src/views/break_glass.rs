@@ -0,0 +1,41 @@
+use dioxus::prelude::*;
+
+/// Local login for when the Kanidm OAuth flow is broken.
+#[component]
+pub fn BreakGlass(error: Option<String>) -> Element {
+    rsx! {
+        div { class: "login-page",
+            div { class: "login-card",
+                div { class: "login-header",
+                    h1 { class: "login-title", "Break-glass Sign In" }
+                    p { class: "login-subtitle",
+                        "For emergencies only. All attempts are logged."
+                    }
+                }
+                if let Some(error) = &error {
+                    div { class: "login-error", "{error}" }
+                }
+                form {
+                    action: "/auth/break-glass",
+                    method: "post",
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "password", "Password" }
+                        input {
+                            id: "password",
+                            name: "password",
+                            class: "form-input",
+                            r#type: "password",
+                            autocomplete: "current-password",
+                            required: true,
+                        }
+                    }
+                    button {
+                        r#type: "submit",
+                        class: "btn btn-danger login-btn",
+                        "Sign in"
+                    }
+                }
+            }
+        }
+    }
+}
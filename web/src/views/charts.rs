@@ -0,0 +1,62 @@
+use dioxus::prelude::*;
+
+/// One bar in a [`BarChart`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartBar {
+    /// Shown when hovering over the bar.
+    pub label: String,
+    pub value: u64,
+}
+
+/// Bars side by side, scaled so the tallest fills the chart.
+#[component]
+pub fn BarChart(bars: Vec<ChartBar>) -> Element {
+    let max = bars.iter().map(|bar| bar.value).max().unwrap_or(0).max(1);
+
+    rsx! {
+        div { class: "bar-chart",
+            for bar in bars.iter() {
+                div {
+                    key: "{bar.label}",
+                    class: "bar-chart-column",
+                    title: "{bar.label}: {bar.value}",
+                    div { class: "bar-chart-bar", style: "height: {bar.value * 100 / max}%" }
+                }
+            }
+        }
+    }
+}
+
+/// A small line showing how values rose and fell, scaled to fit.
+#[component]
+pub fn Sparkline(values: Vec<i64>) -> Element {
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    let range = (max - min).max(1) as f64;
+    let step = 100.0 / values.len().saturating_sub(1).max(1) as f64;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let x = i as f64 * step;
+            let y = 24.0 - (value - min) as f64 / range * 24.0;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    rsx! {
+        svg {
+            class: "sparkline",
+            view_box: "0 0 100 24",
+            preserve_aspect_ratio: "none",
+            polyline {
+                points,
+                fill: "none",
+                stroke: "currentColor",
+                stroke_width: "1.5",
+                vector_effect: "non-scaling-stroke",
+            }
+        }
+    }
+}
@@ -1,37 +1,785 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use dioxus::prelude::*;
-use types::kanidm::Group;
+use dioxus::{document::eval, prelude::*};
+use jiff::Timestamp;
+use types::{
+    job::{Job, JobItem, JobOutput},
+    kanidm::{EntryKind, Group},
+    login_event::LoginEvent,
+    provision::FieldRequirement,
+    validation::FieldErrors,
+};
 use uuid::Uuid;
 
-/// A reusable component that renders a list of groups with checkboxes.
+use crate::{ErrorState, use_error};
+
+fn display_tz() -> Option<jiff::tz::TimeZone> {
+    jiff::tz::TimeZone::get("America/Los_Angeles").ok()
+}
+
+/// Format a timestamp for display.
+pub fn format_timestamp(ts: Timestamp) -> String {
+    display_tz()
+        .map(|tz| ts.to_zoned(tz))
+        .map(|zdt| zdt.strftime("%b %d, %Y at %I:%M %p %Z").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// How long ago a timestamp was, like "5 minutes ago".
+pub fn format_relative(ts: Timestamp) -> String {
+    let secs = Timestamp::now().as_second() - ts.as_second();
+    let (n, unit) = match secs {
+        ..60 => return "just now".to_string(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    let s = if n == 1 { "" } else { "s" };
+    format!("{n} {unit}{s} ago")
+}
+
+/// Format a timestamp as the value of a `datetime-local` input.
+pub fn to_datetime_input(ts: Timestamp) -> String {
+    display_tz()
+        .map(|tz| ts.to_zoned(tz).strftime("%Y-%m-%dT%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Parse the value of a `datetime-local` input, in the same time zone we
+/// display timestamps in.
+pub fn parse_datetime_input(value: &str) -> Option<Timestamp> {
+    let datetime: jiff::civil::DateTime = value.parse().ok()?;
+    let zoned = datetime.to_zoned(display_tz()?).ok()?;
+    Some(zoned.timestamp())
+}
+
+/// Whether emails, ids and tokens are masked, so the screen can be shared or
+/// screenshotted without showing them.
+#[derive(Clone, Copy)]
+pub struct Masking(pub Signal<bool>);
+
+impl Masking {
+    pub fn is_on(&self) -> bool {
+        (self.0)()
+    }
+
+    /// `text` as it should be shown.
+    pub fn show(&self, kind: Sensitive, text: &str) -> String {
+        if self.is_on() {
+            kind.mask(text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+pub fn use_masking() -> Masking {
+    use_context::<Masking>()
+}
+
+/// Groups the admin starred, listed first in group pickers. They're saved
+/// with the admin's preferences.
+#[derive(Clone, Copy)]
+pub struct FavoriteGroups(pub Signal<Vec<Uuid>>);
+
+impl FavoriteGroups {
+    pub fn contains(&self, group_id: Uuid) -> bool {
+        self.0.read().contains(&group_id)
+    }
+
+    /// Star or unstar a group, and save it.
+    pub fn set(mut self, group_id: Uuid, favorite: bool, mut error_state: ErrorState) {
+        self.0.with_mut(|ids| {
+            ids.retain(|id| *id != group_id);
+            if favorite {
+                ids.push(group_id);
+            }
+        });
+        spawn(async move {
+            let result = match api::get_preferences().await {
+                Ok(mut prefs) => {
+                    prefs.set_favorite_group(group_id, favorite);
+                    api::set_preferences(prefs).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error_state.set_server_error(&e);
+            }
+        });
+    }
+}
+
+/// What sort of thing is shown, which decides how much of it masking keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sensitive {
+    /// One or more email addresses, separated by commas. The first letter
+    /// and top-level domain are kept.
+    Email,
+    /// A UUID. Its shape is kept.
+    Id,
+    /// A secret, or a link holding one. Only a link's origin is kept.
+    Token,
+}
+
+impl Sensitive {
+    const DOTS: &str = "••••";
+
+    pub fn mask(&self, text: &str) -> String {
+        match self {
+            Sensitive::Email => text
+                .split(", ")
+                .map(|address| {
+                    let first = address.chars().next().unwrap_or('•');
+                    let tld = address.rsplit_once('.').map_or("", |(_, tld)| tld);
+                    format!("{first}{}@{}.{tld}", Self::DOTS, Self::DOTS)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            Sensitive::Id => text
+                .chars()
+                .map(|c| if c == '-' { c } else { '•' })
+                .collect(),
+            Sensitive::Token => {
+                let origin = text
+                    .split_once("://")
+                    .map(|(scheme, rest)| {
+                        let host = rest.split('/').next().unwrap_or_default();
+                        format!("{scheme}://{host}/")
+                    })
+                    .unwrap_or_default();
+                format!("{origin}{}{}", Self::DOTS, Self::DOTS)
+            }
+        }
+    }
+}
+
+/// Text that's masked while masking is on.
+#[component]
+pub fn Masked(kind: Sensitive, text: String) -> Element {
+    let masking = use_masking();
+    if !masking.is_on() {
+        return rsx! { "{text}" };
+    }
+    rsx! {
+        span { class: "masked", title: "Masked for screen-sharing", {kind.mask(&text)} }
+    }
+}
+
+/// Wait for `ms` milliseconds. Browsers only have timers in JavaScript.
+pub async fn sleep(ms: u32) {
+    let js = format!("setTimeout(() => dioxus.send(null), {ms});");
+    let _ = eval(&js).recv::<()>().await;
+}
+
+/// Have the browser download `contents` as a file.
+pub fn download(filename: &str, content_type: &str, contents: String) {
+    let js = format!(
+        r#"
+        const contents = await dioxus.recv();
+        const url = URL.createObjectURL(new Blob([contents], {{ type: "{content_type}" }}));
+        const link = document.createElement("a");
+        link.href = url;
+        link.download = "{filename}";
+        link.click();
+        URL.revokeObjectURL(url);
+        "#
+    );
+    let _ = eval(&js).send(contents);
+}
+
+/// A CSV of each item in a job, and whether it worked.
+fn job_report(items: &[JobItem]) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+
+    // Reset link jobs are mostly wanted for their links.
+    if items.iter().any(|i| i.output.is_some()) {
+        let mut csv = String::from("user,link,expires_at,emailed,error\n");
+        for item in items {
+            let error = quote(item.error.as_deref().unwrap_or_default());
+            match &item.output {
+                Some(JobOutput::ResetLink {
+                    user_name,
+                    url,
+                    expires_at,
+                    emailed,
+                }) => csv.push_str(&format!(
+                    "{},{},{expires_at},{emailed},{error}\n",
+                    quote(user_name),
+                    quote(url.as_str()),
+                )),
+                None => csv.push_str(&format!("{},,,,{error}\n", quote(&item.label))),
+            }
+        }
+        return csv;
+    }
+
+    let mut csv = String::from("item,result,error\n");
+    for item in items {
+        let result = if item.error.is_some() { "failed" } else { "ok" };
+        csv.push_str(&format!(
+            "{},{result},{}\n",
+            quote(&item.label),
+            quote(item.error.as_deref().unwrap_or_default())
+        ));
+    }
+    csv
+}
+
+/// A background job, started or followed with [`use_job`].
+#[derive(Clone, Copy)]
+pub struct JobHandle {
+    id: Signal<Option<Uuid>>,
+    job: Signal<Option<Job>>,
+    starting: Signal<bool>,
+    error_state: ErrorState,
+}
+
+impl PartialEq for JobHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl JobHandle {
+    /// Start a job with a server fn that returns its id, then follow it.
+    pub fn start(&self, start: impl Future<Output = ServerFnResult<Uuid>> + 'static) {
+        let mut this = *self;
+        spawn(async move {
+            this.starting.set(true);
+            match start.await {
+                Ok(id) => this.id.set(Some(id)),
+                Err(e) => this.error_state.set_server_error(&e),
+            }
+            this.starting.set(false);
+        });
+    }
+
+    /// Follow a job that's already running.
+    pub fn follow(&mut self, id: Uuid) {
+        self.id.set(Some(id));
+    }
+
+    pub fn id(&self) -> Option<Uuid> {
+        (self.id)()
+    }
+
+    /// The job as of the last poll, once there's been one.
+    pub fn job(&self) -> Option<Job> {
+        (self.job)()
+    }
+
+    pub fn is_starting(&self) -> bool {
+        (self.starting)()
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.id.read().is_some()
+    }
+}
+
+/// Start or follow a bulk job, polling its progress until it finishes and
+/// then calling `on_finished`. Render it with [`JobStatus`].
+pub fn use_job(on_finished: impl FnMut(Job) + 'static) -> JobHandle {
+    let error_state = use_error();
+    let id = use_signal(|| None::<Uuid>);
+    let mut job = use_signal(|| None::<Job>);
+    let starting = use_signal(|| false);
+    let on_finished = use_callback(on_finished);
+
+    use_effect(move || {
+        let Some(job_id) = id() else {
+            return;
+        };
+        let mut error_state = error_state;
+        spawn(async move {
+            loop {
+                match api::get_job(job_id).await {
+                    Ok(j) if j.is_finished() => {
+                        job.set(Some(j.clone()));
+                        on_finished.call(j);
+                        break;
+                    }
+                    Ok(j) => job.set(Some(j)),
+                    Err(e) => {
+                        error_state.set_server_error(&e);
+                        break;
+                    }
+                }
+                sleep(1000).await;
+            }
+        });
+    });
+
+    JobHandle {
+        id,
+        job,
+        starting,
+        error_state,
+    }
+}
+
+/// A bulk job's progress, polled until it finishes, and a report of its
+/// results to download.
+#[component]
+pub fn JobProgress(
+    job_id: Uuid,
+    #[props(default)] on_finished: Option<EventHandler<Job>>,
+) -> Element {
+    let mut job = use_job(move |j| {
+        if let Some(handler) = on_finished {
+            handler.call(j);
+        }
+    });
+    use_hook(move || job.follow(job_id));
+
+    rsx! {
+        JobStatus { job }
+    }
+}
+
+/// How far a job from [`use_job`] has got, with a report of its results to
+/// download once it's done.
+#[component]
+pub fn JobStatus(job: JobHandle) -> Element {
+    let mut error_state = use_error();
+
+    let (Some(job_id), Some(j)) = (job.id(), job.job()) else {
+        return rsx! {
+            div { class: "loading", "Starting..." }
+        };
+    };
+    let percent = j.percent();
+
+    rsx! {
+        div { class: "job-progress",
+            div { class: "progress",
+                div { class: "progress-bar", style: "width: {percent}%" }
+            }
+            div { class: "job-progress-status",
+                span { class: "text-muted text-sm",
+                    "{j.done} of {j.total} done"
+                    if j.failed > 0 {
+                        ", {j.failed} failed"
+                    }
+                    if let Some(error) = &j.error {
+                        ". {error}"
+                    }
+                }
+                if j.is_finished() {
+                    button {
+                        class: "btn btn-link",
+                        onclick: move |_| {
+                            spawn(async move {
+                                match api::get_job_items(job_id).await {
+                                    Ok(items) => download(&format!("job-{job_id}.csv"), "text/csv", job_report(&items)),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                            });
+                        },
+                        "Download report"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An expander showing an entry exactly as Kanidm returns it, for debugging
+/// attributes AuthIt! doesn't show. It's only fetched once opened.
+#[component]
+pub fn RawEntry(kind: EntryKind, id: Uuid) -> Element {
+    let mut error_state = use_error();
+    let mut entry = use_signal(|| None::<String>);
+
+    rsx! {
+        details { class: "form-group",
+            summary {
+                class: "form-label",
+                onclick: move |_| {
+                    if entry.read().is_some() {
+                        return;
+                    }
+                    spawn(async move {
+                        match api::get_raw_entry(kind, id).await {
+                            Ok(e) => entry.set(Some(e)),
+                            Err(e) => error_state.set_server_error(&e),
+                        }
+                    });
+                },
+                "Raw entry"
+            }
+            if let Some(entry) = entry() {
+                pre { class: "raw-entry", "{entry}" }
+            } else {
+                div { class: "loading", "Loading..." }
+            }
+        }
+    }
+}
+
+/// Sign-ins to AuthIt!, newest first. The user column is left out when they're
+/// all for one user.
+#[component]
+pub fn LoginEventsTable(events: Vec<LoginEvent>, #[props(default)] show_user: bool) -> Element {
+    if events.is_empty() {
+        return rsx! {
+            p { class: "text-muted text-sm", "No sign-ins recorded." }
+        };
+    }
+
+    rsx! {
+        div { class: "table-container",
+            table {
+                thead {
+                    tr {
+                        th { "When" }
+                        if show_user {
+                            th { "User" }
+                        }
+                        th { "Result" }
+                        th { "Device" }
+                        th { "Location" }
+                    }
+                }
+                tbody {
+                    for event in events {
+                        tr { key: "{event.id}",
+                            td { {format_timestamp(event.at)} }
+                            if show_user {
+                                td { {event.username.as_deref().unwrap_or("Unknown")} }
+                            }
+                            td {
+                                if let Some(failure) = event.failure {
+                                    span { class: "text-danger", "Failed: {failure.label()}" }
+                                } else {
+                                    "Signed in"
+                                }
+                            }
+                            td { title: event.user_agent.clone().unwrap_or_default(),
+                                {event.device.as_deref().or(event.user_agent.as_deref()).unwrap_or("Unknown")}
+                            }
+                            td {
+                                {event.location.as_deref().unwrap_or("Unknown")}
+                                if let Some(ip) = &event.ip_address {
+                                    div { class: "text-muted", "{ip}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The admins who have AuthIt! open right now, for showing presence dots on
+/// their avatars. Only admins are told who's online, so it's empty for
+/// everyone else.
+#[derive(Clone, Copy)]
+pub struct Presence(pub Signal<HashSet<Uuid>>);
+
+impl Presence {
+    pub fn is_online(&self, user_id: Uuid) -> bool {
+        self.0.read().contains(&user_id)
+    }
+}
+
+/// The background of an avatar without an image. It's picked from `key`, so
+/// a user keeps the same color everywhere and from visit to visit.
+pub fn avatar_color(key: &str) -> String {
+    // FNV-1a, which is stable across builds, unlike the standard hasher.
+    let hash = key.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    });
+    format!("hsl({}, 55%, 42%)", hash % 360)
+}
+
+/// A user's uploaded avatar, falling back to their initial on a color of
+/// their own if they don't have one. Admins who are online get a dot. Bump
+/// `version` to show a newly uploaded image.
+#[component]
+pub fn Avatar(
+    user_id: Uuid,
+    name: String,
+    #[props(default = "avatar")] class: &'static str,
+    #[props(default)] version: u32,
+) -> Element {
+    let mut failed = use_signal(|| false);
+    let online = try_use_context::<Presence>().is_some_and(|p| p.is_online(user_id));
+
+    // Try again when the image changes.
+    let mut last_version = use_signal(|| version);
+    if last_version() != version {
+        last_version.set(version);
+        failed.set(false);
+    }
+
+    // The break-glass account has the nil UUID, and no avatar.
+    let image = if failed() || user_id.is_nil() {
+        let initial = name
+            .chars()
+            .next()
+            .unwrap_or('?')
+            .to_uppercase()
+            .to_string();
+        // Everyone without a Kanidm id would share a color, so go by name.
+        let color = if user_id.is_nil() {
+            avatar_color(&name)
+        } else {
+            avatar_color(&user_id.to_string())
+        };
+        rsx! {
+            div { class, style: "background-color: {color}", "{initial}" }
+        }
+    } else {
+        rsx! {
+            img {
+                class,
+                src: "/avatars/{user_id}?v={version}",
+                alt: "{name}",
+                onerror: move |_| failed.set(true),
+            }
+        }
+    };
+
+    if !online {
+        return image;
+    }
+    rsx! {
+        span { class: "avatar-presence", title: "Online now",
+            {image}
+            span { class: "presence-dot" }
+        }
+    }
+}
+
+/// The site-wide announcement banner, if there is an active one.
+#[component]
+pub fn AnnouncementBanner() -> Element {
+    let announcement = use_resource(api::get_active_announcement);
+
+    match &*announcement.read() {
+        Some(Ok(Some(a))) => rsx! {
+            div {
+                class: "announcement-banner announcement-{a.severity.as_str()}",
+                dangerous_inner_html: "{a.html}",
+            }
+        },
+        _ => rsx! {},
+    }
+}
+
+/// A searchable list of groups to pick from. Groups sharing a prefix, like
+/// `eng-`, are shown together.
+///
+/// Type to filter, use the arrow keys to move, and Enter to toggle. "Select
+/// all" and "Clear" only apply to the groups matching the filter. Groups in
+/// `changed` are highlighted, as their membership changed since the page
+/// loaded. Groups in `staged` have a change waiting to be applied. Admins'
+/// favorite groups come first, and can be starred from here.
 #[component]
-pub fn GroupCheckboxList(
+pub fn GroupPicker(
     groups: Vec<Group>,
     selected: HashSet<Uuid>,
     on_toggle: EventHandler<Uuid>,
+    /// Select or deselect several groups at once. Without it, each is toggled
+    /// in turn.
+    #[props(default)]
+    on_set_many: Option<EventHandler<(Vec<Uuid>, bool)>>,
     #[props(default)] updating: Option<Uuid>,
+    #[props(default)] member_counts: HashMap<Uuid, u32>,
+    #[props(default)] changed: HashSet<Uuid>,
+    #[props(default)] staged: HashSet<Uuid>,
+    /// Offer to delete each group. Without it, groups can only be toggled.
+    #[props(default)]
+    on_delete: Option<EventHandler<Uuid>>,
+    /// Offer to set who manages each group in Kanidm.
+    #[props(default)]
+    on_manage: Option<EventHandler<Uuid>>,
+    /// Why the groups can't be changed, if they can't. Shown as a tooltip.
+    #[props(default)]
+    disabled_reason: Option<String>,
 ) -> Element {
+    let mut search = use_signal(String::new);
+    let mut highlighted = use_signal(|| 0usize);
+    let favorites = try_use_context::<FavoriteGroups>();
+    let error_state = use_error();
+
+    let query = search.read().trim().to_lowercase();
+    let filtered: Vec<Group> = groups
+        .iter()
+        .filter(|g| g.name.to_lowercase().contains(&query))
+        .cloned()
+        .collect();
+    let (starred, rest): (Vec<Group>, Vec<Group>) = filtered
+        .into_iter()
+        .partition(|g| favorites.is_some_and(|f| f.contains(g.uuid)));
+    let mut sections: Vec<(Option<String>, Vec<Group>)> = Vec::new();
+    if !starred.is_empty() {
+        sections.push((Some("Favorites".to_string()), starred));
+    }
+    sections.extend(
+        group_by_prefix(&rest)
+            .into_iter()
+            .map(|(prefix, groups)| (prefix.map(|p| format!("{p}*")), groups)),
+    );
+    // The order groups are shown in, for moving with the keyboard.
+    let order: Vec<Uuid> = sections
+        .iter()
+        .flat_map(|(_, groups)| groups.iter().map(|g| g.uuid))
+        .collect();
+    let highlighted_id = order.get(highlighted()).copied();
+    let last = order.len().saturating_sub(1);
+    let locked = disabled_reason.is_some();
+
+    let set_many = move |ids: Vec<Uuid>, select: bool| match on_set_many {
+        Some(handler) => handler.call((ids, select)),
+        None => ids.into_iter().for_each(|id| on_toggle.call(id)),
+    };
+    let to_select: Vec<Uuid> = order
+        .iter()
+        .filter(|id| !selected.contains(id))
+        .copied()
+        .collect();
+    let to_clear: Vec<Uuid> = order
+        .iter()
+        .filter(|id| selected.contains(id))
+        .copied()
+        .collect();
+
     rsx! {
-        ul { class: "group-checklist",
-            for group in groups {
-                {
-                    let is_checked = selected.contains(&group.uuid);
-                    let group_id = group.uuid;
-                    let is_updating = updating == Some(group_id);
-
-                    rsx! {
-                        li { class: "group-checklist-item",
-                            label { class: "checkbox-label",
-                                input {
-                                    r#type: "checkbox",
-                                    checked: is_checked,
-                                    disabled: is_updating,
-                                    onchange: move |_| on_toggle.call(group_id),
-                                }
-                                span { "{group.name}" }
-                                if is_updating {
-                                    span { class: "checkbox-updating", "(updating...)" }
+        div { class: "group-picker",
+            div { class: "group-picker-toolbar",
+                input {
+                    class: "form-input",
+                    r#type: "search",
+                    placeholder: "Filter groups",
+                    value: "{search}",
+                    oninput: move |e| {
+                        search.set(e.value());
+                        highlighted.set(0);
+                    },
+                    onkeydown: move |e: KeyboardEvent| match e.key() {
+                        Key::ArrowDown => {
+                            e.prevent_default();
+                            highlighted.set((highlighted() + 1).min(last));
+                        }
+                        Key::ArrowUp => {
+                            e.prevent_default();
+                            highlighted.set(highlighted().saturating_sub(1));
+                        }
+                        Key::Enter => {
+                            e.prevent_default();
+                            if let Some(id) = highlighted_id.filter(|_| !locked) {
+                                on_toggle.call(id);
+                            }
+                        }
+                        Key::Escape => {
+                            search.set(String::new());
+                            highlighted.set(0);
+                        }
+                        _ => {}
+                    },
+                }
+                button {
+                    class: "btn btn-link",
+                    disabled: locked || to_select.is_empty(),
+                    title: disabled_reason.clone(),
+                    onclick: move |_| set_many(to_select.clone(), true),
+                    "Select all"
+                }
+                button {
+                    class: "btn btn-link",
+                    disabled: locked || to_clear.is_empty(),
+                    title: disabled_reason.clone(),
+                    onclick: move |_| set_many(to_clear.clone(), false),
+                    "Clear"
+                }
+            }
+            if order.is_empty() {
+                p { class: "text-muted text-sm", "No matching groups" }
+            }
+            ul { class: "group-checklist",
+                for (label, section) in sections {
+                    if let Some(label) = label {
+                        li { class: "group-picker-section", "{label}" }
+                    }
+                    for group in section {
+                        {
+                            let is_checked = selected.contains(&group.uuid);
+                            let group_id = group.uuid;
+                            let is_updating = updating == Some(group_id);
+                            let count = member_counts.get(&group_id).copied();
+                            let is_changed = changed.contains(&group_id);
+                            let is_staged = staged.contains(&group_id);
+                            let mut class = "group-checklist-item".to_string();
+                            if is_changed {
+                                class.push_str(" group-changed");
+                            }
+                            if highlighted_id == Some(group_id) {
+                                class.push_str(" group-highlighted");
+                            }
+
+                            rsx! {
+                                li {
+                                    key: "{group_id}",
+                                    class,
+                                    title: if is_changed { "Membership changed since the page loaded" },
+                                    label { class: "checkbox-label",
+                                        input {
+                                            r#type: "checkbox",
+                                            checked: is_checked,
+                                            disabled: locked || is_updating,
+                                            title: disabled_reason.clone(),
+                                            onchange: move |_| on_toggle.call(group_id),
+                                        }
+                                        span { "{group.name}" }
+                                        if let Some(count) = count {
+                                            span {
+                                                class: "group-count",
+                                                title: "People in this group",
+                                                "{count}"
+                                            }
+                                        }
+                                        if is_updating {
+                                            span { class: "checkbox-updating", "(updating...)" }
+                                        }
+                                        if is_staged {
+                                            span { class: "badge badge-warning", "Pending" }
+                                        }
+                                    }
+                                    if let Some(favorites) = favorites {
+                                        {
+                                            let is_favorite = favorites.contains(group_id);
+                                            rsx! {
+                                                button {
+                                                    class: "btn btn-link group-favorite",
+                                                    title: if is_favorite { "Remove from favorites" } else { "Add to favorites" },
+                                                    onclick: move |_| favorites.set(group_id, !is_favorite, error_state),
+                                                    if is_favorite { "★" } else { "☆" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                    if let Some(on_manage) = on_manage {
+                                        button {
+                                            class: "btn btn-link group-manage",
+                                            disabled: locked,
+                                            title: disabled_reason.clone().unwrap_or_else(|| "Manage this group".to_string()),
+                                            onclick: move |_| on_manage.call(group_id),
+                                            "⚙"
+                                        }
+                                    }
+                                    if let Some(on_delete) = on_delete {
+                                        button {
+                                            class: "btn btn-link group-delete",
+                                            disabled: locked,
+                                            title: disabled_reason.clone().unwrap_or_else(|| "Delete group".to_string()),
+                                            onclick: move |_| on_delete.call(group_id),
+                                            "×"
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -42,11 +790,46 @@ pub fn GroupCheckboxList(
     }
 }
 
+/// Split groups into sections sharing a prefix, like `eng-` or `idm_`, keeping
+/// their order. Groups that don't share a prefix with any other come last,
+/// without a label.
+fn group_by_prefix(groups: &[Group]) -> Vec<(Option<String>, Vec<Group>)> {
+    let prefix = |name: &str| name.find(['-', '_']).map(|i| name[..=i].to_string());
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for group in groups {
+        if let Some(p) = prefix(&group.name) {
+            *counts.entry(p).or_default() += 1;
+        }
+    }
+
+    let mut sections: Vec<(Option<String>, Vec<Group>)> = Vec::new();
+    let mut ungrouped = Vec::new();
+    for group in groups {
+        match prefix(&group.name).filter(|p| counts[p] > 1) {
+            Some(p) => match sections.iter_mut().find(|(s, _)| s.as_ref() == Some(&p)) {
+                Some((_, section)) => section.push(group.clone()),
+                None => sections.push((Some(p), vec![group.clone()])),
+            },
+            None => ungrouped.push(group.clone()),
+        }
+    }
+    if !ungrouped.is_empty() {
+        sections.push((None, ungrouped));
+    }
+
+    sections
+}
+
 #[component]
 pub fn UserForm(
     username: Signal<String>,
     display_name: Signal<String>,
     email: Signal<String>,
+    #[props(default)] email_field: FieldRequirement,
+    /// Shown under the fields they're for.
+    #[props(default)]
+    errors: FieldErrors,
 ) -> Element {
     rsx! {
         div { class: "form-group",
@@ -59,6 +842,7 @@ pub fn UserForm(
                 value: "{username}",
                 oninput: move |e| username.set(e.value()),
             }
+            FieldError { errors: errors.clone(), field: "name" }
         }
         div { class: "form-group",
             label { class: "form-label", r#for: "display_name", "Display Name" }
@@ -70,17 +854,35 @@ pub fn UserForm(
                 value: "{display_name}",
                 oninput: move |e| display_name.set(e.value()),
             }
+            FieldError { errors: errors.clone(), field: "display_name" }
         }
-        div { class: "form-group",
-            label { class: "form-label", r#for: "email", "Email" }
-            input {
-                id: "email",
-                class: "form-input",
-                r#type: "email",
-                placeholder: "e.g. jsmith@example.com",
-                value: "{email}",
-                oninput: move |e| email.set(e.value()),
+        if email_field.is_shown() {
+            div { class: "form-group",
+                label { class: "form-label", r#for: "email",
+                    if email_field == FieldRequirement::Required { "Email (required)" } else { "Email" }
+                }
+                input {
+                    id: "email",
+                    class: "form-input",
+                    r#type: "email",
+                    placeholder: "e.g. jsmith@example.com",
+                    required: email_field == FieldRequirement::Required,
+                    value: "{email}",
+                    oninput: move |e| email.set(e.value()),
+                }
+                FieldError { errors: errors.clone(), field: "email" }
             }
         }
     }
 }
+
+/// The message for one field of a form, if it has one.
+#[component]
+pub fn FieldError(errors: FieldErrors, field: String) -> Element {
+    match errors.get(&field) {
+        Some(message) => rsx! {
+            div { class: "form-error", "{message}" }
+        },
+        None => rsx! {},
+    }
+}
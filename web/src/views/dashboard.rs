@@ -1,5 +1,23 @@
-use crate::Route;
+use super::{
+    charts::{BarChart, ChartBar, Sparkline},
+    components::{Avatar, format_relative, format_timestamp, sleep},
+};
+use crate::{Route, use_error};
 use dioxus::prelude::*;
+use jiff::{Timestamp, tz::TimeZone};
+use types::{
+    activity::{Activity, ActivitySubject, KanidmCall},
+    changelog::WhatsNew,
+    group_review::GroupReview,
+    integrity::IntegrityReport,
+    onboarding::Onboarding,
+    shift_note::ShiftNote,
+    stats::DashboardStats,
+};
+use uuid::Uuid;
+
+/// How many events the activity stream shows.
+const ACTIVITY_LIMIT: u32 = 20;
 
 #[component]
 pub fn Dashboard() -> Element {
@@ -9,16 +27,680 @@ pub fn Dashboard() -> Element {
                 h1 { class: "page-title", "Dashboard" }
                 p { class: "page-subtitle", "Welcome to Authit - your Kanidm administration interface." }
             }
-            div { class: "dashboard-grid",
-                Link {
-                    to: Route::users(),
-                    class: "dashboard-card",
-                    h3 { class: "dashboard-card-title", "Manage Users" }
-                    p { class: "dashboard-card-desc",
-                        "View users, manage group memberships, and generate credential reset links."
+            WhatsNewPanel {}
+            div { class: "grid",
+                div { class: "dashboard-grid",
+                    Link {
+                        to: Route::users(),
+                        class: "dashboard-card",
+                        h3 { class: "dashboard-card-title", "Manage Users" }
+                        p { class: "dashboard-card-desc",
+                            "View users, manage group memberships, and generate credential reset links."
+                        }
+                    }
+                }
+                TrendsCard {}
+                ShiftLogCard {}
+                ActivityCard {}
+                OnboardingCard {}
+                GroupReviewsCard {}
+                IntegrityCard {}
+            }
+        }
+    }
+}
+
+/// How accounts, provisioning and sign-ins have been going lately.
+#[component]
+fn TrendsCard() -> Element {
+    let mut error_state = use_error();
+    let mut stats = use_signal(|| None::<DashboardStats>);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_dashboard_stats().await {
+                Ok(s) => stats.set(Some(s)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(stats) = stats() else {
+        return rsx! {};
+    };
+
+    let created: u64 = stats.days.iter().map(|d| d.counts.accounts_created).sum();
+    let deleted: u64 = stats.days.iter().map(|d| d.counts.accounts_deleted).sum();
+    let net = created as i64 - deleted as i64;
+    let growth: Vec<i64> = stats
+        .days
+        .iter()
+        .scan(0, |net, d| {
+            *net += d.counts.accounts_created as i64 - d.counts.accounts_deleted as i64;
+            Some(*net)
+        })
+        .collect();
+    let provisioned: u64 = stats
+        .days
+        .iter()
+        .map(|d| d.counts.provision_links_used)
+        .sum();
+    let provisions: Vec<ChartBar> = stats
+        .days
+        .iter()
+        .map(|d| ChartBar {
+            label: d.date.to_string(),
+            value: d.counts.provision_links_used,
+        })
+        .collect();
+    let sign_ins: u64 = stats.days.iter().map(|d| d.sign_ins).sum();
+    let failed: u64 = stats.days.iter().map(|d| d.failed_sign_ins).sum();
+    let logins: Vec<i64> = stats.days.iter().map(|d| d.sign_ins as i64).collect();
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Last {stats.days.len()} Days" }
+                Link { to: Route::Reports {}, class: "btn btn-link text-sm", "Full report" }
+            }
+            div { class: "card-body",
+                div { class: "report-grid",
+                    div { class: "report-tile",
+                        div { class: "report-tile-header",
+                            span { class: "text-muted text-sm", "Account growth" }
+                            span { class: "report-total", "{net:+}" }
+                        }
+                        Sparkline { values: growth }
+                        p { class: "text-muted text-sm", "{created} created, {deleted} deleted" }
+                    }
+                    div { class: "report-tile",
+                        div { class: "report-tile-header",
+                            span { class: "text-muted text-sm", "Provisioned" }
+                            span { class: "report-total", "{provisioned}" }
+                        }
+                        BarChart { bars: provisions }
+                    }
+                    div { class: "report-tile",
+                        div { class: "report-tile-header",
+                            span { class: "text-muted text-sm", "Sign-ins" }
+                            span { class: "report-total", "{sign_ins}" }
+                        }
+                        Sparkline { values: logins }
+                        p { class: "text-muted text-sm", "{failed} failed" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Release notes, shown after an upgrade until an admin dismisses them.
+#[component]
+fn WhatsNewPanel() -> Element {
+    let mut error_state = use_error();
+    let mut whats_new = use_signal(|| None::<WhatsNew>);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_whats_new().await {
+                Ok(w) => whats_new.set(w),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(news) = whats_new() else {
+        return rsx! {};
+    };
+    let several = news.releases.len() > 1;
+
+    rsx! {
+        div { class: "card whats-new",
+            div { class: "card-header whats-new-header",
+                h2 { class: "card-title", "What's new in AuthIt! v{news.version}" }
+                button {
+                    class: "btn btn-link",
+                    onclick: move |_| {
+                        spawn(async move {
+                            match api::dismiss_whats_new().await {
+                                Ok(()) => whats_new.set(None),
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                        });
+                    },
+                    "Dismiss"
+                }
+            }
+            div { class: "card-body",
+                for release in news.releases.iter() {
+                    div { key: "{release.version}",
+                        if several {
+                            h3 { class: "section-header", "v{release.version}" }
+                        }
+                        ul {
+                            for change in release.changes.iter() {
+                                li { "{change}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Notes admins leave for each other, like upcoming maintenance or things to
+/// hold off on.
+#[component]
+fn ShiftLogCard() -> Element {
+    let mut error_state = use_error();
+    let mut notes = use_signal(|| None::<Vec<ShiftNote>>);
+    let mut draft = use_signal(String::new);
+    let mut posting = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_shift_notes().await {
+                Ok(n) => notes.set(Some(n)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(list) = notes() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Shift Log" }
+            }
+            div { class: "card-body",
+                div { class: "form-group",
+                    textarea {
+                        class: "form-input",
+                        rows: "3",
+                        placeholder: "Leave a note for the other admins. Markdown is supported.",
+                        value: "{draft}",
+                        oninput: move |e| draft.set(e.value()),
+                    }
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: posting() || draft.read().trim().is_empty(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                posting.set(true);
+                                match api::add_shift_note(draft()).await {
+                                    Ok(note) => {
+                                        if let Some(list) = notes.write().as_mut() {
+                                            list.insert(0, note);
+                                        }
+                                        draft.set(String::new());
+                                    }
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                posting.set(false);
+                            });
+                        },
+                        if posting() { "Posting..." } else { "Post" }
+                    }
+                }
+                if list.is_empty() {
+                    div { class: "empty-state", "No notes yet." }
+                }
+                for note in list {
+                    div { class: "shift-note", key: "{note.id}",
+                        div { class: "shift-note-meta text-muted text-sm",
+                            "{note.author}, {format_timestamp(note.created_at)}"
+                            button {
+                                class: "btn btn-link",
+                                onclick: move |_| {
+                                    let id = note.id;
+                                    spawn(async move {
+                                        match api::delete_shift_note(id).await {
+                                            Ok(()) => {
+                                                if let Some(list) = notes.write().as_mut() {
+                                                    list.retain(|n| n.id != id);
+                                                }
+                                            }
+                                            Err(e) => error_state.set_server_error(&e),
+                                        }
+                                    });
+                                },
+                                "Delete"
+                            }
+                        }
+                        div { class: "shift-note-content", dangerous_inner_html: "{note.html}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What's been happening lately, refreshed every few seconds.
+#[component]
+fn ActivityCard() -> Element {
+    let mut error_state = use_error();
+    let mut activity = use_signal(|| None::<Vec<Activity>>);
+    let mut explaining = use_signal(|| None::<Activity>);
+
+    use_effect(move || {
+        spawn(async move {
+            loop {
+                match api::list_recent_activity(ACTIVITY_LIMIT).await {
+                    Ok(a) => activity.set(Some(a)),
+                    Err(e) => {
+                        error_state.set_server_error(&e);
+                        break;
+                    }
+                }
+                sleep(15_000).await;
+            }
+        });
+    });
+
+    let Some(list) = activity() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Recent Activity" }
+            }
+            if list.is_empty() {
+                div { class: "empty-state", "Nothing has happened yet." }
+            } else {
+                ul { class: "activity-list",
+                    for a in list {
+                        li { key: "{a.id}", class: "activity-item",
+                            if let Some(actor) = &a.actor {
+                                Avatar { user_id: actor.user_id, name: actor.name.clone() }
+                            } else {
+                                Avatar { user_id: Uuid::nil(), name: "AuthIt!" }
+                            }
+                            div { class: "activity-summary",
+                                div { "{a.summary}" }
+                                if let Some(ActivitySubject::User { id, name }) = &a.subject {
+                                    Link { to: Route::user_detail(*id), class: "text-sm", "{name}" }
+                                } else if let Some(ActivitySubject::Group { name }) = &a.subject {
+                                    span { class: "text-muted text-sm", "Group {name}" }
+                                }
+                                if let Some(ticket) = &a.ticket {
+                                    span { class: "badge", title: "Ticket", "{ticket}" }
+                                }
+                            }
+                            span {
+                                class: "activity-time",
+                                title: format_timestamp(a.at),
+                                {format_relative(a.at)}
+                            }
+                            button {
+                                class: "btn btn-link text-sm",
+                                title: "Show the Kanidm calls this made",
+                                onclick: {
+                                    let a = a.clone();
+                                    move |_| explaining.set(Some(a.clone()))
+                                },
+                                "Explain"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(activity) = explaining() {
+            ExplainActivityModal { activity, on_close: move |_| explaining.set(None) }
+        }
+    }
+}
+
+/// The Kanidm API calls behind an activity, with what was sent and what came
+/// back.
+#[component]
+fn ExplainActivityModal(activity: Activity, on_close: EventHandler<()>) -> Element {
+    let mut error_state = use_error();
+    let mut calls = use_signal(|| None::<Vec<KanidmCall>>);
+    let id = activity.id;
+
+    use_effect(move || {
+        spawn(async move {
+            match api::explain_activity(id).await {
+                Ok(c) => calls.set(Some(c)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Explain" }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    p { "{activity.summary}" }
+                    p { class: "text-muted text-sm", {format_timestamp(activity.at)} }
+                    match calls() {
+                        None => rsx! {
+                            div { class: "loading", "Loading..." }
+                        },
+                        Some(calls) if calls.is_empty() => rsx! {
+                            div { class: "empty-state",
+                                "No Kanidm calls were recorded for this. It may not have made any, or it happened before they were recorded."
+                            }
+                        },
+                        Some(calls) => rsx! {
+                            for (i, call) in calls.into_iter().enumerate() {
+                                div { key: "{i}", class: "form-group",
+                                    div {
+                                        strong { "{call.method} {call.path}" }
+                                        span { class: "text-muted text-sm", " {call.duration_ms} ms" }
+                                    }
+                                    if let Some(error) = &call.error {
+                                        div { class: "alert alert-error", "{error}" }
+                                    }
+                                    if let Some(request) = &call.request {
+                                        details {
+                                            summary { class: "text-sm", "Sent" }
+                                            pre { class: "raw-entry", "{request}" }
+                                        }
+                                    }
+                                    if let Some(response) = &call.response {
+                                        details {
+                                            summary { class: "text-sm", "Received" }
+                                            pre { class: "raw-entry", "{response}" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "Close"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// New users who haven't finished setting up their accounts, so they can be
+/// chased.
+#[component]
+fn OnboardingCard() -> Element {
+    let mut error_state = use_error();
+    let mut onboardings = use_signal(|| None::<Vec<Onboarding>>);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_incomplete_onboardings().await {
+                Ok(o) => onboardings.set(Some(o)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(list) = onboardings() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Onboarding" }
+            }
+            if list.is_empty() {
+                div { class: "empty-state", "Everyone has finished setting up their account." }
+            } else {
+                div { class: "table-container",
+                    table {
+                        thead {
+                            tr {
+                                th { "User" }
+                                th { "Account created" }
+                                th { "Still to do" }
+                                th {}
+                            }
+                        }
+                        tbody {
+                            for o in list {
+                                tr { key: "{o.id}",
+                                    td {
+                                        Link { to: Route::user_detail(o.user_id), "{o.user_name}" }
+                                    }
+                                    td { {format_timestamp(o.started_at)} }
+                                    td { "{o.pending_steps().join(\", \")}" }
+                                    td {
+                                        button {
+                                            class: "btn btn-link",
+                                            title: "Stop tracking this user",
+                                            onclick: move |_| {
+                                                let id = o.id;
+                                                spawn(async move {
+                                                    match api::dismiss_onboarding(id).await {
+                                                        Ok(()) => {
+                                                            if let Some(list) = onboardings.write().as_mut() {
+                                                                list.retain(|o| o.id != id);
+                                                            }
+                                                        }
+                                                        Err(e) => error_state.set_server_error(&e),
+                                                    }
+                                                });
+                                            },
+                                            "Dismiss"
+                                        }
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
             }
         }
     }
 }
+
+/// Groups due for review or past their expiry. Hidden while there are none.
+#[component]
+fn GroupReviewsCard() -> Element {
+    let mut error_state = use_error();
+    let mut reviews = use_signal(Vec::<GroupReview>::new);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_overdue_group_reviews().await {
+                Ok(r) => reviews.set(r),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    if reviews.read().is_empty() {
+        return rsx! {};
+    }
+    let today = Timestamp::now().to_zoned(TimeZone::UTC).date();
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Groups to Review" }
+            }
+            div { class: "table-container",
+                table {
+                    thead {
+                        tr {
+                            th { "Group" }
+                            th { "Why" }
+                            th { "Last reviewed" }
+                        }
+                    }
+                    tbody {
+                        for r in reviews() {
+                            tr { key: "{r.group_id}",
+                                td {
+                                    "{r.group_name}"
+                                    if r.locked(today).is_some() {
+                                        span { class: "badge badge-warning", "Locked" }
+                                    }
+                                }
+                                td { {r.overdue(today).unwrap_or_default()} }
+                                td {
+                                    match (r.reviewed_at, &r.reviewed_by) {
+                                        (Some(at), Some(by)) => rsx! {
+                                            {format_timestamp(at)}
+                                            div { class: "text-muted text-sm", "by {by}" }
+                                        },
+                                        _ => rsx! { "Never" },
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            p { class: "text-muted text-sm",
+                "Review a group or move its dates with the ⚙ next to it, under Custom Groups on any user's page."
+            }
+        }
+    }
+}
+
+/// People who could be mistaken for each other, and records about deleted
+/// users and groups, found by the periodic integrity check.
+#[component]
+fn IntegrityCard() -> Element {
+    let mut error_state = use_error();
+    let mut report = use_signal(|| None::<IntegrityReport>);
+    let mut checking = use_signal(|| false);
+    let mut cleaning = use_signal(|| None::<Uuid>);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_integrity_report().await {
+                Ok(r) => report.set(r),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Integrity" }
+                button {
+                    class: "btn btn-secondary",
+                    disabled: checking(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            checking.set(true);
+                            match api::run_integrity_check().await {
+                                Ok(r) => report.set(Some(r)),
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            checking.set(false);
+                        });
+                    },
+                    if checking() { "Checking..." } else { "Check now" }
+                }
+            }
+            if let Some(r) = report() {
+                if r.findings.is_empty() {
+                    div { class: "empty-state",
+                        "No shared email addresses or look-alike display names."
+                    }
+                } else {
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Users" }
+                                    th { "Issue" }
+                                }
+                            }
+                            tbody {
+                                for (i, finding) in r.findings.iter().enumerate() {
+                                    tr { key: "{i}",
+                                        td {
+                                            for user in finding.users.iter() {
+                                                div { key: "{user.uuid}",
+                                                    Link {
+                                                        to: Route::user_detail(user.uuid),
+                                                        "{user.display_name} ({user.name})"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        td { "{finding.issue.describe()}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                if !r.orphans.is_empty() {
+                    h3 { class: "section-header", "Records about deleted entries" }
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Entry" }
+                                    th { "Found in" }
+                                    th {}
+                                }
+                            }
+                            tbody {
+                                for orphan in r.orphans.iter().cloned() {
+                                    tr { key: "{orphan.id}",
+                                        td { "{orphan.label()}" }
+                                        td { {orphan.found_in.join(", ")} }
+                                        td {
+                                            button {
+                                                class: "btn btn-secondary",
+                                                disabled: cleaning().is_some(),
+                                                onclick: move |_| {
+                                                    let (kind, id) = (orphan.kind, orphan.id);
+                                                    spawn(async move {
+                                                        cleaning.set(Some(id));
+                                                        match api::clean_up_orphan(kind, id).await {
+                                                            Ok(r) => report.set(Some(r)),
+                                                            Err(e) => error_state.set_server_error(&e),
+                                                        }
+                                                        cleaning.set(None);
+                                                    });
+                                                },
+                                                if cleaning() == Some(orphan.id) { "Cleaning up..." } else { "Clean up" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                p { class: "text-muted text-sm",
+                    "Checked "
+                    {format_timestamp(r.checked_at)}
+                    ". Shared addresses and look-alike names often mean someone was onboarded twice, or is impersonating a colleague. Records about deleted entries are left behind when users or groups are deleted in Kanidm directly."
+                }
+            } else {
+                div { class: "empty-state", "The first check hasn't run yet." }
+            }
+        }
+    }
+}
@@ -0,0 +1,811 @@
+use super::components::{
+    JobProgress, LoginEventsTable, Masked, Sensitive, download, format_timestamp,
+};
+use crate::use_error;
+use dioxus::prelude::*;
+use types::{
+    api_usage::AdminApiUsage,
+    audit::ChainStatus,
+    break_glass::BreakGlassStatus,
+    capability::{Capability, TokenCapabilities},
+    capture::{CaptureStatus, MAX_CAPTURE_MINUTES},
+    job::Job,
+    kanidm::{DomainInfo, KanidmLoad, KanidmNode},
+    login_event::LoginEvent,
+    migration::{MigrationState, MigrationStatus},
+};
+
+#[component]
+pub fn Diagnostics() -> Element {
+    let mut error_state = use_error();
+    let mut domain = use_signal(|| None::<DomainInfo>);
+    let mut load = use_signal(|| None::<KanidmLoad>);
+
+    let refresh_load = move || {
+        spawn(async move {
+            match api::get_kanidm_load().await {
+                Ok(l) => load.set(Some(l)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_domain_info().await {
+                Ok(d) => domain.set(Some(d)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+        refresh_load();
+    });
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Diagnostics" }
+                    p { class: "page-subtitle", "Information about the Kanidm instance AuthIt! is managing." }
+                }
+            }
+            div { class: "grid",
+                div { class: "card",
+                    div { class: "card-header",
+                        h2 { class: "card-title", "Kanidm Domain" }
+                    }
+                    div { class: "card-body",
+                        if let Some(d) = domain() {
+                            div { class: "form-group",
+                                span { class: "form-label", "Display Name" }
+                                div { class: "form-value", "{d.display_name}" }
+                            }
+                            div { class: "form-group",
+                                span { class: "form-label", "Domain" }
+                                div { class: "form-value", "{d.name}" }
+                            }
+                            div { class: "form-group",
+                                span { class: "form-label", "Domain UUID" }
+                                div { class: "form-value form-value-mono",
+                                    Masked { kind: Sensitive::Id, text: d.uuid.to_string() }
+                                }
+                            }
+                            div { class: "form-group",
+                                span { class: "form-label", "Version" }
+                                div { class: "form-value",
+                                    {d.version.clone().unwrap_or_else(|| "Unknown".to_string())}
+                                }
+                            }
+                        } else {
+                            div { class: "loading", "Loading domain info..." }
+                        }
+                        if let Some(l) = load() {
+                            div { class: "form-group",
+                                span { class: "form-label", "Requests to Kanidm" }
+                                div { class: "form-value",
+                                    "{l.in_flight} of {l.max_concurrency} in flight, {l.queued} queued "
+                                    button {
+                                        class: "btn btn-link",
+                                        onclick: move |_| refresh_load(),
+                                        "Refresh"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                KanidmNodesCard {}
+                TokenCapabilitiesCard {}
+                JobsCard {}
+                MigrationsCard {}
+                AuditChainCard {}
+                LoginEventsCard {}
+                ApiUsageCard {}
+                BreakGlassCard {}
+                CaptureCard {}
+            }
+        }
+    }
+}
+
+/// Which Kanidm servers are up, and where requests are going.
+#[component]
+fn KanidmNodesCard() -> Element {
+    let mut error_state = use_error();
+    let mut nodes = use_signal(Vec::<KanidmNode>::new);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_kanidm_nodes().await {
+                Ok(n) => nodes.set(n),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    // Without replicas, everything goes to the one server.
+    if nodes.read().len() <= 1 {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Kanidm Servers" }
+                button {
+                    class: "btn btn-secondary",
+                    onclick: move |_| refresh(),
+                    "Refresh"
+                }
+            }
+            div { class: "table-container",
+                table {
+                    thead {
+                        tr {
+                            th { "URL" }
+                            th { "Role" }
+                            th { "Status" }
+                            th { "Last checked" }
+                        }
+                    }
+                    tbody {
+                        for node in nodes.read().iter() {
+                            tr { key: "{node.url}",
+                                td { class: "form-value-mono", "{node.url}" }
+                                td {
+                                    if node.primary {
+                                        "Primary (writes"
+                                        if node.serving_reads { " and reads" }
+                                        ")"
+                                    } else if node.serving_reads {
+                                        "Replica (reads)"
+                                    } else {
+                                        "Replica"
+                                    }
+                                }
+                                td {
+                                    if node.healthy {
+                                        span { class: "badge badge-primary", "Up" }
+                                    } else {
+                                        span {
+                                            class: "text-danger",
+                                            title: node.error.clone().unwrap_or_default(),
+                                            "Down"
+                                        }
+                                    }
+                                }
+                                td {
+                                    {node.checked_at.map(format_timestamp).unwrap_or_else(|| "Never".to_string())}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What AuthIt!'s Kanidm token can do. Features that need what it can't are
+/// disabled.
+#[component]
+fn TokenCapabilitiesCard() -> Element {
+    let mut error_state = use_error();
+    let mut capabilities = use_signal(|| None::<TokenCapabilities>);
+    let mut checking = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_capabilities().await {
+                Ok(c) => capabilities.set(Some(c.token)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Kanidm Token" }
+                button {
+                    class: "btn btn-secondary",
+                    disabled: checking(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            checking.set(true);
+                            match api::check_token_capabilities().await {
+                                Ok(c) => capabilities.set(Some(c)),
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            checking.set(false);
+                        });
+                    },
+                    if checking() { "Checking..." } else { "Check Again" }
+                }
+            }
+            div { class: "card-body",
+                if let Some(c) = capabilities() {
+                    for capability in Capability::ALL {
+                        div { class: "form-group", key: "{capability.label()}",
+                            span { class: "form-label", "{capability.label()}" }
+                            div { class: "form-value",
+                                if c.allows(capability) {
+                                    span { class: "badge badge-primary", "Allowed" }
+                                } else {
+                                    span { class: "text-danger",
+                                        "Not allowed. Add AuthIt!'s service account to {capability.granted_by()}."
+                                    }
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    div { class: "loading", "Loading..." }
+                }
+            }
+        }
+    }
+}
+
+/// Recent bulk operations, like group imports.
+#[component]
+fn JobsCard() -> Element {
+    let mut error_state = use_error();
+    let mut jobs = use_signal(Vec::<Job>::new);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_jobs().await {
+                Ok(j) => jobs.set(j),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Bulk Jobs" }
+            }
+            div { class: "card-body",
+                if jobs.read().is_empty() {
+                    p { class: "text-muted", "No bulk jobs have run recently." }
+                }
+                for job in jobs.read().iter() {
+                    div { class: "form-group", key: "{job.id}",
+                        span { class: "form-label",
+                            "{job.kind.label()} by {job.started_by}, {format_timestamp(job.created_at)}"
+                        }
+                        JobProgress { job_id: job.id }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The database's migrations. Only pending ones, or ones from a newer version,
+/// are expected to show up here, when another replica is running a different
+/// version.
+#[component]
+fn MigrationsCard() -> Element {
+    let mut error_state = use_error();
+    let mut status = use_signal(MigrationStatus::default);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_migration_status().await {
+                Ok(s) => status.set(s),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let status = status.read();
+    let pending = status.count(MigrationState::Pending);
+    let problems: Vec<_> = status.problems().cloned().collect();
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Database Migrations" }
+            }
+            div { class: "card-body",
+                p {
+                    "{status.count(MigrationState::Applied)} applied"
+                    if pending > 0 { ", {pending} pending" }
+                    "."
+                }
+                if !problems.is_empty() {
+                    div { class: "alert alert-error",
+                        "The database doesn't match this version of AuthIt!, so it won't start again until that's fixed. See \"Database migrations\" in the README."
+                    }
+                }
+                if pending > 0 || !problems.is_empty() {
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Version" }
+                                    th { "Description" }
+                                    th { "State" }
+                                }
+                            }
+                            tbody {
+                                for m in status.migrations.iter().filter(|m| m.state != MigrationState::Applied) {
+                                    tr { key: "{m.version}",
+                                        td { class: "form-value-mono", "{m.version}" }
+                                        td { "{m.description}" }
+                                        td {
+                                            span {
+                                                class: if m.state.is_problem() { "text-danger" } else { "text-muted" },
+                                                "{m.state.label()}"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether the activity stream has been tampered with.
+#[component]
+fn AuditChainCard() -> Element {
+    let mut error_state = use_error();
+    let mut status = use_signal(|| None::<ChainStatus>);
+    let mut checking = use_signal(|| false);
+
+    let check = move || {
+        spawn(async move {
+            checking.set(true);
+            match api::verify_audit_chain().await {
+                Ok(s) => status.set(Some(s)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            checking.set(false);
+        });
+    };
+    use_effect(move || {
+        check();
+    });
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Audit Log Integrity" }
+            }
+            div { class: "card-body",
+                if let Some(s) = status() {
+                    if let Some(broken) = &s.broken_at {
+                        div { class: "alert alert-error",
+                            "The activity stream has been tampered with. The record from {format_timestamp(broken.at)} {broken.reason}."
+                        }
+                    } else {
+                        p { "Intact. {s.records} records checked, {format_timestamp(s.checked_at)}." }
+                    }
+                    if s.redacted > 0 {
+                        p { class: "text-muted text-sm",
+                            "{s.redacted} records about deleted users are redacted, so only their place in the chain was checked."
+                        }
+                    }
+                    if s.unchained > 0 {
+                        p { class: "text-muted text-sm",
+                            "{s.unchained} records from before the chain can't be checked."
+                        }
+                    }
+                } else {
+                    div { class: "loading", "Checking the activity stream..." }
+                }
+                button {
+                    class: "btn btn-link",
+                    disabled: checking(),
+                    onclick: move |_| check(),
+                    if checking() { "Checking..." } else { "Check again" }
+                }
+            }
+        }
+    }
+}
+
+/// How many sign-ins the Diagnostics page shows.
+const LOGIN_EVENT_LIMIT: u32 = 50;
+
+/// Recent sign-ins to AuthIt! by anyone, including failures that never got
+/// as far as Kanidm's logs.
+#[component]
+fn LoginEventsCard() -> Element {
+    let mut error_state = use_error();
+    let mut events = use_signal(|| None::<Vec<LoginEvent>>);
+    let mut failed_only = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_login_events(LOGIN_EVENT_LIMIT).await {
+                Ok(e) => events.set(Some(e)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let shown: Option<Vec<LoginEvent>> = events().map(|events| {
+        events
+            .into_iter()
+            .filter(|e| !failed_only() || !e.succeeded())
+            .collect()
+    });
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "AuthIt! Sign-ins" }
+            }
+            div { class: "card-body",
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: failed_only(),
+                        onchange: move |e| failed_only.set(e.checked()),
+                    }
+                    " Only failures"
+                }
+                if let Some(events) = shown {
+                    LoginEventsTable { events, show_user: true }
+                } else {
+                    div { class: "loading", "Loading sign-ins..." }
+                }
+            }
+        }
+    }
+}
+
+/// How often each admin calls the API. Far more calls than a person would
+/// make suggests a script is using their session.
+#[component]
+fn ApiUsageCard() -> Element {
+    let mut error_state = use_error();
+    let mut usage = use_signal(Vec::<AdminApiUsage>::new);
+    let mut expanded = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_api_usage().await {
+                Ok(u) => usage.set(u),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "API Usage" }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| refresh(),
+                        "Refresh"
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: usage.read().is_empty(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                match api::clear_api_usage().await {
+                                    Ok(()) => refresh(),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                            });
+                        },
+                        "Reset"
+                    }
+                }
+            }
+            if usage.read().is_empty() {
+                div { class: "card-body",
+                    p { class: "text-muted", "No API calls recorded yet." }
+                }
+            } else {
+                div { class: "table-container",
+                    table {
+                        thead {
+                            tr {
+                                th { "Admin" }
+                                th { "Calls" }
+                                th { "Last call" }
+                            }
+                        }
+                        tbody {
+                            for admin in usage.read().iter().cloned() {
+                                {
+                                    let user_id = admin.user_id.clone();
+                                    let is_expanded = expanded.read().as_ref() == Some(&user_id);
+                                    rsx! {
+                                        tr {
+                                            key: "{admin.user_id}",
+                                            onclick: move |_| {
+                                                let id = user_id.clone();
+                                                expanded.set(if is_expanded { None } else { Some(id) });
+                                            },
+                                            td { "{admin.user_name}" }
+                                            td { "{admin.total}" }
+                                            td { "{format_timestamp(admin.last_used_at)}" }
+                                        }
+                                        if is_expanded {
+                                            for call in admin.calls.iter() {
+                                                tr { key: "{admin.user_id}{call.path}",
+                                                    td { class: "form-value-mono", "{call.path}" }
+                                                    td { "{call.count}" }
+                                                    td { "{format_timestamp(call.last_used_at)}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn BreakGlassCard() -> Element {
+    let mut error_state = use_error();
+    let mut status = use_signal(|| None::<BreakGlassStatus>);
+    let mut password = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_break_glass_status().await {
+                Ok(s) => status.set(Some(s)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let Some(s) = status() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Break-glass Account" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "A local admin login at /break-glass for when signing in through Kanidm is broken."
+                }
+                div { class: "form-group",
+                    span { class: "form-label", "Status" }
+                    div { class: "form-value",
+                        if !s.enabled {
+                            "Disabled (set break_glass_enabled to turn on)"
+                        } else if s.is_configured() {
+                            "Enabled"
+                        } else {
+                            "Enabled, but no password has been set"
+                        }
+                    }
+                }
+                if let (Some(updated_at), Some(updated_by)) = (s.updated_at, &s.updated_by) {
+                    div { class: "form-group",
+                        span { class: "form-label", "Password last set" }
+                        div { class: "form-value", "{format_timestamp(updated_at)} by {updated_by}" }
+                    }
+                }
+                if let Some(expires_at) = s.expires_at {
+                    if s.is_expired() {
+                        div { class: "alert alert-error",
+                            "The password expired on {format_timestamp(expires_at)} and no longer works. Set a new one."
+                        }
+                    } else {
+                        div { class: "form-group",
+                            span { class: "form-label", "Password expires" }
+                            div { class: "form-value", {format_timestamp(expires_at)} }
+                        }
+                    }
+                }
+                if let Some(last_success) = s.last_success {
+                    div { class: "form-group",
+                        span { class: "form-label", "Last used" }
+                        div { class: "form-value", {format_timestamp(last_success)} }
+                    }
+                }
+                if s.recent_failures > 0 {
+                    div { class: "alert alert-error",
+                        "{s.recent_failures} failed sign-in attempt(s) in the last hour."
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "break_glass_password",
+                        if s.is_configured() { "Change password" } else { "Set password" }
+                    }
+                    input {
+                        id: "break_glass_password",
+                        class: "form-input",
+                        r#type: "password",
+                        autocomplete: "new-password",
+                        placeholder: "At least {s.policy.min_length} characters",
+                        value: "{password}",
+                        oninput: move |e| {
+                            password.set(e.value());
+                            saved.set(false);
+                        },
+                    }
+                    p { class: "text-muted text-sm", "{s.policy.describe()}" }
+                }
+                button {
+                    class: "btn btn-primary",
+                    disabled: password.read().is_empty() || *saving.read(),
+                    onclick: move |_| {
+                        let new_password = password.read().clone();
+                        spawn(async move {
+                            saving.set(true);
+                            match api::set_break_glass_password(new_password).await {
+                                Ok(()) => {
+                                    password.set(String::new());
+                                    saved.set(true);
+                                    refresh();
+                                }
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            saving.set(false);
+                        });
+                    },
+                    if *saving.read() { "Saving..." } else { "Save Password" }
+                }
+                if *saved.read() {
+                    span { class: "text-muted text-sm", " Saved." }
+                }
+            }
+        }
+    }
+}
+
+/// Recording what AuthIt! sends to Kanidm and which server functions fail,
+/// for self-hosters to attach to bug reports.
+#[component]
+fn CaptureCard() -> Element {
+    let mut error_state = use_error();
+    let mut status = use_signal(CaptureStatus::default);
+    let mut minutes = use_signal(|| 15u32);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_capture_status().await {
+                Ok(s) => status.set(s),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let s = status();
+    let active = s.capture.as_ref().is_some_and(|c| c.is_active());
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Diagnostic Capture" }
+                button {
+                    class: "btn btn-secondary",
+                    onclick: move |_| refresh(),
+                    "Refresh"
+                }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Records calls to Kanidm and failed requests for a while, to attach to a bug report. Tokens and request bodies are never recorded, and URLs, email addresses and IDs are replaced. Other names in error messages may remain, so look over the download before sharing it."
+                }
+                if let Some(c) = &s.capture {
+                    div { class: "form-group",
+                        span { class: "form-label", "Status" }
+                        div { class: "form-value",
+                            if active {
+                                "Recording until {format_timestamp(c.until)}, started by {c.started_by}"
+                            } else {
+                                "Stopped at {format_timestamp(c.until)}, started by {c.started_by}"
+                            }
+                        }
+                    }
+                    div { class: "form-group",
+                        span { class: "form-label", "Recorded" }
+                        div { class: "form-value", "{s.events} event(s)" }
+                    }
+                }
+                if active {
+                    div { class: "page-header-actions",
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    match api::stop_capture().await {
+                                        Ok(()) => refresh(),
+                                        Err(e) => error_state.set_server_error(&e),
+                                    }
+                                });
+                            },
+                            "Stop"
+                        }
+                    }
+                } else {
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "capture_minutes", "Record for (minutes)" }
+                        input {
+                            id: "capture_minutes",
+                            class: "form-input",
+                            r#type: "number",
+                            min: "1",
+                            max: "{MAX_CAPTURE_MINUTES}",
+                            value: "{minutes}",
+                            oninput: move |e| {
+                                if let Ok(m) = e.value().parse() {
+                                    minutes.set(m);
+                                }
+                            },
+                        }
+                    }
+                    div { class: "page-header-actions",
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    match api::start_capture(minutes()).await {
+                                        Ok(()) => refresh(),
+                                        Err(e) => error_state.set_server_error(&e),
+                                    }
+                                });
+                            },
+                            if s.capture.is_some() { "Start New Capture" } else { "Start Capture" }
+                        }
+                        if s.capture.is_some() {
+                            button {
+                                class: "btn btn-secondary",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        match api::get_capture_bundle().await {
+                                            Ok(bundle) => match serde_json::to_string_pretty(&bundle) {
+                                                Ok(json) => download("authit-capture.json", "application/json", json),
+                                                Err(e) => error_state.set(e.to_string()),
+                                            },
+                                            Err(e) => error_state.set_server_error(&e),
+                                        }
+                                    });
+                                },
+                                "Download"
+                            }
+                            button {
+                                class: "btn btn-secondary",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        match api::discard_capture().await {
+                                            Ok(()) => refresh(),
+                                            Err(e) => error_state.set_server_error(&e),
+                                        }
+                                    });
+                                },
+                                "Discard"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::components::{Avatar, Masked, Sensitive};
+use crate::use_error;
+use dioxus::prelude::*;
+use types::directory::DirectoryEntry;
+
+/// A read-only list of everyone in Kanidm, for any signed-in user.
+#[component]
+pub fn Directory() -> Element {
+    let mut error_state = use_error();
+    let mut entries = use_signal(Vec::<DirectoryEntry>::new);
+    let mut loading = use_signal(|| true);
+    let mut search = use_signal(String::new);
+
+    use_effect(move || {
+        spawn(async move {
+            loading.set(true);
+            match api::list_directory().await {
+                Ok(e) => entries.set(e),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loading.set(false);
+        });
+    });
+
+    let filtered = use_memo(move || {
+        let search = search.read().trim().to_lowercase();
+        entries
+            .read()
+            .iter()
+            .filter(|e| {
+                search.is_empty()
+                    || e.display_name.to_lowercase().contains(&search)
+                    || e.legal_name
+                        .as_ref()
+                        .is_some_and(|n| n.to_lowercase().contains(&search))
+                    || e.email_addresses
+                        .iter()
+                        .any(|a| a.to_lowercase().contains(&search))
+                    || e.groups.iter().any(|g| g.to_lowercase().contains(&search))
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Directory" }
+                    p { class: "page-subtitle", "Everyone in the organization." }
+                }
+            }
+            if loading() {
+                div { class: "loading", "Loading directory..." }
+            } else {
+                div { class: "card",
+                    div { class: "card-header",
+                        input {
+                            class: "form-input",
+                            r#type: "search",
+                            placeholder: "Search by name, email, or group",
+                            value: "{search}",
+                            oninput: move |e| search.set(e.value()),
+                        }
+                    }
+                    if filtered.read().is_empty() {
+                        div { class: "empty-state", "Nobody matches your search." }
+                    } else {
+                        div { class: "table-container",
+                            table {
+                                thead {
+                                    tr {
+                                        th { "Name" }
+                                        th { "Email" }
+                                        th { "Groups" }
+                                    }
+                                }
+                                tbody {
+                                    for entry in filtered.read().iter() {
+                                        tr { key: "{entry.uuid}",
+                                            td {
+                                                div { class: "user-name-cell",
+                                                    Avatar {
+                                                        key: "{entry.uuid}",
+                                                        user_id: entry.uuid,
+                                                        name: entry.display_name.clone(),
+                                                    }
+                                                    "{entry.display_name}"
+                                                }
+                                                if let Some(legal_name) = &entry.legal_name {
+                                                    div { class: "text-muted text-sm", "{legal_name}" }
+                                                }
+                                            }
+                                            td {
+                                                for address in entry.email_addresses.iter() {
+                                                    div {
+                                                        a { href: "mailto:{address}",
+                                                            Masked { kind: Sensitive::Email, text: address.clone() }
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            td {
+                                                for group in entry.groups.iter() {
+                                                    span { class: "badge", "{group}" }
+                                                    " "
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::components::{Masked, Sensitive, format_relative, format_timestamp};
+use crate::use_error;
+use dioxus::prelude::*;
+use types::email_queue::{EmailStatus, QueuedEmail};
+
+#[component]
+pub fn EmailQueue() -> Element {
+    let mut error_state = use_error();
+    let mut emails = use_signal(|| None::<Vec<QueuedEmail>>);
+    let mut failed_only = use_signal(|| false);
+
+    // Read outside the task, so the effect below reloads when it changes.
+    let refresh = move || {
+        let failed_only = failed_only();
+        spawn(async move {
+            match api::list_emails(failed_only).await {
+                Ok(e) => emails.set(Some(e)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+    });
+
+    let retry = move |id| {
+        spawn(async move {
+            match api::retry_email(id).await {
+                Ok(()) => refresh(),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Email" }
+                    p { class: "page-subtitle",
+                        "What AuthIt! has emailed. Emails that can't be sent are retried for a few hours, then can be requeued here."
+                    }
+                }
+                div { class: "page-header-actions",
+                    label { class: "checkbox-label",
+                        input {
+                            r#type: "checkbox",
+                            checked: failed_only(),
+                            onchange: move |e| failed_only.set(e.checked()),
+                        }
+                        "Only failed"
+                    }
+                    button { class: "btn btn-secondary", onclick: move |_| refresh(), "Refresh" }
+                }
+            }
+            div { class: "card",
+                if let Some(emails) = emails() {
+                    if emails.is_empty() {
+                        div { class: "card-body",
+                            p { class: "text-muted", "Nothing to show." }
+                        }
+                    } else {
+                        div { class: "table-container",
+                            table {
+                                thead {
+                                    tr {
+                                        th { "Queued" }
+                                        th { "To" }
+                                        th { "Subject" }
+                                        th { "Status" }
+                                    }
+                                }
+                                tbody {
+                                    for email in emails {
+                                        tr { key: "{email.id}",
+                                            td {
+                                                span { title: format_timestamp(email.queued_at),
+                                                    {format_relative(email.queued_at)}
+                                                }
+                                            }
+                                            td {
+                                                Masked { kind: Sensitive::Email, text: email.recipient.clone() }
+                                            }
+                                            td { "{email.subject}" }
+                                            td {
+                                                match email.status() {
+                                                    EmailStatus::Sent => rsx! {
+                                                        span { class: "badge", "Sent" }
+                                                        if let Some(at) = email.sent_at {
+                                                            div { class: "text-muted text-sm", {format_timestamp(at)} }
+                                                        }
+                                                    },
+                                                    EmailStatus::Failed => rsx! {
+                                                        span { class: "badge badge-warning", "Failed" }
+                                                        button {
+                                                            class: "btn btn-link",
+                                                            onclick: {
+                                                                let id = email.id;
+                                                                move |_| retry(id)
+                                                            },
+                                                            "Retry"
+                                                        }
+                                                    },
+                                                    EmailStatus::Pending => rsx! {
+                                                        span { class: "badge badge-primary", "Pending" }
+                                                        if let Some(at) = email.next_attempt_at {
+                                                            div { class: "text-muted text-sm", "Next try {format_timestamp(at)}" }
+                                                        }
+                                                    },
+                                                }
+                                                if let Some(error) = &email.error {
+                                                    div { class: "text-muted text-sm", "{error}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    div { class: "card-body",
+                        div { class: "loading", "Loading emails..." }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::LazyLock;
+
+use crate::use_error;
+use dioxus::{document::eval, prelude::*};
+use types::help::HelpContent;
+
+/// The help text, in English until there are translations to choose from.
+static CONTENT: LazyLock<HelpContent> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../../help/en.json")).expect("help content is valid JSON")
+});
+
+/// A "?" that explains `topic` when clicked.
+#[component]
+pub fn HelpTip(topic: String) -> Element {
+    let mut open = use_signal(|| false);
+    let Some(help) = CONTENT.topics.get(&topic) else {
+        return rsx! {};
+    };
+
+    rsx! {
+        span { class: "help-tip",
+            button {
+                class: "help-tip-button",
+                r#type: "button",
+                aria_label: "About {help.title}",
+                aria_expanded: open(),
+                onclick: move |_| open.set(!open()),
+                "?"
+            }
+            if open() {
+                div { class: "help-popover", role: "note",
+                    strong { "{help.title}" }
+                    for (i, paragraph) in help.body.split("\n\n").enumerate() {
+                        p { key: "{i}", "{paragraph}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where someone is in a tour: asked whether they'd like one, or at a step.
+#[derive(Clone, Copy, PartialEq)]
+enum TourState {
+    Offered,
+    At(usize),
+}
+
+/// Offer a tour of the Users page on an admin's first visit, pointing out
+/// each part in turn. Taking or turning it down is remembered.
+#[component]
+pub fn UsersTour() -> Element {
+    const NAME: &str = "users";
+    let mut error_state = use_error();
+    let mut state = use_signal(|| None::<TourState>);
+    let steps = &CONTENT.users_tour;
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(prefs) = api::get_preferences().await
+                && !prefs.seen_tours.iter().any(|t| t == NAME)
+            {
+                state.set(Some(TourState::Offered));
+            }
+        });
+    });
+
+    // Point at the current step's element, if it's shown.
+    use_effect(move || {
+        let target = match state() {
+            Some(TourState::At(i)) => CONTENT.users_tour.get(i).map(|s| s.target.as_str()),
+            _ => None,
+        };
+        let js = format!(
+            r#"
+            document.querySelectorAll(".tour-target").forEach(e => e.classList.remove("tour-target"));
+            const el = {target:?} && document.getElementById({target:?});
+            if (el) {{
+                el.classList.add("tour-target");
+                el.scrollIntoView({{ behavior: "smooth", block: "nearest" }});
+            }}
+            "#,
+            target = target.unwrap_or_default(),
+        );
+        let _ = eval(&js);
+    });
+
+    let mut finish = move || {
+        state.set(None);
+        spawn(async move {
+            let result = match api::get_preferences().await {
+                Ok(mut prefs) => {
+                    prefs.seen_tours.push(NAME.to_string());
+                    api::set_preferences(prefs).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error_state.set_server_error(&e);
+            }
+        });
+    };
+
+    match state() {
+        None => rsx! {},
+        Some(TourState::Offered) => rsx! {
+            div { class: "tour-panel", role: "dialog", aria_label: "Tour",
+                strong { "New here?" }
+                p { "Take a quick tour of the Users page." }
+                div { class: "tour-actions",
+                    button { class: "btn btn-link", onclick: move |_| finish(), "No thanks" }
+                    button {
+                        class: "btn btn-primary",
+                        onclick: move |_| state.set(Some(TourState::At(0))),
+                        "Start tour"
+                    }
+                }
+            }
+        },
+        Some(TourState::At(i)) => {
+            let Some(step) = steps.get(i) else {
+                return rsx! {};
+            };
+            let last = i + 1 == steps.len();
+            rsx! {
+                div { class: "tour-panel", role: "dialog", aria_label: "Tour",
+                    div { class: "text-muted text-sm", "{i + 1} of {steps.len()}" }
+                    strong { "{step.title}" }
+                    p { "{step.body}" }
+                    div { class: "tour-actions",
+                        button { class: "btn btn-link", onclick: move |_| finish(), "Skip" }
+                        if i > 0 {
+                            button {
+                                class: "btn btn-secondary",
+                                onclick: move |_| state.set(Some(TourState::At(i - 1))),
+                                "Back"
+                            }
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| {
+                                if last {
+                                    finish();
+                                } else {
+                                    state.set(Some(TourState::At(i + 1)));
+                                }
+                            },
+                            if last { "Done" } else { "Next" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use crate::Route;
 use dioxus::prelude::*;
 
 #[component]
 pub fn Login(error: Option<String>) -> Element {
+    let requests_enabled = use_resource(api::account_requests_enabled);
+    let setup_required = use_resource(api::setup_required);
+
     rsx! {
         div { class: "login-page",
             div { class: "login-card",
@@ -12,15 +16,31 @@ pub fn Login(error: Option<String>) -> Element {
                 if let Some(error) = &error {
                     div { class: "login-error", "{error}" }
                 }
+                if let Some(Ok(true)) = *setup_required.read() {
+                    div { class: "alert alert-error",
+                        "AuthIt! isn't set up yet. "
+                        Link { to: Route::Setup {}, "Set it up" }
+                    }
+                }
                 form {
                     action: "/auth/login",
                     method: "get",
+                    label { class: "checkbox-label login-remember",
+                        input { r#type: "checkbox", name: "remember", value: "true" }
+                        span { "Keep me signed in" }
+                    }
                     button {
                         r#type: "submit",
                         class: "btn btn-primary login-btn",
                         "Sign in with Kanidm"
                     }
                 }
+                if let Some(Ok(true)) = *requests_enabled.read() {
+                    p { class: "login-request text-sm",
+                        "Don't have an account? "
+                        Link { to: Route::RequestAccount {}, "Request one" }
+                    }
+                }
             }
         }
     }
@@ -1,4 +1,22 @@
 mod components;
+pub use components::{
+    AnnouncementBanner, Avatar, FavoriteGroups, Masking, Presence, Sensitive, format_timestamp,
+    sleep, use_masking,
+};
+
+mod account_requests;
+pub use account_requests::AccountRequests;
+
+mod audit;
+pub use audit::Audit;
+
+mod background_jobs;
+pub use background_jobs::BackgroundJobs;
+
+mod break_glass;
+pub use break_glass::BreakGlass;
+
+mod charts;
 
 mod login;
 pub use login::Login;
@@ -6,8 +24,48 @@ pub use login::Login;
 mod dashboard;
 pub use dashboard::Dashboard;
 
+mod directory;
+pub use directory::Directory;
+
+mod email_queue;
+pub use email_queue::EmailQueue;
+
+mod diagnostics;
+pub use diagnostics::Diagnostics;
+
+mod help;
+
+mod my_groups;
+pub use my_groups::MyGroups;
+
+mod offboarding;
+pub use offboarding::Offboardings;
+
+mod profile;
+pub use profile::Profile;
+
 mod provision;
 pub use provision::Provision;
 
+mod rotation;
+pub use rotation::Rotations;
+
+mod reports;
+pub use reports::Reports;
+
+mod request_account;
+pub use request_account::RequestAccount;
+
+mod sessions;
+pub use sessions::Sessions;
+
+mod settings;
+pub use settings::Settings;
+
+mod setup;
+pub use setup::Setup;
+
+mod user_prefetch;
+
 mod users;
-pub use users::Users;
+pub use users::{NewUserPrefill, Users, UsersQuery};
@@ -0,0 +1,167 @@
+use std::collections::BTreeSet;
+
+use super::components::{Masked, Sensitive};
+use crate::use_error;
+use dioxus::{document::eval, fullstack::reqwest::Url, prelude::*};
+use types::kanidm::Group;
+use uuid::Uuid;
+
+/// Groups the signed-in user owns, and invite links for them. Owners needn't
+/// be admins; the server only lets their links grant their own groups.
+#[component]
+pub fn MyGroups() -> Element {
+    let mut error_state = use_error();
+    let mut groups = use_signal(|| None::<Vec<Group>>);
+    let mut selected = use_signal(BTreeSet::<String>::new);
+    let mut duration_hours = use_signal(|| 72u32);
+    let mut max_uses = use_signal(|| Some(1u8));
+    let mut generating = use_signal(|| false);
+    let mut invite_url = use_signal(|| None::<Url>);
+    let mut copied = use_signal(|| false);
+    let mut idempotency_key = use_signal(Uuid::new_v4);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_my_groups().await {
+                Ok(g) => groups.set(Some(g)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(list) = groups() else {
+        return rsx! {
+            div { class: "loading", "Loading groups..." }
+        };
+    };
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "My Groups" }
+                    p { class: "page-subtitle", "Invite people to the groups you own." }
+                }
+            }
+            div { class: "card",
+                div { class: "card-header",
+                    h2 { class: "card-title", "New Invite Link" }
+                }
+                div { class: "card-body",
+                    if list.is_empty() {
+                        div { class: "empty-state", "You don't own any groups." }
+                    } else {
+                        div { class: "form-group",
+                            span { class: "form-label", "Add people to" }
+                            for group in list.iter() {
+                                label { class: "checkbox-label", key: "{group.uuid}",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: selected.read().contains(&group.name),
+                                        onchange: {
+                                            let name = group.name.clone();
+                                            move |e: FormEvent| {
+                                                if e.checked() {
+                                                    selected.write().insert(name.clone());
+                                                } else {
+                                                    selected.write().remove(&name);
+                                                }
+                                            }
+                                        },
+                                    }
+                                    span { "{group.name}" }
+                                }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "invite_duration", "Link expires in" }
+                            select {
+                                id: "invite_duration",
+                                class: "form-input",
+                                value: "{duration_hours}",
+                                onchange: move |e| {
+                                    if let Ok(v) = e.value().parse() {
+                                        duration_hours.set(v);
+                                    }
+                                },
+                                option { value: "24", "24 hours" }
+                                option { value: "72", "3 days" }
+                                option { value: "168", "7 days" }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "invite_max_uses", "Maximum uses" }
+                            select {
+                                id: "invite_max_uses",
+                                class: "form-input",
+                                value: "{max_uses().map(|n| n.to_string()).unwrap_or_default()}",
+                                onchange: move |e| {
+                                    let value = e.value();
+                                    if value.is_empty() {
+                                        max_uses.set(None);
+                                    } else if let Ok(v) = value.parse() {
+                                        max_uses.set(Some(v));
+                                    }
+                                },
+                                option { value: "1", "1 use (single person)" }
+                                option { value: "5", "5 uses" }
+                                option { value: "10", "10 uses" }
+                                option { value: "", "Unlimited" }
+                            }
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: selected.read().is_empty() || generating(),
+                            onclick: move |_| {
+                                let group_names: Vec<String> = selected.read().iter().cloned().collect();
+                                spawn(async move {
+                                    generating.set(true);
+                                    match api::create_group_invite(
+                                            duration_hours(),
+                                            max_uses(),
+                                            group_names,
+                                            Some(idempotency_key()),
+                                        )
+                                        .await
+                                    {
+                                        Ok(url) => {
+                                            invite_url.set(Some(url));
+                                            copied.set(false);
+                                            idempotency_key.set(Uuid::new_v4());
+                                        }
+                                        Err(e) => error_state.set_server_error(&e),
+                                    }
+                                    generating.set(false);
+                                });
+                            },
+                            if generating() { "Generating..." } else { "Generate Link" }
+                        }
+                        if let Some(url) = invite_url() {
+                            p { "Share this link with the people you want to invite:" }
+                            div { class: "code-block-wrapper",
+                                div { class: "code-block",
+                                    Masked { kind: Sensitive::Token, text: url.clone() }
+                                }
+                                button {
+                                    class: if copied() { "copy-btn copied" } else { "copy-btn" },
+                                    onclick: move |_| {
+                                        let js = format!(
+                                            r#"navigator.clipboard.writeText("{}")"#,
+                                            url.to_string().replace("\"", "\\\""),
+                                        );
+                                        spawn(async move {
+                                            if eval(&js).recv::<()>().await.is_ok() {
+                                                copied.set(true);
+                                            }
+                                        });
+                                    },
+                                    if copied() { "Copied!" } else { "Copy" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use super::components::{format_timestamp, parse_datetime_input, to_datetime_input};
+use crate::{use_capabilities, use_error};
+use dioxus::prelude::*;
+use types::{capability::Capability, offboarding::Offboarding};
+use uuid::Uuid;
+
+#[component]
+pub fn Offboardings() -> Element {
+    let mut error_state = use_error();
+    let mut offboardings = use_signal(Vec::<Offboarding>::new);
+    let mut loading = use_signal(|| true);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_offboardings().await {
+                Ok(o) => offboardings.set(o),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loading.set(false);
+        });
+    });
+
+    // Replace an offboarding after a change, or remove it if it's gone.
+    let mut update = move |id, updated: Option<Offboarding>| {
+        let mut list = offboardings.write();
+        match updated {
+            Some(o) => {
+                if let Some(existing) = list.iter_mut().find(|o| o.id == id) {
+                    *existing = o;
+                }
+            }
+            None => list.retain(|o| o.id != id),
+        }
+    };
+
+    let (active, completed): (Vec<Offboarding>, Vec<Offboarding>) = offboardings
+        .read()
+        .iter()
+        .cloned()
+        .partition(|o| !o.is_complete());
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Offboarding" }
+                    p { class: "page-subtitle", "Checklists for removing people's access. Start one from a user's details." }
+                }
+            }
+            if loading() {
+                div { class: "loading", "Loading offboardings..." }
+            } else {
+                div { class: "grid",
+                    if active.is_empty() {
+                        div { class: "card",
+                            div { class: "empty-state", "Nobody is being offboarded." }
+                        }
+                    }
+                    for offboarding in active {
+                        OffboardingCard {
+                            key: "{offboarding.id}",
+                            offboarding: offboarding.clone(),
+                            on_change: move |(id, updated)| update(id, updated),
+                        }
+                    }
+                    if !completed.is_empty() {
+                        div { class: "card",
+                            div { class: "card-header",
+                                h2 { class: "card-title", "Completed" }
+                            }
+                            div { class: "table-container",
+                                table {
+                                    thead {
+                                        tr {
+                                            th { "User" }
+                                            th { "Started by" }
+                                            th { "Started" }
+                                            th { "Completed" }
+                                            th { "Account" }
+                                        }
+                                    }
+                                    tbody {
+                                        for o in completed {
+                                            tr { key: "{o.id}",
+                                                td { "{o.user_name}" }
+                                                td { "{o.started_by}" }
+                                                td { {format_timestamp(o.started_at)} }
+                                                td { {o.completed_at.map(format_timestamp)} }
+                                                td {
+                                                    if let Some(at) = o.deleted_at {
+                                                        "Deleted {format_timestamp(at)}"
+                                                    } else if let Some(at) = o.delete_at {
+                                                        "Deletion scheduled {format_timestamp(at)}"
+                                                    } else {
+                                                        "Not deleted"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn OffboardingCard(
+    offboarding: Offboarding,
+    on_change: EventHandler<(Uuid, Option<Offboarding>)>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut busy = use_signal(|| false);
+    let mut disabled_account = use_signal(|| false);
+    let capabilities = use_capabilities();
+    let locked = !capabilities.allows(Capability::ManagePersons);
+    let disabled_reason = capabilities.why_not(Capability::ManagePersons);
+    let mut delete_at = use_signal(|| {
+        offboarding
+            .delete_at
+            .map(to_datetime_input)
+            .unwrap_or_default()
+    });
+
+    let id = offboarding.id;
+    let done = offboarding.steps_done();
+    let total = offboarding.steps.len();
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "{offboarding.user_name}" }
+                span { class: "badge", "{done}/{total} done" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Started by {offboarding.started_by}, {format_timestamp(offboarding.started_at)}"
+                }
+                div { class: "checkbox-group",
+                    for (index, step) in offboarding.steps.iter().enumerate() {
+                        label { class: "checkbox-label", key: "{index}",
+                            input {
+                                r#type: "checkbox",
+                                checked: step.is_done(),
+                                disabled: busy(),
+                                onchange: {
+                                    let mark_done = !step.is_done();
+                                    move |_| {
+                                        spawn(async move {
+                                            busy.set(true);
+                                            match api::set_offboarding_step(id, index as u32, mark_done).await {
+                                                Ok(o) => on_change.call((id, Some(o))),
+                                                Err(e) => error_state.set_server_error(&e),
+                                            }
+                                            busy.set(false);
+                                        });
+                                    }
+                                },
+                            }
+                            span { "{step.label}" }
+                            if let (Some(by), Some(at)) = (&step.done_by, step.done_at) {
+                                span { class: "text-muted text-sm", " — {by}, {format_timestamp(at)}" }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "divider" }
+
+                h3 { class: "section-header", "Account" }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: locked || busy() || disabled_account() || offboarding.deleted_at.is_some(),
+                        title: disabled_reason.clone(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                busy.set(true);
+                                match api::disable_offboarded_account(id).await {
+                                    Ok(()) => disabled_account.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                busy.set(false);
+                            });
+                        },
+                        if disabled_account() { "Account disabled" } else { "Disable account now" }
+                    }
+                }
+                if let Some(at) = offboarding.deleted_at {
+                    p { class: "text-muted", "Deleted {format_timestamp(at)}." }
+                } else {
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "delete_at_{id}", "Delete account at" }
+                        input {
+                            id: "delete_at_{id}",
+                            class: "form-input",
+                            r#type: "datetime-local",
+                            value: "{delete_at}",
+                            oninput: move |e| delete_at.set(e.value()),
+                        }
+                    }
+                    if let Some(at) = offboarding.delete_at {
+                        p { class: "text-muted text-sm", "Scheduled for {format_timestamp(at)}." }
+                    }
+                    div { class: "page-header-actions",
+                        button {
+                            class: "btn btn-danger",
+                            disabled: locked || busy() || parse_datetime_input(&delete_at.read()).is_none(),
+                            title: disabled_reason.clone(),
+                            onclick: move |_| {
+                                let at = parse_datetime_input(&delete_at.read());
+                                spawn(async move {
+                                    busy.set(true);
+                                    match api::schedule_offboarding_deletion(id, at).await {
+                                        Ok(o) => on_change.call((id, Some(o))),
+                                        Err(e) => error_state.set_server_error(&e),
+                                    }
+                                    busy.set(false);
+                                });
+                            },
+                            "Schedule deletion"
+                        }
+                        if offboarding.delete_at.is_some() {
+                            button {
+                                class: "btn btn-secondary",
+                                disabled: locked || busy(),
+                                title: disabled_reason.clone(),
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        busy.set(true);
+                                        match api::schedule_offboarding_deletion(id, None).await {
+                                            Ok(o) => {
+                                                delete_at.set(String::new());
+                                                on_change.call((id, Some(o)));
+                                            }
+                                            Err(e) => error_state.set_server_error(&e),
+                                        }
+                                        busy.set(false);
+                                    });
+                                },
+                                "Cancel deletion"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "divider" }
+
+                button {
+                    class: "btn btn-link",
+                    disabled: busy(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            busy.set(true);
+                            match api::cancel_offboarding(id).await {
+                                Ok(()) => on_change.call((id, None)),
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            busy.set(false);
+                        });
+                    },
+                    "Cancel offboarding"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use super::components::{Masked, Sensitive, format_timestamp};
+use crate::use_error;
+use dioxus::prelude::*;
+use types::{
+    email_verification::{CODE_LENGTH, EmailAddressStatus},
+    profile_change::{ChangePolicy, MyProfile, ProfileChange, ProfileChangeStatus, ProfileField},
+};
+
+/// The signed-in user's own display name and email, which they can change or
+/// ask an admin to change, as the config allows, and which of their email
+/// addresses they've verified.
+#[component]
+pub fn Profile() -> Element {
+    let mut error_state = use_error();
+    let mut profile = use_signal(|| None::<MyProfile>);
+    let mut message = use_signal(|| None::<String>);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_my_profile().await {
+                Ok(p) => profile.set(Some(p)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let Some(current) = profile() else {
+        return rsx! {
+            div { class: "loading", "Loading profile..." }
+        };
+    };
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Profile" }
+                    p { class: "page-subtitle", "How you appear to everyone else." }
+                }
+            }
+            if let Some(message) = message() {
+                div { class: "alert alert-success", "{message}" }
+            }
+            if current.policy.any() {
+                div { class: "card",
+                    div { class: "card-body",
+                        for field in ProfileField::ALL {
+                            if current.policy.of(field) != ChangePolicy::Off {
+                                ProfileFieldForm {
+                                    key: "{field.as_str()}",
+                                    field,
+                                    policy: current.policy.of(field),
+                                    value: match field {
+                                        ProfileField::DisplayName => current.display_name.clone(),
+                                        ProfileField::Email => current.email.clone().unwrap_or_default(),
+                                    },
+                                    pending: current
+                                        .changes
+                                        .iter()
+                                        .any(|c| c.field == field && c.status == ProfileChangeStatus::Pending),
+                                    on_saved: move |applied: bool| {
+                                        message.set(Some(if applied {
+                                            format!("Your {} was changed.", field.label().to_lowercase())
+                                        } else {
+                                            "Your change was sent to an admin to approve.".to_string()
+                                        }));
+                                        refresh();
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if current.can_verify_email && !current.email_addresses.is_empty() {
+                div { class: "card",
+                    div { class: "card-header",
+                        h2 { class: "card-title", "Email Addresses" }
+                    }
+                    div { class: "card-body",
+                        for status in current.email_addresses.iter().cloned() {
+                            EmailVerificationRow {
+                                key: "{status.address}",
+                                status,
+                                on_verified: move |address: String| {
+                                    message.set(Some(format!("{address} was verified.")));
+                                    refresh();
+                                },
+                            }
+                        }
+                    }
+                }
+            }
+            if !current.changes.is_empty() {
+                div { class: "card",
+                    div { class: "card-header",
+                        h2 { class: "card-title", "Your Requests" }
+                    }
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Requested" }
+                                    th { "Field" }
+                                    th { "Change" }
+                                    th { "Status" }
+                                }
+                            }
+                            tbody {
+                                for change in current.changes.iter().cloned() {
+                                    tr { key: "{change.id}",
+                                        td { {format_timestamp(change.created_at)} }
+                                        td { "{change.field.label()}" }
+                                        td { "{change.old_value} → {change.new_value}" }
+                                        td {
+                                            ProfileChangeStatusBadge { change }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProfileFieldForm(
+    field: ProfileField,
+    policy: ChangePolicy,
+    value: String,
+    pending: bool,
+    on_saved: EventHandler<bool>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut draft = use_signal(|| value.clone());
+    let mut saving = use_signal(|| false);
+    let id = format!("profile_{}", field.as_str());
+    let input_type = match field {
+        ProfileField::DisplayName => "text",
+        ProfileField::Email => "email",
+    };
+    let unchanged = draft.read().trim() == value || draft.read().trim().is_empty();
+
+    rsx! {
+        div { class: "form-group",
+            label { class: "form-label", r#for: "{id}", "{field.label()}" }
+            div { class: "page-header-actions",
+                input {
+                    id: "{id}",
+                    class: "form-input",
+                    r#type: input_type,
+                    value: "{draft}",
+                    disabled: pending,
+                    oninput: move |e| draft.set(e.value()),
+                }
+                button {
+                    class: "btn btn-primary",
+                    disabled: pending || unchanged || saving(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            saving.set(true);
+                            match api::propose_profile_change(field, draft()).await {
+                                Ok(applied) => on_saved.call(applied),
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            saving.set(false);
+                        });
+                    },
+                    if saving() {
+                        "Saving..."
+                    } else if policy == ChangePolicy::Review {
+                        "Request Change"
+                    } else {
+                        "Save"
+                    }
+                }
+            }
+            if pending {
+                p { class: "text-muted text-sm", "You've asked to change this. An admin will review it." }
+            } else if policy == ChangePolicy::Review {
+                p { class: "text-muted text-sm", "An admin will review changes before they're made." }
+            }
+        }
+    }
+}
+
+/// One of the user's email addresses, with a way to verify it by entering a
+/// code emailed to it.
+#[component]
+fn EmailVerificationRow(status: EmailAddressStatus, on_verified: EventHandler<String>) -> Element {
+    let mut error_state = use_error();
+    let mut sent = use_signal(|| false);
+    let mut sending = use_signal(|| false);
+    let mut code = use_signal(String::new);
+    let address = use_signal(|| status.address.clone());
+
+    rsx! {
+        div { class: "form-group",
+            div { class: "page-header-actions",
+                span { class: "form-value",
+                    Masked { kind: Sensitive::Email, text: status.address.clone() }
+                }
+                if let Some(at) = status.verified_at {
+                    span { class: "badge", title: "{format_timestamp(at)}", "Verified" }
+                } else {
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: sending(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                sending.set(true);
+                                match api::send_email_verification(address()).await {
+                                    Ok(()) => sent.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                sending.set(false);
+                            });
+                        },
+                        if sending() {
+                            "Sending..."
+                        } else if sent() {
+                            "Send Again"
+                        } else {
+                            "Send Code"
+                        }
+                    }
+                }
+            }
+            if sent() && !status.is_verified() {
+                div { class: "page-header-actions",
+                    input {
+                        class: "form-input",
+                        r#type: "text",
+                        inputmode: "numeric",
+                        autocomplete: "one-time-code",
+                        maxlength: CODE_LENGTH as i64,
+                        placeholder: "Code",
+                        value: "{code}",
+                        oninput: move |e| code.set(e.value()),
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: code.read().trim().len() != CODE_LENGTH,
+                        onclick: move |_| {
+                            spawn(async move {
+                                match api::confirm_email_verification(address(), code()).await {
+                                    Ok(()) => on_verified.call(address()),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                            });
+                        },
+                        "Verify"
+                    }
+                }
+                p { class: "text-muted text-sm", "Enter the code we emailed to this address." }
+            }
+        }
+    }
+}
+
+/// The status of a profile change, with who decided it and why.
+#[component]
+pub fn ProfileChangeStatusBadge(change: ProfileChange) -> Element {
+    let decided = match (&change.decided_by, change.decided_at) {
+        (Some(by), Some(at)) => format!("by {by}, {}", format_timestamp(at)),
+        _ => String::new(),
+    };
+
+    let (class, label) = match change.status {
+        ProfileChangeStatus::Pending => ("badge badge-primary", "Pending"),
+        ProfileChangeStatus::Approved => ("badge", "Approved"),
+        ProfileChangeStatus::Denied => ("badge", "Denied"),
+    };
+
+    rsx! {
+        span { class, "{label}" }
+        if !decided.is_empty() {
+            div { class: "text-muted text-sm", "{decided}" }
+        }
+        if let Some(reason) = &change.reason {
+            div { class: "text-muted text-sm", "{reason}" }
+        }
+    }
+}
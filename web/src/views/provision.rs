@@ -1,28 +1,115 @@
-use super::components::UserForm;
-use dioxus::prelude::*;
-use types::ResetLink;
+use super::components::{AnnouncementBanner, FieldError, UserForm, sleep};
+use crate::field_errors;
+use dioxus::{document::eval, prelude::*};
+use jiff::Timestamp;
+use types::{
+    provision::{FieldRequirement, ProvisionCompletion, ProvisionFields, VerifiedProvisionLink},
+    validation::FieldErrors,
+};
+use uuid::Uuid;
 
 #[component]
 pub fn Provision(token: String) -> Element {
     let username = use_signal(String::new);
     let display_name = use_signal(String::new);
     let email = use_signal(String::new);
+    let mut phone = use_signal(String::new);
     let mut submitting = use_signal(|| false);
+    // Sent with the request, so retrying it can't use the link twice.
+    let idempotency_key = use_hook(Uuid::new_v4);
     let mut error = use_signal(|| None::<String>);
-    let mut reset_link = use_signal(|| None::<ResetLink>);
+    let mut errors = use_signal(FieldErrors::default);
+    let mut completion = use_signal(|| None::<ProvisionCompletion>);
+    let mut token_valid = use_signal(|| None::<Result<VerifiedProvisionLink, ServerFnError>>);
+    let mut now = use_signal(Timestamp::now);
 
-    // Verify token on mount
+    // Verify the token on mount, and again whenever the tab comes back into
+    // view, so a page left open overnight shows whether the link still works
+    // rather than failing on submit.
     let token_for_verify = token.clone();
-    let token_valid = use_resource(move || {
+    use_effect(move || {
         let token = token_for_verify.clone();
-        async move { api::verify_provision(token).await }
+        spawn(async move {
+            token_valid.set(Some(api::verify_provision(token.clone(), false).await));
+            let mut returns = eval(
+                r#"
+                const send = () => {
+                    if (document.visibilityState === "visible") dioxus.send(null);
+                };
+                document.addEventListener("visibilitychange", send);
+                window.addEventListener("focus", send);
+                "#,
+            );
+            while returns.recv::<()>().await.is_ok() {
+                if completion.read().is_none() && !submitting() {
+                    token_valid.set(Some(api::verify_provision(token.clone(), true).await));
+                }
+            }
+        });
     });
 
-    let can_submit = !username.read().is_empty() && !display_name.read().is_empty();
+    // Tick the countdown, checking the link again once it runs out.
+    let token_for_expiry = token.clone();
+    use_effect(move || {
+        let token = token_for_expiry.clone();
+        spawn(async move {
+            // Our clock may not quite match the server's, so only check once.
+            let mut checked = None;
+            loop {
+                sleep(1000).await;
+                now.set(Timestamp::now());
+                let expired = match &*token_valid.read() {
+                    Some(Ok(link)) if link.expires_at <= now() => Some(link.expires_at),
+                    _ => None,
+                };
+                if expired.is_some() && expired != checked && completion.read().is_none() {
+                    checked = expired;
+                    token_valid.set(Some(api::verify_provision(token.clone(), true).await));
+                }
+            }
+        });
+    });
+
+    let fields = match &*token_valid.read() {
+        Some(Ok(link)) => link.fields.clone(),
+        _ => ProvisionFields::default(),
+    };
+    let can_submit = !username.read().is_empty()
+        && !display_name.read().is_empty()
+        && fields.validate(&email.read(), &phone.read()).is_ok();
+
+    if let Some(ProvisionCompletion::AwaitingApproval) = completion.read().as_ref() {
+        return rsx! {
+            div { class: "provision-page",
+                div { class: "provision-card",
+                    div { class: "provision-header",
+                        h1 { class: "provision-title", "Account Requested" }
+                    }
+                    div { class: "provision-body",
+                        p {
+                            "Your account has been created, but an administrator needs to approve it before you can use it. Once they do, we'll email you a link to set up your credentials."
+                        }
+                    }
+                }
+            }
+        };
+    }
 
     // If we have a reset link, redirect to it
-    if let Some(link) = reset_link.read().as_ref() {
+    if let Some(ProvisionCompletion::ResetLink {
+        link,
+        next_steps,
+        redirect_url,
+    }) = completion.read().as_ref()
+    {
         let url = link.url.clone();
+        let steps: Vec<String> = next_steps
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(String::from)
+            .collect();
+        let redirect_url = redirect_url.clone();
         return rsx! {
             div { class: "provision-page",
                 div { class: "provision-card",
@@ -31,10 +118,27 @@ pub fn Provision(token: String) -> Element {
                     }
                     div { class: "provision-body",
                         p { "Your account has been created. Click the button below to set up your credentials." }
-                        a {
-                            href: "{url}",
-                            class: "btn btn-primary btn-lg",
-                            "Set Up Credentials"
+                        for step in steps {
+                            p { "{step}" }
+                        }
+                        if let Some(redirect_url) = redirect_url {
+                            // Credential setup happens on the identity server, which
+                            // can't send them back, so it opens alongside this page.
+                            a {
+                                href: "{url}",
+                                target: "_blank",
+                                rel: "noopener",
+                                class: "btn btn-primary btn-lg",
+                                "Set Up Credentials"
+                            }
+                            p { class: "text-muted text-sm", "Once you've set up your credentials, continue." }
+                            a { href: "{redirect_url}", class: "btn btn-secondary", "Continue" }
+                        } else {
+                            a {
+                                href: "{url}",
+                                class: "btn btn-primary btn-lg",
+                                "Set Up Credentials"
+                            }
                         }
                     }
                 }
@@ -43,20 +147,72 @@ pub fn Provision(token: String) -> Element {
     }
 
     match &*token_valid.read() {
-        Some(Ok(_)) => {
+        Some(Ok(link)) => {
+            let expires_in = link.expires_at.as_second() - now().as_second();
             rsx! {
                 div { class: "provision-page",
                     div { class: "provision-card",
                         div { class: "provision-header",
+                            AnnouncementBanner {}
                             h1 { class: "provision-title", "Create Your Account" }
                             p { class: "provision-subtitle", "Enter your information to create your account." }
+                            p { class: "text-muted text-sm",
+                                "This link expires in {format_countdown(expires_in)}"
+                                if let Some(uses) = link.uses_remaining {
+                                    if uses == 1 {
+                                        " and can create one more account."
+                                    } else {
+                                        " and can create {uses} more accounts."
+                                    }
+                                } else {
+                                    "."
+                                }
+                            }
                         }
                         div { class: "provision-body",
                             if let Some(err) = error.read().as_ref() {
                                 div { class: "alert alert-error", "{err}" }
                             }
 
-                            UserForm { username, display_name, email }
+                            UserForm {
+                                username,
+                                display_name,
+                                email,
+                                email_field: fields.email,
+                                errors: errors(),
+                            }
+                            if !link.groups.is_empty() {
+                                div { class: "form-group",
+                                    label { class: "form-label", "You'll be added to" }
+                                    ul { class: "provision-groups",
+                                        for group in link.groups.clone() {
+                                            li { key: "{group.name}",
+                                                strong { "{group.name}" }
+                                                if let Some(description) = group.description {
+                                                    div { class: "text-muted text-sm", "{description}" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            if fields.phone.is_shown() {
+                                div { class: "form-group",
+                                    label { class: "form-label", r#for: "phone",
+                                        if fields.phone == FieldRequirement::Required { "Phone (required)" } else { "Phone" }
+                                    }
+                                    input {
+                                        id: "phone",
+                                        class: "form-input",
+                                        r#type: "tel",
+                                        placeholder: "e.g. +15551234567",
+                                        required: fields.phone == FieldRequirement::Required,
+                                        value: "{phone}",
+                                        oninput: move |e| phone.set(e.value()),
+                                    }
+                                    FieldError { errors: errors(), field: "phone" }
+                                }
+                            }
                         }
                         div { class: "provision-footer",
                             button {
@@ -65,16 +221,26 @@ pub fn Provision(token: String) -> Element {
                                 onclick: {
                                     let token = token.clone();
                                     move |_| {
+                                        // A second click can land before the button is
+                                        // disabled.
+                                        if submitting() {
+                                            return;
+                                        }
+                                        submitting.set(true);
                                         let token = token.clone();
                                         let name = username.read().clone();
                                         let dname = display_name.read().clone();
                                         let email_address = email.read().clone();
+                                        let phone_number = phone.read().clone();
                                         spawn(async move {
-                                            submitting.set(true);
                                             error.set(None);
-                                            match api::complete_provision(token, name, dname, email_address).await {
-                                                Ok(link) => reset_link.set(Some(link)),
-                                                Err(e) => error.set(Some(e.to_string())),
+                                            errors.set(FieldErrors::default());
+                                            match api::complete_provision(token, name, dname, email_address, phone_number, Some(idempotency_key)).await {
+                                                Ok(done) => completion.set(Some(done)),
+                                                Err(e) => match field_errors(&e) {
+                                                    Some(e) => errors.set(e),
+                                                    None => error.set(Some(e.to_string())),
+                                                },
                                             }
                                             submitting.set(false);
                                         });
@@ -115,3 +281,16 @@ pub fn Provision(token: String) -> Element {
         }
     }
 }
+
+/// How long is left, like "2h 05m" or "4m 30s".
+fn format_countdown(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else {
+        format!("{minutes}m {:02}s", secs % 60)
+    }
+}
@@ -0,0 +1,311 @@
+use super::{
+    charts::{BarChart, ChartBar},
+    components::download,
+};
+use crate::use_error;
+use dioxus::prelude::*;
+use jiff::{Timestamp, ToSpan, civil::Date, tz::TimeZone};
+use types::report::{DigestFrequency, Report, ReportEventKind, ReportSettings};
+
+#[component]
+pub fn Reports() -> Element {
+    let mut error_state = use_error();
+    let today = Timestamp::now().to_zoned(TimeZone::UTC).date();
+    let mut from = use_signal(move || today.checked_sub(29.days()).unwrap_or(today).to_string());
+    let mut to = use_signal(move || today.to_string());
+    let mut report = use_signal(|| None::<Report>);
+    let mut loading = use_signal(|| false);
+
+    let refresh = move || {
+        spawn(async move {
+            let (Ok(start), Ok(end)) = (from().parse::<Date>(), to().parse::<Date>()) else {
+                error_state.set("Choose the days the report should cover.".to_string());
+                return;
+            };
+            loading.set(true);
+            match api::get_report(start, end).await {
+                Ok(r) => report.set(Some(r)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loading.set(false);
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+    });
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Reports" }
+                    p { class: "page-subtitle",
+                        "What happened to accounts and groups over a period, by UTC day."
+                    }
+                }
+            }
+            div { class: "card",
+                div { class: "card-body",
+                    div { class: "report-range",
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "report_from", "From" }
+                            input {
+                                id: "report_from",
+                                class: "form-input",
+                                r#type: "date",
+                                value: "{from}",
+                                oninput: move |e| from.set(e.value()),
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "report_to", "To" }
+                            input {
+                                id: "report_to",
+                                class: "form-input",
+                                r#type: "date",
+                                value: "{to}",
+                                oninput: move |e| to.set(e.value()),
+                            }
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: loading(),
+                            onclick: move |_| {
+                                refresh();
+                            },
+                            if loading() { "Loading..." } else { "Run Report" }
+                        }
+                    }
+                }
+            }
+            if let Some(report) = report() {
+                ReportResults { report }
+            }
+            ReportSettingsCard {}
+        }
+    }
+}
+
+#[component]
+fn ReportResults(report: Report) -> Element {
+    let days_csv = report.days_csv();
+    let groups_csv = report.groups_csv();
+    let filename = format!("authit-report-{}-to-{}", report.from, report.to);
+    let days_filename = format!("{filename}-days.csv");
+    let groups_filename = format!("{filename}-groups.csv");
+    let busiest = report
+        .groups
+        .iter()
+        .map(|churn| churn.added + churn.removed)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let groups: Vec<_> = report
+        .groups
+        .iter()
+        .map(|churn| (churn, (churn.added + churn.removed) * 100 / busiest))
+        .collect();
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "{report.from} to {report.to}" }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| download(&days_filename, "text/csv", days_csv.clone()),
+                        "Download Days CSV"
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| download(&groups_filename, "text/csv", groups_csv.clone()),
+                        "Download Groups CSV"
+                    }
+                }
+            }
+            div { class: "card-body",
+                div { class: "report-grid",
+                    for kind in ReportEventKind::ALL {
+                        div { key: "{kind.as_str()}", class: "report-tile",
+                            div { class: "report-tile-header",
+                                span { class: "text-muted text-sm", "{kind.label()}" }
+                                span { class: "report-total", "{report.totals.of(kind)}" }
+                            }
+                            BarChart {
+                                bars: report
+                                    .days
+                                    .iter()
+                                    .map(|day| ChartBar {
+                                        label: day.date.to_string(),
+                                        value: day.counts.of(kind),
+                                    })
+                                    .collect::<Vec<_>>(),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Membership Changes by Group" }
+            }
+            if report.groups.is_empty() {
+                div { class: "card-body",
+                    p { class: "text-muted text-sm", "Nobody was added to or removed from a group." }
+                }
+            } else {
+                div { class: "table-container",
+                    table {
+                        thead {
+                            tr {
+                                th { "Group" }
+                                th { "Added" }
+                                th { "Removed" }
+                                th { "" }
+                            }
+                        }
+                        tbody {
+                            for (churn, percent) in groups.iter() {
+                                tr { key: "{churn.group}",
+                                    td { "{churn.group}" }
+                                    td { "{churn.added}" }
+                                    td { "{churn.removed}" }
+                                    td { class: "report-churn",
+                                        div { class: "progress",
+                                            div {
+                                                class: "progress-bar",
+                                                style: "width: {percent}%",
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Who gets last month's report by email, at the start of each month, and
+/// who gets the activity digest.
+#[component]
+fn ReportSettingsCard() -> Element {
+    let mut error_state = use_error();
+    let mut recipients = use_signal(String::new);
+    let mut digest = use_signal(DigestFrequency::default);
+    let mut digest_recipients = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_report_settings().await {
+                Ok(s) => {
+                    recipients.set(s.recipients.join("\n"));
+                    digest.set(s.digest);
+                    digest_recipients.set(s.digest_recipients.join("\n"));
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Emailed Reports" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "At the start of each month, last month's report is emailed to these addresses. Leave it empty to send none."
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "report_recipients", "Recipients, one per line" }
+                    textarea {
+                        id: "report_recipients",
+                        class: "form-input",
+                        rows: "3",
+                        value: "{recipients}",
+                        oninput: move |e| {
+                            recipients.set(e.value());
+                            saved.set(false);
+                        },
+                    }
+                }
+                p { class: "text-muted text-sm",
+                    "The activity digest lists accounts created and deleted, changes to the admin group, and provision links about to expire."
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "report_digest", "Activity digest" }
+                    select {
+                        id: "report_digest",
+                        class: "form-input",
+                        value: "{digest().as_str()}",
+                        onchange: move |e| {
+                            if let Ok(frequency) = e.value().parse() {
+                                digest.set(frequency);
+                                saved.set(false);
+                            }
+                        },
+                        for frequency in DigestFrequency::ALL {
+                            option { value: "{frequency.as_str()}", "{frequency.label()}" }
+                        }
+                    }
+                }
+                if digest() != DigestFrequency::Never {
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "report_digest_recipients", "Digest recipients, one per line" }
+                        textarea {
+                            id: "report_digest_recipients",
+                            class: "form-input",
+                            rows: "3",
+                            value: "{digest_recipients}",
+                            oninput: move |e| {
+                                digest_recipients.set(e.value());
+                                saved.set(false);
+                            },
+                        }
+                    }
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            let settings = ReportSettings {
+                                recipients: lines(&recipients.read()),
+                                digest: digest(),
+                                digest_recipients: lines(&digest_recipients.read()),
+                            };
+                            spawn(async move {
+                                saving.set(true);
+                                match api::set_report_settings(settings).await {
+                                    Ok(()) => saved.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                }
+                if saved() {
+                    p { class: "text-muted text-sm", "Saved." }
+                }
+            }
+        }
+    }
+}
+
+/// The non-empty lines of a textarea, trimmed.
+fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
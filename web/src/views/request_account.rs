@@ -0,0 +1,133 @@
+use super::components::AnnouncementBanner;
+use dioxus::prelude::*;
+use types::validation::validate_email;
+use uuid::Uuid;
+
+#[component]
+pub fn RequestAccount() -> Element {
+    let mut name = use_signal(String::new);
+    let mut email = use_signal(String::new);
+    let mut justification = use_signal(String::new);
+    let mut submitting = use_signal(|| false);
+    // Sent with the request, so retrying it can't submit it twice.
+    let idempotency_key = use_hook(Uuid::new_v4);
+    let mut submitted = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let enabled = use_resource(api::account_requests_enabled);
+
+    let can_submit = !name.read().trim().is_empty()
+        && validate_email(email.read().trim()).is_ok()
+        && !justification.read().trim().is_empty();
+
+    if submitted() {
+        return rsx! {
+            div { class: "provision-page",
+                div { class: "provision-card",
+                    div { class: "provision-header",
+                        h1 { class: "provision-title", "Request Sent" }
+                    }
+                    div { class: "provision-body",
+                        p { "An administrator will review your request. If it's approved, you'll get an email with a link to create your account." }
+                    }
+                }
+            }
+        };
+    }
+
+    match &*enabled.read() {
+        Some(Ok(true)) => {
+            rsx! {
+                div { class: "provision-page",
+                    div { class: "provision-card",
+                        div { class: "provision-header",
+                            AnnouncementBanner {}
+                            h1 { class: "provision-title", "Request an Account" }
+                            p { class: "provision-subtitle", "Tell us who you are and why you need an account." }
+                        }
+                        div { class: "provision-body",
+                            if let Some(err) = error.read().as_ref() {
+                                div { class: "alert alert-error", "{err}" }
+                            }
+                            div { class: "form-group",
+                                label { class: "form-label", r#for: "name", "Name" }
+                                input {
+                                    id: "name",
+                                    class: "form-input",
+                                    r#type: "text",
+                                    placeholder: "e.g. John Smith",
+                                    value: "{name}",
+                                    oninput: move |e| name.set(e.value()),
+                                }
+                            }
+                            div { class: "form-group",
+                                label { class: "form-label", r#for: "email", "Email" }
+                                input {
+                                    id: "email",
+                                    class: "form-input",
+                                    r#type: "email",
+                                    placeholder: "e.g. jsmith@example.com",
+                                    value: "{email}",
+                                    oninput: move |e| email.set(e.value()),
+                                }
+                            }
+                            div { class: "form-group",
+                                label { class: "form-label", r#for: "justification", "Why do you need an account?" }
+                                textarea {
+                                    id: "justification",
+                                    class: "form-input",
+                                    rows: "4",
+                                    value: "{justification}",
+                                    oninput: move |e| justification.set(e.value()),
+                                }
+                            }
+                        }
+                        div { class: "provision-footer",
+                            button {
+                                class: "btn btn-primary btn-lg",
+                                disabled: !can_submit || submitting(),
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        submitting.set(true);
+                                        error.set(None);
+                                        match api::submit_account_request(name(), email(), justification(), Some(idempotency_key)).await {
+                                            Ok(()) => submitted.set(true),
+                                            Err(e) => error.set(Some(e.to_string())),
+                                        }
+                                        submitting.set(false);
+                                    });
+                                },
+                                if submitting() { "Sending..." } else { "Send Request" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Some(_) => {
+            rsx! {
+                div { class: "provision-page",
+                    div { class: "provision-card",
+                        div { class: "provision-header",
+                            h1 { class: "provision-title", "Unavailable" }
+                        }
+                        div { class: "provision-body",
+                            p { "Account requests aren't accepted here. Please contact your administrator." }
+                        }
+                    }
+                }
+            }
+        }
+        None => {
+            rsx! {
+                div { class: "provision-page",
+                    div { class: "provision-card",
+                        div { class: "provision-body",
+                            div { class: "loading", "Loading..." }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
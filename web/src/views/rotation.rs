@@ -0,0 +1,163 @@
+use super::components::format_timestamp;
+use crate::use_error;
+use dioxus::prelude::*;
+use types::rotation::{RotationCampaign, RotationMember};
+use uuid::Uuid;
+
+#[component]
+pub fn Rotations() -> Element {
+    let mut error_state = use_error();
+    let mut campaigns = use_signal(Vec::<RotationCampaign>::new);
+    let mut loading = use_signal(|| true);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_rotation_campaigns().await {
+                Ok(c) => campaigns.set(c),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loading.set(false);
+        });
+    });
+
+    let (open, closed): (Vec<RotationCampaign>, Vec<RotationCampaign>) = campaigns
+        .read()
+        .iter()
+        .cloned()
+        .partition(|c| !c.is_closed());
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Credential Rotation" }
+                    p { class: "page-subtitle", "Campaigns asking people to change their credentials by a deadline. Start one from a filtered list of users." }
+                }
+            }
+            if loading() {
+                div { class: "loading", "Loading campaigns..." }
+            } else {
+                div { class: "grid",
+                    if open.is_empty() {
+                        div { class: "card",
+                            div { class: "empty-state", "No campaigns are running." }
+                        }
+                    }
+                    for campaign in open {
+                        CampaignCard {
+                            key: "{campaign.id}",
+                            campaign: campaign.clone(),
+                            on_cancel: move |id| campaigns.write().retain(|c| c.id != id),
+                        }
+                    }
+                    if !closed.is_empty() {
+                        div { class: "card",
+                            div { class: "card-header",
+                                h2 { class: "card-title", "Finished" }
+                            }
+                            div { class: "table-container",
+                                table {
+                                    thead {
+                                        tr {
+                                            th { "Campaign" }
+                                            th { "Started by" }
+                                            th { "Deadline" }
+                                            th { "Rotated" }
+                                            th { "Locked" }
+                                        }
+                                    }
+                                    tbody {
+                                        for c in closed {
+                                            tr { key: "{c.id}",
+                                                td { "{c.name}" }
+                                                td { "{c.started_by}" }
+                                                td { {format_timestamp(c.deadline)} }
+                                                td { "{c.rotated()}/{c.members.len()}" }
+                                                td { "{c.members.iter().filter(|m| m.locked_at.is_some()).count()}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn CampaignCard(campaign: RotationCampaign, on_cancel: EventHandler<Uuid>) -> Element {
+    let mut error_state = use_error();
+    let mut busy = use_signal(|| false);
+
+    let id = campaign.id;
+    let rotated = campaign.rotated();
+    let total = campaign.members.len();
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "{campaign.name}" }
+                span { class: "badge", "{rotated}/{total} rotated" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Started by {campaign.started_by}, {format_timestamp(campaign.started_at)}. Due {format_timestamp(campaign.deadline)}."
+                }
+                if campaign.lock_at_deadline {
+                    p { class: "text-muted text-sm", "Accounts that haven't rotated by then will be locked." }
+                }
+                div { class: "table-container",
+                    table {
+                        thead {
+                            tr {
+                                th { "User" }
+                                th { "Status" }
+                                th { "Last emailed" }
+                            }
+                        }
+                        tbody {
+                            for member in campaign.members.iter() {
+                                tr { key: "{member.user_id}",
+                                    td { "{member.user_name}" }
+                                    td { {status(member)} }
+                                    td { {member.reminded_at.map(format_timestamp).unwrap_or_else(|| "Never".to_string())} }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "divider" }
+
+                button {
+                    class: "btn btn-link",
+                    disabled: busy(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            busy.set(true);
+                            match api::cancel_rotation_campaign(id).await {
+                                Ok(()) => on_cancel.call(id),
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            busy.set(false);
+                        });
+                    },
+                    "Cancel campaign"
+                }
+            }
+        }
+    }
+}
+
+fn status(member: &RotationMember) -> String {
+    if let Some(at) = member.rotated_at {
+        format!("Rotated {}", format_timestamp(at))
+    } else if let Some(at) = member.locked_at {
+        format!("Locked {}", format_timestamp(at))
+    } else {
+        "Pending".to_string()
+    }
+}
@@ -0,0 +1,106 @@
+use super::components::format_timestamp;
+use crate::use_error;
+use dioxus::prelude::*;
+use types::session::SessionInfo;
+
+#[component]
+pub fn Sessions() -> Element {
+    let mut error_state = use_error();
+    let mut sessions = use_signal(Vec::<SessionInfo>::new);
+    let mut loading = use_signal(|| true);
+
+    let refresh = move || {
+        spawn(async move {
+            loading.set(true);
+            match api::list_sessions().await {
+                Ok(s) => sessions.set(s),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loading.set(false);
+        });
+    };
+
+    use_effect(move || {
+        refresh();
+    });
+
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Sessions" }
+                    p { class: "page-subtitle",
+                        "Your active AuthIt! sign-ins. Signing one out locks it straight away."
+                    }
+                }
+            }
+            if *loading.read() {
+                div { class: "loading", "Loading sessions..." }
+            } else {
+                div { class: "card",
+                    div { class: "table-container",
+                        table {
+                            thead {
+                                tr {
+                                    th { "Signed in" }
+                                    th { "Device" }
+                                    th { "Location" }
+                                    th { "Expires" }
+                                    th { "Lifetime" }
+                                    th {}
+                                }
+                            }
+                            tbody {
+                                for session in sessions.read().iter() {
+                                    tr { key: "{session.id}",
+                                        td {
+                                            {format_timestamp(session.created_at)}
+                                            if session.current {
+                                                " "
+                                                span { class: "badge badge-primary", "This session" }
+                                            }
+                                        }
+                                        td { {session.device.as_deref().unwrap_or("Unknown")} }
+                                        td {
+                                            {session.location.as_deref().unwrap_or("Unknown")}
+                                            if let Some(ip) = &session.ip_address {
+                                                div { class: "text-muted", "{ip}" }
+                                            }
+                                        }
+                                        td { {format_timestamp(session.expires_at)} }
+                                        td {
+                                            if session.persistent {
+                                                "Kept signed in"
+                                            } else {
+                                                "Browser session"
+                                            }
+                                        }
+                                        td {
+                                            if !session.current {
+                                                button {
+                                                    class: "btn btn-link text-sm",
+                                                    onclick: {
+                                                        let id = session.id;
+                                                        move |_| {
+                                                            spawn(async move {
+                                                                match api::revoke_session(id).await {
+                                                                    Ok(()) => refresh(),
+                                                                    Err(e) => error_state.set_server_error(&e),
+                                                                }
+                                                            });
+                                                        }
+                                                    },
+                                                    "Sign out"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
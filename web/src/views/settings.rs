@@ -0,0 +1,1550 @@
+use super::components::{download, format_timestamp, parse_datetime_input, to_datetime_input};
+use crate::use_error;
+use dioxus::prelude::*;
+use jiff::{SignedDuration, Timestamp};
+use std::collections::BTreeSet;
+use types::{
+    announcement::{Announcement, Severity},
+    audit::{AuditFormat, AuditManifest},
+    maintenance::Maintenance,
+    notification::{
+        MatrixNotifier, NotificationChannel, NotificationEvent, NotificationRule,
+        NotificationSettings, SlackNotifier,
+    },
+    offboarding::ChecklistTemplate,
+    retention::{PurgeResult, RetentionPolicy},
+    settings_export::{SettingsExport, SettingsImport},
+    tag::Tag,
+    visibility::{Audience, FieldVisibility},
+};
+use uuid::Uuid;
+
+#[component]
+pub fn Settings() -> Element {
+    rsx! {
+        div {
+            div { class: "page-header",
+                div { class: "page-header-content",
+                    h1 { class: "page-title", "Settings" }
+                    p { class: "page-subtitle", "Site-wide settings for AuthIt!." }
+                }
+            }
+            div { class: "grid",
+                AnnouncementCard {}
+                MaintenanceCard {}
+                NotificationsCard {}
+                OffboardingTemplateCard {}
+                TagsCard {}
+                RetentionCard {}
+                FieldVisibilityCard {}
+                TicketCard {}
+                RequiredEmailCard {}
+                AuditExportCard {}
+                SettingsTransferCard {}
+            }
+        }
+    }
+}
+
+#[component]
+fn AnnouncementCard() -> Element {
+    let mut error_state = use_error();
+    let mut loaded = use_signal(|| false);
+    let mut severity = use_signal(Severity::default);
+    let mut content = use_signal(String::new);
+    let mut starts_at = use_signal(String::new);
+    let mut ends_at = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_announcement().await {
+                Ok(Some(a)) => {
+                    severity.set(a.severity);
+                    content.set(a.content);
+                    starts_at.set(a.starts_at.map(to_datetime_input).unwrap_or_default());
+                    ends_at.set(a.ends_at.map(to_datetime_input).unwrap_or_default());
+                }
+                Ok(None) => {}
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loaded.set(true);
+        });
+    });
+
+    let save = move |announcement: Option<Announcement>| {
+        spawn(async move {
+            saving.set(true);
+            match api::set_announcement(announcement).await {
+                Ok(()) => saved.set(true),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            saving.set(false);
+        });
+    };
+
+    if !loaded() {
+        return rsx! {
+            div { class: "loading", "Loading settings..." }
+        };
+    }
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Announcement Banner" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Shown at the top of every page, including the public provision page."
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "announcement_severity", "Severity" }
+                    select {
+                        id: "announcement_severity",
+                        class: "form-input",
+                        value: "{severity().as_str()}",
+                        onchange: move |e| {
+                            if let Ok(s) = e.value().parse() {
+                                severity.set(s);
+                                saved.set(false);
+                            }
+                        },
+                        for s in Severity::ALL {
+                            option { value: "{s.as_str()}", "{s.as_str()}" }
+                        }
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "announcement_content", "Message (markdown)" }
+                    textarea {
+                        id: "announcement_content",
+                        class: "form-input",
+                        rows: "4",
+                        value: "{content}",
+                        oninput: move |e| {
+                            content.set(e.value());
+                            saved.set(false);
+                        },
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "announcement_starts_at", "Starts (optional)" }
+                    input {
+                        id: "announcement_starts_at",
+                        class: "form-input",
+                        r#type: "datetime-local",
+                        value: "{starts_at}",
+                        oninput: move |e| {
+                            starts_at.set(e.value());
+                            saved.set(false);
+                        },
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "announcement_ends_at", "Ends (optional)" }
+                    input {
+                        id: "announcement_ends_at",
+                        class: "form-input",
+                        r#type: "datetime-local",
+                        value: "{ends_at}",
+                        oninput: move |e| {
+                            ends_at.set(e.value());
+                            saved.set(false);
+                        },
+                    }
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: content.read().trim().is_empty() || saving(),
+                        onclick: move |_| {
+                            save(Some(Announcement {
+                                severity: severity(),
+                                content: content(),
+                                starts_at: parse_datetime_input(&starts_at.read()),
+                                ends_at: parse_datetime_input(&ends_at.read()),
+                            }));
+                        },
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            content.set(String::new());
+                            starts_at.set(String::new());
+                            ends_at.set(String::new());
+                            save(None);
+                        },
+                        "Remove"
+                    }
+                }
+                if saved() {
+                    p { class: "text-muted text-sm", "Saved." }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn NotificationsCard() -> Element {
+    let mut error_state = use_error();
+    let mut settings = use_signal(|| None::<NotificationSettings>);
+    // Secrets aren't sent to the client, so we track whether they're set.
+    let mut slack_configured = use_signal(|| false);
+    let mut matrix_configured = use_signal(|| false);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+    let mut testing = use_signal(|| false);
+    let mut tested = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_notification_settings().await {
+                Ok(s) => {
+                    slack_configured.set(s.slack.is_some());
+                    matrix_configured.set(s.matrix.is_some());
+                    settings.set(Some(s));
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let mut edit = move |f: &dyn Fn(&mut NotificationSettings)| {
+        if let Some(s) = settings.write().as_mut() {
+            f(s);
+        }
+        saved.set(false);
+        tested.set(false);
+    };
+
+    let Some(current) = settings() else {
+        return rsx! {
+            div { class: "loading", "Loading notification settings..." }
+        };
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Notifications" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Post messages to Slack or Matrix when these events happen."
+                }
+
+                h3 { class: "section-header", "Slack" }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: current.slack.is_some(),
+                        onchange: move |e| {
+                            let enabled = e.checked();
+                            edit(&|s| s.slack = enabled.then(SlackNotifier::default));
+                        },
+                    }
+                    span { "Send to Slack" }
+                }
+                if let Some(slack) = &current.slack {
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "slack_webhook_url", "Incoming webhook URL" }
+                        input {
+                            id: "slack_webhook_url",
+                            class: "form-input",
+                            r#type: "password",
+                            placeholder: if slack_configured() { "Unchanged" } else { "https://hooks.slack.com/services/..." },
+                            value: "{slack.webhook_url}",
+                            oninput: move |e| {
+                                let value = e.value();
+                                edit(&|s| {
+                                    if let Some(slack) = &mut s.slack {
+                                        slack.webhook_url = value.clone();
+                                    }
+                                });
+                            },
+                        }
+                    }
+                    EventChecklist {
+                        selected: slack.events.clone(),
+                        on_toggle: move |event| {
+                            edit(&|s| {
+                                if let Some(slack) = &mut s.slack {
+                                    toggle(&mut slack.events, event);
+                                }
+                            });
+                        },
+                    }
+                }
+
+                div { class: "divider" }
+
+                h3 { class: "section-header", "Matrix" }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: current.matrix.is_some(),
+                        onchange: move |e| {
+                            let enabled = e.checked();
+                            edit(&|s| s.matrix = enabled.then(MatrixNotifier::default));
+                        },
+                    }
+                    span { "Send to Matrix" }
+                }
+                if let Some(matrix) = &current.matrix {
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "matrix_homeserver", "Homeserver" }
+                        input {
+                            id: "matrix_homeserver",
+                            class: "form-input",
+                            placeholder: "https://matrix.example.com",
+                            value: "{matrix.homeserver}",
+                            oninput: move |e| {
+                                let value = e.value();
+                                edit(&|s| {
+                                    if let Some(matrix) = &mut s.matrix {
+                                        matrix.homeserver = value.clone();
+                                    }
+                                });
+                            },
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "matrix_room_id", "Room ID" }
+                        input {
+                            id: "matrix_room_id",
+                            class: "form-input",
+                            placeholder: "!abc123:example.com",
+                            value: "{matrix.room_id}",
+                            oninput: move |e| {
+                                let value = e.value();
+                                edit(&|s| {
+                                    if let Some(matrix) = &mut s.matrix {
+                                        matrix.room_id = value.clone();
+                                    }
+                                });
+                            },
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "matrix_access_token", "Access token" }
+                        input {
+                            id: "matrix_access_token",
+                            class: "form-input",
+                            r#type: "password",
+                            placeholder: if matrix_configured() { "Unchanged" } else { "" },
+                            value: "{matrix.access_token}",
+                            oninput: move |e| {
+                                let value = e.value();
+                                edit(&|s| {
+                                    if let Some(matrix) = &mut s.matrix {
+                                        matrix.access_token = value.clone();
+                                    }
+                                });
+                            },
+                        }
+                    }
+                    EventChecklist {
+                        selected: matrix.events.clone(),
+                        on_toggle: move |event| {
+                            edit(&|s| {
+                                if let Some(matrix) = &mut s.matrix {
+                                    toggle(&mut matrix.events, event);
+                                }
+                            });
+                        },
+                    }
+                }
+
+                div { class: "divider" }
+
+                h3 { class: "section-header", "Rules" }
+                p { class: "text-muted text-sm",
+                    "Send events to an email address or notifier only when they match, like membership changes to groups matching prod-*. A blank pattern matches anything, and * matches any run of characters."
+                }
+                if !current.rules.is_empty() {
+                    ul {
+                        for (i, rule) in current.rules.iter().enumerate() {
+                            li { key: "{i}",
+                                span { "{rule.describe()}" }
+                                button {
+                                    class: "btn btn-link",
+                                    onclick: move |_| {
+                                        edit(&|s| {
+                                            s.rules.remove(i);
+                                        });
+                                    },
+                                    "Remove"
+                                }
+                            }
+                        }
+                    }
+                }
+                NotificationRuleForm {
+                    on_add: move |rule: NotificationRule| {
+                        edit(&|s| s.rules.push(rule.clone()));
+                    },
+                }
+
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                let Some(s) = settings() else { return };
+                                saving.set(true);
+                                match api::set_notification_settings(s.clone()).await {
+                                    Ok(()) => {
+                                        slack_configured.set(s.slack.is_some());
+                                        matrix_configured.set(s.matrix.is_some());
+                                        saved.set(true);
+                                    }
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: !saved() || testing(),
+                        title: "Save first to test the saved settings.",
+                        onclick: move |_| {
+                            spawn(async move {
+                                testing.set(true);
+                                match api::send_test_notification().await {
+                                    Ok(()) => tested.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                testing.set(false);
+                            });
+                        },
+                        if testing() { "Sending..." } else { "Send test" }
+                    }
+                }
+                if tested() {
+                    p { class: "text-muted text-sm", "Test sent." }
+                } else if saved() {
+                    p { class: "text-muted text-sm", "Saved." }
+                }
+            }
+        }
+    }
+}
+
+/// A new notification rule, added to the settings when it's filled in. It's
+/// only kept once the settings are saved.
+#[component]
+fn NotificationRuleForm(on_add: EventHandler<NotificationRule>) -> Element {
+    let mut event = use_signal(|| NotificationEvent::GroupMembershipChanged);
+    let mut group = use_signal(String::new);
+    let mut user = use_signal(String::new);
+    let mut channel = use_signal(|| "email".to_string());
+    let mut to = use_signal(String::new);
+
+    let rule = move || NotificationRule {
+        event: event(),
+        group: group().trim().to_string(),
+        user: user().trim().to_string(),
+        channel: match channel().as_str() {
+            "slack" => NotificationChannel::Slack,
+            "matrix" => NotificationChannel::Matrix,
+            _ => NotificationChannel::Email {
+                to: to().trim().to_string(),
+            },
+        },
+    };
+
+    rsx! {
+        div { class: "form-group",
+            label { class: "form-label", r#for: "rule_event", "Event" }
+            select {
+                id: "rule_event",
+                class: "form-input",
+                value: "{event().as_str()}",
+                onchange: move |e| {
+                    if let Ok(ev) = e.value().parse() {
+                        event.set(ev);
+                    }
+                },
+                for ev in NotificationEvent::ALL {
+                    option { value: "{ev.as_str()}", "{ev.label()}" }
+                }
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "rule_group", "Groups matching" }
+            input {
+                id: "rule_group",
+                class: "form-input",
+                placeholder: "prod-*",
+                value: "{group}",
+                oninput: move |e| group.set(e.value()),
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "rule_user", "Users matching" }
+            input {
+                id: "rule_user",
+                class: "form-input",
+                value: "{user}",
+                oninput: move |e| user.set(e.value()),
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "rule_channel", "Send to" }
+            select {
+                id: "rule_channel",
+                class: "form-input",
+                value: "{channel}",
+                onchange: move |e| channel.set(e.value()),
+                option { value: "email", "Email" }
+                option { value: "slack", "Slack" }
+                option { value: "matrix", "Matrix" }
+            }
+        }
+        if channel() == "email" {
+            div { class: "form-group",
+                label { class: "form-label", r#for: "rule_to", "Email address" }
+                input {
+                    id: "rule_to",
+                    class: "form-input",
+                    r#type: "email",
+                    placeholder: "security@example.com",
+                    value: "{to}",
+                    oninput: move |e| to.set(e.value()),
+                }
+            }
+        }
+        button {
+            class: "btn btn-secondary",
+            disabled: channel() == "email" && to.read().trim().is_empty(),
+            onclick: move |_| {
+                on_add.call(rule());
+                group.set(String::new());
+                user.set(String::new());
+                to.set(String::new());
+            },
+            "Add rule"
+        }
+    }
+}
+
+#[component]
+fn OffboardingTemplateCard() -> Element {
+    let mut error_state = use_error();
+    let mut loaded = use_signal(|| false);
+    let mut steps = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_offboarding_template().await {
+                Ok(t) => steps.set(t.steps.join("\n")),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loaded.set(true);
+        });
+    });
+
+    if !loaded() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Offboarding Checklist" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "The steps new offboarding checklists start with, one per line. Existing checklists aren't changed."
+                }
+                div { class: "form-group",
+                    textarea {
+                        class: "form-input",
+                        rows: "6",
+                        value: "{steps}",
+                        oninput: move |e| {
+                            steps.set(e.value());
+                            saved.set(false);
+                        },
+                    }
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            let template = ChecklistTemplate {
+                                steps: steps.read().lines().map(String::from).collect(),
+                            };
+                            spawn(async move {
+                                saving.set(true);
+                                match api::set_offboarding_template(template).await {
+                                    Ok(()) => saved.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                }
+                if saved() {
+                    p { class: "text-muted text-sm", "Saved." }
+                }
+            }
+        }
+    }
+}
+
+/// Local labels for users. Renaming or deleting one changes it for everyone
+/// who has it.
+#[component]
+fn TagsCard() -> Element {
+    let mut error_state = use_error();
+    let mut tags = use_signal(Vec::<Tag>::new);
+    let mut new_tag = use_signal(String::new);
+    let mut editing = use_signal(|| None::<(Uuid, String)>);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::list_tags().await {
+                Ok(t) => tags.set(t),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "User Tags" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Labels for users, like contractor or offboarding-q3. They're kept in AuthIt!, not Kanidm."
+                }
+                if tags.read().is_empty() {
+                    p { class: "text-muted", "No tags yet." }
+                }
+                for tag in tags.read().iter().cloned() {
+                    div { class: "email-row", key: "{tag.id}",
+                        if let Some((id, name)) = editing().filter(|(id, _)| *id == tag.id) {
+                            input {
+                                class: "form-input",
+                                value: "{name}",
+                                oninput: move |e| editing.set(Some((id, e.value()))),
+                            }
+                            button {
+                                class: "btn btn-link",
+                                onclick: move |_| {
+                                    let name = name.clone();
+                                    spawn(async move {
+                                        match api::rename_tag(id, name).await {
+                                            Ok(()) => {
+                                                editing.set(None);
+                                                refresh();
+                                            }
+                                            Err(e) => error_state.set_server_error(&e),
+                                        }
+                                    });
+                                },
+                                "Save"
+                            }
+                            button {
+                                class: "btn btn-link",
+                                onclick: move |_| editing.set(None),
+                                "Cancel"
+                            }
+                        } else {
+                            span { class: "badge badge-primary", "{tag.name}" }
+                            span { class: "text-muted text-sm", "{tag.user_count} user(s)" }
+                            button {
+                                class: "btn btn-link",
+                                onclick: {
+                                    let tag = tag.clone();
+                                    move |_| editing.set(Some((tag.id, tag.name.clone())))
+                                },
+                                "Rename"
+                            }
+                            button {
+                                class: "btn btn-link",
+                                title: "Delete",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        match api::delete_tag(tag.id).await {
+                                            Ok(()) => refresh(),
+                                            Err(e) => error_state.set_server_error(&e),
+                                        }
+                                    });
+                                },
+                                "×"
+                            }
+                        }
+                    }
+                }
+                div { class: "email-row",
+                    input {
+                        class: "form-input",
+                        placeholder: "New tag",
+                        value: "{new_tag}",
+                        oninput: move |e| new_tag.set(e.value()),
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: new_tag.read().trim().is_empty(),
+                        onclick: move |_| {
+                            let name = new_tag();
+                            spawn(async move {
+                                match api::create_tag(name).await {
+                                    Ok(_) => {
+                                        new_tag.set(String::new());
+                                        refresh();
+                                    }
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                            });
+                        },
+                        "Add"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn MaintenanceCard() -> Element {
+    let mut error_state = use_error();
+    let mut maintenance = use_signal(|| None::<Maintenance>);
+    let mut message = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+
+    let reload = move || {
+        spawn(async move {
+            match api::get_maintenance().await {
+                Ok(m) => maintenance.set(m),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+    use_effect(move || {
+        reload();
+    });
+
+    let set = move |new_message: Option<String>| {
+        spawn(async move {
+            saving.set(true);
+            match api::set_maintenance(new_message).await {
+                Ok(()) => reload(),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            saving.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Maintenance Mode" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "While on, everyone but admins sees a maintenance page and can't make changes. Useful during Kanidm upgrades."
+                }
+                if let Some(m) = maintenance() {
+                    p {
+                        "On since {format_timestamp(m.started_at)}, started by {m.started_by}."
+                    }
+                    if !m.message.is_empty() {
+                        p { class: "text-muted text-sm", "{m.message}" }
+                    }
+                    div { class: "page-header-actions",
+                        button {
+                            class: "btn btn-primary",
+                            disabled: saving(),
+                            onclick: move |_| set(None),
+                            if saving() { "Ending..." } else { "End Maintenance" }
+                        }
+                    }
+                } else {
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "maintenance_message", "Message (optional)" }
+                        textarea {
+                            id: "maintenance_message",
+                            class: "form-input",
+                            rows: "2",
+                            value: "{message}",
+                            oninput: move |e| message.set(e.value()),
+                        }
+                    }
+                    div { class: "page-header-actions",
+                        button {
+                            class: "btn btn-danger",
+                            disabled: saving(),
+                            onclick: move |_| set(Some(message())),
+                            if saving() { "Starting..." } else { "Start Maintenance" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RetentionCard() -> Element {
+    let mut error_state = use_error();
+    let mut policy = use_signal(|| None::<RetentionPolicy>);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+    let mut purging = use_signal(|| false);
+    let mut purged = use_signal(|| None::<PurgeResult>);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_retention_policy().await {
+                Ok(p) => policy.set(Some(p)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(current) = policy() else {
+        return rsx! {};
+    };
+
+    let mut update = move |f: fn(&mut RetentionPolicy) -> &mut u32, value: String| {
+        if let (Some(p), Ok(days)) = (policy.write().as_mut(), value.parse()) {
+            *f(p) = days;
+            saved.set(false);
+        }
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Data Retention" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "How many days to keep data AuthIt! no longer needs. Older data is purged hourly."
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_sessions", "Expired sessions" }
+                    input {
+                        id: "retention_sessions",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.expired_sessions_days}",
+                        oninput: move |e| update(|p| &mut p.expired_sessions_days, e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_links", "Expired provision links" }
+                    input {
+                        id: "retention_links",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.provision_links_days}",
+                        oninput: move |e| update(|p| &mut p.provision_links_days, e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_jobs", "Finished bulk jobs, webhook calls and sent emails" }
+                    input {
+                        id: "retention_jobs",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.jobs_days}",
+                        oninput: move |e| update(|p| &mut p.jobs_days, e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_requests", "Decided account requests" }
+                    input {
+                        id: "retention_requests",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.account_requests_days}",
+                        oninput: move |e| update(|p| &mut p.account_requests_days, e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_activity", "Dashboard activity" }
+                    input {
+                        id: "retention_activity",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.activity_days}",
+                        oninput: move |e| update(|p| &mut p.activity_days, e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_shift_notes", "Shift log notes" }
+                    input {
+                        id: "retention_shift_notes",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.shift_notes_days}",
+                        oninput: move |e| update(|p| &mut p.shift_notes_days, e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "retention_login_events", "Sign-in history" }
+                    input {
+                        id: "retention_login_events",
+                        class: "form-input",
+                        r#type: "number",
+                        min: "0",
+                        value: "{current.login_events_days}",
+                        oninput: move |e| update(|p| &mut p.login_events_days, e.value()),
+                    }
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                saving.set(true);
+                                match api::set_retention_policy(current).await {
+                                    Ok(()) => saved.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: purging(),
+                        onclick: move |_| {
+                            spawn(async move {
+                                purging.set(true);
+                                match api::purge_old_data().await {
+                                    Ok(result) => purged.set(Some(result)),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                purging.set(false);
+                            });
+                        },
+                        if purging() { "Purging..." } else { "Purge Now" }
+                    }
+                }
+                if let Some(result) = purged() {
+                    p { class: "text-muted text-sm",
+                        "Purged {result.sessions} sessions, {result.provision_links} provision links, {result.jobs} jobs, {result.account_requests} account requests, {result.activity} activity entries, {result.shift_notes} shift log notes and {result.login_events} sign-ins."
+                    }
+                } else if saved() {
+                    p { class: "text-muted text-sm", "Saved." }
+                }
+            }
+        }
+    }
+}
+
+/// Whether admins must say which ticket they're working on before dangerous
+/// changes.
+#[component]
+fn TicketCard() -> Element {
+    let mut error_state = use_error();
+    let mut required = use_signal(|| None::<bool>);
+    let mut saving = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_ticket_status().await {
+                Ok(s) => required.set(Some(s.required)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(current) = required() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Ticket References" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Admins can enter the ticket they're working on in the sidebar. It's recorded with their changes in the activity stream and notifications."
+                }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: current,
+                        disabled: saving(),
+                        onchange: move |e| {
+                            let value = e.checked();
+                            spawn(async move {
+                                saving.set(true);
+                                match api::set_require_ticket(value).await {
+                                    Ok(()) => required.set(Some(value)),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                    }
+                    " Require one for dangerous changes, like deleting users or changing who's an admin"
+                }
+            }
+        }
+    }
+}
+
+/// Whether every new account must have an email address.
+#[component]
+fn RequiredEmailCard() -> Element {
+    let mut error_state = use_error();
+    let mut required = use_signal(|| None::<bool>);
+    let mut saving = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_require_email().await {
+                Ok(r) => required.set(Some(r)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(current) = required() else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Email Addresses" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Applies to accounts admins create and ones made with provision links, whatever the link says. Existing accounts aren't changed."
+                }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: current,
+                        disabled: saving(),
+                        onchange: move |e| {
+                            let value = e.checked();
+                            spawn(async move {
+                                saving.set(true);
+                                match api::set_require_email(value).await {
+                                    Ok(()) => required.set(Some(value)),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                    }
+                    " Require an email address for every new account"
+                }
+            }
+        }
+    }
+}
+
+/// Download the activity stream for auditors, and check downloads they hand
+/// back.
+#[component]
+fn AuditExportCard() -> Element {
+    let mut error_state = use_error();
+    let mut from =
+        use_signal(|| to_datetime_input(Timestamp::now() - SignedDuration::from_hours(30 * 24)));
+    let mut to = use_signal(|| to_datetime_input(Timestamp::now()));
+    let mut file_format = use_signal(AuditFormat::default);
+    let mut sign = use_signal(|| true);
+    let mut exporting = use_signal(|| false);
+    let mut export_file = use_signal(|| None::<String>);
+    let mut manifest_file = use_signal(|| None::<String>);
+    let mut verified = use_signal(|| None::<Result<(), String>>);
+
+    let export = move |_| {
+        let (Some(start), Some(end)) = (parse_datetime_input(&from()), parse_datetime_input(&to()))
+        else {
+            error_state.set("Choose when the export should start and end.".to_string());
+            return;
+        };
+        spawn(async move {
+            exporting.set(true);
+            match api::export_audit_log(start, end, file_format(), sign()).await {
+                Ok(export) => {
+                    download(
+                        &export.filename,
+                        file_format().content_type(),
+                        export.content,
+                    );
+                    if let Some(manifest) = export.manifest {
+                        match serde_json::to_string_pretty(&manifest) {
+                            Ok(json) => download(
+                                &format!("{}.manifest.json", export.filename),
+                                "application/json",
+                                json,
+                            ),
+                            Err(e) => error_state.set(e.to_string()),
+                        }
+                    }
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            exporting.set(false);
+        });
+    };
+
+    let verify = move |_| {
+        let (Some(content), Some(manifest)) = (export_file(), manifest_file()) else {
+            return;
+        };
+        let manifest: AuditManifest = match serde_json::from_str(&manifest) {
+            Ok(m) => m,
+            Err(_) => {
+                verified.set(Some(
+                    Err("That isn't a manifest AuthIt! wrote.".to_string()),
+                ));
+                return;
+            }
+        };
+        spawn(async move {
+            match api::verify_audit_export(content, manifest).await {
+                Ok(()) => verified.set(Some(Ok(()))),
+                Err(e) => verified.set(Some(Err(e.to_string()))),
+            }
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Audit Log Export" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Download the activity stream for a time range. A signed export comes with a manifest that AuthIt! can later use to prove neither file has been changed. Exports are themselves recorded."
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "audit_from", "From" }
+                    input {
+                        id: "audit_from",
+                        class: "form-input",
+                        r#type: "datetime-local",
+                        value: "{from}",
+                        oninput: move |e| from.set(e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "audit_to", "To" }
+                    input {
+                        id: "audit_to",
+                        class: "form-input",
+                        r#type: "datetime-local",
+                        value: "{to}",
+                        oninput: move |e| to.set(e.value()),
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "audit_format", "Format" }
+                    select {
+                        id: "audit_format",
+                        class: "form-input",
+                        value: "{file_format().extension()}",
+                        onchange: move |e| {
+                            file_format.set(if e.value() == "jsonl" { AuditFormat::Jsonl } else { AuditFormat::Csv });
+                        },
+                        option { value: "csv", "CSV" }
+                        option { value: "jsonl", "JSON Lines" }
+                    }
+                }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: sign(),
+                        onchange: move |e| sign.set(e.checked()),
+                    }
+                    " Sign it"
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: exporting(),
+                        onclick: export,
+                        if exporting() { "Exporting..." } else { "Export" }
+                    }
+                }
+
+                div { class: "divider" }
+
+                p { class: "text-muted text-sm", "Check a signed export against its manifest." }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "audit_verify_export", "Export" }
+                    input {
+                        id: "audit_verify_export",
+                        r#type: "file",
+                        accept: ".csv,.jsonl",
+                        onchange: move |e| {
+                            let Some(file) = e.files().into_iter().next() else {
+                                return;
+                            };
+                            spawn(async move {
+                                verified.set(None);
+                                match file.read_bytes().await {
+                                    Ok(data) => export_file.set(Some(String::from_utf8_lossy(&data).into_owned())),
+                                    Err(e) => error_state.set(format!("Failed to read file: {e}")),
+                                }
+                            });
+                        },
+                    }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "audit_verify_manifest", "Manifest" }
+                    input {
+                        id: "audit_verify_manifest",
+                        r#type: "file",
+                        accept: ".json",
+                        onchange: move |e| {
+                            let Some(file) = e.files().into_iter().next() else {
+                                return;
+                            };
+                            spawn(async move {
+                                verified.set(None);
+                                match file.read_bytes().await {
+                                    Ok(data) => manifest_file.set(Some(String::from_utf8_lossy(&data).into_owned())),
+                                    Err(e) => error_state.set(format!("Failed to read file: {e}")),
+                                }
+                            });
+                        },
+                    }
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: export_file().is_none() || manifest_file().is_none(),
+                        onclick: verify,
+                        "Verify"
+                    }
+                }
+                match verified() {
+                    Some(Ok(())) => rsx! {
+                        p { class: "text-sm", "The export matches its manifest, and the manifest was signed by AuthIt!." }
+                    },
+                    Some(Err(e)) => rsx! {
+                        p { class: "text-danger text-sm", "{e}" }
+                    },
+                    None => rsx! {},
+                }
+            }
+        }
+    }
+}
+
+/// Download these settings as JSON, and load them from a download, like one
+/// from another instance.
+#[component]
+fn SettingsTransferCard() -> Element {
+    let mut error_state = use_error();
+    let mut include_secrets = use_signal(|| false);
+    let mut exporting = use_signal(|| false);
+    let mut document = use_signal(|| None::<SettingsExport>);
+    let mut preview = use_signal(|| None::<SettingsImport>);
+    let mut importing = use_signal(|| false);
+
+    let export = move |_| {
+        spawn(async move {
+            exporting.set(true);
+            match api::export_settings(include_secrets()).await {
+                Ok(export) => match serde_json::to_string_pretty(&export) {
+                    Ok(json) => download("authit-settings.json", "application/json", json),
+                    Err(e) => error_state.set(e.to_string()),
+                },
+                Err(e) => error_state.set_server_error(&e),
+            }
+            exporting.set(false);
+        });
+    };
+
+    let import = move |apply: bool| {
+        let Some(doc) = document() else {
+            return;
+        };
+        spawn(async move {
+            importing.set(true);
+            match api::import_settings(doc, apply).await {
+                Ok(result) => preview.set(Some(result)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            importing.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Export and Import" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Download these settings as JSON to review or keep, or to set up another AuthIt! the same way. Importing shows what would change before changing it, and only adds tags."
+                }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: include_secrets(),
+                        onchange: move |e| include_secrets.set(e.checked()),
+                    }
+                    " Include notifier secrets"
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: exporting(),
+                        onclick: export,
+                        if exporting() { "Exporting..." } else { "Export" }
+                    }
+                }
+
+                div { class: "divider" }
+
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "settings_import", "Settings file" }
+                    input {
+                        id: "settings_import",
+                        r#type: "file",
+                        accept: ".json",
+                        onchange: move |e| {
+                            let Some(file) = e.files().into_iter().next() else {
+                                return;
+                            };
+                            spawn(async move {
+                                document.set(None);
+                                preview.set(None);
+                                let data = match file.read_bytes().await {
+                                    Ok(data) => data,
+                                    Err(e) => {
+                                        error_state.set(format!("Failed to read file: {e}"));
+                                        return;
+                                    }
+                                };
+                                match serde_json::from_slice::<SettingsExport>(&data) {
+                                    Ok(doc) => {
+                                        document.set(Some(doc));
+                                        import(false);
+                                    }
+                                    Err(_) => error_state.set("That isn't a settings file AuthIt! wrote.".to_string()),
+                                }
+                            });
+                        },
+                    }
+                }
+                match preview() {
+                    Some(result) if result.changes.is_empty() => rsx! {
+                        p { class: "text-sm", "These settings are already in use." }
+                    },
+                    Some(result) if result.applied => rsx! {
+                        p { class: "text-sm", "Imported. Reload the page to see the new settings." }
+                    },
+                    Some(result) => rsx! {
+                        p { class: "text-sm", "Importing will change:" }
+                        ul {
+                            for change in result.changes.iter() {
+                                li { key: "{change}", "{change}" }
+                            }
+                        }
+                        div { class: "page-header-actions",
+                            button {
+                                class: "btn btn-primary",
+                                disabled: importing(),
+                                onclick: move |_| import(true),
+                                if importing() { "Importing..." } else { "Import" }
+                            }
+                        }
+                    },
+                    None => rsx! {},
+                }
+            }
+        }
+    }
+}
+
+/// Who may see email addresses, legal names and groups in the directory and on
+/// user pages. The server leaves out what each viewer may not see.
+#[component]
+fn FieldVisibilityCard() -> Element {
+    let mut error_state = use_error();
+    let mut visibility = use_signal(|| None::<FieldVisibility>);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_field_visibility().await {
+                Ok(v) => visibility.set(Some(v)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    let Some(current) = visibility() else {
+        return rsx! {};
+    };
+
+    let mut update = move |f: fn(&mut FieldVisibility) -> &mut Audience, audience: Audience| {
+        if let Some(v) = visibility.write().as_mut() {
+            *f(v) = audience;
+            saved.set(false);
+        }
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "card-header",
+                h2 { class: "card-title", "Field Visibility" }
+            }
+            div { class: "card-body",
+                p { class: "text-muted text-sm",
+                    "Who can see each field in the directory and on user pages. Admins always see email addresses and groups on user pages."
+                }
+                AudiencePicker {
+                    id: "visibility_email",
+                    label: "Email addresses (directory)",
+                    audience: current.email_addresses.clone(),
+                    onchange: move |a| update(|v| &mut v.email_addresses, a),
+                }
+                AudiencePicker {
+                    id: "visibility_legal_name",
+                    label: "Legal names",
+                    audience: current.legal_name.clone(),
+                    onchange: move |a| update(|v| &mut v.legal_name, a),
+                }
+                AudiencePicker {
+                    id: "visibility_groups",
+                    label: "Groups (directory)",
+                    audience: current.groups.clone(),
+                    onchange: move |a| update(|v| &mut v.groups, a),
+                }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-primary",
+                        disabled: saving(),
+                        onclick: move |_| {
+                            let current = current.clone();
+                            spawn(async move {
+                                saving.set(true);
+                                match api::set_field_visibility(current).await {
+                                    Ok(()) => saved.set(true),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                saving.set(false);
+                            });
+                        },
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                }
+                if saved() {
+                    p { class: "text-muted text-sm", "Saved." }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn AudiencePicker(
+    id: String,
+    label: String,
+    audience: Audience,
+    onchange: EventHandler<Audience>,
+) -> Element {
+    let kind = match &audience {
+        Audience::Everyone => "everyone",
+        Audience::Admins => "admins",
+        Audience::Group(_) => "group",
+        Audience::Nobody => "nobody",
+    };
+    let group = match &audience {
+        Audience::Group(group) => group.clone(),
+        _ => String::new(),
+    };
+
+    rsx! {
+        div { class: "form-group",
+            label { class: "form-label", r#for: "{id}", "{label}" }
+            select {
+                id: "{id}",
+                class: "form-input",
+                value: "{kind}",
+                onchange: move |e| {
+                    let audience = match e.value().as_str() {
+                        "everyone" => Audience::Everyone,
+                        "admins" => Audience::Admins,
+                        "group" => Audience::Group(String::new()),
+                        _ => Audience::Nobody,
+                    };
+                    onchange.call(audience);
+                },
+                option { value: "everyone", "Everyone" }
+                option { value: "admins", "Admins" }
+                option { value: "group", "Members of a group" }
+                option { value: "nobody", "Nobody" }
+            }
+            if kind == "group" {
+                input {
+                    class: "form-input",
+                    placeholder: "Group name",
+                    value: "{group}",
+                    oninput: move |e| onchange.call(Audience::Group(e.value())),
+                }
+            }
+        }
+    }
+}
+
+fn toggle(events: &mut BTreeSet<NotificationEvent>, event: NotificationEvent) {
+    if !events.remove(&event) {
+        events.insert(event);
+    }
+}
+
+#[component]
+fn EventChecklist(
+    selected: BTreeSet<NotificationEvent>,
+    on_toggle: EventHandler<NotificationEvent>,
+) -> Element {
+    rsx! {
+        div { class: "checkbox-group",
+            for event in NotificationEvent::ALL {
+                label { class: "checkbox-label", key: "{event.label()}",
+                    input {
+                        r#type: "checkbox",
+                        checked: selected.contains(&event),
+                        onchange: move |_| on_toggle.call(event),
+                    }
+                    span { "{event.label()}" }
+                }
+            }
+        }
+    }
+}
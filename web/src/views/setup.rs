@@ -0,0 +1,215 @@
+use crate::Route;
+use dioxus::prelude::*;
+use types::setup::SetupCheck;
+
+/// The select option for creating the admin group. Group names can't have
+/// spaces, so it can't clash with one.
+const NEW_GROUP: &str = " new";
+
+/// The first-run setup, for picking the admin group when it isn't
+/// configured, creating it if need be, and putting someone in it when nobody
+/// is.
+#[component]
+pub fn Setup() -> Element {
+    let required = use_resource(api::setup_required);
+    let mut code = use_signal(String::new);
+    let mut checks = use_signal(|| None::<Vec<SetupCheck>>);
+    let mut groups = use_signal(Vec::<String>::new);
+    let mut chosen = use_signal(|| None::<String>);
+    let mut admin_group = use_signal(String::new);
+    let mut new_group = use_signal(|| "authit_admins".to_string());
+    let mut bootstrap_user = use_signal(String::new);
+    let mut working = use_signal(|| false);
+    let mut done = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let run_checks = move || {
+        spawn(async move {
+            working.set(true);
+            error.set(None);
+            match api::check_setup(code()).await {
+                Ok(results) => {
+                    if results.iter().all(SetupCheck::passed) {
+                        match api::list_setup_groups(code()).await {
+                            Ok(names) => groups.set(names),
+                            Err(e) => error.set(Some(e.to_string())),
+                        }
+                        match api::chosen_setup_group(code()).await {
+                            Ok(Some(group)) => {
+                                admin_group.set(group.clone());
+                                chosen.set(Some(group));
+                            }
+                            Ok(None) => chosen.set(None),
+                            Err(e) => error.set(Some(e.to_string())),
+                        }
+                    }
+                    checks.set(Some(results));
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            working.set(false);
+        });
+    };
+
+    let group_name = move || {
+        if admin_group() == NEW_GROUP {
+            new_group().trim().to_string()
+        } else {
+            admin_group()
+        }
+    };
+
+    let body = if done() {
+        let group = group_name();
+        rsx! {
+            p { "AuthIt! is set up. Members of {group} can now sign in." }
+            Link { to: Route::Login { error: None }, class: "btn btn-primary", "Sign In" }
+        }
+    } else {
+        match &*required.read() {
+            None => rsx! {
+                div { class: "loading", "Loading..." }
+            },
+            Some(Ok(false)) => rsx! {
+                p { "AuthIt! is already set up." }
+                Link { to: Route::Login { error: None }, "Sign in" }
+            },
+            Some(Err(e)) => rsx! {
+                div { class: "alert alert-error", "{e}" }
+            },
+            Some(Ok(true)) => rsx! {
+                if let Some(err) = error.read().as_ref() {
+                    div { class: "alert alert-error", "{err}" }
+                }
+                div { class: "form-group",
+                    label { class: "form-label", r#for: "setup_code", "Setup code" }
+                    input {
+                        id: "setup_code",
+                        class: "form-input",
+                        r#type: "text",
+                        autocomplete: "off",
+                        value: "{code}",
+                        oninput: move |e| {
+                            code.set(e.value());
+                            checks.set(None);
+                        },
+                    }
+                    p { class: "text-muted text-sm", "AuthIt! logs this code when it starts." }
+                }
+                button {
+                    class: "btn btn-secondary",
+                    disabled: code.read().trim().is_empty() || working(),
+                    onclick: move |_| run_checks(),
+                    if working() { "Checking..." } else { "Check Kanidm" }
+                }
+                if let Some(results) = checks() {
+                    ul {
+                        for check in results.iter() {
+                            li {
+                                if check.passed() {
+                                    span { class: "badge badge-primary", "OK" }
+                                } else {
+                                    span { class: "badge badge-warning", "Failed" }
+                                }
+                                " {check.label}"
+                                if let Some(e) = &check.error {
+                                    p { class: "text-danger text-sm", "{e}" }
+                                }
+                            }
+                        }
+                    }
+                    if results.iter().all(SetupCheck::passed) {
+                        if let Some(group) = chosen() {
+                            p {
+                                "Nobody is in {group}, the admin group, so nobody can sign in yet. "
+                                "AuthIt! creates it in Kanidm if it's missing."
+                            }
+                        } else {
+                            div { class: "form-group",
+                                label { class: "form-label", r#for: "setup_admin_group", "Admin group" }
+                                select {
+                                    id: "setup_admin_group",
+                                    class: "form-input",
+                                    value: "{admin_group}",
+                                    onchange: move |e| admin_group.set(e.value()),
+                                    option { value: "", "Choose a group..." }
+                                    for name in groups() {
+                                        option { value: "{name}", "{name}" }
+                                    }
+                                    option { value: NEW_GROUP, "Create a new group..." }
+                                }
+                                p { class: "text-muted text-sm",
+                                    "Its members can create and delete users, and manage their groups."
+                                }
+                            }
+                            if admin_group() == NEW_GROUP {
+                                div { class: "form-group",
+                                    label { class: "form-label", r#for: "setup_new_group", "New group name" }
+                                    input {
+                                        id: "setup_new_group",
+                                        class: "form-input",
+                                        r#type: "text",
+                                        value: "{new_group}",
+                                        oninput: move |e| new_group.set(e.value()),
+                                    }
+                                }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "setup_bootstrap_user", "Add to the group" }
+                            input {
+                                id: "setup_bootstrap_user",
+                                class: "form-input",
+                                r#type: "text",
+                                autocomplete: "off",
+                                placeholder: "Kanidm username",
+                                value: "{bootstrap_user}",
+                                oninput: move |e| bootstrap_user.set(e.value()),
+                            }
+                            p { class: "text-muted text-sm",
+                                "Usually yourself, so you can sign in. Leave it empty only if the group already has members."
+                            }
+                        }
+                    }
+                }
+            },
+        }
+    };
+
+    rsx! {
+        div { class: "provision-page",
+            div { class: "provision-card",
+                div { class: "provision-header",
+                    h1 { class: "provision-title", "Set Up AuthIt!" }
+                    p { class: "provision-subtitle", "Check the connection to Kanidm and pick who can use AuthIt!." }
+                }
+                div { class: "provision-body", {body} }
+                if !done() && !group_name().is_empty() {
+                    div { class: "provision-footer",
+                        button {
+                            class: "btn btn-primary btn-lg",
+                            disabled: working(),
+                            onclick: move |_| {
+                                spawn(async move {
+                                    working.set(true);
+                                    error.set(None);
+                                    let create_missing = chosen().is_some() || admin_group() == NEW_GROUP;
+                                    let user = Some(bootstrap_user().trim().to_string())
+                                        .filter(|u| !u.is_empty());
+                                    match api::complete_setup(code(), group_name(), create_missing, user)
+                                        .await
+                                    {
+                                        Ok(()) => done.set(true),
+                                        Err(e) => error.set(Some(e.to_string())),
+                                    }
+                                    working.set(false);
+                                });
+                            },
+                            if working() { "Saving..." } else { "Finish Setup" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use super::components::sleep;
+use dioxus::prelude::*;
+use jiff::{SignedDuration, Timestamp};
+use types::user_detail::UserDetail;
+use uuid::Uuid;
+
+/// How long the pointer has to rest on a row before its user is fetched, so
+/// sweeping across the list doesn't fetch everyone.
+const HOVER_DELAY_MS: u32 = 150;
+
+/// How long prefetched details are good for. After this, the user's page
+/// fetches them itself.
+const FRESH_FOR: SignedDuration = SignedDuration::from_secs(30);
+
+struct Prefetched {
+    fetched_at: Timestamp,
+    detail: UserDetail,
+}
+
+/// Fetches a user's details while their row is hovered or focused, so their
+/// page opens with them already there.
+#[derive(Clone, Copy)]
+pub struct UserPrefetch {
+    details: Signal<HashMap<Uuid, Prefetched>>,
+    fetching: Signal<Option<(Uuid, Task)>>,
+}
+
+impl UserPrefetch {
+    fn new() -> Self {
+        Self {
+            details: Signal::new(HashMap::new()),
+            fetching: Signal::new(None),
+        }
+    }
+
+    /// Fetch a user's details after a moment, unless they're already fresh
+    /// or `cancel` is called first.
+    pub fn start(&mut self, user_id: Uuid) {
+        if self.fresh(user_id) {
+            return;
+        }
+        if let Some((id, _)) = *self.fetching.peek()
+            && id == user_id
+        {
+            return;
+        }
+        self.cancel();
+
+        let mut details = self.details;
+        let mut fetching = self.fetching;
+        let task = spawn(async move {
+            sleep(HOVER_DELAY_MS).await;
+            // Failures are left for the page to show, when it fetches again.
+            if let Ok(detail) = api::get_user_detail(user_id).await {
+                let fetched = Prefetched {
+                    fetched_at: Timestamp::now(),
+                    detail,
+                };
+                details.write().insert(user_id, fetched);
+            }
+            fetching.set(None);
+        });
+        self.fetching.set(Some((user_id, task)));
+    }
+
+    /// Stop fetching, as the pointer or focus moved on.
+    pub fn cancel(&mut self) {
+        if let Some((_, task)) = self.fetching.take() {
+            task.cancel();
+        }
+    }
+
+    /// Take a user's prefetched details, if they're there and fresh. Once
+    /// taken, they're fetched again next time.
+    pub fn take(&mut self, user_id: Uuid) -> Option<UserDetail> {
+        if !self.fresh(user_id) {
+            return None;
+        }
+        self.details.write().remove(&user_id).map(|p| p.detail)
+    }
+
+    fn fresh(&self, user_id: Uuid) -> bool {
+        self.details
+            .peek()
+            .get(&user_id)
+            .is_some_and(|p| p.fetched_at + FRESH_FOR > Timestamp::now())
+    }
+}
+
+/// Share prefetched details between the user list and the user's page.
+pub fn provide_user_prefetch() -> UserPrefetch {
+    use_context_provider(UserPrefetch::new)
+}
@@ -1,24 +1,194 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use super::components::{GroupCheckboxList, UserForm};
-use crate::{Route, use_error};
+use super::components::{
+    Avatar, FieldError, GroupPicker, JobStatus, LoginEventsTable, Masked, RawEntry, Sensitive,
+    UserForm, download, format_relative, format_timestamp, parse_datetime_input, to_datetime_input,
+    use_job,
+};
+use super::help::{HelpTip, UsersTour};
+use super::user_prefetch::provide_user_prefetch;
+use crate::{
+    ErrorInfo, Route, field_errors, is_edit_conflict, use_capabilities, use_error, use_shortcuts,
+};
 use dioxus::prelude::*;
 use dioxus::{document::eval, fullstack::reqwest::Url};
-use jiff::Timestamp;
+use jiff::{Timestamp, civil::Date, tz::TimeZone};
 use types::{
     ResetLink,
-    kanidm::{Group, Person},
+    activity::Activity,
+    batch::{BatchResult, ChangeOutcome, PendingChange, stage, staged_membership},
+    capability::Capability,
+    group_deletion::GroupDeletionImpact,
+    group_import::GroupImport,
+    group_review::GroupReview,
+    group_webhook::{MembershipAction, WebhookDelivery},
+    ids::{GroupId, PersonId},
+    kanidm::{
+        EntryKind, Group, GroupClassification, LdapStatus, NewPersonSetup, Person, PosixSetup,
+    },
+    login_event::LoginEvent,
+    preferences::{ProvisionDefaults, SavedView, UserFilter, UserPreferences, UserShortcut},
+    provision::{
+        EmailRestriction, FieldRequirement, ProvisionFailureCount, ProvisionFields,
+        ProvisionFollowUp, ProvisionFunnel, ProvisionLinkSummary,
+    },
+    scheduled_membership::ScheduledMembershipChange,
+    tag::Tag,
+    user_deletion::UserDeletionImpact,
+    user_detail::UserDetail,
+    validation::{FieldErrors, validate_email},
 };
 use uuid::Uuid;
 
+/// Columns of the user list that can be hidden, by the name they're saved
+/// under and their heading. Names are always shown.
+const HIDEABLE_COLUMNS: [(&str, &str); 3] = [
+    ("username", "Username"),
+    ("email", "Email"),
+    ("groups", "Groups"),
+];
+
+/// What to fill the create user form with, from a deep link like
+/// `/users/new?name=jsmith&email=jsmith@example.com`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NewUserPrefill {
+    pub username: String,
+    pub display_name: String,
+    pub email: String,
+}
+
+/// A dialog a deep link like `/users/{id}?action=reset` can open on a user's
+/// page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserAction {
+    Reset,
+    Delete,
+}
+
+impl UserAction {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "reset" => Some(Self::Reset),
+            "delete" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reset => "reset",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// The user list's filter in the URL, like `/users?credentials=unset`, so a
+/// refresh, the back button or a shared link shows the same users. On a user's
+/// page it can also say which dialog to open.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsersQuery {
+    pub filter: UserFilter,
+    pub action: Option<UserAction>,
+}
+
+impl UsersQuery {
+    pub fn filtered(filter: UserFilter) -> Self {
+        Self {
+            filter,
+            action: None,
+        }
+    }
+}
+
+impl FromQuery for UsersQuery {
+    fn from_query(query: &str) -> Self {
+        let pairs = query.split('&').filter_map(|pair| pair.split_once('='));
+        let action = pairs
+            .clone()
+            .find(|(key, _)| *key == "action")
+            .and_then(|(_, value)| UserAction::parse(value));
+        Self {
+            filter: UserFilter::from_query_pairs(pairs),
+            action,
+        }
+    }
+}
+
+impl std::fmt::Display for UsersQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut pairs = self.filter.to_query_pairs();
+        if let Some(action) = self.action {
+            pairs.push(("action", action.as_str().to_string()));
+        }
+        let query: Vec<String> = pairs
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        f.write_str(&query.join("&"))
+    }
+}
+
 #[component]
-pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
+pub fn Users(
+    user_id: ReadSignal<Option<Uuid>>,
+    /// The filter the URL asks for.
+    url_filter: ReadSignal<UserFilter>,
+    #[props(default)] prefill: Option<NewUserPrefill>,
+    #[props(default)] action: Option<UserAction>,
+) -> Element {
     let mut users = use_signal(Vec::<Person>::new);
     let mut groups = use_signal(Vec::<Group>::new);
     let mut loading = use_signal(|| true);
     let mut error_state = use_error();
-    let mut show_create_form = use_signal(|| false);
+    let mut show_create_form = use_signal(|| prefill.is_some());
     let mut show_provision_modal = use_signal(|| false);
+    let mut show_import_modal = use_signal(|| false);
+    let mut member_counts = use_signal(HashMap::<Uuid, u32>::new);
+    let mut initial_counts = use_signal(|| None::<HashMap<Uuid, u32>>);
+    let mut classification = use_signal(GroupClassification::default);
+    // Changes staged to be reviewed and applied together.
+    let pending = use_signal(Vec::<PendingChange>::new);
+    let mut show_review = use_signal(|| false);
+    let mut tags = use_signal(Vec::<Tag>::new);
+    let mut user_tags = use_signal(HashMap::<Uuid, Vec<Uuid>>::new);
+    let mut filter = use_signal(|| url_filter.peek().clone());
+    let mut show_filter = use_signal(|| false);
+    let mut preferences = use_signal(|| None::<UserPreferences>);
+    // Only fetched once a filter needs it, as it's a request per user.
+    let mut credential_status = use_signal(|| None::<HashMap<Uuid, bool>>);
+    let mut show_bulk_delete = use_signal(|| false);
+    let mut show_bulk_reset = use_signal(|| false);
+    let mut show_rotation = use_signal(|| false);
+    let mut show_compare = use_signal(|| false);
+    let mut prefetch = provide_user_prefetch();
+    let capabilities = use_capabilities();
+    // Large directories can leave everyone's groups out of the list, so
+    // they're fetched for the selected user instead.
+    let lazy_setting = use_resource(api::group_membership_is_lazy);
+    let lazy_membership = use_memo(move || matches!(*lazy_setting.read(), Some(Ok(true))));
+
+    let mut refresh_tags = move || {
+        spawn(async move {
+            match (api::list_tags().await, api::list_user_tags().await) {
+                (Ok(t), Ok(u)) => {
+                    tags.set(t);
+                    user_tags.set(u);
+                }
+                (Err(e), _) | (_, Err(e)) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    let mut refresh_counts = move || {
+        spawn(async move {
+            if let Ok(counts) = api::group_member_counts().await {
+                if initial_counts.read().is_none() {
+                    initial_counts.set(Some(counts.clone()));
+                }
+                member_counts.set(counts);
+            }
+        });
+    };
 
     // Fetch users and groups on mount
     use_effect(move || {
@@ -29,9 +199,7 @@ pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
             let groups_result = api::list_groups().await;
 
             match (users_result, groups_result) {
-                (Ok(mut u), Ok(mut g)) => {
-                    u.sort_unstable();
-                    g.sort_unstable();
+                (Ok(u), Ok(g)) => {
                     users.set(u);
                     groups.set(g);
                 }
@@ -41,19 +209,190 @@ pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
             }
             loading.set(false);
         });
+        spawn(async move {
+            if let Ok(c) = api::get_group_classification().await {
+                classification.set(c);
+            }
+        });
+        spawn(async move {
+            if let Ok(prefs) = api::get_preferences().await {
+                preferences.set(Some(prefs));
+            }
+        });
+        refresh_counts();
+        refresh_tags();
+    });
+
+    // Keep the filter and the URL in step, following the URL when it changes,
+    // like with the back button, and replacing it when the filter does.
+    use_effect(move || filter.set(url_filter()));
+    use_effect(move || {
+        let current = filter();
+        if current == *url_filter.peek() {
+            return;
+        }
+        let query = UsersQuery::filtered(current);
+        navigator().replace(match *user_id.peek() {
+            Some(user_id) => Route::UserDetail { user_id, query },
+            None => Route::UserList { query },
+        });
+    });
+
+    use_effect(move || {
+        if filter.read().has_credentials.is_none() || credential_status.peek().is_some() {
+            return;
+        }
+        let user_ids: Vec<Uuid> = users.peek().iter().map(|u| u.uuid).collect();
+        spawn(async move {
+            match api::get_credential_status(user_ids).await {
+                Ok(status) => credential_status.set(Some(status)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+
+    // Users shown in the list, narrowed by the filter.
+    let shown_users = use_memo(move || {
+        let mut filter = filter();
+        // Without memberships in the list, there's nothing to match groups on.
+        if lazy_membership() {
+            filter.groups.clear();
+        }
+        let user_tags = user_tags.read();
+        let groups = groups.read();
+        let credential_status = credential_status.read();
+        users
+            .read()
+            .iter()
+            .filter(|u| {
+                let tags = user_tags
+                    .get(&u.uuid)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let has_credentials = credential_status
+                    .as_ref()
+                    .and_then(|s| s.get(&u.uuid).copied());
+                filter.matches(u, tags, &groups, has_credentials)
+            })
+            .cloned()
+            .collect::<Vec<Person>>()
+    });
+
+    let mut save_views = move |views: Vec<SavedView>| {
+        let Some(mut prefs) = preferences() else {
+            return;
+        };
+        prefs.saved_views = views;
+        preferences.set(Some(prefs.clone()));
+        spawn(async move {
+            if let Err(e) = api::set_preferences(prefs).await {
+                error_state.set_server_error(&e);
+            }
+        });
+    };
+    let mut set_column_hidden = move |column: &'static str, hidden: bool| {
+        let Some(mut prefs) = preferences() else {
+            return;
+        };
+        prefs.set_column_hidden(column, hidden);
+        preferences.set(Some(prefs));
+        // Start from what's saved, as favorites or the theme may have
+        // changed since the page loaded.
+        spawn(async move {
+            let result = match api::get_preferences().await {
+                Ok(mut prefs) => {
+                    prefs.set_column_hidden(column, hidden);
+                    api::set_preferences(prefs).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                error_state.set_server_error(&e);
+            }
+        });
+    };
+    let shows = move |column: &str| {
+        !preferences
+            .read()
+            .as_ref()
+            .is_some_and(|p| p.hides_column(column))
+    };
+    let saved_views = preferences
+        .read()
+        .as_ref()
+        .map(|p| p.saved_views.clone())
+        .unwrap_or_default();
+
+    // Groups whose membership changed since the page loaded, whether by us or
+    // another admin.
+    let changed_groups = use_memo(move || {
+        let counts = member_counts.read();
+        match initial_counts.read().as_ref() {
+            Some(initial) => counts
+                .iter()
+                .filter(|(id, count)| initial.get(id) != Some(count))
+                .map(|(id, _)| *id)
+                .collect::<HashSet<Uuid>>(),
+            None => HashSet::new(),
+        }
     });
 
+    let mut selected_groups = use_resource(move || lazy_groups(lazy_membership(), user_id()));
     let selected_user = use_memo(move || {
-        user_id().and_then(|id| users.read().iter().find(|u| u.uuid == id).cloned())
+        let user = user_id().and_then(|id| users.read().iter().find(|u| u.uuid == id).cloned())?;
+        Some(with_groups(user, &selected_groups.read()))
     });
 
-    let refresh_users = move || {
+    let mut shortcuts = use_shortcuts();
+    use_effect(move || {
+        let Some(user) = selected_user() else {
+            return;
+        };
+        let shortcut = UserShortcut {
+            user_id: user.uuid,
+            display_name: user.display_name,
+        };
+        spawn(async move {
+            if let Ok(s) = api::record_user_view(shortcut).await {
+                shortcuts.set(s);
+            }
+        });
+    });
+
+    // Everything the selected user's page shows beyond the list, in one
+    // request, unless it was prefetched while their row was hovered.
+    let mut user_detail = use_resource(move || async move {
+        let user_id = user_id()?;
+        let result = match prefetch.take(user_id) {
+            Some(detail) => Ok(detail),
+            None => api::get_user_detail(user_id).await,
+        };
+        match result {
+            Ok(detail) => Some(detail),
+            Err(e) => {
+                error_state.set_server_error(&e);
+                None
+            }
+        }
+    });
+
+    let mut refresh_users = move || {
         spawn(async move {
-            if let Ok(mut u) = api::list_users().await {
-                u.sort_unstable();
+            if let Ok(u) = api::list_users().await {
                 users.set(u);
             }
         });
+        refresh_counts();
+        user_detail.restart();
+        selected_groups.restart();
+    };
+
+    let mut refresh_groups = move || {
+        spawn(async move {
+            if let Ok(g) = api::list_groups().await {
+                groups.set(g);
+            }
+        });
     };
 
     rsx! {
@@ -63,22 +402,52 @@ pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
                     h1 { class: "page-title", "User Management" }
                     p { class: "page-subtitle", "View and manage Kanidm users and their group memberships." }
                 }
-                div { class: "page-header-actions",
+                div { class: "page-header-actions", id: "users-actions",
+                    if !pending.read().is_empty() {
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| show_review.set(true),
+                            "Review Changes ({pending.read().len()})"
+                        }
+                    }
                     button {
                         class: "btn btn-secondary",
-                        onclick: move |_| show_provision_modal.set(true),
-                        "Generate Provision Link"
+                        disabled: users.read().len() < 2,
+                        onclick: move |_| show_compare.set(true),
+                        "Compare Users"
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: !capabilities.allows(Capability::ManageGroups),
+                        title: capabilities.why_not(Capability::ManageGroups),
+                        onclick: move |_| show_import_modal.set(true),
+                        "Import Groups"
+                    }
+                    if capabilities.provision_links() {
+                        button {
+                            class: "btn btn-secondary",
+                            disabled: !capabilities.allows(Capability::ManagePersons),
+                            title: capabilities.why_not(Capability::ManagePersons),
+                            onclick: move |_| show_provision_modal.set(true),
+                            "Generate Provision Link"
+                        }
                     }
                     button {
                         class: "btn btn-primary",
+                        disabled: !capabilities.allows(Capability::ManagePersons),
+                        title: capabilities.why_not(Capability::ManagePersons),
                         onclick: move |_| show_create_form.set(true),
                         "Create User"
                     }
                 }
             }
 
+            UsersTour {}
+
             if *show_create_form.read() {
                 CreateUserModal {
+                    classification: classification(),
+                    prefill: prefill.clone().unwrap_or_default(),
                     on_close: move |_| show_create_form.set(false),
                     on_created: move |_| {
                         show_create_form.set(false);
@@ -87,43 +456,289 @@ pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
                 }
             }
 
+            if *show_review.read() {
+                ReviewChangesModal {
+                    pending,
+                    modified: users
+                        .read()
+                        .iter()
+                        .filter_map(|u| Some((u.uuid, u.modified.clone()?)))
+                        .collect(),
+                    on_close: move |_| show_review.set(false),
+                    on_applied: move |_| refresh_users(),
+                }
+            }
+
+            if show_compare() {
+                CompareUsersModal {
+                    users: users(),
+                    lazy_membership: lazy_membership(),
+                    groups: groups(),
+                    classification: classification(),
+                    first: selected_user().map(|u| u.uuid),
+                    pending,
+                    on_close: move |_| show_compare.set(false),
+                }
+            }
+
             if *show_provision_modal.read() {
                 ProvisionLinkModal {
+                    classification: classification(),
                     on_close: move |_| show_provision_modal.set(false),
                 }
             }
 
+            if *show_import_modal.read() {
+                ImportGroupsModal {
+                    on_close: move |_| show_import_modal.set(false),
+                    on_imported: move |_| {
+                        refresh_users();
+                        refresh_groups();
+                    },
+                }
+            }
+
+            if show_bulk_delete() {
+                BulkDeleteModal {
+                    users: shown_users(),
+                    on_close: move |_| show_bulk_delete.set(false),
+                    on_finished: move |_| {
+                        refresh_users();
+                        refresh_tags();
+                    },
+                }
+            }
+
+            if show_bulk_reset() {
+                BulkResetModal {
+                    users: shown_users(),
+                    on_close: move |_| show_bulk_reset.set(false),
+                }
+            }
+
+            if show_rotation() {
+                StartRotationModal {
+                    users: shown_users(),
+                    on_close: move |_| show_rotation.set(false),
+                }
+            }
+
             if *loading.read() {
                 div { class: "loading", "Loading users..." }
             } else {
                 div { class: "grid grid-cols-3",
-                    div { class: "card",
+                    div { class: "card", id: "users-list",
                         div { class: "card-header",
                             h2 { class: "card-title", "Users" }
+                            div { class: "page-header-actions",
+                                button {
+                                    class: "btn btn-secondary",
+                                    onclick: move |_| show_filter.set(!show_filter()),
+                                    if show_filter() { "Hide Filter" } else { "Filter" }
+                                }
+                                details { class: "column-picker",
+                                    summary { class: "btn btn-secondary", "Columns" }
+                                    div { class: "column-picker-menu",
+                                        for (column, heading) in HIDEABLE_COLUMNS {
+                                            label { class: "checkbox-label", key: "{column}",
+                                                input {
+                                                    r#type: "checkbox",
+                                                    checked: shows(column),
+                                                    disabled: preferences.read().is_none(),
+                                                    onchange: move |e: FormEvent| set_column_hidden(column, !e.checked()),
+                                                }
+                                                span { "{heading}" }
+                                            }
+                                        }
+                                    }
+                                }
+                                if !filter.read().is_empty() && !shown_users.read().is_empty() {
+                                    button {
+                                        class: "btn btn-secondary",
+                                        disabled: !capabilities.allows(Capability::ResetCredentials),
+                                        title: capabilities.why_not(Capability::ResetCredentials),
+                                        onclick: move |_| show_bulk_reset.set(true),
+                                        "Reset {shown_users.read().len()}"
+                                    }
+                                    button {
+                                        class: "btn btn-secondary",
+                                        disabled: !capabilities.allows(Capability::ManagePersons),
+                                        title: capabilities.why_not(Capability::ManagePersons),
+                                        onclick: move |_| show_rotation.set(true),
+                                        "Rotate {shown_users.read().len()}"
+                                    }
+                                    button {
+                                        class: "btn btn-danger",
+                                        disabled: !capabilities.allows(Capability::ManagePersons),
+                                        title: capabilities.why_not(Capability::ManagePersons),
+                                        onclick: move |_| show_bulk_delete.set(true),
+                                        "Delete {shown_users.read().len()}"
+                                    }
+                                }
+                            }
+                        }
+                        div { class: "view-tabs",
+                            span { class: if filter.read().is_empty() { "view-tab active" } else { "view-tab" },
+                                button {
+                                    class: "btn btn-link",
+                                    onclick: move |_| filter.set(UserFilter::default()),
+                                    "All"
+                                }
+                            }
+                            for view in saved_views.iter().cloned() {
+                                {
+                                    let is_active = *filter.read() == view.filter;
+                                    let name = view.name.clone();
+                                    let saved_views = saved_views.clone();
+                                    rsx! {
+                                        span {
+                                            key: "{view.name}",
+                                            class: if is_active { "view-tab active" } else { "view-tab" },
+                                            button {
+                                                class: "btn btn-link",
+                                                onclick: move |_| filter.set(view.filter.clone()),
+                                                "{view.name}"
+                                            }
+                                            button {
+                                                class: "btn btn-link",
+                                                title: "Delete view",
+                                                onclick: move |_| {
+                                                    let views = saved_views
+                                                        .iter()
+                                                        .filter(|v| v.name != name)
+                                                        .cloned()
+                                                        .collect();
+                                                    save_views(views);
+                                                },
+                                                "×"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if show_filter() {
+                            UserFilterPanel {
+                                filter,
+                                tags: tags(),
+                                groups: if lazy_membership() { Vec::new() } else { groups() },
+                                can_save: preferences.read().is_some(),
+                                on_save: {
+                                    let saved_views = saved_views.clone();
+                                    move |name: String| {
+                                        let mut views: Vec<SavedView> = saved_views
+                                            .iter()
+                                            .filter(|v| v.name != name)
+                                            .cloned()
+                                            .collect();
+                                        views.push(SavedView {
+                                            name,
+                                            filter: filter(),
+                                        });
+                                        save_views(views);
+                                    }
+                                },
+                            }
                         }
                         div { class: "table-container",
                             table {
                                 thead {
                                     tr {
                                         th { "Name" }
-                                        th { "Username" }
-                                        th { "Email" }
+                                        if shows("username") {
+                                            th { "Username" }
+                                        }
+                                        if shows("email") {
+                                            th { "Email" }
+                                        }
+                                        if !lazy_membership() && shows("groups") {
+                                            th { "Groups" }
+                                        }
                                     }
                                 }
                                 tbody {
-                                    for user in users.read().iter() {
+                                    for user in shown_users.read().iter() {
                                         {
                                             let user_id = user.uuid;
                                             let is_selected = selected_user().as_ref().map(|u| u.uuid == user_id).unwrap_or(false);
+                                            let tag_ids = user_tags.read().get(&user_id).cloned().unwrap_or_default();
+                                            let user_tag_names: Vec<String> = tags
+                                                .read()
+                                                .iter()
+                                                .filter(|t| tag_ids.contains(&t.id))
+                                                .map(|t| t.name.clone())
+                                                .collect();
+                                            let custom_group_count = user
+                                                .group_names()
+                                                .filter(|g| !classification.read().is_builtin(g))
+                                                .count();
+                                            let key_groups: Vec<String> = classification
+                                                .read()
+                                                .key_groups
+                                                .iter()
+                                                .filter(|k| user.group_names().any(|g| g == k.as_str()))
+                                                .cloned()
+                                                .collect();
                                             rsx! {
                                                 tr {
                                                     class: if is_selected { "selected" },
+                                                    tabindex: "0",
                                                     onclick: move |_| {
-                                                        navigator().replace(Route::UserDetail { user_id });
+                                                        navigator().replace(Route::UserDetail {
+                                                            user_id,
+                                                            query: UsersQuery::filtered(filter()),
+                                                        });
+                                                    },
+                                                    onkeydown: move |e| {
+                                                        if e.key() == Key::Enter {
+                                                            navigator().replace(Route::UserDetail {
+                                                                user_id,
+                                                                query: UsersQuery::filtered(filter()),
+                                                            });
+                                                        }
                                                     },
-                                                    td { "{user.display_name}" }
-                                                    td { "{user.name}" }
-                                                    td { {user.email_addresses.join(", ")} }
+                                                    // Fetch what their page shows while the admin decides.
+                                                    onmouseenter: move |_| {
+                                                        if !is_selected {
+                                                            prefetch.start(user_id);
+                                                        }
+                                                    },
+                                                    onmouseleave: move |_| prefetch.cancel(),
+                                                    onfocus: move |_| {
+                                                        if !is_selected {
+                                                            prefetch.start(user_id);
+                                                        }
+                                                    },
+                                                    onblur: move |_| prefetch.cancel(),
+                                                    td {
+                                                        div { class: "user-name-cell",
+                                                            Avatar {
+                                                                key: "{user_id}",
+                                                                user_id,
+                                                                name: user.display_name.clone(),
+                                                            }
+                                                            "{user.display_name}"
+                                                            for name in user_tag_names {
+                                                                span { class: "badge", "{name}" }
+                                                            }
+                                                        }
+                                                    }
+                                                    if shows("username") {
+                                                        td { "{user.name}" }
+                                                    }
+                                                    if shows("email") {
+                                                        td {
+                                                            Masked { kind: Sensitive::Email, text: user.email_addresses.join(", ") }
+                                                        }
+                                                    }
+                                                    if !lazy_membership() && shows("groups") {
+                                                        td {
+                                                            span { title: "Custom groups", "{custom_group_count}" }
+                                                            for group in key_groups {
+                                                                span { class: "badge badge-primary", "{group}" }
+                                                            }
+                                                        }
+                                                    }
                                                 }
                                             }
                                         }
@@ -135,11 +750,25 @@ pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
                     if let Some(u) = selected_user() {
                         UserDetailsCard {
                             user: u.clone(),
+                            detail: user_detail.read().clone().flatten().filter(|d| d.person.uuid == u.uuid),
+                            action,
                             groups: groups.read().clone(),
-                            on_updated: move |_| refresh_users(),
+                            classification: classification(),
+                            member_counts: member_counts.read().clone(),
+                            changed_groups: changed_groups(),
+                            tags: tags(),
+                            user_tags: user_tags.read().get(&u.uuid).cloned().unwrap_or_default(),
+                            on_tags_changed: move |_| refresh_tags(),
+                            pending,
                             on_deleted: move |_| {
                                 refresh_users();
-                                navigator().replace(Route::UserList {});
+                                navigator().replace(Route::UserList {
+                                    query: UsersQuery::filtered(filter()),
+                                });
+                            },
+                            on_groups_changed: move |_| {
+                                refresh_users();
+                                refresh_groups();
                             },
                         }
                     }
@@ -151,46 +780,48 @@ pub fn Users(user_id: ReadSignal<Option<Uuid>>) -> Element {
 
 #[component]
 fn ExpiryTime(expires_at: Timestamp) -> Element {
-    let formatted = jiff::tz::TimeZone::get("America/Los_Angeles")
-        .ok()
-        .map(|tz| expires_at.to_zoned(tz))
-        .map(|zdt| zdt.strftime("%b %d, %Y at %I:%M %p %Z").to_string())
-        .unwrap_or_else(|| "Unknown".to_string());
+    let formatted = format_timestamp(expires_at);
 
     rsx! {
         span { class: "text-muted", "Expires: {formatted}" }
     }
 }
 
-fn is_builtin_group(name: &str) -> bool {
-    name.starts_with("idm_") || name.starts_with("system_") || name.starts_with("builtin_")
-}
-
-/// Check if user is member of group
-fn is_member_of(user: &Person, group: &Group) -> bool {
-    // user.groups contains entries like "groupname@domain"
-    // group.name is just "groupname"
-    let prefix = format!("{}@", group.name);
-    user.groups.iter().any(|g| g.starts_with(&prefix))
-}
-
 #[component]
 fn UserDetailsCard(
     user: Person,
+    /// What the list doesn't have, once it's fetched.
+    detail: Option<UserDetail>,
+    action: Option<UserAction>,
     groups: Vec<Group>,
-    on_updated: EventHandler<()>,
+    classification: GroupClassification,
+    member_counts: HashMap<Uuid, u32>,
+    changed_groups: HashSet<Uuid>,
+    tags: Vec<Tag>,
+    user_tags: Vec<Uuid>,
+    on_tags_changed: EventHandler<()>,
+    pending: Signal<Vec<PendingChange>>,
     on_deleted: EventHandler<()>,
+    on_groups_changed: EventHandler<()>,
 ) -> Element {
     let mut error_state = use_error();
+    let mut deleting_group = use_signal(|| None::<Uuid>);
+    let mut managing_group = use_signal(|| None::<Uuid>);
+    let mut creating_group = use_signal(|| false);
+    let mut shortcuts = use_shortcuts();
     let mut generating_reset = use_signal(|| false);
+    let mut exporting = use_signal(|| false);
     let mut reset_link = use_signal(|| None::<ResetLink>);
-    let mut updating_group = use_signal(|| None::<Uuid>);
     let mut copied = use_signal(|| false);
     let mut prev_user_id = use_signal(|| user.uuid);
-    let mut show_delete_confirm = use_signal(|| false);
+    let mut show_delete_confirm = use_signal(|| action == Some(UserAction::Delete));
+    let mut show_reset_confirm = use_signal(|| action == Some(UserAction::Reset));
     let mut deleting = use_signal(|| false);
+    let mut saving_groups = use_signal(|| false);
+    let capabilities = use_capabilities();
 
     let user_id = user.uuid;
+    let is_pinned = shortcuts.read().is_pinned(user_id);
 
     // Clear reset link when user changes
     if *prev_user_id.read() != user_id {
@@ -198,107 +829,296 @@ fn UserDetailsCard(
         reset_link.set(None);
         copied.set(false);
         show_delete_confirm.set(false);
+        show_reset_confirm.set(false);
     }
 
+    let mut generate_reset = move || {
+        spawn(async move {
+            generating_reset.set(true);
+            match api::generate_reset_link(user_id).await {
+                Ok(link) => reset_link.set(Some(link)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            generating_reset.set(false);
+            show_reset_confirm.set(false);
+        });
+    };
+
+    // Group memberships as they'll be once staged changes are applied.
+    let member_ids: HashSet<Uuid> = groups
+        .iter()
+        .filter(|g| {
+            staged_membership(&pending.read(), user_id, g.uuid)
+                .unwrap_or_else(|| user.is_member_of(g))
+        })
+        .map(|g| g.uuid)
+        .collect();
+    let staged_ids: HashSet<Uuid> = pending
+        .read()
+        .iter()
+        .filter(|c| c.user_id() == user_id)
+        .filter_map(|c| c.group_id())
+        .collect();
+
+    // Change the user's groups right away, in one call. The server works out
+    // what to add and remove, and reports any changes that failed.
+    let set_groups = {
+        let member_ids = member_ids.clone();
+        let modified = user.modified.clone();
+        move |(ids, select): (Vec<Uuid>, bool)| {
+            let mut wanted = member_ids.clone();
+            for id in &ids {
+                if select {
+                    wanted.insert(*id);
+                } else {
+                    wanted.remove(id);
+                }
+            }
+            if wanted == member_ids {
+                return;
+            }
+            let group_ids: Vec<GroupId> = wanted.into_iter().map(GroupId).collect();
+            let modified = modified.clone();
+            spawn(async move {
+                saving_groups.set(true);
+                match api::set_user_groups(PersonId(user_id), group_ids, modified).await {
+                    Ok(changes) => {
+                        // What was staged for this user is done or moot now.
+                        let mut pending = pending;
+                        pending
+                            .write()
+                            .retain(|c| c.user_id() != user_id || c.group_id().is_none());
+                        if !changes.is_complete() {
+                            let failed: Vec<String> = changes
+                                .failed
+                                .iter()
+                                .map(|f| format!("{}: {}", f.group_name, f.error))
+                                .collect();
+                            error_state
+                                .set(format!("Some group changes failed. {}", failed.join("; ")));
+                        }
+                        on_groups_changed.call(());
+                    }
+                    Err(e) => error_state.set_server_error(&e),
+                }
+                saving_groups.set(false);
+            });
+        }
+    };
+    let groups_disabled = capabilities
+        .why_not(Capability::ManageGroups)
+        .or_else(|| saving_groups().then(|| "Saving group changes...".to_string()));
+
     // Separate groups into custom and built-in (already sorted from parent)
     let custom_groups: Vec<Group> = groups
         .iter()
-        .filter(|g| !is_builtin_group(&g.name))
+        .filter(|g| !classification.is_builtin(&g.name))
         .cloned()
         .collect();
     let builtin_groups: Vec<Group> = groups
         .iter()
-        .filter(|g| is_builtin_group(&g.name))
+        .filter(|g| classification.is_builtin(&g.name))
         .cloned()
         .collect();
 
     rsx! {
-        div { class: "card",
+        div { class: "card", id: "user-details",
             div { class: "card-header",
                 h2 { class: "card-title", "User Details" }
+                button {
+                    class: "btn btn-secondary",
+                    onclick: {
+                        let shortcut = UserShortcut {
+                            user_id,
+                            display_name: user.display_name.clone(),
+                        };
+                        move |_| {
+                            let shortcut = shortcut.clone();
+                            spawn(async move {
+                                match api::set_user_pinned(shortcut, !is_pinned).await {
+                                    Ok(s) => shortcuts.set(s),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                            });
+                        }
+                    },
+                    if is_pinned { "Unpin" } else { "Pin" }
+                }
             }
             div { class: "card-body",
+                AvatarUpload { key: "{user.uuid}", user_id, name: user.display_name.clone() }
                 div { class: "form-group",
                     span { class: "form-label", "Username" }
                     div { class: "form-value", "{user.name}" }
+                    if let Some(spn) = &user.spn {
+                        div { class: "text-muted text-sm", title: "How Kanidm and its OAuth clients refer to them", "{spn}" }
+                    }
                 }
-                div { class: "form-group",
-                    span { class: "form-label", "Email" }
-                    div { class: "form-value", "{user.email_addresses.join(\", \")}" }
+                NameField {
+                    key: "{user.uuid}-display",
+                    id: "display_name",
+                    label: "Display Name",
+                    saved: user.display_name.clone(),
+                    staged: staged_name(&pending.read(), user_id, false),
+                    on_stage: {
+                        let user_name = user.name.clone();
+                        move |display_name| {
+                            let change = PendingChange::SetDisplayName {
+                                user_id,
+                                user_name: user_name.clone(),
+                                display_name,
+                            };
+                            stage(&mut pending.write(), change);
+                        }
+                    },
+                }
+                if capabilities.legal_names() {
+                    NameField {
+                        key: "{user.uuid}-legal",
+                        id: "legal_name",
+                        label: "Legal Name",
+                        saved: user.legal_name.clone().unwrap_or_default(),
+                        staged: staged_name(&pending.read(), user_id, true),
+                        on_stage: {
+                            let user_name = user.name.clone();
+                            move |legal_name: String| {
+                                let change = PendingChange::SetLegalName {
+                                    user_id,
+                                    user_name: user_name.clone(),
+                                    legal_name: Some(legal_name).filter(|n| !n.trim().is_empty()),
+                                };
+                                stage(&mut pending.write(), change);
+                            }
+                        },
+                    }
+                }
+                EmailAddresses {
+                    key: "{user.uuid}",
+                    user_id,
+                    user_name: user.name.clone(),
+                    addresses: user.email_addresses.clone(),
+                    pending,
                 }
                 div { class: "form-group",
                     span { class: "form-label", "UUID" }
-                    div { class: "form-value form-value-mono", "{user.uuid}" }
+                    div { class: "form-value form-value-mono",
+                        Masked { kind: Sensitive::Id, text: user.uuid.to_string() }
+                    }
+                }
+                if let Some(detail) = &detail {
+                    CredentialStatus { has_credentials: detail.has_credentials }
+                    PhoneNumber {
+                        key: "{user.uuid}",
+                        user_id,
+                        user_name: user.name.clone(),
+                        saved: detail.phone_number.clone(),
+                        pending,
+                    }
+                    LdapSection { status: detail.ldap_status.clone() }
+                    LoginHistory { events: detail.login_events.clone() }
+                    RecentActivity { activity: detail.recent_activity.clone() }
+                }
+                RawEntry { key: "{user.uuid}", kind: EntryKind::Person, id: user_id }
+                if !tags.is_empty() {
+                    UserTags {
+                        user_id,
+                        tags: tags.clone(),
+                        selected: user_tags.clone(),
+                        on_change: on_tags_changed,
+                    }
                 }
 
                 div { class: "divider" }
 
                 h3 { class: "section-header", "Custom Groups" }
-                {
-                    let custom_member_ids: HashSet<Uuid> = custom_groups
-                        .iter()
-                        .filter(|g| is_member_of(&user, g))
-                        .map(|g| g.uuid)
-                        .collect();
-                    let custom_member_ids_clone = custom_member_ids.clone();
-                    rsx! {
-                        GroupCheckboxList {
-                            groups: custom_groups.clone(),
-                            selected: custom_member_ids,
-                            updating: *updating_group.read(),
-                            on_toggle: move |group_id: Uuid| {
-                                let user_id = user_id;
-                                let add = !custom_member_ids_clone.contains(&group_id);
-                                spawn(async move {
-                                    updating_group.set(Some(group_id));
-                                    match api::update_user_group(user_id, group_id, add).await {
-                                        Ok(()) => on_updated.call(()),
-                                        Err(e) => error_state.set_server_error(&e),
-                                    }
-                                    updating_group.set(None);
-                                });
-                            },
+                GroupPicker {
+                    disabled_reason: groups_disabled.clone(),
+                    groups: custom_groups.clone(),
+                    selected: member_ids.clone(),
+                    member_counts: member_counts.clone(),
+                    changed: changed_groups.clone(),
+                    staged: staged_ids.clone(),
+                    on_delete: move |group_id| deleting_group.set(Some(group_id)),
+                    on_manage: move |group_id| managing_group.set(Some(group_id)),
+                    on_set_many: set_groups.clone(),
+                    on_toggle: {
+                        let set_groups = set_groups.clone();
+                        let member_ids = member_ids.clone();
+                        move |group_id: Uuid| {
+                            set_groups((vec![group_id], !member_ids.contains(&group_id)));
                         }
-                    }
+                    },
                 }
                 if custom_groups.is_empty() {
                     p { class: "text-muted", "No custom groups" }
                 }
+                if creating_group() {
+                    NewGroupForm {
+                        key: "{user.uuid}",
+                        user_id,
+                        templates: custom_groups.clone(),
+                        on_cancel: move |_| creating_group.set(false),
+                        on_created: move |_| {
+                            creating_group.set(false);
+                            on_groups_changed.call(());
+                        },
+                    }
+                } else {
+                    button {
+                        class: "btn btn-link",
+                        disabled: !capabilities.allows(Capability::ManageGroups),
+                        title: capabilities.why_not(Capability::ManageGroups),
+                        onclick: move |_| creating_group.set(true),
+                        "+ New group with this user"
+                    }
+                }
+                if let Some(detail) = &detail {
+                    GroupOwnership {
+                        key: "{user.uuid}",
+                        user_id,
+                        groups: custom_groups.iter().filter(|g| member_ids.contains(&g.uuid)).cloned().collect::<Vec<_>>(),
+                        owned: detail.owned_groups.clone(),
+                    }
+                }
 
                 div { class: "divider" }
 
-                h3 { class: "section-header", "Built-in Groups" }
-                {
-                    let builtin_member_ids: HashSet<Uuid> = builtin_groups
-                        .iter()
-                        .filter(|g| is_member_of(&user, g))
-                        .map(|g| g.uuid)
-                        .collect();
-                    let builtin_member_ids_clone = builtin_member_ids.clone();
-                    rsx! {
-                        GroupCheckboxList {
-                            groups: builtin_groups.clone(),
-                            selected: builtin_member_ids,
-                            updating: *updating_group.read(),
-                            on_toggle: move |group_id: Uuid| {
-                                let user_id = user_id;
-                                let add = !builtin_member_ids_clone.contains(&group_id);
-                                spawn(async move {
-                                    updating_group.set(Some(group_id));
-                                    match api::update_user_group(user_id, group_id, add).await {
-                                        Ok(()) => on_updated.call(()),
-                                        Err(e) => error_state.set_server_error(&e),
-                                    }
-                                    updating_group.set(None);
-                                });
-                            },
+                h3 { class: "section-header",
+                    "Built-in Groups"
+                    HelpTip { topic: "builtin_groups" }
+                }
+                GroupPicker {
+                    disabled_reason: groups_disabled.clone(),
+                    groups: builtin_groups.clone(),
+                    selected: member_ids.clone(),
+                    member_counts: member_counts.clone(),
+                    changed: changed_groups.clone(),
+                    staged: staged_ids.clone(),
+                    on_set_many: set_groups.clone(),
+                    on_toggle: {
+                        let set_groups = set_groups.clone();
+                        let member_ids = member_ids.clone();
+                        move |group_id: Uuid| {
+                            set_groups((vec![group_id], !member_ids.contains(&group_id)));
                         }
+                    },
+                }
+
+                if let Some(detail) = &detail {
+                    ScheduledChanges {
+                        key: "{user.uuid}",
+                        user_id,
+                        groups: groups.clone(),
+                        scheduled: detail.scheduled_changes.clone(),
                     }
                 }
 
                 div { class: "divider" }
 
-                h3 { class: "section-header", "Credential Reset" }
+                h3 { class: "section-header", id: "user-credential-reset",
+                    "Credential Reset"
+                    HelpTip { topic: "reset_links" }
+                }
                 if let Some(link) = reset_link.read().as_ref() {
                     {
                         let url = link.url.clone();
@@ -306,7 +1126,9 @@ fn UserDetailsCard(
                         rsx! {
                             div { class: "reset-link-container",
                                 div { class: "code-block-wrapper",
-                                    div { class: "code-block", "{url}" }
+                                    div { class: "code-block",
+                                        Masked { kind: Sensitive::Token, text: url.clone() }
+                                    }
                                     button {
                                         class: if *copied.read() { "copy-btn copied" } else { "copy-btn" },
                                         title: if *copied.read() { "Copied!" } else { "Copy to clipboard" },
@@ -358,6 +1180,9 @@ fn UserDetailsCard(
                                 div { class: "reset-link-expiry",
                                     ExpiryTime { expires_at }
                                 }
+                                p { class: "text-muted text-sm",
+                                    "You'll be notified once they've used it."
+                                }
                                 button {
                                     onclick: move |_| {
                                         reset_link.set(None);
@@ -371,19 +1196,10 @@ fn UserDetailsCard(
                     }
                 } else {
                     button {
-                        onclick: {
-                            move |_| {
-                                spawn(async move {
-                                    generating_reset.set(true);
-                                    match api::generate_reset_link(user_id).await {
-                                        Ok(link) => reset_link.set(Some(link)),
-                                        Err(e) => error_state.set_server_error(&e),
-                                    }
-                                    generating_reset.set(false);
-                                });
-                            }
-                        },
-                        disabled: *generating_reset.read(),
+                        onclick: move |_| generate_reset(),
+                        disabled: *generating_reset.read()
+                            || !capabilities.allows(Capability::ResetCredentials),
+                        title: capabilities.why_not(Capability::ResetCredentials),
                         class: "btn btn-primary",
                         if *generating_reset.read() {
                             "Generating..."
@@ -395,17 +1211,95 @@ fn UserDetailsCard(
 
                 div { class: "divider" }
 
-                h3 { class: "section-header section-header-danger", "Danger Zone" }
+                h3 { class: "section-header", "Personal Data" }
+                p { class: "text-muted text-sm",
+                    "Download everything AuthIt! and Kanidm hold about this user, for a subject-access request."
+                }
                 button {
-                    class: "btn btn-danger",
-                    onclick: move |_| show_delete_confirm.set(true),
-                    "Delete User"
+                    class: "btn btn-secondary",
+                    disabled: exporting(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            exporting.set(true);
+                            match api::export_user(user_id).await {
+                                Ok(export) => match serde_json::to_string_pretty(&export) {
+                                    Ok(json) => {
+                                        download(
+                                            &format!("user-{user_id}.json"),
+                                            "application/json",
+                                            json,
+                                        )
+                                    }
+                                    Err(e) => error_state.set(e.to_string()),
+                                },
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                            exporting.set(false);
+                        });
+                    },
+                    if exporting() { "Exporting..." } else { "Export Data" }
+                }
+
+                div { class: "divider" }
+
+                h3 { class: "section-header section-header-danger", "Danger Zone" }
+                div { class: "page-header-actions",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: !capabilities.allows(Capability::ManagePersons),
+                        title: capabilities.why_not(Capability::ManagePersons),
+                        onclick: move |_| {
+                            spawn(async move {
+                                match api::start_offboarding(user_id).await {
+                                    Ok(_) => {
+                                        navigator().push(Route::Offboardings {});
+                                    }
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                            });
+                        },
+                        "Start Offboarding"
+                    }
+                    button {
+                        class: "btn btn-danger",
+                        disabled: !capabilities.allows(Capability::ManagePersons),
+                        title: capabilities.why_not(Capability::ManagePersons),
+                        onclick: move |_| show_delete_confirm.set(true),
+                        "Delete User"
+                    }
                 }
             }
         }
 
+        if let Some(group_id) = deleting_group() {
+            DeleteGroupModal {
+                key: "{group_id}",
+                group_id,
+                on_close: move |_| deleting_group.set(None),
+                on_deleted: move |_| {
+                    deleting_group.set(None);
+                    on_groups_changed.call(());
+                },
+            }
+        }
+        if let Some(group) = managing_group().and_then(|id| custom_groups.iter().find(|g| g.uuid == id)) {
+            GroupManagerModal {
+                key: "{group.uuid}",
+                group: group.clone(),
+                on_close: move |_| managing_group.set(None),
+            }
+        }
+        if show_reset_confirm() {
+            ResetConfirmModal {
+                user_name: user.display_name.clone(),
+                generating: generating_reset(),
+                on_close: move |_| show_reset_confirm.set(false),
+                on_confirm: move |_| generate_reset(),
+            }
+        }
         if *show_delete_confirm.read() {
             DeleteConfirmModal {
+                user_id,
                 user_name: user.display_name.clone(),
                 deleting: *deleting.read(),
                 on_close: move |_| show_delete_confirm.set(false),
@@ -429,99 +1323,270 @@ fn UserDetailsCard(
 }
 
 #[component]
-fn DeleteConfirmModal(
+fn AvatarUpload(user_id: Uuid, name: String) -> Element {
+    let mut error_state = use_error();
+    let mut version = use_signal(|| 0u32);
+    let mut uploading = use_signal(|| false);
+
+    rsx! {
+        div { class: "avatar-upload",
+            Avatar {
+                user_id,
+                name,
+                class: "avatar avatar-lg",
+                version: version(),
+            }
+            label { class: "btn btn-secondary",
+                if uploading() {
+                    "Uploading..."
+                } else {
+                    "Upload Picture"
+                }
+                input {
+                    r#type: "file",
+                    accept: "image/png,image/jpeg,image/webp",
+                    hidden: true,
+                    disabled: uploading(),
+                    onchange: move |e| {
+                        let Some(file) = e.files().into_iter().next() else {
+                            return;
+                        };
+                        spawn(async move {
+                            uploading.set(true);
+                            match file.read_bytes().await {
+                                Ok(data) => match api::upload_avatar(user_id, data.to_vec()).await {
+                                    Ok(()) => version += 1,
+                                    Err(e) => error_state.set_server_error(&e),
+                                },
+                                Err(e) => error_state.set(format!("Failed to read file: {e}")),
+                            }
+                            uploading.set(false);
+                        });
+                    },
+                }
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: uploading(),
+                onclick: move |_| {
+                    spawn(async move {
+                        match api::delete_avatar(user_id).await {
+                            Ok(()) => version += 1,
+                            Err(e) => error_state.set_server_error(&e),
+                        }
+                    });
+                },
+                "Remove"
+            }
+        }
+    }
+}
+
+/// A user's email addresses, in Kanidm's order. The first is the primary
+/// address. Edits are staged, to be applied with the user's other changes.
+#[component]
+fn EmailAddresses(
+    user_id: Uuid,
     user_name: String,
-    deleting: bool,
-    on_close: EventHandler<()>,
-    on_confirm: EventHandler<()>,
+    addresses: Vec<String>,
+    pending: Signal<Vec<PendingChange>>,
 ) -> Element {
+    let mut error_state = use_error();
+    let mut new_address = use_signal(String::new);
+    let mut verified = use_signal(Vec::<String>::new);
+    let user_name = use_signal(|| user_name);
+    let capabilities = use_capabilities();
+
+    use_effect(move || {
+        spawn(async move {
+            match api::list_email_verifications(user_id).await {
+                Ok(statuses) => verified.set(
+                    statuses
+                        .into_iter()
+                        .filter(|s| s.is_verified())
+                        .map(|s| s.address.to_lowercase())
+                        .collect(),
+                ),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    });
+    let locked = !capabilities.allows(Capability::ManagePersons);
+    let disabled_reason = capabilities.why_not(Capability::ManagePersons);
+
+    let staged = pending.read().iter().find_map(|c| match c {
+        PendingChange::SetEmailAddresses {
+            user_id: u,
+            addresses,
+            ..
+        } if *u == user_id => Some(addresses.clone()),
+        _ => None,
+    });
+    let is_staged = staged.is_some();
+    let addresses = staged.unwrap_or(addresses);
+
+    let mut save = move |addresses: Vec<String>| {
+        let change = PendingChange::SetEmailAddresses {
+            user_id,
+            user_name: user_name(),
+            addresses,
+        };
+        stage(&mut pending.write(), change);
+        new_address.set(String::new());
+    };
+
+    let count = addresses.len();
+
     rsx! {
-        div { class: "modal-overlay",
-            onclick: move |_| if !deleting { on_close.call(()) },
-            div { class: "modal modal-sm",
-                onclick: move |e| e.stop_propagation(),
-                div { class: "modal-header",
-                    h2 { class: "modal-title", "Delete User" }
-                    if !deleting {
+        div { class: "form-group",
+            span { class: "form-label",
+                "Email "
+                if is_staged {
+                    span { class: "badge badge-warning", "Pending" }
+                }
+            }
+            if addresses.is_empty() {
+                p { class: "text-muted", "No email addresses" }
+            }
+            for (i, address) in addresses.iter().enumerate() {
+                div { class: "email-row", key: "{address}",
+                    span { class: "form-value",
+                        Masked { kind: Sensitive::Email, text: address.clone() }
+                    }
+                    if verified.read().contains(&address.to_lowercase()) {
+                        span { class: "badge", "Verified" }
+                    }
+                    if i == 0 {
+                        span { class: "badge badge-primary", "Primary" }
+                    } else {
                         button {
-                            class: "modal-close",
-                            onclick: move |_| on_close.call(()),
-                            "×"
+                            class: "btn btn-link",
+                            disabled: locked,
+                            title: disabled_reason.clone(),
+                            onclick: {
+                                let addresses = addresses.clone();
+                                move |_| {
+                                    let mut addresses = addresses.clone();
+                                    let primary = addresses.remove(i);
+                                    addresses.insert(0, primary);
+                                    save(addresses);
+                                }
+                            },
+                            "Make primary"
                         }
                     }
-                }
-                div { class: "modal-body",
-                    p { "Are you sure you want to delete " strong { "{user_name}" } "?" }
-                    p { class: "text-muted", "This action cannot be undone." }
-                }
-                div { class: "modal-footer",
-                    button {
-                        class: "btn btn-secondary",
-                        disabled: deleting,
-                        onclick: move |_| on_close.call(()),
-                        "Cancel"
+                    if i + 1 < count {
+                        button {
+                            class: "btn btn-link",
+                            disabled: locked,
+                            title: disabled_reason.clone().unwrap_or_else(|| "Move down".to_string()),
+                            onclick: {
+                                let addresses = addresses.clone();
+                                move |_| {
+                                    let mut addresses = addresses.clone();
+                                    addresses.swap(i, i + 1);
+                                    save(addresses);
+                                }
+                            },
+                            "↓"
+                        }
                     }
                     button {
-                        class: "btn btn-danger",
-                        disabled: deleting,
-                        onclick: move |_| on_confirm.call(()),
-                        if deleting { "Deleting..." } else { "Delete" }
+                        class: "btn btn-link",
+                        disabled: locked,
+                        title: disabled_reason.clone().unwrap_or_else(|| "Remove".to_string()),
+                        onclick: {
+                            let addresses = addresses.clone();
+                            move |_| {
+                                let mut addresses = addresses.clone();
+                                addresses.remove(i);
+                                save(addresses);
+                            }
+                        },
+                        "×"
                     }
                 }
             }
+            div { class: "email-row",
+                input {
+                    class: "form-input",
+                    r#type: "email",
+                    placeholder: "Add an address",
+                    disabled: locked,
+                    title: disabled_reason.clone(),
+                    value: "{new_address}",
+                    oninput: move |e| new_address.set(e.value()),
+                }
+                button {
+                    class: "btn btn-secondary",
+                    disabled: locked || validate_email(new_address.read().trim()).is_err(),
+                    onclick: {
+                        let addresses = addresses.clone();
+                        move |_| {
+                            let mut addresses = addresses.clone();
+                            addresses.push(new_address.read().trim().to_string());
+                            save(addresses);
+                        }
+                    },
+                    "Add"
+                }
+            }
         }
     }
 }
 
+/// The user's phone number, which we store ourselves as Kanidm has no
+/// attribute for it. Edits are staged, to be applied with the user's other
+/// changes.
 #[component]
-fn CreateUserModal(on_close: EventHandler<()>, on_created: EventHandler<()>) -> Element {
-    let mut error_state = use_error();
-    let username = use_signal(String::new);
-    let display_name = use_signal(String::new);
-    let email = use_signal(String::new);
-    let mut creating = use_signal(|| false);
+fn PhoneNumber(
+    user_id: Uuid,
+    user_name: String,
+    saved: Option<String>,
+    pending: Signal<Vec<PendingChange>>,
+) -> Element {
+    let saved = saved.unwrap_or_default();
+    let mut phone_number = use_signal(|| {
+        staged_phone_number(&pending.peek(), user_id).unwrap_or_else(|| saved.clone())
+    });
+    let user_name = use_signal(|| user_name);
 
-    let can_submit = !username.read().is_empty() && !display_name.read().is_empty();
+    let staged = staged_phone_number(&pending.read(), user_id);
+
+    let is_staged = staged.is_some();
+    let current = staged.unwrap_or(saved);
+    let changed = phone_number() != current;
 
     rsx! {
-        div { class: "modal-overlay",
-            onclick: move |_| on_close.call(()),
-            div { class: "modal",
-                onclick: move |e| e.stop_propagation(),
-                div { class: "modal-header",
-                    h2 { class: "modal-title", "Create User" }
-                    button {
-                        class: "modal-close",
-                        onclick: move |_| on_close.call(()),
-                        "×"
-                    }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "phone_number",
+                "Phone "
+                if is_staged {
+                    span { class: "badge badge-warning", "Pending" }
                 }
-                div { class: "modal-body",
-                    UserForm { username, display_name, email }
+            }
+            div { class: "email-row",
+                input {
+                    id: "phone_number",
+                    class: "form-input",
+                    r#type: "tel",
+                    placeholder: "e.g. +15551234567",
+                    value: "{phone_number}",
+                    oninput: move |e| phone_number.set(e.value()),
                 }
-                div { class: "modal-footer",
+                if changed {
                     button {
                         class: "btn btn-secondary",
-                        onclick: move |_| on_close.call(()),
-                        "Cancel"
-                    }
-                    button {
-                        class: "btn btn-primary",
-                        disabled: !can_submit || *creating.read(),
                         onclick: move |_| {
-                            let name = username.read().clone();
-                            let dname = display_name.read().clone();
-                            let mail = email.read().clone();
-                            spawn(async move {
-                                creating.set(true);
-                                match api::create_user(name, dname, mail).await {
-                                    Ok(()) => on_created.call(()),
-                                    Err(e) => error_state.set_server_error(&e),
-                                }
-                                creating.set(false);
-                            });
+                            let number = Some(phone_number()).filter(|p| !p.trim().is_empty());
+                            let change = PendingChange::SetPhoneNumber {
+                                user_id,
+                                user_name: user_name(),
+                                phone_number: number,
+                            };
+                            stage(&mut pending.write(), change);
                         },
-                        if *creating.read() { "Creating..." } else { "Create" }
+                        "Stage"
                     }
                 }
             }
@@ -529,36 +1594,75 @@ fn CreateUserModal(on_close: EventHandler<()>, on_created: EventHandler<()>) ->
     }
 }
 
+/// Compare two users' group memberships side by side, and stage giving one
+/// the groups only the other has.
 #[component]
-fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
-    let mut error_state = use_error();
-    let mut duration_hours = use_signal(|| 24u32);
-    let mut max_uses = use_signal(|| Some(1u8));
-    let mut generating = use_signal(|| false);
-    let mut provision_url = use_signal(|| None::<Url>);
-    let mut copied = use_signal(|| false);
-    let mut groups = use_signal(Vec::<Group>::new);
-    let mut selected_groups = use_signal(HashSet::<Uuid>::new);
+fn CompareUsersModal(
+    users: Vec<Person>,
+    lazy_membership: bool,
+    groups: Vec<Group>,
+    classification: GroupClassification,
+    first: Option<Uuid>,
+    pending: Signal<Vec<PendingChange>>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let capabilities = use_capabilities();
+    let mut a_id = use_signal(|| first.or(users.first().map(|u| u.uuid)));
+    let mut b_id = use_signal(|| None::<Uuid>);
 
-    // Fetch groups on mount
-    use_effect(move || {
-        spawn(async move {
-            if let Ok(mut g) = api::list_groups().await {
-                g.sort_unstable();
-                groups.set(g);
-            }
-        });
-    });
+    let find = |id: Option<Uuid>| {
+        id.and_then(|id| users.iter().find(|u| u.uuid == id))
+            .cloned()
+    };
+    // Without groups in the list, each user's are fetched when chosen.
+    let a_groups = use_resource(move || lazy_groups(lazy_membership, a_id()));
+    let b_groups = use_resource(move || lazy_groups(lazy_membership, b_id()));
+    let a = find(a_id()).map(|u| with_groups(u, &a_groups.read()));
+    let b = find(b_id()).map(|u| with_groups(u, &b_groups.read()));
 
-    // Filter to custom groups only (use memo to track reactivity)
-    let custom_groups = use_memo(move || {
-        groups
-            .read()
+    // Memberships as they'll be once staged changes are applied.
+    let is_member = |user: &Person, group: &Group| {
+        staged_membership(&pending.read(), user.uuid, group.uuid)
+            .unwrap_or_else(|| user.is_member_of(group))
+    };
+    let rows: Vec<(Group, bool, bool)> = match (&a, &b) {
+        (Some(a), Some(b)) => groups
             .iter()
-            .filter(|g| !is_builtin_group(&g.name))
-            .cloned()
-            .collect::<Vec<Group>>()
-    });
+            .map(|g| (g.clone(), is_member(a, g), is_member(b, g)))
+            .filter(|(_, in_a, in_b)| *in_a || *in_b)
+            .collect(),
+        _ => Vec::new(),
+    };
+    // Only custom groups are copied in bulk; built-in ones grant too much
+    // to hand out without looking.
+    let only_a: Vec<Group> = rows
+        .iter()
+        .filter(|(g, in_a, in_b)| *in_a && !*in_b && !classification.is_builtin(&g.name))
+        .map(|(g, ..)| g.clone())
+        .collect();
+    let only_b: Vec<Group> = rows
+        .iter()
+        .filter(|(g, in_a, in_b)| *in_b && !*in_a && !classification.is_builtin(&g.name))
+        .map(|(g, ..)| g.clone())
+        .collect();
+
+    let stage_adds = move |user: Person, groups: Vec<Group>| {
+        let mut pending = pending;
+        let mut pending = pending.write();
+        for group in groups {
+            stage(
+                &mut pending,
+                PendingChange::AddToGroup {
+                    user_id: user.uuid,
+                    user_name: user.name.clone(),
+                    group_id: group.uuid,
+                    group_name: group.name,
+                },
+            );
+        }
+    };
+    let can_stage = capabilities.allows(Capability::ManageGroups);
+    let why_not = capabilities.why_not(Capability::ManageGroups);
 
     rsx! {
         div { class: "modal-overlay",
@@ -566,7 +1670,7 @@ fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
             div { class: "modal",
                 onclick: move |e| e.stop_propagation(),
                 div { class: "modal-header",
-                    h2 { class: "modal-title", "Generate Provision Link" }
+                    h2 { class: "modal-title", "Compare Users" }
                     button {
                         class: "modal-close",
                         onclick: move |_| on_close.call(()),
@@ -574,44 +1678,2198 @@ fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
                     }
                 }
                 div { class: "modal-body",
-                    if let Some(url) = provision_url.read().as_ref() {
-                        {
-                            let url = url.clone();
-                            rsx! {
-                                p { "Share this link with the user to let them create their own account:" }
-                                div { class: "code-block-wrapper",
-                                    div { class: "code-block", "{url}" }
-                                    button {
-                                        class: if *copied.read() { "copy-btn copied" } else { "copy-btn" },
-                                        title: if *copied.read() { "Copied!" } else { "Copy to clipboard" },
-                                        onclick: {
-                                            let url = url.clone();
-                                            move |_| {
-                                                let url = url.clone();
-                                                spawn(async move {
-                                                    let js = format!(
-                                                        r#"navigator.clipboard.writeText("{}")"#,
-                                                        url.to_string().replace("\"", "\\\"")
-                                                    );
-                                                    if eval(&js).recv::<()>().await.is_ok() {
-                                                        copied.set(true);
+                    div { class: "email-row",
+                        for (i, selected) in [a_id(), b_id()].into_iter().enumerate() {
+                            select {
+                                class: "form-input",
+                                value: selected.map(|id| id.to_string()).unwrap_or_default(),
+                                onchange: move |e| {
+                                    let id = e.value().parse().ok();
+                                    if i == 0 { a_id.set(id) } else { b_id.set(id) }
+                                },
+                                option { value: "", "Pick a user" }
+                                for user in users.iter() {
+                                    option { value: "{user.uuid}", "{user.display_name} ({user.name})" }
+                                }
+                            }
+                        }
+                    }
+                    if let (Some(a), Some(b)) = (a.clone(), b.clone()) {
+                        if a.uuid == b.uuid {
+                            p { class: "text-muted", "Pick two different users." }
+                        } else if rows.is_empty() {
+                            p { class: "text-muted", "Neither is in any groups." }
+                        } else {
+                            div { class: "page-header-actions",
+                                button {
+                                    class: "btn btn-secondary",
+                                    disabled: !can_stage || only_a.is_empty(),
+                                    title: why_not.clone(),
+                                    onclick: {
+                                        let (b, only_a) = (b.clone(), only_a.clone());
+                                        move |_| stage_adds(b.clone(), only_a.clone())
+                                    },
+                                    "Give {b.name} {a.name}'s {only_a.len()} missing groups"
+                                }
+                                button {
+                                    class: "btn btn-secondary",
+                                    disabled: !can_stage || only_b.is_empty(),
+                                    title: why_not.clone(),
+                                    onclick: {
+                                        let (a, only_b) = (a.clone(), only_b.clone());
+                                        move |_| stage_adds(a.clone(), only_b.clone())
+                                    },
+                                    "Give {a.name} {b.name}'s {only_b.len()} missing groups"
+                                }
+                            }
+                            div { class: "table-container",
+                                table {
+                                    thead {
+                                        tr {
+                                            th { "Group" }
+                                            th { "{a.name}" }
+                                            th { "{b.name}" }
+                                            th {}
+                                        }
+                                    }
+                                    tbody {
+                                        for (group, in_a, in_b) in rows.iter().cloned() {
+                                            tr { key: "{group.uuid}",
+                                                class: if in_a == in_b { "text-muted" },
+                                                td { "{group.name}" }
+                                                td { if in_a { "✓" } else { "—" } }
+                                                td { if in_b { "✓" } else { "—" } }
+                                                td {
+                                                    if in_a != in_b {
+                                                        button {
+                                                            class: "btn btn-link",
+                                                            disabled: !can_stage,
+                                                            title: why_not.clone(),
+                                                            onclick: {
+                                                                let to = if in_a { b.clone() } else { a.clone() };
+                                                                let group = group.clone();
+                                                                move |_| stage_adds(to.clone(), vec![group.clone()])
+                                                            },
+                                                            if in_a { "Add {b.name}" } else { "Add {a.name}" }
+                                                        }
                                                     }
-                                                });
-                                            }
-                                        },
-                                        if *copied.read() {
-                                            svg {
-                                                width: "16",
-                                                height: "16",
-                                                view_box: "0 0 24 24",
-                                                fill: "none",
-                                                stroke: "currentColor",
-                                                stroke_width: "2",
-                                                stroke_linecap: "round",
-                                                stroke_linejoin: "round",
-                                                polyline { points: "20 6 9 17 4 12" }
+                                                }
                                             }
-                                        } else {
+                                        }
+                                    }
+                                }
+                            }
+                            p { class: "text-muted text-sm",
+                                "Additions are staged, so review and apply them as usual. Built-in groups are only copied one at a time."
+                            }
+                        }
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-primary",
+                        onclick: move |_| on_close.call(()),
+                        "Done"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A user's groups, fetched on their own when the list leaves them out.
+async fn lazy_groups(lazy: bool, user_id: Option<Uuid>) -> Option<(Uuid, Vec<String>)> {
+    let user_id = user_id.filter(|_| lazy)?;
+    let groups = api::get_user_groups(PersonId(user_id)).await.ok()?;
+    Some((user_id, groups))
+}
+
+/// `user`, with the groups fetched for them if these are theirs.
+fn with_groups(mut user: Person, fetched: &Option<Option<(Uuid, Vec<String>)>>) -> Person {
+    if let Some(Some((user_id, groups))) = fetched
+        && *user_id == user.uuid
+    {
+        user.groups = groups.clone();
+    }
+    user
+}
+
+/// One of the user's names. Edits are staged, to be applied with the user's
+/// other changes.
+#[component]
+fn NameField(
+    id: &'static str,
+    label: &'static str,
+    saved: String,
+    staged: Option<String>,
+    on_stage: EventHandler<String>,
+) -> Element {
+    let is_staged = staged.is_some();
+    let current = staged.unwrap_or(saved);
+    let mut name = use_signal(|| current.clone());
+    let changed = name() != current;
+
+    rsx! {
+        div { class: "form-group",
+            label { class: "form-label", r#for: id,
+                "{label} "
+                if is_staged {
+                    span { class: "badge badge-warning", "Pending" }
+                }
+            }
+            div { class: "email-row",
+                input {
+                    id,
+                    class: "form-input",
+                    r#type: "text",
+                    value: "{name}",
+                    oninput: move |e| name.set(e.value()),
+                }
+                if changed {
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_stage.call(name()),
+                        "Stage"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The display name, or with `legal` the legal name, staged for the user, if
+/// any. An empty legal name means it's being removed.
+fn staged_name(changes: &[PendingChange], user_id: Uuid, legal: bool) -> Option<String> {
+    changes.iter().find_map(|c| match c {
+        PendingChange::SetDisplayName {
+            user_id: u,
+            display_name,
+            ..
+        } if *u == user_id && !legal => Some(display_name.clone()),
+        PendingChange::SetLegalName {
+            user_id: u,
+            legal_name,
+            ..
+        } if *u == user_id && legal => Some(legal_name.clone().unwrap_or_default()),
+        _ => None,
+    })
+}
+
+/// The phone number staged for the user, if any. An empty string means it's
+/// being removed.
+fn staged_phone_number(changes: &[PendingChange], user_id: Uuid) -> Option<String> {
+    changes.iter().find_map(|c| match c {
+        PendingChange::SetPhoneNumber {
+            user_id: u,
+            phone_number,
+            ..
+        } if *u == user_id => Some(phone_number.clone().unwrap_or_default()),
+        _ => None,
+    })
+}
+
+/// Shows how the user appears over LDAP, to help debug apps that can't find
+/// them. Hidden if LDAP isn't enabled.
+#[component]
+fn LdapSection(status: Option<LdapStatus>) -> Element {
+    let Some(status) = status else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div { class: "divider" }
+
+        h3 { class: "section-header", "LDAP" }
+        div { class: "form-group",
+            span { class: "form-label", "DN" }
+            div { class: "form-value form-value-mono", "{status.dn}" }
+        }
+        if let Some(gid) = &status.gid_number {
+            div { class: "form-group",
+                span { class: "form-label", "GID number" }
+                div { class: "form-value form-value-mono", "{gid}" }
+            }
+        }
+        if let Some(shell) = &status.login_shell {
+            div { class: "form-group",
+                span { class: "form-label", "Login shell" }
+                div { class: "form-value form-value-mono", "{shell}" }
+            }
+        }
+        if status.missing.is_empty() {
+            p { class: "text-muted text-sm", "POSIX attributes are complete." }
+        } else {
+            div { class: "alert alert-error",
+                "Missing attributes LDAP clients may need:"
+                ul {
+                    for attr in status.missing.iter() {
+                        li { key: "{attr}", "{attr}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An expander showing the user's latest sign-ins to AuthIt!.
+#[component]
+fn LoginHistory(events: Vec<LoginEvent>) -> Element {
+    rsx! {
+        details { class: "form-group",
+            summary { class: "form-label", "AuthIt! sign-ins" }
+            LoginEventsTable { events }
+        }
+    }
+}
+
+/// Whether the user has set up credentials, or is still to use their reset
+/// link.
+#[component]
+fn CredentialStatus(has_credentials: Option<bool>) -> Element {
+    rsx! {
+        div { class: "form-group",
+            span { class: "form-label", "Credentials" }
+            div { class: "form-value",
+                match has_credentials {
+                    Some(true) => "Set up",
+                    Some(false) => "Not set up yet",
+                    None => "Unknown",
+                }
+            }
+        }
+    }
+}
+
+/// An expander showing what the user did, or had done to them, lately.
+#[component]
+fn RecentActivity(activity: Vec<Activity>) -> Element {
+    rsx! {
+        details { class: "form-group",
+            summary { class: "form-label", "Recent activity" }
+            if activity.is_empty() {
+                p { class: "text-muted text-sm", "Nothing recorded." }
+            } else {
+                ul { class: "activity-list",
+                    for a in activity {
+                        li { key: "{a.id}", class: "activity-item",
+                            div { class: "activity-summary",
+                                div { "{a.summary}" }
+                                if let Some(actor) = &a.actor {
+                                    span { class: "text-muted text-sm", "By {actor.name}" }
+                                }
+                                if let Some(ticket) = &a.ticket {
+                                    span { class: "badge", title: "Ticket", "{ticket}" }
+                                }
+                            }
+                            span {
+                                class: "activity-time",
+                                title: format_timestamp(a.at),
+                                {format_relative(a.at)}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn DeleteConfirmModal(
+    user_id: Uuid,
+    user_name: String,
+    deleting: bool,
+    on_close: EventHandler<()>,
+    on_confirm: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut impact = use_signal(|| None::<UserDeletionImpact>);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_user_deletion_impact(user_id).await {
+                Ok(i) => impact.set(Some(i)),
+                Err(e) => {
+                    error_state.set_server_error(&e);
+                    on_close.call(());
+                }
+            }
+        });
+    });
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !deleting { on_close.call(()) },
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Delete User" }
+                    if !deleting {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    p { "Are you sure you want to delete " strong { "{user_name}" } "?" }
+                    match impact.read().as_ref() {
+                        None => rsx! {
+                            div { class: "loading", "Checking what will be lost..." }
+                        },
+                        Some(impact) => rsx! {
+                            DeletionImpact { impact: impact.clone() }
+                        },
+                    }
+                    p { class: "text-muted", "This action cannot be undone." }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: deleting,
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-danger",
+                        disabled: deleting || impact.read().is_none(),
+                        onclick: move |_| on_confirm.call(()),
+                        if deleting { "Deleting..." } else { "Delete" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What deleting a user will lose.
+#[component]
+fn DeletionImpact(impact: UserDeletionImpact) -> Element {
+    rsx! {
+        if let Some(at) = impact.provisioned_at {
+            p { class: "text-muted text-sm",
+                "They created their account from a provision link on {format_timestamp(at)}."
+            }
+        }
+        if impact.groups.is_empty() {
+            p { "They aren't in any groups." }
+        } else {
+            p { "They'll be removed from {impact.groups.len()} group(s):" }
+            ul {
+                for group in &impact.groups {
+                    li { "{group}" }
+                }
+            }
+        }
+        if !impact.owned_groups.is_empty() {
+            div { class: "alert alert-warning",
+                p { "These groups will be left without them as an owner:" }
+                ul {
+                    for group in &impact.owned_groups {
+                        li { "{group}" }
+                    }
+                }
+            }
+        }
+        match impact.oauth2_sessions {
+            Some(0) => rsx! {},
+            Some(n) => rsx! {
+                p { "Their {n} OAuth2 session(s) in Kanidm will end." }
+            },
+            None => rsx! {
+                p { class: "text-muted text-sm", "Kanidm didn't say whether they have OAuth2 sessions." }
+            },
+        }
+        if !impact.records.is_empty() {
+            p { "AuthIt! will delete:" }
+            ul {
+                for record in &impact.records {
+                    li { "{record}" }
+                }
+            }
+        }
+        if !impact.mentions.is_empty() {
+            p {
+                if impact.anonymized {
+                    "These records mention them, and will be kept with their name replaced:"
+                } else {
+                    "These records mention them, and will be kept:"
+                }
+            }
+            ul {
+                for mention in &impact.mentions {
+                    li { "{mention}" }
+                }
+            }
+        }
+    }
+}
+
+/// Confirm making a reset link, when a deep link asks for one.
+#[component]
+fn ResetConfirmModal(
+    user_name: String,
+    generating: bool,
+    on_close: EventHandler<()>,
+    on_confirm: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !generating { on_close.call(()) },
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Reset Credentials" }
+                    if !generating {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    p { "Generate a credential reset link for " strong { "{user_name}" } "?" }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: generating,
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: generating,
+                        onclick: move |_| on_confirm.call(()),
+                        if generating { "Generating..." } else { "Generate Reset Link" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Create a group and add the user to it, without leaving their page.
+#[component]
+fn NewGroupForm(
+    user_id: Uuid,
+    templates: Vec<Group>,
+    on_cancel: EventHandler<()>,
+    on_created: EventHandler<Group>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut name = use_signal(String::new);
+    let mut description = use_signal(String::new);
+    let mut template = use_signal(|| None::<Uuid>);
+    let mut creating = use_signal(|| false);
+    let idempotency_key = use_hook(Uuid::new_v4);
+
+    rsx! {
+        div { class: "form-group",
+            label { class: "form-label", r#for: "new_group_name", "Group name" }
+            input {
+                id: "new_group_name",
+                class: "form-input",
+                value: "{name}",
+                oninput: move |e| name.set(e.value()),
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "new_group_description", "Description (optional)" }
+            input {
+                id: "new_group_description",
+                class: "form-input",
+                value: "{description}",
+                oninput: move |e| description.set(e.value()),
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "new_group_template", "Start with the members of" }
+            select {
+                id: "new_group_template",
+                class: "form-input",
+                value: template().map(|t| t.to_string()).unwrap_or_default(),
+                onchange: move |e| template.set(e.value().parse().ok()),
+                option { value: "", "Nobody else" }
+                for group in templates {
+                    option { value: "{group.uuid}", "{group.name}" }
+                }
+            }
+        }
+        div { class: "page-header-actions",
+            button {
+                class: "btn btn-secondary",
+                disabled: creating(),
+                onclick: move |_| on_cancel.call(()),
+                "Cancel"
+            }
+            button {
+                class: "btn btn-primary",
+                disabled: creating() || name.read().trim().is_empty(),
+                onclick: move |_| {
+                    let description = Some(description()).filter(|d| !d.trim().is_empty());
+                    spawn(async move {
+                        creating.set(true);
+                        match api::create_group_for_user(
+                            PersonId(user_id),
+                            name(),
+                            description,
+                            template().map(GroupId),
+                            Some(idempotency_key),
+                        )
+                        .await
+                        {
+                            Ok(group) => on_created.call(group),
+                            Err(e) => error_state.set_server_error(&e),
+                        }
+                        creating.set(false);
+                    });
+                },
+                if creating() { "Creating..." } else { "Create and Add" }
+            }
+        }
+    }
+}
+
+/// Edit the user list's filter, and save it as a named view.
+#[component]
+fn UserFilterPanel(
+    filter: Signal<UserFilter>,
+    tags: Vec<Tag>,
+    groups: Vec<Group>,
+    can_save: bool,
+    on_save: EventHandler<String>,
+) -> Element {
+    let mut name = use_signal(String::new);
+    let current = filter();
+    let selected_groups: HashSet<Uuid> = current.groups.iter().copied().collect();
+
+    rsx! {
+        div { class: "card-body",
+            if !tags.is_empty() {
+                div { class: "form-group",
+                    span { class: "form-label", "Tagged" }
+                    div { class: "checkbox-group",
+                        for tag in tags {
+                            label { class: "checkbox-label", key: "{tag.id}",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: current.tags.contains(&tag.id),
+                                    onchange: move |e| {
+                                        filter.with_mut(|f| {
+                                            f.tags.retain(|t| *t != tag.id);
+                                            if e.checked() {
+                                                f.tags.push(tag.id);
+                                            }
+                                        });
+                                    },
+                                }
+                                span { "{tag.name}" }
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "form-group",
+                span { class: "form-label", "In groups" }
+                GroupPicker {
+                    groups,
+                    selected: selected_groups,
+                    on_toggle: move |group_id: Uuid| {
+                        filter.with_mut(|f| {
+                            if f.groups.contains(&group_id) {
+                                f.groups.retain(|g| *g != group_id);
+                            } else {
+                                f.groups.push(group_id);
+                            }
+                        });
+                    },
+                }
+            }
+            div { class: "form-group",
+                label { class: "form-label", r#for: "filter_credentials", "Credentials" }
+                select {
+                    id: "filter_credentials",
+                    class: "form-input",
+                    value: match current.has_credentials {
+                        None => "",
+                        Some(true) => "set",
+                        Some(false) => "unset",
+                    },
+                    onchange: move |e| {
+                        let value = match e.value().as_str() {
+                            "set" => Some(true),
+                            "unset" => Some(false),
+                            _ => None,
+                        };
+                        filter.with_mut(|f| f.has_credentials = value);
+                    },
+                    option { value: "", "Any" }
+                    option { value: "set", "Set up" }
+                    option { value: "unset", "Not set up" }
+                }
+            }
+            div { class: "form-group",
+                label { class: "form-label", r#for: "filter_created_after", "Created after" }
+                input {
+                    id: "filter_created_after",
+                    class: "form-input",
+                    r#type: "datetime-local",
+                    value: current.created_after.map(to_datetime_input).unwrap_or_default(),
+                    oninput: move |e| filter.with_mut(|f| f.created_after = parse_datetime_input(&e.value())),
+                }
+            }
+            div { class: "form-group",
+                label { class: "form-label", r#for: "filter_created_before", "Created before" }
+                input {
+                    id: "filter_created_before",
+                    class: "form-input",
+                    r#type: "datetime-local",
+                    value: current.created_before.map(to_datetime_input).unwrap_or_default(),
+                    oninput: move |e| filter.with_mut(|f| f.created_before = parse_datetime_input(&e.value())),
+                }
+            }
+            div { class: "email-row",
+                input {
+                    class: "form-input",
+                    placeholder: "View name",
+                    value: "{name}",
+                    oninput: move |e| name.set(e.value()),
+                }
+                button {
+                    class: "btn btn-secondary",
+                    disabled: !can_save || name.read().trim().is_empty() || current.is_empty(),
+                    onclick: move |_| {
+                        on_save.call(name.read().trim().to_string());
+                        name.set(String::new());
+                    },
+                    "Save View"
+                }
+            }
+        }
+    }
+}
+
+/// Tick the local tags a user has. Changes save straight away.
+#[component]
+fn UserTags(
+    user_id: Uuid,
+    tags: Vec<Tag>,
+    selected: Vec<Uuid>,
+    on_change: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut saving = use_signal(|| false);
+
+    rsx! {
+        div { class: "form-group",
+            span { class: "form-label", "Tags" }
+            div { class: "checkbox-group",
+                for tag in tags {
+                    {
+                        let is_checked = selected.contains(&tag.id);
+                        let selected = selected.clone();
+                        rsx! {
+                            label { class: "checkbox-label", key: "{tag.id}",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: is_checked,
+                                    disabled: saving(),
+                                    onchange: move |_| {
+                                        let mut tag_ids = selected.clone();
+                                        if is_checked {
+                                            tag_ids.retain(|id| *id != tag.id);
+                                        } else {
+                                            tag_ids.push(tag.id);
+                                        }
+                                        spawn(async move {
+                                            saving.set(true);
+                                            match api::set_user_tags(user_id, tag_ids).await {
+                                                Ok(()) => on_change.call(()),
+                                                Err(e) => error_state.set_server_error(&e),
+                                            }
+                                            saving.set(false);
+                                        });
+                                    },
+                                }
+                                span { "{tag.name}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Which of a user's groups they own, and so can invite people to.
+#[component]
+fn GroupOwnership(user_id: Uuid, groups: Vec<Group>, owned: Vec<Uuid>) -> Element {
+    let mut error_state = use_error();
+    let mut owned = use_signal(|| owned);
+    let mut saving = use_signal(|| false);
+
+    let owned_ids = owned();
+    if groups.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "form-group",
+            span { class: "form-label", "Owns" }
+            p { class: "text-muted text-sm",
+                "Owners can make invite links that add people to their groups, without being admins."
+            }
+            div { class: "checkbox-group",
+                for group in groups {
+                    {
+                        let group_id = group.uuid;
+                        let is_owner = owned_ids.contains(&group_id);
+                        rsx! {
+                            label { class: "checkbox-label", key: "{group_id}",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: is_owner,
+                                    disabled: saving(),
+                                    onchange: move |_| {
+                                        spawn(async move {
+                                            saving.set(true);
+                                            match api::set_group_owner(group_id, user_id, !is_owner).await {
+                                                Ok(()) => {
+                                                    let mut ids = owned.write();
+                                                    if is_owner {
+                                                        ids.retain(|id| *id != group_id);
+                                                    } else {
+                                                        ids.push(group_id);
+                                                    }
+                                                }
+                                                Err(e) => error_state.set_server_error(&e),
+                                            }
+                                            saving.set(false);
+                                        });
+                                    },
+                                }
+                                span { "{group.name}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Group membership changes to make later, like granting access from Monday
+/// morning, with a form to schedule another.
+#[component]
+fn ScheduledChanges(
+    user_id: Uuid,
+    groups: Vec<Group>,
+    scheduled: Vec<ScheduledMembershipChange>,
+) -> Element {
+    let mut error_state = use_error();
+    let capabilities = use_capabilities();
+    let mut changes = use_signal(|| scheduled);
+    let mut add = use_signal(|| true);
+    let mut group_id = use_signal(|| None::<Uuid>);
+    let mut run_at = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+    let locked = !capabilities.allows(Capability::ManageGroups);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::list_user_scheduled_changes(user_id).await {
+                Ok(c) => changes.set(c),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    let when = parse_datetime_input(&run_at.read());
+    let schedule = move |_| {
+        let (Some(group_id), Some(when)) = (group_id(), when) else {
+            return;
+        };
+        spawn(async move {
+            saving.set(true);
+            match api::schedule_membership_change(PersonId(user_id), GroupId(group_id), add(), when)
+                .await
+            {
+                Ok(_) => {
+                    run_at.set(String::new());
+                    refresh();
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            saving.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "form-group",
+            span { class: "form-label", "Scheduled Changes" }
+            for change in changes() {
+                ScheduledChangeRow {
+                    key: "{change.id}",
+                    change,
+                    show_user: false,
+                    locked,
+                    on_cancelled: move |_| refresh(),
+                }
+            }
+            div { class: "page-header-actions",
+                select {
+                    class: "form-input",
+                    aria_label: "Add or remove",
+                    disabled: locked,
+                    onchange: move |e| add.set(e.value() == "add"),
+                    option { value: "add", selected: add(), "Add to" }
+                    option { value: "remove", selected: !add(), "Remove from" }
+                }
+                select {
+                    class: "form-input",
+                    aria_label: "Group",
+                    disabled: locked,
+                    onchange: move |e| group_id.set(e.value().parse().ok()),
+                    option { value: "", "Pick a group" }
+                    for group in groups {
+                        option {
+                            key: "{group.uuid}",
+                            value: "{group.uuid}",
+                            selected: group_id() == Some(group.uuid),
+                            "{group.name}"
+                        }
+                    }
+                }
+                input {
+                    class: "form-input",
+                    r#type: "datetime-local",
+                    aria_label: "When",
+                    disabled: locked,
+                    value: "{run_at}",
+                    oninput: move |e| run_at.set(e.value()),
+                }
+                button {
+                    class: "btn btn-secondary",
+                    disabled: locked || saving() || group_id().is_none() || when.is_none(),
+                    title: capabilities.why_not(Capability::ManageGroups),
+                    onclick: schedule,
+                    if saving() { "Scheduling..." } else { "Schedule" }
+                }
+            }
+        }
+    }
+}
+
+/// The membership changes waiting to be made to a group.
+#[component]
+fn GroupScheduledChanges(group_id: Uuid) -> Element {
+    let mut error_state = use_error();
+    let capabilities = use_capabilities();
+    let mut changes = use_signal(Vec::<ScheduledMembershipChange>::new);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::list_group_scheduled_changes(group_id).await {
+                Ok(c) => changes.set(c),
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    if changes.read().is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "form-group",
+            span { class: "form-label", "Scheduled Changes" }
+            for change in changes() {
+                ScheduledChangeRow {
+                    key: "{change.id}",
+                    change,
+                    show_user: true,
+                    locked: !capabilities.allows(Capability::ManageGroups),
+                    on_cancelled: move |_| refresh(),
+                }
+            }
+        }
+    }
+}
+
+/// A pending membership change, naming the group it's to, or with
+/// `show_user` the user it's for.
+#[component]
+fn ScheduledChangeRow(
+    change: ScheduledMembershipChange,
+    show_user: bool,
+    locked: bool,
+    on_cancelled: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut cancelling = use_signal(|| false);
+    let id = change.id;
+    let description = match (change.action, show_user) {
+        (MembershipAction::Added, false) => format!("Add to {}", change.group_name),
+        (MembershipAction::Removed, false) => format!("Remove from {}", change.group_name),
+        (MembershipAction::Added, true) => format!("Add {}", change.user_name),
+        (MembershipAction::Removed, true) => format!("Remove {}", change.user_name),
+    };
+
+    rsx! {
+        div { class: "email-row",
+            span { class: "badge badge-warning", "Pending" }
+            span { class: "form-value", "{description}" }
+            span {
+                class: "text-muted text-sm",
+                title: "Scheduled by {change.created_by}",
+                "{format_timestamp(change.run_at)}"
+            }
+            button {
+                class: "btn btn-link",
+                disabled: locked || cancelling(),
+                onclick: move |_| {
+                    spawn(async move {
+                        cancelling.set(true);
+                        match api::cancel_scheduled_membership_change(id).await {
+                            Ok(()) => on_cancelled.call(()),
+                            Err(e) => error_state.set_server_error(&e),
+                        }
+                        cancelling.set(false);
+                    });
+                },
+                "Cancel"
+            }
+        }
+    }
+}
+
+/// Delete every user in a list, like everyone with a tag, as a background
+/// job.
+#[component]
+fn BulkDeleteModal(
+    users: Vec<Person>,
+    on_close: EventHandler<()>,
+    on_finished: EventHandler<()>,
+) -> Element {
+    let job = use_job(move |_| on_finished.call(()));
+    let user_ids: Vec<Uuid> = users.iter().map(|u| u.uuid).collect();
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Delete Users" }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    if job.is_started() {
+                        JobStatus { job }
+                    } else {
+                        p { "Are you sure you want to delete these {users.len()} users?" }
+                        ul {
+                            for user in users.iter() {
+                                li { key: "{user.uuid}", "{user.display_name} ({user.name})" }
+                            }
+                        }
+                        p { class: "text-muted", "This action cannot be undone." }
+                    }
+                }
+                div { class: "modal-footer",
+                    if job.is_started() {
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| on_close.call(()),
+                            "Done"
+                        }
+                    } else {
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| on_close.call(()),
+                            "Cancel"
+                        }
+                        button {
+                            class: "btn btn-danger",
+                            disabled: job.is_starting(),
+                            onclick: move |_| job.start(api::start_delete_users(user_ids.clone())),
+                            if job.is_starting() { "Starting..." } else { "Delete" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Make credential reset links for every user in a list, like after a
+/// security incident, as a background job. The links are in its report.
+#[component]
+fn BulkResetModal(users: Vec<Person>, on_close: EventHandler<()>) -> Element {
+    let job = use_job(|_| {});
+    let mut email = use_signal(|| false);
+    let user_ids: Vec<Uuid> = users.iter().map(|u| u.uuid).collect();
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Reset Credentials" }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    if job.is_started() {
+                        JobStatus { job }
+                        p { class: "text-muted text-sm",
+                            "Download the report for each user's link. Anyone with a link can set that user's credentials, so keep it safe."
+                        }
+                    } else {
+                        p { "Make credential reset links for these {users.len()} users?" }
+                        ul {
+                            for user in users.iter() {
+                                li { key: "{user.uuid}", "{user.display_name} ({user.name})" }
+                            }
+                        }
+                        label { class: "checkbox-label",
+                            input {
+                                r#type: "checkbox",
+                                checked: email(),
+                                onchange: move |e| email.set(e.checked()),
+                            }
+                            " Email each link to its user's primary address"
+                        }
+                    }
+                }
+                div { class: "modal-footer",
+                    if job.is_started() {
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| on_close.call(()),
+                            "Done"
+                        }
+                    } else {
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| on_close.call(()),
+                            "Cancel"
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: job.is_starting(),
+                            onclick: move |_| job.start(api::start_reset_credentials(user_ids.clone(), email())),
+                            if job.is_starting() { "Starting..." } else { "Make Links" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Start a campaign asking every user in a list to change their credentials
+/// by a deadline.
+#[component]
+fn StartRotationModal(users: Vec<Person>, on_close: EventHandler<()>) -> Element {
+    let mut error_state = use_error();
+    let mut name = use_signal(String::new);
+    let mut deadline = use_signal(String::new);
+    let mut lock = use_signal(|| false);
+    let mut starting = use_signal(|| false);
+    let user_ids: Vec<Uuid> = users.iter().map(|u| u.uuid).collect();
+
+    let deadline_at = parse_datetime_input(&deadline.read());
+    let can_start = !name.read().trim().is_empty() && deadline_at.is_some() && !starting();
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Rotate Credentials" }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    p { "Ask these {users.len()} users to change their credentials? They'll be emailed now, and reminded until they do." }
+                    ul {
+                        for user in users.iter() {
+                            li { key: "{user.uuid}", "{user.display_name} ({user.name})" }
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "rotation_name", "Name" }
+                        input {
+                            id: "rotation_name",
+                            class: "form-input",
+                            placeholder: "e.g. Laptop theft, March",
+                            value: "{name}",
+                            oninput: move |e| name.set(e.value()),
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "form-label", r#for: "rotation_deadline", "Deadline" }
+                        input {
+                            id: "rotation_deadline",
+                            class: "form-input",
+                            r#type: "datetime-local",
+                            value: "{deadline}",
+                            oninput: move |e| deadline.set(e.value()),
+                        }
+                    }
+                    label { class: "checkbox-label",
+                        input {
+                            r#type: "checkbox",
+                            checked: lock(),
+                            onchange: move |e| lock.set(e.checked()),
+                        }
+                        " Lock the accounts of anyone who hasn't by the deadline"
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: !can_start,
+                        onclick: move |_| {
+                            let Some(deadline_at) = deadline_at else {
+                                return;
+                            };
+                            let user_ids = user_ids.clone();
+                            spawn(async move {
+                                starting.set(true);
+                                match api::start_rotation_campaign(name(), user_ids, deadline_at, lock())
+                                    .await
+                                {
+                                    Ok(_) => {
+                                        navigator().push(Route::Rotations {});
+                                    }
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                starting.set(false);
+                            });
+                        },
+                        if starting() { "Starting..." } else { "Start" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Confirm deleting a group, after showing who's in it and what in AuthIt!
+/// still uses it.
+#[component]
+fn DeleteGroupModal(
+    group_id: Uuid,
+    on_close: EventHandler<()>,
+    on_deleted: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut impact = use_signal(|| None::<GroupDeletionImpact>);
+    let mut force = use_signal(|| false);
+    let mut deleting = use_signal(|| false);
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_group_deletion_impact(group_id).await {
+                Ok(i) => impact.set(Some(i)),
+                Err(e) => {
+                    error_state.set_server_error(&e);
+                    on_close.call(());
+                }
+            }
+        });
+    });
+
+    let can_delete = impact
+        .read()
+        .as_ref()
+        .is_some_and(|i| i.blocked.is_none() && (force() || !i.needs_force()));
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !deleting() { on_close.call(()) },
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Delete Group" }
+                    if !deleting() {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    match impact.read().as_ref() {
+                        None => rsx! {
+                            div { class: "loading", "Checking what uses this group..." }
+                        },
+                        Some(impact) => rsx! {
+                            p {
+                                "Delete "
+                                strong { "{impact.group.name}" }
+                                "? It has {impact.member_count} direct member(s), who will lose anything it grants."
+                            }
+                            if let Some(reason) = &impact.blocked {
+                                div { class: "alert alert-error", "This group can't be deleted. {reason}" }
+                            } else if impact.needs_force() {
+                                div { class: "alert alert-warning",
+                                    p { "AuthIt! still uses this group:" }
+                                    ul {
+                                        for reference in &impact.references {
+                                            li { "{reference}" }
+                                        }
+                                    }
+                                }
+                                label { class: "checkbox-label",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: force(),
+                                        onchange: move |e| force.set(e.checked()),
+                                    }
+                                    "Delete it anyway"
+                                }
+                            }
+                            p { class: "text-muted", "This action cannot be undone." }
+                            RawEntry { kind: EntryKind::Group, id: group_id }
+                        },
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: deleting(),
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-danger",
+                        disabled: deleting() || !can_delete,
+                        onclick: move |_| {
+                            spawn(async move {
+                                deleting.set(true);
+                                match api::delete_group(group_id, force()).await {
+                                    Ok(()) => on_deleted.call(()),
+                                    Err(e) => error_state.set_server_error(&e),
+                                }
+                                deleting.set(false);
+                            });
+                        },
+                        if deleting() { "Deleting..." } else { "Delete" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Set who Kanidm lets manage a group, so it can be delegated without
+/// making anyone a Kanidm admin.
+#[component]
+fn GroupManagerModal(group: Group, on_close: EventHandler<()>) -> Element {
+    let mut error_state = use_error();
+    let mut manager = use_signal(|| None::<String>);
+    let mut saving = use_signal(|| false);
+    let group_id = group.uuid;
+
+    use_effect(move || {
+        spawn(async move {
+            match api::get_group_managed_by(group_id).await {
+                Ok(m) => manager.set(Some(m.unwrap_or_default())),
+                Err(e) => {
+                    error_state.set_server_error(&e);
+                    on_close.call(());
+                }
+            }
+        });
+    });
+
+    let save = move |_| {
+        let value = manager().unwrap_or_default();
+        spawn(async move {
+            saving.set(true);
+            let value = Some(value).filter(|v| !v.trim().is_empty());
+            match api::set_group_managed_by(group_id, value).await {
+                Ok(()) => on_close.call(()),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            saving.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !saving() { on_close.call(()) },
+            div { class: "modal modal-sm",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Manage {group.name}" }
+                    if !saving() {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    match manager() {
+                        None => rsx! {
+                            div { class: "loading", "Loading..." }
+                        },
+                        Some(value) => rsx! {
+                            div { class: "form-group",
+                                label { class: "form-label", "Managed by" }
+                                input {
+                                    class: "form-input",
+                                    r#type: "text",
+                                    placeholder: "A group or service account",
+                                    value: "{value}",
+                                    disabled: saving(),
+                                    oninput: move |e| manager.set(Some(e.value())),
+                                }
+                                p { class: "text-muted text-sm",
+                                    "Kanidm lets its members change this group, including who is in it. Leave it empty for only Kanidm's admins to."
+                                }
+                            }
+                        },
+                    }
+                    JoinLinkForm { group_id }
+                    GroupWebhookForm { group_id }
+                    GroupReviewForm { group_id }
+                    GroupScheduledChanges { group_id }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: saving(),
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: saving() || manager().is_none(),
+                        onclick: save,
+                        if saving() { "Saving..." } else { "Save" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Make a link that adds people who already have an account to a group when
+/// they sign in and follow it.
+#[component]
+fn JoinLinkForm(group_id: Uuid) -> Element {
+    let mut error_state = use_error();
+    let mut duration_hours = use_signal(|| 72u32);
+    let mut allowed_domains = use_signal(String::new);
+    let mut creating = use_signal(|| false);
+    let mut join_url = use_signal(|| None::<Url>);
+    let mut copied = use_signal(|| false);
+
+    rsx! {
+        h3 { class: "section-header", "Join Link" }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "join_duration", "Link expires in" }
+            select {
+                id: "join_duration",
+                class: "form-input",
+                value: "{duration_hours}",
+                onchange: move |e| {
+                    if let Ok(v) = e.value().parse() {
+                        duration_hours.set(v);
+                    }
+                },
+                option { value: "24", "24 hours" }
+                option { value: "72", "3 days" }
+                option { value: "168", "7 days" }
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "join_domains", "Only for email domains" }
+            input {
+                id: "join_domains",
+                class: "form-input",
+                r#type: "text",
+                placeholder: "example.com, *.example.org",
+                value: "{allowed_domains}",
+                oninput: move |e| allowed_domains.set(e.value()),
+            }
+            p { class: "text-muted text-sm",
+                "Anyone who signs in and follows the link is added. Leave this empty to allow everyone."
+            }
+        }
+        button {
+            class: "btn btn-secondary",
+            disabled: creating(),
+            onclick: move |_| {
+                spawn(async move {
+                    creating.set(true);
+                    match api::create_group_join_link(group_id, duration_hours(), allowed_domains())
+                        .await
+                    {
+                        Ok(url) => {
+                            join_url.set(Some(url));
+                            copied.set(false);
+                        }
+                        Err(e) => error_state.set_server_error(&e),
+                    }
+                    creating.set(false);
+                });
+            },
+            if creating() { "Creating..." } else { "Create Join Link" }
+        }
+        if let Some(url) = join_url() {
+            div { class: "code-block-wrapper",
+                div { class: "code-block",
+                    Masked { kind: Sensitive::Token, text: url.clone() }
+                }
+                button {
+                    class: if copied() { "copy-btn copied" } else { "copy-btn" },
+                    onclick: move |_| {
+                        let js = format!(r#"navigator.clipboard.writeText("{url}")"#);
+                        spawn(async move {
+                            if eval(&js).recv::<()>().await.is_ok() {
+                                copied.set(true);
+                            }
+                        });
+                    },
+                    if copied() { "Copied!" } else { "Copy" }
+                }
+            }
+        }
+    }
+}
+
+/// When the group should be reviewed and when it expires, for ad-hoc groups
+/// that shouldn't outlive what they were made for.
+#[component]
+fn GroupReviewForm(group_id: Uuid) -> Element {
+    let mut error_state = use_error();
+    let mut review = use_signal(|| None::<Option<GroupReview>>);
+    let mut review_by = use_signal(String::new);
+    let mut expires_on = use_signal(String::new);
+    let mut lock = use_signal(|| false);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_group_review(group_id).await {
+                Ok(r) => {
+                    let date = |d: Option<Date>| d.map(|d| d.to_string()).unwrap_or_default();
+                    review_by.set(date(r.as_ref().and_then(|r| r.review_by)));
+                    expires_on.set(date(r.as_ref().and_then(|r| r.expires_on)));
+                    lock.set(r.as_ref().is_some_and(|r| r.lock_when_overdue));
+                    review.set(Some(r));
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+        });
+    };
+
+    use_effect(move || refresh());
+
+    let Some(current) = review() else {
+        return rsx! {};
+    };
+    let today = Timestamp::now().to_zoned(TimeZone::UTC).date();
+    let overdue = current.as_ref().and_then(|r| r.overdue(today));
+
+    let save = move |_| {
+        let (Ok(review_by), Ok(expires_on)) = (parse_date(&review_by()), parse_date(&expires_on()))
+        else {
+            return;
+        };
+        spawn(async move {
+            saving.set(true);
+            match api::set_group_review(group_id, review_by, expires_on, lock()).await {
+                Ok(()) => {
+                    saved.set(true);
+                    refresh();
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            saving.set(false);
+        });
+    };
+
+    let reviewed = move |_| {
+        let Ok(next) = parse_date(&review_by()) else {
+            return;
+        };
+        spawn(async move {
+            saving.set(true);
+            match api::mark_group_reviewed(group_id, next).await {
+                Ok(()) => {
+                    saved.set(true);
+                    refresh();
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            saving.set(false);
+        });
+    };
+
+    rsx! {
+        h3 { class: "section-header", "Review" }
+        if let Some(reason) = &overdue {
+            div { class: "alert alert-warning", "This group {reason}." }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "review_by", "Review by" }
+            input {
+                id: "review_by",
+                class: "form-input",
+                r#type: "date",
+                value: "{review_by}",
+                oninput: move |e| {
+                    review_by.set(e.value());
+                    saved.set(false);
+                },
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "expires_on", "Expires on" }
+            input {
+                id: "expires_on",
+                class: "form-input",
+                r#type: "date",
+                value: "{expires_on}",
+                oninput: move |e| {
+                    expires_on.set(e.value());
+                    saved.set(false);
+                },
+            }
+            p { class: "text-muted text-sm",
+                "Admins are notified and it's listed on the Dashboard once either date passes."
+            }
+        }
+        label { class: "checkbox-label",
+            input {
+                r#type: "checkbox",
+                checked: lock(),
+                onchange: move |e| {
+                    lock.set(e.checked());
+                    saved.set(false);
+                },
+            }
+            span { "Don't let anyone be added while it's overdue" }
+        }
+        div { class: "page-header-actions",
+            button {
+                class: "btn btn-secondary",
+                disabled: saving(),
+                onclick: save,
+                if saving() { "Saving..." } else { "Save Dates" }
+            }
+            if current.is_some() {
+                button {
+                    class: "btn btn-link",
+                    disabled: saving(),
+                    title: "Record that it's still needed, with the review date above as the next one",
+                    onclick: reviewed,
+                    "Mark Reviewed"
+                }
+            }
+        }
+        if saved() {
+            p { class: "text-muted text-sm", "Saved." }
+        }
+        if let Some(r) = &current
+            && let (Some(at), Some(by)) = (r.reviewed_at, &r.reviewed_by)
+        {
+            p { class: "text-muted text-sm",
+                "Last reviewed by {by}, "
+                {format_timestamp(at)}
+                "."
+            }
+        }
+    }
+}
+
+/// A date input's value, which is empty when it's not set.
+fn parse_date(value: &str) -> Result<Option<Date>, jiff::Error> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    value.parse().map(Some)
+}
+
+/// A webhook the group calls when people join or leave it, so a downstream
+/// system can give them access, with its latest deliveries.
+#[component]
+fn GroupWebhookForm(group_id: Uuid) -> Element {
+    let mut error_state = use_error();
+    let mut loaded = use_signal(|| false);
+    let mut configured = use_signal(|| false);
+    let mut url = use_signal(String::new);
+    let mut secret = use_signal(String::new);
+    let mut on_add = use_signal(|| true);
+    let mut on_remove = use_signal(|| true);
+    let mut deliveries = use_signal(Vec::<WebhookDelivery>::new);
+    let mut saving = use_signal(|| false);
+    let mut saved = use_signal(|| false);
+
+    let refresh = move || {
+        spawn(async move {
+            match api::get_group_webhook(group_id).await {
+                Ok(Some(webhook)) => {
+                    configured.set(true);
+                    url.set(webhook.url);
+                    on_add.set(webhook.on_add);
+                    on_remove.set(webhook.on_remove);
+                }
+                Ok(None) => configured.set(false),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            match api::list_webhook_deliveries(group_id).await {
+                Ok(d) => deliveries.set(d),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            loaded.set(true);
+        });
+    };
+
+    use_effect(move || refresh());
+
+    if !loaded() {
+        return rsx! {};
+    }
+
+    rsx! {
+        h3 { class: "section-header", "Provisioning Webhook" }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "webhook_url", "URL" }
+            input {
+                id: "webhook_url",
+                class: "form-input",
+                r#type: "url",
+                placeholder: "https://gitlab-sync.example.com/authit",
+                value: "{url}",
+                oninput: move |e| {
+                    url.set(e.value());
+                    saved.set(false);
+                },
+            }
+        }
+        div { class: "form-group",
+            label { class: "form-label", r#for: "webhook_secret", "Signing secret" }
+            input {
+                id: "webhook_secret",
+                class: "form-input",
+                r#type: "password",
+                placeholder: if configured() { "Unchanged" } else { "" },
+                value: "{secret}",
+                oninput: move |e| {
+                    secret.set(e.value());
+                    saved.set(false);
+                },
+            }
+            p { class: "text-muted text-sm",
+                "Each call is a JSON POST, signed with this in the X-AuthIt-Signature header. Failed calls are retried for a few hours."
+            }
+        }
+        label { class: "checkbox-label",
+            input {
+                r#type: "checkbox",
+                checked: on_add(),
+                onchange: move |e| {
+                    on_add.set(e.checked());
+                    saved.set(false);
+                },
+            }
+            span { "When someone is added" }
+        }
+        label { class: "checkbox-label",
+            input {
+                r#type: "checkbox",
+                checked: on_remove(),
+                onchange: move |e| {
+                    on_remove.set(e.checked());
+                    saved.set(false);
+                },
+            }
+            span { "When someone is removed" }
+        }
+        div { class: "page-header-actions",
+            button {
+                class: "btn btn-secondary",
+                disabled: saving() || url.read().trim().is_empty(),
+                onclick: move |_| {
+                    spawn(async move {
+                        saving.set(true);
+                        match api::set_group_webhook(group_id, url(), secret(), on_add(), on_remove())
+                            .await
+                        {
+                            Ok(()) => {
+                                secret.set(String::new());
+                                saved.set(true);
+                                refresh();
+                            }
+                            Err(e) => error_state.set_server_error(&e),
+                        }
+                        saving.set(false);
+                    });
+                },
+                if saving() { "Saving..." } else { "Save Webhook" }
+            }
+            if configured() {
+                button {
+                    class: "btn btn-link",
+                    disabled: saving(),
+                    onclick: move |_| {
+                        spawn(async move {
+                            match api::remove_group_webhook(group_id).await {
+                                Ok(()) => {
+                                    url.set(String::new());
+                                    saved.set(false);
+                                    refresh();
+                                }
+                                Err(e) => error_state.set_server_error(&e),
+                            }
+                        });
+                    },
+                    "Remove"
+                }
+            }
+        }
+        if saved() {
+            p { class: "text-muted text-sm", "Saved." }
+        }
+        if !deliveries().is_empty() {
+            div { class: "table-container",
+                table {
+                    thead {
+                        tr {
+                            th { "User" }
+                            th { "Change" }
+                            th { "Status" }
+                        }
+                    }
+                    tbody {
+                        for delivery in deliveries() {
+                            tr { key: "{delivery.id}",
+                                td { "{delivery.user_name}" }
+                                td { "{delivery.action.as_str()}" }
+                                td {
+                                    if let Some(at) = delivery.delivered_at {
+                                        span { class: "badge", "Delivered" }
+                                        div { class: "text-muted text-sm", {format_timestamp(at)} }
+                                    } else if delivery.gave_up() {
+                                        span { class: "badge badge-warning", "Failed" }
+                                        button {
+                                            class: "btn btn-link",
+                                            onclick: {
+                                                let id = delivery.id;
+                                                move |_| {
+                                                    spawn(async move {
+                                                        match api::retry_webhook_delivery(id).await {
+                                                            Ok(()) => refresh(),
+                                                            Err(e) => error_state.set_server_error(&e),
+                                                        }
+                                                    });
+                                                }
+                                            },
+                                            "Retry"
+                                        }
+                                    } else {
+                                        span { class: "badge badge-primary", "Pending" }
+                                    }
+                                    if let Some(error) = &delivery.error {
+                                        div { class: "text-muted text-sm", "{error}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn CreateUserModal(
+    classification: GroupClassification,
+    #[props(default)] prefill: NewUserPrefill,
+    on_close: EventHandler<()>,
+    on_created: EventHandler<()>,
+) -> Element {
+    let capabilities = use_capabilities();
+    let mut error_state = use_error();
+    let username = use_signal(|| prefill.username.clone());
+    let display_name = use_signal(|| prefill.display_name.clone());
+    let email = use_signal(|| prefill.email.clone());
+    let mut legal_name = use_signal(String::new);
+    let mut groups = use_signal(Vec::<Group>::new);
+    let mut selected_groups = use_signal(HashSet::<Uuid>::new);
+    let mut member_counts = use_signal(HashMap::<Uuid, u32>::new);
+    let mut posix = use_signal(|| false);
+    let mut gid_number = use_signal(String::new);
+    let mut login_shell = use_signal(String::new);
+    let mut email_required = use_signal(|| false);
+    let mut creating = use_signal(|| false);
+    let idempotency_key = use_hook(Uuid::new_v4);
+    let mut errors = use_signal(FieldErrors::default);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(counts) = api::group_member_counts().await {
+                member_counts.set(counts);
+            }
+            if let Ok(g) = api::list_groups().await {
+                groups.set(g);
+            }
+            if let Ok(required) = api::get_require_email().await {
+                email_required.set(required);
+            }
+        });
+    });
+
+    let custom_groups = use_memo(move || {
+        groups
+            .read()
+            .iter()
+            .filter(|g| !classification.is_builtin(&g.name))
+            .cloned()
+            .collect::<Vec<Group>>()
+    });
+
+    let gid_valid =
+        gid_number.read().trim().is_empty() || gid_number.read().trim().parse::<u32>().is_ok();
+    let can_submit = !username.read().is_empty()
+        && !display_name.read().is_empty()
+        && (!email_required() || !email.read().trim().is_empty())
+        && (!posix() || gid_valid);
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Create User" }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    UserForm {
+                        username,
+                        display_name,
+                        email,
+                        email_field: if email_required() {
+                            FieldRequirement::Required
+                        } else {
+                            FieldRequirement::Optional
+                        },
+                        errors: errors(),
+                    }
+                    if capabilities.legal_names() {
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "new_legal_name", "Legal Name" }
+                            input {
+                                id: "new_legal_name",
+                                class: "form-input",
+                                r#type: "text",
+                                value: "{legal_name}",
+                                oninput: move |e| legal_name.set(e.value()),
+                            }
+                            FieldError { errors: errors(), field: "legal_name" }
+                        }
+                    }
+                    if !custom_groups.read().is_empty() {
+                        div { class: "form-group",
+                            label { class: "form-label", "Add to groups" }
+                            GroupPicker {
+                                groups: custom_groups.read().clone(),
+                                selected: selected_groups.read().clone(),
+                                member_counts: member_counts.read().clone(),
+                                on_toggle: move |group_id: Uuid| {
+                                    selected_groups.with_mut(|set| {
+                                        if set.contains(&group_id) {
+                                            set.remove(&group_id);
+                                        } else {
+                                            set.insert(group_id);
+                                        }
+                                    });
+                                },
+                            }
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "checkbox-label",
+                            input {
+                                r#type: "checkbox",
+                                checked: posix(),
+                                onchange: move |e| posix.set(e.checked()),
+                            }
+                            "Enable POSIX, to sign in to Unix hosts"
+                        }
+                    }
+                    if posix() {
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "new_gid_number", "GID number" }
+                            input {
+                                id: "new_gid_number",
+                                class: "form-input",
+                                r#type: "number",
+                                min: "0",
+                                placeholder: "Picked by Kanidm",
+                                value: "{gid_number}",
+                                oninput: move |e| gid_number.set(e.value()),
+                            }
+                            if !gid_valid {
+                                div { class: "form-error", "Enter a whole number, or leave it blank." }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "new_login_shell", "Login shell" }
+                            input {
+                                id: "new_login_shell",
+                                class: "form-input",
+                                r#type: "text",
+                                placeholder: "e.g. /bin/bash",
+                                value: "{login_shell}",
+                                oninput: move |e| login_shell.set(e.value()),
+                            }
+                            FieldError { errors: errors(), field: "login_shell" }
+                        }
+                    }
+                }
+                div { class: "modal-footer",
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: move |_| on_close.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: !can_submit || *creating.read(),
+                        onclick: move |_| {
+                            let name = username.read().clone();
+                            let dname = display_name.read().clone();
+                            let mail = email.read().clone();
+                            let setup = NewPersonSetup {
+                                group_ids: selected_groups.read().iter().copied().collect(),
+                                legal_name: Some(legal_name.read().clone())
+                                    .filter(|n| !n.trim().is_empty()),
+                                posix: posix().then(|| PosixSetup {
+                                    gid_number: gid_number.read().trim().parse().ok(),
+                                    login_shell: Some(login_shell.read().clone())
+                                        .filter(|s| !s.trim().is_empty()),
+                                }),
+                            };
+                            spawn(async move {
+                                creating.set(true);
+                                errors.set(FieldErrors::default());
+                                match api::create_user(name, dname, mail, setup, Some(idempotency_key))
+                                    .await
+                                {
+                                    Ok(()) => on_created.call(()),
+                                    Err(e) => match field_errors(&e) {
+                                        Some(e) => errors.set(e),
+                                        None => error_state.set_server_error(&e),
+                                    },
+                                }
+                                creating.set(false);
+                            });
+                        },
+                        if *creating.read() { "Creating..." } else { "Create" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProvisionLinkModal(classification: GroupClassification, on_close: EventHandler<()>) -> Element {
+    let mut error_state = use_error();
+    let mut duration_hours = use_signal(|| 24u32);
+    let mut max_uses = use_signal(|| Some(1u8));
+    let mut remind = use_signal(|| false);
+    let mut require_approval = use_signal(|| false);
+    let mut generating = use_signal(|| false);
+    // A new key for each link, so a retry doesn't create a second one.
+    let mut idempotency_key = use_signal(Uuid::new_v4);
+    let mut provision_url = use_signal(|| None::<Url>);
+    let mut copied = use_signal(|| false);
+    let mut groups = use_signal(Vec::<Group>::new);
+    let mut selected_groups = use_signal(HashSet::<Uuid>::new);
+    let mut preferences = use_signal(|| None::<UserPreferences>);
+    let mut email_field = use_signal(|| ProvisionFields::default().email);
+    let mut phone_field = use_signal(|| ProvisionFields::default().phone);
+    let mut allowed_domains = use_signal(String::new);
+    let mut invite_email = use_signal(String::new);
+    let mut redirect_url = use_signal(String::new);
+    let mut next_steps = use_signal(String::new);
+    let mut webhook_url = use_signal(String::new);
+    let mut webhook_secret = use_signal(String::new);
+    let mut member_counts = use_signal(HashMap::<Uuid, u32>::new);
+    let mut earlier_links = use_signal(Vec::<ProvisionLinkSummary>::new);
+    let mut failure_causes = use_signal(Vec::<ProvisionFailureCount>::new);
+    let mut duplicated_from = use_signal(|| None::<Timestamp>);
+    let mut editing_link = use_signal(|| None::<Uuid>);
+    let mut resending = use_signal(|| None::<Uuid>);
+
+    // Fetch groups and the admin's saved defaults on mount
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(links) = api::list_provision_links().await {
+                earlier_links.set(links);
+            }
+            if let Ok(causes) = api::get_provision_failure_causes().await {
+                failure_causes.set(causes);
+            }
+        });
+        spawn(async move {
+            if let Ok(counts) = api::group_member_counts().await {
+                member_counts.set(counts);
+            }
+            let Ok(g) = api::list_groups().await else {
+                return;
+            };
+
+            if let Ok(prefs) = api::get_preferences().await {
+                let defaults = &prefs.provision_defaults;
+                duration_hours.set(defaults.duration_hours);
+                max_uses.set(defaults.max_uses);
+                selected_groups.set(
+                    g.iter()
+                        .filter(|g| defaults.group_names.contains(&g.name))
+                        .map(|g| g.uuid)
+                        .collect(),
+                );
+                preferences.set(Some(prefs));
+            }
+            groups.set(g);
+        });
+    });
+
+    // Fill the form in from an earlier link, leaving its expiry to adjust.
+    let mut duplicate = move |link: ProvisionLinkSummary| {
+        duration_hours.set(link.duration_hours());
+        max_uses.set(link.max_uses);
+        email_field.set(link.fields.email);
+        phone_field.set(link.fields.phone);
+        require_approval.set(link.require_approval);
+        allowed_domains.set(link.email_restriction.domains.join(", "));
+        invite_email.set(link.email_restriction.address.clone().unwrap_or_default());
+        redirect_url.set(link.follow_up.redirect_url.clone().unwrap_or_default());
+        next_steps.set(link.follow_up.next_steps.clone());
+        webhook_url.set(link.follow_up.webhook_url.clone().unwrap_or_default());
+        // Secrets aren't sent back, so it has to be entered again.
+        webhook_secret.set(String::new());
+        selected_groups.set(
+            groups
+                .read()
+                .iter()
+                .filter(|g| link.groups.contains(&g.name))
+                .map(|g| g.uuid)
+                .collect(),
+        );
+        duplicated_from.set(Some(link.created_at));
+    };
+
+    // Filter to custom groups only (use memo to track reactivity)
+    let custom_groups = use_memo(move || {
+        groups
+            .read()
+            .iter()
+            .filter(|g| !classification.is_builtin(&g.name))
+            .cloned()
+            .collect::<Vec<Group>>()
+    });
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title",
+                        "Generate Provision Link"
+                        HelpTip { topic: "provision_links" }
+                    }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    if let Some(url) = provision_url.read().as_ref() {
+                        {
+                            let url = url.clone();
+                            rsx! {
+                                p { "Share this link with the user to let them create their own account:" }
+                                div { class: "code-block-wrapper",
+                                    div { class: "code-block",
+                                        Masked { kind: Sensitive::Token, text: url.clone() }
+                                    }
+                                    button {
+                                        class: if *copied.read() { "copy-btn copied" } else { "copy-btn" },
+                                        title: if *copied.read() { "Copied!" } else { "Copy to clipboard" },
+                                        onclick: {
+                                            let url = url.clone();
+                                            move |_| {
+                                                let url = url.clone();
+                                                spawn(async move {
+                                                    let js = format!(
+                                                        r#"navigator.clipboard.writeText("{}")"#,
+                                                        url.to_string().replace("\"", "\\\"")
+                                                    );
+                                                    if eval(&js).recv::<()>().await.is_ok() {
+                                                        copied.set(true);
+                                                    }
+                                                });
+                                            }
+                                        },
+                                        if *copied.read() {
+                                            svg {
+                                                width: "16",
+                                                height: "16",
+                                                view_box: "0 0 24 24",
+                                                fill: "none",
+                                                stroke: "currentColor",
+                                                stroke_width: "2",
+                                                stroke_linecap: "round",
+                                                stroke_linejoin: "round",
+                                                polyline { points: "20 6 9 17 4 12" }
+                                            }
+                                        } else {
                                             svg {
                                                 width: "16",
                                                 height: "16",
@@ -627,55 +3885,291 @@ fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
                                         }
                                     }
                                 }
-                                p { class: "text-muted text-sm", "This link will expire based on the duration you selected." }
+                                p { class: "text-muted text-sm", "This link will expire based on the duration you selected." }
+                            }
+                        }
+                    } else {
+                        p { class: "text-muted", "Generate a link that allows someone to create their own account." }
+                        if !failure_causes.read().is_empty() {
+                            ProvisionFailureCauses { counts: failure_causes() }
+                        }
+                        if !earlier_links.read().is_empty() {
+                            details { class: "form-group",
+                                summary { class: "form-label", "Earlier links" }
+                                div { class: "table-container",
+                                    table {
+                                        thead {
+                                            tr {
+                                                th { "Created" }
+                                                th { "Expires" }
+                                                th { "Groups" }
+                                                th { "Used" }
+                                                th {}
+                                            }
+                                        }
+                                        tbody {
+                                            for link in earlier_links.read().iter().cloned() {
+                                                tr { key: "{link.id}",
+                                                    td { "{format_timestamp(link.created_at)}" }
+                                                    td { "{format_timestamp(link.expires_at)}" }
+                                                    td {
+                                                        {link.groups.join(", ")}
+                                                        if !link.email_restriction.is_empty() {
+                                                            div { class: "text-muted text-sm",
+                                                                "Only {link.email_restriction.describe()}"
+                                                            }
+                                                        }
+                                                        if let Some(at) = link.last_emailed_at {
+                                                            div { class: "text-muted text-sm",
+                                                                "Emailed {format_timestamp(at)}"
+                                                            }
+                                                        }
+                                                    }
+                                                    td {
+                                                        match link.max_uses {
+                                                            Some(max) => rsx! { "{link.use_count} of {max}" },
+                                                            None => rsx! { "{link.use_count}" },
+                                                        }
+                                                        ProvisionFunnelSummary { funnel: link.funnel.clone() }
+                                                    }
+                                                    td {
+                                                        button {
+                                                            class: "btn btn-link",
+                                                            onclick: move |_| editing_link.set(Some(link.id)),
+                                                            "Edit"
+                                                        }
+                                                        button {
+                                                            class: "btn btn-link",
+                                                            onclick: {
+                                                                let link = link.clone();
+                                                                move |_| duplicate(link.clone())
+                                                            },
+                                                            "Duplicate"
+                                                        }
+                                                        if link.email_restriction.address.is_some() {
+                                                            button {
+                                                                class: "btn btn-link",
+                                                                disabled: resending() == Some(link.id),
+                                                                title: "Email the link to its address again",
+                                                                onclick: move |_| {
+                                                                    let id = link.id;
+                                                                    spawn(async move {
+                                                                        resending.set(Some(id));
+                                                                        match api::resend_provision_link(id).await {
+                                                                            Ok(updated) => {
+                                                                                for l in earlier_links.write().iter_mut() {
+                                                                                    if l.id == id {
+                                                                                        *l = updated.clone();
+                                                                                    }
+                                                                                }
+                                                                            }
+                                                                            Err(e) => error_state.set_server_error(&e),
+                                                                        }
+                                                                        resending.set(None);
+                                                                    });
+                                                                },
+                                                                if resending() == Some(link.id) { "Sending..." } else { "Resend" }
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                if editing_link() == Some(link.id) {
+                                                    tr { key: "{link.id}-edit",
+                                                        td { colspan: "5",
+                                                            EditProvisionLink {
+                                                                link: link.clone(),
+                                                                on_cancel: move |_| editing_link.set(None),
+                                                                on_saved: move |updated: ProvisionLinkSummary| {
+                                                                    for l in earlier_links.write().iter_mut() {
+                                                                        if l.id == updated.id {
+                                                                            *l = updated.clone();
+                                                                        }
+                                                                    }
+                                                                    editing_link.set(None);
+                                                                },
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if let Some(at) = duplicated_from() {
+                            p { class: "text-muted text-sm",
+                                "Copied from the link created {format_timestamp(at)}. Adjust when it expires below."
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "duration", "Link expires in" }
+                            select {
+                                id: "duration",
+                                class: "form-input",
+                                value: "{duration_hours}",
+                                onchange: move |e| {
+                                    if let Ok(v) = e.value().parse() {
+                                        duration_hours.set(v);
+                                    }
+                                },
+                                option { value: "1", "1 hour" }
+                                option { value: "4", "4 hours" }
+                                option { value: "24", "24 hours" }
+                                option { value: "72", "3 days" }
+                                option { value: "168", "7 days" }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "max_uses", "Maximum uses" }
+                            select {
+                                id: "max_uses",
+                                class: "form-input",
+                                value: "{max_uses().map(|n| n.to_string()).unwrap_or_default()}",
+                                onchange: move |e| {
+                                    let value = e.value();
+                                    if value.is_empty() {
+                                        max_uses.set(None);
+                                    } else if let Ok(v) = value.parse() {
+                                        max_uses.set(Some(v));
+                                    }
+                                },
+                                option { value: "1", "1 use (single user)" }
+                                option { value: "5", "5 uses" }
+                                option { value: "10", "10 uses" }
+                                option { value: "", "Unlimited" }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "checkbox-label",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: remind(),
+                                    onchange: move |e| remind.set(e.checked()),
+                                }
+                                span { "Remind me if it's about to expire unused, or once it's used up" }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "checkbox-label",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: require_approval(),
+                                    onchange: move |e| require_approval.set(e.checked()),
+                                }
+                                span { "Hold new accounts until an admin approves them" }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "email_field", "Email address" }
+                            select {
+                                id: "email_field",
+                                class: "form-input",
+                                value: "{email_field().as_str()}",
+                                onchange: move |e| {
+                                    if let Ok(v) = e.value().parse() {
+                                        email_field.set(v);
+                                    }
+                                },
+                                for requirement in FieldRequirement::ALL {
+                                    option { value: "{requirement.as_str()}", "{requirement.as_str()}" }
+                                }
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "allowed_domains", "Only for email domains" }
+                            input {
+                                id: "allowed_domains",
+                                class: "form-input",
+                                placeholder: "example.com, *.example.org",
+                                value: "{allowed_domains}",
+                                oninput: move |e| allowed_domains.set(e.value()),
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "invite_email", "Or only for this email address" }
+                            input {
+                                id: "invite_email",
+                                class: "form-input",
+                                r#type: "email",
+                                value: "{invite_email}",
+                                oninput: move |e| invite_email.set(e.value()),
+                            }
+                            if require_approval() || !allowed_domains.read().trim().is_empty() || !invite_email.read().trim().is_empty() {
+                                p { class: "text-muted text-sm",
+                                    "An email address will be required."
+                                }
                             }
                         }
-                    } else {
-                        p { class: "text-muted", "Generate a link that allows someone to create their own account." }
                         div { class: "form-group",
-                            label { class: "form-label", r#for: "duration", "Link expires in" }
+                            label { class: "form-label", r#for: "phone_field", "Phone number" }
                             select {
-                                id: "duration",
+                                id: "phone_field",
                                 class: "form-input",
-                                value: "{duration_hours}",
+                                value: "{phone_field().as_str()}",
                                 onchange: move |e| {
                                     if let Ok(v) = e.value().parse() {
-                                        duration_hours.set(v);
+                                        phone_field.set(v);
                                     }
                                 },
-                                option { value: "1", "1 hour" }
-                                option { value: "4", "4 hours" }
-                                option { value: "24", "24 hours" }
-                                option { value: "72", "3 days" }
-                                option { value: "168", "7 days" }
+                                for requirement in FieldRequirement::ALL {
+                                    option { value: "{requirement.as_str()}", "{requirement.as_str()}" }
+                                }
                             }
                         }
                         div { class: "form-group",
-                            label { class: "form-label", r#for: "max_uses", "Maximum uses" }
-                            select {
-                                id: "max_uses",
+                            label { class: "form-label", r#for: "redirect_url", "Once they're set up, send them to" }
+                            input {
+                                id: "redirect_url",
                                 class: "form-input",
-                                value: "{max_uses().map(|n| n.to_string()).unwrap_or_default()}",
-                                onchange: move |e| {
-                                    let value = e.value();
-                                    if value.is_empty() {
-                                        max_uses.set(None);
-                                    } else if let Ok(v) = value.parse() {
-                                        max_uses.set(Some(v));
-                                    }
-                                },
-                                option { value: "1", "1 use (single user)" }
-                                option { value: "5", "5 uses" }
-                                option { value: "10", "10 uses" }
-                                option { value: "", "Unlimited" }
+                                r#type: "url",
+                                placeholder: "https://app.example.com",
+                                value: "{redirect_url}",
+                                oninput: move |e| redirect_url.set(e.value()),
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "next_steps", "Next steps to show them" }
+                            textarea {
+                                id: "next_steps",
+                                class: "form-input",
+                                rows: 3,
+                                placeholder: "Ask IT for your laptop on your first morning.",
+                                value: "{next_steps}",
+                                oninput: move |e| next_steps.set(e.value()),
+                            }
+                        }
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "follow_up_webhook", "Webhook to call once their account is ready" }
+                            input {
+                                id: "follow_up_webhook",
+                                class: "form-input",
+                                r#type: "url",
+                                placeholder: "https://hr.example.com/authit",
+                                value: "{webhook_url}",
+                                oninput: move |e| webhook_url.set(e.value()),
+                            }
+                            if !webhook_url.read().trim().is_empty() {
+                                input {
+                                    class: "form-input",
+                                    r#type: "password",
+                                    aria_label: "Webhook signing secret",
+                                    placeholder: "Signing secret",
+                                    value: "{webhook_secret}",
+                                    oninput: move |e| webhook_secret.set(e.value()),
+                                }
+                                p { class: "text-muted text-sm",
+                                    "Calls are signed like group webhooks, in the X-AuthIt-Signature header."
+                                }
                             }
                         }
                         if !custom_groups.read().is_empty() {
                             div { class: "form-group",
                                 label { class: "form-label", "Add to groups" }
-                                GroupCheckboxList {
+                                GroupPicker {
                                     groups: custom_groups.read().clone(),
                                     selected: selected_groups.read().clone(),
+                                    member_counts: member_counts.read().clone(),
                                     on_toggle: move |group_id: Uuid| {
                                         selected_groups.with_mut(|set| {
                                             if set.contains(&group_id) {
@@ -709,6 +4203,35 @@ fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
                             onclick: move |_| {
                                 let hours = *duration_hours.read();
                                 let uses = *max_uses.read();
+                                let remind = remind();
+                                let require_approval = require_approval();
+                                let fields = ProvisionFields {
+                                    email: email_field(),
+                                    phone: phone_field(),
+                                };
+                                let follow_up = ProvisionFollowUp {
+                                    redirect_url: Some(redirect_url()),
+                                    next_steps: next_steps(),
+                                    webhook_url: Some(webhook_url()),
+                                    webhook_secret: webhook_secret(),
+                                };
+                                let follow_up = match follow_up.normalized() {
+                                    Ok(f) => f,
+                                    Err(e) => {
+                                        error_state.set(e.to_string());
+                                        return;
+                                    }
+                                };
+                                let restriction = match EmailRestriction::parse(
+                                    &allowed_domains.read(),
+                                    &invite_email.read(),
+                                ) {
+                                    Ok(r) => r,
+                                    Err(e) => {
+                                        error_state.set(e.to_string());
+                                        return;
+                                    }
+                                };
                                 // Convert selected group UUIDs to group names
                                 let group_names: Vec<String> = groups
                                     .read()
@@ -718,8 +4241,32 @@ fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
                                     .collect();
                                 spawn(async move {
                                     generating.set(true);
-                                    match api::generate_provision_url(hours, uses, group_names).await {
-                                        Ok(url) => provision_url.set(Some(url)),
+                                    match api::generate_provision_url(
+                                            hours,
+                                            uses,
+                                            group_names.clone(),
+                                            fields,
+                                            restriction,
+                                            remind,
+                                            require_approval,
+                                            follow_up,
+                                            Some(idempotency_key()),
+                                        )
+                                        .await
+                                    {
+                                        Ok(url) => {
+                                            idempotency_key.set(Uuid::new_v4());
+                                            provision_url.set(Some(url));
+                                            // Remember these settings for next time.
+                                            if let Some(mut prefs) = preferences() {
+                                                prefs.provision_defaults = ProvisionDefaults {
+                                                    duration_hours: hours,
+                                                    max_uses: uses,
+                                                    group_names,
+                                                };
+                                                let _ = api::set_preferences(prefs).await;
+                                            }
+                                        }
                                         Err(e) => error_state.set_server_error(&e),
                                     }
                                     generating.set(false);
@@ -733,3 +4280,460 @@ fn ProvisionLinkModal(on_close: EventHandler<()>) -> Element {
         }
     }
 }
+
+/// Import groups and their members from a CSV, previewing the changes first.
+#[component]
+fn ImportGroupsModal(on_close: EventHandler<()>, on_imported: EventHandler<()>) -> Element {
+    let mut error_state = use_error();
+    let mut csv = use_signal(String::new);
+    let mut preview = use_signal(|| None::<GroupImport>);
+    let mut working = use_signal(|| false);
+    let job = use_job(move |_| on_imported.call(()));
+
+    let check = move |_| {
+        let data = csv.read().clone();
+        spawn(async move {
+            working.set(true);
+            match api::preview_group_import(data).await {
+                Ok(import) => preview.set(Some(import)),
+                Err(e) => error_state.set_server_error(&e),
+            }
+            working.set(false);
+        });
+    };
+
+    let import = move |_| job.start(api::start_group_import(csv.read().clone()));
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            div { class: "modal",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Import Groups" }
+                    button {
+                        class: "modal-close",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                div { class: "modal-body",
+                    if job.is_started() {
+                        JobStatus { job }
+                    } else {
+                        div { class: "form-group",
+                            label { class: "form-label", r#for: "group_csv", "CSV" }
+                            textarea {
+                                id: "group_csv",
+                                class: "form-input",
+                                rows: "8",
+                                placeholder: "group,username\nengineering,alice\nengineering,bob",
+                                value: "{csv}",
+                                oninput: move |e| {
+                                    csv.set(e.value());
+                                    preview.set(None);
+                                },
+                            }
+                            p { class: "text-muted text-sm",
+                                "One group,username pair per row. Missing groups are created, and each listed group ends up with exactly the listed people."
+                            }
+                        }
+                        if let Some(import) = preview.read().as_ref() {
+                            ImportPreview { import: import.clone() }
+                        }
+                    }
+                }
+                div { class: "modal-footer",
+                    if job.is_started() {
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| on_close.call(()),
+                            "Done"
+                        }
+                    } else {
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| on_close.call(()),
+                            "Cancel"
+                        }
+                        if preview.read().is_none() {
+                            button {
+                                class: "btn btn-primary",
+                                disabled: csv.read().trim().is_empty() || working(),
+                                onclick: check,
+                                if working() { "Checking..." } else { "Preview" }
+                            }
+                        } else {
+                            button {
+                                class: "btn btn-primary",
+                                disabled: job.is_starting()
+                                    || preview.read().as_ref().is_some_and(|p| p.changes.is_empty()),
+                                onclick: import,
+                                if job.is_starting() { "Starting..." } else { "Import" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ImportPreview(import: GroupImport) -> Element {
+    rsx! {
+        if !import.unknown_users.is_empty() {
+            div { class: "alert alert-error",
+                "These users don't exist, and will be skipped: "
+                {import.unknown_users.join(", ")}
+            }
+        }
+        if import.changes.is_empty() {
+            div { class: "empty-state", "Kanidm already matches the CSV." }
+        } else {
+            div { class: "table-container",
+                table {
+                    thead {
+                        tr {
+                            th { "Group" }
+                            th { "Add" }
+                            th { "Remove" }
+                        }
+                    }
+                    tbody {
+                        for change in import.changes.iter() {
+                            tr { key: "{change.group}",
+                                td {
+                                    "{change.group}"
+                                    if change.create {
+                                        " "
+                                        span { class: "badge badge-primary", "New" }
+                                    }
+                                }
+                                td { {change.add.join(", ")} }
+                                td { {change.remove.join(", ")} }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Change when an already sent provision link expires, and how many times it
+/// can be used.
+/// How far people got with a link: whether it was ever opened, and why any
+/// attempts to use it failed.
+#[component]
+fn ProvisionFunnelSummary(funnel: ProvisionFunnel) -> Element {
+    let opened = match (funnel.opens, funnel.last_opened_at) {
+        (_, None) => "Never opened".to_string(),
+        (1, Some(at)) => format!("Opened once, {}", format_timestamp(at)),
+        (n, Some(at)) => format!("Opened {n} times, last {}", format_timestamp(at)),
+    };
+    let causes = funnel
+        .failure_causes
+        .iter()
+        .map(|c| format!("{} {}", c.count, c.cause.label().to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    rsx! {
+        div { class: "text-muted text-sm", "{opened}" }
+        if funnel.opens > 0 && funnel.completions == 0 && funnel.failure_count == 0 {
+            div { class: "text-muted text-sm", "Not completed yet" }
+        }
+        if funnel.failure_count > 0 {
+            details { class: "text-sm",
+                summary { class: "text-muted", "{funnel.failure_count} failed: {causes}" }
+                ul { class: "funnel-failures",
+                    for (i, failure) in funnel.failures.iter().enumerate() {
+                        li { key: "{i}",
+                            span { class: "text-muted", "{format_timestamp(failure.at)}: " }
+                            span { class: "badge badge-warning", "{failure.cause.label()}" }
+                            " {failure.reason}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Why provision links failed in the last 30 days, most common first, so
+/// admins can tell people getting stuck from Kanidm or AuthIt! having
+/// trouble.
+#[component]
+fn ProvisionFailureCauses(counts: Vec<ProvisionFailureCount>) -> Element {
+    let total: u32 = counts.iter().map(|c| c.count).sum();
+    let system: u32 = counts
+        .iter()
+        .filter(|c| c.cause.is_system())
+        .map(|c| c.count)
+        .sum();
+
+    rsx! {
+        details { class: "form-group",
+            summary { class: "form-label", "Failed attempts in the last 30 days: {total}" }
+            ul { class: "funnel-failures",
+                for c in counts.iter() {
+                    li { key: "{c.cause.as_str()}", "{c.cause.label()}: {c.count}" }
+                }
+            }
+            if system > 0 {
+                p { class: "text-danger text-sm",
+                    "{system} of these were problems with Kanidm or AuthIt!, rather than with what people entered or the links they used."
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn EditProvisionLink(
+    link: ProvisionLinkSummary,
+    on_cancel: EventHandler<()>,
+    on_saved: EventHandler<ProvisionLinkSummary>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut expires_in_hours = use_signal(|| 72u32);
+    let mut max_uses = use_signal(|| link.max_uses);
+    let mut saving = use_signal(|| false);
+    let id = link.id;
+
+    rsx! {
+        div { class: "email-row",
+            select {
+                class: "form-input",
+                value: "{expires_in_hours}",
+                onchange: move |e| {
+                    if let Ok(v) = e.value().parse() {
+                        expires_in_hours.set(v);
+                    }
+                },
+                option { value: "1", "Expire in 1 hour" }
+                option { value: "24", "Expire in 24 hours" }
+                option { value: "72", "Expire in 3 days" }
+                option { value: "168", "Expire in 7 days" }
+                option { value: "336", "Expire in 14 days" }
+                option { value: "720", "Expire in 30 days" }
+            }
+            input {
+                class: "form-input",
+                r#type: "number",
+                min: "{link.use_count.max(1)}",
+                max: "255",
+                placeholder: "Unlimited uses",
+                value: "{max_uses().map(|n| n.to_string()).unwrap_or_default()}",
+                oninput: move |e| max_uses.set(e.value().parse().ok()),
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: saving(),
+                onclick: move |_| on_cancel.call(()),
+                "Cancel"
+            }
+            button {
+                class: "btn btn-primary",
+                disabled: saving(),
+                onclick: move |_| {
+                    spawn(async move {
+                        saving.set(true);
+                        match api::update_provision_link(id, expires_in_hours(), max_uses()).await {
+                            Ok(updated) => on_saved.call(updated),
+                            Err(e) => error_state.set_server_error(&e),
+                        }
+                        saving.set(false);
+                    });
+                },
+                if saving() { "Saving..." } else { "Save" }
+            }
+        }
+        p { class: "text-muted text-sm", "The link's URL stays the same." }
+    }
+}
+
+/// Review staged changes, then apply them in one batch.
+#[component]
+fn ReviewChangesModal(
+    pending: Signal<Vec<PendingChange>>,
+    /// Each user's `Person::modified` as last loaded.
+    modified: HashMap<Uuid, String>,
+    on_close: EventHandler<()>,
+    on_applied: EventHandler<()>,
+) -> Element {
+    let mut error_state = use_error();
+    let mut result = use_signal(|| None::<BatchResult>);
+    let mut applying = use_signal(|| false);
+    let mut conflict = use_signal(|| None::<String>);
+
+    let apply = move |_| {
+        let changes = pending.read().clone();
+        let modified = modified.clone();
+        spawn(async move {
+            applying.set(true);
+            match api::apply_changes(changes, modified).await {
+                Ok(batch) => {
+                    // Nothing applied unless everything did, so on failure
+                    // keep the changes staged to fix and retry.
+                    if batch.is_complete() {
+                        pending.set(Vec::new());
+                    }
+                    result.set(Some(batch));
+                    on_applied.call(());
+                }
+                Err(e) if is_edit_conflict(&e) => {
+                    conflict.set(Some(ErrorInfo::from_server_error(&e).message));
+                }
+                Err(e) => error_state.set_server_error(&e),
+            }
+            applying.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "modal-overlay",
+            onclick: move |_| if !applying() { on_close.call(()) },
+            div { class: "modal",
+                onclick: move |e| e.stop_propagation(),
+                div { class: "modal-header",
+                    h2 { class: "modal-title", "Review Changes" }
+                    if !applying() {
+                        button {
+                            class: "modal-close",
+                            onclick: move |_| on_close.call(()),
+                            "×"
+                        }
+                    }
+                }
+                div { class: "modal-body",
+                    if let Some(message) = conflict() {
+                        div { class: "alert alert-warning",
+                            p { "{message}." }
+                            p { "Nothing was applied. Reload to see their changes, then review yours again; they're still staged." }
+                            button {
+                                class: "btn btn-primary",
+                                onclick: move |_| {
+                                    conflict.set(None);
+                                    on_applied.call(());
+                                },
+                                "Reload"
+                            }
+                        }
+                    }
+                    if let Some(batch) = result.read().as_ref() {
+                        if batch.is_complete() {
+                            div { class: "alert alert-success",
+                                "Applied {batch.results.len()} changes."
+                            }
+                        } else {
+                            div { class: "alert alert-error",
+                                "A change failed, so the batch wasn't applied. The changes are still staged."
+                            }
+                        }
+                        div { class: "table-container",
+                            table {
+                                thead {
+                                    tr {
+                                        th { "User" }
+                                        th { "Change" }
+                                        th { "Result" }
+                                    }
+                                }
+                                tbody {
+                                    for r in batch.results.iter() {
+                                        tr {
+                                            td { "{r.change.user_name()}" }
+                                            td { {r.change.describe()} }
+                                            td {
+                                                match &r.outcome {
+                                                    ChangeOutcome::Applied => rsx! {
+                                                        span { class: "badge badge-primary", "Applied" }
+                                                    },
+                                                    ChangeOutcome::Failed(e) => rsx! {
+                                                        span { class: "text-danger", "Failed: {e}" }
+                                                    },
+                                                    ChangeOutcome::RolledBack => rsx! {
+                                                        span { class: "text-muted", "Rolled back" }
+                                                    },
+                                                    ChangeOutcome::RollbackFailed(e) => rsx! {
+                                                        span { class: "text-danger", "Still applied, rollback failed: {e}" }
+                                                    },
+                                                    ChangeOutcome::Skipped => rsx! {
+                                                        span { class: "text-muted", "Not attempted" }
+                                                    },
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    } else if pending.read().is_empty() {
+                        div { class: "empty-state", "No changes are staged." }
+                    } else {
+                        p { class: "text-muted",
+                            "These changes are applied together. If any fails, the rest are rolled back."
+                        }
+                        div { class: "table-container",
+                            table {
+                                thead {
+                                    tr {
+                                        th { "User" }
+                                        th { "Change" }
+                                        th {}
+                                    }
+                                }
+                                tbody {
+                                    for (i, change) in pending.read().iter().enumerate() {
+                                        tr {
+                                            td { "{change.user_name()}" }
+                                            td { {change.describe()} }
+                                            td {
+                                                button {
+                                                    class: "btn btn-link",
+                                                    title: "Discard",
+                                                    disabled: applying(),
+                                                    onclick: move |_| {
+                                                        pending.write().remove(i);
+                                                    },
+                                                    "×"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "modal-footer",
+                    if result.read().is_some() {
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| on_close.call(()),
+                            "Done"
+                        }
+                    } else {
+                        button {
+                            class: "btn btn-secondary",
+                            disabled: applying() || pending.read().is_empty(),
+                            onclick: move |_| {
+                                pending.set(Vec::new());
+                                on_close.call(());
+                            },
+                            "Discard All"
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: applying() || pending.read().is_empty(),
+                            onclick: apply,
+                            if applying() { "Applying..." } else { "Apply {pending.read().len()} Changes" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}